@@ -0,0 +1,366 @@
+use aelys_air::cgen::{RUNTIME_HEADER, RUNTIME_HEADER_NAME, emit_c};
+use aelys_air::layout::compute_layouts;
+use aelys_air::lower::lower;
+use aelys_air::mono::monomorphize;
+use aelys_air::*;
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+use std::process::Command;
+
+fn lower_source(code: &str) -> AirProgram {
+    let src = Source::new("<test>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let mut air = lower(&typed);
+    compute_layouts(&mut air);
+    monomorphize(air)
+}
+
+fn c_source(code: &str) -> String {
+    emit_c(&lower_source(code)).unwrap_or_else(|e| panic!("emit_c failed: {e}"))
+}
+
+fn c_error(code: &str) -> String {
+    match emit_c(&lower_source(code)) {
+        Ok(out) => panic!("expected emit_c to fail, got:\n{out}"),
+        Err(e) => e.to_string(),
+    }
+}
+
+fn attribs() -> FunctionAttribs {
+    FunctionAttribs {
+        inline: InlineHint::Default,
+        no_gc: false,
+        no_unwind: false,
+        cold: false,
+    }
+}
+
+fn extern_fn(name: &str, conv: CallingConv) -> AirFunction {
+    AirFunction {
+        id: FunctionId(1),
+        name: name.into(),
+        gc_mode: GcMode::Manual,
+        type_params: vec![],
+        params: vec![AirParam {
+            id: LocalId(0),
+            ty: AirType::I32,
+            name: "x".into(),
+            span: None,
+        }],
+        ret_ty: AirType::I32,
+        locals: vec![],
+        blocks: vec![],
+        is_extern: true,
+        calling_conv: conv,
+        attributes: attribs(),
+        span: None,
+    }
+}
+
+fn caller(conv: CallingConv) -> AirFunction {
+    AirFunction {
+        id: FunctionId(0),
+        name: "main".into(),
+        gc_mode: GcMode::Manual,
+        type_params: vec![],
+        params: vec![],
+        ret_ty: AirType::I32,
+        locals: vec![AirLocal {
+            id: LocalId(0),
+            ty: AirType::I32,
+            name: None,
+            is_mut: false,
+            span: None,
+        }],
+        blocks: vec![AirBlock {
+            id: BlockId(0),
+            stmts: vec![AirStmt {
+                kind: AirStmtKind::Assign {
+                    place: Place::Local(LocalId(0)),
+                    rvalue: Rvalue::Call {
+                        func: Callee::Extern("abs".into(), conv),
+                        args: vec![Operand::Const(AirConst::Int(-3, AirIntSize::I32))],
+                    },
+                },
+                span: None,
+            }],
+            terminator: AirTerminator::Return(Some(Operand::Copy(LocalId(0)))),
+        }],
+        is_extern: false,
+        calling_conv: CallingConv::Aelys,
+        attributes: attribs(),
+        span: None,
+    }
+}
+
+fn program(functions: Vec<AirFunction>) -> AirProgram {
+    AirProgram {
+        functions,
+        structs: vec![],
+        globals: vec![],
+        source_files: vec![],
+        mono_instances: vec![],
+    }
+}
+
+fn cc_available() -> bool {
+    Command::new("cc").arg("--version").output().is_ok()
+}
+
+// compiles with the system C compiler and returns stdout, or None when
+// there's no compiler around
+fn compile_and_run(code: &str) -> Option<String> {
+    if !cc_available() {
+        return None;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let c_path = dir.path().join("prog.c");
+    let exe_path = dir.path().join("prog");
+    std::fs::write(&c_path, c_source(code)).unwrap();
+    std::fs::write(dir.path().join(RUNTIME_HEADER_NAME), RUNTIME_HEADER).unwrap();
+
+    let cc = Command::new("cc")
+        .args(["-std=c11", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&exe_path)
+        .arg(&c_path)
+        .arg("-lm")
+        .output()
+        .unwrap();
+    assert!(
+        cc.status.success(),
+        "cc failed:\n{}",
+        String::from_utf8_lossy(&cc.stderr)
+    );
+
+    let run = Command::new(&exe_path).output().unwrap();
+    assert!(run.status.success(), "program failed: {:?}", run);
+    Some(String::from_utf8(run.stdout).unwrap())
+}
+
+#[test]
+fn struct_layout_becomes_static_asserts() {
+    let out = c_source(
+        r#"
+struct Point { x: int, y: float, ok: bool }
+fn get_x(p: Point) -> int { p.x }
+"#,
+    );
+    assert!(out.contains("typedef struct aelys_t_Point aelys_t_Point;"));
+    assert!(out.contains("    int64_t x;\n    double y;\n    bool ok;\n"));
+    assert!(out.contains("_Static_assert(offsetof(aelys_t_Point, y) == 8"));
+    assert!(out.contains("_Static_assert(offsetof(aelys_t_Point, ok) == 16"));
+}
+
+#[test]
+fn nested_structs_are_emitted_after_their_fields() {
+    let out = c_source(
+        r#"
+struct Outer { inner: Inner, tag: int }
+struct Inner { v: int }
+"#,
+    );
+    let inner = out.find("struct aelys_t_Inner {").unwrap();
+    let outer = out.find("struct aelys_t_Outer {").unwrap();
+    assert!(inner < outer);
+}
+
+#[test]
+fn missing_layout_is_an_error() {
+    let src = Source::new("<test>", "struct P { x: int }");
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let err = emit_c(&lower(&typed)).unwrap_err();
+    assert!(err.message.contains("compute_layouts"), "{err}");
+}
+
+#[test]
+fn blocks_become_labels_and_gotos() {
+    let out = c_source(
+        r#"
+fn count(n: int) -> int {
+    let mut i = 0
+    while i < n { i += 1 }
+    i
+}
+"#,
+    );
+    assert!(out.contains("AELYS_INTERNAL int64_t aelys_count(int64_t l0) {"));
+    assert!(out.contains("goto bb"));
+    assert!(out.contains(") goto bb"));
+}
+
+#[test]
+fn entry_point_wraps_main() {
+    let out = c_source("fn main() { println(1) }");
+    assert!(out.contains("#ifndef AELYS_NO_MAIN\nint main(void) {\n    aelys_main();"));
+    let out = c_source("fn helper() -> int { 1 }");
+    assert!(!out.contains("int main(void)"));
+}
+
+#[test]
+fn c_extern_gets_a_plain_prototype() {
+    let out = emit_c(&program(vec![
+        caller(CallingConv::C),
+        extern_fn("abs", CallingConv::C),
+    ]))
+    .unwrap();
+    assert!(out.contains("extern int32_t abs(int32_t);"));
+    assert!(out.contains("l0 = abs(-3);"));
+}
+
+#[test]
+fn rust_extern_is_rejected() {
+    let err = emit_c(&program(vec![
+        caller(CallingConv::C),
+        extern_fn("abs", CallingConv::Rust),
+    ]))
+    .unwrap_err();
+    assert!(err.message.contains("C calling convention"), "{err}");
+}
+
+#[test]
+fn unknown_extern_call_is_rejected() {
+    let err = emit_c(&program(vec![caller(CallingConv::C)])).unwrap_err();
+    assert_eq!(err.function.as_deref(), Some("main"));
+    assert!(err.message.contains("undeclared extern `abs`"), "{err}");
+}
+
+#[test]
+fn leftover_type_param_is_rejected() {
+    let mut f = caller(CallingConv::C);
+    f.locals[0].ty = AirType::Param(TypeParamId(0));
+    let err = emit_c(&program(vec![f])).unwrap_err();
+    assert!(err.message.contains("monomorphization"), "{err}");
+}
+
+#[test]
+fn unsupported_runtime_call_names_the_function() {
+    let err = c_error(
+        r#"
+fn f() -> int {
+    let mut v = Vec[1, 2]
+    v.push(3)
+    v.len()
+}
+"#,
+    );
+    assert!(err.contains("in function `f`"), "{err}");
+    assert!(err.contains("`push` is not available in C output"), "{err}");
+}
+
+#[test]
+fn string_literals_are_escaped() {
+    let out = c_source(
+        r#"
+fn main() { println("tab\tquote\" back\\ é") }
+"#,
+    );
+    assert!(out.contains(r#""tab\tquote\" back\\ \303\251""#), "{out}");
+}
+
+#[test]
+fn write_only_locals_are_voided() {
+    let out = c_source(
+        r#"
+fn main() {
+    let unused = 5
+    println(1)
+}
+"#,
+    );
+    assert!(out.contains("    (void)l"), "{out}");
+    // and nothing else in it trips -Wall -Wextra
+    let Some(out) = compile_and_run(
+        "fn main() {\n    let unused = 5\n    let also = unused + 1\n    println(1)\n}",
+    ) else {
+        return;
+    };
+    assert_eq!(out, "1\n");
+}
+
+#[test]
+fn runs_like_the_vm() {
+    let code = r#"
+let LIMIT = 10
+
+fn fib(n: int) -> int {
+    if n < 2 { return n }
+    fib(n - 1) + fib(n - 2)
+}
+
+fn main() {
+    let mut i = 0
+    while i < LIMIT {
+        if i % 3 == 0 and i != 0 { println(fib(i)) }
+        i += 1
+    }
+    let xs = Array[4, 5, 6]
+    let mut total = 0
+    for x in xs { total += x }
+    println("total={}", total)
+    println(7 / 2)
+    println(-7 % 3)
+    println(1.0 / 3.0)
+    println(1e20)
+    println(2.0)
+    println("héllo"[1])
+    println("ab" + "c" == "abc")
+}
+"#;
+    let Some(out) = compile_and_run(code) else {
+        return;
+    };
+    assert_eq!(
+        out,
+        "2\n8\n34\ntotal=15\n3\n-1\n0.3333333333333333\n100000000000000000000.0\n2.0\né\ntrue\n"
+    );
+}
+
+#[test]
+fn captured_variables_are_shared_in_c() {
+    let code = r#"
+fn main() {
+    let mut count = 0
+    fn bump(n: int) -> int {
+        count = count + n
+        count
+    }
+    bump(5)
+    count = count + 100
+    let seen = bump(1)
+    println(seen)
+    println(count)
+}
+"#;
+    let Some(out) = compile_and_run(code) else {
+        return;
+    };
+    assert_eq!(out, "106\n106\n");
+}
+
+#[test]
+fn structs_round_trip_through_c() {
+    let code = r#"
+struct Point { x: int, y: int }
+
+fn manhattan(p: Point) -> int {
+    let dx = p.x
+    let dy = p.y
+    dx + dy
+}
+
+fn main() {
+    let p = Point { x: 3, y: 4 }
+    println(manhattan(p))
+}
+"#;
+    let Some(out) = compile_and_run(code) else {
+        return;
+    };
+    assert_eq!(out, "7\n");
+}
@@ -0,0 +1,244 @@
+use aelys::{new_vm, run_with_vm_and_opt};
+use aelys_air::interp::{Interpreter, Value, run_function};
+use aelys_air::layout::compute_layouts;
+use aelys_air::lower::lower;
+use aelys_air::mono::monomorphize;
+use aelys_air::passes::{PassManager, SsaConstruction};
+use aelys_air::*;
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_opt::{OptimizationLevel, Optimizer};
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+use std::path::PathBuf;
+
+fn lower_source(code: &str, level: OptimizationLevel) -> AirProgram {
+    let src = Source::new("<test>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let typed = Optimizer::new(level).optimize(typed);
+    let mut air = lower(&typed);
+    compute_layouts(&mut air);
+    monomorphize(air)
+}
+
+fn run_air(
+    code: &str,
+    level: OptimizationLevel,
+    passes: Option<PassManager>,
+) -> Result<String, String> {
+    let mut air = lower_source(code, level);
+    if let Some(mut passes) = passes {
+        passes.run(&mut air);
+    }
+    run_function(&air, "main", vec![])
+        .map(|v| v.to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn run_vm(code: &str, level: OptimizationLevel) -> Result<String, String> {
+    let mut vm = new_vm().unwrap();
+    let value = run_with_vm_and_opt(&mut vm, &format!("{code}\nmain()"), "<test>", level)
+        .map_err(|e| e.to_string())?;
+    Ok(vm.value_to_string(value))
+}
+
+fn fixtures() -> Vec<(String, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/air_diff");
+    let mut files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "aelys"))
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .map(|p| {
+            let name = p.file_name().unwrap().to_string_lossy().into_owned();
+            (name, std::fs::read_to_string(&p).unwrap())
+        })
+        .collect()
+}
+
+fn diff_fixtures(level: OptimizationLevel, passes: impl Fn() -> Option<PassManager>) {
+    let mut failures = Vec::new();
+    for (name, code) in fixtures() {
+        let vm = run_vm(&code, level);
+        let air = run_air(&code, level, passes());
+        if vm.is_err() || vm != air {
+            failures.push(format!("{name}: vm = {vm:?}, air = {air:?}"));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn fixtures_agree_with_vm_unoptimized() {
+    diff_fixtures(OptimizationLevel::None, || None);
+}
+
+#[test]
+fn fixtures_agree_with_vm_optimized() {
+    diff_fixtures(OptimizationLevel::Standard, || None);
+}
+
+#[test]
+fn fixtures_agree_with_vm_in_ssa_form() {
+    diff_fixtures(OptimizationLevel::None, || {
+        let mut pm = PassManager::new();
+        pm.add(SsaConstruction);
+        Some(pm)
+    });
+}
+
+#[test]
+fn fixtures_agree_with_vm_after_air_passes() {
+    diff_fixtures(OptimizationLevel::None, || Some(PassManager::standard()));
+    diff_fixtures(
+        OptimizationLevel::Standard,
+        || Some(PassManager::standard()),
+    );
+}
+
+#[test]
+fn println_output_is_captured() {
+    let air = lower_source(
+        r#"
+fn main() {
+    println("a")
+    print(1)
+    println(2.0)
+}
+"#,
+        OptimizationLevel::None,
+    );
+    let mut interp = Interpreter::new(&air);
+    interp.call("main", vec![]).unwrap();
+    assert_eq!(interp.take_output(), "a\n12.0\n");
+}
+
+#[test]
+fn struct_fields_go_through_layout() {
+    let air = lower_source(
+        r#"
+struct Pair { a: int, b: float }
+
+fn make(a: int) -> Pair { Pair { a: a, b: 2.5 } }
+
+fn main() -> float {
+    let p = make(4)
+    p.b * 2.0 + p.a
+}
+"#,
+        OptimizationLevel::None,
+    );
+    let result = run_function(&air, "main", vec![]).unwrap();
+    assert_eq!(result.to_string(), "9.0");
+}
+
+#[test]
+fn generic_structs_run_through_their_instances() {
+    let air = lower_source(
+        r#"
+struct Pair<A, B> { a: A, b: B }
+
+fn swap<A, B>(p: Pair<A, B>) -> Pair<B, A> {
+    return Pair { a: p.b, b: p.a }
+}
+
+fn main() -> float {
+    let p = swap(Pair { a: 3, b: 0.5 })
+    let q = swap(Pair { a: "x", b: 4 })
+    p.a + p.b + q.a
+}
+"#,
+        OptimizationLevel::None,
+    );
+    let result = run_function(&air, "main", vec![]).unwrap();
+    assert_eq!(result.to_string(), "7.5");
+}
+
+#[test]
+fn struct_without_layout_is_an_error() {
+    let src = Source::new(
+        "<test>",
+        "struct P { x: int }\nfn main() -> int { P { x: 1 }.x }",
+    );
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let air = lower(&typed);
+    let err = run_function(&air, "main", vec![]).unwrap_err();
+    assert!(err.message.contains("compute_layouts"), "{err}");
+}
+
+#[test]
+fn arguments_are_passed_in() {
+    let air = lower_source(
+        "fn add(a: int, b: int) -> int { a + b }",
+        OptimizationLevel::None,
+    );
+    let result = run_function(&air, "add", vec![Value::Int(2), Value::Int(40)]).unwrap();
+    assert_eq!(result.to_string(), "42");
+
+    let err = run_function(&air, "add", vec![Value::Int(2)]).unwrap_err();
+    assert!(err.message.contains("expects 2 argument(s)"), "{err}");
+}
+
+#[test]
+fn division_by_zero_names_the_function() {
+    let air = lower_source(
+        "fn div(a: int, b: int) -> int { a / b }\nfn main() -> int { div(1, 0) }",
+        OptimizationLevel::None,
+    );
+    let err = run_function(&air, "main", vec![]).unwrap_err();
+    assert_eq!(err.function.as_deref(), Some("div"));
+    assert!(err.message.contains("division by zero"), "{err}");
+}
+
+#[test]
+fn out_of_bounds_index_is_an_error() {
+    let air = lower_source(
+        "fn main() -> int { let xs = Array[1, 2]\n xs[5] }",
+        OptimizationLevel::None,
+    );
+    let err = run_function(&air, "main", vec![]).unwrap_err();
+    assert!(err.message.contains("index out of bounds"), "{err}");
+}
+
+#[test]
+fn runaway_recursion_hits_depth_limit() {
+    let air = lower_source(
+        "fn down(n: int) -> int { down(n + 1) }\nfn main() -> int { down(0) }",
+        OptimizationLevel::None,
+    );
+    let err = run_function(&air, "main", vec![]).unwrap_err();
+    assert!(err.message.contains("stack overflow"), "{err}");
+}
+
+#[test]
+fn infinite_loop_hits_step_limit() {
+    let air = lower_source(
+        "fn main() -> int { let mut i = 0\n while true { i += 1 }\n i }",
+        OptimizationLevel::None,
+    );
+    let err = Interpreter::new(&air)
+        .with_step_limit(1_000)
+        .call("main", vec![])
+        .unwrap_err();
+    assert!(err.message.contains("step limit"), "{err}");
+}
+
+#[test]
+fn generic_functions_run_after_mono() {
+    let air = lower_source(
+        r#"
+fn id<T>(x: T) -> T { x }
+fn main() -> int { id(41) + 1 }
+"#,
+        OptimizationLevel::None,
+    );
+    let result = run_function(&air, "main", vec![]).unwrap();
+    assert_eq!(result.to_string(), "42");
+}
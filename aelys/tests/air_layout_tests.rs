@@ -1,5 +1,5 @@
 use aelys_air::{
-    AirProgram, AirStructDef, AirStructField, AirType, CallingConv,
+    AirProgram, AirStructDef, AirStructField, AirType, CallingConv, TypeParamId,
     layout::{compute_layouts, layout_of},
 };
 
@@ -245,3 +245,25 @@ fn mutual_cycle_panics() {
     ]);
     compute_layouts(&mut prog);
 }
+
+#[test]
+fn generic_templates_are_left_for_monomorphization() {
+    let mut boxed = sdef("Box", vec![field("value", AirType::Param(TypeParamId(0)))]);
+    boxed.type_params = vec![TypeParamId(0)];
+    let holder = sdef(
+        "Holder",
+        vec![field(
+            "inner",
+            AirType::GenericStruct {
+                name: "Box".into(),
+                args: vec![AirType::I8],
+            },
+        )],
+    );
+    let plain = sdef("Plain", vec![field("x", AirType::I32)]);
+    let mut prog = program(vec![boxed, holder, plain]);
+    compute_layouts(&mut prog);
+    assert_eq!(prog.structs[0].fields[0].offset, None);
+    assert_eq!(prog.structs[1].fields[0].offset, None);
+    assert_eq!(prog.structs[2].fields[0].offset, Some(0));
+}
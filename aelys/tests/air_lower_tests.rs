@@ -183,3 +183,216 @@ fn float_literal_defaults_to_f64() {
         Some(AirConst::Float(_, AirFloatSize::F64))
     ));
 }
+
+#[test]
+fn nested_if_branches_to_first_block_of_region() {
+    let air = lower_source(
+        "fn f(c: bool, d: bool) -> int {\n    let mut x = 0\n    if c {\n        if d { x = 1 }\n        x = x + 10\n    }\n    return x\n}",
+    );
+    let f = func(&air, "f");
+    let AirTerminator::Branch { then_block, .. } = &f.blocks[0].terminator else {
+        panic!("entry block should branch on `c`");
+    };
+    let then = f.blocks.iter().find(|b| b.id == *then_block).unwrap();
+    assert!(
+        matches!(then.terminator, AirTerminator::Branch { .. }),
+        "outer then-target must be the block testing `d`"
+    );
+}
+
+#[test]
+fn tail_expression_becomes_return() {
+    let air = lower_source("fn f(n: int) -> int {\n    n + 1\n}");
+    let f = func(&air, "f");
+    assert!(
+        f.blocks
+            .iter()
+            .any(|b| matches!(b.terminator, AirTerminator::Return(Some(_))))
+    );
+}
+
+#[test]
+fn top_level_function_reading_global_is_not_a_closure() {
+    let air = lower_source("let LIMIT = 10\nfn f(n: int) -> bool {\n    n < LIMIT\n}");
+    let f = func(&air, "f");
+    assert_eq!(f.params.len(), 1);
+    assert!(!air.structs.iter().any(|s| s.is_closure_env));
+}
+
+#[test]
+fn method_call_passes_receiver_first() {
+    let air = lower_source("fn f() -> int {\n    let a = Array[1, 2]\n    a.len()\n}");
+    let f = func(&air, "f");
+    assert!(has_named_call(f, "__aelys_method_len"));
+}
+
+#[test]
+fn calling_a_parameter_goes_through_fn_ptr() {
+    let air = lower_source("fn apply(f, x: int) -> int { f(x) }");
+    let f = func(&air, "apply");
+    assert!(!has_named_call(f, "f"));
+    assert!(f.blocks.iter().flat_map(|b| &b.stmts).any(|s| matches!(
+        &s.kind,
+        AirStmtKind::Assign {
+            rvalue: Rvalue::Call {
+                func: Callee::FnPtr(_),
+                ..
+            },
+            ..
+        }
+    )));
+}
+
+#[test]
+fn closure_call_passes_environment_first() {
+    let air = lower_source(
+        "fn main() -> int {\n    let base = 10\n    fn add(x: int) -> int { x + base }\n    add(1)\n}",
+    );
+    let main = func(&air, "main");
+    let call_args = main
+        .blocks
+        .iter()
+        .flat_map(|b| &b.stmts)
+        .find_map(|s| match &s.kind {
+            AirStmtKind::Assign {
+                rvalue:
+                    Rvalue::Call {
+                        func: Callee::Named(n),
+                        args,
+                    },
+                ..
+            } if n == "add" => Some(args),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(call_args.len(), 2);
+    let Operand::Copy(env) = &call_args[0] else {
+        panic!("environment should be a local");
+    };
+    let env_ty = &main.locals.iter().find(|l| l.id == *env).unwrap().ty;
+    assert!(matches!(env_ty, AirType::Ptr(inner)
+        if matches!(&**inner, AirType::Struct(s) if s == "__closure_env_add")));
+}
+
+// printed AIR of whole functions, so block ids and branch targets are pinned
+
+fn printed(code: &str, name: &str) -> String {
+    let air = lower_source(code);
+    aelys_air::print::print_function(func(&air, name), &air)
+}
+
+#[test]
+fn printed_while_with_break_inside_if() {
+    let out = printed(
+        "fn f(n: int) -> int {\n    let mut i = 0\n    let mut s = 0\n    while i < n {\n        if i == 3 {\n            break\n        }\n        s = s + i\n        i = i + 1\n    }\n    return s\n}",
+        "f",
+    );
+    assert_eq!(
+        out,
+        "fn f(n: i64) -> i64  [managed, aelys]
+  locals:
+    %0: i64
+    %1: i64
+    %2: i64
+    %3: bool
+    %4: bool
+    %5: i64
+    %6: i64
+  block3:
+    %1: i64 = use 0i64
+    %2: i64 = use 0i64
+    goto block0
+  block0:
+    %3: bool = binop lt %1: i64, %0: i64
+    branch %3: bool -> block1 else block2
+  block1:
+    %4: bool = binop eq %1: i64, 3i64
+    branch %4: bool -> block4 else block6
+  block4:
+    goto block2
+  block6:
+    %5: i64 = binop add %2: i64, %1: i64
+    %2: i64 = use %5: i64
+    %6: i64 = binop add %1: i64, 1i64
+    %1: i64 = use %6: i64
+    goto block0
+  block2:
+    return %2: i64
+"
+    );
+}
+
+#[test]
+fn printed_tail_if_else_returns_from_both_arms() {
+    let out = printed("fn g(c: bool) -> int {\n    if c { 1 } else { 2 }\n}", "g");
+    assert_eq!(
+        out,
+        "fn g(c: bool) -> i64  [managed, aelys]
+  locals:
+    %0: bool
+  block2:
+    branch %0: bool -> block0 else block1
+  block0:
+    return 1i64
+  block1:
+    return 2i64
+"
+    );
+}
+
+#[test]
+fn printed_short_circuit_condition_merges_before_branch() {
+    let out = printed(
+        "fn k(c: bool, d: bool) -> int {\n    if c and d {\n        return 1\n    } else {\n        return 2\n    }\n}",
+        "k",
+    );
+    assert_eq!(
+        out,
+        "fn k(c: bool, d: bool) -> i64  [managed, aelys]
+  locals:
+    %0: bool
+    %1: bool
+    %2: bool
+  block2:
+    %2: bool = use %0: bool
+    branch %2: bool -> block0 else block1
+  block0:
+    %2: bool = use %1: bool
+    goto block1
+  block1:
+    branch %2: bool -> block3 else block4
+  block3:
+    return 1i64
+  block4:
+    return 2i64
+"
+    );
+}
+
+#[test]
+fn printed_placeholders_take_the_following_arguments() {
+    let out = printed(
+        "fn h(a: int, b: int) {\n    println(\"{} and {}\", a, b)\n}",
+        "h",
+    );
+    assert_eq!(
+        out,
+        "fn h(a: i64, b: i64) -> i64  [managed, aelys]
+  locals:
+    %0: i64
+    %1: i64
+    %2: str
+    %3: str
+    %4: str
+    %5: str
+    %6: i64
+  block0:
+    %2: str = call __aelys_to_string(%0: i64)
+    %3: str = call __aelys_to_string(%1: i64)
+    %4: str = call __aelys_str_concat(%2: str, \" and \")
+    %5: str = call __aelys_str_concat(%4: str, %3: str)
+    %6: i64 = call println(%5: str)
+    return %6: i64
+"
+    );
+}
@@ -0,0 +1,174 @@
+use aelys_air::interp::run_function;
+use aelys_air::layout::compute_layouts;
+use aelys_air::lower::lower;
+use aelys_air::mono::monomorphize;
+use aelys_air::passes::*;
+use aelys_air::*;
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+
+fn lower_source(code: &str) -> AirProgram {
+    let src = Source::new("<test>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let mut air = lower(&typed);
+    compute_layouts(&mut air);
+    monomorphize(air)
+}
+
+fn func<'a>(air: &'a AirProgram, name: &str) -> &'a AirFunction {
+    air.functions
+        .iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("function '{name}' not found"))
+}
+
+fn phi_count(f: &AirFunction) -> usize {
+    f.blocks
+        .iter()
+        .flat_map(|b| &b.stmts)
+        .filter(|s| {
+            matches!(
+                s.kind,
+                AirStmtKind::Assign {
+                    rvalue: Rvalue::Phi(_),
+                    ..
+                }
+            )
+        })
+        .count()
+}
+
+fn has_branch(f: &AirFunction) -> bool {
+    f.blocks
+        .iter()
+        .any(|b| matches!(b.terminator, AirTerminator::Branch { .. }))
+}
+
+fn stmt_count(f: &AirFunction) -> usize {
+    f.blocks.iter().map(|b| b.stmts.len()).sum()
+}
+
+const LOOP: &str = "fn sum_to(n: int) -> int {
+    let mut total = 0
+    let mut i = 0
+    while i < n {
+        total += i
+        i += 1
+    }
+    return total
+}";
+
+#[test]
+fn ssa_puts_phis_at_loop_headers() {
+    let mut air = lower_source(LOOP);
+    PassManager::new().add(SsaConstruction).run(&mut air);
+    assert!(phi_count(func(&air, "sum_to")) >= 2);
+}
+
+#[test]
+fn standard_pipeline_leaves_no_phis() {
+    let mut air = lower_source(LOOP);
+    PassManager::standard().run(&mut air);
+    for f in &air.functions {
+        assert_eq!(phi_count(f), 0, "phi left in {}", f.name);
+    }
+    let out = run_function(&air, "sum_to", vec![interp::Value::Int(10)]).unwrap();
+    assert_eq!(out.to_string(), "45");
+}
+
+#[test]
+fn out_of_ssa_without_cleanup_still_runs() {
+    let mut air = lower_source(LOOP);
+    PassManager::new()
+        .add(SsaConstruction)
+        .add(SsaDestruction)
+        .run(&mut air);
+    assert_eq!(phi_count(func(&air, "sum_to")), 0);
+    let out = run_function(&air, "sum_to", vec![interp::Value::Int(5)]).unwrap();
+    assert_eq!(out.to_string(), "10");
+}
+
+#[test]
+fn constant_branch_is_folded_away() {
+    let mut air = lower_source(
+        "fn pick() -> int {
+    let a = 2
+    let b = 3
+    if a * b > 5 {
+        return 1
+    }
+    return 0
+}",
+    );
+    assert!(has_branch(func(&air, "pick")));
+    PassManager::standard().run(&mut air);
+    let f = func(&air, "pick");
+    assert!(!has_branch(f));
+    assert_eq!(f.blocks.len(), 1);
+}
+
+#[test]
+fn constant_folding_respects_division_by_zero() {
+    let mut air = lower_source("fn boom() -> int {\n    let z = 0\n    return 10 / z\n}");
+    PassManager::standard().run(&mut air);
+    assert!(run_function(&air, "boom", vec![]).is_err());
+}
+
+#[test]
+fn dead_assignments_are_removed() {
+    let mut air = lower_source(
+        "fn f(x: int) -> int {
+    let unused = x * 3
+    let also = unused + 1
+    return x
+}",
+    );
+    let before = stmt_count(func(&air, "f"));
+    let changed = PassManager::new()
+        .add(SsaConstruction)
+        .add(DeadCodeElimination)
+        .run(&mut air);
+    assert!(changed);
+    assert!(stmt_count(func(&air, "f")) < before);
+}
+
+#[test]
+fn calls_survive_dce_even_when_unused() {
+    let mut air = lower_source(
+        "fn side() -> int {\n    println(\"hi\")\n    return 1\n}\nfn f() -> int {\n    let x = side()\n    return 2\n}",
+    );
+    PassManager::standard().run(&mut air);
+    let f = func(&air, "f");
+    assert!(f.blocks.iter().flat_map(|b| &b.stmts).any(|s| matches!(
+        &s.kind,
+        AirStmtKind::Assign {
+            rvalue: Rvalue::Call { .. },
+            ..
+        } | AirStmtKind::CallVoid { .. }
+    )));
+}
+
+#[test]
+fn empty_pipeline_changes_nothing() {
+    let mut air = lower_source(LOOP);
+    let before = print::print_program(&air);
+    assert!(!PassManager::new().run(&mut air));
+    assert_eq!(print::print_program(&air), before);
+}
+
+#[test]
+fn observer_sees_input_then_every_pass() {
+    let mut air = lower_source(LOOP);
+    let mut pm = PassManager::standard();
+    let mut seen = Vec::new();
+    pm.run_with_observer(&mut air, |stage, _| seen.push(stage.to_string()));
+    let mut expected = vec!["input".to_string()];
+    expected.extend(pm.pass_names().iter().map(|s| s.to_string()));
+    assert_eq!(seen, expected);
+    assert_eq!(seen[1], "ssa");
+    assert!(seen.contains(&"out-of-ssa".to_string()));
+}
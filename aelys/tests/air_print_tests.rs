@@ -454,4 +454,11 @@ fn type_formats() {
         "fn(i64) -> bool"
     );
     assert_eq!(fmt_type(&AirType::Param(TypeParamId(0))), "T0");
+    assert_eq!(
+        fmt_type(&AirType::GenericStruct {
+            name: "Pair".into(),
+            args: vec![AirType::Param(TypeParamId(0)), AirType::Str],
+        }),
+        "Pair<T0, str>"
+    );
 }
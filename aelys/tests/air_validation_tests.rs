@@ -370,6 +370,107 @@ fn caller() -> i32 {
     );
 }
 
+#[test]
+fn generic_struct_instantiated_per_argument_list() {
+    let mut program = lower_source(
+        r#"
+struct Pair<A, B> { a: A, b: B }
+struct Box<T> { value: T }
+fn main() -> int {
+    let p = Pair { a: 1, b: "x" }
+    let q = Pair { a: true, b: 2 }
+    let r = Pair { a: Box { value: 3 }, b: 4 }
+    return p.a
+}
+"#,
+    );
+    compute_layouts(&mut program);
+    let program = monomorphize(program);
+
+    let names: Vec<&str> = program.structs.iter().map(|s| s.name.as_str()).collect();
+    for expected in [
+        "__mono_Pair_i64_str",
+        "__mono_Pair_bool_i64",
+        "__mono_Pair_Box_i64_i64",
+        "__mono_Box_i64",
+    ] {
+        assert!(names.contains(&expected), "missing {expected} in {names:?}");
+    }
+    assert!(
+        !names.contains(&"Pair") && !names.contains(&"Box"),
+        "generic templates should be dropped: {names:?}"
+    );
+
+    let offsets = |name: &str| -> Vec<Option<u32>> {
+        let def = program.structs.iter().find(|s| s.name == name).unwrap();
+        def.fields.iter().map(|f| f.offset).collect()
+    };
+    assert_eq!(offsets("__mono_Pair_bool_i64"), vec![Some(0), Some(8)]);
+    assert_eq!(offsets("__mono_Pair_Box_i64_i64"), vec![Some(0), Some(8)]);
+
+    let nested = program
+        .structs
+        .iter()
+        .find(|s| s.name == "__mono_Pair_Box_i64_i64")
+        .unwrap();
+    assert_eq!(
+        nested.fields[0].ty,
+        AirType::Struct("__mono_Box_i64".to_string())
+    );
+
+    let inits: Vec<&str> = func(&program, "main")
+        .blocks
+        .iter()
+        .flat_map(|b| &b.stmts)
+        .filter_map(|s| match &s.kind {
+            AirStmtKind::Assign {
+                rvalue: Rvalue::StructInit { name, .. },
+                ..
+            } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert!(inits.iter().all(|n| n.starts_with("__mono_")), "{inits:?}");
+    assert!(verify(&program).is_empty());
+}
+
+#[test]
+fn generic_calls_inside_instances_are_instantiated() {
+    let mut program = lower_source(
+        r#"
+struct Box<T> { value: T }
+fn wrap<T>(x: T) -> Box<T> {
+    return Box { value: x }
+}
+fn twice<T>(x: T) -> Box<T> {
+    return wrap(x)
+}
+fn main() -> f64 {
+    let a = twice(1)
+    let b = twice(2.5)
+    return b.value
+}
+"#,
+    );
+    compute_layouts(&mut program);
+    let program = monomorphize(program);
+
+    let names: Vec<&str> = program.functions.iter().map(|f| f.name.as_str()).collect();
+    for expected in [
+        "__mono_twice_i64",
+        "__mono_twice_f64",
+        "__mono_wrap_i64",
+        "__mono_wrap_f64",
+    ] {
+        assert!(names.contains(&expected), "missing {expected} in {names:?}");
+    }
+    assert_eq!(
+        func(&program, "__mono_wrap_f64").ret_ty,
+        AirType::Struct("__mono_Box_f64".to_string())
+    );
+    assert!(verify(&program).is_empty(), "{:?}", verify(&program));
+}
+
 #[test]
 fn gc_mode_propagation() {
     let air = lower_source(
@@ -0,0 +1,252 @@
+use aelys_air::layout::compute_layouts;
+use aelys_air::lower::lower;
+use aelys_air::mono::monomorphize;
+use aelys_air::passes::{PassManager, SsaConstruction};
+use aelys_air::*;
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+use std::path::PathBuf;
+
+fn lower_source(code: &str) -> AirProgram {
+    let src = Source::new("<test>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let mut air = lower(&typed);
+    compute_layouts(&mut air);
+    monomorphize(air)
+}
+
+fn func_mut<'a>(air: &'a mut AirProgram, name: &str) -> &'a mut AirFunction {
+    air.functions
+        .iter_mut()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("function '{name}' not found"))
+}
+
+fn messages(air: &AirProgram) -> Vec<String> {
+    verify(air).iter().map(|e| e.to_string()).collect()
+}
+
+fn assert_reports(air: &AirProgram, needle: &str) {
+    let errors = messages(air);
+    assert!(
+        errors.iter().any(|e| e.contains(needle)),
+        "expected an error containing {needle:?}, got {errors:#?}"
+    );
+}
+
+const ADD: &str = "fn add(a: int, b: int) -> int {\n    let c = a + b\n    return c\n}\nfn main() -> int {\n    return add(1, 2)\n}";
+
+#[test]
+fn fixtures_verify_before_and_after_passes() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/air_diff");
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        let code = std::fs::read_to_string(&path).unwrap();
+        let mut air = lower_source(&code);
+        assert_eq!(messages(&air), Vec::<String>::new(), "{}", path.display());
+
+        let mut ssa = air.clone();
+        PassManager::new().add(SsaConstruction).run(&mut ssa);
+        assert_eq!(
+            messages(&ssa),
+            Vec::<String>::new(),
+            "{} (ssa)",
+            path.display()
+        );
+
+        PassManager::standard().run(&mut air);
+        assert_eq!(
+            messages(&air),
+            Vec::<String>::new(),
+            "{} (passes)",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn local_type_mismatch_is_reported() {
+    let mut air =
+        lower_source("fn add(a: i32, b: i32) -> i32 {\n    let c = a + b\n    return c\n}");
+    let f = func_mut(&mut air, "add");
+    let c = f
+        .locals
+        .iter_mut()
+        .find(|l| l.name.as_deref() == Some("c"))
+        .unwrap();
+    c.ty = AirType::Bool;
+    assert_reports(&air, "assignment expects bool, got i32");
+}
+
+#[test]
+fn use_before_assignment_is_reported() {
+    let mut air = lower_source(ADD);
+    let f = func_mut(&mut air, "add");
+    let c = f
+        .locals
+        .iter()
+        .find(|l| l.name.as_deref() == Some("c"))
+        .unwrap()
+        .id;
+    for block in f.blocks.iter_mut() {
+        block.stmts.retain(
+            |s| !matches!(&s.kind, AirStmtKind::Assign { place: Place::Local(id), .. } if *id == c),
+        );
+    }
+    assert_reports(&air, &format!("%{} is used before it is assigned", c.0));
+}
+
+#[test]
+fn assignment_on_one_branch_only_is_not_definite() {
+    let mut air = lower_source(
+        "fn pick(flag: bool) -> int {\n    let mut x = 0\n    if flag {\n        x = 1\n    }\n    return x\n}",
+    );
+    assert!(verify(&air).is_empty());
+    let f = func_mut(&mut air, "pick");
+    // drop the `let mut x = 0` in the entry block, the `if` still assigns it
+    let x = f
+        .locals
+        .iter()
+        .find(|l| l.name.as_deref() == Some("x"))
+        .unwrap()
+        .id;
+    f.blocks[0].stmts.retain(
+        |s| !matches!(&s.kind, AirStmtKind::Assign { place: Place::Local(id), .. } if *id == x),
+    );
+    assert_reports(&air, "is used before it is assigned");
+}
+
+#[test]
+fn jump_to_missing_block_is_reported() {
+    let mut air = lower_source(
+        "fn count(n: int) -> int {\n    let mut i = 0\n    while i < n {\n        i += 1\n    }\n    return i\n}",
+    );
+    let f = func_mut(&mut air, "count");
+    let block = f
+        .blocks
+        .iter_mut()
+        .find(|b| matches!(b.terminator, AirTerminator::Goto(_)))
+        .unwrap();
+    block.terminator = AirTerminator::Goto(BlockId(9999));
+    assert_reports(&air, "jumps to missing block9999");
+}
+
+#[test]
+fn reachable_unreachable_terminator_is_reported() {
+    let mut air = lower_source(ADD);
+    let f = func_mut(&mut air, "add");
+    let last = f.blocks.len() - 1;
+    f.blocks[last].terminator = AirTerminator::Unreachable;
+    assert_reports(&air, "`unreachable` terminator can be reached");
+}
+
+#[test]
+fn call_arity_is_checked() {
+    let mut air = lower_source(ADD);
+    let f = func_mut(&mut air, "main");
+    for stmt in f.blocks.iter_mut().flat_map(|b| b.stmts.iter_mut()) {
+        if let AirStmtKind::Assign {
+            rvalue: Rvalue::Call { args, .. },
+            ..
+        } = &mut stmt.kind
+        {
+            args.pop();
+        }
+    }
+    assert_reports(&air, "`add` takes 2 arguments, called with 1");
+}
+
+#[test]
+fn direct_call_to_missing_function_is_reported() {
+    let mut air = lower_source(ADD);
+    let f = func_mut(&mut air, "main");
+    for stmt in f.blocks.iter_mut().flat_map(|b| b.stmts.iter_mut()) {
+        if let AirStmtKind::Assign {
+            rvalue: Rvalue::Call { func, .. },
+            ..
+        } = &mut stmt.kind
+        {
+            *func = Callee::Direct(FunctionId(4242));
+        }
+    }
+    assert_reports(&air, "call to missing function id 4242");
+}
+
+#[test]
+fn unknown_field_is_reported() {
+    let mut air = lower_source(
+        "struct Point { x: int, y: int }\nfn get_x(p: Point) -> int {\n    return p.x\n}",
+    );
+    let f = func_mut(&mut air, "get_x");
+    for stmt in f.blocks.iter_mut().flat_map(|b| b.stmts.iter_mut()) {
+        if let AirStmtKind::Assign {
+            rvalue: Rvalue::FieldAccess { field, .. },
+            ..
+        } = &mut stmt.kind
+        {
+            *field = "z".to_string();
+        }
+    }
+    assert_reports(&air, "struct `Point` has no field `z`");
+}
+
+#[test]
+fn leftover_type_param_is_reported() {
+    let mut air = lower_source(
+        "fn identity<T>(x: T) -> T {\n    return x\n}\nfn main() -> int {\n    return identity(1)\n}",
+    );
+    assert!(verify(&air).is_empty());
+    let f = func_mut(&mut air, "main");
+    f.ret_ty = AirType::Param(TypeParamId(0));
+    assert_reports(
+        &air,
+        "return type still has a type parameter after monomorphization",
+    );
+}
+
+#[test]
+fn generic_templates_are_not_checked() {
+    let src = Source::new(
+        "<test>",
+        "fn identity<T>(x: T) -> T {\n    return x\n}\nfn main() -> int {\n    return identity(1)\n}",
+    );
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src).unwrap();
+    let air = lower(&typed);
+    assert!(
+        air.functions.iter().any(|f| !f.type_params.is_empty()),
+        "expected a generic template before monomorphization"
+    );
+    assert!(verify(&air).is_empty(), "{:#?}", messages(&air));
+}
+
+#[test]
+fn errors_name_function_and_block() {
+    let mut air = lower_source(ADD);
+    let f = func_mut(&mut air, "add");
+    let entry = f.blocks[0].id;
+    f.blocks[0].terminator = AirTerminator::Goto(BlockId(777));
+    let errors = verify(&air);
+    let err = errors
+        .iter()
+        .find(|e| e.message.contains("block777"))
+        .unwrap();
+    assert_eq!(err.function.as_deref(), Some("add"));
+    assert_eq!(err.block, Some(entry));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "in function `add`, block{}: jumps to missing block777",
+            entry.0
+        )
+    );
+}
@@ -0,0 +1,264 @@
+mod common;
+use common::*;
+
+#[test]
+fn sort_typed_storage() {
+    assert_aelys_str(
+        r#"
+let a = [5, -2, 9, 0, 3]
+a.sort()
+let f = Vec[2.5, -1.0, 0.5]
+f.sort()
+let b = [true, false, true]
+b.sort()
+"{a} {f} {b}"
+"#,
+        "[-2, 0, 3, 5, 9] Vec[-1.0, 0.5, 2.5] [false, true, true]",
+    );
+}
+
+#[test]
+fn sort_untyped_values() {
+    assert_aelys_str(
+        r#"
+let words = Vec["pear", "Apple", "fig"]
+words.sort()
+let mut nums = Vec[]
+nums.push(3)
+nums.push(1.5)
+nums.push(-2)
+nums.sort()
+"{words} {nums}"
+"#,
+        "Vec[Apple, fig, pear] Vec[-2, 1.5, 3]",
+    );
+    assert_aelys_error_contains(
+        r#"
+let mut mixed = Vec[]
+mixed.push(1)
+mixed.push("one")
+mixed.sort()
+"#,
+        "expected numbers, strings or bools of one kind, got int and string",
+    );
+}
+
+#[test]
+fn sort_by_comparator_is_stable() {
+    assert_aelys_str(
+        r#"
+let v = Vec["bb", "a", "cc", "d", ""]
+let by_len = fn(x, y) {
+    return x.len() - y.len()
+}
+v.sort_by(by_len)
+let desc = [3, 1, 2]
+desc.sort_by(fn(x, y) { return y - x })
+"{v} {desc}"
+"#,
+        "Vec[, a, d, bb, cc] [3, 2, 1]",
+    );
+}
+
+#[test]
+fn sort_by_comparator_errors() {
+    assert_aelys_error_contains(
+        "let a = [2, 1]\na.sort_by(fn(x, y) { return true })",
+        "expected int from the comparator, got bool",
+    );
+    assert_aelys_error_contains(
+        "let a = [2, 1]\na.sort_by(fn(x, y) { return x + \"\" })",
+        "'addition'",
+    );
+    assert_aelys_error_contains(
+        "let v = Vec[3, 2, 1]\nlet shrink = fn(x, y) {\nv.pop()\nreturn x - y\n}\nv.sort_by(shrink)",
+        "length changed from 3",
+    );
+}
+
+#[test]
+fn sort_by_key_keeps_keys_alive() {
+    // the keys are fresh strings and the key function churns the heap, so
+    // collections would run mid-sort if they were allowed to
+    assert_aelys_str(
+        r#"
+let v = Vec[]
+for i in 0..300 {
+    v.push((i * 7919) % 300)
+}
+let key = fn(n) {
+    let mut junk = ""
+    for j in 0..200 {
+        junk = junk + "x"
+    }
+    return "k" + __tostring(1000 + n)
+}
+v.sort_by_key(key)
+let mut ok = true
+for i in 0..v.len() {
+    if v[i] != i {
+        ok = false
+    }
+}
+let words = ["ccc", "a", "bb", "dd"]
+words.sort_by_key(fn(w) { return w.len() })
+"{ok} {words}"
+"#,
+        "true [a, bb, dd, ccc]",
+    );
+}
+
+#[test]
+fn sort_by_from_a_function_keeps_its_locals() {
+    assert_aelys_str(
+        r#"
+fn ranked(items, weights) {
+    let label = "ranked"
+    let mut calls = 0
+    let by_weight = fn(x) {
+        calls++
+        return weights[x]
+    }
+    items.sort_by_key(by_weight)
+    return "{label} {items} {calls}"
+}
+ranked(Vec[0, 1, 2, 3], [40, 10, 30, 20])
+"#,
+        "ranked Vec[1, 3, 2, 0] 4",
+    );
+}
+
+#[test]
+fn searching() {
+    assert_aelys_str(
+        r#"
+let sorted = [1, 3, 5, 7, 9, 11]
+let names = Vec["ann", "bob", "cy"]
+let f = [0.5, 1.5]
+"{sorted.binary_search(7)} {sorted.binary_search(4)} {names.binary_search("cy")} {sorted.contains(9)} {sorted.contains(2)} {names.index_of("bob")} {names.index_of("zed")} {f.contains(1.5)}"
+"#,
+        "3 -1 2 true false 1 -1 true",
+    );
+    assert_aelys_error_contains(
+        "let a = [1, 2]\na.binary_search(\"x\")",
+        "got int and string",
+    );
+}
+
+#[test]
+fn ints_and_floats_match_by_value() {
+    // typed storage and untyped values answer alike
+    assert_aelys_str(
+        r#"
+let ints = [1, 2]
+let floats = Vec[1.0, 2.0]
+let mut mixed = Vec[]
+mixed.push("a")
+mixed.push(2.0)
+"{ints.contains(2.0)} {ints.index_of(2.0)} {ints.contains(2.5)} {floats.index_of(2)} {mixed.index_of(2)}"
+"#,
+        "true 1 false 1 1",
+    );
+}
+
+#[test]
+fn nan_sorts_last() {
+    assert_aelys_str(
+        r#"
+let z = 0.0
+let nan = z / z
+let f = [3.0, nan, -1.0, -nan, 2.0]
+f.sort()
+let mut v = Vec[]
+v.push(nan)
+v.push(1)
+v.push(-0.5)
+v.sort()
+"{f} {v}"
+"#,
+        "[-1.0, 2.0, 3.0, NaN, NaN] Vec[-0.5, 1, NaN]",
+    );
+}
+
+#[test]
+fn min_max_and_join() {
+    assert_aelys_str(
+        r#"
+let a = [4, -3, 8]
+let f = Vec[2.5, 9.0, -0.5]
+let s = ["kiwi", "apple"]
+let empty = Vec<Int>[]
+"{a.min()} {a.max()} {f.min()} {f.max()} {s.min()} {empty.max()} {a.join(", ")} {s.join("")}"
+"#,
+        "-3 8 -0.5 9.0 apple null 4, -3, 8 kiwiapple",
+    );
+}
+
+#[test]
+fn reverse_and_swap_work_on_arrays() {
+    assert_aelys_str(
+        r#"
+let a = [1, 2, 3, 4]
+a.reverse()
+a.swap(0, 3)
+let b = Vec[true, false]
+b.swap(0, 1)
+"{a} {b}"
+"#,
+        "[1, 3, 2, 4] Vec[false, true]",
+    );
+    assert_aelys_error_contains("let a = [1, 2]\na.swap(0, 2)", "index out of bounds");
+}
+
+#[test]
+fn vec_insert_remove_extend_dedup() {
+    assert_aelys_str(
+        r#"
+let v = Vec[1, 2, 2, 3]
+v.insert(0, 0)
+v.insert(5, 3)
+let removed = v.remove(1)
+v.extend([3, 4, 4])
+v.extend(Vec[5])
+v.dedup()
+let words = Vec["a", "a", "b", "a"]
+words.dedup()
+"{removed} {v} {words}"
+"#,
+        "1 Vec[0, 2, 3, 4, 5] Vec[a, b, a]",
+    );
+}
+
+#[test]
+fn vec_only_methods_reject_arrays_and_bad_elements() {
+    assert_aelys_error_contains("let a = [1, 2]\na.insert(0, 5)", "expected vec, got array");
+    assert_aelys_error_contains("let a = [1, 2]\na.dedup()", "expected vec, got array");
+    assert_aelys_error_contains("let v = Vec[1]\nv.insert(2, 5)", "index out of bounds");
+    assert_aelys_error_contains("let v = Vec[1]\nv.remove(1)", "index out of bounds");
+    assert_aelys_error_contains(
+        "let v = Vec[1, 2]\nlet extra = Vec[]\nextra.push(3)\nextra.push(\"four\")\nv.extend(extra)",
+        "expected matching element type, got string",
+    );
+}
+
+#[test]
+fn shared_names_follow_the_receiver() {
+    // reverse, contains and join are string methods too; on an untyped
+    // receiver whichever fits what turns up at runtime runs
+    assert_aelys_str(
+        r#"
+let things = Vec[]
+things.push("abc")
+things.push(Vec[1, 2, 3])
+things.push("a\nb")
+let mut out = ""
+for i in 0..2 {
+    let x = things[i]
+    let r = x.reverse()
+    out = out + "{x}/{r} {x.contains("b")} "
+}
+"{out}{things[2].join("+")} {things[1].join("+")}"
+"#,
+        "abc/cba true Vec[3, 2, 1]/null false a+b 3+2+1",
+    );
+}
@@ -1,6 +1,12 @@
-use aelys_runtime::{VM, Value, builtin_type, register_builtins};
+use aelys_common::error::RuntimeErrorKind;
+use aelys_runtime::{
+    VM, Value, builtin_assert, builtin_assert_eq, builtin_type, register_builtins,
+};
 use aelys_syntax::Source;
 
+mod common;
+use common::*;
+
 fn make_test_vm() -> VM {
     let source = Source::new("test.aelys".to_string(), "".to_string());
     VM::new(source).unwrap()
@@ -17,6 +23,8 @@ fn test_register_builtins() {
     assert!(vm.get_global("free").is_some());
     assert!(vm.get_global("load").is_some());
     assert!(vm.get_global("store").is_some());
+    assert!(vm.get_global("assert").is_some());
+    assert!(vm.get_global("assert_eq").is_some());
 }
 
 #[test]
@@ -36,3 +44,65 @@ fn test_builtin_type() {
     let result = builtin_type(&mut vm, &[Value::null()]).unwrap();
     assert!(result.is_ptr());
 }
+
+#[test]
+fn test_builtin_assert() {
+    let mut vm = make_test_vm();
+
+    assert!(builtin_assert(&mut vm, &[Value::bool(true)]).is_ok());
+    assert!(builtin_assert(&mut vm, &[Value::int(0)]).is_ok());
+
+    let err = builtin_assert(&mut vm, &[Value::bool(false)]).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        RuntimeErrorKind::AssertionFailed {
+            left: None,
+            right: None
+        }
+    ));
+    assert!(builtin_assert(&mut vm, &[Value::null()]).is_err());
+}
+
+#[test]
+fn test_builtin_assert_eq_reports_both_sides() {
+    let mut vm = make_test_vm();
+
+    assert!(builtin_assert_eq(&mut vm, &[Value::int(3), Value::int(3)]).is_ok());
+
+    let err = builtin_assert_eq(&mut vm, &[Value::int(2), Value::int(3)]).unwrap_err();
+    match err.kind {
+        RuntimeErrorKind::AssertionFailed { left, right } => {
+            assert_eq!(left.as_deref(), Some("2"));
+            assert_eq!(right.as_deref(), Some("3"));
+        }
+        other => panic!("expected an assertion failure, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_assert_eq_compares_contents() {
+    run_aelys_ok(
+        r#"
+assert_eq("ab", "a" + "b")
+assert_eq([1, 2, 3], [1, 2, 3])
+assert_eq(Vec<int>[1, 2], Vec<int>[1, 2])
+assert(1 < 2)
+"#,
+    );
+}
+
+#[test]
+fn test_assert_eq_failure_message() {
+    let err = run_aelys_err(
+        r#"
+let name = "bob"
+let kind = type(name)
+assert_eq(name, "alice")
+"#,
+    );
+    assert!(err.contains("assertion failed: left == right"), "{}", err);
+    assert!(err.contains("left: \"bob\""), "{}", err);
+    assert!(err.contains("right: \"alice\""), "{}", err);
+    // points at the failing call, not at an earlier native call
+    assert!(err.contains(":4:"), "{}", err);
+}
@@ -0,0 +1,132 @@
+use aelys_backend::Compiler;
+use aelys_frontend::{lexer::Lexer, parser::Parser};
+use aelys_runtime::{CoverageReport, FileCoverage, VM, VmConfig};
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+
+const PROGRAM: &str = "fn classify(n: int) -> int {
+    if n < 0 {
+        return -1
+    }
+    return 1
+}
+fn never() -> int {
+    return 0
+}
+let mut total = 0
+for i in 0..4 {
+    total += classify(i)
+}";
+
+fn cover(code: &str) -> CoverageReport {
+    let src = Source::new("<coverage>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src.clone()).unwrap();
+    let (mut func, mut heap, _) = Compiler::new(None, src.clone())
+        .compile_typed(&typed)
+        .unwrap();
+
+    let config = VmConfig {
+        coverage: true,
+        ..VmConfig::default()
+    };
+    let mut vm = VM::with_config_and_args(src, config, vec![]).unwrap();
+    let remap = vm.merge_heap(&mut heap).unwrap();
+    func.remap_constants(&remap);
+    vm.cover_source("cov.aelys", &func);
+    let main = vm.alloc_function(func).unwrap();
+    vm.execute(main).unwrap();
+    vm.take_coverage().unwrap()
+}
+
+fn file(report: &CoverageReport) -> &FileCoverage {
+    assert_eq!(report.files.len(), 1);
+    &report.files[0]
+}
+
+#[test]
+fn counts_line_hits() {
+    let report = cover(PROGRAM);
+    let file = file(&report);
+
+    assert_eq!(file.path, "cov.aelys");
+    assert_eq!(file.lines.get(&2), Some(&4));
+    assert_eq!(file.lines.get(&3), Some(&0));
+    assert_eq!(file.lines.get(&5), Some(&4));
+    assert_eq!(file.lines.get(&8), Some(&0));
+    // braces and blank lines have no code
+    assert_eq!(file.lines.get(&4), None);
+}
+
+#[test]
+fn counts_function_calls() {
+    let report = cover(PROGRAM);
+    let calls = |name: &str| {
+        file(&report)
+            .functions
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.calls)
+    };
+
+    assert_eq!(calls("classify"), Some(4));
+    assert_eq!(calls("never"), Some(0));
+}
+
+#[test]
+fn records_both_sides_of_branches() {
+    let report = cover(PROGRAM);
+    let file = file(&report);
+
+    let if_branch = file.branches.iter().find(|b| b.line == 2).unwrap();
+    // n < 0 never held: jumped past the body every time
+    assert_eq!(if_branch.taken, Some([4, 0]));
+}
+
+#[test]
+fn branches_that_never_ran_have_no_counts() {
+    let report =
+        cover("fn never(b: bool) -> int {\n    if b {\n        return 1\n    }\n    return 0\n}");
+    assert_eq!(file(&report).branches[0].taken, None);
+    assert!(report.to_lcov().contains("BRDA:2,0,0,-\nBRDA:2,0,1,-\n"));
+}
+
+#[test]
+fn writes_lcov() {
+    let lcov = cover(PROGRAM).to_lcov();
+
+    assert!(lcov.starts_with("TN:\nSF:cov.aelys\n"));
+    assert!(lcov.contains("FN:2,classify\n"));
+    assert!(lcov.contains("FNDA:0,never\n"));
+    assert!(lcov.contains("DA:3,0\n"));
+    // the loop at the top level is walked first
+    assert!(lcov.contains("BRDA:11,0,0,4\nBRDA:11,0,1,1\n"));
+    assert!(lcov.contains("BRDA:2,1,0,4\nBRDA:2,1,1,0\n"));
+    assert!(lcov.contains("FNF:2\nFNH:1\n"));
+    assert!(lcov.ends_with("end_of_record\n"));
+}
+
+#[test]
+fn merge_adds_counts() {
+    let mut report = cover(PROGRAM);
+    report.merge(cover(PROGRAM));
+    let file = file(&report);
+
+    assert_eq!(file.lines.get(&2), Some(&8));
+    let classify = file
+        .functions
+        .iter()
+        .find(|f| f.name == "classify")
+        .unwrap();
+    assert_eq!(classify.calls, 8);
+    let if_branch = file.branches.iter().find(|b| b.line == 2).unwrap();
+    assert_eq!(if_branch.taken, Some([8, 0]));
+}
+
+#[test]
+fn off_unless_configured() {
+    let src = Source::new("<coverage>", "");
+    let mut vm = VM::with_config_and_args(src, VmConfig::default(), vec![]).unwrap();
+    assert!(vm.take_coverage().is_none());
+}
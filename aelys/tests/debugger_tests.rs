@@ -0,0 +1,291 @@
+use aelys_backend::Compiler;
+use aelys_bytecode::asm::binary::{deserialize, serialize};
+use aelys_driver::load_file;
+use aelys_frontend::{lexer::Lexer, parser::Parser};
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::{
+    Breakpoints, DebugCommand, DebugFrontend, Debugger, Function, GcRef, Heap, StopEvent,
+    StopReason, VM, VmConfig,
+};
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::Arc;
+
+const PROGRAM: &str = "fn add(a: int, b: int) -> int {
+    let sum = a + b
+    return sum
+}
+fn main() {
+    let x = 10
+    let y = add(x, 5)
+    let z = y * 2
+}
+main()";
+
+fn compile(code: &str) -> (Function, Heap, Arc<Source>) {
+    let src = Source::new("<debug>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src.clone()).unwrap();
+    let (func, heap, _) = Compiler::new(None, src.clone())
+        .compile_typed(&typed)
+        .unwrap();
+    (func, heap, src)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Stop {
+    source: String,
+    line: u32,
+    function: String,
+    reason: StopReason,
+    locals: Vec<(String, String)>,
+}
+
+struct Scripted {
+    breakpoints: Vec<(String, u32)>,
+    commands: VecDeque<DebugCommand>,
+    stops: Rc<RefCell<Vec<Stop>>>,
+}
+
+impl DebugFrontend for Scripted {
+    fn on_stop(
+        &mut self,
+        vm: &VM,
+        stop: &StopEvent,
+        breakpoints: &mut Breakpoints,
+    ) -> DebugCommand {
+        for (source, line) in self.breakpoints.drain(..) {
+            breakpoints.add(&source, line);
+        }
+        let locals = vm
+            .frame_locals(0)
+            .into_iter()
+            .map(|(name, value)| (name, vm.value_to_string(value)))
+            .collect();
+        self.stops.borrow_mut().push(Stop {
+            source: stop.source.clone(),
+            line: stop.line,
+            function: stop.function.clone(),
+            reason: stop.reason,
+            locals,
+        });
+        self.commands.pop_front().unwrap_or(DebugCommand::Continue)
+    }
+}
+
+fn debug_run(breakpoints: &[u32], commands: &[DebugCommand]) -> Vec<Stop> {
+    debug_program(PROGRAM, breakpoints, commands)
+}
+
+fn debug_program(code: &str, breakpoints: &[u32], commands: &[DebugCommand]) -> Vec<Stop> {
+    let (mut func, mut heap, src) = compile(code);
+    let mut vm = VM::with_config_and_args(src, VmConfig::default(), vec![]).unwrap();
+    let remap = vm.merge_heap(&mut heap).unwrap();
+    func.remap_constants(&remap);
+    let main = vm.alloc_function(func).unwrap();
+
+    let breakpoints: Vec<_> = breakpoints
+        .iter()
+        .map(|&line| ("<debug>".to_string(), line))
+        .collect();
+    debug_vm(vm, main, breakpoints, commands)
+}
+
+fn debug_vm(
+    mut vm: VM,
+    main: GcRef,
+    breakpoints: Vec<(String, u32)>,
+    commands: &[DebugCommand],
+) -> Vec<Stop> {
+    let stops = Rc::new(RefCell::new(Vec::new()));
+    let frontend = Scripted {
+        breakpoints,
+        commands: commands.iter().copied().collect(),
+        stops: Rc::clone(&stops),
+    };
+    vm.attach_debugger(Debugger::new(Box::new(frontend)).stop_on_entry());
+    vm.execute(main).unwrap();
+    stops.take()
+}
+
+fn locals(stop: &Stop) -> Vec<(&str, &str)> {
+    stop.locals
+        .iter()
+        .map(|(n, v)| (n.as_str(), v.as_str()))
+        .collect()
+}
+
+#[test]
+fn compiler_records_local_names() {
+    let (func, _, _) = compile(PROGRAM);
+    let add = func
+        .nested_functions
+        .iter()
+        .find(|f| f.name.as_deref() == Some("add"))
+        .unwrap();
+    let names: Vec<_> = add.local_names.iter().map(|l| l.name.as_str()).collect();
+    for expected in ["a", "b", "sum"] {
+        assert!(
+            names.contains(&expected),
+            "{expected} missing from {names:?}"
+        );
+    }
+    for local in &add.local_names {
+        assert!(local.start <= local.end);
+        assert!(local.end as usize <= add.bytecode.len());
+    }
+}
+
+#[test]
+fn local_names_survive_avbc_roundtrip() {
+    let (func, heap, _) = compile(PROGRAM);
+    let bytes = serialize(&func, &heap);
+    let (read, _) = deserialize(&bytes).unwrap();
+    let find = |f: &Function| {
+        f.nested_functions
+            .iter()
+            .find(|f| f.name.as_deref() == Some("main"))
+            .unwrap()
+            .local_names
+            .clone()
+    };
+    assert_eq!(find(&read), find(&func));
+}
+
+#[test]
+fn breakpoint_stops_with_locals() {
+    let stops = debug_run(&[3], &[DebugCommand::Continue]);
+    assert_eq!(stops.len(), 2);
+    assert_eq!(stops[0].reason, StopReason::Entry);
+    assert_eq!(stops[1].reason, StopReason::Breakpoint);
+    assert_eq!(stops[1].line, 3);
+    assert_eq!(stops[1].function, "add");
+    assert!(locals(&stops[1]).contains(&("sum", "15")));
+}
+
+#[test]
+fn next_steps_over_calls() {
+    let stops = debug_run(
+        &[6],
+        &[
+            DebugCommand::Continue,
+            DebugCommand::Next,
+            DebugCommand::Next,
+            DebugCommand::Continue,
+        ],
+    );
+    let lines: Vec<_> = stops
+        .iter()
+        .map(|s| (s.function.as_str(), s.line))
+        .collect();
+    assert_eq!(&lines[1..], &[("main", 6), ("main", 7), ("main", 8)]);
+    assert!(locals(&stops[3]).contains(&("y", "15")));
+}
+
+#[test]
+fn step_enters_calls_and_finish_returns() {
+    let stops = debug_run(
+        &[7],
+        &[
+            DebugCommand::Continue,
+            DebugCommand::Step,
+            DebugCommand::Finish,
+            DebugCommand::Continue,
+        ],
+    );
+    let lines: Vec<_> = stops
+        .iter()
+        .map(|s| (s.function.as_str(), s.line))
+        .collect();
+    assert_eq!(&lines[1..3], &[("main", 7), ("add", 2)]);
+    // the call writes straight into `y`, so the caller resumes on the next line
+    assert_eq!(lines[3], ("main", 8));
+    assert_eq!(stops.len(), 4);
+}
+
+#[test]
+fn quit_stops_the_program() {
+    let stops = debug_run(&[3], &[DebugCommand::Quit]);
+    assert_eq!(stops.len(), 1);
+}
+
+#[test]
+fn quit_inside_a_native_callback_stops_the_program() {
+    let code = "fn by_value(a, b) {
+    return a - b
+}
+fn main() {
+    let xs = [3, 1, 2]
+    xs.sort_by(by_value)
+    let first = xs[0]
+}
+main()";
+    // line 7 is only reached if the caller of sort_by kept running
+    let stops = debug_program(code, &[2, 7], &[DebugCommand::Continue, DebugCommand::Quit]);
+    let lines: Vec<_> = stops
+        .iter()
+        .map(|s| (s.function.as_str(), s.line, s.reason))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            ("<script>", 1, StopReason::Entry),
+            ("by_value", 2, StopReason::Breakpoint),
+        ]
+    );
+}
+
+#[test]
+fn breakpoints_belong_to_a_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("util.aelys"),
+        "pub fn helper(x) {\n    let y = x + 1\n    return y\n}\n",
+    )
+    .unwrap();
+    let main_path = dir.path().join("main.aelys");
+    std::fs::write(
+        &main_path,
+        "needs util\nlet a = util.helper(1)\nlet b = a * 2\n",
+    )
+    .unwrap();
+    let main_file = main_path.canonicalize().unwrap().display().to_string();
+    let util_file = dir
+        .path()
+        .join("util.aelys")
+        .canonicalize()
+        .unwrap()
+        .display()
+        .to_string();
+
+    let run = |breakpoints: &[(&str, u32)]| {
+        let loaded = load_file(
+            &main_path,
+            VmConfig::default(),
+            vec![],
+            OptimizationLevel::None,
+        )
+        .unwrap();
+        let breakpoints = breakpoints
+            .iter()
+            .map(|&(file, line)| (file.to_string(), line))
+            .collect();
+        debug_vm(loaded.vm, loaded.main, breakpoints, &[])
+            .into_iter()
+            .map(|s| (s.source, s.function, s.line))
+            .collect::<Vec<_>>()
+    };
+
+    // line 3 of main.aelys, not `return y` on line 3 of util.aelys
+    let stops = run(&[(&main_file, 3)]);
+    assert_eq!(stops.len(), 2, "{stops:?}");
+    assert_eq!(stops[1], (main_file.clone(), "<script>".to_string(), 3));
+
+    let stops = run(&[(&util_file, 2)]);
+    assert_eq!(stops.len(), 2, "{stops:?}");
+    assert_eq!(stops[1], (util_file.clone(), "helper".to_string(), 2));
+}
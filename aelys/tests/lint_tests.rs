@@ -0,0 +1,173 @@
+use aelys_common::WarningKind;
+use aelys_frontend::{lexer::Lexer, parser::Parser};
+use aelys_sema::lint::{self, LintConfig, LintLevel, LintModule};
+use aelys_sema::{TypeInference, TypedProgram};
+use aelys_syntax::{NeedsStmt, Source, StmtKind};
+
+fn typecheck(name: &str, code: &str) -> (TypedProgram, Vec<NeedsStmt>) {
+    let src = Source::new(format!("{}.aelys", name), code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let (needs, stmts): (Vec<_>, Vec<_>) = Parser::new(tokens, src.clone())
+        .parse()
+        .unwrap()
+        .into_iter()
+        .partition(|s| matches!(s.kind, StmtKind::Needs(_)));
+    let needs = needs
+        .into_iter()
+        .filter_map(|s| match s.kind {
+            StmtKind::Needs(needs) => Some(needs),
+            _ => None,
+        })
+        .collect();
+    let known = ["util::area".to_string()].into_iter().collect();
+    let aliases = ["util".to_string()].into_iter().collect();
+    let program = TypeInference::infer_program_with_imports(stmts, src, aliases, known).unwrap();
+    (program, needs)
+}
+
+fn lint_files(files: &[(&str, &str)], config: &LintConfig) -> Vec<WarningKind> {
+    let checked: Vec<_> = files
+        .iter()
+        .map(|(name, code)| (name.to_string(), typecheck(name, code)))
+        .collect();
+    let modules: Vec<_> = checked
+        .iter()
+        .map(|(name, (program, needs))| LintModule {
+            program,
+            name: name.clone(),
+            needs: needs.clone(),
+        })
+        .collect();
+    lint::lint(&modules, config)
+        .into_iter()
+        .map(|w| w.kind)
+        .collect()
+}
+
+fn lint(code: &str) -> Vec<WarningKind> {
+    lint_files(&[("main", code)], &LintConfig::new())
+}
+
+#[test]
+fn code_after_return_is_unreachable() {
+    let found = lint("fn f(x: int) -> int {\n    return x\n    print(x)\n    print(x)\n}");
+    assert_eq!(found, [WarningKind::UnreachableCode]);
+
+    let found = lint(
+        "fn g(x: int) -> int {\n    if x > 0 {\n        return 1\n    } else {\n        return 2\n    }\n    return 3\n}",
+    );
+    assert_eq!(found, [WarningKind::UnreachableCode]);
+
+    assert!(
+        lint("fn h(x: int) -> int {\n    if x > 0 {\n        return 1\n    }\n    return 2\n}")
+            .is_empty()
+    );
+}
+
+#[test]
+fn comparisons_with_a_fixed_result() {
+    let found = lint("let x = 3\nlet a = 1 < 2\nlet b = x != x\nlet c = x < 4\n");
+    assert_eq!(
+        found,
+        [
+            WarningKind::ConstantComparison { result: true },
+            WarningKind::ConstantComparison { result: false },
+        ]
+    );
+}
+
+#[test]
+fn float_equality_is_suspicious() {
+    let found = lint(
+        "fn same(a: float, b: float) -> bool {\n    return a == b\n}\nlet fixed = 0.5 != 0.1\n",
+    );
+    assert_eq!(
+        found,
+        [
+            WarningKind::FloatEquality {
+                op: "==".to_string()
+            },
+            WarningKind::ConstantComparison { result: true },
+        ]
+    );
+}
+
+#[test]
+fn no_gc_functions_must_free() {
+    let found = lint(
+        "@no_gc\nfn leaks(n: int) -> int {\n    let buf = alloc(n)\n    return load(buf, 0)\n}\n@no_gc\nfn tidy(n: int) {\n    let buf = alloc(n)\n    free(buf)\n}\nfn gc(n: int) {\n    let buf = alloc(n)\n}\n",
+    );
+    assert_eq!(
+        found,
+        [WarningKind::MissingFree {
+            name: "leaks".to_string()
+        }]
+    );
+}
+
+#[test]
+fn dynamic_types_in_public_functions() {
+    let found = lint(
+        "pub fn loose(x) {\n    return x\n}\nfn private(y) {\n    return y\n}\npub fn tight(x: int) -> int {\n    return x\n}\n",
+    );
+    assert_eq!(
+        found,
+        [
+            WarningKind::DynamicInPublicApi {
+                what: "return type of 'loose'".to_string()
+            },
+            WarningKind::DynamicInPublicApi {
+                what: "parameter 'x' of 'loose'".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn allow_decorator_silences_rules_inside() {
+    let code = "@allow(float_equality, unreachable_code)\nfn f(a: float) -> bool {\n    let inner = fn(b: float) -> bool { return b == 1.5 }\n    return a == 0.5\n    print(a)\n}\nfn g(a: float) -> bool {\n    return a == 0.5\n}\n";
+    assert_eq!(
+        lint(code),
+        [WarningKind::FloatEquality {
+            op: "==".to_string()
+        }]
+    );
+}
+
+#[test]
+fn config_levels() {
+    let mut config = LintConfig::new();
+    config.set("constant_comparison", LintLevel::Allow).unwrap();
+    config.set("float_equality", LintLevel::Deny).unwrap();
+    assert!(config.set("no_such_rule", LintLevel::Deny).is_err());
+
+    let found = lint_files(
+        &[("main", "let a = 1 < 2\nlet b = 0.1 == 0.2 + 0.0\n")],
+        &config,
+    );
+    assert_eq!(
+        found,
+        [WarningKind::FloatEquality {
+            op: "==".to_string()
+        }]
+    );
+    assert_eq!(config.level("float_equality"), LintLevel::Deny);
+    assert_eq!(config.level("unused_pub"), LintLevel::Warn);
+}
+
+#[test]
+fn unused_pub_looks_at_importers() {
+    let util = "pub fn area(w: int, h: int) -> int {\n    return w * h\n}\npub fn perimeter(w: int, h: int) -> int {\n    return 2 * (w + h)\n}\n@allow(unused_pub)\npub fn spare() -> int {\n    return 0\n}\n";
+    let main = "needs util\nprint(util.area(2, 3))\n";
+
+    let found = lint_files(&[("main", main), ("util", util)], &LintConfig::new());
+    assert_eq!(
+        found,
+        [WarningKind::UnusedPub {
+            name: "perimeter".to_string()
+        }]
+    );
+
+    // nothing imports util, so its pub items are left alone
+    assert!(lint_files(&[("util", util)], &LintConfig::new()).is_empty());
+}
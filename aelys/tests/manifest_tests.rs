@@ -16,3 +16,22 @@ fn parse_manifest_modules_and_build_flags() {
     assert!(opengl.capabilities.contains(&"gpu".to_string()));
     assert_eq!(manifest.build.bundle_native_modules, Some(true));
 }
+
+#[test]
+fn parse_manifest_lint_levels() {
+    let raw = r#"
+        [lint]
+        float_equality = "deny"
+        unused_pub = "allow"
+    "#;
+
+    let manifest = Manifest::parse(raw).expect("parse");
+    assert_eq!(manifest.lint.get("float_equality").unwrap(), "deny");
+    assert_eq!(manifest.lint.len(), 2);
+    // an empty [lint] table isn't written back
+    assert!(
+        !String::from_utf8(Manifest::default().to_bytes())
+            .unwrap()
+            .contains("lint")
+    );
+}
@@ -795,3 +795,39 @@ a.shared() + b.shared()
     let result = run_file(&main_path).expect("Both aliased should work");
     assert_eq!(result.as_int(), Some(3));
 }
+
+#[test]
+fn test_alias_functions_named_like_array_methods() {
+    let dir = create_module_env();
+
+    write_file(
+        &dir,
+        "store.aelys",
+        r#"
+pub fn remove(x) { x - 1 }
+pub fn insert(a, b) { a + b }
+"#,
+    );
+
+    // inside these functions the calls can't take the CallGlobal path, and
+    // must still not turn into array::remove/insert
+    let main_path = write_file(
+        &dir,
+        "main.aelys",
+        r#"
+needs store as s
+fn go(n) {
+    let a = n + 1
+    return s.remove(a)
+}
+fn go2(n) {
+    let a = n + 1
+    return s.insert(a, n)
+}
+go(10) * 100 + go2(10)
+"#,
+    );
+
+    let result = run_file(&main_path).expect("Aliased calls should reach the module");
+    assert_eq!(result.as_int(), Some(1021));
+}
@@ -198,6 +198,43 @@ fn test_function_with_decorator() {
     }
 }
 
+#[test]
+fn test_decorator_arguments() {
+    let stmts = parse("@allow(float_equality, unused_pub)\nfn close() { }");
+
+    match &stmts[0].kind {
+        StmtKind::Function(func) => {
+            assert_eq!(func.decorators[0].name, "allow");
+            assert_eq!(func.decorators[0].args, ["float_equality", "unused_pub"]);
+        }
+        _ => panic!("Expected function declaration"),
+    }
+}
+
+#[test]
+fn test_test_decorator() {
+    let stmts = parse("@test\nfn checks_things() { }\nfn helper() { }");
+
+    match (&stmts[0].kind, &stmts[1].kind) {
+        (StmtKind::Function(test), StmtKind::Function(helper)) => {
+            assert!(test.is_test());
+            assert!(!helper.is_test());
+        }
+        _ => panic!("Expected function declarations"),
+    }
+}
+
+#[test]
+fn test_test_decorator_rejects_parameters() {
+    let src = Source::new("<test>", "@test\nfn needs_arg(x) { }");
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let err = Parser::new(tokens, src).parse().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("no parameters on a @test function")
+    );
+}
+
 #[test]
 fn test_and_or_expressions() {
     let stmts = parse("a and b or c");
@@ -0,0 +1,166 @@
+use aelys_backend::Compiler;
+use aelys_frontend::{lexer::Lexer, parser::Parser};
+use aelys_runtime::{ProfileReport, Profiler, VM, VmConfig};
+use aelys_sema::TypeInference;
+use aelys_syntax::Source;
+
+fn profile(code: &str) -> ProfileReport {
+    let src = Source::new("<profile>", code);
+    let tokens = Lexer::with_source(src.clone()).scan().unwrap();
+    let ast = Parser::new(tokens, src.clone()).parse().unwrap();
+    let typed = TypeInference::infer_program(ast, src.clone()).unwrap();
+    let (mut func, mut heap, _) = Compiler::new(None, src.clone())
+        .compile_typed(&typed)
+        .unwrap();
+
+    let mut vm = VM::with_config_and_args(src, VmConfig::default(), vec![]).unwrap();
+    let remap = vm.merge_heap(&mut heap).unwrap();
+    func.remap_constants(&remap);
+    let main = vm.alloc_function(func).unwrap();
+
+    vm.attach_profiler(Profiler::new());
+    vm.execute(main).unwrap();
+    vm.detach_profiler().unwrap()
+}
+
+#[test]
+fn counts_calls_including_recursion() {
+    let report = profile(
+        "fn fib(n: int) -> int {
+    if n < 2 { return n }
+    return fib(n - 1) + fib(n - 2)
+}
+fn twice() {
+    fib(5)
+    fib(5)
+}
+twice()",
+    );
+
+    let calls = |name: &str| {
+        report
+            .functions
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.calls)
+    };
+    // fib(5) makes 15 calls
+    assert_eq!(calls("fib"), Some(30));
+    assert_eq!(calls("twice"), Some(1));
+    assert_eq!(calls("<script>"), Some(1));
+}
+
+#[test]
+fn counts_repeated_calls_and_calls_from_natives() {
+    let report = profile(
+        "fn by_value(a, b) { return a - b }
+fn down(n: int) -> int {
+    if n == 0 { return 0 }
+    return down(n - 1)
+}
+fn main() {
+    for i in 0..10 { down(3) }
+    let xs = [2, 1]
+    xs.sort_by(by_value)
+    xs.sort_by(by_value)
+}
+main()",
+    );
+
+    let calls = |name: &str| {
+        report
+            .functions
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| f.calls)
+    };
+    assert_eq!(calls("down"), Some(40));
+    assert_eq!(calls("by_value"), Some(2));
+    // running a callback doesn't count as calling its callers again
+    assert_eq!(calls("main"), Some(1));
+    assert_eq!(calls("<script>"), Some(1));
+    let stacks: Vec<&str> = report.folded.iter().map(|(s, _)| s.as_str()).collect();
+    assert!(stacks.contains(&"<script>;main;by_value"), "{:?}", stacks);
+}
+
+#[test]
+fn inclusive_time_covers_exclusive_time() {
+    let report = profile(
+        "fn spin(n: int) -> int {
+    let mut total = 0
+    for i in 0..n { total += i }
+    return total
+}
+fn outer() -> int { return spin(20000) + spin(20000) }
+outer()",
+    );
+
+    for f in &report.functions {
+        assert!(f.inclusive >= f.exclusive, "{:?}", f);
+    }
+    let outer = report.functions.iter().find(|f| f.name == "outer").unwrap();
+    let spin = report.functions.iter().find(|f| f.name == "spin").unwrap();
+    assert!(outer.inclusive >= spin.inclusive);
+    assert!(report.total >= outer.inclusive);
+}
+
+#[test]
+fn counts_opcodes() {
+    let report = profile("let mut x = 0\nwhile x < 100 { x += 1 }");
+
+    assert!(!report.opcodes.is_empty());
+    let executed: u64 = report.opcodes.iter().map(|(_, count)| *count).sum();
+    assert!(executed >= 100);
+    // most frequent first
+    assert!(report.opcodes.windows(2).all(|w| w[0].1 >= w[1].1));
+}
+
+#[test]
+fn folds_stacks_by_caller() {
+    let report = profile(
+        "fn leaf() -> int { return 1 }
+fn middle() -> int { return leaf() }
+middle()
+leaf()",
+    );
+
+    let stacks: Vec<&str> = report.folded.iter().map(|(s, _)| s.as_str()).collect();
+    assert!(stacks.contains(&"<script>;middle;leaf"), "{:?}", stacks);
+    assert!(stacks.contains(&"<script>;leaf"), "{:?}", stacks);
+}
+
+#[test]
+fn repeated_stacks_fold_into_one_entry() {
+    let report = profile(
+        "fn down(n: int) -> int {
+    if n == 0 { return 0 }
+    return down(n - 1)
+}
+fn leaf() -> int { return 1 }
+let mut i = 0
+while i < 50 {
+    leaf()
+    i += 1
+}
+down(2)",
+    );
+
+    let stacks: Vec<&str> = report.folded.iter().map(|(s, _)| s.as_str()).collect();
+    assert_eq!(
+        stacks,
+        [
+            "<script>",
+            "<script>;down",
+            "<script>;down;down",
+            "<script>;down;down;down",
+            "<script>;leaf",
+        ]
+    );
+}
+
+#[test]
+fn detach_without_profiler_is_none() {
+    let src = Source::new("<profile>", "");
+    let mut vm = VM::with_config_and_args(src, VmConfig::default(), vec![]).unwrap();
+    assert!(vm.detach_profiler().is_none());
+}
@@ -421,6 +421,77 @@ fn infer_struct_field_access_type() {
     }
 }
 
+#[test]
+fn parse_type_annotation_with_several_args() {
+    let stmts = parse("fn f(p: Pair<A, Array<Vec<int>>>) {}");
+    let aelys_syntax::StmtKind::Function(func) = &stmts[0].kind else {
+        panic!("expected function");
+    };
+    let ann = func.params[0].type_annotation.as_ref().unwrap();
+    assert_eq!(ann.name, "Pair");
+    assert_eq!(ann.type_args.len(), 2);
+    assert_eq!(ann.type_args[1].name, "Array");
+    assert_eq!(ann.type_args[1].type_args[0].name, "Vec");
+    assert_eq!(ann.type_args[1].type_args[0].type_args[0].name, "int");
+}
+
+#[test]
+fn infer_generic_struct_literal_and_field() {
+    let result = infer(
+        r#"
+        struct Pair<A, B> { a: A, b: B }
+        let p = Pair { a: 1, b: "x" }
+        let s: string = p.b
+    "#,
+    );
+    match &result.program.stmts[1].kind {
+        aelys_sema::TypedStmtKind::Let { var_type, .. } => {
+            assert_eq!(
+                *var_type,
+                InferType::GenericStruct {
+                    name: "Pair".to_string(),
+                    args: vec![InferType::I64, InferType::String],
+                }
+            );
+        }
+        _ => panic!("expected Let"),
+    }
+}
+
+#[test]
+fn infer_generic_call_instantiates_struct_args() {
+    let result = infer(
+        r#"
+        struct Pair<A, B> { a: A, b: B }
+        fn swap<A, B>(p: Pair<A, B>) -> Pair<B, A> {
+            return Pair { a: p.b, b: p.a }
+        }
+        let q = swap(Pair { a: 1, b: 2.5 })
+    "#,
+    );
+    match &result.program.stmts[2].kind {
+        aelys_sema::TypedStmtKind::Let { var_type, .. } => {
+            assert_eq!(var_type.to_string(), "Pair<f64, i64>");
+        }
+        _ => panic!("expected Let"),
+    }
+}
+
+#[test]
+fn generic_struct_field_mismatch_is_an_error() {
+    let src = aelys_syntax::Source::new(
+        "<test>",
+        "struct Box<T> { value: T }\nlet b: Box<int> = Box { value: \"s\" }",
+    );
+    let tokens = aelys_frontend::lexer::Lexer::with_source(src.clone())
+        .scan()
+        .unwrap();
+    let ast = aelys_frontend::parser::Parser::new(tokens, src.clone())
+        .parse()
+        .unwrap();
+    assert!(aelys_sema::TypeInference::infer_program(ast, src).is_err());
+}
+
 // ---------------------------------------------------------------------------
 // Sema: unification with sized types
 // ---------------------------------------------------------------------------
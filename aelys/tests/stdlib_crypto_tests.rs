@@ -0,0 +1,75 @@
+mod common;
+use common::*;
+
+#[test]
+fn crypto_hmac_known_vectors() {
+    let code = r#"
+needs std.crypto
+crypto.hmac("sha256", "key", "The quick brown fox jumps over the lazy dog")
+"#;
+    assert_aelys_str(
+        code,
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8",
+    );
+    let code = r#"
+needs std.crypto
+crypto.hmac("sha512", "key", "The quick brown fox jumps over the lazy dog")
+"#;
+    assert_aelys_str(
+        code,
+        "b42af09057bac1e2d41708e48a902e09b5ff7f12ab428a4fe86653c73dd248fb\
+         82f948a549f7b791a5b41915ee4d1ec3935357e4e2317250d0372afa2ebeeb3a",
+    );
+}
+
+#[test]
+fn crypto_hmac_with_buffer_key() {
+    let code = r#"
+needs std.crypto
+needs std.bytes
+let key = bytes.from_string("key")
+let same = crypto.hmac("sha256", key, "msg") == crypto.hmac("sha256", "key", "msg")
+bytes.free(key)
+same
+"#;
+    assert_aelys_bool(code, true);
+    assert_aelys_error_contains(
+        "needs std.crypto\ncrypto.hmac(\"blake3\", \"k\", \"m\")",
+        "\"sha256\" or \"sha512\"",
+    );
+}
+
+#[test]
+fn crypto_constant_time_eq() {
+    let code = r#"
+needs std.crypto
+needs std.bytes
+let buf = bytes.from_string("token")
+let results = [
+    crypto.constant_time_eq("token", buf),
+    crypto.constant_time_eq("token", "tokem"),
+    crypto.constant_time_eq("token", "token!"),
+    crypto.constant_time_eq("", ""),
+]
+bytes.free(buf)
+results[0] and not results[1] and not results[2] and results[3]
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn crypto_random_bytes() {
+    let code = r#"
+needs std.crypto
+needs std.bytes
+let a = crypto.random_bytes(32)
+let b = crypto.random_bytes(32)
+let ok = bytes.size(a) == 32 and not bytes.equals(a, b)
+bytes.free(a)
+bytes.free(b)
+ok
+"#;
+    assert_aelys_bool(code, true);
+    assert_aelys_int("needs std.crypto\ncrypto.random_hex(16).len()", 32);
+    assert_aelys_error_contains("needs std.crypto\ncrypto.random_bytes(-1)", "length -1");
+}
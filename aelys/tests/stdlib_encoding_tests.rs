@@ -0,0 +1,141 @@
+mod common;
+use common::*;
+
+#[test]
+fn base64_round_trip() {
+    assert_aelys_str(
+        "needs std.encoding\nencoding.base64_encode(\"hello?>\")",
+        "aGVsbG8/Pg==",
+    );
+    let code = r#"
+needs std.encoding
+needs std.bytes
+let buf = encoding.base64_decode("aGVsbG8/Pg==")
+let s = bytes.decode(buf, 0, bytes.size(buf))
+bytes.free(buf)
+s
+"#;
+    assert_aelys_str(code, "hello?>");
+    assert_aelys_error_contains(
+        "needs std.encoding\nencoding.base64_decode(\"a$==\")",
+        "valid base64",
+    );
+}
+
+#[test]
+fn base64url_round_trip() {
+    assert_aelys_str(
+        "needs std.encoding\nencoding.base64url_encode(\"hello?>\")",
+        "aGVsbG8_Pg",
+    );
+    // padded and unpadded input both decode
+    let code = r#"
+needs std.encoding
+let a = encoding.decode_text(encoding.base64url_decode("aGVsbG8_Pg"), "utf-8")
+let b = encoding.decode_text(encoding.base64url_decode("aGVsbG8_Pg=="), "utf-8")
+a + b
+"#;
+    assert_aelys_str(code, "hello?>hello?>");
+}
+
+#[test]
+fn hex_round_trip() {
+    let code = r#"
+needs std.encoding
+needs std.bytes
+let buf = encoding.hex_decode("DEADbeef00")
+let ok = bytes.size(buf) == 5 and bytes.read_u32_be(buf, 0) == 0xDEADBEEF
+let back = encoding.hex_encode(buf)
+bytes.free(buf)
+if ok { back } else { "" }
+"#;
+    assert_aelys_str(code, "deadbeef00");
+    assert_aelys_error_contains("needs std.encoding\nencoding.hex_decode(\"abc\")", "odd");
+    assert_aelys_error_contains(
+        "needs std.encoding\nencoding.hex_decode(\"zz\")",
+        "'z' at position 0",
+    );
+}
+
+#[test]
+fn url_encoding() {
+    assert_aelys_str(
+        "needs std.encoding\nencoding.url_encode(\"a b&c=é/~\")",
+        "a%20b%26c%3D%C3%A9%2F~",
+    );
+    assert_aelys_str(
+        "needs std.encoding\nencoding.url_decode(\"a%20b%26c%3d%C3%A9+\")",
+        "a b&c=é+",
+    );
+    assert_aelys_error_contains(
+        "needs std.encoding\nencoding.url_decode(\"100%\")",
+        "bad escape at position 3",
+    );
+    assert_aelys_error_contains(
+        "needs std.encoding\nencoding.url_decode(\"%FF\")",
+        "invalid UTF-8",
+    );
+}
+
+#[test]
+fn utf16_round_trip() {
+    let code = r#"
+needs std.encoding
+needs std.bytes
+let le = encoding.encode_text("h€😀", "utf-16le")
+let be = encoding.encode_text("h€😀", "UTF-16BE")
+// h, €, and a surrogate pair
+let sizes = bytes.size(le) == 8 and bytes.read_u16(le, 0) == 104 and bytes.read_u16_be(be, 2) == 0x20AC
+let text = encoding.decode_text(le, "utf-16le") + encoding.decode_text(be, "utf-16be")
+if sizes { text } else { "" }
+"#;
+    assert_aelys_str(code, "h€😀h€😀");
+    let code = r#"
+needs std.encoding
+needs std.bytes
+let buf = bytes.alloc(2)
+bytes.write_u16(buf, 0, 0xD800)
+encoding.decode_text(buf, "utf-16le")
+"#;
+    assert_aelys_error_contains(code, "unpaired surrogate 0xd800");
+}
+
+#[test]
+fn latin1_round_trip() {
+    let code = r#"
+needs std.encoding
+needs std.bytes
+let buf = encoding.encode_text("café", "latin-1")
+let ok = bytes.size(buf) == 4 and bytes.read_u8(buf, 3) == 0xE9
+if ok { encoding.decode_text(buf, "iso-8859-1") } else { "" }
+"#;
+    assert_aelys_str(code, "café");
+    assert_aelys_error_contains(
+        "needs std.encoding\nencoding.encode_text(\"a€\", \"latin-1\")",
+        "'€' at character 1",
+    );
+    assert_aelys_error_contains(
+        "needs std.encoding\nencoding.encode_text(\"a\", \"ebcdic\")",
+        "\"ebcdic\"",
+    );
+}
+
+#[test]
+fn encoding_in_no_gc_function() {
+    let code = r#"
+needs std.encoding
+needs std.bytes
+
+@no_gc
+fn frame(payload: string) -> string {
+    let buf = bytes.alloc(4)
+    bytes.write_u32_be(buf, 0, payload.len())
+    let header = encoding.hex_encode(buf)
+    bytes.free(buf)
+    return header + encoding.base64_encode(payload)
+}
+
+frame("hi")
+"#;
+    assert_aelys_str(code, "00000002aGk=");
+}
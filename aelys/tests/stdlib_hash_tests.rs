@@ -0,0 +1,76 @@
+mod common;
+use common::*;
+
+#[test]
+fn hash_sha2_known_vectors() {
+    assert_aelys_str(
+        "needs std.hash\nhash.sha256(\"abc\")",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    );
+    assert_aelys_str(
+        "needs std.hash\nhash.sha256(\"\")",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    );
+    assert_aelys_str(
+        "needs std.hash\nhash.sha512(\"abc\")",
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+         2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+    );
+}
+
+#[test]
+fn hash_blake3_known_vectors() {
+    assert_aelys_str(
+        "needs std.hash\nhash.blake3(\"\")",
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+    );
+    assert_aelys_str(
+        "needs std.hash\nhash.blake3(\"abc\")",
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+    );
+}
+
+#[test]
+fn hash_checksums() {
+    assert_aelys_int("needs std.hash\nhash.crc32(\"123456789\")", 0xCBF43926);
+    assert_aelys_int("needs std.hash\nhash.crc32(\"\")", 0);
+    assert_aelys_str("needs std.hash\nhash.fnv1a(\"\")", "cbf29ce484222325");
+    assert_aelys_str("needs std.hash\nhash.fnv1a(\"a\")", "af63dc4c8601ec8c");
+}
+
+#[test]
+fn hash_byte_buffers_hash_like_strings() {
+    let code = r#"
+needs std.hash
+needs std.bytes
+let buf = bytes.alloc(3)
+bytes.write_u8(buf, 0, 97)
+bytes.write_u8(buf, 1, 98)
+bytes.write_u8(buf, 2, 99)
+let same = hash.sha256(buf) == hash.sha256("abc") and hash.crc32(buf) == hash.crc32("abc")
+bytes.free(buf)
+same
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn hash_digest_returns_raw_bytes() {
+    let code = r#"
+needs std.hash
+needs std.bytes
+let d = hash.digest("sha512", "abc")
+let result = bytes.size(d) * 1000 + bytes.read_u8(d, 0)
+bytes.free(d)
+result
+"#;
+    // first byte of SHA-512("abc") is 0xdd
+    assert_aelys_int(code, 64 * 1000 + 0xdd);
+    assert_aelys_error_contains("needs std.hash\nhash.digest(\"md5\", \"x\")", "\"md5\"");
+}
+
+#[test]
+fn hash_rejects_other_values() {
+    assert_aelys_error_contains("needs std.hash\nhash.sha256(1.5)", "string or byte buffer");
+    assert_aelys_error_contains("needs std.hash\nhash.sha256(12345)", "byte buffer handle");
+}
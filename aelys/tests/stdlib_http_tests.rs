@@ -0,0 +1,349 @@
+mod common;
+use aelys_runtime::VmConfig;
+use aelys_runtime::vm::{GcRef, ObjectKind};
+use common::*;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn net_config() -> VmConfig {
+    let mut config = VmConfig::default();
+    config.capabilities.allow_net = true;
+    config
+}
+
+fn run_with_net(source: &str) -> Result<String, String> {
+    let mut vm = aelys::new_vm_with_config(net_config(), Vec::new()).unwrap();
+    let result = aelys::run_with_vm(&mut vm, source, "<test>").map_err(|e| e.to_string())?;
+    let obj = result
+        .as_ptr()
+        .and_then(|ptr| vm.heap().get(GcRef::new(ptr)));
+    match obj.map(|obj| &obj.kind) {
+        Some(ObjectKind::String(s)) => Ok(s.as_str().to_string()),
+        _ => Ok(format!("{:?}", result)),
+    }
+}
+
+/// One-shot server: reads a request (head and Content-Length body), sends
+/// `response` and hands the raw request back.
+fn serve_once(response: &'static str) -> (u16, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut request = String::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(n) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = n.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        request.push_str(&String::from_utf8(body).unwrap());
+        (&stream).write_all(response.as_bytes()).unwrap();
+        request
+    });
+    (port, handle)
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Send a raw request to a server that may still be starting up and return
+/// the whole response.
+fn raw_request(port: u16, request: &str) -> String {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut stream = loop {
+        match TcpStream::connect(("127.0.0.1", port)) {
+            Ok(stream) => break stream,
+            Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            Err(e) => panic!("server never came up: {}", e),
+        }
+    };
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+fn serve_in_thread(source: String) -> thread::JoinHandle<Result<String, String>> {
+    thread::spawn(move || run_with_net(&source))
+}
+
+#[test]
+fn http_denied_without_capability() {
+    assert_aelys_error_contains(
+        "needs std.http\nhttp.get(\"http://127.0.0.1:1/\", null)",
+        "capability",
+    );
+}
+
+#[test]
+fn http_get_sends_headers_and_reads_response() {
+    let (port, server) =
+        serve_once("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello");
+    let code = format!(
+        r#"
+needs std.http
+let r = http.get("http://127.0.0.1:{port}/path?q=1#frag", [["headers", [["X-Token", "abc"]]]])
+let status = json.get(r, "status")
+let body = json.get(r, "body")
+let kind = http.header(r, "content-type")
+"{{status}} {{body}} {{kind}} {{http.header(r, "missing")}}"
+"#
+    );
+    assert_eq!(run_with_net(&code).unwrap(), "200 hello text/plain null");
+    let request = server.join().unwrap();
+    assert!(
+        request.starts_with("GET /path?q=1 HTTP/1.1\r\n"),
+        "{}",
+        request
+    );
+    assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", port)));
+    assert!(request.contains("X-Token: abc\r\n"));
+    assert!(request.contains("Connection: close\r\n"));
+}
+
+#[test]
+fn http_post_reads_chunked_response() {
+    let (port, server) = serve_once(
+        "HTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n\
+         4\r\nWiki\r\n6;ext=1\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\nX-Trailer: t\r\n\r\n",
+    );
+    let code = format!(
+        r#"
+needs std.http
+let r = http.post("http://127.0.0.1:{port}/submit", "a=1&b=2", null)
+"{{json.get(r, "status")}} {{json.get(r, "reason")}}|{{json.get(r, "body")}}"
+"#
+    );
+    assert_eq!(
+        run_with_net(&code).unwrap(),
+        "201 Created|Wikipedia in \r\n\r\nchunks."
+    );
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /submit HTTP/1.1\r\n"));
+    assert!(request.contains("Content-Length: 7\r\n"));
+    assert!(request.ends_with("\r\n\r\na=1&b=2"));
+}
+
+#[test]
+fn http_rejects_huge_chunk_after_a_normal_one() {
+    let (port, server) = serve_once(
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+         4\r\nWiki\r\nffffffffffffffff\r\nx\r\n0\r\n\r\n",
+    );
+    let code = format!("needs std.http\nhttp.get(\"http://127.0.0.1:{port}/\", null)");
+    let err = run_with_net(&code).unwrap_err();
+    assert!(err.contains("body larger than"), "{}", err);
+    server.join().unwrap();
+}
+
+#[test]
+fn http_request_with_binary_body_until_eof() {
+    // no Content-Length: the body runs until the server closes
+    let (port, server) = serve_once("HTTP/1.0 404 Not Found\r\n\r\n\x01\x02\x03");
+    let code = format!(
+        r#"
+needs std.http
+needs std.bytes
+let r = http.request("put", "http://127.0.0.1:{port}", null, [["binary", true]])
+let buf = json.get(r, "body")
+let out = "{{json.get(r, "status")}} {{bytes.size(buf)}} {{bytes.read_u8(buf, 2)}}"
+bytes.free(buf)
+out
+"#
+    );
+    assert_eq!(run_with_net(&code).unwrap(), "404 3 3");
+    let request = server.join().unwrap();
+    assert!(request.starts_with("PUT / HTTP/1.1\r\n"));
+    assert!(request.contains("Content-Length: 0\r\n"));
+}
+
+#[test]
+fn http_client_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let code = format!(
+        "needs std.http\nhttp.get(\"http://127.0.0.1:{}/\", [[\"timeout\", 200]])",
+        port
+    );
+    let err = run_with_net(&code).unwrap_err();
+    assert!(err.contains("timed out after 200ms"), "{}", err);
+    drop(listener);
+}
+
+#[test]
+fn http_client_rejects_bad_urls() {
+    for (url, message) in [
+        ("https://example.com/", "https is not supported"),
+        ("ftp://example.com/", "is not an http:// URL"),
+        ("http:///path", "no host"),
+        ("http://host:99999/", "invalid port"),
+    ] {
+        let code = format!("needs std.http\nhttp.get(\"{}\", null)", url);
+        let err = run_with_net(&code).unwrap_err();
+        assert!(err.contains(message), "{}: {}", url, err);
+    }
+    let err = run_with_net(
+        "needs std.http\nhttp.get(\"http://127.0.0.1:1/\", [[\"headers\", [[\"X\", \"a\\nb\"]]]])",
+    )
+    .unwrap_err();
+    assert!(err.contains("line break"), "{}", err);
+}
+
+#[test]
+fn http_serve_dispatches_to_handler() {
+    let port = free_port();
+    let source = format!(
+        r#"
+needs std.http
+let mut hits = 0
+
+fn handle(req) {{
+    hits += 1
+    let path = json.get(req, "path")
+    if path == "/stop" {{
+        http.stop()
+        return null
+    }}
+    if path == "/echo" {{
+        let kind = http.header(req, "Content-Type")
+        return [
+            ["status", 201],
+            ["headers", [["X-Method", json.get(req, "method")], ["Content-Type", kind]]],
+            ["body", json.get(req, "body")],
+        ]
+    }}
+    return "hello {{json.get(req, "query")}}"
+}}
+
+http.serve("127.0.0.1:{port}", handle)
+"served {{hits}}"
+"#
+    );
+    let server = serve_in_thread(source);
+
+    let response = raw_request(port, "GET /greet?name=x HTTP/1.1\r\nHost: t\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("Content-Type: text/plain; charset=utf-8\r\n"));
+    assert!(response.contains("Content-Length: 12\r\n"));
+    assert!(response.ends_with("\r\n\r\nhello name=x"));
+
+    let response = raw_request(
+        port,
+        "POST /echo HTTP/1.1\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n\
+         3\r\n{\"a\r\n4\r\n\":1}\r\n0\r\n\r\n",
+    );
+    assert!(
+        response.starts_with("HTTP/1.1 201 Created\r\n"),
+        "{}",
+        response
+    );
+    assert!(response.contains("X-Method: POST\r\n"));
+    assert!(response.contains("Content-Type: application/json\r\n"));
+    assert!(response.ends_with("\r\n\r\n{\"a\":1}"));
+
+    let response = raw_request(port, "HEAD / HTTP/1.1\r\n\r\n");
+    assert!(response.contains("Content-Length: 6\r\n"));
+    assert!(response.ends_with("\r\n\r\n"));
+
+    let response = raw_request(port, "nonsense\r\n\r\n");
+    assert!(
+        response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{}",
+        response
+    );
+
+    let response = raw_request(port, "GET /stop HTTP/1.1\r\n\r\n");
+    assert!(
+        response.starts_with("HTTP/1.1 204 No Content\r\n"),
+        "{}",
+        response
+    );
+
+    // the 400 never reached the handler
+    assert_eq!(server.join().unwrap().unwrap(), "served 4");
+}
+
+#[test]
+fn http_serve_handler_error_answers_500_and_fails() {
+    let port = free_port();
+    let source = format!(
+        r#"
+needs std.http
+http.serve("127.0.0.1:{port}", fn(req) {{
+    return [["status", 42]]
+}})
+"#
+    );
+    let server = serve_in_thread(source);
+    let response = raw_request(port, "GET / HTTP/1.1\r\n\r\n");
+    assert!(
+        response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"),
+        "{}",
+        response
+    );
+    let err = server.join().unwrap().unwrap_err();
+    assert!(
+        err.contains("status must be an int in 100..=999, got 42"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn http_serve_closure_handler_keeps_caller_state() {
+    let port = free_port();
+    // serve is called from inside a function whose locals must survive the
+    // handler's calls, allocations and collections
+    let source = format!(
+        r#"
+needs std.http
+
+fn run(limit) {{
+    let before = "kept"
+    let mut count = 0
+    let handler = fn(req) {{
+        count++
+        let mut junk = ""
+        for i in 0..2000 {{
+            junk = junk + "x"
+        }}
+        if count == limit {{
+            http.stop()
+        }}
+        return "{{count}}:{{junk.len()}}"
+    }}
+    http.serve("127.0.0.1:{port}", handler)
+    return "{{before}} {{count}}"
+}}
+
+run(3)
+"#
+    );
+    let server = serve_in_thread(source);
+    for n in 1..=3 {
+        let response = raw_request(port, "GET / HTTP/1.1\r\n\r\n");
+        assert!(
+            response.ends_with(&format!("\r\n\r\n{}:2000", n)),
+            "{}",
+            response
+        );
+    }
+    assert_eq!(server.join().unwrap().unwrap(), "kept 3");
+}
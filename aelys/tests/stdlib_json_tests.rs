@@ -0,0 +1,161 @@
+mod common;
+use aelys_common::error::AelysError;
+use common::*;
+
+#[test]
+fn json_parse_objects_and_arrays() {
+    let code = r#"
+let doc = json.parse("{{\"name\": \"aelys\", \"tags\": [\"a\", \"b\", \"c\"], \"n\": 40}}")
+let tags = json.get(doc, "tags")
+json.get(doc, "n") + tags.len() - 1
+"#;
+    assert_aelys_int(code, 42);
+}
+
+#[test]
+fn json_parse_scalars() {
+    assert_aelys_bool(r#"json.parse("true")"#, true);
+    assert_aelys_null(r#"json.parse(" null ")"#);
+    assert_aelys_str(r#"json.parse("\"a\\u00e9\\ud83d\\ude00\"")"#, "aé😀");
+    assert_aelys_int(r#"json.parse("-17")"#, -17);
+    let value = run_aelys(r#"json.parse("2.5e2")"#);
+    assert_eq!(value.as_float(), Some(250.0));
+}
+
+#[test]
+fn json_get_has_and_keys() {
+    let code = r#"
+let doc = json.parse("{{\"b\": 1, \"a\": 2, \"b\": 3}}")
+let keys = json.keys(doc)
+if json.has(doc, "a") and not json.has(doc, "c") and json.get(doc, "c") == null and keys.len() == 2 {
+    json.get(doc, "b")
+} else {
+    0
+}
+"#;
+    // a repeated key keeps its last value
+    assert_aelys_int(code, 3);
+}
+
+#[test]
+fn json_stringify_sorts_keys() {
+    let code = r#"
+json.stringify(json.parse("{{\"b\": [1, 2.0, null], \"a\": {{\"z\": true, \"y\": \"q\\\"\"}}}}"), 0)
+"#;
+    assert_aelys_str(code, r#"{"a":{"y":"q\"","z":true},"b":[1,2.0,null]}"#);
+}
+
+#[test]
+fn json_stringify_pretty() {
+    let code = r#"json.stringify([["k", Vec[1, 2]], ["e", []]], 2)"#;
+    assert_aelys_str(code, "{\n  \"e\": {},\n  \"k\": [\n    1,\n    2\n  ]\n}");
+}
+
+#[test]
+fn json_stringify_arrays_vecs_and_strings() {
+    assert_aelys_str(r#"json.stringify([1, 2, 3], 0)"#, "[1,2,3]");
+    assert_aelys_str(
+        r#"
+let v = Vec<string>[]
+v.push("tab\there")
+json.stringify(v, 0)
+"#,
+        r#"["tab\there"]"#,
+    );
+}
+
+#[test]
+fn json_round_trips() {
+    let code = r#"
+let text = "{{\"items\": [{{\"id\": 1}}, {{\"id\": 2}}], \"ok\": false}}"
+let once = json.stringify(json.parse(text), 0)
+json.stringify(json.parse(once), 0) == once
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn json_parse_errors_have_line_and_column() {
+    let err = run_aelys_err(r#"json.parse("{{\"a\": [1, 2,\n  }}")"#);
+    assert!(err.contains("json.parse"), "{}", err);
+    assert!(err.contains("line 2, column 3"), "{}", err);
+
+    assert_aelys_error_contains(r#"json.parse("[1] 2")"#, "trailing characters");
+    assert_aelys_error_contains(r#"json.parse("{{\"a\" 1}}")"#, "expected ':'");
+    assert_aelys_error_contains(r#"json.parse("")"#, "empty input");
+}
+
+#[test]
+fn json_parse_rejects_numbers_out_of_range() {
+    let err = run_aelys_err(r#"json.parse("[1,\n 1e400]")"#);
+    assert!(err.contains("number out of range"), "{}", err);
+    assert!(err.contains("line 2, column 2"), "{}", err);
+    assert_aelys_error_contains(r#"json.parse("-1e400")"#, "out of range");
+    let huge = "9".repeat(400);
+    assert_aelys_error_contains(&format!("json.parse(\"{huge}\")"), "out of range");
+
+    let code = r#"json.stringify(json.parse("1e308"), 0) == json.stringify(1e308, 0)"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn json_stringify_rejects_non_json_values() {
+    assert_aelys_error_contains(
+        r#"
+fn f() { 1 }
+json.stringify(f, 0)
+"#,
+        "no JSON representation",
+    );
+    assert_aelys_error_contains(r#"json.stringify(1, -1)"#, "indent");
+}
+
+#[test]
+fn json_read_streams_values_from_a_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = dir.path().join("events.ndjson");
+    std::fs::write(&data, "{\"n\": 1}\n{\"n\": 2}\n\n[39]\n").unwrap();
+    let src = format!(
+        r#"
+needs std.fs
+let f = fs.open("{}", "r")
+let a = json.read(f)
+let b = json.read(f)
+let c = json.read(f)
+let end = json.read(f)
+fs.close(f)
+if end == null {{ json.get(a, "n") + json.get(b, "n") + c[0] }} else {{ 0 }}
+"#,
+        data.display().to_string().replace('\\', "/")
+    );
+    let path = dir.path().join("read.aelys");
+    std::fs::write(&path, src).unwrap();
+
+    let mut config = aelys_runtime::VmConfig::default();
+    config.capabilities.allow_fs = true;
+    let value = aelys_driver::run_file_with_config(&path, config, Vec::new()).unwrap();
+    assert_eq!(value.as_int(), Some(42));
+}
+
+#[test]
+fn json_read_reports_syntax_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = dir.path().join("bad.json");
+    std::fs::write(&data, "[1,\n 2,,]").unwrap();
+    let src = format!(
+        "needs std.fs\njson.read(fs.open(\"{}\", \"r\"))\n",
+        data.display().to_string().replace('\\', "/")
+    );
+    let path = dir.path().join("bad.aelys");
+    std::fs::write(&path, src).unwrap();
+
+    let mut config = aelys_runtime::VmConfig::default();
+    config.capabilities.allow_fs = true;
+    match aelys_driver::run_file_with_config(&path, config, Vec::new()).unwrap_err() {
+        AelysError::Runtime(err) => {
+            let msg = err.kind.message();
+            assert!(msg.contains("line 2, column 4"), "{}", msg);
+        }
+        err => panic!("expected a runtime error, got {}", err),
+    }
+}
@@ -0,0 +1,208 @@
+#![cfg(unix)]
+
+mod common;
+use aelys_runtime::vm::{GcRef, ObjectKind};
+use aelys_runtime::{Value, VmConfig};
+use common::*;
+
+fn exec_config() -> VmConfig {
+    let mut config = VmConfig::default();
+    config.capabilities.allow_exec = true;
+    config
+}
+
+fn run_with_exec(source: &str) -> Result<Value, String> {
+    let mut vm = aelys::new_vm_with_config(exec_config(), Vec::new()).unwrap();
+    aelys::run_with_vm(&mut vm, source, "<test>").map_err(|e| e.to_string())
+}
+
+fn assert_exec_str(source: &str, expected: &str) {
+    let mut vm = aelys::new_vm_with_config(exec_config(), Vec::new()).unwrap();
+    let result =
+        aelys::run_with_vm(&mut vm, source, "<test>").expect("Aelys execution should succeed");
+    let obj = result
+        .as_ptr()
+        .and_then(|ptr| vm.heap().get(GcRef::new(ptr)));
+    match obj.map(|obj| &obj.kind) {
+        Some(ObjectKind::String(s)) => assert_eq!(s.as_str(), expected),
+        _ => panic!("Expected string '{}' but got {:?}", expected, result),
+    }
+}
+
+#[test]
+fn spawn_denied_without_capability() {
+    let code = r#"
+needs std.sys
+sys.spawn("true", null, null)
+"#;
+    assert_aelys_error_contains(code, "capability");
+}
+
+#[test]
+fn spawn_reads_stdout_lines_and_exit_code() {
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sh", ["-c", "echo one; printf 'two\r\n'; echo three; exit 7"], null)
+let a = sys.read_stdout(p)
+let b = sys.read_stdout(p)
+let c = sys.read_stdout(p)
+let end = sys.read_stdout(p)
+let code = sys.wait(p)
+sys.close(p)
+let done = end == null
+"{a},{b},{c},{code},{done}"
+"#;
+    assert_exec_str(code, "one,two,three,7,true");
+}
+
+#[test]
+fn spawn_streams_stdin() {
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sh", ["-c", "while read l; do echo \"<$l>\"; echo \"err $l\" >&2; done"], null)
+sys.write_stdin(p, "x\n")
+let first = sys.read_stdout(p)
+sys.write_stdin(p, "y\n")
+let second = sys.read_stdout(p)
+sys.close_stdin(p)
+let err = sys.read_stderr(p)
+sys.wait(p)
+first + second + err
+"#;
+    assert_exec_str(code, "<x><y>err x");
+}
+
+#[test]
+fn spawn_with_cwd_and_env() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().canonicalize().unwrap();
+    let code = format!(
+        r#"
+needs std.sys
+let p = sys.spawn("sh", ["-c", "echo $(pwd):$AELYS_SPAWN_VAR:$HOME"], [["cwd", "{}"], ["clear_env", true], ["env", [["AELYS_SPAWN_VAR", "set"]]]])
+sys.read_stdout(p)
+"#,
+        dir.display()
+    );
+    assert_exec_str(&code, &format!("{}:set:", dir.display()));
+}
+
+#[test]
+fn try_wait_and_kill() {
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sleep", ["10"], [["stdout", "null"], ["stderr", "null"]])
+let running = sys.try_wait(p) == null
+sys.kill(p)
+let code = sys.wait(p)
+if running and sys.child_pid(p) > 0 { code } else { 0 }
+"#;
+    assert_eq!(run_with_exec(code).unwrap().as_int(), Some(-1));
+}
+
+#[test]
+fn wait_timeout_kills_the_child() {
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sleep", ["10"], [["timeout", 50]])
+sys.wait(p)
+"#;
+    let err = run_with_exec(code).unwrap_err();
+    assert!(err.contains("timed out after 50ms"), "{}", err);
+}
+
+#[test]
+fn read_timeout() {
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sleep", ["10"], [["timeout", 50]])
+sys.read_stdout(p)
+"#;
+    let err = run_with_exec(code).unwrap_err();
+    assert!(err.contains("sys.read_stdout"), "{}", err);
+    assert!(err.contains("timed out"), "{}", err);
+}
+
+#[test]
+fn spawn_errors() {
+    let err = run_with_exec(
+        r#"
+needs std.sys
+sys.spawn("definitely-not-a-program-aelys", null, null)
+"#,
+    )
+    .unwrap_err();
+    assert!(err.contains("failed to spawn"), "{}", err);
+
+    let err = run_with_exec(
+        r#"
+needs std.sys
+sys.spawn("true", null, [["shell", true]])
+"#,
+    )
+    .unwrap_err();
+    assert!(err.contains("unknown option 'shell'"), "{}", err);
+
+    let err = run_with_exec(
+        r#"
+needs std.sys
+let p = sys.spawn("true", null, [["stdout", "null"]])
+sys.read_stdout(p)
+"#,
+    )
+    .unwrap_err();
+    assert!(err.contains("not piped"), "{}", err);
+}
+
+#[test]
+fn output_is_read_through_a_bounded_buffer() {
+    // far more lines than are buffered: the child waits for the reader
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sh", ["-c", "i=0; while [ $i -lt 5000 ]; do echo $i; i=$((i+1)); done"], null)
+let mut count = 0
+let mut last = ""
+let mut line = sys.read_stdout(p)
+while line != null {
+    count += 1
+    last = line
+    line = sys.read_stdout(p)
+}
+sys.wait(p)
+"{count} {last}"
+"#;
+    assert_exec_str(code, "5000 4999");
+
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sh", ["-c", "head -c 200000 /dev/zero | tr '\\0' x; echo"], null)
+let a = sys.read_stdout(p)
+let b = sys.read_stdout(p)
+let c = sys.read_stdout(p)
+let d = sys.read_stdout(p)
+"{a.len()} {b.len()} {c.len()} {d.len()} {sys.read_stdout(p)}"
+"#;
+    assert_exec_str(code, "65536 65536 65536 3392 null");
+}
+
+#[test]
+fn dropping_the_vm_kills_open_children() {
+    if !std::path::Path::new("/proc/self").exists() {
+        return;
+    }
+    let mut vm = aelys::new_vm_with_config(exec_config(), Vec::new()).unwrap();
+    let code = r#"
+needs std.sys
+let p = sys.spawn("sleep", ["30"], [["stdout", "null"], ["stderr", "null"]])
+sys.child_pid(p)
+"#;
+    let pid = aelys::run_with_vm(&mut vm, code, "<test>")
+        .unwrap()
+        .as_int()
+        .unwrap();
+    let proc_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+    assert!(proc_dir.exists());
+    drop(vm);
+    // killed and reaped, so not even a zombie is left
+    assert!(!proc_dir.exists());
+}
@@ -0,0 +1,178 @@
+mod common;
+use aelys::{new_vm_with_config, run_with_vm_and_opt};
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::VmConfig;
+use common::*;
+
+fn run_seeded(seed: u64, code: &str) -> String {
+    let config = VmConfig {
+        seed: Some(seed),
+        ..VmConfig::default()
+    };
+    let mut vm = new_vm_with_config(config, Vec::new()).expect("Failed to create VM");
+    let result = run_with_vm_and_opt(&mut vm, code, "<test>", OptimizationLevel::Standard)
+        .expect("Aelys execution should succeed");
+    vm.value_to_string(result)
+}
+
+#[test]
+fn same_seed_same_sequence() {
+    let code = r#"
+needs std.random
+let a = random.new(42)
+let b = random.new(42)
+let c = random.new(43)
+let mut same = true
+let mut differs = false
+for i in 0..50 {
+    let x = random.float(a)
+    if x != random.float(b) {
+        same = false
+    }
+    if x != random.float(c) {
+        differs = true
+    }
+}
+random.free(a)
+random.free(b)
+random.free(c)
+"{same} {differs}"
+"#;
+    assert_aelys_str(code, "true true");
+}
+
+#[test]
+fn draws_stay_in_range() {
+    let code = r#"
+needs std.random
+let g = random.new(7)
+let mut ok = true
+let mut seen_lo = false
+let mut seen_hi = false
+for i in 0..2000 {
+    let f = random.float(g)
+    let n = random.int(g, -3, 3)
+    let u = random.uniform(g, 2.5, 4)
+    let e = random.exponential(g, 2)
+    if f < 0 or f >= 1 or n < -3 or n > 3 or u < 2.5 or u >= 4 or e < 0 {
+        ok = false
+    }
+    if n == -3 {
+        seen_lo = true
+    }
+    if n == 3 {
+        seen_hi = true
+    }
+}
+"{ok} {seen_lo} {seen_hi} {random.int(g, 5, 5)} {random.uniform(g, 1.5, 1.5)}"
+"#;
+    assert_aelys_str(code, "true true true 5 1.5");
+}
+
+#[test]
+fn normal_and_exponential_means() {
+    let code = r#"
+needs std.random
+let g = random.new(2024)
+let n = 20000
+let mut sum = 0.0
+let mut sq = 0.0
+let mut esum = 0.0
+for i in 0..n {
+    let x = random.normal(g, 10, 2)
+    sum = sum + x
+    sq = sq + (x - 10) * (x - 10)
+    esum = esum + random.exponential(g, 4)
+}
+let mean = sum / n
+let sd = math.sqrt(sq / n)
+let emean = esum / n
+math.abs(mean - 10) < 0.1 and math.abs(sd - 2) < 0.1 and math.abs(emean - 0.25) < 0.01
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn shuffle_and_choice_on_collections() {
+    let code = r#"
+needs std.random
+let g = random.new(1)
+let a = [1, 2, 3, 4, 5, 6, 7, 8]
+random.shuffle(g, a)
+let v = Vec["a", "b", "c", "d"]
+random.shuffle(g, v)
+let mut sum = 0
+for i in 0..a.len() {
+    sum = sum + a[i]
+}
+let sorted = Vec[v[0], v[1], v[2], v[3]]
+sorted.sort()
+let pick = random.choice(g, v)
+let empty = Vec<Int>[]
+"{sum} {sorted} {v.contains(pick)} {random.choice(g, empty)}"
+"#;
+    assert_aelys_str(code, "36 Vec[a, b, c, d] true null");
+}
+
+#[test]
+fn shuffle_moves_things_around() {
+    let code = r#"
+needs std.random
+let g = random.new(99)
+let f = Vec<Float>[]
+for i in 0..20 {
+    f.push(i * 1.0)
+}
+random.shuffle(g, f)
+let mut moved = 0
+for i in 0..20 {
+    if f[i] != i * 1.0 {
+        moved++
+    }
+}
+moved > 10
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn default_generator_follows_the_vm_seed() {
+    let code = r#"
+needs std.random
+let a = [1, 2, 3, 4, 5]
+random.shuffle(null, a)
+"{random.int(null, 0, 1000000)} {sys.random_int(0, 1000000)} {math.randint(0, 1000000)} {random.float(null)} {a}"
+"#;
+    let code = format!("needs std.sys\n{}", code);
+    let first = run_seeded(5, &code);
+    assert_eq!(first, run_seeded(5, &code));
+    assert_ne!(first, run_seeded(6, &code));
+}
+
+#[test]
+fn random_errors() {
+    assert_aelys_error_contains(
+        "needs std.random\nrandom.int(null, 3, 1)",
+        "expected lo <= hi, got 3 > 1",
+    );
+    assert_aelys_error_contains(
+        "needs std.random\nrandom.normal(null, 0, -1)",
+        "expected sd >= 0",
+    );
+    assert_aelys_error_contains(
+        "needs std.random\nrandom.exponential(null, 0)",
+        "expected rate > 0",
+    );
+    assert_aelys_error_contains(
+        "needs std.random\nrandom.float(12345)",
+        "expected generator handle or null",
+    );
+    assert_aelys_error_contains(
+        "needs std.random\nrandom.shuffle(null, \"abc\")",
+        "expected array or vec, got string",
+    );
+    assert_aelys_error_contains(
+        "needs std.random\nlet g = random.new(1)\nrandom.free(g)\nrandom.float(g)",
+        "expected generator handle or null",
+    );
+}
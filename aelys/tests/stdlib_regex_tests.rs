@@ -0,0 +1,179 @@
+mod common;
+use common::*;
+
+#[test]
+fn regex_is_match_with_handle_and_pattern() {
+    let code = r#"
+needs std.regex
+let re = regex.compile("^[a-z]+\\d*$")
+regex.is_match(re, "abc123") and not regex.is_match(re, "123abc") and regex.is_match("b+", "abbc")
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn regex_find_and_find_all() {
+    assert_aelys_str(
+        r#"
+needs std.regex
+regex.find("\\d+", "order 66 of 99")
+"#,
+        "66",
+    );
+    assert_aelys_null(
+        r#"
+needs std.regex
+regex.find("\\d+", "none here")
+"#,
+    );
+    let code = r#"
+needs std.regex
+let all = regex.find_all("[a-z]+", "one, two; three")
+if all.len() == 3 { all[2] } else { "" }
+"#;
+    assert_aelys_str(code, "three");
+}
+
+#[test]
+fn regex_captures_by_index() {
+    let code = r#"
+needs std.regex
+let caps = regex.captures("(\\w+)@(\\w+)(\\.org)?", "mail bob@example now")
+caps[0] + "|" + caps[1] + "|" + caps[2]
+"#;
+    assert_aelys_str(code, "bob@example|bob|example");
+    assert_aelys_bool(
+        r#"
+needs std.regex
+regex.captures("(a)(b)?", "a")[2] == null
+"#,
+        true,
+    );
+    assert_aelys_null(
+        r#"
+needs std.regex
+regex.captures("(a)", "xyz")
+"#,
+    );
+}
+
+#[test]
+fn regex_group_by_index_and_name() {
+    let code = r#"
+needs std.regex
+let re = regex.compile("(?P<key>\\w+)=(?P<value>\\w+)")
+regex.group(re, "x = 1; port=8080", "value") + "/" + regex.group(re, "port=8080", 1)
+"#;
+    assert_aelys_str(code, "8080/port");
+    assert_aelys_error_contains(
+        r#"
+needs std.regex
+regex.group("(?P<a>x)", "x", "b")
+"#,
+        "no group named 'b'",
+    );
+    assert_aelys_error_contains(
+        r#"
+needs std.regex
+regex.group("(x)", "x", 2)
+"#,
+        "no group 2",
+    );
+}
+
+#[test]
+fn regex_replace_with_group_references() {
+    let code = r#"
+needs std.regex
+let re = regex.compile("(?P<y>\\d{{4}})-(?P<m>\\d{{2}})")
+regex.replace_all(re, "2024-05 and 1999-12", "$m/${{y}}")
+"#;
+    assert_aelys_str(code, "05/2024 and 12/1999");
+    assert_aelys_str(
+        r#"
+needs std.regex
+regex.replace("o", "foo boo", "0")
+"#,
+        "f0o boo",
+    );
+    assert_aelys_str(
+        r#"
+needs std.regex
+regex.replace_all("(\\w+) (\\w+)", "hello world", "$2 $1 $$")
+"#,
+        "world hello $",
+    );
+}
+
+#[test]
+fn regex_split() {
+    let code = r#"
+needs std.regex
+let parts = regex.split("\\s*[,;]\\s*", "a , b;c ;  d")
+if parts.len() == 4 { parts[0] + parts[1] + parts[2] + parts[3] } else { "" }
+"#;
+    assert_aelys_str(code, "abcd");
+}
+
+#[test]
+fn regex_escape_matches_literally() {
+    let code = r#"
+needs std.regex
+let re = regex.escape("1+1=2?")
+regex.is_match(re, "is 1+1=2?") and not regex.is_match(re, "11=2")
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn regex_patterns_in_loops() {
+    let code = r#"
+needs std.regex
+let mut n = 0
+for i in 0..500 {
+    if regex.is_match("^(a|b)*c$", "ababc") {
+        n += 1
+    }
+}
+n
+"#;
+    assert_aelys_int(code, 500);
+}
+
+#[test]
+fn regex_matching_is_linear() {
+    // catastrophic for a backtracking engine
+    let code = r#"
+needs std.regex
+let s = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa!"
+regex.is_match("^(a+)+$", s)
+"#;
+    assert_aelys_bool(code, false);
+}
+
+#[test]
+fn regex_errors() {
+    assert_aelys_error_contains(
+        r#"
+needs std.regex
+regex.compile("(unclosed")
+"#,
+        "regex.compile",
+    );
+    assert_aelys_error_contains(
+        r#"
+needs std.regex
+let re = regex.compile("a")
+regex.free(re)
+regex.is_match(re, "a")
+"#,
+        "invalid regex handle",
+    );
+    assert_aelys_error_contains(
+        r#"
+needs std.regex
+regex.is_match(true, "a")
+"#,
+        "string",
+    );
+}
@@ -0,0 +1,302 @@
+mod common;
+use aelys::{new_vm_with_config, run_with_vm_and_opt};
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::VmConfig;
+use common::*;
+
+#[test]
+fn spawn_join_with_globals() {
+    let code = r#"
+needs std.thread
+let SCALE = 10
+fn square(x) {
+    return x * x * SCALE
+}
+fn work(lo, hi) {
+    let mut total = 0
+    for i in lo..hi {
+        total += square(i)
+    }
+    return total
+}
+let a = thread.spawn(work, [0, 50])
+let b = thread.spawn(work, [50, 100])
+thread.join(a) + thread.join(b)
+"#;
+    assert_aelys_int(code, 3283500);
+}
+
+#[test]
+fn arguments_and_results_are_copies() {
+    let code = r#"
+needs std.thread
+fn bump(a) {
+    a[0] = 100
+    return a
+}
+let data = [1, 2, 3]
+let back = thread.join(thread.spawn(bump, [data]))
+back[1] = 50
+"{data[0]} {data[1]} {back[0]} {back[1]}"
+"#;
+    assert_aelys_str(code, "1 2 100 50");
+}
+
+#[test]
+fn closures_and_callbacks_cross_over() {
+    let code = r#"
+needs std.thread
+fn fib(n) {
+    if n < 2 {
+        return n
+    }
+    return fib(n - 1) + fib(n - 2)
+}
+fn apply(f, x) {
+    return f(x)
+}
+let k = 5
+let c = thread.join(thread.spawn(fn() { return k * 2 }, null))
+let f = thread.join(thread.spawn(apply, [fib, 20]))
+"{c} {f}"
+"#;
+    assert_aelys_str(code, "10 6765");
+}
+
+#[test]
+fn nested_spawn_inside_function() {
+    let code = r#"
+needs std.thread
+fn g(n) {
+    return n * 3
+}
+fn f(n) {
+    let t = thread.spawn(g, [n])
+    return thread.join(t) + 1
+}
+thread.join(thread.spawn(f, [4]))
+"#;
+    assert_aelys_int(code, 13);
+}
+
+#[test]
+fn bounded_channel_producer_consumer() {
+    let code = r#"
+needs std.thread
+fn producer(ch, n) {
+    for i in 0..n {
+        thread.send(ch, i)
+    }
+    thread.close(ch)
+    return null
+}
+let ch = thread.channel(2)
+let p = thread.spawn(producer, [ch, 100])
+let mut sum = 0
+let mut v = thread.recv(ch)
+while v != null {
+    sum += v
+    v = thread.recv(ch)
+}
+thread.join(p)
+sum
+"#;
+    assert_aelys_int(code, 4950);
+}
+
+#[test]
+fn try_recv_and_timeout_on_empty_channel() {
+    let code = r#"
+needs std.thread
+let ch = thread.channel(null)
+let a = thread.try_recv(ch)
+let b = thread.recv_timeout(ch, 10)
+thread.send(ch, "hi")
+let c = thread.try_recv(ch)
+"{a} {b} {c}"
+"#;
+    assert_aelys_str(code, "null null hi");
+}
+
+#[test]
+fn child_error_surfaces_at_join() {
+    let code = r#"
+needs std.thread
+fn boom(x) {
+    return x / 0
+}
+thread.join(thread.spawn(boom, [1]))
+"#;
+    assert_aelys_error_contains(code, "division by zero");
+}
+
+#[test]
+fn functions_cannot_go_through_channels() {
+    let code = r#"
+needs std.thread
+fn f() {
+    return 1
+}
+let ch = thread.channel(null)
+thread.send(ch, f)
+"#;
+    assert_aelys_error_contains(code, "thread.send");
+}
+
+#[test]
+fn max_threads_caps_running_threads() {
+    let config = VmConfig {
+        max_threads: 0,
+        ..VmConfig::default()
+    };
+    let mut vm = new_vm_with_config(config, Vec::new()).expect("Failed to create VM");
+    let code = r#"
+needs std.thread
+fn f() {
+    return 1
+}
+thread.spawn(f, null)
+"#;
+    let err = run_with_vm_and_opt(&mut vm, code, "<test>", OptimizationLevel::Standard)
+        .expect_err("spawn should be refused");
+    assert!(err.to_string().contains("max-threads"), "{}", err);
+}
+
+#[test]
+fn closed_channel_drains_then_refuses_sends() {
+    let code = r#"
+needs std.thread
+let ch = thread.channel(null)
+thread.send(ch, 1)
+thread.close(ch)
+let a = thread.recv(ch)
+let b = thread.recv(ch)
+let c = thread.try_recv(ch)
+"{a} {b} {c}"
+"#;
+    assert_aelys_str(code, "1 null null");
+
+    let code = r#"
+needs std.thread
+let ch = thread.channel(null)
+thread.close(ch)
+thread.send(ch, 1)
+"#;
+    assert_aelys_error_contains(code, "closed channel");
+}
+
+#[test]
+fn only_channel_handles_are_channels() {
+    assert_aelys_error_contains("needs std.thread\nthread.send(12345, 1)", "channel handle");
+    assert_aelys_error_contains(
+        "needs std.thread\nneeds std.regex\nthread.recv(regex.compile(\"a\"))",
+        "channel handle",
+    );
+
+    // handles belong to the VM that made them, not to the process
+    let mut first = new_vm_with_config(VmConfig::default(), Vec::new()).unwrap();
+    let handle = run_with_vm_and_opt(
+        &mut first,
+        "needs std.thread\nthread.channel(null)",
+        "<test>",
+        OptimizationLevel::Standard,
+    )
+    .unwrap();
+    let mut second = new_vm_with_config(VmConfig::default(), Vec::new()).unwrap();
+    let code = format!(
+        "needs std.thread\nthread.send({}, 1)",
+        handle.as_int().unwrap()
+    );
+    let err = run_with_vm_and_opt(&mut second, &code, "<test>", OptimizationLevel::Standard)
+        .expect_err("another VM's channel handle");
+    assert!(err.to_string().contains("channel handle"), "{}", err);
+}
+
+#[test]
+fn threads_share_the_channels_they_start_with() {
+    let code = r#"
+needs std.thread
+let requests = thread.channel(null)
+let replies = thread.channel(null)
+fn worker() {
+    let mut n = thread.recv(requests)
+    while n != null {
+        thread.send(replies, n * n)
+        n = thread.recv(requests)
+    }
+    return null
+}
+let t = thread.spawn(worker, null)
+for i in 1..4 {
+    thread.send(requests, i)
+}
+thread.close(requests)
+let mut total = 0
+for i in 1..4 {
+    total += thread.recv(replies)
+}
+thread.join(t)
+total
+"#;
+    assert_aelys_int(code, 14);
+}
+
+#[test]
+fn env_changes_are_refused_while_threads_run() {
+    let code = r#"
+needs std.thread
+needs std.sys as sys
+fn wait(ch) {
+    return thread.recv(ch)
+}
+let ch = thread.channel(null)
+let t = thread.spawn(wait, [ch])
+sys.set_env("AELYS_THREAD_ENV_TEST", "x")
+"#;
+    assert_aelys_error_contains(code, "thread(s) running");
+
+    let code = r#"
+needs std.thread
+needs std.sys as sys
+fn change() {
+    sys.unset_env("AELYS_THREAD_ENV_TEST")
+    return 1
+}
+thread.join(thread.spawn(change, null))
+"#;
+    assert_aelys_error_contains(code, "sys.unset_env");
+
+    let code = r#"
+needs std.thread
+needs std.sys as sys
+fn f() {
+    return 1
+}
+thread.join(thread.spawn(f, null))
+sys.set_env("AELYS_THREAD_ENV_TEST", "ok")
+let v = sys.env("AELYS_THREAD_ENV_TEST")
+sys.unset_env("AELYS_THREAD_ENV_TEST")
+v
+"#;
+    assert_aelys_str(code, "ok");
+}
+
+#[test]
+fn null_is_not_a_message() {
+    let code = r#"
+needs std.thread
+let ch = thread.channel(null)
+thread.send(ch, null)
+"#;
+    assert_aelys_error_contains(code, "thread.send");
+
+    let code = r#"
+needs std.thread
+let ch = thread.channel(null)
+thread.send(ch, [null, 1])
+thread.close(ch)
+let v = thread.recv(ch)
+"{v[0]} {v[1]} {thread.recv(ch)}"
+"#;
+    assert_aelys_str(code, "null 1 null");
+}
@@ -299,3 +299,181 @@ let s = format("%b")
 "#;
     assert_aelys_int(code, 42);
 }
+
+#[test]
+fn datetime_in_named_zone_across_dst() {
+    // New York springs forward at 2024-03-10 02:00 and falls back at
+    // 2024-11-03 02:00
+    let code = r#"
+let dt = time.datetime(2024, 3, 10, 1, 30, 0, "America/New_York")
+let gap = time.datetime(2024, 3, 10, 2, 30, 0, "America/New_York")
+let twice = time.datetime(2024, 11, 3, 1, 30, 0, "America/New_York")
+"{dt} {time.add(dt, 3600)} {time.add_days(dt, 1)} {gap} {twice}"
+"#;
+    assert_aelys_str(
+        code,
+        "2024-03-10T01:30:00-05:00[America/New_York] \
+         2024-03-10T03:30:00-04:00[America/New_York] \
+         2024-03-11T01:30:00-04:00[America/New_York] \
+         2024-03-10T03:30:00-04:00[America/New_York] \
+         2024-11-03T01:30:00-04:00[America/New_York]",
+    );
+}
+
+#[test]
+fn datetime_zone_conversion() {
+    let code = r#"
+let dt = "2024-07-01T12:00:00Z"
+let paris = time.to_zone(dt, "Europe/Paris")
+"{paris} {time.to_zone(paris, "+05:30")} {time.to_zone(paris, "UTC")} {time.field(paris, "hour")} {time.field(paris, "offset")} {time.field(paris, "zone")} {time.compare(paris, dt)}"
+"#;
+    assert_aelys_str(
+        code,
+        "2024-07-01T14:00:00+02:00[Europe/Paris] 2024-07-01T17:30:00+05:30 \
+         2024-07-01T12:00:00Z 14 7200 Europe/Paris 0",
+    );
+}
+
+#[test]
+fn datetime_parse_iso() {
+    let code = r#"
+let a = time.parse_datetime("2024-05-17T10:00:00.5+02:00")
+let b = time.parse_datetime("2024-05-17 10:00")
+let c = time.parse_datetime("2024-05-17")
+let d = time.parse_datetime("2024-01-15T08:00:00[Europe/Paris]")
+let e = time.parse_datetime("2024-01-15T08:00:00+01:00[Europe/Paris]")
+"{a} {b} {c} {d} {d == e}"
+"#;
+    assert_aelys_str(
+        code,
+        "2024-05-17T10:00:00.5+02:00 2024-05-17T10:00:00Z 2024-05-17T00:00:00Z \
+         2024-01-15T08:00:00+01:00[Europe/Paris] true",
+    );
+    assert_aelys_error_contains("time.parse_datetime(\"2023-02-29\")", "no such date");
+    assert_aelys_error_contains(
+        "time.parse_datetime(\"2024-01-15T08:00:00+02:00[Europe/Paris]\")",
+        "the offset doesn't match the zone",
+    );
+    assert_aelys_error_contains(
+        "time.to_zone(\"2024-01-15T08:00:00Z\", \"Mars/Olympus\")",
+        "unknown zone 'Mars/Olympus'",
+    );
+    assert_aelys_error_contains(
+        "time.to_zone(\"2024-01-15T08:00:00Z\", \"../../etc/passwd\")",
+        "invalid zone name",
+    );
+}
+
+#[test]
+fn datetime_calendar_arithmetic() {
+    let code = r#"
+let jan31 = "2024-01-31T09:15:00Z"
+let a = time.add_months(jan31, 1)
+let b = time.add_months(jan31, 13)
+let c = time.add_days(jan31, -31)
+let d = time.add(jan31, -0.25)
+"{a} {b} {c} {d} {time.diff(a, jan31)} {time.compare(c, jan31)}"
+"#;
+    assert_aelys_str(
+        code,
+        "2024-02-29T09:15:00Z 2025-02-28T09:15:00Z 2023-12-31T09:15:00Z \
+         2024-01-31T09:14:59.75Z 2505600.0 -1",
+    );
+}
+
+#[test]
+fn datetime_timestamps_and_fields() {
+    let code = r#"
+let dt = time.from_timestamp(1700000000, "Asia/Tokyo")
+"{dt} {time.timestamp(dt)} {time.field(dt, "weekday")} {time.field(dt, "yearday")} {time.from_timestamp(-1.5, null)}"
+"#;
+    assert_aelys_str(
+        code,
+        "2023-11-15T07:13:20+09:00[Asia/Tokyo] 1700000000.0 3 319 1969-12-31T23:59:58.5Z",
+    );
+    assert_aelys_error_contains(
+        "time.field(\"2024-01-01T00:00:00Z\", \"century\")",
+        "got 'century'",
+    );
+    assert_aelys_error_contains(
+        "time.datetime(2024, 2, 30, 0, 0, 0, null)",
+        "expected a real date and time",
+    );
+}
+
+#[test]
+fn datetime_strftime_and_strptime() {
+    let code = r#"
+let dt = time.datetime(2024, 3, 5, 21, 7, 9.25, "Europe/Paris")
+let out = time.strftime(dt, "%A %e %B %Y, %I:%M:%S.%f %p %Z %z %:z day %j, %F %T %%")
+let back = time.strptime("05/03/2024 9:07 pm", "%d/%m/%Y %I:%M %p", "Europe/Paris")
+let rfc = time.strptime("Tue, 05 Mar 2024 21:07:09 +0100", "%a, %d %b %Y %T %z", null)
+let utc = time.strptime("1709669229", "%s", null)
+"{out}|{back}|{rfc}|{utc}"
+"#;
+    assert_aelys_str(
+        code,
+        "Tuesday  5 March 2024, 09:07:09.250000 PM CET +0100 +01:00 day 065, 2024-03-05 21:07:09 %|\
+         2024-03-05T21:07:00+01:00[Europe/Paris]|2024-03-05T21:07:09+01:00|2024-03-05T20:07:09Z",
+    );
+    assert_aelys_error_contains(
+        "time.strptime(\"2024-13-01\", \"%Y-%m-%d\", null)",
+        "no such date or time",
+    );
+    assert_aelys_error_contains(
+        "time.strptime(\"2024/01/01\", \"%Y-%m-%d\", null)",
+        "at column 5 (expected '-')",
+    );
+    assert_aelys_error_contains(
+        "time.strftime(\"2024-01-01T00:00:00Z\", \"%Q\")",
+        "expected known format specifier, got %Q",
+    );
+}
+
+#[test]
+fn durations() {
+    let code = r#"
+let a = time.parse_duration("PT1H30M")
+let b = time.parse_duration("1h30m")
+let c = time.parse_duration("-P1DT0.5S")
+let d = time.parse_duration("250ms")
+let e = time.parse_duration("2d 4h 10us")
+"{a} {a == b} {c} {d} {time.duration_str(a)} {time.duration_str(c)} {time.duration_str(d)} {time.duration_str(e)} {time.duration_str(0)} {time.duration_str(42e-9)}"
+"#;
+    assert_aelys_str(
+        code,
+        "5400.0 true -86400.5 0.25 1h30m -1d0.5s 250ms 2d4h0.00001s 0s 42ns",
+    );
+    assert_aelys_error_contains("time.parse_duration(\"P1M\")", "no fixed length");
+    assert_aelys_error_contains(
+        "time.parse_duration(\"5 parsecs\")",
+        "unknown unit 'parsecs'",
+    );
+}
+
+#[test]
+fn wall_clock_and_monotonic_clock() {
+    let code = r#"
+let a = time.instant()
+let utc = time.utc_now()
+let local = time.local_now()
+let b = time.instant()
+let skew = time.diff(local, utc)
+b >= a and skew >= 0 and skew < 5 and time.timestamp(utc) > 1600000000 and utc.ends_with("Z")
+"#;
+    assert_aelys_bool(code, true);
+}
+
+#[test]
+fn datetime_functions_stay_qualified() {
+    // add/diff/compare are only time.add etc., a script's own add is untouched
+    let code = r#"
+fn add(a, b) {
+    return a * b
+}
+let dt = time.add("2024-01-01T00:00:00Z", 60)
+"{add(3, 4)} {dt}"
+"#;
+    assert_aelys_str(code, "12 2024-01-01T00:01:00Z");
+    assert_aelys_error_contains("diff(1, 2)", "diff");
+}
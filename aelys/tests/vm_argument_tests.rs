@@ -68,6 +68,40 @@ fn invalid_vm_arg_value_errors() {
     }
 }
 
+#[test]
+fn seed_vm_arg() {
+    assert_eq!(parse_vm_args(&[]).unwrap().config.seed, None);
+    let parsed = parse_vm_args(&["-ae.seed=42".to_string()]).expect("should parse");
+    assert_eq!(parsed.config.seed, Some(42));
+    let parsed = parse_vm_args(&["--ae-seed=7".to_string()]).expect("should parse");
+    assert_eq!(parsed.config.seed, Some(7));
+    let err = parse_vm_args(&["-ae.seed=-1".to_string()])
+        .err()
+        .expect("should error");
+    match err {
+        VmArgsError::InvalidValue { reason, .. } => {
+            assert!(reason.contains("non-negative integer"));
+        }
+        _ => panic!("unexpected error"),
+    }
+}
+
+#[test]
+fn max_threads_vm_arg() {
+    assert_eq!(parse_vm_args(&[]).unwrap().config.max_threads, 64);
+    let parsed = parse_vm_args(&["-ae.max-threads=4".to_string()]).expect("should parse");
+    assert_eq!(parsed.config.max_threads, 4);
+    let err = parse_vm_args(&["-ae.max-threads=-1".to_string()])
+        .err()
+        .expect("should error");
+    match err {
+        VmArgsError::InvalidValue { reason, .. } => {
+            assert!(reason.contains("non-negative integer"));
+        }
+        _ => panic!("unexpected error"),
+    }
+}
+
 #[test]
 fn parse_vm_args_capabilities_flags() {
     let parsed = parse_vm_args(&[
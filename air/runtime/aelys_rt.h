@@ -0,0 +1,285 @@
+/*
+ * aelys_rt.h - header-only runtime for C emitted by `aelys compile --emit-c`
+ *
+ * Everything here is static inline so the generated file stays a single
+ * translation unit. Strings are NUL-terminated UTF-8 (8 bytes, like AIR's
+ * `str`), slices are a {ptr, len} pair (16 bytes, like AIR's `[T]`), so struct
+ * layouts computed by `air::layout` hold for the C structs too.
+ *
+ * Memory handed out by the runtime is never reclaimed: there's no GC on this
+ * side yet, which is fine for short-lived programs and tools.
+ */
+
+#ifndef AELYS_RT_H
+#define AELYS_RT_H
+
+#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#ifndef __STDC_NO_ATOMICS__
+#include <stdatomic.h>
+#define AELYS_FENCE(order) atomic_thread_fence(order)
+#else
+#define AELYS_FENCE(order) ((void)0)
+#endif
+
+/* generated functions and globals are all static; not every one of them is used */
+#if defined(__GNUC__) || defined(__clang__)
+#define AELYS_INTERNAL static __attribute__((unused))
+#else
+#define AELYS_INTERNAL static
+#endif
+
+typedef const char *aelys_str;
+
+typedef struct {
+    void *ptr;
+    int64_t len;
+} aelys_slice;
+
+/* ---- errors ---------------------------------------------------------- */
+
+_Noreturn static inline void aelysrt_panic(const char *msg) {
+    fflush(stdout);
+    fprintf(stderr, "error: %s\n", msg);
+    exit(1);
+}
+
+static inline int64_t aelysrt_check_index(int64_t len, int64_t index) {
+    if (index < 0 || index >= len) {
+        char msg[96];
+        snprintf(msg, sizeof msg, "index out of bounds: index %" PRId64 ", length %" PRId64,
+                 index, len);
+        aelysrt_panic(msg);
+    }
+    return index;
+}
+
+#define AELYS_DIV(a, b) ((b) == 0 ? (aelysrt_panic("division by zero"), (a)) : (a) / (b))
+#define AELYS_REM(a, b) ((b) == 0 ? (aelysrt_panic("division by zero"), (a)) : (a) % (b))
+
+static inline int64_t aelysrt_checked_add(int64_t a, int64_t b) {
+    if ((b > 0 && a > INT64_MAX - b) || (b < 0 && a < INT64_MIN - b)) {
+        aelysrt_panic("integer overflow");
+    }
+    return a + b;
+}
+
+static inline int64_t aelysrt_checked_sub(int64_t a, int64_t b) {
+    if ((b < 0 && a > INT64_MAX + b) || (b > 0 && a < INT64_MIN + b)) {
+        aelysrt_panic("integer overflow");
+    }
+    return a - b;
+}
+
+static inline int64_t aelysrt_checked_mul(int64_t a, int64_t b) {
+    if (a != 0 && b != 0) {
+        if ((a == -1 && b == INT64_MIN) || (b == -1 && a == INT64_MIN)) {
+            aelysrt_panic("integer overflow");
+        }
+        int64_t r = a * b;
+        if (r / b != a) {
+            aelysrt_panic("integer overflow");
+        }
+        return r;
+    }
+    return 0;
+}
+
+/* ---- memory ---------------------------------------------------------- */
+
+static inline void *aelysrt_alloc(size_t size) {
+    void *p = calloc(1, size ? size : 1);
+    if (!p) {
+        aelysrt_panic("out of memory");
+    }
+    return p;
+}
+
+static inline void aelysrt_free(void *p) {
+    free(p);
+}
+
+/* ---- strings --------------------------------------------------------- */
+
+static inline aelys_str aelysrt_str_concat(aelys_str a, aelys_str b) {
+    size_t la = strlen(a), lb = strlen(b);
+    char *out = (char *)aelysrt_alloc(la + lb + 1);
+    memcpy(out, a, la);
+    memcpy(out + la, b, lb);
+    return out;
+}
+
+static inline bool aelysrt_str_eq(aelys_str a, aelys_str b) {
+    return strcmp(a, b) == 0;
+}
+
+static inline int aelysrt_str_cmp(aelys_str a, aelys_str b) {
+    return strcmp(a, b);
+}
+
+/* byte length, same as the VM's len() */
+static inline int64_t aelysrt_str_len(aelys_str s) {
+    return (int64_t)strlen(s);
+}
+
+static inline int64_t aelysrt_str_char_count(aelys_str s) {
+    int64_t n = 0;
+    for (const unsigned char *p = (const unsigned char *)s; *p; p++) {
+        if ((*p & 0xC0) != 0x80) {
+            n++;
+        }
+    }
+    return n;
+}
+
+/* indexing goes by character, the result is a one-character string */
+static inline aelys_str aelysrt_str_index(aelys_str s, int64_t index) {
+    aelysrt_check_index(aelysrt_str_char_count(s), index);
+    const unsigned char *p = (const unsigned char *)s;
+    int64_t seen = -1;
+    const unsigned char *start = p;
+    for (; *p; p++) {
+        if ((*p & 0xC0) != 0x80 && ++seen == index) {
+            start = p;
+            break;
+        }
+    }
+    const unsigned char *end = start + 1;
+    while (*end && (*end & 0xC0) == 0x80) {
+        end++;
+    }
+    size_t len = (size_t)(end - start);
+    char *out = (char *)aelysrt_alloc(len + 1);
+    memcpy(out, start, len);
+    return out;
+}
+
+static inline aelys_str aelysrt_str_dup(const char *s) {
+    size_t len = strlen(s);
+    char *out = (char *)aelysrt_alloc(len + 1);
+    memcpy(out, s, len);
+    return out;
+}
+
+static inline aelys_str aelysrt_i64_to_str(int64_t v) {
+    char buf[24];
+    snprintf(buf, sizeof buf, "%" PRId64, v);
+    return aelysrt_str_dup(buf);
+}
+
+static inline aelys_str aelysrt_u64_to_str(uint64_t v) {
+    char buf[24];
+    snprintf(buf, sizeof buf, "%" PRIu64, v);
+    return aelysrt_str_dup(buf);
+}
+
+static inline aelys_str aelysrt_bool_to_str(bool v) {
+    return v ? "true" : "false";
+}
+
+/* same output as the VM: shortest round-trip digits, never an exponent,
+   always a fractional part for integral values */
+static inline aelys_str aelysrt_f64_to_str(double v) {
+    if (isnan(v)) {
+        return "NaN";
+    }
+    if (isinf(v)) {
+        return v > 0 ? "inf" : "-inf";
+    }
+
+    char sci[40];
+    for (int prec = 0; prec < 17; prec++) {
+        snprintf(sci, sizeof sci, "%.*e", prec, v);
+        if (strtod(sci, NULL) == v) {
+            break;
+        }
+    }
+
+    char digits[24];
+    int ndigits = 0;
+    const char *p = sci;
+    bool negative = *p == '-';
+    if (negative) {
+        p++;
+    }
+    for (; *p && *p != 'e'; p++) {
+        if (*p != '.') {
+            digits[ndigits++] = *p;
+        }
+    }
+    int exp10 = atoi(p + 1);
+    while (ndigits > 1 && digits[ndigits - 1] == '0') {
+        ndigits--;
+    }
+
+    char out[400];
+    size_t n = 0;
+    if (negative) {
+        out[n++] = '-';
+    }
+    if (exp10 < 0) {
+        out[n++] = '0';
+        out[n++] = '.';
+        for (int i = 0; i < -exp10 - 1; i++) {
+            out[n++] = '0';
+        }
+        for (int i = 0; i < ndigits; i++) {
+            out[n++] = digits[i];
+        }
+    } else {
+        for (int i = 0; i <= exp10; i++) {
+            out[n++] = i < ndigits ? digits[i] : '0';
+        }
+        out[n++] = '.';
+        if (ndigits > exp10 + 1) {
+            for (int i = exp10 + 1; i < ndigits; i++) {
+                out[n++] = digits[i];
+            }
+        } else {
+            out[n++] = '0';
+        }
+    }
+    out[n] = '\0';
+    return aelysrt_str_dup(out);
+}
+
+/* ---- console --------------------------------------------------------- */
+
+static inline void aelysrt_print(aelys_str s) {
+    fputs(s ? s : "null", stdout);
+}
+
+static inline void aelysrt_println(aelys_str s) {
+    fputs(s ? s : "null", stdout);
+    fputc('\n', stdout);
+}
+
+/* ---- slices (Array / Vec) -------------------------------------------- */
+
+static inline aelys_slice aelysrt_slice_new(size_t elem_size, int64_t len, const void *init) {
+    aelys_slice s;
+    s.ptr = aelysrt_alloc(elem_size * (size_t)(len > 0 ? len : 0));
+    s.len = len;
+    if (init && len > 0) {
+        memcpy(s.ptr, init, elem_size * (size_t)len);
+    }
+    return s;
+}
+
+static inline aelys_slice aelysrt_slice_sized(size_t elem_size, int64_t len) {
+    if (len < 0) {
+        aelysrt_panic("negative array size");
+    }
+    return aelysrt_slice_new(elem_size, len, NULL);
+}
+
+#define AELYS_AT(T, s, i) (((T *)(s).ptr)[aelysrt_check_index((s).len, (i))])
+
+#endif /* AELYS_RT_H */
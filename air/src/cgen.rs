@@ -0,0 +1,1358 @@
+// AIR -> C11 translation
+//
+// Expects a program that went through `layout::compute_layouts` and
+// `mono::monomorphize`: struct field offsets are turned into static asserts,
+// and generic leftovers are an error. Everything the program needs at runtime
+// comes from the header-only `aelys_rt.h`, so the output is a single `.c` file.
+
+use crate::passes::visit::used_locals;
+use crate::print::fmt_type;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+pub const RUNTIME_HEADER_NAME: &str = "aelys_rt.h";
+pub const RUNTIME_HEADER: &str = include_str!("../runtime/aelys_rt.h");
+
+#[derive(Debug, Clone)]
+pub struct CGenError {
+    pub message: String,
+    pub function: Option<String>,
+}
+
+impl fmt::Display for CGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(func) => write!(f, "in function `{}`: {}", func, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for CGenError {}
+
+type CResult<T> = Result<T, CGenError>;
+
+fn err<T>(message: impl Into<String>) -> CResult<T> {
+    Err(CGenError {
+        message: message.into(),
+        function: None,
+    })
+}
+
+pub fn emit_c(program: &AirProgram) -> Result<String, CGenError> {
+    let cgen = CGen::new(program);
+    let mut out = String::new();
+
+    let _ = writeln!(out, "/* generated by aelys */");
+    for file in &program.source_files {
+        let _ = writeln!(out, "/* source: {} */", file.replace("*/", "* /"));
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "#include \"{}\"", RUNTIME_HEADER_NAME);
+    let _ = writeln!(out);
+
+    cgen.emit_structs(&mut out)?;
+    cgen.emit_globals(&mut out)?;
+    cgen.emit_prototypes(&mut out)?;
+
+    for func in cgen.emitted_functions() {
+        cgen.emit_function(func, &mut out).map_err(|mut e| {
+            e.function.get_or_insert_with(|| func.name.clone());
+            e
+        })?;
+    }
+
+    if let Some(main) = cgen.functions.get("main")
+        && !main.is_extern
+        && main.params.is_empty()
+    {
+        let _ = writeln!(out, "#ifndef AELYS_NO_MAIN");
+        let _ = writeln!(out, "int main(void) {{");
+        let _ = writeln!(out, "    {}();", fn_name(main));
+        let _ = writeln!(out, "    return 0;");
+        let _ = writeln!(out, "}}");
+        let _ = writeln!(out, "#endif");
+    }
+
+    Ok(out)
+}
+
+// ============================================================================
+// Naming
+// ============================================================================
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else",
+    "enum", "extern", "float", "for", "goto", "if", "inline", "int", "long", "register",
+    "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
+    "union", "unsigned", "void", "volatile", "while", "bool", "true", "false",
+];
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn field_name(name: &str) -> String {
+    let s = sanitize(name);
+    if C_KEYWORDS.contains(&s.as_str()) || s.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("{}_", s)
+    } else {
+        s
+    }
+}
+
+fn struct_name(name: &str) -> String {
+    format!("aelys_t_{}", sanitize(name))
+}
+
+fn global_name(name: &str) -> String {
+    format!("aelys_g_{}", sanitize(name))
+}
+
+// externs keep their symbol name, everything else is prefixed so it can't
+// clash with libc or the runtime
+fn fn_name(func: &AirFunction) -> String {
+    if func.is_extern {
+        func.name.clone()
+    } else {
+        format!("aelys_{}", sanitize(&func.name))
+    }
+}
+
+fn local_name(id: LocalId) -> String {
+    format!("l{}", id.0)
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+fn is_signed_int(ty: &AirType) -> bool {
+    matches!(ty, AirType::I8 | AirType::I16 | AirType::I32 | AirType::I64)
+}
+
+fn is_unsigned_int(ty: &AirType) -> bool {
+    matches!(ty, AirType::U8 | AirType::U16 | AirType::U32 | AirType::U64)
+}
+
+fn is_int(ty: &AirType) -> bool {
+    is_signed_int(ty) || is_unsigned_int(ty)
+}
+
+fn is_float(ty: &AirType) -> bool {
+    matches!(ty, AirType::F32 | AirType::F64)
+}
+
+fn int_size_type(size: AirIntSize) -> AirType {
+    match size {
+        AirIntSize::I8 => AirType::I8,
+        AirIntSize::I16 => AirType::I16,
+        AirIntSize::I32 => AirType::I32,
+        AirIntSize::I64 => AirType::I64,
+        AirIntSize::U8 => AirType::U8,
+        AirIntSize::U16 => AirType::U16,
+        AirIntSize::U32 => AirType::U32,
+        AirIntSize::U64 => AirType::U64,
+    }
+}
+
+// C declarator for `ty` named `name`; an empty name gives the bare type
+fn c_decl(ty: &AirType, name: &str) -> CResult<String> {
+    let base = match ty {
+        AirType::I8 => "int8_t",
+        AirType::I16 => "int16_t",
+        AirType::I32 => "int32_t",
+        AirType::I64 => "int64_t",
+        AirType::U8 => "uint8_t",
+        AirType::U16 => "uint16_t",
+        AirType::U32 => "uint32_t",
+        AirType::U64 => "uint64_t",
+        AirType::F32 => "float",
+        AirType::F64 => "double",
+        AirType::Bool => "bool",
+        AirType::Str => "aelys_str",
+        AirType::Void => "void",
+        AirType::Slice(_) => "aelys_slice",
+        AirType::Struct(s) => return Ok(join_decl(&struct_name(s), name)),
+        AirType::Ptr(inner) => {
+            let inner_name = if matches!(**inner, AirType::Array(..) | AirType::FnPtr { .. }) {
+                format!("(*{})", name)
+            } else {
+                format!("*{}", name)
+            };
+            return c_decl(inner, &inner_name);
+        }
+        AirType::Array(inner, len) => return c_decl(inner, &format!("{}[{}]", name, len)),
+        AirType::FnPtr { params, ret, conv } => {
+            if *conv == CallingConv::Rust {
+                return err(
+                    "function pointers with the Rust calling convention cannot be emitted as C",
+                );
+            }
+            let ps = c_param_types(params)?;
+            return c_decl(ret, &format!("(*{})({})", name, ps));
+        }
+        AirType::Param(id) => {
+            return err(format!(
+                "generic type parameter T{} survived monomorphization",
+                id.0
+            ));
+        }
+        AirType::GenericStruct { name, .. } => {
+            return err(format!(
+                "generic struct `{}` survived monomorphization",
+                name
+            ));
+        }
+    };
+    Ok(join_decl(base, name))
+}
+
+fn join_decl(base: &str, name: &str) -> String {
+    if name.is_empty() {
+        base.to_string()
+    } else {
+        format!("{} {}", base, name)
+    }
+}
+
+fn c_type(ty: &AirType) -> CResult<String> {
+    c_decl(ty, "")
+}
+
+fn c_param_types(params: &[AirType]) -> CResult<String> {
+    if params.is_empty() {
+        return Ok("void".to_string());
+    }
+    let ps: CResult<Vec<_>> = params.iter().map(c_type).collect();
+    Ok(ps?.join(", "))
+}
+
+fn zero_value(ty: &AirType) -> CResult<String> {
+    Ok(match ty {
+        t if is_int(t) => "0".to_string(),
+        AirType::F32 => "0.0f".to_string(),
+        AirType::F64 => "0.0".to_string(),
+        AirType::Bool => "false".to_string(),
+        AirType::Str => "\"\"".to_string(),
+        AirType::Ptr(_) | AirType::FnPtr { .. } => "NULL".to_string(),
+        AirType::Struct(_) | AirType::Slice(_) => format!("({}){{0}}", c_type(ty)?),
+        AirType::Array(..) => "{0}".to_string(),
+        AirType::Void => return err("cannot materialize a value of type void"),
+        other => return err(format!("no zero value for type {}", fmt_type(other))),
+    })
+}
+
+fn c_string_literal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    let mut prev_was_octal = false;
+    for b in s.bytes() {
+        let octal = !(0x20..0x7f).contains(&b) && !matches!(b, b'\n' | b'\t' | b'\r');
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            b'\r' => out.push_str("\\r"),
+            // `??x` would be a trigraph in older compilers
+            b'?' => out.push_str("\\?"),
+            _ if octal => {
+                let _ = write!(out, "\\{:03o}", b);
+            }
+            _ => {
+                // octal escapes are at most three digits, so a following
+                // digit is safe; split anyway to keep the output readable
+                if prev_was_octal && b.is_ascii_digit() {
+                    out.push_str("\" \"");
+                }
+                out.push(b as char);
+            }
+        }
+        prev_was_octal = octal;
+    }
+    out.push('"');
+    out
+}
+
+fn c_int_literal(v: i64, ty: &AirType) -> String {
+    if is_unsigned_int(ty) {
+        return format!("UINT64_C({})", v as u64);
+    }
+    if v == i64::MIN {
+        "INT64_MIN".to_string()
+    } else if i32::try_from(v).is_ok() {
+        v.to_string()
+    } else {
+        format!("INT64_C({})", v)
+    }
+}
+
+fn c_float_literal(v: f64, size: AirFloatSize) -> String {
+    let s = if v.is_nan() {
+        "NAN".to_string()
+    } else if v.is_infinite() {
+        if v > 0.0 { "INFINITY" } else { "(-INFINITY)" }.to_string()
+    } else {
+        let mut s = format!("{:?}", v);
+        if !s.contains(['.', 'e', 'E']) {
+            s.push_str(".0");
+        }
+        s
+    };
+    match size {
+        AirFloatSize::F64 => s,
+        AirFloatSize::F32 => format!("((float){})", s),
+    }
+}
+
+fn fence_order(order: Ordering) -> &'static str {
+    match order {
+        Ordering::Relaxed => "memory_order_relaxed",
+        Ordering::Acquire => "memory_order_acquire",
+        Ordering::Release => "memory_order_release",
+        Ordering::AcqRel => "memory_order_acq_rel",
+        Ordering::SeqCst => "memory_order_seq_cst",
+    }
+}
+
+// libm functions reachable as `math.<name>` (or a bare imported `<name>`)
+fn math_builtin(name: &str) -> Option<(&'static str, usize)> {
+    Some(match name {
+        "sqrt" => ("sqrt", 1),
+        "cbrt" => ("cbrt", 1),
+        "abs" => ("fabs", 1),
+        "sin" => ("sin", 1),
+        "cos" => ("cos", 1),
+        "tan" => ("tan", 1),
+        "asin" => ("asin", 1),
+        "acos" => ("acos", 1),
+        "atan" => ("atan", 1),
+        "sinh" => ("sinh", 1),
+        "cosh" => ("cosh", 1),
+        "tanh" => ("tanh", 1),
+        "exp" => ("exp", 1),
+        "log" => ("log", 1),
+        "log10" => ("log10", 1),
+        "log2" => ("log2", 1),
+        "floor" => ("floor", 1),
+        "ceil" => ("ceil", 1),
+        "round" => ("round", 1),
+        "trunc" => ("trunc", 1),
+        "atan2" => ("atan2", 2),
+        "pow" => ("pow", 2),
+        "min" => ("fmin", 2),
+        "max" => ("fmax", 2),
+        _ => return None,
+    })
+}
+
+// ============================================================================
+// Program-level emission
+// ============================================================================
+
+struct CGen<'a> {
+    program: &'a AirProgram,
+    functions: HashMap<&'a str, &'a AirFunction>,
+    functions_by_id: HashMap<FunctionId, &'a AirFunction>,
+    structs: HashMap<&'a str, &'a AirStructDef>,
+    globals: HashMap<&'a str, &'a AirGlobal>,
+}
+
+// result of lowering a call: either a value expression or a statement that
+// has to stand on its own (printing, global stores, index stores)
+enum CallCode {
+    Value(String),
+    Stmt(String),
+}
+
+impl<'a> CGen<'a> {
+    fn new(program: &'a AirProgram) -> Self {
+        Self {
+            program,
+            functions: program
+                .functions
+                .iter()
+                .map(|f| (f.name.as_str(), f))
+                .collect(),
+            functions_by_id: program.functions.iter().map(|f| (f.id, f)).collect(),
+            structs: program
+                .structs
+                .iter()
+                .map(|s| (s.name.as_str(), s))
+                .collect(),
+            globals: program
+                .globals
+                .iter()
+                .map(|g| (g.name.as_str(), g))
+                .collect(),
+        }
+    }
+
+    // generic templates are left behind by monomorphize only when nothing
+    // instantiated them, so there is nothing to emit for them
+    fn emitted_functions(&self) -> impl Iterator<Item = &'a AirFunction> {
+        self.program
+            .functions
+            .iter()
+            .filter(|f| f.type_params.is_empty() && !f.is_extern)
+    }
+
+    fn emit_structs(&self, out: &mut String) -> CResult<()> {
+        let defs: Vec<&AirStructDef> = self
+            .program
+            .structs
+            .iter()
+            .filter(|s| s.type_params.is_empty())
+            .collect();
+        if defs.is_empty() {
+            return Ok(());
+        }
+
+        for def in &defs {
+            let name = struct_name(&def.name);
+            let _ = writeln!(out, "typedef struct {} {};", name, name);
+        }
+        let _ = writeln!(out);
+
+        let mut done: HashSet<&str> = HashSet::new();
+        for def in &defs {
+            self.emit_struct(def, &mut done, &mut Vec::new(), out)?;
+        }
+        Ok(())
+    }
+
+    // structs embedded by value have to be complete before their users
+    fn emit_struct(
+        &self,
+        def: &'a AirStructDef,
+        done: &mut HashSet<&'a str>,
+        visiting: &mut Vec<&'a str>,
+        out: &mut String,
+    ) -> CResult<()> {
+        if done.contains(def.name.as_str()) {
+            return Ok(());
+        }
+        if visiting.contains(&def.name.as_str()) {
+            return err(format!(
+                "recursive struct `{}` cannot be laid out",
+                def.name
+            ));
+        }
+        visiting.push(&def.name);
+
+        for field in &def.fields {
+            let mut inner = &field.ty;
+            while let AirType::Array(el, _) = inner {
+                inner = el;
+            }
+            if let AirType::Struct(dep) = inner {
+                let Some(dep_def) = self.structs.get(dep.as_str()) else {
+                    return err(format!(
+                        "struct `{}` uses unknown struct `{}`",
+                        def.name, dep
+                    ));
+                };
+                self.emit_struct(dep_def, done, visiting, out)?;
+            }
+        }
+
+        visiting.pop();
+        done.insert(&def.name);
+
+        let name = struct_name(&def.name);
+        let _ = writeln!(out, "struct {} {{", name);
+        if def.fields.is_empty() {
+            // empty structs are a GNU extension, keep it standard
+            let _ = writeln!(out, "    char _unused;");
+        }
+        for field in &def.fields {
+            let _ = writeln!(out, "    {};", c_decl(&field.ty, &field_name(&field.name))?);
+        }
+        let _ = writeln!(out, "}};");
+        for field in &def.fields {
+            let Some(offset) = field.offset else {
+                return err(format!(
+                    "field `{}.{}` has no offset; run layout::compute_layouts before emitting C",
+                    def.name, field.name
+                ));
+            };
+            let _ = writeln!(
+                out,
+                "_Static_assert(offsetof({}, {}) == {}, \"layout of {}.{}\");",
+                name,
+                field_name(&field.name),
+                offset,
+                def.name,
+                field.name
+            );
+        }
+        let _ = writeln!(out);
+        Ok(())
+    }
+
+    fn emit_globals(&self, out: &mut String) -> CResult<()> {
+        if self.program.globals.is_empty() {
+            return Ok(());
+        }
+        for global in &self.program.globals {
+            let decl = c_decl(&global.ty, &global_name(&global.name))?;
+            // compound literals aren't constant expressions in static initializers
+            let init = match (&global.init, &global.ty) {
+                (_, AirType::Struct(_) | AirType::Slice(_) | AirType::Array(..)) => {
+                    "{0}".to_string()
+                }
+                (Some(c), ty) => self.const_expr(c, Some(ty))?,
+                (None, ty) => zero_value(ty)?,
+            };
+            let _ = writeln!(out, "AELYS_INTERNAL {} = {};", decl, init);
+        }
+        let _ = writeln!(out);
+        Ok(())
+    }
+
+    fn emit_prototypes(&self, out: &mut String) -> CResult<()> {
+        let mut any = false;
+        for func in &self.program.functions {
+            if !func.type_params.is_empty() {
+                continue;
+            }
+            let storage = if func.is_extern {
+                match func.calling_conv {
+                    CallingConv::C => "extern",
+                    _ => {
+                        return err(format!(
+                            "extern function `{}` must use the C calling convention",
+                            func.name
+                        ));
+                    }
+                }
+            } else {
+                "AELYS_INTERNAL"
+            };
+            let _ = writeln!(out, "{} {};", storage, self.signature(func, false)?);
+            any = true;
+        }
+        if any {
+            let _ = writeln!(out);
+        }
+        Ok(())
+    }
+
+    fn signature(&self, func: &AirFunction, named_params: bool) -> CResult<String> {
+        let params = if func.params.is_empty() {
+            "void".to_string()
+        } else {
+            let ps: CResult<Vec<_>> = func
+                .params
+                .iter()
+                .map(|p| {
+                    if named_params {
+                        c_decl(&p.ty, &local_name(p.id))
+                    } else {
+                        c_type(&p.ty)
+                    }
+                })
+                .collect();
+            ps?.join(", ")
+        };
+        c_decl(&func.ret_ty, &format!("{}({})", fn_name(func), params))
+    }
+
+    // ========================================================================
+    // Functions
+    // ========================================================================
+
+    fn emit_function(&self, func: &'a AirFunction, out: &mut String) -> CResult<()> {
+        let cx = FnCx::new(self, func);
+
+        let _ = writeln!(out, "AELYS_INTERNAL {} {{", self.signature(func, true)?);
+
+        let params: HashSet<LocalId> = func.params.iter().map(|p| p.id).collect();
+        for local in &func.locals {
+            if params.contains(&local.id) || local.ty == AirType::Void {
+                continue;
+            }
+            if matches!(local.ty, AirType::Array(..)) {
+                return err(format!(
+                    "local %{} has array type {}; fixed-size array locals are not supported",
+                    local.id.0,
+                    fmt_type(&local.ty)
+                ));
+            }
+            let _ = writeln!(
+                out,
+                "    {} = {};",
+                c_decl(&local.ty, &local_name(local.id))?,
+                zero_value(&local.ty)?
+            );
+        }
+        // temporaries that are only written (kept at -O0, or results nobody
+        // reads) would trip -Wunused-variable and friends
+        let used = used_locals(func);
+        for local in &func.locals {
+            if !used.contains(&local.id) && local.ty != AirType::Void {
+                let _ = writeln!(out, "    (void){};", local_name(local.id));
+            }
+        }
+
+        let targets = jump_targets(func);
+        for block in &func.blocks {
+            if targets.contains(&block.id) {
+                let _ = writeln!(out, "bb{}:;", block.id.0);
+            }
+            for stmt in &block.stmts {
+                cx.emit_stmt(&stmt.kind, out)?;
+            }
+            cx.emit_terminator(&block.terminator, out)?;
+        }
+
+        let _ = writeln!(out, "}}");
+        let _ = writeln!(out);
+        Ok(())
+    }
+}
+
+fn jump_targets(func: &AirFunction) -> HashSet<BlockId> {
+    let mut targets = HashSet::new();
+    for block in &func.blocks {
+        match &block.terminator {
+            AirTerminator::Goto(t) => {
+                targets.insert(*t);
+            }
+            AirTerminator::Branch {
+                then_block,
+                else_block,
+                ..
+            } => {
+                targets.insert(*then_block);
+                targets.insert(*else_block);
+            }
+            AirTerminator::Switch {
+                targets: cases,
+                default,
+                ..
+            } => {
+                targets.extend(cases.iter().map(|(_, t)| *t));
+                targets.insert(*default);
+            }
+            AirTerminator::Invoke { normal, .. } => {
+                targets.insert(*normal);
+            }
+            _ => {}
+        }
+    }
+    targets
+}
+
+struct FnCx<'g, 'a> {
+    g: &'g CGen<'a>,
+    func: &'a AirFunction,
+    local_types: HashMap<LocalId, &'a AirType>,
+}
+
+impl<'g, 'a> FnCx<'g, 'a> {
+    fn new(g: &'g CGen<'a>, func: &'a AirFunction) -> Self {
+        let mut local_types = HashMap::new();
+        for local in &func.locals {
+            local_types.insert(local.id, &local.ty);
+        }
+        for param in &func.params {
+            local_types.insert(param.id, &param.ty);
+        }
+        Self {
+            g,
+            func,
+            local_types,
+        }
+    }
+
+    fn local_ty(&self, id: LocalId) -> CResult<&'a AirType> {
+        match self.local_types.get(&id) {
+            Some(ty) => Ok(ty),
+            None => err(format!("use of undeclared local %{}", id.0)),
+        }
+    }
+
+    fn operand_ty(&self, op: &Operand) -> CResult<Option<AirType>> {
+        Ok(match op {
+            Operand::Copy(id) | Operand::Move(id) => Some(self.local_ty(*id)?.clone()),
+            Operand::Const(c) => match c {
+                AirConst::IntLiteral(_) => Some(AirType::I64),
+                AirConst::Int(_, size) => Some(int_size_type(*size)),
+                AirConst::Float(_, AirFloatSize::F32) => Some(AirType::F32),
+                AirConst::Float(_, AirFloatSize::F64) => Some(AirType::F64),
+                AirConst::Bool(_) => Some(AirType::Bool),
+                AirConst::Str(_) => Some(AirType::Str),
+                AirConst::Null => None,
+                AirConst::ZeroInit(ty) | AirConst::Undef(ty) => Some(ty.clone()),
+            },
+        })
+    }
+
+    fn operand(&self, op: &Operand, expected: Option<&AirType>) -> CResult<String> {
+        match op {
+            Operand::Copy(id) | Operand::Move(id) => {
+                if *self.local_ty(*id)? == AirType::Void {
+                    return err(format!("local %{} of type void used as a value", id.0));
+                }
+                Ok(local_name(*id))
+            }
+            Operand::Const(c) => self.g.const_expr(c, expected),
+        }
+    }
+
+    fn place(&self, place: &Place) -> CResult<(String, AirType)> {
+        match place {
+            Place::Local(id) => Ok((local_name(*id), self.local_ty(*id)?.clone())),
+            Place::Field(id, field) => {
+                let base_ty = self.local_ty(*id)?;
+                let (access, struct_ty) = match base_ty {
+                    AirType::Ptr(inner) => (
+                        format!("{}->{}", local_name(*id), field_name(field)),
+                        &**inner,
+                    ),
+                    other => (format!("{}.{}", local_name(*id), field_name(field)), other),
+                };
+                Ok((access, self.g.field_ty(struct_ty, field)?))
+            }
+            Place::Deref(id) => match self.local_ty(*id)? {
+                AirType::Ptr(inner) => Ok((format!("(*{})", local_name(*id)), (**inner).clone())),
+                other => err(format!(
+                    "cannot dereference %{} of type {}",
+                    id.0,
+                    fmt_type(other)
+                )),
+            },
+            Place::Index(id, index) => {
+                let idx = self.operand(index, Some(&AirType::I64))?;
+                self.index_access(&local_name(*id), self.local_ty(*id)?, &idx)
+            }
+        }
+    }
+
+    fn index_access(&self, base: &str, base_ty: &AirType, idx: &str) -> CResult<(String, AirType)> {
+        match base_ty {
+            AirType::Slice(el) => Ok((
+                format!("AELYS_AT({}, {}, {})", c_type(el)?, base, idx),
+                (**el).clone(),
+            )),
+            AirType::Array(el, n) => Ok((
+                format!("{}[aelysrt_check_index({}, {})]", base, n, idx),
+                (**el).clone(),
+            )),
+            AirType::Ptr(el) => Ok((format!("{}[{}]", base, idx), (**el).clone())),
+            other => err(format!("cannot index a value of type {}", fmt_type(other))),
+        }
+    }
+
+    // ========================================================================
+    // Statements
+    // ========================================================================
+
+    fn emit_stmt(&self, stmt: &AirStmtKind, out: &mut String) -> CResult<()> {
+        match stmt {
+            AirStmtKind::Assign { place, rvalue } => self.emit_assign(place, rvalue, out),
+            AirStmtKind::GcAlloc { local, ty, .. } | AirStmtKind::Alloc { local, ty } => {
+                let _ = writeln!(
+                    out,
+                    "    {} = aelysrt_alloc(sizeof({}));",
+                    local_name(*local),
+                    c_type(ty)?
+                );
+                Ok(())
+            }
+            AirStmtKind::Free(local) => {
+                let _ = writeln!(out, "    aelysrt_free({});", local_name(*local));
+                Ok(())
+            }
+            // no collector on the C side, managed memory simply lives on
+            AirStmtKind::GcDrop(_) | AirStmtKind::ArenaCreate(_) | AirStmtKind::ArenaDestroy(_) => {
+                Ok(())
+            }
+            AirStmtKind::CallVoid { func, args } => {
+                match self.call(func, args, None)? {
+                    CallCode::Value(expr) | CallCode::Stmt(expr) => {
+                        let _ = writeln!(out, "    {};", expr);
+                    }
+                }
+                Ok(())
+            }
+            AirStmtKind::MemoryFence(order) => {
+                let _ = writeln!(out, "    AELYS_FENCE({});", fence_order(*order));
+                Ok(())
+            }
+        }
+    }
+
+    fn emit_assign(&self, place: &Place, rvalue: &Rvalue, out: &mut String) -> CResult<()> {
+        let (dest, dest_ty) = self.place(place)?;
+
+        if let Rvalue::Call { func, args } = rvalue {
+            let expected = (dest_ty != AirType::Void).then_some(&dest_ty);
+            match self.call(func, args, expected)? {
+                CallCode::Value(expr) if dest_ty == AirType::Void => {
+                    let _ = writeln!(out, "    {};", expr);
+                }
+                CallCode::Value(expr) => {
+                    let _ = writeln!(out, "    {} = {};", dest, expr);
+                }
+                CallCode::Stmt(stmt) => {
+                    let _ = writeln!(out, "    {};", stmt);
+                    if dest_ty != AirType::Void {
+                        let _ = writeln!(out, "    {} = {};", dest, zero_value(&dest_ty)?);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let expr = self.rvalue(rvalue, &dest_ty)?;
+        if dest_ty == AirType::Void {
+            let _ = writeln!(out, "    (void)({});", expr);
+        } else {
+            let _ = writeln!(out, "    {} = {};", dest, expr);
+        }
+        Ok(())
+    }
+
+    fn rvalue(&self, rvalue: &Rvalue, dest_ty: &AirType) -> CResult<String> {
+        match rvalue {
+            Rvalue::Use(Operand::Const(AirConst::Null))
+                if matches!(dest_ty, AirType::FnPtr { .. }) =>
+            {
+                err("closures and lambdas are not supported in C output")
+            }
+            Rvalue::Use(op) => self.operand(op, Some(dest_ty)),
+            Rvalue::BinaryOp(op, a, b) => self.binary(op, a, b),
+            Rvalue::UnaryOp(op, a) => {
+                let x = self.operand(a, Some(dest_ty))?;
+                Ok(match op {
+                    UnOp::Neg => format!("(-{})", x),
+                    UnOp::Not => format!("(!{})", x),
+                    UnOp::BitNot => format!("(~{})", x),
+                })
+            }
+            Rvalue::Call { func, args } => match self.call(func, args, Some(dest_ty))? {
+                CallCode::Value(expr) => Ok(expr),
+                CallCode::Stmt(_) => err("call has no value"),
+            },
+            Rvalue::StructInit { name, fields } => {
+                let Some(def) = self.g.structs.get(name.as_str()) else {
+                    return err(format!("unknown struct `{}`", name));
+                };
+                let mut inits = Vec::with_capacity(fields.len());
+                for (field, op) in fields {
+                    let Some(fdef) = def.fields.iter().find(|f| &f.name == field) else {
+                        return err(format!("struct `{}` has no field `{}`", name, field));
+                    };
+                    inits.push(format!(
+                        ".{} = {}",
+                        field_name(field),
+                        self.operand(op, Some(&fdef.ty))?
+                    ));
+                }
+                if inits.is_empty() {
+                    return Ok(format!("({}){{0}}", struct_name(name)));
+                }
+                Ok(format!("({}){{ {} }}", struct_name(name), inits.join(", ")))
+            }
+            Rvalue::FieldAccess { base, field } => {
+                let base_expr = self.operand(base, None)?;
+                match self.operand_ty(base)? {
+                    Some(AirType::Ptr(inner)) => {
+                        self.g.field_ty(&inner, field)?;
+                        Ok(format!("{}->{}", base_expr, field_name(field)))
+                    }
+                    Some(ty @ AirType::Struct(_)) => {
+                        self.g.field_ty(&ty, field)?;
+                        Ok(format!("{}.{}", base_expr, field_name(field)))
+                    }
+                    Some(other) => err(format!(
+                        "field access `.{}` on type {}",
+                        field,
+                        fmt_type(&other)
+                    )),
+                    None => err(format!("field access `.{}` on null", field)),
+                }
+            }
+            Rvalue::AddressOf(id) => Ok(format!("(&{})", local_name(*id))),
+            Rvalue::Deref(op) => Ok(format!("(*{})", self.operand(op, None)?)),
+            Rvalue::Cast { operand, from, to } => {
+                let x = self.operand(operand, Some(from))?;
+                if from == to {
+                    Ok(x)
+                } else if *to == AirType::Str {
+                    self.g.to_str(&x, Some(from))
+                } else {
+                    Ok(format!("(({}){})", c_type(to)?, x))
+                }
+            }
+            Rvalue::Discriminant(_) => err("enum discriminants are not supported in C output"),
+            Rvalue::Phi(_) => err("phi nodes have to be lowered out of SSA before C output"),
+        }
+    }
+
+    fn binary(&self, op: &BinOp, a: &Operand, b: &Operand) -> CResult<String> {
+        let ta = self.operand_ty(a)?;
+        let tb = self.operand_ty(b)?;
+        let ty = ta.clone().or_else(|| tb.clone());
+        let x = self.operand(a, tb.as_ref())?;
+        let y = self.operand(b, ta.as_ref())?;
+
+        if ty == Some(AirType::Str) {
+            return Ok(match op {
+                BinOp::Add => format!("aelysrt_str_concat({}, {})", x, y),
+                BinOp::Eq => format!("aelysrt_str_eq({}, {})", x, y),
+                BinOp::Ne => format!("(!aelysrt_str_eq({}, {}))", x, y),
+                BinOp::Lt => format!("(aelysrt_str_cmp({}, {}) < 0)", x, y),
+                BinOp::Le => format!("(aelysrt_str_cmp({}, {}) <= 0)", x, y),
+                BinOp::Gt => format!("(aelysrt_str_cmp({}, {}) > 0)", x, y),
+                BinOp::Ge => format!("(aelysrt_str_cmp({}, {}) >= 0)", x, y),
+                _ => return err("unsupported operator on strings"),
+            });
+        }
+
+        let floating = ta.as_ref().is_some_and(is_float) || tb.as_ref().is_some_and(is_float);
+        let sym = match op {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div if floating => "/",
+            BinOp::Div => return Ok(format!("AELYS_DIV({}, {})", x, y)),
+            BinOp::Rem if floating => return Ok(format!("fmod({}, {})", x, y)),
+            BinOp::Rem => return Ok(format!("AELYS_REM({}, {})", x, y)),
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::BitXor => "^",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::CheckedAdd if floating => "+",
+            BinOp::CheckedSub if floating => "-",
+            BinOp::CheckedMul if floating => "*",
+            BinOp::CheckedAdd => return Ok(format!("aelysrt_checked_add({}, {})", x, y)),
+            BinOp::CheckedSub => return Ok(format!("aelysrt_checked_sub({}, {})", x, y)),
+            BinOp::CheckedMul => return Ok(format!("aelysrt_checked_mul({}, {})", x, y)),
+        };
+        Ok(format!("({} {} {})", x, sym, y))
+    }
+
+    // ========================================================================
+    // Terminators
+    // ========================================================================
+
+    fn emit_terminator(&self, term: &AirTerminator, out: &mut String) -> CResult<()> {
+        let ret_ty = &self.func.ret_ty;
+        match term {
+            AirTerminator::Return(Some(op)) if *ret_ty != AirType::Void => {
+                let _ = writeln!(out, "    return {};", self.operand(op, Some(ret_ty))?);
+            }
+            AirTerminator::Return(_) if *ret_ty == AirType::Void => {
+                let _ = writeln!(out, "    return;");
+            }
+            AirTerminator::Return(_) => {
+                let _ = writeln!(out, "    return {};", zero_value(ret_ty)?);
+            }
+            AirTerminator::Goto(target) => {
+                let _ = writeln!(out, "    goto bb{};", target.0);
+            }
+            AirTerminator::Branch {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "    if ({}) goto bb{}; else goto bb{};",
+                    self.operand(cond, Some(&AirType::Bool))?,
+                    then_block.0,
+                    else_block.0
+                );
+            }
+            AirTerminator::Switch {
+                discr,
+                targets,
+                default,
+            } => {
+                let discr_ty = self.operand_ty(discr)?;
+                let d = self.operand(discr, None)?;
+                for (value, target) in targets {
+                    let v = self.g.const_expr(value, discr_ty.as_ref())?;
+                    let cond = if discr_ty == Some(AirType::Str) {
+                        format!("aelysrt_str_eq({}, {})", d, v)
+                    } else {
+                        format!("{} == {}", d, v)
+                    };
+                    let _ = writeln!(out, "    if ({}) goto bb{};", cond, target.0);
+                }
+                let _ = writeln!(out, "    goto bb{};", default.0);
+            }
+            // nothing unwinds in C output, so an invoke is a call plus a jump
+            AirTerminator::Invoke {
+                func,
+                args,
+                ret,
+                normal,
+                ..
+            } => {
+                self.emit_assign(
+                    ret,
+                    &Rvalue::Call {
+                        func: func.clone(),
+                        args: args.clone(),
+                    },
+                    out,
+                )?;
+                let _ = writeln!(out, "    goto bb{};", normal.0);
+            }
+            AirTerminator::Unwind => {
+                let _ = writeln!(out, "    aelysrt_panic(\"unwind\");");
+            }
+            AirTerminator::Unreachable => {
+                let _ = writeln!(out, "    aelysrt_panic(\"entered unreachable code\");");
+            }
+            AirTerminator::Panic { message, .. } => {
+                let _ = writeln!(out, "    aelysrt_panic({});", c_string_literal(message));
+            }
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // Calls
+    // ========================================================================
+
+    fn call(
+        &self,
+        callee: &Callee,
+        args: &[Operand],
+        expected: Option<&AirType>,
+    ) -> CResult<CallCode> {
+        match callee {
+            Callee::Direct(id) => match self.g.functions_by_id.get(id) {
+                Some(func) => self.call_function(func, args),
+                None => err(format!("call to unknown function #{}", id.0)),
+            },
+            Callee::Extern(name, conv) => {
+                if *conv != CallingConv::C {
+                    return err(format!(
+                        "extern `{}` must use the C calling convention",
+                        name
+                    ));
+                }
+                match self.g.functions.get(name.as_str()) {
+                    Some(func) if func.is_extern => self.call_function(func, args),
+                    _ => err(format!("call to undeclared extern `{}`", name)),
+                }
+            }
+            Callee::FnPtr(id) => {
+                let AirType::FnPtr { params, .. } = self.local_ty(*id)? else {
+                    return err(format!("%{} is not a function pointer", id.0));
+                };
+                let args = self.args(args, params)?;
+                Ok(CallCode::Value(format!("{}({})", local_name(*id), args)))
+            }
+            Callee::Named(name) => self.call_named(name, args, expected),
+        }
+    }
+
+    fn args(&self, args: &[Operand], params: &[AirType]) -> CResult<String> {
+        if args.len() != params.len() {
+            return err(format!(
+                "expected {} argument(s), found {}",
+                params.len(),
+                args.len()
+            ));
+        }
+        let mut out = Vec::with_capacity(args.len());
+        for (arg, ty) in args.iter().zip(params) {
+            out.push(self.operand(arg, Some(ty))?);
+        }
+        Ok(out.join(", "))
+    }
+
+    fn call_function(&self, func: &AirFunction, args: &[Operand]) -> CResult<CallCode> {
+        if !func.type_params.is_empty() {
+            return err(format!(
+                "call to generic function `{}` was not monomorphized",
+                func.name
+            ));
+        }
+        let params: Vec<AirType> = func.params.iter().map(|p| p.ty.clone()).collect();
+        let args = self.args(args, &params).map_err(|e| CGenError {
+            message: format!("call to `{}`: {}", func.name, e.message),
+            function: None,
+        })?;
+        let call = format!("{}({})", fn_name(func), args);
+        Ok(if func.ret_ty == AirType::Void {
+            CallCode::Stmt(call)
+        } else {
+            CallCode::Value(call)
+        })
+    }
+
+    fn call_named(
+        &self,
+        name: &str,
+        args: &[Operand],
+        expected: Option<&AirType>,
+    ) -> CResult<CallCode> {
+        if let Some(func) = self.g.functions.get(name) {
+            return self.call_function(func, args);
+        }
+
+        if let Some(global) = name.strip_prefix("__aelys_global_get_") {
+            return self.global_get(global).map(CallCode::Value);
+        }
+        if let Some(global) = name.strip_prefix("__aelys_global_set_") {
+            let Some(g) = self.g.globals.get(global) else {
+                return err(format!("assignment to unknown global `{}`", global));
+            };
+            let [value] = args else {
+                return err(format!("global store to `{}` takes one value", global));
+            };
+            let v = self.operand(value, Some(&g.ty))?;
+            return Ok(CallCode::Stmt(format!("{} = {}", global_name(global), v)));
+        }
+
+        match name {
+            "__aelys_str_concat" => {
+                let [a, b] = args else {
+                    return err("string concatenation takes two operands");
+                };
+                let a = self.to_str_operand(a)?;
+                let b = self.to_str_operand(b)?;
+                Ok(CallCode::Value(format!("aelysrt_str_concat({}, {})", a, b)))
+            }
+            "__aelys_to_string" | "__aelys_method_to_string" => {
+                let [a] = args else {
+                    return err("to_string takes one operand");
+                };
+                self.to_str_operand(a).map(CallCode::Value)
+            }
+            "__aelys_len" | "__aelys_method_len" => {
+                let [a] = args else {
+                    return err("len takes one operand");
+                };
+                let x = self.operand(a, None)?;
+                match self.operand_ty(a)? {
+                    Some(AirType::Str) => Ok(CallCode::Value(format!("aelysrt_str_len({})", x))),
+                    Some(AirType::Slice(_)) => Ok(CallCode::Value(format!("({}).len", x))),
+                    Some(AirType::Array(_, n)) => Ok(CallCode::Value(format!("INT64_C({})", n))),
+                    other => err(format!("len() of {}", describe(other.as_ref()))),
+                }
+            }
+            "__aelys_index" => {
+                let [base, index] = args else {
+                    return err("indexing takes a base and an index");
+                };
+                let b = self.operand(base, None)?;
+                let i = self.operand(index, Some(&AirType::I64))?;
+                match self.operand_ty(base)? {
+                    Some(AirType::Str) => {
+                        Ok(CallCode::Value(format!("aelysrt_str_index({}, {})", b, i)))
+                    }
+                    Some(ty) => Ok(CallCode::Value(self.index_access(&b, &ty, &i)?.0)),
+                    None => err("indexing null"),
+                }
+            }
+            "__aelys_index_set" => {
+                let [base, index, value] = args else {
+                    return err("index assignment takes a base, an index and a value");
+                };
+                let b = self.operand(base, None)?;
+                let i = self.operand(index, Some(&AirType::I64))?;
+                let Some(base_ty) = self.operand_ty(base)? else {
+                    return err("indexing null");
+                };
+                if base_ty == AirType::Str {
+                    return err("strings are immutable");
+                }
+                let (slot, el_ty) = self.index_access(&b, &base_ty, &i)?;
+                let v = self.operand(value, Some(&el_ty))?;
+                Ok(CallCode::Stmt(format!("{} = {}", slot, v)))
+            }
+            "__aelys_array_new" | "__aelys_vec_new" => {
+                let el_ty = match expected {
+                    Some(AirType::Slice(el)) => (**el).clone(),
+                    _ => match args.first() {
+                        Some(first) => self.operand_ty(first)?.unwrap_or(AirType::I64),
+                        None => AirType::I64,
+                    },
+                };
+                let el = c_type(&el_ty)?;
+                if args.is_empty() {
+                    return Ok(CallCode::Value(format!(
+                        "aelysrt_slice_new(sizeof({}), 0, NULL)",
+                        el
+                    )));
+                }
+                let mut items = Vec::with_capacity(args.len());
+                for arg in args {
+                    items.push(self.operand(arg, Some(&el_ty))?);
+                }
+                Ok(CallCode::Value(format!(
+                    "aelysrt_slice_new(sizeof({}), {}, ({}[]){{ {} }})",
+                    el,
+                    args.len(),
+                    el,
+                    items.join(", ")
+                )))
+            }
+            "__aelys_array_sized" => {
+                let [size] = args else {
+                    return err("sized array takes one operand");
+                };
+                let el_ty = match expected {
+                    Some(AirType::Slice(el)) => (**el).clone(),
+                    _ => AirType::I64,
+                };
+                Ok(CallCode::Value(format!(
+                    "aelysrt_slice_sized(sizeof({}), {})",
+                    c_type(&el_ty)?,
+                    self.operand(size, Some(&AirType::I64))?
+                )))
+            }
+            "print" | "println" | "io.print" | "io.println" => {
+                let func = if name.ends_with("println") {
+                    "aelysrt_println"
+                } else {
+                    "aelysrt_print"
+                };
+                match args {
+                    [] => Ok(CallCode::Stmt(format!("{}(\"\")", func))),
+                    [a] => Ok(CallCode::Stmt(format!(
+                        "{}({})",
+                        func,
+                        self.to_str_operand(a)?
+                    ))),
+                    _ => err(format!("{} takes at most one argument in C output", name)),
+                }
+            }
+            _ => {
+                let bare = name.strip_prefix("math.").unwrap_or(name);
+                if let Some((cname, arity)) = math_builtin(bare) {
+                    if args.len() != arity {
+                        return err(format!("`{}` takes {} argument(s)", name, arity));
+                    }
+                    let mut xs = Vec::with_capacity(arity);
+                    for arg in args {
+                        xs.push(format!(
+                            "(double){}",
+                            self.operand(arg, Some(&AirType::F64))?
+                        ));
+                    }
+                    return Ok(CallCode::Value(format!("{}({})", cname, xs.join(", "))));
+                }
+                if let Some(method) = name.strip_prefix("__aelys_method_") {
+                    return err(format!("method `{}` is not available in C output", method));
+                }
+                err(format!("`{}` is not available in C output", name))
+            }
+        }
+    }
+
+    fn global_get(&self, name: &str) -> CResult<String> {
+        if let Some(g) = self.g.globals.get(name) {
+            if g.init.is_none() {
+                return err(format!(
+                    "global `{}` has no constant initializer and cannot be emitted as C",
+                    name
+                ));
+            }
+            return Ok(global_name(name));
+        }
+        if let Some(func) = self.g.functions.get(name) {
+            return Ok(fn_name(func));
+        }
+        err(format!("unknown global `{}`", name))
+    }
+
+    fn to_str_operand(&self, op: &Operand) -> CResult<String> {
+        let ty = self.operand_ty(op)?;
+        let x = self.operand(op, ty.as_ref())?;
+        self.g.to_str(&x, ty.as_ref())
+    }
+}
+
+fn describe(ty: Option<&AirType>) -> String {
+    match ty {
+        Some(ty) => fmt_type(ty),
+        None => "null".to_string(),
+    }
+}
+
+impl CGen<'_> {
+    fn const_expr(&self, c: &AirConst, expected: Option<&AirType>) -> CResult<String> {
+        Ok(match c {
+            AirConst::IntLiteral(v) => match expected {
+                Some(t) if is_float(t) => c_float_literal(*v as f64, AirFloatSize::F64),
+                Some(t) => c_int_literal(*v, t),
+                None => c_int_literal(*v, &AirType::I64),
+            },
+            AirConst::Int(v, size) => c_int_literal(*v, &int_size_type(*size)),
+            AirConst::Float(v, size) => c_float_literal(*v, *size),
+            AirConst::Bool(b) => b.to_string(),
+            AirConst::Str(s) => c_string_literal(s),
+            AirConst::Null => match expected {
+                Some(AirType::Str | AirType::Ptr(_) | AirType::FnPtr { .. }) | None => {
+                    "NULL".to_string()
+                }
+                Some(AirType::Void) => "0".to_string(),
+                Some(ty) => zero_value(ty)?,
+            },
+            AirConst::ZeroInit(ty) | AirConst::Undef(ty) => zero_value(ty)?,
+        })
+    }
+
+    fn field_ty(&self, struct_ty: &AirType, field: &str) -> CResult<AirType> {
+        let AirType::Struct(name) = struct_ty else {
+            return err(format!(
+                "field `{}` of non-struct type {}",
+                field,
+                fmt_type(struct_ty)
+            ));
+        };
+        let Some(def) = self.structs.get(name.as_str()) else {
+            return err(format!("unknown struct `{}`", name));
+        };
+        match def.fields.iter().find(|f| f.name == field) {
+            Some(f) => Ok(f.ty.clone()),
+            None => err(format!("struct `{}` has no field `{}`", name, field)),
+        }
+    }
+
+    fn to_str(&self, expr: &str, ty: Option<&AirType>) -> CResult<String> {
+        Ok(match ty {
+            None => "\"null\"".to_string(),
+            Some(AirType::Str) => expr.to_string(),
+            Some(t) if is_signed_int(t) => format!("aelysrt_i64_to_str((int64_t){})", expr),
+            Some(t) if is_unsigned_int(t) => format!("aelysrt_u64_to_str((uint64_t){})", expr),
+            Some(t) if is_float(t) => format!("aelysrt_f64_to_str((double){})", expr),
+            Some(AirType::Bool) => format!("aelysrt_bool_to_str({})", expr),
+            Some(other) => {
+                return err(format!(
+                    "cannot convert {} to a string in C output",
+                    fmt_type(other)
+                ));
+            }
+        })
+    }
+}
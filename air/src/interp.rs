@@ -0,0 +1,1051 @@
+// reference interpreter for AIR
+//
+// Executes an `AirProgram` as-is, block by block, so lowering and mono can be
+// checked by running programs instead of reading `--emit-air` dumps. Speed is
+// not a goal. Struct values are stored by field offset, so layouts have to be
+// computed first (`layout::compute_layouts`).
+
+use crate::print::fmt_type;
+use crate::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+const DEFAULT_STEP_LIMIT: u64 = 10_000_000;
+// same frame limit as the VM
+const DEFAULT_MAX_DEPTH: usize = 1024;
+
+#[derive(Clone)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(Rc<str>),
+    Struct(StructValue),
+    Array(Rc<RefCell<Vec<Value>>>),
+    Vec(Rc<RefCell<Vec<Value>>>),
+    Ptr(Rc<RefCell<Value>>),
+    Func(String),
+}
+
+#[derive(Clone)]
+pub struct StructValue {
+    pub name: String,
+    // (offset, value), in declaration order
+    pub slots: Vec<(u32, Value)>,
+}
+
+impl StructValue {
+    fn slot(&self, offset: u32) -> Option<&Value> {
+        self.slots
+            .iter()
+            .find(|(o, _)| *o == offset)
+            .map(|(_, v)| v)
+    }
+
+    fn slot_mut(&mut self, offset: u32) -> Option<&mut Value> {
+        self.slots
+            .iter_mut()
+            .find(|(o, _)| *o == offset)
+            .map(|(_, v)| v)
+    }
+}
+
+// formatting follows the VM so results can be compared as strings
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(n) => {
+                if n.fract() == 0.0 {
+                    write!(f, "{}.0", n)
+                } else {
+                    write!(f, "{}", n)
+                }
+            }
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Struct(s) => {
+                write!(f, "{} {{ ", s.name)?;
+                for (i, (offset, v)) in s.slots.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "@{}: {}", offset, v)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Array(items) => write!(f, "[{}]", join(&items.borrow())),
+            Value::Vec(items) => write!(f, "Vec[{}]", join(&items.borrow())),
+            Value::Ptr(_) => write!(f, "<ptr>"),
+            Value::Func(name) => write!(f, "<function {}>", name),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{:?}", s),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
+fn join(items: &[Value]) -> String {
+    items
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone)]
+pub struct InterpError {
+    pub message: String,
+    pub function: Option<String>,
+}
+
+impl fmt::Display for InterpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(func) => write!(f, "in function `{}`: {}", func, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for InterpError {}
+
+type IResult<T> = Result<T, InterpError>;
+
+fn err<T>(message: impl Into<String>) -> IResult<T> {
+    Err(InterpError {
+        message: message.into(),
+        function: None,
+    })
+}
+
+pub fn run_function(program: &AirProgram, name: &str, args: Vec<Value>) -> IResult<Value> {
+    Interpreter::new(program).call(name, args)
+}
+
+pub struct Interpreter<'a> {
+    functions: HashMap<&'a str, &'a AirFunction>,
+    functions_by_id: HashMap<FunctionId, &'a AirFunction>,
+    structs: HashMap<&'a str, &'a AirStructDef>,
+    globals: HashMap<String, Value>,
+    output: String,
+    echo: bool,
+    steps: u64,
+    step_limit: u64,
+    max_depth: usize,
+}
+
+struct Frame<'a> {
+    func: &'a AirFunction,
+    blocks: HashMap<BlockId, &'a AirBlock>,
+    block: &'a AirBlock,
+    next_stmt: usize,
+    locals: HashMap<LocalId, Rc<RefCell<Value>>>,
+    local_types: HashMap<LocalId, &'a AirType>,
+    // set while this frame waits on a callee
+    pending: Option<Pending<'a>>,
+}
+
+struct Pending<'a> {
+    dest: Option<&'a Place>,
+    // only for `invoke`, which continues in another block
+    then: Option<BlockId>,
+}
+
+enum Resolved<'a> {
+    Value(Value),
+    Function(&'a AirFunction, Vec<Value>),
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(program: &'a AirProgram) -> Self {
+        let globals = program
+            .globals
+            .iter()
+            .map(|g| {
+                let value = match &g.init {
+                    Some(c) => const_value(c),
+                    None => Value::Null,
+                };
+                (g.name.clone(), value)
+            })
+            .collect();
+
+        Self {
+            functions: program
+                .functions
+                .iter()
+                .map(|f| (f.name.as_str(), f))
+                .collect(),
+            functions_by_id: program.functions.iter().map(|f| (f.id, f)).collect(),
+            structs: program
+                .structs
+                .iter()
+                .map(|s| (s.name.as_str(), s))
+                .collect(),
+            globals,
+            output: String::new(),
+            echo: false,
+            steps: 0,
+            step_limit: DEFAULT_STEP_LIMIT,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    pub fn with_step_limit(mut self, limit: u64) -> Self {
+        self.step_limit = limit;
+        self
+    }
+
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    // also write print/println output to the real stdout
+    pub fn with_echo(mut self, echo: bool) -> Self {
+        self.echo = echo;
+        self
+    }
+
+    // everything printed so far
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name)
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> IResult<Value> {
+        let Some(func) = self.functions.get(name).copied() else {
+            return err(format!("no function named `{}`", name));
+        };
+        let mut stack = vec![self.enter(func, args, 0)?];
+        self.run(&mut stack).map_err(|mut e| {
+            if let Some(top) = stack.last() {
+                e.function.get_or_insert_with(|| top.func.name.clone());
+            }
+            e
+        })
+    }
+
+    fn enter(&self, func: &'a AirFunction, args: Vec<Value>, depth: usize) -> IResult<Frame<'a>> {
+        if func.is_extern {
+            return err(format!("cannot call extern function `{}`", func.name));
+        }
+        if !func.type_params.is_empty() {
+            return err(format!(
+                "generic function `{}` has to be monomorphized before it runs",
+                func.name
+            ));
+        }
+        if args.len() != func.params.len() {
+            return err(format!(
+                "`{}` expects {} argument(s), got {}",
+                func.name,
+                func.params.len(),
+                args.len()
+            ));
+        }
+        if depth >= self.max_depth {
+            return err("stack overflow");
+        }
+        let Some(entry) = func.blocks.first() else {
+            return err(format!("function `{}` has no blocks", func.name));
+        };
+
+        let mut frame = Frame {
+            func,
+            blocks: func.blocks.iter().map(|b| (b.id, b)).collect(),
+            block: entry,
+            next_stmt: 0,
+            locals: HashMap::new(),
+            local_types: HashMap::new(),
+            pending: None,
+        };
+        for local in &func.locals {
+            frame.local_types.insert(local.id, &local.ty);
+            let zero = self.zero_value(&local.ty)?;
+            frame.locals.insert(local.id, Rc::new(RefCell::new(zero)));
+        }
+        for (param, arg) in func.params.iter().zip(args) {
+            frame.local_types.insert(param.id, &param.ty);
+            frame.locals.insert(param.id, Rc::new(RefCell::new(arg)));
+        }
+        Ok(frame)
+    }
+
+    // calls push a frame instead of recursing, so deep AIR recursion doesn't
+    // take the Rust stack with it
+    fn run(&mut self, stack: &mut Vec<Frame<'a>>) -> IResult<Value> {
+        loop {
+            let depth = stack.len();
+            let frame = stack
+                .last_mut()
+                .expect("interpreter stack is never empty here");
+
+            if let Some(stmt) = frame.block.stmts.get(frame.next_stmt) {
+                frame.next_stmt += 1;
+                self.tick()?;
+                if let Some(callee) = self.exec_stmt(frame, &stmt.kind, depth)? {
+                    stack.push(callee);
+                }
+                continue;
+            }
+
+            self.tick()?;
+            let next = match &frame.block.terminator {
+                AirTerminator::Return(op) => {
+                    let value = match op {
+                        Some(op) => self.operand(frame, op)?,
+                        None => Value::Null,
+                    };
+                    stack.pop();
+                    let Some(caller) = stack.last_mut() else {
+                        return Ok(value);
+                    };
+                    let Some(pending) = caller.pending.take() else {
+                        return err("returned into a frame that wasn't calling");
+                    };
+                    if let Some(dest) = pending.dest {
+                        self.store(caller, dest, value)?;
+                    }
+                    if let Some(target) = pending.then {
+                        self.jump(caller, target)?;
+                    }
+                    continue;
+                }
+                AirTerminator::Goto(target) => *target,
+                AirTerminator::Branch {
+                    cond,
+                    then_block,
+                    else_block,
+                } => match self.operand(frame, cond)? {
+                    Value::Bool(true) => *then_block,
+                    Value::Bool(false) => *else_block,
+                    other => return err(format!("branch on non-bool value {:?}", other)),
+                },
+                AirTerminator::Switch {
+                    discr,
+                    targets,
+                    default,
+                } => {
+                    let d = self.operand(frame, discr)?;
+                    targets
+                        .iter()
+                        .find(|(c, _)| values_equal(&d, &const_value(c)))
+                        .map(|(_, t)| *t)
+                        .unwrap_or(*default)
+                }
+                AirTerminator::Invoke {
+                    func: callee,
+                    args,
+                    ret,
+                    normal,
+                    ..
+                } => {
+                    let dest_ty = self.place_type(frame, ret);
+                    match self.resolve_call(frame, callee, args, dest_ty.as_ref())? {
+                        Resolved::Value(value) => {
+                            self.store(frame, ret, value)?;
+                            *normal
+                        }
+                        Resolved::Function(func, args) => {
+                            frame.pending = Some(Pending {
+                                dest: Some(ret),
+                                then: Some(*normal),
+                            });
+                            let callee = self.enter(func, args, depth)?;
+                            stack.push(callee);
+                            continue;
+                        }
+                    }
+                }
+                AirTerminator::Unwind => return err("unwind"),
+                AirTerminator::Unreachable => return err("entered unreachable code"),
+                AirTerminator::Panic { message, .. } => return err(message.clone()),
+            };
+            self.jump(frame, next)?;
+        }
+    }
+
+    fn jump(&mut self, frame: &mut Frame<'a>, target: BlockId) -> IResult<()> {
+        let Some(block) = frame.blocks.get(&target).copied() else {
+            return err(format!("jump to missing block{}", target.0));
+        };
+        let from = frame.block.id;
+        frame.block = block;
+        frame.next_stmt = 0;
+
+        // phis at the top of the block read their inputs all at once
+        let mut incoming = Vec::new();
+        for stmt in &block.stmts {
+            let AirStmtKind::Assign {
+                place,
+                rvalue: Rvalue::Phi(inputs),
+            } = &stmt.kind
+            else {
+                break;
+            };
+            let Some((_, op)) = inputs.iter().find(|(pred, _)| *pred == from) else {
+                return err(format!(
+                    "phi in block{} has no input for block{}",
+                    target.0, from.0
+                ));
+            };
+            incoming.push((place, self.operand(frame, op)?));
+        }
+        frame.next_stmt = incoming.len();
+        for (place, value) in incoming {
+            self.store(frame, place, value)?;
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) -> IResult<()> {
+        self.steps += 1;
+        if self.steps > self.step_limit {
+            return err(format!("step limit of {} exceeded", self.step_limit));
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // Statements
+    // ========================================================================
+
+    // returns the callee frame when the statement calls an AIR function
+    fn exec_stmt(
+        &mut self,
+        frame: &mut Frame<'a>,
+        stmt: &'a AirStmtKind,
+        depth: usize,
+    ) -> IResult<Option<Frame<'a>>> {
+        let (dest, func, args) = match stmt {
+            AirStmtKind::Assign {
+                place,
+                rvalue: Rvalue::Call { func, args },
+            } => (Some(place), func, args),
+            AirStmtKind::CallVoid { func, args } => (None, func, args),
+            AirStmtKind::Assign { place, rvalue } => {
+                let value = self.rvalue(frame, rvalue)?;
+                self.store(frame, place, value)?;
+                return Ok(None);
+            }
+            AirStmtKind::GcAlloc { local, ty, .. } | AirStmtKind::Alloc { local, ty } => {
+                let boxed = Value::Ptr(Rc::new(RefCell::new(self.zero_value(ty)?)));
+                self.store(frame, &Place::Local(*local), boxed)?;
+                return Ok(None);
+            }
+            // memory is reference counted here, releasing is a no-op
+            AirStmtKind::GcDrop(_)
+            | AirStmtKind::Free(_)
+            | AirStmtKind::ArenaCreate(_)
+            | AirStmtKind::ArenaDestroy(_)
+            | AirStmtKind::MemoryFence(_) => return Ok(None),
+        };
+
+        let dest_ty = dest.and_then(|place| self.place_type(frame, place));
+        match self.resolve_call(frame, func, args, dest_ty.as_ref())? {
+            Resolved::Value(value) => {
+                if let Some(place) = dest {
+                    self.store(frame, place, value)?;
+                }
+                Ok(None)
+            }
+            Resolved::Function(func, args) => {
+                frame.pending = Some(Pending { dest, then: None });
+                self.enter(func, args, depth).map(Some)
+            }
+        }
+    }
+
+    fn local_cell(&self, frame: &Frame<'a>, id: LocalId) -> IResult<Rc<RefCell<Value>>> {
+        match frame.locals.get(&id) {
+            Some(cell) => Ok(cell.clone()),
+            None => err(format!("use of undeclared local %{}", id.0)),
+        }
+    }
+
+    fn place_type(&self, frame: &Frame<'a>, place: &Place) -> Option<AirType> {
+        let base = *frame.local_types.get(&place_local(place))?;
+        match place {
+            Place::Local(_) => Some(base.clone()),
+            Place::Field(_, field) => {
+                let def = match base {
+                    AirType::Struct(name) => self.structs.get(name.as_str())?,
+                    AirType::Ptr(inner) => match &**inner {
+                        AirType::Struct(name) => self.structs.get(name.as_str())?,
+                        _ => return None,
+                    },
+                    _ => return None,
+                };
+                def.fields
+                    .iter()
+                    .find(|f| &f.name == field)
+                    .map(|f| f.ty.clone())
+            }
+            Place::Deref(_) | Place::Index(..) => match base {
+                AirType::Ptr(inner) | AirType::Slice(inner) | AirType::Array(inner, _) => {
+                    Some((**inner).clone())
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn store(&mut self, frame: &Frame<'a>, place: &Place, value: Value) -> IResult<()> {
+        match place {
+            Place::Local(id) => {
+                *self.local_cell(frame, *id)?.borrow_mut() = value;
+                Ok(())
+            }
+            Place::Field(id, field) => {
+                let cell = self.local_cell(frame, *id)?;
+                let target = match &*cell.borrow() {
+                    Value::Ptr(inner) => Some(inner.clone()),
+                    _ => None,
+                };
+                let target = target.unwrap_or(cell);
+                let mut slot = target.borrow_mut();
+                let Value::Struct(s) = &mut *slot else {
+                    return err(format!("field store `.{}` into a non-struct", field));
+                };
+                let offset = self.field_offset(&s.name, field)?;
+                match s.slot_mut(offset) {
+                    Some(v) => {
+                        *v = value;
+                        Ok(())
+                    }
+                    None => err(format!(
+                        "struct `{}` has no slot at offset {}",
+                        s.name, offset
+                    )),
+                }
+            }
+            Place::Deref(id) => match &*self.local_cell(frame, *id)?.borrow() {
+                Value::Ptr(target) => {
+                    *target.borrow_mut() = value;
+                    Ok(())
+                }
+                other => err(format!("store through non-pointer {:?}", other)),
+            },
+            Place::Index(id, index) => {
+                let idx = self.operand(frame, index)?;
+                let base = self.local_cell(frame, *id)?.borrow().clone();
+                index_set(&base, &idx, value)
+            }
+        }
+    }
+
+    fn operand(&self, frame: &Frame<'a>, op: &Operand) -> IResult<Value> {
+        match op {
+            Operand::Copy(id) | Operand::Move(id) => {
+                Ok(self.local_cell(frame, *id)?.borrow().clone())
+            }
+            Operand::Const(c) => match c {
+                AirConst::ZeroInit(ty) | AirConst::Undef(ty) => self.zero_value(ty),
+                other => Ok(const_value(other)),
+            },
+        }
+    }
+
+    // ========================================================================
+    // Rvalues
+    // ========================================================================
+
+    fn rvalue(&mut self, frame: &Frame<'a>, rvalue: &Rvalue) -> IResult<Value> {
+        match rvalue {
+            Rvalue::Use(op) => self.operand(frame, op),
+            Rvalue::BinaryOp(op, a, b) => {
+                let x = self.operand(frame, a)?;
+                let y = self.operand(frame, b)?;
+                binary(op, &x, &y)
+            }
+            Rvalue::UnaryOp(op, a) => match (op, self.operand(frame, a)?) {
+                (UnOp::Neg, Value::Int(n)) => Ok(Value::Int(n.wrapping_neg())),
+                (UnOp::Neg, Value::Float(n)) => Ok(Value::Float(-n)),
+                (UnOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                (UnOp::BitNot, Value::Int(n)) => Ok(Value::Int(!n)),
+                (_, other) => err(format!("invalid unary operand {:?}", other)),
+            },
+            Rvalue::Call { .. } => unreachable!("calls are dispatched by exec_stmt"),
+            Rvalue::Phi(_) => err("phi outside the top of a block"),
+            Rvalue::StructInit { name, fields } => {
+                let Some(def) = self.structs.get(name.as_str()).copied() else {
+                    return err(format!("unknown struct `{}`", name));
+                };
+                let mut value = self.zero_struct(def)?;
+                for (field, op) in fields {
+                    let offset = self.field_offset(name, field)?;
+                    let v = self.operand(frame, op)?;
+                    if let Some(slot) = value.slot_mut(offset) {
+                        *slot = v;
+                    }
+                }
+                Ok(Value::Struct(value))
+            }
+            Rvalue::FieldAccess { base, field } => {
+                let base = match self.operand(frame, base)? {
+                    Value::Ptr(target) => target.borrow().clone(),
+                    other => other,
+                };
+                let Value::Struct(s) = base else {
+                    return err(format!("field access `.{}` on {:?}", field, base));
+                };
+                let offset = self.field_offset(&s.name, field)?;
+                match s.slot(offset) {
+                    Some(v) => Ok(v.clone()),
+                    None => err(format!(
+                        "struct `{}` has no slot at offset {}",
+                        s.name, offset
+                    )),
+                }
+            }
+            Rvalue::AddressOf(id) => Ok(Value::Ptr(self.local_cell(frame, *id)?)),
+            Rvalue::Deref(op) => match self.operand(frame, op)? {
+                Value::Ptr(target) => Ok(target.borrow().clone()),
+                other => err(format!("dereference of non-pointer {:?}", other)),
+            },
+            Rvalue::Cast { operand, to, .. } => cast(self.operand(frame, operand)?, to),
+            Rvalue::Discriminant(_) => err("discriminant is not supported by the interpreter"),
+        }
+    }
+
+    fn field_offset(&self, struct_name: &str, field: &str) -> IResult<u32> {
+        let Some(def) = self.structs.get(struct_name) else {
+            return err(format!("unknown struct `{}`", struct_name));
+        };
+        let Some(f) = def.fields.iter().find(|f| f.name == field) else {
+            return err(format!("struct `{}` has no field `{}`", struct_name, field));
+        };
+        match f.offset {
+            Some(offset) => Ok(offset),
+            None => err(format!(
+                "struct `{}` has no layout; run layout::compute_layouts first",
+                struct_name
+            )),
+        }
+    }
+
+    fn zero_struct(&self, def: &AirStructDef) -> IResult<StructValue> {
+        let mut slots = Vec::with_capacity(def.fields.len());
+        for field in &def.fields {
+            let Some(offset) = field.offset else {
+                return err(format!(
+                    "struct `{}` has no layout; run layout::compute_layouts first",
+                    def.name
+                ));
+            };
+            slots.push((offset, self.zero_value(&field.ty)?));
+        }
+        Ok(StructValue {
+            name: def.name.clone(),
+            slots,
+        })
+    }
+
+    fn zero_value(&self, ty: &AirType) -> IResult<Value> {
+        Ok(match ty {
+            AirType::I8
+            | AirType::I16
+            | AirType::I32
+            | AirType::I64
+            | AirType::U8
+            | AirType::U16
+            | AirType::U32
+            | AirType::U64 => Value::Int(0),
+            AirType::F32 | AirType::F64 => Value::Float(0.0),
+            AirType::Bool => Value::Bool(false),
+            AirType::Str => Value::Str(Rc::from("")),
+            AirType::Struct(name) => match self.structs.get(name.as_str()) {
+                Some(def) => Value::Struct(self.zero_struct(def)?),
+                None => return err(format!("unknown struct `{}`", name)),
+            },
+            AirType::Array(inner, n) => {
+                let zero = self.zero_value(inner)?;
+                Value::Array(Rc::new(RefCell::new(vec![zero; *n as usize])))
+            }
+            AirType::Ptr(_)
+            | AirType::Slice(_)
+            | AirType::FnPtr { .. }
+            | AirType::Param(_)
+            | AirType::GenericStruct { .. }
+            | AirType::Void => Value::Null,
+        })
+    }
+
+    // ========================================================================
+    // Calls
+    // ========================================================================
+
+    // builtins run right away, AIR functions come back to be pushed
+    fn resolve_call(
+        &mut self,
+        frame: &Frame<'a>,
+        callee: &Callee,
+        args: &[Operand],
+        dest_ty: Option<&AirType>,
+    ) -> IResult<Resolved<'a>> {
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.operand(frame, arg)?);
+        }
+        let func = match callee {
+            Callee::Direct(id) => match self.functions_by_id.get(id).copied() {
+                Some(func) => func,
+                None => return err(format!("call to unknown function #{}", id.0)),
+            },
+            Callee::Extern(name, _) => {
+                return err(format!("cannot call extern function `{}`", name));
+            }
+            Callee::FnPtr(id) => match &*self.local_cell(frame, *id)?.borrow() {
+                Value::Func(name) => match self.functions.get(name.as_str()).copied() {
+                    Some(func) => func,
+                    None => return err(format!("no function named `{}`", name)),
+                },
+                other => return err(format!("call through non-function value {:?}", other)),
+            },
+            Callee::Named(name) => match self.functions.get(name.as_str()).copied() {
+                Some(func) => func,
+                None => return self.builtin(name, values, dest_ty).map(Resolved::Value),
+            },
+        };
+        Ok(Resolved::Function(func, values))
+    }
+
+    fn builtin(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        dest_ty: Option<&AirType>,
+    ) -> IResult<Value> {
+        if let Some(global) = name.strip_prefix("__aelys_global_get_") {
+            if let Some(v) = self.globals.get(global) {
+                return Ok(v.clone());
+            }
+            if self.functions.contains_key(global) {
+                return Ok(Value::Func(global.to_string()));
+            }
+            return err(format!("unknown global `{}`", global));
+        }
+        if let Some(global) = name.strip_prefix("__aelys_global_set_") {
+            let value = args.into_iter().next().unwrap_or(Value::Null);
+            self.globals.insert(global.to_string(), value);
+            return Ok(Value::Null);
+        }
+
+        match (name, args.as_slice()) {
+            ("__aelys_str_concat", [a, b]) => Ok(Value::Str(Rc::from(format!("{}{}", a, b)))),
+            ("__aelys_to_string" | "__aelys_method_to_string", [a]) => {
+                Ok(Value::Str(Rc::from(a.to_string())))
+            }
+            ("__aelys_len" | "__aelys_method_len", [a]) => match a {
+                Value::Str(s) => Ok(Value::Int(s.len() as i64)),
+                Value::Array(items) | Value::Vec(items) => {
+                    Ok(Value::Int(items.borrow().len() as i64))
+                }
+                other => err(format!("len() of {:?}", other)),
+            },
+            ("__aelys_method_is_empty", [a]) => match a {
+                Value::Str(s) => Ok(Value::Bool(s.is_empty())),
+                Value::Array(items) | Value::Vec(items) => {
+                    Ok(Value::Bool(items.borrow().is_empty()))
+                }
+                other => err(format!("is_empty() of {:?}", other)),
+            },
+            ("__aelys_index", [base, idx]) => index_get(base, idx),
+            ("__aelys_index_set", [base, idx, value]) => {
+                index_set(base, idx, value.clone())?;
+                Ok(Value::Null)
+            }
+            ("__aelys_array_new", items) => Ok(Value::Array(Rc::new(RefCell::new(items.to_vec())))),
+            ("__aelys_vec_new", items) => Ok(Value::Vec(Rc::new(RefCell::new(items.to_vec())))),
+            ("__aelys_array_sized", [Value::Int(n)]) => {
+                if *n < 0 {
+                    return err("negative array size");
+                }
+                let zero = match dest_ty {
+                    Some(AirType::Slice(el)) => self.zero_value(el)?,
+                    _ => Value::Int(0),
+                };
+                Ok(Value::Array(Rc::new(RefCell::new(vec![zero; *n as usize]))))
+            }
+            ("__aelys_method_push", [Value::Vec(items), value]) => {
+                items.borrow_mut().push(value.clone());
+                Ok(Value::Null)
+            }
+            ("__aelys_method_pop", [Value::Vec(items)]) => {
+                Ok(items.borrow_mut().pop().unwrap_or(Value::Null))
+            }
+            ("print" | "io.print", [a]) => {
+                self.write_output(&a.to_string());
+                Ok(Value::Null)
+            }
+            ("println" | "io.println", [a]) => {
+                self.write_output(&format!("{}\n", a));
+                Ok(Value::Null)
+            }
+            ("println" | "io.println", []) => {
+                self.write_output("\n");
+                Ok(Value::Null)
+            }
+            _ => {
+                let bare = name.strip_prefix("math.").unwrap_or(name);
+                if let Some(v) = math(bare, &args)? {
+                    return Ok(v);
+                }
+                err(format!(
+                    "`{}` with {} argument(s) is not supported by the interpreter",
+                    name,
+                    args.len()
+                ))
+            }
+        }
+    }
+
+    fn write_output(&mut self, text: &str) {
+        if self.echo {
+            print!("{}", text);
+        }
+        self.output.push_str(text);
+    }
+}
+
+fn place_local(place: &Place) -> LocalId {
+    match place {
+        Place::Local(id) | Place::Field(id, _) | Place::Deref(id) | Place::Index(id, _) => *id,
+    }
+}
+
+fn const_value(c: &AirConst) -> Value {
+    match c {
+        AirConst::IntLiteral(v) | AirConst::Int(v, _) => Value::Int(*v),
+        AirConst::Float(v, _) => Value::Float(*v),
+        AirConst::Bool(b) => Value::Bool(*b),
+        AirConst::Str(s) => Value::Str(Rc::from(s.as_str())),
+        AirConst::Null | AirConst::ZeroInit(_) | AirConst::Undef(_) => Value::Null,
+    }
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Null, Value::Null) => true,
+        (Value::Int(x), Value::Int(y)) => x == y,
+        (Value::Float(x), Value::Float(y)) => x == y,
+        (Value::Int(x), Value::Float(y)) | (Value::Float(y), Value::Int(x)) => (*x as f64) == *y,
+        (Value::Bool(x), Value::Bool(y)) => x == y,
+        (Value::Str(x), Value::Str(y)) => x == y,
+        (Value::Array(x), Value::Array(y)) | (Value::Vec(x), Value::Vec(y)) => Rc::ptr_eq(x, y),
+        (Value::Ptr(x), Value::Ptr(y)) => Rc::ptr_eq(x, y),
+        (Value::Func(x), Value::Func(y)) => x == y,
+        (Value::Struct(x), Value::Struct(y)) => {
+            x.name == y.name
+                && x.slots.len() == y.slots.len()
+                && x.slots
+                    .iter()
+                    .zip(&y.slots)
+                    .all(|((_, a), (_, b))| values_equal(a, b))
+        }
+        _ => false,
+    }
+}
+
+fn binary(op: &BinOp, x: &Value, y: &Value) -> IResult<Value> {
+    use Value::{Bool, Float, Int, Str};
+
+    match op {
+        BinOp::Eq => return Ok(Bool(values_equal(x, y))),
+        BinOp::Ne => return Ok(Bool(!values_equal(x, y))),
+        _ => {}
+    }
+
+    Ok(match (op, x, y) {
+        (BinOp::Add, Str(a), Str(b)) => Str(Rc::from(format!("{}{}", a, b))),
+        (BinOp::Lt, Str(a), Str(b)) => Bool(a < b),
+        (BinOp::Le, Str(a), Str(b)) => Bool(a <= b),
+        (BinOp::Gt, Str(a), Str(b)) => Bool(a > b),
+        (BinOp::Ge, Str(a), Str(b)) => Bool(a >= b),
+
+        (BinOp::And, Bool(a), Bool(b)) => Bool(*a && *b),
+        (BinOp::Or, Bool(a), Bool(b)) => Bool(*a || *b),
+
+        (BinOp::Add, Int(a), Int(b)) => Int(a.wrapping_add(*b)),
+        (BinOp::Sub, Int(a), Int(b)) => Int(a.wrapping_sub(*b)),
+        (BinOp::Mul, Int(a), Int(b)) => Int(a.wrapping_mul(*b)),
+        (BinOp::Div | BinOp::Rem, Int(_), Int(0)) => return err("division by zero"),
+        (BinOp::Div, Int(a), Int(b)) => Int(a.wrapping_div(*b)),
+        (BinOp::Rem, Int(a), Int(b)) => Int(a.wrapping_rem(*b)),
+        (BinOp::CheckedAdd, Int(a), Int(b)) => Int(checked(a.checked_add(*b))?),
+        (BinOp::CheckedSub, Int(a), Int(b)) => Int(checked(a.checked_sub(*b))?),
+        (BinOp::CheckedMul, Int(a), Int(b)) => Int(checked(a.checked_mul(*b))?),
+        (BinOp::Lt, Int(a), Int(b)) => Bool(a < b),
+        (BinOp::Le, Int(a), Int(b)) => Bool(a <= b),
+        (BinOp::Gt, Int(a), Int(b)) => Bool(a > b),
+        (BinOp::Ge, Int(a), Int(b)) => Bool(a >= b),
+        (BinOp::BitAnd, Int(a), Int(b)) => Int(a & b),
+        (BinOp::BitOr, Int(a), Int(b)) => Int(a | b),
+        (BinOp::BitXor, Int(a), Int(b)) => Int(a ^ b),
+        (BinOp::Shl, Int(a), Int(b)) => Int(a.wrapping_shl(*b as u32)),
+        (BinOp::Shr, Int(a), Int(b)) => Int(a.wrapping_shr(*b as u32)),
+
+        (_, Int(_) | Float(_), Int(_) | Float(_)) => {
+            let a = as_f64(x);
+            let b = as_f64(y);
+            match op {
+                BinOp::Add | BinOp::CheckedAdd => Float(a + b),
+                BinOp::Sub | BinOp::CheckedSub => Float(a - b),
+                BinOp::Mul | BinOp::CheckedMul => Float(a * b),
+                BinOp::Div => Float(a / b),
+                BinOp::Rem => Float(a % b),
+                BinOp::Lt => Bool(a < b),
+                BinOp::Le => Bool(a <= b),
+                BinOp::Gt => Bool(a > b),
+                BinOp::Ge => Bool(a >= b),
+                _ => return err(format!("invalid operands {:?} and {:?}", x, y)),
+            }
+        }
+
+        _ => return err(format!("invalid operands {:?} and {:?}", x, y)),
+    })
+}
+
+fn checked(v: Option<i64>) -> IResult<i64> {
+    match v {
+        Some(v) => Ok(v),
+        None => err("integer overflow"),
+    }
+}
+
+fn as_f64(v: &Value) -> f64 {
+    match v {
+        Value::Int(n) => *n as f64,
+        Value::Float(n) => *n,
+        _ => f64::NAN,
+    }
+}
+
+fn cast(value: Value, to: &AirType) -> IResult<Value> {
+    let int = |v: &Value| -> IResult<i64> {
+        match v {
+            Value::Int(n) => Ok(*n),
+            Value::Float(f) => Ok(*f as i64),
+            Value::Bool(b) => Ok(*b as i64),
+            other => err(format!("cannot cast {:?} to {}", other, fmt_type(to))),
+        }
+    };
+    Ok(match to {
+        AirType::I8 => Value::Int(int(&value)? as i8 as i64),
+        AirType::I16 => Value::Int(int(&value)? as i16 as i64),
+        AirType::I32 => Value::Int(int(&value)? as i32 as i64),
+        AirType::I64 => Value::Int(int(&value)?),
+        AirType::U8 => Value::Int(int(&value)? as u8 as i64),
+        AirType::U16 => Value::Int(int(&value)? as u16 as i64),
+        AirType::U32 => Value::Int(int(&value)? as u32 as i64),
+        AirType::U64 => Value::Int(int(&value)?),
+        AirType::F32 => Value::Float(as_f64(&value) as f32 as f64),
+        AirType::F64 => Value::Float(as_f64(&value)),
+        AirType::Bool => match value {
+            Value::Bool(b) => Value::Bool(b),
+            Value::Int(n) => Value::Bool(n != 0),
+            other => return err(format!("cannot cast {:?} to bool", other)),
+        },
+        AirType::Str => Value::Str(Rc::from(value.to_string())),
+        _ => value,
+    })
+}
+
+fn index_of(idx: &Value, len: usize) -> IResult<usize> {
+    match idx {
+        Value::Int(i) if *i >= 0 && (*i as usize) < len => Ok(*i as usize),
+        Value::Int(i) => err(format!("index out of bounds: index {}, length {}", i, len)),
+        other => err(format!("non-integer index {:?}", other)),
+    }
+}
+
+fn index_get(base: &Value, idx: &Value) -> IResult<Value> {
+    match base {
+        Value::Array(items) | Value::Vec(items) => {
+            let items = items.borrow();
+            let i = index_of(idx, items.len())?;
+            Ok(items[i].clone())
+        }
+        // strings index by character, like the VM
+        Value::Str(s) => {
+            let count = s.chars().count();
+            let i = index_of(idx, count)?;
+            let ch = s.chars().nth(i).map(String::from).unwrap_or_default();
+            Ok(Value::Str(Rc::from(ch)))
+        }
+        Value::Ptr(target) => index_get(&target.borrow(), idx),
+        other => err(format!("cannot index {:?}", other)),
+    }
+}
+
+fn index_set(base: &Value, idx: &Value, value: Value) -> IResult<()> {
+    match base {
+        Value::Array(items) | Value::Vec(items) => {
+            let mut items = items.borrow_mut();
+            let i = index_of(idx, items.len())?;
+            items[i] = value;
+            Ok(())
+        }
+        Value::Ptr(target) => index_set(&target.borrow(), idx, value),
+        other => err(format!("cannot index-assign into {:?}", other)),
+    }
+}
+
+fn math(name: &str, args: &[Value]) -> IResult<Option<Value>> {
+    let f = |i: usize| as_f64(&args[i]);
+    let unary: Option<fn(f64) -> f64> = match name {
+        "sqrt" => Some(f64::sqrt),
+        "cbrt" => Some(f64::cbrt),
+        "sin" => Some(f64::sin),
+        "cos" => Some(f64::cos),
+        "tan" => Some(f64::tan),
+        "asin" => Some(f64::asin),
+        "acos" => Some(f64::acos),
+        "atan" => Some(f64::atan),
+        "exp" => Some(f64::exp),
+        "log" => Some(f64::ln),
+        "log10" => Some(f64::log10),
+        "log2" => Some(f64::log2),
+        "floor" => Some(f64::floor),
+        "ceil" => Some(f64::ceil),
+        "round" => Some(f64::round),
+        "trunc" => Some(f64::trunc),
+        _ => None,
+    };
+    if let Some(op) = unary {
+        if args.len() != 1 {
+            return err(format!("math.{} takes 1 argument", name));
+        }
+        return Ok(Some(Value::Float(op(f(0)))));
+    }
+    Ok(match (name, args) {
+        ("abs", [Value::Int(n)]) => Some(Value::Int(n.wrapping_abs())),
+        ("abs", [_]) => Some(Value::Float(f(0).abs())),
+        ("pow", [_, _]) => Some(Value::Float(f(0).powf(f(1)))),
+        ("atan2", [_, _]) => Some(Value::Float(f(0).atan2(f(1)))),
+        ("min", [Value::Int(a), Value::Int(b)]) => Some(Value::Int(*a.min(b))),
+        ("max", [Value::Int(a), Value::Int(b)]) => Some(Value::Int(*a.max(b))),
+        ("min", [_, _]) => Some(Value::Float(f(0).min(f(1)))),
+        ("max", [_, _]) => Some(Value::Float(f(0).max(f(1)))),
+        _ => None,
+    })
+}
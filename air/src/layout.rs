@@ -17,6 +17,9 @@ pub fn layout_of(ty: &AirType) -> TypeLayout {
         AirType::Void => TypeLayout { size: 0, align: 1 },
         AirType::Slice(_) => TypeLayout { size: 16, align: 8 },
         AirType::Param(_) => TypeLayout { size: 8, align: 8 },
+        AirType::GenericStruct { name, .. } => {
+            panic!("layout_of: {name}<..> has no layout until monomorphize instantiates it")
+        }
         AirType::Array(inner, n) => {
             let el = layout_of(inner);
             TypeLayout {
@@ -44,6 +47,12 @@ pub fn compute_layouts(program: &mut AirProgram) {
     let mut resolved: HashMap<String, TypeLayout> = HashMap::new();
 
     for idx in order {
+        // templates and anything applying one get their layout per instance,
+        // once monomorphize has created them
+        let def = &program.structs[idx];
+        if !def.type_params.is_empty() || def.fields.iter().any(|f| is_generic(&f.ty)) {
+            continue;
+        }
         let (total, offsets) = struct_layout(&program.structs[idx], &resolved);
         resolved.insert(program.structs[idx].name.clone(), total);
         for (i, off) in offsets.into_iter().enumerate() {
@@ -52,6 +61,14 @@ pub fn compute_layouts(program: &mut AirProgram) {
     }
 }
 
+fn is_generic(ty: &AirType) -> bool {
+    match ty {
+        AirType::GenericStruct { .. } => true,
+        AirType::Array(inner, _) => is_generic(inner),
+        _ => false,
+    }
+}
+
 fn resolved_layout(ty: &AirType, structs: &HashMap<String, TypeLayout>) -> TypeLayout {
     match ty {
         AirType::Struct(name) => *structs
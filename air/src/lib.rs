@@ -1,9 +1,15 @@
 // AIR, the Aelys Intermediate Representation
 
+pub mod cgen;
+pub mod interp;
 pub mod layout;
 pub mod lower;
 pub mod mono;
+pub mod passes;
 pub mod print;
+mod verify;
+
+pub use verify::{AirError, verify};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LocalId(pub u32);
@@ -43,6 +49,12 @@ pub enum AirType {
     Str,
     Ptr(Box<AirType>),
     Struct(String),
+    // generic struct applied to type arguments, replaced by a concrete
+    // instance during monomorphization
+    GenericStruct {
+        name: String,
+        args: Vec<AirType>,
+    },
     Array(Box<AirType>, u64),
     Slice(Box<AirType>),
     FnPtr {
@@ -247,6 +259,8 @@ pub enum Rvalue {
         to: AirType,
     },
     Discriminant(Operand),
+    // only between passes::SsaConstruction and passes::SsaDestruction
+    Phi(Vec<(BlockId, Operand)>),
 }
 
 #[derive(Clone)]
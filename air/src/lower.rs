@@ -4,6 +4,7 @@ use aelys_sema::{
     TypedProgram, TypedStmt, TypedStmtKind,
 };
 use aelys_syntax::BinaryOp;
+use std::collections::{HashMap, HashSet};
 
 pub fn lower(program: &TypedProgram) -> AirProgram {
     let mut cx = LoweringContext::new(program);
@@ -36,7 +37,16 @@ struct LoweringContext<'a> {
     loop_stack: Vec<LoopBlocks>,
     type_params_map: Vec<(String, TypeParamId)>,
     pending_block_id: Option<BlockId>,
-    block_aliases: Vec<(u32, u32)>,
+    global_names: HashSet<String>,
+    // generic struct name -> number of type parameters
+    generic_structs: HashMap<String, usize>,
+    // nested functions that take an environment: name -> captured (name, type)
+    closure_captures: Vec<(String, Vec<(String, AirType)>)>,
+    // names the current function's nested functions capture; locals with
+    // these names live in a heap cell so both sides see every write
+    boxed_names: HashSet<String>,
+    // locals holding a pointer to such a cell instead of the value
+    boxed_locals: HashSet<LocalId>,
 }
 
 struct LoopBlocks {
@@ -64,7 +74,28 @@ impl<'a> LoweringContext<'a> {
             loop_stack: Vec::new(),
             type_params_map: Vec::new(),
             pending_block_id: None,
-            block_aliases: Vec::new(),
+            global_names: program
+                .stmts
+                .iter()
+                .filter_map(|stmt| match &stmt.kind {
+                    TypedStmtKind::Let { name, .. } => Some(name.clone()),
+                    TypedStmtKind::Function(func) => Some(func.name.clone()),
+                    _ => None,
+                })
+                .collect(),
+            generic_structs: program
+                .stmts
+                .iter()
+                .filter_map(|stmt| match &stmt.kind {
+                    TypedStmtKind::StructDecl {
+                        name, type_params, ..
+                    } if !type_params.is_empty() => Some((name.clone(), type_params.len())),
+                    _ => None,
+                })
+                .collect(),
+            closure_captures: Vec::new(),
+            boxed_names: HashSet::new(),
+            boxed_locals: HashSet::new(),
         }
     }
 
@@ -135,6 +166,53 @@ impl<'a> LoweringContext<'a> {
             .map(|(_, id)| *id)
     }
 
+    // a `let` or parameter: captured names get a fresh cell
+    fn declare_local(
+        &mut self,
+        name: &str,
+        ty: AirType,
+        is_mut: bool,
+        span: Option<Span>,
+    ) -> LocalId {
+        if !self.boxed_names.contains(name) {
+            return self.alloc_named_local(name, ty, is_mut, span);
+        }
+        let cell = self.alloc_named_local(name, AirType::Ptr(Box::new(ty.clone())), false, span);
+        self.boxed_locals.insert(cell);
+        self.emit(
+            AirStmtKind::GcAlloc {
+                local: cell,
+                ty,
+                arena: ArenaId(0),
+            },
+            span,
+        );
+        cell
+    }
+
+    fn local_place(&self, id: LocalId) -> Place {
+        if self.boxed_locals.contains(&id) {
+            Place::Deref(id)
+        } else {
+            Place::Local(id)
+        }
+    }
+
+    fn read_local(&mut self, id: LocalId, ty: AirType, span: Option<Span>) -> Operand {
+        if !self.boxed_locals.contains(&id) {
+            return Operand::Copy(id);
+        }
+        let tmp = self.alloc_temp(ty);
+        self.emit(
+            AirStmtKind::Assign {
+                place: Place::Local(tmp),
+                rvalue: Rvalue::Deref(Operand::Copy(id)),
+            },
+            span,
+        );
+        Operand::Copy(tmp)
+    }
+
     fn emit(&mut self, kind: AirStmtKind, span: Option<Span>) {
         self.current_stmts.push(AirStmt { kind, span });
     }
@@ -202,10 +280,20 @@ impl<'a> LoweringContext<'a> {
             InferType::Struct(name) => {
                 if let Some((_, id)) = self.type_params_map.iter().find(|(n, _)| n == name) {
                     AirType::Param(*id)
+                } else if let Some(&arity) = self.generic_structs.get(name) {
+                    // a bare generic struct name leaves its arguments dynamic
+                    AirType::GenericStruct {
+                        name: name.clone(),
+                        args: vec![AirType::I64; arity],
+                    }
                 } else {
                     AirType::Struct(name.clone())
                 }
             }
+            InferType::GenericStruct { name, args } => AirType::GenericStruct {
+                name: name.clone(),
+                args: args.iter().map(|a| self.lower_type_from_infer(a)).collect(),
+            },
             InferType::Var(_) | InferType::Dynamic => AirType::I64,
         }
     }
@@ -280,18 +368,36 @@ impl<'a> LoweringContext<'a> {
         let saved_blocks = std::mem::take(&mut self.current_blocks);
         let saved_stmts = std::mem::take(&mut self.current_stmts);
         let saved_names = std::mem::take(&mut self.locals_by_name);
-        let saved_aliases = std::mem::take(&mut self.block_aliases);
         let saved_pending = self.pending_block_id.take();
         let saved_next_local = self.next_local_id;
         let saved_next_block = self.next_block_id;
+        let saved_boxed_names =
+            std::mem::replace(&mut self.boxed_names, captured_by_nested(&func.body));
+        let saved_boxed_locals = std::mem::take(&mut self.boxed_locals);
         self.next_local_id = 0;
         self.next_block_id = 0;
 
         let func_id = self.alloc_function_id();
         let gc_mode = self.gc_mode_for_function(func);
 
-        if !func.captures.is_empty() {
-            self.lower_closure(func, func_id, gc_mode);
+        // globals are reached through __aelys_global_get_*, only enclosing
+        // locals need to live in the environment
+        let captures: Vec<(String, InferType)> = func
+            .captures
+            .iter()
+            .filter(|(name, _)| {
+                !self.global_names.contains(name) || saved_names.iter().any(|(n, _)| n == name)
+            })
+            .cloned()
+            .collect();
+
+        if !captures.is_empty() {
+            let fields = captures
+                .iter()
+                .map(|(name, ty)| (name.clone(), self.capture_type(ty)))
+                .collect();
+            self.closure_captures.push((func.name.clone(), fields));
+            self.lower_closure(func, &captures, func_id, gc_mode);
         } else {
             self.lower_plain_function(func, func_id, gc_mode);
         }
@@ -301,10 +407,11 @@ impl<'a> LoweringContext<'a> {
         self.current_blocks = saved_blocks;
         self.current_stmts = saved_stmts;
         self.locals_by_name = saved_names;
-        self.block_aliases = saved_aliases;
         self.pending_block_id = saved_pending;
         self.next_local_id = saved_next_local;
         self.next_block_id = saved_next_block;
+        self.boxed_names = saved_boxed_names;
+        self.boxed_locals = saved_boxed_locals;
     }
 
     fn lower_plain_function(&mut self, func: &TypedFunction, func_id: FunctionId, gc_mode: GcMode) {
@@ -312,9 +419,8 @@ impl<'a> LoweringContext<'a> {
         let params = self.lower_params(&func.params);
         let ret_ty = self.lower_type_from_infer(&func.return_type);
 
-        self.lower_body(&func.body);
+        self.lower_function_body(&func.body, &func.return_type);
         self.finalize_function_body();
-        self.resolve_block_aliases();
 
         let air_func = AirFunction {
             id: func_id,
@@ -334,16 +440,21 @@ impl<'a> LoweringContext<'a> {
         self.type_params_map.clear();
     }
 
-    fn lower_closure(&mut self, func: &TypedFunction, func_id: FunctionId, gc_mode: GcMode) {
+    fn lower_closure(
+        &mut self,
+        func: &TypedFunction,
+        captures: &[(String, InferType)],
+        func_id: FunctionId,
+        gc_mode: GcMode,
+    ) {
         let type_params = self.lower_type_params(&func.type_params);
 
         let env_name = format!("__closure_env_{}", func.name);
-        let env_fields: Vec<AirStructField> = func
-            .captures
+        let env_fields: Vec<AirStructField> = captures
             .iter()
             .map(|(name, ty)| AirStructField {
                 name: name.clone(),
-                ty: self.lower_type_from_infer(ty),
+                ty: self.capture_type(ty),
                 offset: None,
             })
             .collect();
@@ -365,9 +476,9 @@ impl<'a> LoweringContext<'a> {
             span: Some(self.span(&func.span)),
         });
 
-        for (cap_name, cap_ty) in &func.captures {
-            let local_id =
-                self.alloc_named_local(cap_name, self.lower_type_from_infer(cap_ty), false, None);
+        for (cap_name, cap_ty) in captures {
+            let local_id = self.alloc_named_local(cap_name, self.capture_type(cap_ty), false, None);
+            self.boxed_locals.insert(local_id);
             self.emit(
                 AirStmtKind::Assign {
                     place: Place::Local(local_id),
@@ -383,9 +494,8 @@ impl<'a> LoweringContext<'a> {
         let user_params = self.lower_params(&func.params);
         let ret_ty = self.lower_type_from_infer(&func.return_type);
 
-        self.lower_body(&func.body);
+        self.lower_function_body(&func.body, &func.return_type);
         self.finalize_function_body();
-        self.resolve_block_aliases();
 
         let mut all_params = vec![self.current_params.remove(0)];
         all_params.extend(user_params);
@@ -408,8 +518,13 @@ impl<'a> LoweringContext<'a> {
         self.type_params_map.clear();
     }
 
+    // captured variables are shared, the environment holds their cells
+    fn capture_type(&self, ty: &InferType) -> AirType {
+        AirType::Ptr(Box::new(self.lower_type_from_infer(ty)))
+    }
+
     fn lower_params(&mut self, params: &[TypedParam]) -> Vec<AirParam> {
-        params
+        let params: Vec<AirParam> = params
             .iter()
             .map(|p| {
                 let ty = self.lower_type_from_infer(&p.ty);
@@ -426,7 +541,21 @@ impl<'a> LoweringContext<'a> {
                     span: Some(self.span(&p.span)),
                 }
             })
-            .collect()
+            .collect();
+        // a captured parameter moves into its cell on entry
+        for param in &params {
+            if self.boxed_names.contains(&param.name) {
+                let cell = self.declare_local(&param.name, param.ty.clone(), true, param.span);
+                self.emit(
+                    AirStmtKind::Assign {
+                        place: Place::Deref(cell),
+                        rvalue: Rvalue::Use(Operand::Copy(param.id)),
+                    },
+                    param.span,
+                );
+            }
+        }
+        params
     }
 
     fn func_attribs(&self, func: &TypedFunction) -> FunctionAttribs {
@@ -503,9 +632,66 @@ impl<'a> LoweringContext<'a> {
         }
     }
 
+    // the trailing expression of a function body is its return value, same as the VM backend
+    fn lower_function_body(&mut self, stmts: &[TypedStmt], return_type: &InferType) {
+        let Some((last, rest)) = stmts.split_last() else {
+            return;
+        };
+        self.lower_body(rest);
+        if matches!(return_type, InferType::Null) {
+            self.lower_stmt(last);
+        } else {
+            self.lower_tail_stmt(last);
+        }
+    }
+
+    fn lower_tail_stmt(&mut self, stmt: &TypedStmt) {
+        match &stmt.kind {
+            TypedStmtKind::Expression(expr) => {
+                let operand = self.lower_expr(expr);
+                self.seal_block(AirTerminator::Return(Some(operand)));
+            }
+            TypedStmtKind::Block(stmts) => {
+                if let Some((last, rest)) = stmts.split_last() {
+                    self.lower_body(rest);
+                    self.lower_tail_stmt(last);
+                }
+            }
+            TypedStmtKind::If {
+                condition,
+                then_branch,
+                else_branch: Some(else_branch),
+            } => {
+                let cond = self.lower_expr(condition);
+                let then_id = self.alloc_block_id();
+                let else_id = self.alloc_block_id();
+                self.seal_block(AirTerminator::Branch {
+                    cond,
+                    then_block: then_id,
+                    else_block: else_id,
+                });
+
+                self.start_block(then_id);
+                self.lower_tail_stmt(then_branch);
+                if !self.last_block_is_terminated() {
+                    self.seal_block(AirTerminator::Return(None));
+                }
+
+                self.start_block(else_id);
+                self.lower_tail_stmt(else_branch);
+                if !self.last_block_is_terminated() {
+                    self.seal_block(AirTerminator::Return(None));
+                }
+            }
+            _ => self.lower_stmt(stmt),
+        }
+    }
+
     fn finalize_function_body(&mut self) {
+        // a pending block is a merge/exit target some branch jumps to, it must exist
         if (self.current_stmts.is_empty() && self.current_blocks.is_empty())
             || !self.current_stmts.is_empty()
+            || self.pending_block_id.is_some()
         {
             self.seal_block(AirTerminator::Return(None));
         }
@@ -525,11 +711,11 @@ impl<'a> LoweringContext<'a> {
                 ..
             } => {
                 let ty = self.lower_type_from_infer(var_type);
-                let local = self.alloc_named_local(name, ty, *mutable, sp);
+                let local = self.declare_local(name, ty, *mutable, sp);
                 let operand = self.lower_expr(initializer);
                 self.emit(
                     AirStmtKind::Assign {
-                        place: Place::Local(local),
+                        place: self.local_place(local),
                         rvalue: Rvalue::Use(operand),
                     },
                     sp,
@@ -612,21 +798,21 @@ impl<'a> LoweringContext<'a> {
             },
         });
 
+        self.start_block(then_id);
         self.lower_stmt(then_branch);
         if !self.last_block_is_terminated() {
             self.seal_block(AirTerminator::Goto(merge_id));
         }
-        self.fixup_block_id(then_id);
 
         if let Some(else_br) = else_branch {
+            self.start_block(else_id);
             self.lower_stmt(else_br);
             if !self.last_block_is_terminated() {
                 self.seal_block(AirTerminator::Goto(merge_id));
             }
-            self.fixup_block_id(else_id);
         }
 
-        self.fixup_block_id_noop(merge_id);
+        self.start_block(merge_id);
     }
 
     fn lower_while(&mut self, condition: &TypedExpr, body: &TypedStmt, _sp: Option<Span>) {
@@ -636,26 +822,26 @@ impl<'a> LoweringContext<'a> {
 
         self.seal_block(AirTerminator::Goto(header_id));
 
+        self.start_block(header_id);
         let cond = self.lower_expr(condition);
         self.seal_block(AirTerminator::Branch {
             cond,
             then_block: body_id,
             else_block: exit_id,
         });
-        self.fixup_block_id(header_id);
 
         self.loop_stack.push(LoopBlocks {
             header: header_id,
             exit: exit_id,
         });
+        self.start_block(body_id);
         self.lower_stmt(body);
         if !self.last_block_is_terminated() {
             self.seal_block(AirTerminator::Goto(header_id));
         }
-        self.fixup_block_id(body_id);
         self.loop_stack.pop();
 
-        self.fixup_block_id_noop(exit_id);
+        self.start_block(exit_id);
     }
 
     fn lower_for(
@@ -696,6 +882,7 @@ impl<'a> LoweringContext<'a> {
 
         self.seal_block(AirTerminator::Goto(header_id));
 
+        self.start_block(header_id);
         let cmp_op = if inclusive { BinOp::Le } else { BinOp::Lt };
         let cond_local = self.alloc_temp(AirType::Bool);
         self.emit(
@@ -714,19 +901,19 @@ impl<'a> LoweringContext<'a> {
             then_block: body_id,
             else_block: exit_id,
         });
-        self.fixup_block_id(header_id);
 
         self.loop_stack.push(LoopBlocks {
             header: incr_id,
             exit: exit_id,
         });
+        self.start_block(body_id);
         self.lower_stmt(body);
         if !self.last_block_is_terminated() {
             self.seal_block(AirTerminator::Goto(incr_id));
         }
-        self.fixup_block_id(body_id);
         self.loop_stack.pop();
 
+        self.start_block(incr_id);
         let step_operand = if let Some(step_expr) = step {
             self.lower_expr(step_expr)
         } else {
@@ -740,9 +927,8 @@ impl<'a> LoweringContext<'a> {
             None,
         );
         self.seal_block(AirTerminator::Goto(header_id));
-        self.fixup_block_id(incr_id);
 
-        self.fixup_block_id_noop(exit_id);
+        self.start_block(exit_id);
     }
 
     fn lower_foreach(
@@ -795,6 +981,7 @@ impl<'a> LoweringContext<'a> {
 
         self.seal_block(AirTerminator::Goto(header_id));
 
+        self.start_block(header_id);
         let cond_local = self.alloc_temp(AirType::Bool);
         self.emit(
             AirStmtKind::Assign {
@@ -812,8 +999,8 @@ impl<'a> LoweringContext<'a> {
             then_block: body_id,
             else_block: exit_id,
         });
-        self.fixup_block_id(header_id);
 
+        self.start_block(body_id);
         self.emit(
             AirStmtKind::Assign {
                 place: Place::Local(elem_local),
@@ -833,9 +1020,9 @@ impl<'a> LoweringContext<'a> {
         if !self.last_block_is_terminated() {
             self.seal_block(AirTerminator::Goto(incr_id));
         }
-        self.fixup_block_id(body_id);
         self.loop_stack.pop();
 
+        self.start_block(incr_id);
         self.emit(
             AirStmtKind::Assign {
                 place: Place::Local(idx_local),
@@ -848,88 +1035,27 @@ impl<'a> LoweringContext<'a> {
             None,
         );
         self.seal_block(AirTerminator::Goto(header_id));
-        self.fixup_block_id(incr_id);
 
-        self.fixup_block_id_noop(exit_id);
+        self.start_block(exit_id);
     }
 
     // ========================================================================
-    // Block ID fixup helpers
+    // Block ID helpers
     //
-    // We pre-allocate block IDs, then seal blocks in order.
-    // After sealing, the block gets the next sequential ID. We fix it up to
-    // the pre-allocated ID so branch targets stay valid.
+    // Branch targets are allocated up front. `start_block` marks the id the
+    // next sealed block will take, so a target always names the first block
+    // of its region even when the region itself spans several blocks.
     // ========================================================================
 
-    fn fixup_block_id(&mut self, target: BlockId) {
-        if let Some(block) = self.current_blocks.last_mut() {
-            let old_id = block.id;
-            block.id = target;
-            if old_id != target {
-                self.block_aliases.push((old_id.0, target.0));
-            }
-        }
-    }
-
-    fn fixup_block_id_noop(&mut self, target: BlockId) {
-        self.pending_block_id = Some(target);
-    }
-
-    fn resolve_block_aliases(&mut self) {
-        if self.block_aliases.is_empty() {
-            return;
-        }
-        let resolve = |id: &mut BlockId, aliases: &[(u32, u32)]| {
-            let mut current = id.0;
-            for _ in 0..aliases.len() {
-                if let Some(&(_, to)) = aliases.iter().find(|(from, _)| *from == current) {
-                    current = to;
-                } else {
-                    break;
-                }
-            }
-            *id = BlockId(current);
-        };
-        for block in &mut self.current_blocks {
-            let aliases = &self.block_aliases;
-            match &mut block.terminator {
-                AirTerminator::Goto(id) => resolve(id, aliases),
-                AirTerminator::Branch {
-                    then_block,
-                    else_block,
-                    ..
-                } => {
-                    resolve(then_block, aliases);
-                    resolve(else_block, aliases);
-                }
-                AirTerminator::Switch {
-                    targets, default, ..
-                } => {
-                    for (_, id) in targets {
-                        resolve(id, aliases);
-                    }
-                    resolve(default, aliases);
-                }
-                AirTerminator::Invoke { normal, unwind, .. } => {
-                    resolve(normal, aliases);
-                    resolve(unwind, aliases);
-                }
-                AirTerminator::Return(_)
-                | AirTerminator::Unreachable
-                | AirTerminator::Unwind
-                | AirTerminator::Panic { .. } => {}
-            }
-        }
-        self.block_aliases.clear();
+    fn start_block(&mut self, id: BlockId) {
+        self.pending_block_id = Some(id);
     }
 
+    // the open block is dead when nothing jumps to it and nothing falls into it
     fn last_block_is_terminated(&self) -> bool {
         self.current_stmts.is_empty()
-            && self.current_blocks.last().is_some_and(|b| {
-                !matches!(b.terminator, AirTerminator::Goto(_))
-                    || matches!(b.terminator, AirTerminator::Return(_))
-                    || matches!(b.terminator, AirTerminator::Unreachable)
-            })
+            && self.pending_block_id.is_none()
+            && !self.current_blocks.is_empty()
     }
 
     fn lower_expr(&mut self, expr: &TypedExpr) -> Operand {
@@ -956,7 +1082,8 @@ impl<'a> LoweringContext<'a> {
 
             TypedExprKind::Identifier(name) => {
                 if let Some(id) = self.lookup_local(name) {
-                    Operand::Copy(id)
+                    let ty = self.lower_type_from_infer(&expr.ty);
+                    self.read_local(id, ty, sp)
                 } else {
                     let tmp = self.alloc_temp(self.lower_type_from_infer(&expr.ty));
                     self.emit(
@@ -1009,9 +1136,19 @@ impl<'a> LoweringContext<'a> {
             TypedExprKind::Or { left, right } => self.lower_short_circuit(left, right, false, expr),
 
             TypedExprKind::Call { callee, args } => {
-                let lowered_args: Vec<Operand> = args.iter().map(|a| self.lower_expr(a)).collect();
-                let func = self.lower_callee(callee);
-                let result_ty = self.lower_type_from_infer(&expr.ty);
+                let (func, lowered_args) = self.lower_call_parts(callee, args, sp);
+                let result_ty = match (&func, &expr.ty) {
+                    // sema leaves method results dynamic, but these two are known
+                    (Callee::Named(name), InferType::Dynamic)
+                        if name == "__aelys_method_to_string" =>
+                    {
+                        AirType::Str
+                    }
+                    (Callee::Named(name), InferType::Dynamic) if name == "__aelys_method_len" => {
+                        AirType::I64
+                    }
+                    _ => self.lower_type_from_infer(&expr.ty),
+                };
                 let tmp = self.alloc_temp(result_ty);
                 self.emit(
                     AirStmtKind::Assign {
@@ -1031,12 +1168,16 @@ impl<'a> LoweringContext<'a> {
                 if let Some(id) = self.lookup_local(name) {
                     self.emit(
                         AirStmtKind::Assign {
-                            place: Place::Local(id),
-                            rvalue: Rvalue::Use(val),
+                            place: self.local_place(id),
+                            rvalue: Rvalue::Use(val.clone()),
                         },
                         sp,
                     );
-                    Operand::Copy(id)
+                    if self.boxed_locals.contains(&id) {
+                        val
+                    } else {
+                        Operand::Copy(id)
+                    }
                 } else {
                     self.emit(
                         AirStmtKind::CallVoid {
@@ -1066,7 +1207,7 @@ impl<'a> LoweringContext<'a> {
                 captures,
             } => self.lower_lambda(params, return_type, body, captures, expr),
 
-            TypedExprKind::FmtString(parts) => self.lower_fmt_string(parts, sp),
+            TypedExprKind::FmtString(parts) => self.lower_fmt_string(parts, &[], sp),
 
             TypedExprKind::Member { object, member } => {
                 let base = self.lower_expr(object);
@@ -1252,8 +1393,7 @@ impl<'a> LoweringContext<'a> {
         let sp = Some(self.span(&expr.span));
         match &expr.kind {
             TypedExprKind::Call { callee, args } => {
-                let lowered_args: Vec<Operand> = args.iter().map(|a| self.lower_expr(a)).collect();
-                let func = self.lower_callee(callee);
+                let (func, lowered_args) = self.lower_call_parts(callee, args, sp);
                 if matches!(expr.ty, InferType::Null) {
                     self.emit(
                         AirStmtKind::CallVoid {
@@ -1281,7 +1421,7 @@ impl<'a> LoweringContext<'a> {
                 if let Some(id) = self.lookup_local(name) {
                     self.emit(
                         AirStmtKind::Assign {
-                            place: Place::Local(id),
+                            place: self.local_place(id),
                             rvalue: Rvalue::Use(val),
                         },
                         sp,
@@ -1318,9 +1458,122 @@ impl<'a> LoweringContext<'a> {
         }
     }
 
+    // `recv.method(args)` becomes `__aelys_method_<name>(recv, args)`, module
+    // members stay `module.member`. Placeholder format strings consume the
+    // arguments right after them, like the VM backend does.
+    fn lower_call_parts(
+        &mut self,
+        callee: &TypedExpr,
+        args: &[TypedExpr],
+        sp: Option<Span>,
+    ) -> (Callee, Vec<Operand>) {
+        if let TypedExprKind::Member { object, member } = &callee.kind
+            && !self.is_module_ref(object)
+        {
+            let mut lowered = vec![self.lower_expr(object)];
+            lowered.extend(self.lower_call_args(args, sp));
+            return (Callee::Named(format!("__aelys_method_{}", member)), lowered);
+        }
+        if let TypedExprKind::Identifier(name) = &callee.kind
+            && self.lookup_local(name).is_none()
+            && let Some(env) = self.closure_env_arg(name)
+        {
+            let mut lowered = vec![env];
+            lowered.extend(self.lower_call_args(args, sp));
+            return (Callee::Named(name.clone()), lowered);
+        }
+        let lowered = self.lower_call_args(args, sp);
+        (self.lower_callee(callee), lowered)
+    }
+
+    // builds the environment a nested function closes over, from the
+    // caller's view of the captured names
+    fn closure_env_arg(&mut self, name: &str) -> Option<Operand> {
+        let fields = self
+            .closure_captures
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, fields)| fields.clone())?;
+
+        let env_name = format!("__closure_env_{}", name);
+        let mut init = Vec::with_capacity(fields.len());
+        for (field, ty) in fields {
+            let value = match self.lookup_local(&field) {
+                Some(id) if self.boxed_locals.contains(&id) => Operand::Copy(id),
+                // not declared through declare_local (a loop variable), so
+                // hand over the local itself
+                Some(id) => {
+                    let ptr = self.alloc_temp(ty);
+                    self.emit(
+                        AirStmtKind::Assign {
+                            place: Place::Local(ptr),
+                            rvalue: Rvalue::AddressOf(id),
+                        },
+                        None,
+                    );
+                    Operand::Copy(ptr)
+                }
+                None => Operand::Const(AirConst::Undef(ty)),
+            };
+            init.push((field, value));
+        }
+
+        let env = self.alloc_temp(AirType::Struct(env_name.clone()));
+        self.emit(
+            AirStmtKind::Assign {
+                place: Place::Local(env),
+                rvalue: Rvalue::StructInit {
+                    name: env_name.clone(),
+                    fields: init,
+                },
+            },
+            None,
+        );
+        let ptr = self.alloc_temp(AirType::Ptr(Box::new(AirType::Struct(env_name))));
+        self.emit(
+            AirStmtKind::Assign {
+                place: Place::Local(ptr),
+                rvalue: Rvalue::AddressOf(env),
+            },
+            None,
+        );
+        Some(Operand::Copy(ptr))
+    }
+
+    fn lower_call_args(&mut self, args: &[TypedExpr], sp: Option<Span>) -> Vec<Operand> {
+        if let Some(TypedExprKind::FmtString(parts)) = args.first().map(|a| &a.kind) {
+            let placeholders = parts
+                .iter()
+                .filter(|p| matches!(p, TypedFmtStringPart::Placeholder))
+                .count();
+            if placeholders > 0 && args.len() > placeholders {
+                let mut lowered = vec![self.lower_fmt_string(parts, &args[1..=placeholders], sp)];
+                lowered.extend(args[placeholders + 1..].iter().map(|a| self.lower_expr(a)));
+                return lowered;
+            }
+        }
+        args.iter().map(|a| self.lower_expr(a)).collect()
+    }
+
+    fn is_module_ref(&self, expr: &TypedExpr) -> bool {
+        matches!(&expr.kind, TypedExprKind::Identifier(name)
+            if self.lookup_local(name).is_none() && !self.global_names.contains(name))
+    }
+
     fn lower_callee(&mut self, callee: &TypedExpr) -> Callee {
         match &callee.kind {
-            TypedExprKind::Identifier(name) => Callee::Named(name.clone()),
+            TypedExprKind::Identifier(name) => match self.lookup_local(name) {
+                Some(id) if self.boxed_locals.contains(&id) => {
+                    let ty = self.lower_type_from_infer(&callee.ty);
+                    match self.read_local(id, ty, None) {
+                        Operand::Copy(tmp) => Callee::FnPtr(tmp),
+                        _ => unreachable!("boxed reads go through a temporary"),
+                    }
+                }
+                Some(id) => Callee::FnPtr(id),
+                None => Callee::Named(name.clone()),
+            },
             TypedExprKind::Member { object, member } => {
                 if let TypedExprKind::Identifier(mod_name) = &object.kind {
                     Callee::Named(format!("{}.{}", mod_name, member))
@@ -1399,6 +1652,7 @@ impl<'a> LoweringContext<'a> {
             });
         }
 
+        self.start_block(eval_right_id);
         let rhs = self.lower_expr(right);
         self.emit(
             AirStmtKind::Assign {
@@ -1408,9 +1662,8 @@ impl<'a> LoweringContext<'a> {
             None,
         );
         self.seal_block(AirTerminator::Goto(merge_id));
-        self.fixup_block_id(eval_right_id);
 
-        self.fixup_block_id_noop(merge_id);
+        self.start_block(merge_id);
         Operand::Copy(result)
     }
 
@@ -1439,6 +1692,7 @@ impl<'a> LoweringContext<'a> {
             else_block: else_id,
         });
 
+        self.start_block(then_id);
         let then_val = self.lower_expr(then_branch);
         self.emit(
             AirStmtKind::Assign {
@@ -1448,8 +1702,8 @@ impl<'a> LoweringContext<'a> {
             None,
         );
         self.seal_block(AirTerminator::Goto(merge_id));
-        self.fixup_block_id(then_id);
 
+        self.start_block(else_id);
         let else_val = self.lower_expr(else_branch);
         self.emit(
             AirStmtKind::Assign {
@@ -1459,9 +1713,8 @@ impl<'a> LoweringContext<'a> {
             None,
         );
         self.seal_block(AirTerminator::Goto(merge_id));
-        self.fixup_block_id(else_id);
 
-        self.fixup_block_id_noop(merge_id);
+        self.start_block(merge_id);
         Operand::Copy(result)
     }
 
@@ -1501,8 +1754,14 @@ impl<'a> LoweringContext<'a> {
     }
 
     // format string → __aelys_str_concat / __aelys_to_string
-    fn lower_fmt_string(&mut self, parts: &[TypedFmtStringPart], sp: Option<Span>) -> Operand {
+    fn lower_fmt_string(
+        &mut self,
+        parts: &[TypedFmtStringPart],
+        placeholder_args: &[TypedExpr],
+        sp: Option<Span>,
+    ) -> Operand {
         let mut operands: Vec<Operand> = Vec::new();
+        let mut placeholder_args = placeholder_args.iter();
 
         for part in parts {
             match part {
@@ -1510,27 +1769,16 @@ impl<'a> LoweringContext<'a> {
                     operands.push(Operand::Const(AirConst::Str(s.clone())));
                 }
                 TypedFmtStringPart::Expr(expr) => {
-                    let val = self.lower_expr(expr);
-                    if matches!(expr.ty, InferType::String) {
+                    let val = self.lower_fmt_part(expr);
+                    operands.push(val);
+                }
+                TypedFmtStringPart::Placeholder => match placeholder_args.next() {
+                    Some(arg) => {
+                        let val = self.lower_fmt_part(arg);
                         operands.push(val);
-                    } else {
-                        let str_tmp = self.alloc_temp(AirType::Str);
-                        self.emit(
-                            AirStmtKind::Assign {
-                                place: Place::Local(str_tmp),
-                                rvalue: Rvalue::Call {
-                                    func: Callee::Named("__aelys_to_string".to_string()),
-                                    args: vec![val],
-                                },
-                            },
-                            None,
-                        );
-                        operands.push(Operand::Copy(str_tmp));
                     }
-                }
-                TypedFmtStringPart::Placeholder => {
-                    operands.push(Operand::Const(AirConst::Str(String::new())));
-                }
+                    None => operands.push(Operand::Const(AirConst::Str(String::new()))),
+                },
             }
         }
 
@@ -1558,9 +1806,59 @@ impl<'a> LoweringContext<'a> {
         }
         acc
     }
+
+    fn lower_fmt_part(&mut self, expr: &TypedExpr) -> Operand {
+        let val = self.lower_expr(expr);
+        if matches!(expr.ty, InferType::String) {
+            return val;
+        }
+        let str_tmp = self.alloc_temp(AirType::Str);
+        self.emit(
+            AirStmtKind::Assign {
+                place: Place::Local(str_tmp),
+                rvalue: Rvalue::Call {
+                    func: Callee::Named("__aelys_to_string".to_string()),
+                    args: vec![val],
+                },
+            },
+            None,
+        );
+        Operand::Copy(str_tmp)
+    }
 }
 
 // helpers
+
+// every name some function nested in `stmts` captures, at any depth
+fn captured_by_nested(stmts: &[TypedStmt]) -> HashSet<String> {
+    fn walk(stmt: &TypedStmt, out: &mut HashSet<String>) {
+        match &stmt.kind {
+            TypedStmtKind::Function(func) => {
+                out.extend(func.captures.iter().map(|(name, _)| name.clone()));
+                func.body.iter().for_each(|s| walk(s, out));
+            }
+            TypedStmtKind::Block(stmts) => stmts.iter().for_each(|s| walk(s, out)),
+            TypedStmtKind::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                walk(then_branch, out);
+                if let Some(else_branch) = else_branch {
+                    walk(else_branch, out);
+                }
+            }
+            TypedStmtKind::While { body, .. }
+            | TypedStmtKind::For { body, .. }
+            | TypedStmtKind::ForEach { body, .. } => walk(body, out),
+            _ => {}
+        }
+    }
+    let mut out = HashSet::new();
+    stmts.iter().for_each(|s| walk(s, &mut out));
+    out
+}
+
 fn infer_to_int_size(ty: &InferType) -> AirIntSize {
     match ty {
         InferType::I8 => AirIntSize::I8,
@@ -1,152 +1,173 @@
+use crate::layout::compute_layouts;
+use crate::passes::visit::for_each_operand_mut;
 use crate::*;
-use std::collections::{HashMap, HashSet};
-
-type MangledMap<'a> = HashMap<&'a str, Vec<(&'a (String, Vec<String>), &'a String)>>;
+use std::collections::{HashMap, VecDeque};
 
 pub fn monomorphize(mut program: AirProgram) -> AirProgram {
     let mut ctx = MonoContext::new(&program);
-    ctx.collect_mono_requests(&program);
     ctx.instantiate(&mut program);
     ctx.rewrite_call_sites(&mut program);
     program.functions.retain(|f| f.type_params.is_empty());
+    if instantiate_structs(&mut program) {
+        compute_layouts(&mut program);
+    }
     program
 }
 
 struct MonoContext {
-    generic_functions: HashMap<String, usize>,
-    requests: Vec<MonoRequest>,
+    generic_functions: HashMap<String, GenericSignature>,
     instantiated: HashMap<(String, Vec<String>), String>,
     next_function_id: u32,
 }
 
+struct GenericSignature {
+    index: usize,
+    type_params: Vec<TypeParamId>,
+    params: Vec<AirType>,
+}
+
 struct MonoRequest {
     function_name: String,
     type_args: Vec<AirType>,
 }
 
+type LocalTypes = HashMap<LocalId, AirType>;
+
 impl MonoContext {
     fn new(program: &AirProgram) -> Self {
-        let generic_functions: HashMap<String, usize> = program
+        let generic_functions = program
             .functions
             .iter()
             .enumerate()
             .filter(|(_, f)| !f.type_params.is_empty())
-            .map(|(i, f)| (f.name.clone(), i))
+            .map(|(i, f)| {
+                let sig = GenericSignature {
+                    index: i,
+                    type_params: f.type_params.clone(),
+                    params: f.params.iter().map(|p| p.ty.clone()).collect(),
+                };
+                (f.name.clone(), sig)
+            })
             .collect();
 
         Self {
             generic_functions,
-            requests: Vec::new(),
             instantiated: HashMap::new(),
             next_function_id: program.functions.len() as u32,
         }
     }
 
-    fn collect_mono_requests(&mut self, program: &AirProgram) {
-        let generic_names: HashSet<String> = self.generic_functions.keys().cloned().collect();
+    // instances are scanned like any other function, so a generic function
+    // calling another one gets that callee instantiated as well
+    fn instantiate(&mut self, program: &mut AirProgram) {
+        let mut pending: VecDeque<usize> = (0..program.functions.len())
+            .filter(|&i| program.functions[i].type_params.is_empty())
+            .collect();
+
+        while let Some(idx) = pending.pop_front() {
+            for request in self.collect_requests(&program.functions[idx]) {
+                let key = (
+                    request.function_name.clone(),
+                    type_args_key(&request.type_args),
+                );
+                if self.instantiated.contains_key(&key) {
+                    continue;
+                }
+
+                let original_func = &program.functions[self.generic_functions[&key.0].index];
+                let original_id = original_func.id;
+                let new_id = FunctionId(self.next_function_id);
+                self.next_function_id += 1;
+
+                let mangled_name = mangle_name(&request.function_name, &request.type_args);
+                let saved_type_params = original_func.type_params.clone();
+                let mut new_func = original_func.clone();
+                new_func.id = new_id;
+                new_func.name = mangled_name.clone();
+                new_func.type_params = Vec::new();
 
-        for func in &program.functions {
-            if func.type_params.is_empty() {
-                self.collect_from_function(func, program, &generic_names);
+                for_each_type_mut(&mut new_func, &mut |ty| {
+                    substitute_type(ty, &saved_type_params, &request.type_args)
+                });
+
+                self.instantiated.insert(key, mangled_name);
+                program.mono_instances.push(MonoInstance {
+                    original: original_id,
+                    type_args: request.type_args,
+                    result: new_id,
+                });
+                program.functions.push(new_func);
+                pending.push_back(program.functions.len() - 1);
             }
         }
     }
 
-    fn collect_from_function(
-        &mut self,
-        func: &AirFunction,
-        program: &AirProgram,
-        generic_names: &HashSet<String>,
-    ) {
-        for block in &func.blocks {
-            for stmt in &block.stmts {
-                self.collect_from_stmt(stmt, func, program, generic_names);
+    fn collect_requests(&self, func: &AirFunction) -> Vec<MonoRequest> {
+        let types = local_types(func);
+        let mut requests = Vec::new();
+        let mut collect = |callee: &Callee, args: &[Operand]| {
+            if let Some((function_name, type_args)) = self.resolve_call(callee, args, &types) {
+                requests.push(MonoRequest {
+                    function_name,
+                    type_args,
+                });
             }
-            self.collect_from_terminator(&block.terminator, func, program, generic_names);
-        }
-    }
+        };
 
-    fn collect_from_stmt(
-        &mut self,
-        stmt: &AirStmt,
-        caller: &AirFunction,
-        program: &AirProgram,
-        generic_names: &HashSet<String>,
-    ) {
-        match &stmt.kind {
-            AirStmtKind::Assign {
-                rvalue: Rvalue::Call { func: callee, args },
-                ..
-            } => {
-                self.try_collect(callee, args, caller, program, generic_names);
+        for block in &func.blocks {
+            for stmt in &block.stmts {
+                match &stmt.kind {
+                    AirStmtKind::Assign {
+                        rvalue: Rvalue::Call { func: callee, args },
+                        ..
+                    }
+                    | AirStmtKind::CallVoid { func: callee, args } => collect(callee, args),
+                    _ => {}
+                }
             }
-            AirStmtKind::CallVoid { func: callee, args } => {
-                self.try_collect(callee, args, caller, program, generic_names);
+            if let AirTerminator::Invoke {
+                func: callee, args, ..
+            } = &block.terminator
+            {
+                collect(callee, args);
             }
-            _ => {}
         }
+        requests
     }
 
-    fn collect_from_terminator(
-        &mut self,
-        term: &AirTerminator,
-        caller: &AirFunction,
-        program: &AirProgram,
-        generic_names: &HashSet<String>,
-    ) {
-        if let AirTerminator::Invoke {
-            func: callee, args, ..
-        } = term
-        {
-            self.try_collect(callee, args, caller, program, generic_names);
-        }
-    }
-
-    fn try_collect(
-        &mut self,
+    // the generic function a call goes to and the type arguments it is
+    // instantiated with there
+    fn resolve_call(
+        &self,
         callee: &Callee,
         args: &[Operand],
-        caller: &AirFunction,
-        program: &AirProgram,
-        generic_names: &HashSet<String>,
-    ) {
-        let name = match callee {
-            Callee::Named(n) if generic_names.contains(n) => n,
-            _ => return,
+        types: &LocalTypes,
+    ) -> Option<(String, Vec<AirType>)> {
+        let Callee::Named(name) = callee else {
+            return None;
         };
-
-        let func_idx = self.generic_functions[name];
-        let generic_func = &program.functions[func_idx];
-
-        if let Some(type_args) = self.infer_type_args(generic_func, args, caller) {
-            let key = (name.clone(), self.type_args_key(&type_args));
-            if !self.instantiated.contains_key(&key) {
-                self.requests.push(MonoRequest {
-                    function_name: name.clone(),
-                    type_args,
-                });
-            }
-        }
+        let sig = self.generic_functions.get(name)?;
+        Some((name.clone(), self.infer_type_args(sig, args, types)))
     }
 
     fn infer_type_args(
         &self,
-        generic_func: &AirFunction,
+        sig: &GenericSignature,
         args: &[Operand],
-        caller: &AirFunction,
-    ) -> Option<Vec<AirType>> {
+        types: &LocalTypes,
+    ) -> Vec<AirType> {
         let mut resolved: HashMap<u32, AirType> = HashMap::new();
 
-        for (param, arg) in generic_func.params.iter().zip(args.iter()) {
-            let arg_ty = self.operand_type(arg, caller);
-            self.unify_param(&param.ty, &arg_ty, &mut resolved);
+        for (param_ty, arg) in sig.params.iter().zip(args.iter()) {
+            let arg_ty = operand_type(arg, types);
+            self.unify_param(param_ty, &arg_ty, &mut resolved);
         }
 
-        let mut type_args = Vec::with_capacity(generic_func.type_params.len());
-        for tp in &generic_func.type_params {
-            type_args.push(resolved.get(&tp.0)?.clone());
-        }
-        Some(type_args)
+        // a parameter no argument pins down is dynamic, like in sema
+        sig.type_params
+            .iter()
+            .map(|tp| resolved.get(&tp.0).cloned().unwrap_or(AirType::I64))
+            .collect()
     }
 
     fn unify_param(
@@ -187,106 +208,24 @@ impl MonoContext {
                     self.unify_param(ret, arg_ret, resolved);
                 }
             }
-            _ => {}
-        }
-    }
-
-    fn operand_type(&self, operand: &Operand, caller: &AirFunction) -> AirType {
-        match operand {
-            Operand::Const(c) => match c {
-                AirConst::IntLiteral(_) => AirType::I64,
-                AirConst::Int(_, size) => match size {
-                    AirIntSize::I8 => AirType::I8,
-                    AirIntSize::I16 => AirType::I16,
-                    AirIntSize::I32 => AirType::I32,
-                    AirIntSize::I64 => AirType::I64,
-                    AirIntSize::U8 => AirType::U8,
-                    AirIntSize::U16 => AirType::U16,
-                    AirIntSize::U32 => AirType::U32,
-                    AirIntSize::U64 => AirType::U64,
-                },
-                AirConst::Float(_, size) => match size {
-                    AirFloatSize::F32 => AirType::F32,
-                    AirFloatSize::F64 => AirType::F64,
-                },
-                AirConst::Bool(_) => AirType::Bool,
-                AirConst::Str(_) => AirType::Str,
-                AirConst::Null => AirType::Void,
-                AirConst::ZeroInit(ty) | AirConst::Undef(ty) => ty.clone(),
-            },
-            Operand::Copy(id) | Operand::Move(id) => caller
-                .params
-                .iter()
-                .find(|p| p.id == *id)
-                .map(|p| p.ty.clone())
-                .or_else(|| {
-                    caller
-                        .locals
-                        .iter()
-                        .find(|l| l.id == *id)
-                        .map(|l| l.ty.clone())
-                })
-                .unwrap_or(AirType::I64),
-        }
-    }
-
-    fn instantiate(&mut self, program: &mut AirProgram) {
-        let mut new_functions = Vec::new();
-        let mut mono_instances = Vec::new();
-
-        for request in &self.requests {
-            let key = (
-                request.function_name.clone(),
-                self.type_args_key(&request.type_args),
-            );
-
-            if self.instantiated.contains_key(&key) {
-                continue;
+            AirType::GenericStruct { name, args } => {
+                if let AirType::GenericStruct {
+                    name: arg_name,
+                    args: arg_args,
+                } = arg_ty
+                    && name == arg_name
+                {
+                    for (p, a) in args.iter().zip(arg_args.iter()) {
+                        self.unify_param(p, a, resolved);
+                    }
+                }
             }
-
-            let func_idx = self.generic_functions[&request.function_name];
-            let original_func = &program.functions[func_idx];
-            let original_id = original_func.id;
-            let new_id = FunctionId(self.next_function_id);
-            self.next_function_id += 1;
-
-            let mangled_name = self.mangle_name(&request.function_name, &request.type_args);
-            let saved_type_params = original_func.type_params.clone();
-            let mut new_func = original_func.clone();
-            new_func.id = new_id;
-            new_func.name = mangled_name.clone();
-            new_func.type_params = Vec::new();
-
-            self.substitute_types_in_function(
-                &mut new_func,
-                &saved_type_params,
-                &request.type_args,
-            );
-
-            new_functions.push(new_func);
-            self.instantiated.insert(key, mangled_name);
-
-            mono_instances.push(MonoInstance {
-                original: original_id,
-                type_args: request.type_args.clone(),
-                result: new_id,
-            });
+            _ => {}
         }
-
-        program.functions.extend(new_functions);
-        program.mono_instances.extend(mono_instances);
     }
 
     fn rewrite_call_sites(&self, program: &mut AirProgram) {
-        let name_to_mangled: MangledMap = {
-            let mut map: HashMap<&str, Vec<_>> = HashMap::new();
-            for (key, mangled) in &self.instantiated {
-                map.entry(key.0.as_str()).or_default().push((key, mangled));
-            }
-            map
-        };
-
-        if name_to_mangled.is_empty() {
+        if self.instantiated.is_empty() {
             return;
         }
 
@@ -294,83 +233,81 @@ impl MonoContext {
             if !func.type_params.is_empty() {
                 continue;
             }
+            let types = local_types(func);
             for block in &mut func.blocks {
                 for stmt in &mut block.stmts {
-                    self.rewrite_stmt(stmt, &name_to_mangled);
+                    match &mut stmt.kind {
+                        AirStmtKind::Assign {
+                            rvalue: Rvalue::Call { func: callee, args },
+                            ..
+                        }
+                        | AirStmtKind::CallVoid { func: callee, args } => {
+                            self.rewrite_callee(callee, args, &types);
+                        }
+                        _ => {}
+                    }
+                }
+                if let AirTerminator::Invoke {
+                    func: callee, args, ..
+                } = &mut block.terminator
+                {
+                    self.rewrite_callee(callee, args, &types);
                 }
-                self.rewrite_terminator(&mut block.terminator, &name_to_mangled);
-            }
-        }
-    }
-
-    fn rewrite_stmt(&self, stmt: &mut AirStmt, name_map: &MangledMap) {
-        match &mut stmt.kind {
-            AirStmtKind::Assign {
-                rvalue: Rvalue::Call { func: callee, .. },
-                ..
-            } => {
-                self.rewrite_callee(callee, name_map);
-            }
-            AirStmtKind::CallVoid { func: callee, .. } => {
-                self.rewrite_callee(callee, name_map);
             }
-            _ => {}
         }
     }
 
-    fn rewrite_terminator(&self, term: &mut AirTerminator, name_map: &MangledMap) {
-        if let AirTerminator::Invoke { func: callee, .. } = term {
-            self.rewrite_callee(callee, name_map);
-        }
-    }
-
-    fn rewrite_callee(&self, callee: &mut Callee, name_map: &MangledMap) {
-        if let Callee::Named(name) = callee
-            && let Some(entries) = name_map.get(name.as_str())
-            && let Some((_, mangled)) = entries.first()
+    fn rewrite_callee(&self, callee: &mut Callee, args: &[Operand], types: &LocalTypes) {
+        if let Some((name, type_args)) = self.resolve_call(callee, args, types)
+            && let Some(mangled) = self.instantiated.get(&(name, type_args_key(&type_args)))
         {
-            *name = (*mangled).clone();
+            *callee = Callee::Named(mangled.clone());
         }
     }
+}
 
-    fn type_args_key(&self, types: &[AirType]) -> Vec<String> {
-        types.iter().map(type_to_string).collect()
-    }
+fn local_types(func: &AirFunction) -> LocalTypes {
+    let mut types: LocalTypes = func.locals.iter().map(|l| (l.id, l.ty.clone())).collect();
+    types.extend(func.params.iter().map(|p| (p.id, p.ty.clone())));
+    types
+}
 
-    fn mangle_name(&self, name: &str, type_args: &[AirType]) -> String {
-        if type_args.is_empty() {
-            return name.to_string();
-        }
-        let type_str = type_args
-            .iter()
-            .map(type_to_string)
-            .collect::<Vec<_>>()
-            .join("_");
-        format!("__mono_{}_{}", name, type_str)
+fn operand_type(operand: &Operand, types: &LocalTypes) -> AirType {
+    match operand {
+        Operand::Const(c) => match c {
+            AirConst::IntLiteral(_) => AirType::I64,
+            AirConst::Int(_, size) => match size {
+                AirIntSize::I8 => AirType::I8,
+                AirIntSize::I16 => AirType::I16,
+                AirIntSize::I32 => AirType::I32,
+                AirIntSize::I64 => AirType::I64,
+                AirIntSize::U8 => AirType::U8,
+                AirIntSize::U16 => AirType::U16,
+                AirIntSize::U32 => AirType::U32,
+                AirIntSize::U64 => AirType::U64,
+            },
+            AirConst::Float(_, size) => match size {
+                AirFloatSize::F32 => AirType::F32,
+                AirFloatSize::F64 => AirType::F64,
+            },
+            AirConst::Bool(_) => AirType::Bool,
+            AirConst::Str(_) => AirType::Str,
+            AirConst::Null => AirType::Void,
+            AirConst::ZeroInit(ty) | AirConst::Undef(ty) => ty.clone(),
+        },
+        Operand::Copy(id) | Operand::Move(id) => types.get(id).cloned().unwrap_or(AirType::I64),
     }
+}
 
-    fn substitute_types_in_function(
-        &self,
-        func: &mut AirFunction,
-        type_params: &[TypeParamId],
-        type_args: &[AirType],
-    ) {
-        for param in &mut func.params {
-            substitute_type(&mut param.ty, type_params, type_args);
-        }
-        substitute_type(&mut func.ret_ty, type_params, type_args);
-
-        for local in &mut func.locals {
-            substitute_type(&mut local.ty, type_params, type_args);
-        }
+fn type_args_key(types: &[AirType]) -> Vec<String> {
+    types.iter().map(type_to_string).collect()
+}
 
-        for block in &mut func.blocks {
-            for stmt in &mut block.stmts {
-                substitute_stmt(stmt, type_params, type_args);
-            }
-            substitute_terminator(&mut block.terminator, type_params, type_args);
-        }
+fn mangle_name(name: &str, type_args: &[AirType]) -> String {
+    if type_args.is_empty() {
+        return name.to_string();
     }
+    format!("__mono_{}_{}", name, type_args_key(type_args).join("_"))
 }
 
 fn type_to_string(ty: &AirType) -> String {
@@ -389,6 +326,9 @@ fn type_to_string(ty: &AirType) -> String {
         AirType::Str => "str".to_string(),
         AirType::Ptr(inner) => format!("ptr_{}", type_to_string(inner)),
         AirType::Struct(name) => name.clone(),
+        AirType::GenericStruct { name, args } => {
+            format!("{}_{}", name, type_args_key(args).join("_"))
+        }
         AirType::Array(inner, size) => format!("array_{}_{}", type_to_string(inner), size),
         AirType::Slice(inner) => format!("slice_{}", type_to_string(inner)),
         AirType::FnPtr { .. } => "fnptr".to_string(),
@@ -397,6 +337,40 @@ fn type_to_string(ty: &AirType) -> String {
     }
 }
 
+// every type written in a function body: signature, locals, allocations,
+// casts and typed constants
+fn for_each_type_mut(func: &mut AirFunction, f: &mut impl FnMut(&mut AirType)) {
+    for param in &mut func.params {
+        f(&mut param.ty);
+    }
+    f(&mut func.ret_ty);
+    for local in &mut func.locals {
+        f(&mut local.ty);
+    }
+
+    for block in &mut func.blocks {
+        for stmt in &mut block.stmts {
+            match &mut stmt.kind {
+                AirStmtKind::GcAlloc { ty, .. } | AirStmtKind::Alloc { ty, .. } => f(ty),
+                AirStmtKind::Assign {
+                    rvalue: Rvalue::Cast { from, to, .. },
+                    ..
+                } => {
+                    f(from);
+                    f(to);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for_each_operand_mut(func, |op| {
+        if let Operand::Const(AirConst::ZeroInit(ty) | AirConst::Undef(ty)) = op {
+            f(ty);
+        }
+    });
+}
+
 fn substitute_type(ty: &mut AirType, type_params: &[TypeParamId], type_args: &[AirType]) {
     match ty {
         AirType::Param(id) => {
@@ -415,54 +389,125 @@ fn substitute_type(ty: &mut AirType, type_params: &[TypeParamId], type_args: &[A
             }
             substitute_type(ret, type_params, type_args);
         }
+        AirType::GenericStruct { args, .. } => {
+            for a in args {
+                substitute_type(a, type_params, type_args);
+            }
+        }
         _ => {}
     }
 }
 
-fn substitute_stmt(stmt: &mut AirStmt, type_params: &[TypeParamId], type_args: &[AirType]) {
-    match &mut stmt.kind {
-        AirStmtKind::Assign { rvalue, .. } => {
-            substitute_rvalue(rvalue, type_params, type_args);
-        }
-        AirStmtKind::GcAlloc { ty, .. } | AirStmtKind::Alloc { ty, .. } => {
-            substitute_type(ty, type_params, type_args);
+// Generic structs get one instance per distinct argument list, named like
+// function instances (`__mono_Pair_i64_str`). This runs after the function
+// instances exist, so their substituted types are covered too.
+fn instantiate_structs(program: &mut AirProgram) -> bool {
+    let (templates, concrete): (Vec<_>, Vec<_>) = std::mem::take(&mut program.structs)
+        .into_iter()
+        .partition(|s| !s.type_params.is_empty());
+    program.structs = concrete;
+    if templates.is_empty() {
+        return false;
+    }
+
+    let mut mono = StructMono {
+        templates: templates.into_iter().map(|s| (s.name.clone(), s)).collect(),
+        instances: Vec::new(),
+        origin: HashMap::new(),
+    };
+
+    for def in &mut program.structs {
+        for field in &mut def.fields {
+            mono.concretize(&mut field.ty);
         }
-        _ => {}
     }
+    for global in &mut program.globals {
+        mono.concretize(&mut global.ty);
+    }
+    for func in &mut program.functions {
+        mono.concretize_function(func);
+    }
+
+    program.structs.extend(mono.instances);
+    true
 }
 
-fn substitute_rvalue(rvalue: &mut Rvalue, type_params: &[TypeParamId], type_args: &[AirType]) {
-    match rvalue {
-        Rvalue::Cast { from, to, .. } => {
-            substitute_type(from, type_params, type_args);
-            substitute_type(to, type_params, type_args);
-        }
-        Rvalue::StructInit { name, .. } => {
-            for tp in type_params {
-                if let Some(replacement) = type_args.get(tp.0 as usize) {
-                    let mangled_suffix = type_to_string(replacement);
-                    if name.contains("__mono_") {
-                        continue;
+struct StructMono {
+    templates: HashMap<String, AirStructDef>,
+    instances: Vec<AirStructDef>,
+    // instance name -> template it came from
+    origin: HashMap<String, String>,
+}
+
+impl StructMono {
+    fn concretize(&mut self, ty: &mut AirType) {
+        match ty {
+            AirType::GenericStruct { name, args } => {
+                let mangled = mangle_name(name, args);
+                if !self.origin.contains_key(&mangled)
+                    && let Some(template) = self.templates.get(name.as_str())
+                {
+                    self.origin.insert(mangled.clone(), name.clone());
+                    let mut instance = template.clone();
+                    instance.name = mangled.clone();
+                    instance.type_params = Vec::new();
+                    for field in &mut instance.fields {
+                        substitute_type(&mut field.ty, &template.type_params, args);
+                        field.offset = None;
                     }
-                    *name = format!("__mono_{}_{}", name, mangled_suffix);
+                    // fields may apply other generic structs
+                    for field in &mut instance.fields {
+                        self.concretize(&mut field.ty);
+                    }
+                    self.instances.push(instance);
+                }
+                *ty = AirType::Struct(mangled);
+            }
+            AirType::Ptr(inner) | AirType::Array(inner, _) | AirType::Slice(inner) => {
+                self.concretize(inner)
+            }
+            AirType::FnPtr { params, ret, .. } => {
+                for p in params {
+                    self.concretize(p);
                 }
+                self.concretize(ret);
             }
+            _ => {}
         }
-        _ => {}
     }
-}
 
-fn substitute_terminator(
-    term: &mut AirTerminator,
-    type_params: &[TypeParamId],
-    type_args: &[AirType],
-) {
-    if let AirTerminator::Invoke { func: callee, .. } = term {
-        substitute_callee(callee, type_params, type_args);
-    }
-}
+    fn concretize_function(&mut self, func: &mut AirFunction) {
+        for_each_type_mut(func, &mut |ty| self.concretize(ty));
 
-fn substitute_callee(_callee: &mut Callee, _type_params: &[TypeParamId], _type_args: &[AirType]) {
-    // Callee rewriting happens in the separate rewrite_call_sites pass
-    // after all instances are known. No per-function substitution needed.
+        // struct literals name the template, the instance comes from the
+        // type of the local they initialize
+        let types = local_types(func);
+        for block in &mut func.blocks {
+            for stmt in &mut block.stmts {
+                if let AirStmtKind::Assign {
+                    place: Place::Local(dest),
+                    rvalue: Rvalue::StructInit { name, .. },
+                } = &mut stmt.kind
+                    && let Some(template) = self.templates.get(name.as_str())
+                {
+                    let arity = template.type_params.len();
+                    *name = match types.get(dest) {
+                        Some(AirType::Struct(instance))
+                            if self.origin.get(instance) == Some(name) =>
+                        {
+                            instance.clone()
+                        }
+                        _ => {
+                            let mut ty = AirType::GenericStruct {
+                                name: name.clone(),
+                                args: vec![AirType::I64; arity],
+                            };
+                            self.concretize(&mut ty);
+                            type_to_string(&ty)
+                        }
+                    };
+                }
+            }
+        }
+    }
 }
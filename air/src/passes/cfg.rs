@@ -0,0 +1,237 @@
+// control flow graph queries shared by the passes
+
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+pub fn successors(term: &AirTerminator) -> Vec<BlockId> {
+    match term {
+        AirTerminator::Goto(target) => vec![*target],
+        AirTerminator::Branch {
+            then_block,
+            else_block,
+            ..
+        } => vec![*then_block, *else_block],
+        AirTerminator::Switch {
+            targets, default, ..
+        } => {
+            let mut out: Vec<_> = targets.iter().map(|(_, t)| *t).collect();
+            out.push(*default);
+            out
+        }
+        AirTerminator::Invoke { normal, unwind, .. } => vec![*normal, *unwind],
+        AirTerminator::Return(_)
+        | AirTerminator::Unwind
+        | AirTerminator::Unreachable
+        | AirTerminator::Panic { .. } => Vec::new(),
+    }
+}
+
+pub fn retarget(term: &mut AirTerminator, from: BlockId, to: BlockId) {
+    let fix = |b: &mut BlockId| {
+        if *b == from {
+            *b = to;
+        }
+    };
+    match term {
+        AirTerminator::Goto(target) => fix(target),
+        AirTerminator::Branch {
+            then_block,
+            else_block,
+            ..
+        } => {
+            fix(then_block);
+            fix(else_block);
+        }
+        AirTerminator::Switch {
+            targets, default, ..
+        } => {
+            for (_, t) in targets {
+                fix(t);
+            }
+            fix(default);
+        }
+        AirTerminator::Invoke { normal, unwind, .. } => {
+            fix(normal);
+            fix(unwind);
+        }
+        AirTerminator::Return(_)
+        | AirTerminator::Unwind
+        | AirTerminator::Unreachable
+        | AirTerminator::Panic { .. } => {}
+    }
+}
+
+// each predecessor is listed once, even when it branches to the block twice
+pub fn predecessors(func: &AirFunction) -> HashMap<BlockId, Vec<BlockId>> {
+    let mut preds: HashMap<BlockId, Vec<BlockId>> =
+        func.blocks.iter().map(|b| (b.id, Vec::new())).collect();
+    for block in &func.blocks {
+        for succ in successors(&block.terminator) {
+            let list = preds.entry(succ).or_default();
+            if !list.contains(&block.id) {
+                list.push(block.id);
+            }
+        }
+    }
+    preds
+}
+
+// reverse postorder from the entry block, unreachable blocks left out
+pub fn reverse_postorder(func: &AirFunction) -> Vec<BlockId> {
+    let Some(entry) = func.blocks.first() else {
+        return Vec::new();
+    };
+    let succs: HashMap<BlockId, Vec<BlockId>> = func
+        .blocks
+        .iter()
+        .map(|b| (b.id, successors(&b.terminator)))
+        .collect();
+
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    let mut stack = vec![(entry.id, 0usize)];
+    visited.insert(entry.id);
+    while let Some((block, next)) = stack.pop() {
+        let out = succs.get(&block).map(Vec::as_slice).unwrap_or(&[]);
+        if let Some(&succ) = out.get(next) {
+            stack.push((block, next + 1));
+            if succs.contains_key(&succ) && visited.insert(succ) {
+                stack.push((succ, 0));
+            }
+        } else {
+            order.push(block);
+        }
+    }
+    order.reverse();
+    order
+}
+
+pub struct Dominators {
+    idom: HashMap<BlockId, BlockId>,
+    entry: BlockId,
+}
+
+impl Dominators {
+    // Cooper, Harvey & Kennedy, "A Simple, Fast Dominance Algorithm"
+    pub fn compute(func: &AirFunction) -> Self {
+        let rpo = reverse_postorder(func);
+        let entry = rpo.first().copied().unwrap_or(BlockId(0));
+        let index: HashMap<BlockId, usize> = rpo.iter().enumerate().map(|(i, b)| (*b, i)).collect();
+        let preds = predecessors(func);
+
+        let mut idom: HashMap<BlockId, BlockId> = HashMap::new();
+        idom.insert(entry, entry);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in rpo.iter().skip(1) {
+                let mut new_idom: Option<BlockId> = None;
+                for &pred in &preds[&block] {
+                    if !idom.contains_key(&pred) {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(current) => intersect(&idom, &index, pred, current),
+                    });
+                }
+                if let Some(new_idom) = new_idom
+                    && idom.get(&block) != Some(&new_idom)
+                {
+                    idom.insert(block, new_idom);
+                    changed = true;
+                }
+            }
+        }
+        Self { idom, entry }
+    }
+
+    pub fn idom(&self, block: BlockId) -> Option<BlockId> {
+        if block == self.entry {
+            return None;
+        }
+        self.idom.get(&block).copied()
+    }
+
+    // children in the dominator tree
+    pub fn tree(&self) -> HashMap<BlockId, Vec<BlockId>> {
+        let mut children: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
+        for (&block, &parent) in &self.idom {
+            if block != self.entry {
+                children.entry(parent).or_default().push(block);
+            }
+        }
+        for list in children.values_mut() {
+            list.sort_by_key(|b| b.0);
+        }
+        children
+    }
+
+    pub fn frontiers(&self, func: &AirFunction) -> HashMap<BlockId, HashSet<BlockId>> {
+        let preds = predecessors(func);
+        let mut df: HashMap<BlockId, HashSet<BlockId>> = HashMap::new();
+        for block in func.blocks.iter().map(|b| b.id) {
+            if !self.idom.contains_key(&block) {
+                continue;
+            }
+            let reachable: Vec<_> = preds[&block]
+                .iter()
+                .filter(|p| self.idom.contains_key(p))
+                .collect();
+            if reachable.len() < 2 {
+                continue;
+            }
+            let stop = self.idom(block);
+            for &&pred in &reachable {
+                let mut runner = Some(pred);
+                while let Some(r) = runner {
+                    if Some(r) == stop {
+                        break;
+                    }
+                    df.entry(r).or_default().insert(block);
+                    runner = self.idom(r);
+                }
+            }
+        }
+        df
+    }
+}
+
+fn intersect(
+    idom: &HashMap<BlockId, BlockId>,
+    index: &HashMap<BlockId, usize>,
+    mut a: BlockId,
+    mut b: BlockId,
+) -> BlockId {
+    while a != b {
+        while index[&a] > index[&b] {
+            a = idom[&a];
+        }
+        while index[&b] > index[&a] {
+            b = idom[&b];
+        }
+    }
+    a
+}
+
+// drops blocks the entry can't reach, along with the phi inputs they fed
+pub fn remove_unreachable(func: &mut AirFunction) -> bool {
+    let reachable: HashSet<BlockId> = reverse_postorder(func).into_iter().collect();
+    let before = func.blocks.len();
+    func.blocks.retain(|b| reachable.contains(&b.id));
+    if func.blocks.len() == before {
+        return false;
+    }
+    for block in func.blocks.iter_mut() {
+        for stmt in block.stmts.iter_mut() {
+            if let AirStmtKind::Assign {
+                rvalue: Rvalue::Phi(inputs),
+                ..
+            } = &mut stmt.kind
+            {
+                inputs.retain(|(pred, _)| reachable.contains(pred));
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,82 @@
+// dead block, dead assignment and dead local elimination
+
+use super::AirPass;
+use super::cfg;
+use super::visit;
+use crate::*;
+use std::collections::HashSet;
+
+pub struct DeadCodeElimination;
+
+impl AirPass for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn run(&mut self, program: &mut AirProgram) -> bool {
+        let mut changed = false;
+        for func in program.functions.iter_mut() {
+            if func.blocks.is_empty() {
+                continue;
+            }
+            changed |= cfg::remove_unreachable(func);
+            while remove_dead_assignments(func) {
+                changed = true;
+            }
+            changed |= remove_dead_locals(func);
+        }
+        changed
+    }
+}
+
+fn remove_dead_assignments(func: &mut AirFunction) -> bool {
+    let used = visit::used_locals(func);
+    let mut changed = false;
+    for block in func.blocks.iter_mut() {
+        block.stmts.retain(|stmt| {
+            let dead = match &stmt.kind {
+                AirStmtKind::Assign {
+                    place: Place::Local(id),
+                    rvalue,
+                } => !used.contains(id) && !has_side_effects(rvalue),
+                _ => false,
+            };
+            changed |= dead;
+            !dead
+        });
+    }
+    changed
+}
+
+// calls, and anything that can fail at runtime, stay even when the result is unused
+fn has_side_effects(rvalue: &Rvalue) -> bool {
+    match rvalue {
+        Rvalue::Call { .. } | Rvalue::Deref(_) => true,
+        Rvalue::BinaryOp(op, _, divisor) => match op {
+            BinOp::Div | BinOp::Rem => {
+                !matches!(
+                    divisor,
+                    Operand::Const(AirConst::IntLiteral(v) | AirConst::Int(v, _)) if *v != 0 && *v != -1
+                ) && !matches!(divisor, Operand::Const(AirConst::Float(..)))
+            }
+            BinOp::CheckedAdd | BinOp::CheckedSub | BinOp::CheckedMul => true,
+            _ => false,
+        },
+        Rvalue::Use(_)
+        | Rvalue::UnaryOp(..)
+        | Rvalue::StructInit { .. }
+        | Rvalue::FieldAccess { .. }
+        | Rvalue::AddressOf(_)
+        | Rvalue::Cast { .. }
+        | Rvalue::Discriminant(_)
+        | Rvalue::Phi(_) => false,
+    }
+}
+
+fn remove_dead_locals(func: &mut AirFunction) -> bool {
+    let mut referenced: HashSet<LocalId> = visit::used_locals(func);
+    referenced.extend(visit::def_counts(func).into_keys());
+    let before = func.locals.len();
+    func.locals.retain(|l| referenced.contains(&l.id));
+    func.locals.len() != before
+}
@@ -0,0 +1,80 @@
+// AIR optimization passes
+//
+// These run on monomorphized AIR with layouts computed, after the typed-AST
+// optimizer in `aelys-opt` has done its part. The standard pipeline goes into
+// SSA, cleans up, and comes back out, so backends never see a phi.
+
+mod cfg;
+mod dce;
+mod propagate;
+mod simplify_cfg;
+mod ssa;
+pub(crate) mod visit;
+
+pub use dce::DeadCodeElimination;
+pub use propagate::{ConstantPropagation, CopyPropagation};
+pub use simplify_cfg::SimplifyCfg;
+pub use ssa::{SsaConstruction, SsaDestruction};
+
+use crate::AirProgram;
+
+pub trait AirPass {
+    fn name(&self) -> &'static str;
+    // true when the program changed
+    fn run(&mut self, program: &mut AirProgram) -> bool;
+}
+
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Box<dyn AirPass>>,
+}
+
+impl PassManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn standard() -> Self {
+        let mut pm = Self::new();
+        pm.add(SsaConstruction);
+        for _ in 0..2 {
+            pm.add(ConstantPropagation);
+            pm.add(CopyPropagation);
+            pm.add(SimplifyCfg);
+            pm.add(DeadCodeElimination);
+        }
+        pm.add(SsaDestruction);
+        pm.add(SimplifyCfg);
+        pm.add(DeadCodeElimination);
+        pm
+    }
+
+    pub fn add(&mut self, pass: impl AirPass + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn pass_names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|p| p.name()).collect()
+    }
+
+    pub fn run(&mut self, program: &mut AirProgram) -> bool {
+        self.run_with_observer(program, |_, _| {})
+    }
+
+    // `observe` sees the program before the first pass (as "input") and after
+    // every pass, for `--emit-air=passes`
+    pub fn run_with_observer(
+        &mut self,
+        program: &mut AirProgram,
+        mut observe: impl FnMut(&str, &AirProgram),
+    ) -> bool {
+        observe("input", program);
+        let mut changed = false;
+        for pass in &mut self.passes {
+            changed |= pass.run(program);
+            observe(pass.name(), program);
+        }
+        changed
+    }
+}
@@ -0,0 +1,384 @@
+// constant and copy propagation over single-definition locals
+//
+// Both only touch locals with exactly one definition that aren't pinned, which
+// after SsaConstruction is nearly all of them. The definition then dominates
+// every use, so substituting its value is safe.
+
+use super::AirPass;
+use super::visit;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+pub struct ConstantPropagation;
+
+impl AirPass for ConstantPropagation {
+    fn name(&self) -> &'static str {
+        "const-prop"
+    }
+
+    fn run(&mut self, program: &mut AirProgram) -> bool {
+        let mut changed = false;
+        for func in program.functions.iter_mut() {
+            while propagate_constants(func) {
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn single_defs(func: &AirFunction) -> HashSet<LocalId> {
+    let pinned = visit::pinned_locals(func);
+    visit::def_counts(func)
+        .into_iter()
+        .filter(|(id, n)| *n == 1 && !pinned.contains(id))
+        .map(|(id, _)| id)
+        .collect()
+}
+
+fn propagate_constants(func: &mut AirFunction) -> bool {
+    let single = single_defs(func);
+    let params: HashSet<LocalId> = func.params.iter().map(|p| p.id).collect();
+    let mut changed = false;
+
+    // fold first so freshly folded values propagate in the same round
+    for block in func.blocks.iter_mut() {
+        let mut folded_phi = false;
+        for stmt in block.stmts.iter_mut() {
+            let AirStmtKind::Assign { rvalue, .. } = &mut stmt.kind else {
+                continue;
+            };
+            if let Some(c) = fold(rvalue) {
+                folded_phi |= matches!(rvalue, Rvalue::Phi(_));
+                *rvalue = Rvalue::Use(Operand::Const(c));
+                changed = true;
+            }
+        }
+        if folded_phi {
+            visit::hoist_phis(block);
+        }
+    }
+
+    let mut known: HashMap<LocalId, AirConst> = HashMap::new();
+    for block in &func.blocks {
+        for stmt in &block.stmts {
+            if let AirStmtKind::Assign {
+                place: Place::Local(id),
+                rvalue: Rvalue::Use(Operand::Const(c)),
+            } = &stmt.kind
+                && single.contains(id)
+                && !params.contains(id)
+                && is_scalar(c)
+            {
+                known.insert(*id, c.clone());
+            }
+        }
+    }
+    if known.is_empty() {
+        return changed;
+    }
+
+    visit::for_each_operand_mut(func, |op| {
+        if let Operand::Copy(id) | Operand::Move(id) = op
+            && let Some(c) = known.get(id)
+        {
+            *op = Operand::Const(c.clone());
+            changed = true;
+        }
+    });
+    changed
+}
+
+fn is_scalar(c: &AirConst) -> bool {
+    matches!(
+        c,
+        AirConst::IntLiteral(_)
+            | AirConst::Int(..)
+            | AirConst::Float(..)
+            | AirConst::Bool(_)
+            | AirConst::Str(_)
+            | AirConst::Null
+    )
+}
+
+fn const_int(op: &Operand) -> Option<i64> {
+    match op {
+        Operand::Const(AirConst::IntLiteral(v) | AirConst::Int(v, _)) => Some(*v),
+        _ => None,
+    }
+}
+
+// None for untyped literals, which behave like i64
+fn int_size(op: &Operand) -> Option<AirIntSize> {
+    match op {
+        Operand::Const(AirConst::Int(_, size)) if *size != AirIntSize::I64 => Some(*size),
+        _ => None,
+    }
+}
+
+// unsigned values live in an i64 here, so only fold the range both agree on
+fn fits(v: i64, size: Option<AirIntSize>) -> bool {
+    match size {
+        None => true,
+        Some(AirIntSize::I8) => i8::try_from(v).is_ok(),
+        Some(AirIntSize::I16) => i16::try_from(v).is_ok(),
+        Some(AirIntSize::I32) => i32::try_from(v).is_ok(),
+        Some(AirIntSize::I64) => true,
+        Some(AirIntSize::U8) => u8::try_from(v).is_ok(),
+        Some(AirIntSize::U16) => u16::try_from(v).is_ok(),
+        Some(AirIntSize::U32) => u32::try_from(v).is_ok(),
+        Some(AirIntSize::U64) => v >= 0,
+    }
+}
+
+fn const_float(op: &Operand) -> Option<f64> {
+    match op {
+        Operand::Const(AirConst::Float(v, _)) => Some(*v),
+        _ => None,
+    }
+}
+
+fn const_bool(op: &Operand) -> Option<bool> {
+    match op {
+        Operand::Const(AirConst::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+// integer results keep the left operand's width; anything that could trap or
+// overflow at runtime is left for the runtime to report
+fn fold(rvalue: &Rvalue) -> Option<AirConst> {
+    match rvalue {
+        Rvalue::BinaryOp(op, a, b) => {
+            if let (Some(x), Some(y)) = (const_int(a), const_int(b)) {
+                let size = int_size(a);
+                if size != int_size(b) || !fits(x, size) || !fits(y, size) {
+                    return None;
+                }
+                let int = |v: Option<i64>| -> Option<AirConst> {
+                    let v = v.filter(|v| fits(*v, size))?;
+                    Some(match a {
+                        Operand::Const(AirConst::Int(_, size)) => AirConst::Int(v, *size),
+                        _ => AirConst::IntLiteral(v),
+                    })
+                };
+                return Some(match op {
+                    BinOp::Add | BinOp::CheckedAdd => int(x.checked_add(y))?,
+                    BinOp::Sub | BinOp::CheckedSub => int(x.checked_sub(y))?,
+                    BinOp::Mul | BinOp::CheckedMul => int(x.checked_mul(y))?,
+                    BinOp::Div if y != 0 => int(x.checked_div(y))?,
+                    BinOp::Rem if y != 0 => int(x.checked_rem(y))?,
+                    BinOp::BitAnd => int(Some(x & y))?,
+                    BinOp::BitOr => int(Some(x | y))?,
+                    BinOp::BitXor => int(Some(x ^ y))?,
+                    BinOp::Eq => AirConst::Bool(x == y),
+                    BinOp::Ne => AirConst::Bool(x != y),
+                    BinOp::Lt => AirConst::Bool(x < y),
+                    BinOp::Le => AirConst::Bool(x <= y),
+                    BinOp::Gt => AirConst::Bool(x > y),
+                    BinOp::Ge => AirConst::Bool(x >= y),
+                    _ => return None,
+                });
+            }
+            if let (Some(x), Some(y)) = (const_float(a), const_float(b)) {
+                // f32 arithmetic would need its own rounding, leave it alone
+                let size = match a {
+                    Operand::Const(AirConst::Float(_, size @ AirFloatSize::F64)) => *size,
+                    _ => return None,
+                };
+                return Some(match op {
+                    BinOp::Add => AirConst::Float(x + y, size),
+                    BinOp::Sub => AirConst::Float(x - y, size),
+                    BinOp::Mul => AirConst::Float(x * y, size),
+                    BinOp::Div => AirConst::Float(x / y, size),
+                    BinOp::Eq => AirConst::Bool(x == y),
+                    BinOp::Ne => AirConst::Bool(x != y),
+                    BinOp::Lt => AirConst::Bool(x < y),
+                    BinOp::Le => AirConst::Bool(x <= y),
+                    BinOp::Gt => AirConst::Bool(x > y),
+                    BinOp::Ge => AirConst::Bool(x >= y),
+                    _ => return None,
+                });
+            }
+            if let (Some(x), Some(y)) = (const_bool(a), const_bool(b)) {
+                return Some(AirConst::Bool(match op {
+                    BinOp::And => x && y,
+                    BinOp::Or => x || y,
+                    BinOp::Eq => x == y,
+                    BinOp::Ne => x != y,
+                    _ => return None,
+                }));
+            }
+            None
+        }
+        Rvalue::UnaryOp(op, a) => match (op, a) {
+            (UnOp::Neg, Operand::Const(AirConst::Int(v, size))) => {
+                let v = v.checked_neg().filter(|v| fits(*v, Some(*size)))?;
+                Some(AirConst::Int(v, *size))
+            }
+            (UnOp::Neg, Operand::Const(AirConst::IntLiteral(v))) => {
+                Some(AirConst::IntLiteral(v.checked_neg()?))
+            }
+            (UnOp::Neg, Operand::Const(AirConst::Float(v, size))) => {
+                Some(AirConst::Float(-v, *size))
+            }
+            (UnOp::Not, Operand::Const(AirConst::Bool(b))) => Some(AirConst::Bool(!b)),
+            _ => None,
+        },
+        // a phi whose inputs agree on one constant (undef agrees with anything)
+        Rvalue::Phi(inputs) => {
+            let mut value: Option<&AirConst> = None;
+            for (_, op) in inputs {
+                match op {
+                    Operand::Const(AirConst::Undef(_)) => {}
+                    Operand::Const(c) if is_scalar(c) => match value {
+                        None => value = Some(c),
+                        Some(v) if same_const(v, c) => {}
+                        Some(_) => return None,
+                    },
+                    _ => return None,
+                }
+            }
+            value.cloned()
+        }
+        _ => None,
+    }
+}
+
+fn same_const(a: &AirConst, b: &AirConst) -> bool {
+    match (a, b) {
+        (AirConst::IntLiteral(x), AirConst::IntLiteral(y)) => x == y,
+        (AirConst::Int(x, sx), AirConst::Int(y, sy)) => x == y && sx == sy,
+        (AirConst::Float(x, sx), AirConst::Float(y, sy)) => x.to_bits() == y.to_bits() && sx == sy,
+        (AirConst::Bool(x), AirConst::Bool(y)) => x == y,
+        (AirConst::Str(x), AirConst::Str(y)) => x == y,
+        (AirConst::Null, AirConst::Null) => true,
+        _ => false,
+    }
+}
+
+pub struct CopyPropagation;
+
+impl AirPass for CopyPropagation {
+    fn name(&self) -> &'static str {
+        "copy-prop"
+    }
+
+    fn run(&mut self, program: &mut AirProgram) -> bool {
+        let mut changed = false;
+        for func in program.functions.iter_mut() {
+            while propagate_copies(func) {
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn propagate_copies(func: &mut AirFunction) -> bool {
+    let mut changed = simplify_trivial_phis(func);
+    let single = single_defs(func);
+    let params: HashSet<LocalId> = func.params.iter().map(|p| p.id).collect();
+
+    let mut copies: HashMap<LocalId, LocalId> = HashMap::new();
+    for block in &func.blocks {
+        for stmt in &block.stmts {
+            if let AirStmtKind::Assign {
+                place: Place::Local(dest),
+                rvalue: Rvalue::Use(Operand::Copy(src) | Operand::Move(src)),
+            } = &stmt.kind
+                && dest != src
+                && single.contains(dest)
+                && single.contains(src)
+                && !params.contains(dest)
+                && visit::local_type(func, *dest) == visit::local_type(func, *src)
+            {
+                copies.insert(*dest, *src);
+            }
+        }
+    }
+    if copies.is_empty() {
+        return changed;
+    }
+
+    let resolve = |mut id: LocalId| {
+        let mut steps = 0;
+        while let Some(next) = copies.get(&id) {
+            id = *next;
+            steps += 1;
+            if steps > copies.len() {
+                break;
+            }
+        }
+        id
+    };
+    visit::for_each_operand_mut(func, |op| {
+        if let Operand::Copy(id) | Operand::Move(id) = op {
+            let target = resolve(*id);
+            if target != *id {
+                *id = target;
+                changed = true;
+            }
+        }
+    });
+    changed
+}
+
+// `x = phi [a, x, a]` is just `x = a`, `x = phi [undef, x]` is undef
+fn simplify_trivial_phis(func: &mut AirFunction) -> bool {
+    let types: HashMap<LocalId, AirType> = func
+        .params
+        .iter()
+        .map(|p| (p.id, p.ty.clone()))
+        .chain(func.locals.iter().map(|l| (l.id, l.ty.clone())))
+        .collect();
+    let mut changed = false;
+    for block in func.blocks.iter_mut() {
+        let mut resolved = false;
+        for stmt in block.stmts.iter_mut() {
+            let AirStmtKind::Assign {
+                place: Place::Local(dest),
+                rvalue,
+            } = &mut stmt.kind
+            else {
+                continue;
+            };
+            let Rvalue::Phi(inputs) = rvalue else {
+                continue;
+            };
+            let mut value: Option<LocalId> = None;
+            let mut trivial = true;
+            let mut undef = false;
+            for (_, op) in inputs.iter() {
+                match op {
+                    Operand::Copy(id) | Operand::Move(id) if id == dest => {}
+                    Operand::Const(AirConst::Undef(_)) => undef = true,
+                    Operand::Copy(id) | Operand::Move(id) => match value {
+                        None => value = Some(*id),
+                        Some(v) if v == *id => {}
+                        Some(_) => trivial = false,
+                    },
+                    Operand::Const(_) => trivial = false,
+                }
+            }
+            // `phi [undef, a]` can't become `a`: a loop header phi fed by a value
+            // defined further down the loop would then read it before its definition
+            if !trivial || (undef && value.is_some()) {
+                continue;
+            }
+            // nothing but undef and itself: the value is never defined on any path
+            *rvalue = match value {
+                Some(v) => Rvalue::Use(Operand::Copy(v)),
+                None => Rvalue::Use(Operand::Const(AirConst::Undef(
+                    types.get(dest).cloned().unwrap_or(AirType::Void),
+                ))),
+            };
+            resolved = true;
+        }
+        if resolved {
+            visit::hoist_phis(block);
+            changed = true;
+        }
+    }
+    changed
+}
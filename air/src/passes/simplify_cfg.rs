@@ -0,0 +1,213 @@
+// branch folding, jump threading through empty blocks, block merging
+
+use super::AirPass;
+use super::cfg;
+use super::visit;
+use crate::*;
+
+pub struct SimplifyCfg;
+
+impl AirPass for SimplifyCfg {
+    fn name(&self) -> &'static str {
+        "simplify-cfg"
+    }
+
+    fn run(&mut self, program: &mut AirProgram) -> bool {
+        let mut changed = false;
+        for func in program.functions.iter_mut() {
+            if func.blocks.is_empty() {
+                continue;
+            }
+            loop {
+                let mut round = fold_branches(func);
+                round |= cfg::remove_unreachable(func);
+                round |= thread_empty_blocks(func);
+                round |= cfg::remove_unreachable(func);
+                round |= merge_blocks(func);
+                if !round {
+                    break;
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn fold_branches(func: &mut AirFunction) -> bool {
+    let mut changed = false;
+    let mut dropped: Vec<(BlockId, BlockId)> = Vec::new();
+    for block in func.blocks.iter_mut() {
+        let before = cfg::successors(&block.terminator);
+        let target = match &block.terminator {
+            AirTerminator::Branch {
+                cond: Operand::Const(AirConst::Bool(b)),
+                then_block,
+                else_block,
+            } => Some(if *b { *then_block } else { *else_block }),
+            AirTerminator::Branch {
+                then_block,
+                else_block,
+                ..
+            } if then_block == else_block => Some(*then_block),
+            AirTerminator::Switch {
+                discr: Operand::Const(c),
+                targets,
+                default,
+            } => switch_target(c, targets).or(Some(*default)),
+            AirTerminator::Switch {
+                targets, default, ..
+            } if targets.is_empty() => Some(*default),
+            _ => None,
+        };
+        if let Some(target) = target {
+            block.terminator = AirTerminator::Goto(target);
+            dropped.extend(
+                before
+                    .into_iter()
+                    .filter(|b| *b != target)
+                    .map(|succ| (block.id, succ)),
+            );
+            changed = true;
+        }
+    }
+    for (pred, succ) in dropped {
+        drop_phi_inputs(func, succ, pred);
+    }
+    changed
+}
+
+// None when the discriminant can't be compared at compile time
+fn switch_target(discr: &AirConst, targets: &[(AirConst, BlockId)]) -> Option<BlockId> {
+    let key = |c: &AirConst| match c {
+        AirConst::IntLiteral(v) | AirConst::Int(v, _) => Some(*v),
+        AirConst::Bool(b) => Some(*b as i64),
+        _ => None,
+    };
+    let d = key(discr)?;
+    for (c, target) in targets {
+        if key(c)? == d {
+            return Some(*target);
+        }
+    }
+    None
+}
+
+fn drop_phi_inputs(func: &mut AirFunction, block: BlockId, pred: BlockId) {
+    let Some(block) = func.blocks.iter_mut().find(|b| b.id == block) else {
+        return;
+    };
+    for stmt in block.stmts.iter_mut() {
+        if let AirStmtKind::Assign {
+            rvalue: Rvalue::Phi(inputs),
+            ..
+        } = &mut stmt.kind
+        {
+            inputs.retain(|(p, _)| *p != pred);
+        }
+    }
+}
+
+fn has_phis(block: &AirBlock) -> bool {
+    block.stmts.first().is_some_and(visit::is_phi)
+}
+
+// `E: goto T` with nothing else in it: send E's predecessors straight to T
+fn thread_empty_blocks(func: &mut AirFunction) -> bool {
+    let entry = func.blocks[0].id;
+    let mut changed = false;
+    let forwards: Vec<(BlockId, BlockId)> = func
+        .blocks
+        .iter()
+        .filter(|b| b.id != entry && b.stmts.is_empty())
+        .filter_map(|b| match b.terminator {
+            AirTerminator::Goto(target) if target != b.id => Some((b.id, target)),
+            _ => None,
+        })
+        .collect();
+
+    for (empty, target) in forwards {
+        // a phi in the target tells its predecessors apart, keep those edges
+        if func
+            .blocks
+            .iter()
+            .find(|b| b.id == target)
+            .is_none_or(has_phis)
+        {
+            continue;
+        }
+        for block in func.blocks.iter_mut() {
+            if block.id != empty && cfg::successors(&block.terminator).contains(&empty) {
+                cfg::retarget(&mut block.terminator, empty, target);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+// `A: ...; goto B` where A is B's only predecessor: glue B onto A
+fn merge_blocks(func: &mut AirFunction) -> bool {
+    let entry = func.blocks[0].id;
+    let mut changed = false;
+    loop {
+        let preds = cfg::predecessors(func);
+        let candidate = func.blocks.iter().find_map(|a| match a.terminator {
+            AirTerminator::Goto(b) if b != a.id && b != entry && preds[&b] == [a.id] => {
+                Some((a.id, b))
+            }
+            _ => None,
+        });
+        let Some((a, b)) = candidate else {
+            return changed;
+        };
+
+        let b_index = func.blocks.iter().position(|blk| blk.id == b).unwrap();
+        let mut merged = func.blocks.remove(b_index);
+        // a single predecessor makes every phi a plain copy
+        for stmt in merged.stmts.iter_mut() {
+            if let AirStmtKind::Assign { place, rvalue } = &mut stmt.kind
+                && let Rvalue::Phi(inputs) = rvalue
+            {
+                let op = match inputs.iter().find(|(p, _)| *p == a) {
+                    Some((_, op)) => op.clone(),
+                    None => {
+                        let ty = match place {
+                            Place::Local(id) => visit::local_type(func, *id).cloned(),
+                            _ => None,
+                        };
+                        Operand::Const(AirConst::Undef(ty.unwrap_or(AirType::Void)))
+                    }
+                };
+                *rvalue = Rvalue::Use(op);
+            }
+        }
+        for succ in cfg::successors(&merged.terminator) {
+            rename_phi_pred(func, succ, b, a);
+        }
+
+        let a_block = func.blocks.iter_mut().find(|blk| blk.id == a).unwrap();
+        a_block.stmts.append(&mut merged.stmts);
+        a_block.terminator = merged.terminator;
+        changed = true;
+    }
+}
+
+fn rename_phi_pred(func: &mut AirFunction, block: BlockId, from: BlockId, to: BlockId) {
+    let Some(block) = func.blocks.iter_mut().find(|b| b.id == block) else {
+        return;
+    };
+    for stmt in block.stmts.iter_mut() {
+        if let AirStmtKind::Assign {
+            rvalue: Rvalue::Phi(inputs),
+            ..
+        } = &mut stmt.kind
+        {
+            for (pred, _) in inputs.iter_mut() {
+                if *pred == from {
+                    *pred = to;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,387 @@
+// SSA construction (Cytron et al.) and the way back out
+//
+// Every local that is only ever read through operands and written whole gets
+// one definition per assignment, with phis at the iterated dominance frontier.
+// Pinned locals (address taken, projected writes, ...) stay as they are.
+
+use super::AirPass;
+use super::cfg::{self, Dominators};
+use super::visit;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+pub struct SsaConstruction;
+
+impl AirPass for SsaConstruction {
+    fn name(&self) -> &'static str {
+        "ssa"
+    }
+
+    fn run(&mut self, program: &mut AirProgram) -> bool {
+        let mut changed = false;
+        for func in program.functions.iter_mut() {
+            if !func.is_extern && !func.blocks.is_empty() {
+                changed |= construct(func);
+            }
+        }
+        changed
+    }
+}
+
+fn construct(func: &mut AirFunction) -> bool {
+    cfg::remove_unreachable(func);
+    isolate_entry(func);
+
+    let pinned = visit::pinned_locals(func);
+    let candidates: Vec<LocalId> = func
+        .params
+        .iter()
+        .map(|p| p.id)
+        .chain(func.locals.iter().map(|l| l.id))
+        .filter(|id| !pinned.contains(id))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if candidates.is_empty() {
+        return false;
+    }
+    let is_candidate: HashSet<LocalId> = candidates.iter().copied().collect();
+
+    let doms = Dominators::compute(func);
+    let frontiers = doms.frontiers(func);
+    let entry = func.blocks[0].id;
+
+    // blocks that write each candidate
+    let mut def_blocks: HashMap<LocalId, HashSet<BlockId>> = HashMap::new();
+    for param in &func.params {
+        if is_candidate.contains(&param.id) {
+            def_blocks.entry(param.id).or_default().insert(entry);
+        }
+    }
+    for block in &func.blocks {
+        for stmt in &block.stmts {
+            if let AirStmtKind::Assign {
+                place: Place::Local(id),
+                ..
+            } = &stmt.kind
+                && is_candidate.contains(id)
+            {
+                def_blocks.entry(*id).or_default().insert(block.id);
+            }
+        }
+        if let AirTerminator::Invoke {
+            ret: Place::Local(id),
+            ..
+        } = &block.terminator
+            && is_candidate.contains(id)
+        {
+            def_blocks.entry(*id).or_default().insert(block.id);
+        }
+    }
+
+    // phi placement, in a stable order so output doesn't depend on hashing
+    let mut phi_vars: HashMap<BlockId, Vec<LocalId>> = HashMap::new();
+    let mut vars: Vec<_> = def_blocks.keys().copied().collect();
+    vars.sort_by_key(|id| id.0);
+    for var in vars {
+        let mut work: Vec<BlockId> = def_blocks[&var].iter().copied().collect();
+        let mut has_phi: HashSet<BlockId> = HashSet::new();
+        while let Some(block) = work.pop() {
+            let Some(df) = frontiers.get(&block) else {
+                continue;
+            };
+            let mut df: Vec<_> = df.iter().copied().collect();
+            df.sort_by_key(|b| b.0);
+            for target in df {
+                if has_phi.insert(target) {
+                    phi_vars.entry(target).or_default().push(var);
+                    if !def_blocks[&var].contains(&target) {
+                        work.push(target);
+                    }
+                }
+            }
+        }
+    }
+    for block in func.blocks.iter_mut() {
+        if let Some(vars) = phi_vars.get(&block.id) {
+            let phis = vars.iter().map(|var| AirStmt {
+                kind: AirStmtKind::Assign {
+                    place: Place::Local(*var),
+                    rvalue: Rvalue::Phi(Vec::new()),
+                },
+                span: None,
+            });
+            block.stmts.splice(0..0, phis);
+        }
+    }
+
+    let mut renamer = Renamer {
+        next_local: visit::next_local_id(func),
+        stacks: HashMap::new(),
+        new_locals: Vec::new(),
+        templates: func
+            .locals
+            .iter()
+            .map(|l| (l.id, l.clone()))
+            .chain(func.params.iter().map(|p| {
+                (
+                    p.id,
+                    AirLocal {
+                        id: p.id,
+                        ty: p.ty.clone(),
+                        name: Some(p.name.clone()),
+                        is_mut: false,
+                        span: p.span,
+                    },
+                )
+            }))
+            .collect(),
+        is_candidate,
+    };
+    for param in &func.params {
+        if renamer.is_candidate.contains(&param.id) {
+            renamer.stacks.entry(param.id).or_default().push(param.id);
+        }
+    }
+
+    let index: HashMap<BlockId, usize> = func
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (b.id, i))
+        .collect();
+    let tree = doms.tree();
+
+    // explicit stack: (block, entered?) so deep dominator trees don't recurse
+    let mut work = vec![(entry, false)];
+    let mut pushed: HashMap<BlockId, Vec<LocalId>> = HashMap::new();
+    while let Some((block_id, done)) = work.pop() {
+        if done {
+            for var in pushed.remove(&block_id).unwrap_or_default() {
+                renamer.stacks.get_mut(&var).map(Vec::pop);
+            }
+            continue;
+        }
+        let block = &mut func.blocks[index[&block_id]];
+        let phi_count = phi_vars.get(&block_id).map_or(0, Vec::len);
+        let mut defined = Vec::new();
+
+        for (i, stmt) in block.stmts.iter_mut().enumerate() {
+            if i >= phi_count {
+                visit::stmt_operands_mut(&mut stmt.kind, &mut |op| renamer.rename_use(op));
+            }
+            if let AirStmtKind::Assign {
+                place: Place::Local(id),
+                ..
+            } = &mut stmt.kind
+                && renamer.is_candidate.contains(id)
+            {
+                let var = *id;
+                *id = renamer.define(var);
+                defined.push(var);
+            }
+        }
+        visit::terminator_operands_mut(&mut block.terminator, &mut |op| renamer.rename_use(op));
+        if let AirTerminator::Invoke {
+            ret: Place::Local(id),
+            ..
+        } = &mut block.terminator
+            && renamer.is_candidate.contains(id)
+        {
+            let var = *id;
+            *id = renamer.define(var);
+            defined.push(var);
+        }
+
+        let mut succs = cfg::successors(&block.terminator);
+        succs.dedup();
+        let mut seen = HashSet::new();
+        for succ in succs {
+            if !seen.insert(succ) {
+                continue;
+            }
+            let Some(vars) = phi_vars.get(&succ) else {
+                continue;
+            };
+            let incoming: Vec<Operand> = vars.iter().map(|var| renamer.current(*var)).collect();
+            let succ_block = &mut func.blocks[index[&succ]];
+            for (stmt, op) in succ_block.stmts.iter_mut().zip(incoming) {
+                if let AirStmtKind::Assign {
+                    rvalue: Rvalue::Phi(inputs),
+                    ..
+                } = &mut stmt.kind
+                {
+                    inputs.push((block_id, op));
+                }
+            }
+        }
+
+        pushed.insert(block_id, defined);
+        work.push((block_id, true));
+        if let Some(children) = tree.get(&block_id) {
+            for child in children.iter().rev() {
+                work.push((*child, false));
+            }
+        }
+    }
+
+    func.locals.extend(renamer.new_locals);
+    true
+}
+
+// the entry block can't hold phis, so give it a fresh block when something
+// jumps back to it
+fn isolate_entry(func: &mut AirFunction) {
+    let entry = func.blocks[0].id;
+    if cfg::predecessors(func)[&entry].is_empty() {
+        return;
+    }
+    let id = BlockId(visit::next_block_id(func));
+    func.blocks.insert(
+        0,
+        AirBlock {
+            id,
+            stmts: Vec::new(),
+            terminator: AirTerminator::Goto(entry),
+        },
+    );
+}
+
+struct Renamer {
+    next_local: u32,
+    stacks: HashMap<LocalId, Vec<LocalId>>,
+    new_locals: Vec<AirLocal>,
+    templates: HashMap<LocalId, AirLocal>,
+    is_candidate: HashSet<LocalId>,
+}
+
+impl Renamer {
+    fn define(&mut self, var: LocalId) -> LocalId {
+        let id = LocalId(self.next_local);
+        self.next_local += 1;
+        let template = &self.templates[&var];
+        self.new_locals.push(AirLocal {
+            id,
+            ty: template.ty.clone(),
+            name: template.name.clone(),
+            is_mut: false,
+            span: template.span,
+        });
+        self.stacks.entry(var).or_default().push(id);
+        id
+    }
+
+    fn current(&self, var: LocalId) -> Operand {
+        match self.stacks.get(&var).and_then(|s| s.last()) {
+            Some(id) => Operand::Copy(*id),
+            None => Operand::Const(AirConst::Undef(self.templates[&var].ty.clone())),
+        }
+    }
+
+    fn rename_use(&self, op: &mut Operand) {
+        let (Operand::Copy(id) | Operand::Move(id)) = op else {
+            return;
+        };
+        if !self.is_candidate.contains(id) {
+            return;
+        }
+        match self.stacks.get(id).and_then(|s| s.last()) {
+            Some(current) => *id = *current,
+            // read before any write: the local still holds its initial value
+            None => *op = Operand::Const(AirConst::Undef(self.templates[id].ty.clone())),
+        }
+    }
+}
+
+pub struct SsaDestruction;
+
+impl AirPass for SsaDestruction {
+    fn name(&self) -> &'static str {
+        "out-of-ssa"
+    }
+
+    fn run(&mut self, program: &mut AirProgram) -> bool {
+        let mut changed = false;
+        for func in program.functions.iter_mut() {
+            changed |= destruct(func);
+        }
+        changed
+    }
+}
+
+// each phi becomes a temporary written at the end of every predecessor and
+// copied into the phi's local where the phi was; the temporaries keep loop
+// carried values from clobbering each other (swap / lost-copy problems)
+fn destruct(func: &mut AirFunction) -> bool {
+    let mut next_local = visit::next_local_id(func);
+    let mut next_block = visit::next_block_id(func);
+    let mut copies: HashMap<(BlockId, BlockId), Vec<AirStmt>> = HashMap::new();
+    let mut new_locals = Vec::new();
+
+    for block in func.blocks.iter_mut() {
+        for stmt in block.stmts.iter_mut() {
+            let AirStmtKind::Assign { place, rvalue } = &mut stmt.kind else {
+                break;
+            };
+            let Rvalue::Phi(inputs) = rvalue else {
+                break;
+            };
+            let Place::Local(dest) = place else {
+                continue;
+            };
+            let ty = func
+                .locals
+                .iter()
+                .find(|l| l.id == *dest)
+                .map(|l| l.ty.clone())
+                .unwrap_or(AirType::Void);
+            let temp = LocalId(next_local);
+            next_local += 1;
+            new_locals.push(AirLocal {
+                id: temp,
+                ty,
+                name: None,
+                is_mut: true,
+                span: None,
+            });
+            for (pred, op) in std::mem::take(inputs) {
+                copies.entry((pred, block.id)).or_default().push(AirStmt {
+                    kind: AirStmtKind::Assign {
+                        place: Place::Local(temp),
+                        rvalue: Rvalue::Use(op),
+                    },
+                    span: None,
+                });
+            }
+            *rvalue = Rvalue::Use(Operand::Copy(temp));
+        }
+    }
+    if copies.is_empty() {
+        return false;
+    }
+
+    let mut split_blocks = Vec::new();
+    let mut edges: Vec<_> = copies.into_iter().collect();
+    edges.sort_by_key(|((p, s), _)| (p.0, s.0));
+    for ((pred, succ), stmts) in edges {
+        let Some(block) = func.blocks.iter_mut().find(|b| b.id == pred) else {
+            continue;
+        };
+        // an invoke writes its result in the terminator, copies have to come after it
+        if matches!(block.terminator, AirTerminator::Invoke { .. }) {
+            let id = BlockId(next_block);
+            next_block += 1;
+            cfg::retarget(&mut block.terminator, succ, id);
+            split_blocks.push(AirBlock {
+                id,
+                stmts,
+                terminator: AirTerminator::Goto(succ),
+            });
+        } else {
+            block.stmts.extend(stmts);
+        }
+    }
+    func.blocks.extend(split_blocks);
+    func.locals.extend(new_locals);
+    true
+}
@@ -0,0 +1,254 @@
+// walking operands and locals inside a function
+
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+pub fn rvalue_operands_mut(rvalue: &mut Rvalue, f: &mut impl FnMut(&mut Operand)) {
+    match rvalue {
+        Rvalue::Use(op)
+        | Rvalue::UnaryOp(_, op)
+        | Rvalue::Deref(op)
+        | Rvalue::Discriminant(op)
+        | Rvalue::FieldAccess { base: op, .. }
+        | Rvalue::Cast { operand: op, .. } => f(op),
+        Rvalue::BinaryOp(_, a, b) => {
+            f(a);
+            f(b);
+        }
+        Rvalue::Call { args, .. } => args.iter_mut().for_each(f),
+        Rvalue::StructInit { fields, .. } => fields.iter_mut().for_each(|(_, op)| f(op)),
+        Rvalue::Phi(incoming) => incoming.iter_mut().for_each(|(_, op)| f(op)),
+        Rvalue::AddressOf(_) => {}
+    }
+}
+
+fn place_operands_mut(place: &mut Place, f: &mut impl FnMut(&mut Operand)) {
+    if let Place::Index(_, idx) = place {
+        f(idx);
+    }
+}
+
+pub fn stmt_operands_mut(kind: &mut AirStmtKind, f: &mut impl FnMut(&mut Operand)) {
+    match kind {
+        AirStmtKind::Assign { place, rvalue } => {
+            place_operands_mut(place, f);
+            rvalue_operands_mut(rvalue, f);
+        }
+        AirStmtKind::CallVoid { args, .. } => args.iter_mut().for_each(f),
+        AirStmtKind::GcAlloc { .. }
+        | AirStmtKind::GcDrop(_)
+        | AirStmtKind::ArenaCreate(_)
+        | AirStmtKind::ArenaDestroy(_)
+        | AirStmtKind::Alloc { .. }
+        | AirStmtKind::Free(_)
+        | AirStmtKind::MemoryFence(_) => {}
+    }
+}
+
+pub fn terminator_operands_mut(term: &mut AirTerminator, f: &mut impl FnMut(&mut Operand)) {
+    match term {
+        AirTerminator::Return(Some(op))
+        | AirTerminator::Branch { cond: op, .. }
+        | AirTerminator::Switch { discr: op, .. } => f(op),
+        AirTerminator::Invoke { args, ret, .. } => {
+            args.iter_mut().for_each(&mut *f);
+            place_operands_mut(ret, f);
+        }
+        AirTerminator::Return(None)
+        | AirTerminator::Goto(_)
+        | AirTerminator::Unwind
+        | AirTerminator::Unreachable
+        | AirTerminator::Panic { .. } => {}
+    }
+}
+
+pub fn for_each_operand_mut(func: &mut AirFunction, mut f: impl FnMut(&mut Operand)) {
+    for block in &mut func.blocks {
+        for stmt in &mut block.stmts {
+            stmt_operands_mut(&mut stmt.kind, &mut f);
+        }
+        terminator_operands_mut(&mut block.terminator, &mut f);
+    }
+}
+
+fn place_base(place: &Place) -> LocalId {
+    match place {
+        Place::Local(id) | Place::Field(id, _) | Place::Deref(id) | Place::Index(id, _) => *id,
+    }
+}
+
+fn callee_local(callee: &Callee) -> Option<LocalId> {
+    match callee {
+        Callee::FnPtr(id) => Some(*id),
+        _ => None,
+    }
+}
+
+// locals referenced other than through an operand or a whole-local write:
+// address taken, written through a place projection, called through, freed.
+// Passes leave these alone.
+pub fn pinned_locals(func: &AirFunction) -> HashSet<LocalId> {
+    let mut pinned = HashSet::new();
+    let place = |p: &Place, pinned: &mut HashSet<LocalId>| {
+        if !matches!(p, Place::Local(_)) {
+            pinned.insert(place_base(p));
+        }
+    };
+    for block in &func.blocks {
+        for stmt in &block.stmts {
+            match &stmt.kind {
+                AirStmtKind::Assign { place: p, rvalue } => {
+                    place(p, &mut pinned);
+                    match rvalue {
+                        Rvalue::AddressOf(id) => {
+                            pinned.insert(*id);
+                        }
+                        Rvalue::Call { func, .. } => pinned.extend(callee_local(func)),
+                        _ => {}
+                    }
+                }
+                AirStmtKind::CallVoid { func, .. } => pinned.extend(callee_local(func)),
+                AirStmtKind::GcAlloc { local, .. }
+                | AirStmtKind::Alloc { local, .. }
+                | AirStmtKind::GcDrop(local)
+                | AirStmtKind::Free(local) => {
+                    pinned.insert(*local);
+                }
+                AirStmtKind::ArenaCreate(_)
+                | AirStmtKind::ArenaDestroy(_)
+                | AirStmtKind::MemoryFence(_) => {}
+            }
+        }
+        if let AirTerminator::Invoke { func, ret, .. } = &block.terminator {
+            place(ret, &mut pinned);
+            pinned.extend(callee_local(func));
+        }
+    }
+    pinned
+}
+
+// whole-local writes, parameters count as one
+pub fn def_counts(func: &AirFunction) -> HashMap<LocalId, usize> {
+    let mut defs: HashMap<LocalId, usize> = HashMap::new();
+    for param in &func.params {
+        *defs.entry(param.id).or_default() += 1;
+    }
+    for block in &func.blocks {
+        for stmt in &block.stmts {
+            if let AirStmtKind::Assign {
+                place: Place::Local(id),
+                ..
+            } = &stmt.kind
+            {
+                *defs.entry(*id).or_default() += 1;
+            }
+        }
+        if let AirTerminator::Invoke {
+            ret: Place::Local(id),
+            ..
+        } = &block.terminator
+        {
+            *defs.entry(*id).or_default() += 1;
+        }
+    }
+    defs
+}
+
+// locals that are read anywhere: operands, projections, pinned uses
+pub fn used_locals(func: &AirFunction) -> HashSet<LocalId> {
+    let mut used = pinned_locals(func);
+    let mut read = |op: &Operand| {
+        if let Operand::Copy(id) | Operand::Move(id) = op {
+            used.insert(*id);
+        }
+    };
+    for block in &func.blocks {
+        for stmt in &block.stmts {
+            match &stmt.kind {
+                AirStmtKind::Assign { place, rvalue } => {
+                    if let Place::Index(_, idx) = place {
+                        read(idx);
+                    }
+                    match rvalue {
+                        Rvalue::Use(op)
+                        | Rvalue::UnaryOp(_, op)
+                        | Rvalue::Deref(op)
+                        | Rvalue::Discriminant(op)
+                        | Rvalue::FieldAccess { base: op, .. }
+                        | Rvalue::Cast { operand: op, .. } => read(op),
+                        Rvalue::BinaryOp(_, a, b) => {
+                            read(a);
+                            read(b);
+                        }
+                        Rvalue::Call { args, .. } => args.iter().for_each(&mut read),
+                        Rvalue::StructInit { fields, .. } => {
+                            fields.iter().for_each(|(_, op)| read(op))
+                        }
+                        Rvalue::Phi(incoming) => incoming.iter().for_each(|(_, op)| read(op)),
+                        Rvalue::AddressOf(_) => {}
+                    }
+                }
+                AirStmtKind::CallVoid { args, .. } => args.iter().for_each(&mut read),
+                _ => {}
+            }
+        }
+        match &block.terminator {
+            AirTerminator::Return(Some(op))
+            | AirTerminator::Branch { cond: op, .. }
+            | AirTerminator::Switch { discr: op, .. } => read(op),
+            AirTerminator::Invoke { args, ret, .. } => {
+                args.iter().for_each(&mut read);
+                if let Place::Index(_, idx) = ret {
+                    read(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    used
+}
+
+pub fn local_type(func: &AirFunction, id: LocalId) -> Option<&AirType> {
+    func.params
+        .iter()
+        .find(|p| p.id == id)
+        .map(|p| &p.ty)
+        .or_else(|| func.locals.iter().find(|l| l.id == id).map(|l| &l.ty))
+}
+
+pub fn next_local_id(func: &AirFunction) -> u32 {
+    func.params
+        .iter()
+        .map(|p| p.id.0)
+        .chain(func.locals.iter().map(|l| l.id.0))
+        .max()
+        .map_or(0, |m| m + 1)
+}
+
+pub fn next_block_id(func: &AirFunction) -> u32 {
+    func.blocks
+        .iter()
+        .map(|b| b.id.0)
+        .max()
+        .map_or(0, |m| m + 1)
+}
+
+pub fn is_phi(stmt: &AirStmt) -> bool {
+    matches!(
+        stmt.kind,
+        AirStmtKind::Assign {
+            rvalue: Rvalue::Phi(_),
+            ..
+        }
+    )
+}
+
+// phis have to stay at the top of their block; after some of them were
+// resolved into plain assignments, move the rest back up
+pub fn hoist_phis(block: &mut AirBlock) {
+    let (phis, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut block.stmts)
+        .into_iter()
+        .partition(is_phi);
+    block.stmts = phis;
+    block.stmts.extend(rest);
+}
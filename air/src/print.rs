@@ -19,6 +19,10 @@ pub fn fmt_type(ty: &AirType) -> String {
         AirType::Void => "void".into(),
         AirType::Ptr(inner) => format!("*{}", fmt_type(inner)),
         AirType::Struct(name) => name.clone(),
+        AirType::GenericStruct { name, args } => {
+            let args: Vec<_> = args.iter().map(fmt_type).collect();
+            format!("{}<{}>", name, args.join(", "))
+        }
         AirType::Array(inner, len) => format!("[{}; {}]", fmt_type(inner), len),
         AirType::Slice(inner) => format!("[{}]", fmt_type(inner)),
         AirType::FnPtr { params, ret, .. } => {
@@ -86,7 +90,7 @@ fn fmt_operand(op: &Operand, func: &AirFunction) -> String {
     }
 }
 
-fn fmt_binop(op: &BinOp) -> &'static str {
+pub fn fmt_binop(op: &BinOp) -> &'static str {
     match op {
         BinOp::Add => "add",
         BinOp::Sub => "sub",
@@ -217,6 +221,13 @@ fn fmt_rvalue(rv: &Rvalue, func: &AirFunction, program: &AirProgram) -> String {
             format!("cast {} -> {}", fmt_operand(operand, func), fmt_type(to))
         }
         Rvalue::Discriminant(op) => format!("discriminant {}", fmt_operand(op, func)),
+        Rvalue::Phi(incoming) => {
+            let parts: Vec<_> = incoming
+                .iter()
+                .map(|(block, op)| format!("block{}: {}", block.0, fmt_operand(op, func)))
+                .collect();
+            format!("phi [{}]", parts.join(", "))
+        }
     }
 }
 
@@ -0,0 +1,844 @@
+// AIR verifier
+//
+// Checks a program the way `layout::compute_layouts` and `mono::monomorphize`
+// leave it: operand types against the declared locals, definite assignment,
+// control flow targets, direct call arity, struct field names, and that no
+// type parameter is left outside a generic template. Templates themselves
+// (anything with `type_params`) are skipped, mono never hands them to a backend.
+//
+// `dynamic` lowers to i64, so an i64 on either side of a check could be
+// anything and is let through, as are untyped int literals into any integer and
+// `null` into anything. Mixed int/float arithmetic and `str + x` are fine too,
+// the VM does those. Dead blocks (code after a `return`) are left alone.
+
+use crate::print::fmt_type;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AirError {
+    pub message: String,
+    pub function: Option<String>,
+    pub block: Option<BlockId>,
+}
+
+impl fmt::Display for AirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.function, self.block) {
+            (Some(func), Some(block)) => {
+                write!(
+                    f,
+                    "in function `{}`, block{}: {}",
+                    func, block.0, self.message
+                )
+            }
+            (Some(func), None) => write!(f, "in function `{}`: {}", func, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for AirError {}
+
+pub fn verify(program: &AirProgram) -> Vec<AirError> {
+    let mut errors = Vec::new();
+    let mut top = |message: String| {
+        errors.push(AirError {
+            message,
+            function: None,
+            block: None,
+        })
+    };
+
+    let mut struct_names = HashSet::new();
+    for def in &program.structs {
+        if !struct_names.insert(def.name.as_str()) {
+            top(format!("struct `{}` is defined twice", def.name));
+        }
+        if !def.type_params.is_empty() {
+            continue;
+        }
+        for field in &def.fields {
+            if has_type_param(&field.ty) {
+                top(format!(
+                    "field `{}.{}` still has a type parameter after monomorphization",
+                    def.name, field.name
+                ));
+            }
+        }
+    }
+    for global in &program.globals {
+        if has_type_param(&global.ty) {
+            top(format!(
+                "global `{}` still has a type parameter after monomorphization",
+                global.name
+            ));
+        }
+    }
+
+    let mut ids = HashSet::new();
+    for func in &program.functions {
+        if !ids.insert(func.id) {
+            top(format!("function id {} is used twice", func.id.0));
+        }
+    }
+
+    for func in &program.functions {
+        if func.type_params.is_empty() {
+            FunctionVerifier::new(program, func, &mut errors).run();
+        }
+    }
+    errors
+}
+
+fn has_type_param(ty: &AirType) -> bool {
+    match ty {
+        AirType::Param(_) | AirType::GenericStruct { .. } => true,
+        AirType::Ptr(inner) | AirType::Array(inner, _) | AirType::Slice(inner) => {
+            has_type_param(inner)
+        }
+        AirType::FnPtr { params, ret, .. } => {
+            params.iter().any(has_type_param) || has_type_param(ret)
+        }
+        _ => false,
+    }
+}
+
+fn is_float(ty: &AirType) -> bool {
+    matches!(ty, AirType::F32 | AirType::F64)
+}
+
+fn is_int(ty: &AirType) -> bool {
+    matches!(
+        ty,
+        AirType::I8
+            | AirType::I16
+            | AirType::I32
+            | AirType::I64
+            | AirType::U8
+            | AirType::U16
+            | AirType::U32
+            | AirType::U64
+    )
+}
+
+// what an operand or rvalue is known to produce
+#[derive(Clone)]
+enum Ty {
+    Known(AirType),
+    IntLiteral,
+    // null, runtime helpers, anything the verifier can't see through
+    Any,
+}
+
+impl Ty {
+    fn fits(&self, expected: &AirType) -> bool {
+        match self {
+            Ty::Known(ty) => ty == expected || *ty == AirType::I64 || *expected == AirType::I64,
+            Ty::IntLiteral => is_int(expected),
+            Ty::Any => true,
+        }
+    }
+
+    fn agrees_with(&self, other: &Ty) -> bool {
+        match (self, other) {
+            (Ty::Known(a), b) | (b, Ty::Known(a)) => b.fits(a),
+            _ => true,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Ty::Known(ty) => fmt_type(ty),
+            Ty::IntLiteral => "int literal".into(),
+            Ty::Any => "unknown".into(),
+        }
+    }
+}
+
+fn const_ty(c: &AirConst) -> Ty {
+    Ty::Known(match c {
+        AirConst::IntLiteral(_) => return Ty::IntLiteral,
+        AirConst::Int(_, size) => match size {
+            AirIntSize::I8 => AirType::I8,
+            AirIntSize::I16 => AirType::I16,
+            AirIntSize::I32 => AirType::I32,
+            AirIntSize::I64 => AirType::I64,
+            AirIntSize::U8 => AirType::U8,
+            AirIntSize::U16 => AirType::U16,
+            AirIntSize::U32 => AirType::U32,
+            AirIntSize::U64 => AirType::U64,
+        },
+        AirConst::Float(_, AirFloatSize::F32) => AirType::F32,
+        AirConst::Float(_, AirFloatSize::F64) => AirType::F64,
+        AirConst::Bool(_) => AirType::Bool,
+        AirConst::Str(_) => AirType::Str,
+        AirConst::Null => return Ty::Any,
+        AirConst::ZeroInit(ty) | AirConst::Undef(ty) => ty.clone(),
+    })
+}
+
+struct FunctionVerifier<'a, 'e> {
+    program: &'a AirProgram,
+    func: &'a AirFunction,
+    errors: &'e mut Vec<AirError>,
+    locals: HashMap<LocalId, &'a AirType>,
+    block: Option<BlockId>,
+}
+
+impl<'a, 'e> FunctionVerifier<'a, 'e> {
+    fn new(program: &'a AirProgram, func: &'a AirFunction, errors: &'e mut Vec<AirError>) -> Self {
+        Self {
+            program,
+            func,
+            errors,
+            locals: HashMap::new(),
+            block: None,
+        }
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(AirError {
+            message: message.into(),
+            function: Some(self.func.name.clone()),
+            block: self.block,
+        });
+    }
+
+    fn run(&mut self) {
+        self.check_signature();
+        if self.func.is_extern {
+            return;
+        }
+        if self.func.blocks.is_empty() {
+            self.error("function has no blocks");
+            return;
+        }
+        let reachable = self.check_control_flow();
+        for block in &self.func.blocks {
+            self.block = Some(block.id);
+            for stmt in &block.stmts {
+                self.check_stmt(&stmt.kind);
+            }
+            self.check_terminator(&block.terminator);
+        }
+        self.block = None;
+        self.check_definite_assignment(&reachable);
+    }
+
+    fn check_signature(&mut self) {
+        let func = self.func;
+        let params: HashMap<LocalId, &AirType> =
+            func.params.iter().map(|p| (p.id, &p.ty)).collect();
+        let mut declared = Vec::new();
+        declared.extend(func.params.iter().map(|p| (p.id, &p.ty, true)));
+        declared.extend(func.locals.iter().map(|l| (l.id, &l.ty, false)));
+        for (id, ty, is_param) in declared {
+            // lowering mirrors params into `locals`, that's fine as long as they agree
+            let mirrored = !is_param && params.get(&id) == Some(&ty);
+            if self.locals.insert(id, ty).is_some() && !mirrored {
+                self.error(format!("%{} is declared twice", id.0));
+            }
+            if mirrored {
+                continue;
+            }
+            if has_type_param(ty) {
+                self.error(format!(
+                    "%{} still has a type parameter after monomorphization",
+                    id.0
+                ));
+            }
+        }
+        if has_type_param(&func.ret_ty) {
+            self.error("return type still has a type parameter after monomorphization");
+        }
+    }
+
+    // returns the blocks reachable from the entry
+    fn check_control_flow(&mut self) -> HashSet<BlockId> {
+        let mut known = HashSet::new();
+        for block in &self.func.blocks {
+            if !known.insert(block.id) {
+                self.block = Some(block.id);
+                self.error(format!("block{} is defined twice", block.id.0));
+            }
+        }
+        for block in &self.func.blocks {
+            self.block = Some(block.id);
+            for target in successors(&block.terminator) {
+                if !known.contains(&target) {
+                    self.error(format!("jumps to missing block{}", target.0));
+                }
+            }
+        }
+        self.block = None;
+
+        let succs: HashMap<BlockId, Vec<BlockId>> = self
+            .func
+            .blocks
+            .iter()
+            .map(|b| (b.id, successors(&b.terminator)))
+            .collect();
+        let entry = self.func.blocks[0].id;
+        let mut reachable = HashSet::from([entry]);
+        let mut work = vec![entry];
+        while let Some(block) = work.pop() {
+            for succ in succs.get(&block).into_iter().flatten() {
+                if succs.contains_key(succ) && reachable.insert(*succ) {
+                    work.push(*succ);
+                }
+            }
+        }
+        for block in &self.func.blocks {
+            if reachable.contains(&block.id)
+                && matches!(block.terminator, AirTerminator::Unreachable)
+            {
+                self.block = Some(block.id);
+                self.error("`unreachable` terminator can be reached from the entry");
+            }
+        }
+        self.block = None;
+        reachable
+    }
+
+    fn local_ty(&mut self, id: LocalId) -> Option<&'a AirType> {
+        let ty = self.locals.get(&id).copied();
+        if ty.is_none() {
+            self.error(format!("%{} is not declared", id.0));
+        }
+        ty
+    }
+
+    fn operand_ty(&mut self, op: &Operand) -> Ty {
+        match op {
+            Operand::Copy(id) | Operand::Move(id) => match self.local_ty(*id) {
+                Some(ty) => Ty::Known(ty.clone()),
+                None => Ty::Any,
+            },
+            Operand::Const(c) => const_ty(c),
+        }
+    }
+
+    fn expect(&mut self, actual: &Ty, expected: &AirType, what: impl fmt::Display) {
+        if !actual.fits(expected) {
+            self.error(format!(
+                "{} expects {}, got {}",
+                what,
+                fmt_type(expected),
+                actual.describe()
+            ));
+        }
+    }
+
+    fn struct_def(&mut self, name: &str) -> Option<&'a AirStructDef> {
+        let def = self.program.structs.iter().find(|s| s.name == name);
+        if def.is_none() {
+            self.error(format!("struct `{}` is not defined", name));
+        }
+        def
+    }
+
+    fn field_ty(&mut self, base: &AirType, field: &str) -> Option<AirType> {
+        let name = match base {
+            AirType::Struct(name) => name,
+            AirType::Ptr(inner) => match &**inner {
+                AirType::Struct(name) => name,
+                _ => {
+                    self.error(format!(
+                        "field `{}` of non-struct {}",
+                        field,
+                        fmt_type(base)
+                    ));
+                    return None;
+                }
+            },
+            // dynamic values lower to i64, their fields are resolved at runtime
+            AirType::I64 => return None,
+            _ => {
+                self.error(format!(
+                    "field `{}` of non-struct {}",
+                    field,
+                    fmt_type(base)
+                ));
+                return None;
+            }
+        };
+        let def = self.struct_def(name)?;
+        match def.fields.iter().find(|f| f.name == field) {
+            Some(f) => Some(f.ty.clone()),
+            None => {
+                self.error(format!("struct `{}` has no field `{}`", name, field));
+                None
+            }
+        }
+    }
+
+    fn place_ty(&mut self, place: &Place) -> Ty {
+        match place {
+            Place::Local(id) => match self.local_ty(*id) {
+                Some(ty) => Ty::Known(ty.clone()),
+                None => Ty::Any,
+            },
+            Place::Field(id, field) => {
+                let Some(base) = self.local_ty(*id) else {
+                    return Ty::Any;
+                };
+                self.field_ty(base, field).map_or(Ty::Any, Ty::Known)
+            }
+            Place::Deref(id) => match self.local_ty(*id) {
+                Some(AirType::Ptr(inner)) => Ty::Known((**inner).clone()),
+                Some(AirType::I64) | None => Ty::Any,
+                Some(other) => {
+                    self.error(format!(
+                        "deref of non-pointer %{}: {}",
+                        id.0,
+                        fmt_type(other)
+                    ));
+                    Ty::Any
+                }
+            },
+            Place::Index(id, index) => {
+                let index = self.operand_ty(index);
+                if let Ty::Known(ty) = &index
+                    && !is_int(ty)
+                {
+                    self.error(format!("index into %{} is {}", id.0, fmt_type(ty)));
+                }
+                match self.local_ty(*id) {
+                    Some(AirType::Array(inner, _) | AirType::Slice(inner)) => {
+                        Ty::Known((**inner).clone())
+                    }
+                    _ => Ty::Any,
+                }
+            }
+        }
+    }
+
+    fn check_call(&mut self, callee: &Callee, args: &[Operand]) -> Ty {
+        let arg_tys: Vec<Ty> = args.iter().map(|a| self.operand_ty(a)).collect();
+        let target = match callee {
+            Callee::Direct(id) => {
+                let target = self.program.functions.iter().find(|f| f.id == *id);
+                if target.is_none() {
+                    self.error(format!("call to missing function id {}", id.0));
+                }
+                target
+            }
+            // lowering calls program functions by name, runtime helpers have no definition here
+            Callee::Named(name) => self.program.functions.iter().find(|f| f.name == *name),
+            Callee::FnPtr(id) => {
+                self.local_ty(*id);
+                None
+            }
+            Callee::Extern(..) => None,
+        };
+        match target {
+            Some(target) => {
+                if target.params.len() != args.len() {
+                    self.error(format!(
+                        "`{}` takes {} argument{}, called with {}",
+                        target.name,
+                        target.params.len(),
+                        if target.params.len() == 1 { "" } else { "s" },
+                        args.len()
+                    ));
+                } else if target.type_params.is_empty() {
+                    for (i, (param, arg)) in target.params.iter().zip(&arg_tys).enumerate() {
+                        self.expect(
+                            arg,
+                            &param.ty,
+                            format_args!("argument {} of `{}`", i + 1, target.name),
+                        );
+                    }
+                }
+                if target.type_params.is_empty() {
+                    Ty::Known(target.ret_ty.clone())
+                } else {
+                    Ty::Any
+                }
+            }
+            None => Ty::Any,
+        }
+    }
+
+    fn rvalue_ty(&mut self, rvalue: &Rvalue) -> Ty {
+        match rvalue {
+            Rvalue::Use(op) => self.operand_ty(op),
+            Rvalue::BinaryOp(op, lhs, rhs) => {
+                let l = self.operand_ty(lhs);
+                let r = self.operand_ty(rhs);
+                let shift = matches!(op, BinOp::Shl | BinOp::Shr);
+                let numeric = |t: &Ty| match t {
+                    Ty::Known(ty) => is_int(ty) || is_float(ty),
+                    _ => true,
+                };
+                let concat = matches!(op, BinOp::Add)
+                    && [&l, &r]
+                        .iter()
+                        .any(|t| matches!(t, Ty::Known(AirType::Str)));
+                let mixed = (numeric(&l) && numeric(&r)) || concat;
+                if !shift && !mixed && !l.agrees_with(&r) {
+                    self.error(format!(
+                        "binop {} between {} and {}",
+                        print::fmt_binop(op),
+                        l.describe(),
+                        r.describe()
+                    ));
+                }
+                match op {
+                    BinOp::Eq
+                    | BinOp::Ne
+                    | BinOp::Lt
+                    | BinOp::Le
+                    | BinOp::Gt
+                    | BinOp::Ge
+                    | BinOp::And
+                    | BinOp::Or => Ty::Known(AirType::Bool),
+                    _ if shift => l,
+                    _ if concat => Ty::Known(AirType::Str),
+                    _ => match (l, r) {
+                        (Ty::Known(ty), _) | (_, Ty::Known(ty)) if is_float(&ty) => Ty::Known(ty),
+                        (Ty::Known(ty), _) | (_, Ty::Known(ty)) => Ty::Known(ty),
+                        (Ty::IntLiteral, Ty::IntLiteral) => Ty::IntLiteral,
+                        _ => Ty::Any,
+                    },
+                }
+            }
+            Rvalue::UnaryOp(_, op) => self.operand_ty(op),
+            Rvalue::Call { func, args } => self.check_call(func, args),
+            Rvalue::StructInit { name, fields } => {
+                let Some(def) = self.struct_def(name) else {
+                    return Ty::Any;
+                };
+                for (field, value) in fields {
+                    let value = self.operand_ty(value);
+                    match def.fields.iter().find(|f| f.name == *field) {
+                        Some(f) => {
+                            if def.type_params.is_empty() {
+                                self.expect(
+                                    &value,
+                                    &f.ty,
+                                    format_args!("field `{}.{}`", name, field),
+                                );
+                            }
+                        }
+                        None => self.error(format!("struct `{}` has no field `{}`", name, field)),
+                    }
+                }
+                Ty::Known(AirType::Struct(name.clone()))
+            }
+            Rvalue::FieldAccess { base, field } => match self.operand_ty(base) {
+                Ty::Known(base) => self.field_ty(&base, field).map_or(Ty::Any, Ty::Known),
+                _ => Ty::Any,
+            },
+            Rvalue::AddressOf(id) => match self.local_ty(*id) {
+                Some(ty) => Ty::Known(AirType::Ptr(Box::new(ty.clone()))),
+                None => Ty::Any,
+            },
+            Rvalue::Deref(op) => match self.operand_ty(op) {
+                Ty::Known(AirType::Ptr(inner)) => Ty::Known(*inner),
+                _ => Ty::Any,
+            },
+            Rvalue::Cast { operand, from, to } => {
+                let actual = self.operand_ty(operand);
+                self.expect(&actual, from, "cast");
+                Ty::Known(to.clone())
+            }
+            Rvalue::Discriminant(op) => {
+                self.operand_ty(op);
+                Ty::Any
+            }
+            Rvalue::Phi(inputs) => {
+                let mut ty = Ty::Any;
+                for (_, op) in inputs {
+                    let input = self.operand_ty(op);
+                    if !input.agrees_with(&ty) {
+                        self.error(format!(
+                            "phi mixes {} and {}",
+                            ty.describe(),
+                            input.describe()
+                        ));
+                    }
+                    if let Ty::Known(_) = input {
+                        ty = input;
+                    }
+                }
+                ty
+            }
+        }
+    }
+
+    fn check_stmt(&mut self, kind: &AirStmtKind) {
+        match kind {
+            AirStmtKind::Assign { place, rvalue } => {
+                let value = self.rvalue_ty(rvalue);
+                if let Ty::Known(dest) = self.place_ty(place) {
+                    self.expect(&value, &dest, "assignment");
+                }
+            }
+            AirStmtKind::GcAlloc { local, .. }
+            | AirStmtKind::Alloc { local, .. }
+            | AirStmtKind::GcDrop(local)
+            | AirStmtKind::Free(local) => {
+                self.local_ty(*local);
+            }
+            AirStmtKind::CallVoid { func, args } => {
+                self.check_call(func, args);
+            }
+            AirStmtKind::ArenaCreate(_)
+            | AirStmtKind::ArenaDestroy(_)
+            | AirStmtKind::MemoryFence(_) => {}
+        }
+    }
+
+    fn check_terminator(&mut self, term: &AirTerminator) {
+        match term {
+            AirTerminator::Return(Some(op)) => {
+                let actual = self.operand_ty(op);
+                // untyped returns (`return null` on one path, a value on another) lower to void
+                if self.func.ret_ty != AirType::Void {
+                    let ret = self.func.ret_ty.clone();
+                    self.expect(&actual, &ret, "return");
+                }
+            }
+            AirTerminator::Return(None) => {}
+            AirTerminator::Branch { cond, .. } => {
+                let cond = self.operand_ty(cond);
+                self.expect(&cond, &AirType::Bool, "branch condition");
+            }
+            AirTerminator::Switch { discr, .. } => {
+                self.operand_ty(discr);
+            }
+            AirTerminator::Invoke {
+                func, args, ret, ..
+            } => {
+                let value = self.check_call(func, args);
+                if let Ty::Known(dest) = self.place_ty(ret) {
+                    self.expect(&value, &dest, "invoke result");
+                }
+            }
+            AirTerminator::Goto(_)
+            | AirTerminator::Unwind
+            | AirTerminator::Unreachable
+            | AirTerminator::Panic { .. } => {}
+        }
+    }
+
+    // forward "definitely assigned" dataflow; params start assigned, taking a
+    // local's address counts as assigning it since the pointee can be written
+    fn check_definite_assignment(&mut self, reachable: &HashSet<BlockId>) {
+        let func = self.func;
+        let all: HashSet<LocalId> = self.locals.keys().copied().collect();
+        let entry = func.blocks[0].id;
+        let mut preds: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
+        for block in func.blocks.iter().filter(|b| reachable.contains(&b.id)) {
+            for succ in successors(&block.terminator) {
+                preds.entry(succ).or_default().push(block.id);
+            }
+        }
+
+        let mut out: HashMap<BlockId, HashSet<LocalId>> = HashMap::new();
+        let block_in = |block: BlockId, out: &HashMap<BlockId, HashSet<LocalId>>| {
+            if block == entry {
+                return func.params.iter().map(|p| p.id).collect();
+            }
+            let mut set: Option<HashSet<LocalId>> = None;
+            for pred in preds.get(&block).into_iter().flatten() {
+                let pred_out = out.get(pred).unwrap_or(&all);
+                set = Some(match set {
+                    None => pred_out.clone(),
+                    Some(s) => s.intersection(pred_out).copied().collect(),
+                });
+            }
+            set.unwrap_or_default()
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in func.blocks.iter().filter(|b| reachable.contains(&b.id)) {
+                let mut assigned = block_in(block.id, &out);
+                for stmt in &block.stmts {
+                    assign_stmt(&stmt.kind, &mut assigned);
+                }
+                assign_terminator(&block.terminator, &mut assigned);
+                if out.get(&block.id) != Some(&assigned) {
+                    out.insert(block.id, assigned);
+                    changed = true;
+                }
+            }
+        }
+
+        let mut reported = HashSet::new();
+        for block in func.blocks.iter().filter(|b| reachable.contains(&b.id)) {
+            let mut assigned = block_in(block.id, &out);
+            let mut missing = Vec::new();
+            for stmt in &block.stmts {
+                if let AirStmtKind::Assign {
+                    rvalue: Rvalue::Phi(inputs),
+                    ..
+                } = &stmt.kind
+                {
+                    for (pred, op) in inputs {
+                        if let Operand::Copy(id) | Operand::Move(id) = op
+                            && !out.get(pred).is_some_and(|s| s.contains(id))
+                        {
+                            missing.push(*id);
+                        }
+                    }
+                } else {
+                    stmt_uses(&stmt.kind, &mut |id| {
+                        if !assigned.contains(&id) {
+                            missing.push(id);
+                        }
+                    });
+                }
+                assign_stmt(&stmt.kind, &mut assigned);
+            }
+            terminator_uses(&block.terminator, &mut |id| {
+                if !assigned.contains(&id) {
+                    missing.push(id);
+                }
+            });
+
+            self.block = Some(block.id);
+            for id in missing {
+                if all.contains(&id) && reported.insert(id) {
+                    self.error(format!("%{} is used before it is assigned", id.0));
+                }
+            }
+        }
+        self.block = None;
+    }
+}
+
+fn successors(term: &AirTerminator) -> Vec<BlockId> {
+    match term {
+        AirTerminator::Goto(target) => vec![*target],
+        AirTerminator::Branch {
+            then_block,
+            else_block,
+            ..
+        } => vec![*then_block, *else_block],
+        AirTerminator::Switch {
+            targets, default, ..
+        } => targets
+            .iter()
+            .map(|(_, t)| *t)
+            .chain(std::iter::once(*default))
+            .collect(),
+        AirTerminator::Invoke { normal, unwind, .. } => vec![*normal, *unwind],
+        AirTerminator::Return(_)
+        | AirTerminator::Unwind
+        | AirTerminator::Unreachable
+        | AirTerminator::Panic { .. } => Vec::new(),
+    }
+}
+
+fn assign_stmt(kind: &AirStmtKind, assigned: &mut HashSet<LocalId>) {
+    match kind {
+        AirStmtKind::Assign { place, rvalue } => {
+            if let Place::Local(id) = place {
+                assigned.insert(*id);
+            }
+            if let Rvalue::AddressOf(id) = rvalue {
+                assigned.insert(*id);
+            }
+        }
+        AirStmtKind::GcAlloc { local, .. } | AirStmtKind::Alloc { local, .. } => {
+            assigned.insert(*local);
+        }
+        _ => {}
+    }
+}
+
+fn assign_terminator(term: &AirTerminator, assigned: &mut HashSet<LocalId>) {
+    if let AirTerminator::Invoke {
+        ret: Place::Local(id),
+        ..
+    } = term
+    {
+        assigned.insert(*id);
+    }
+}
+
+fn operand_use(op: &Operand, f: &mut impl FnMut(LocalId)) {
+    if let Operand::Copy(id) | Operand::Move(id) = op {
+        f(*id);
+    }
+}
+
+fn callee_use(callee: &Callee, f: &mut impl FnMut(LocalId)) {
+    if let Callee::FnPtr(id) = callee {
+        f(*id);
+    }
+}
+
+// writing through a field or index reads the base; a plain local store doesn't
+fn place_uses(place: &Place, f: &mut impl FnMut(LocalId)) {
+    match place {
+        Place::Local(_) => {}
+        Place::Field(id, _) | Place::Deref(id) => f(*id),
+        Place::Index(id, index) => {
+            f(*id);
+            operand_use(index, f);
+        }
+    }
+}
+
+fn stmt_uses(kind: &AirStmtKind, f: &mut impl FnMut(LocalId)) {
+    match kind {
+        AirStmtKind::Assign { place, rvalue } => {
+            place_uses(place, f);
+            match rvalue {
+                Rvalue::Use(op) | Rvalue::UnaryOp(_, op) | Rvalue::Deref(op) => operand_use(op, f),
+                Rvalue::BinaryOp(_, lhs, rhs) => {
+                    operand_use(lhs, f);
+                    operand_use(rhs, f);
+                }
+                Rvalue::Call { func, args } => {
+                    callee_use(func, f);
+                    args.iter().for_each(|a| operand_use(a, f));
+                }
+                Rvalue::StructInit { fields, .. } => {
+                    fields.iter().for_each(|(_, v)| operand_use(v, f));
+                }
+                Rvalue::FieldAccess { base, .. } => operand_use(base, f),
+                Rvalue::Cast { operand, .. } => operand_use(operand, f),
+                Rvalue::Discriminant(op) => operand_use(op, f),
+                Rvalue::AddressOf(_) | Rvalue::Phi(_) => {}
+            }
+        }
+        AirStmtKind::CallVoid { func, args } => {
+            callee_use(func, f);
+            args.iter().for_each(|a| operand_use(a, f));
+        }
+        AirStmtKind::GcDrop(id) | AirStmtKind::Free(id) => f(*id),
+        AirStmtKind::GcAlloc { .. }
+        | AirStmtKind::Alloc { .. }
+        | AirStmtKind::ArenaCreate(_)
+        | AirStmtKind::ArenaDestroy(_)
+        | AirStmtKind::MemoryFence(_) => {}
+    }
+}
+
+fn terminator_uses(term: &AirTerminator, f: &mut impl FnMut(LocalId)) {
+    match term {
+        AirTerminator::Return(Some(op))
+        | AirTerminator::Branch { cond: op, .. }
+        | AirTerminator::Switch { discr: op, .. } => operand_use(op, f),
+        AirTerminator::Invoke {
+            func, args, ret, ..
+        } => {
+            callee_use(func, f);
+            args.iter().for_each(|a| operand_use(a, f));
+            place_uses(ret, f);
+        }
+        AirTerminator::Return(None)
+        | AirTerminator::Goto(_)
+        | AirTerminator::Unwind
+        | AirTerminator::Unreachable
+        | AirTerminator::Panic { .. } => {}
+    }
+}
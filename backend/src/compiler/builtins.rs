@@ -2,9 +2,78 @@ use super::Compiler;
 
 impl Compiler {
     // VM intrinsics
-    pub const BUILTINS: &'static [&'static str] =
-        &["alloc", "free", "load", "store", "type", "__tostring"];
+    pub const BUILTINS: &'static [&'static str] = &[
+        "alloc",
+        "free",
+        "load",
+        "store",
+        "type",
+        "__tostring",
+        "assert",
+        "assert_eq",
+    ];
     pub fn is_builtin(name: &str) -> bool {
         Self::BUILTINS.contains(&name)
     }
+
+    // `s.method(args)` on a string compiles to `string::method(s, args)`;
+    // the count excludes the receiver
+    pub const STRING_METHODS: &'static [(&'static str, usize)] = &[
+        ("len", 0),
+        ("char_len", 0),
+        ("chars", 0),
+        ("bytes", 0),
+        ("to_upper", 0),
+        ("to_lower", 0),
+        ("capitalize", 0),
+        ("trim", 0),
+        ("trim_start", 0),
+        ("trim_end", 0),
+        ("is_empty", 0),
+        ("is_whitespace", 0),
+        ("is_numeric", 0),
+        ("is_alphabetic", 0),
+        ("is_alphanumeric", 0),
+        ("reverse", 0),
+        ("lines", 0),
+        ("line_count", 0),
+        ("char_at", 1),
+        ("byte_at", 1),
+        ("contains", 1),
+        ("starts_with", 1),
+        ("ends_with", 1),
+        ("find", 1),
+        ("rfind", 1),
+        ("count", 1),
+        ("split", 1),
+        ("repeat", 1),
+        ("concat", 1),
+        ("join", 1),
+        ("substr", 2),
+        ("replace", 2),
+        ("replace_first", 2),
+        ("pad_left", 2),
+        ("pad_right", 2),
+    ];
+
+    // `a.method(args)` on an Array or Vec compiles to `array::method(a, args)`.
+    // reverse, contains and join share their names with string methods; when
+    // the receiver's type isn't known the runtime picks by what it gets
+    pub const ARRAY_METHODS: &'static [(&'static str, usize)] = &[
+        ("sort", 0),
+        ("sort_by", 1),
+        ("sort_by_key", 1),
+        ("binary_search", 1),
+        ("min", 0),
+        ("max", 0),
+        ("reverse", 0),
+        ("swap", 2),
+        ("contains", 1),
+        ("index_of", 1),
+        ("insert", 2),
+        ("remove", 1),
+        ("extend", 1),
+        ("dedup", 0),
+        ("join", 1),
+    ];
 }
@@ -31,51 +31,9 @@ impl Compiler {
 
         // Check for Array/Vec method calls first
         if let TypedExprKind::Member { object, member } = &callee.kind {
-            // Handle Array methods
-            if let InferType::Array(_) = &object.ty
-                && member == "len"
-                && args.is_empty()
-            {
-                return self.compile_array_len(object, dest, span);
-            }
-
-            // Handle Vec methods
-            if let InferType::Vec(inner) = &object.ty {
-                match member.as_str() {
-                    "len" if args.is_empty() => {
-                        return self.compile_vec_len(object, dest, span);
-                    }
-                    "push" if args.len() == 1 => {
-                        return self.compile_vec_push(object, inner, &args[0], dest, span);
-                    }
-                    "pop" if args.is_empty() => {
-                        return self.compile_vec_pop(object, inner, dest, span);
-                    }
-                    "capacity" if args.is_empty() => {
-                        return self.compile_vec_capacity(object, dest, span);
-                    }
-                    "reserve" if args.len() == 1 => {
-                        return self.compile_vec_reserve(object, &args[0], dest, span);
-                    }
-                    _ => {}
-                }
-            }
-
-            // Handle String methods: s.method(args) → string::method(s, args...)
-            if matches!(&object.ty, InferType::String)
-                && let Some(expected_args) = Self::string_method_arity(member)
-                && args.len() == expected_args
-            {
-                return self.compile_string_method_call(object, member, args, dest, span);
-            }
-
-            // Handle to_string() on any type
-            if member == "to_string" && args.is_empty() {
-                return self.compile_tostring_method(object, dest, span);
-            }
-
-            // Module alias calls must be checked before Dynamic dispatch,
-            // otherwise methods like "join" get intercepted as string methods
+            // Module alias calls come before any method dispatch, otherwise
+            // mod.remove(x) or thread.join(t) get compiled as Array/String
+            // methods. Whatever path they take, they don't fall through
             if let TypedExprKind::Identifier(module_name) = &object.kind
                 && self.module_aliases.contains(module_name)
             {
@@ -138,6 +96,58 @@ impl Compiler {
                         return Ok(());
                     }
                 }
+                return self.compile_typed_call_fallback(callee, args, dest, span);
+            }
+
+            // Handle Array methods
+            if let InferType::Array(_) = &object.ty
+                && member == "len"
+                && args.is_empty()
+            {
+                return self.compile_array_len(object, dest, span);
+            }
+
+            // Handle Vec methods
+            if let InferType::Vec(inner) = &object.ty {
+                match member.as_str() {
+                    "len" if args.is_empty() => {
+                        return self.compile_vec_len(object, dest, span);
+                    }
+                    "push" if args.len() == 1 => {
+                        return self.compile_vec_push(object, inner, &args[0], dest, span);
+                    }
+                    "pop" if args.is_empty() => {
+                        return self.compile_vec_pop(object, inner, dest, span);
+                    }
+                    "capacity" if args.is_empty() => {
+                        return self.compile_vec_capacity(object, dest, span);
+                    }
+                    "reserve" if args.len() == 1 => {
+                        return self.compile_vec_reserve(object, &args[0], dest, span);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Handle the rest of the Array/Vec methods: a.method(args) → array::method(a, args...)
+            if matches!(&object.ty, InferType::Array(_) | InferType::Vec(_))
+                && let Some(expected_args) = Self::method_arity(Self::ARRAY_METHODS, member)
+                && args.len() == expected_args
+            {
+                return self.compile_native_method_call("array", object, member, args, dest, span);
+            }
+
+            // Handle String methods: s.method(args) → string::method(s, args...)
+            if matches!(&object.ty, InferType::String)
+                && let Some(expected_args) = Self::method_arity(Self::STRING_METHODS, member)
+                && args.len() == expected_args
+            {
+                return self.compile_native_method_call("string", object, member, args, dest, span);
+            }
+
+            // Handle to_string() on any type
+            if member == "to_string" && args.is_empty() {
+                return self.compile_tostring_method(object, dest, span);
             }
 
             // Handle Vec/Array/String methods on Dynamic-typed objects (runtime dispatch)
@@ -168,11 +178,21 @@ impl Compiler {
                     _ => {}
                 }
 
+                // Array/Vec methods next; the ones that are string methods too
+                // hand strings over to those at runtime
+                if let Some(expected_args) = Self::method_arity(Self::ARRAY_METHODS, member)
+                    && args.len() == expected_args
+                {
+                    return self
+                        .compile_native_method_call("array", object, member, args, dest, span);
+                }
+
                 // String methods on dynamic types (excludes len, handled above)
-                if let Some(expected_args) = Self::string_method_arity(member)
+                if let Some(expected_args) = Self::method_arity(Self::STRING_METHODS, member)
                     && args.len() == expected_args
                 {
-                    return self.compile_string_method_call(object, member, args, dest, span);
+                    return self
+                        .compile_native_method_call("string", object, member, args, dest, span);
                 }
             }
         }
@@ -284,34 +304,25 @@ impl Compiler {
         Ok(())
     }
 
-    /// returns the number of extra args (excluding self) expected by a string method, or None if not a valid method.
-    fn string_method_arity(method: &str) -> Option<usize> {
-        match method {
-            // 0-arg methods (only self)
-            "len" | "char_len" | "chars" | "bytes" | "to_upper" | "to_lower" | "capitalize"
-            | "trim" | "trim_start" | "trim_end" | "is_empty" | "is_whitespace" | "is_numeric"
-            | "is_alphabetic" | "is_alphanumeric" | "reverse" | "lines" | "line_count" => Some(0),
-            // 1-arg methods (self + 1 arg)
-            "char_at" | "byte_at" | "contains" | "starts_with" | "ends_with" | "find" | "rfind"
-            | "count" | "split" | "repeat" | "concat" => Some(1),
-            // 2-arg methods (self + 2 args)
-            "substr" | "replace" | "replace_first" | "pad_left" | "pad_right" => Some(2),
-            // join takes self + 1 arg (separator)
-            "join" => Some(1),
-            _ => None,
-        }
+    /// returns the number of extra args (excluding self) expected by a method in `table`, or None if not a valid method.
+    fn method_arity(table: &[(&str, usize)], method: &str) -> Option<usize> {
+        table
+            .iter()
+            .find(|(name, _)| *name == method)
+            .map(|&(_, arity)| arity)
     }
 
-    /// compile s.method(args) as string::method(s, args...)
-    fn compile_string_method_call(
+    /// compile s.method(args) as module::method(s, args...)
+    fn compile_native_method_call(
         &mut self,
+        module: &str,
         object: &aelys_sema::TypedExpr,
         method: &str,
         args: &[aelys_sema::TypedExpr],
         dest: u8,
         span: Span,
     ) -> Result<()> {
-        let qualified_name = format!("string::{}", method);
+        let qualified_name = format!("{}::{}", module, method);
         let total_args = 1 + args.len(); // self + extra args
 
         let global_idx = self.get_or_create_global_index(&qualified_name);
@@ -322,7 +333,7 @@ impl Compiler {
             let arg_start = match dest.checked_add(1) {
                 Some(s) => s,
                 None => {
-                    return self.compile_string_method_call_fallback(
+                    return self.compile_native_method_call_fallback(
                         object,
                         &qualified_name,
                         args,
@@ -359,7 +370,7 @@ impl Compiler {
                     }
                 }
 
-                // First arg: the string object itself
+                // First arg: the receiver itself
                 self.compile_typed_expr(object, arg_start)?;
 
                 // Remaining args
@@ -386,10 +397,10 @@ impl Compiler {
             }
         }
 
-        self.compile_string_method_call_fallback(object, &qualified_name, args, dest, span)
+        self.compile_native_method_call_fallback(object, &qualified_name, args, dest, span)
     }
 
-    fn compile_string_method_call_fallback(
+    fn compile_native_method_call_fallback(
         &mut self,
         object: &aelys_sema::TypedExpr,
         qualified_name: &str,
@@ -413,7 +424,7 @@ impl Compiler {
         self.accessed_globals.insert(qualified_name.to_string());
         self.emit_b(OpCode::GetGlobalIdx, callee_reg, global_idx as i16, span);
 
-        // first arg: the string object
+        // first arg: the receiver
         self.compile_typed_expr(object, callee_reg + 1)?;
 
         // remaining stuff
@@ -14,6 +14,7 @@ pub(super) fn finalize_untyped_function(
 ) -> Result<()> {
     func_compiler.current.global_layout = build_untyped_global_layout(&func_compiler);
     func_compiler.current.compute_global_layout_hash();
+    func_compiler.close_local_names();
     func_compiler.current.finalize_bytecode();
 
     parent.mark_captures_from_nested(&func_compiler);
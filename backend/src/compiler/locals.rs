@@ -16,6 +16,7 @@ impl Compiler {
             is_captured: false,
             resolved_type,
             is_freed: false,
+            start: self.current_offset(),
         });
     }
 }
@@ -21,6 +21,9 @@ impl Compiler {
             if liveness.is_dead_after(&local.name, stmt_idx) {
                 self.register_pool[local.register as usize] = false;
                 local.is_freed = true;
+                self.current
+                    .local_names
+                    .push(local.debug_name(self.current.current_offset()));
                 already_freed.insert(local.name.clone());
                 freed += 1;
             }
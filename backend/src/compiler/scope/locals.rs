@@ -29,6 +29,7 @@ impl Compiler {
             is_captured: false,
             resolved_type: aelys_sema::ResolvedType::Dynamic,
             is_freed: false,
+            start: self.current_offset(),
         });
 
         Ok(register)
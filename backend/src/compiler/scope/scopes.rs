@@ -23,8 +23,22 @@ impl Compiler {
                     .emit_a(OpCode::CloseUpvals, lowest_captured, 0, 0, 0);
             }
 
+            let end = self.current_offset();
             for local in self.locals.drain(scope.start..) {
                 self.register_pool[local.register as usize] = false;
+                if !local.is_freed {
+                    self.current.local_names.push(local.debug_name(end));
+                }
+            }
+        }
+    }
+
+    // Locals still in scope when the function ends live until its last instruction.
+    pub fn close_local_names(&mut self) {
+        let end = self.current_offset();
+        for local in &self.locals {
+            if !local.is_freed {
+                self.current.local_names.push(local.debug_name(end));
             }
         }
     }
@@ -1,4 +1,4 @@
-use aelys_bytecode::{Function, Heap};
+use aelys_bytecode::{Function, Heap, LocalName};
 use aelys_sema::ResolvedType;
 use aelys_syntax::Source;
 use std::collections::{HashMap, HashSet};
@@ -14,6 +14,18 @@ pub struct Local {
     pub is_captured: bool, // closure capture
     pub resolved_type: ResolvedType,
     pub is_freed: bool, // liveness freed this reg
+    pub start: usize,   // first instruction where the local is live (debug info)
+}
+
+impl Local {
+    pub fn debug_name(&self, end: usize) -> LocalName {
+        LocalName {
+            name: self.name.clone(),
+            register: self.register,
+            start: self.start as u32,
+            end: end as u32,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
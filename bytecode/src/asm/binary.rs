@@ -1,6 +1,6 @@
 //! Binary serialization for .avbc format
 
-use crate::bytecode::{Function, GlobalLayout, UpvalueDescriptor};
+use crate::bytecode::{Function, GlobalLayout, LocalName, UpvalueDescriptor};
 use crate::heap::Heap;
 use crate::object::{GcRef, ObjectKind};
 use crate::value::Value;
@@ -11,7 +11,10 @@ use thiserror::Error;
 pub const MAGIC: &[u8; 4] = b"VBXQ";
 
 /// Current format version
-pub const VERSION: u16 = 1;
+pub const VERSION: u16 = 2;
+
+/// Oldest version we still read (v1 has no local names)
+const MIN_VERSION: u16 = 1;
 
 const MAX_BYTECODE_LEN: usize = 1_000_000;
 const MAX_CONSTANTS: usize = 65_535;
@@ -19,6 +22,7 @@ const MAX_NESTED_FUNCTIONS: usize = 4_096;
 const MAX_UPVALUE_DESCRIPTORS: usize = 256;
 const MAX_LINES: usize = 1_000_000;
 const MAX_GLOBAL_NAMES: usize = 65_535;
+const MAX_LOCAL_NAMES: usize = 65_535;
 const MAX_STRING_LEN: usize = 1_000_000;
 const MAX_NESTING_DEPTH: usize = 64;
 const MAX_SECTION_LEN: usize = 256 * 1024 * 1024;
@@ -178,6 +182,11 @@ impl BinaryWriter {
                     // CallGlobal - write as-is but reset cache words
                     self.write_u32(instr);
                     skip_cache_words = 2;
+                } else if opcode == 104 {
+                    // CallGlobalNative - the cached heap index of the native
+                    // only means something in the VM that ran the function
+                    self.write_u32(instr);
+                    skip_cache_words = 2;
                 } else {
                     self.write_u32(instr);
                 }
@@ -210,6 +219,16 @@ impl BinaryWriter {
             self.write_u16(name.len() as u16);
             self.write_bytes(name.as_bytes());
         }
+
+        // Local names (debug info, v2+)
+        self.write_u16(func.local_names.len() as u16);
+        for local in &func.local_names {
+            self.write_u16(local.name.len() as u16);
+            self.write_bytes(local.name.as_bytes());
+            self.write_u8(local.register);
+            self.write_u32(local.start);
+            self.write_u32(local.end);
+        }
     }
 
     fn write_constant(&mut self, value: &Value, heap: &Heap) {
@@ -324,6 +343,7 @@ fn write_bytes_to(buf: &mut Vec<u8>, bytes: &[u8]) {
 struct BinaryReader<'a> {
     cursor: Cursor<&'a [u8]>,
     heap: Heap,
+    version: u16,
 }
 
 impl<'a> BinaryReader<'a> {
@@ -331,6 +351,7 @@ impl<'a> BinaryReader<'a> {
         Self {
             cursor: Cursor::new(data),
             heap: Heap::new(),
+            version: VERSION,
         }
     }
 
@@ -343,9 +364,10 @@ impl<'a> BinaryReader<'a> {
         }
 
         let version = self.read_u16()?;
-        if version != VERSION {
+        if !(MIN_VERSION..=VERSION).contains(&version) {
             return Err(BinaryError::UnsupportedVersion(version));
         }
+        self.version = version;
 
         let _flags = self.read_u16()?;
         let _func_count = self.read_u32()?;
@@ -366,9 +388,10 @@ impl<'a> BinaryReader<'a> {
         }
 
         let version = self.read_u16()?;
-        if version != VERSION {
+        if !(MIN_VERSION..=VERSION).contains(&version) {
             return Err(BinaryError::UnsupportedVersion(version));
         }
+        self.version = version;
 
         let _flags = self.read_u16()?;
         let _func_count = self.read_u32()?;
@@ -512,6 +535,12 @@ impl<'a> BinaryReader<'a> {
             global_names.push(name);
         }
 
+        let local_names = if self.version >= 2 {
+            self.read_local_names()?
+        } else {
+            Vec::new()
+        };
+
         // Compute global_layout_hash from global layout names
         let mut func = Function::new(name, arity);
         func.num_registers = num_registers;
@@ -520,12 +549,40 @@ impl<'a> BinaryReader<'a> {
         func.nested_functions = nested_functions;
         func.upvalue_descriptors = upvalue_descriptors;
         func.lines = lines;
+        func.local_names = local_names;
         func.global_layout = GlobalLayout::new(global_names);
         func.compute_global_layout_hash();
 
         Ok(func)
     }
 
+    fn read_local_names(&mut self) -> Result<Vec<LocalName>> {
+        let count = self.read_u16()? as usize;
+        if count > MAX_LOCAL_NAMES {
+            return Err(BinaryError::LimitExceeded {
+                what: "local names",
+                limit: MAX_LOCAL_NAMES,
+            });
+        }
+        let mut locals = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name_len = self.read_u16()? as usize;
+            let mut bytes = vec![0u8; name_len];
+            self.cursor.read_exact(&mut bytes)?;
+            let name = String::from_utf8(bytes).map_err(|_| BinaryError::InvalidUtf8)?;
+            let register = self.read_u8()?;
+            let start = self.read_u32()?;
+            let end = self.read_u32()?;
+            locals.push(LocalName {
+                name,
+                register,
+                start,
+                end,
+            });
+        }
+        Ok(locals)
+    }
+
     fn validate_func_markers(constants: &[Value], nested_count: usize) -> Result<()> {
         for constant in constants {
             if let Some(func_idx) = constant.as_nested_fn_marker()
@@ -1,4 +1,5 @@
 use super::Function;
+use crate::bytecode::LocalName;
 
 impl Function {
     pub(super) fn add_line(&mut self, line: u32) {
@@ -30,4 +31,9 @@ impl Function {
         }
         0
     }
+
+    /// Named locals whose live range covers instruction `ip`, innermost last.
+    pub fn live_locals(&self, ip: usize) -> impl Iterator<Item = &LocalName> {
+        self.local_names.iter().filter(move |l| l.covers(ip))
+    }
 }
@@ -1,5 +1,6 @@
 use super::buffer::BytecodeBuffer;
 use super::global_layout::GlobalLayout;
+use super::local_name::LocalName;
 use super::opcode::OpCode;
 use super::upvalue::UpvalueDescriptor;
 use crate::value::Value;
@@ -25,6 +26,7 @@ pub struct Function {
     pub nested_functions: Vec<Function>,
     pub upvalue_descriptors: Vec<UpvalueDescriptor>,
     pub lines: Vec<(u16, u32)>,
+    pub local_names: Vec<LocalName>,
     pub global_layout: Arc<GlobalLayout>,
     pub global_layout_hash: u64,
 }
@@ -42,6 +44,7 @@ impl Function {
             nested_functions: Vec::new(),
             upvalue_descriptors: Vec::new(),
             lines: Vec::new(),
+            local_names: Vec::new(),
             global_layout: GlobalLayout::empty(),
             global_layout_hash: 0,
         }
@@ -91,6 +94,7 @@ impl Function {
     pub fn strip_debug_info(&mut self) {
         self.name = None;
         self.lines.clear();
+        self.local_names.clear();
         let names = self.global_layout.names();
         if !names.is_empty() {
             let stripped: Vec<String> = names
@@ -0,0 +1,16 @@
+/// Debug info tying a source-level local to the register that holds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalName {
+    pub name: String,
+    pub register: u8,
+    /// First instruction index where the local is live.
+    pub start: u32,
+    /// One past the last instruction index where the local is live.
+    pub end: u32,
+}
+
+impl LocalName {
+    pub fn covers(&self, ip: usize) -> bool {
+        (self.start as usize) <= ip && ip < (self.end as usize)
+    }
+}
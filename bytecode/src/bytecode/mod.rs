@@ -4,6 +4,7 @@ mod buffer;
 mod decode;
 mod function;
 mod global_layout;
+mod local_name;
 mod opcode;
 mod upvalue;
 
@@ -11,5 +12,6 @@ pub use buffer::BytecodeBuffer;
 pub use decode::{decode_a, decode_b, decode_c};
 pub use function::Function;
 pub use global_layout::GlobalLayout;
+pub use local_name::LocalName;
 pub use opcode::OpCode;
 pub use upvalue::UpvalueDescriptor;
@@ -20,4 +20,4 @@ pub use kinds::ObjectKind;
 pub use native::NativeFunction;
 pub use string::AelysString;
 pub use upvalue::{AelysUpvalue, UpvalueLocation};
-pub use vec::AelysVec;
+pub use vec::{AelysVec, VecData};
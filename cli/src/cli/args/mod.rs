@@ -3,6 +3,8 @@ mod usage;
 
 use aelys_opt::OptimizationLevel;
 
+pub use crate::cli::diagnostics::MessageFormat;
+
 pub use parse::parse_args;
 pub use usage::usage;
 
@@ -12,11 +14,16 @@ pub enum Command {
     Run {
         path: String,
         program_args: Vec<String>,
+        profile: bool,
+        coverage: Option<String>,
     },
     Compile {
         path: String,
         output: Option<String>,
         emit_air: bool,
+        emit_air_passes: bool,
+        emit_c: bool,
+        verify_air: bool,
     },
     Asm {
         path: String,
@@ -24,6 +31,24 @@ pub enum Command {
         stdout: bool,
     },
     Repl,
+    Debug {
+        path: String,
+        program_args: Vec<String>,
+    },
+    Dap,
+    Lsp,
+    Fmt {
+        paths: Vec<String>,
+        check: bool,
+    },
+    Lint {
+        paths: Vec<String>,
+    },
+    Test {
+        path: String,
+        filters: Vec<String>,
+        coverage: Option<String>,
+    },
     Version,
 }
 
@@ -33,4 +58,5 @@ pub struct ParsedArgs {
     pub vm_args: Vec<String>,
     pub opt_level: OptimizationLevel,
     pub warning_flags: Vec<String>,
+    pub message_format: MessageFormat,
 }
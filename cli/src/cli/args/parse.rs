@@ -1,14 +1,22 @@
 // hand-rolled recursive descent, clap felt overkill for this
 
-use super::{Command, ParsedArgs};
+use super::{Command, MessageFormat, ParsedArgs};
 use aelys_opt::OptimizationLevel;
 
+const DEFAULT_COVERAGE_OUTPUT: &str = "lcov.info";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CommandName {
     Run,
     Compile,
     Asm,
     Repl,
+    Debug,
+    Dap,
+    Lsp,
+    Fmt,
+    Lint,
+    Test,
     Help,
     Version,
 }
@@ -23,13 +31,22 @@ struct Parser<'a> {
     index: usize,
     command: Option<CommandName>,
     path: Option<String>,
+    paths: Vec<String>,
+    filters: Vec<String>,
     program_args: Vec<String>,
     vm_args: Vec<String>,
     opt_level: OptimizationLevel,
     output: Option<String>,
     stdout: bool,
     emit_air: bool,
+    emit_air_passes: bool,
+    emit_c: bool,
+    verify_air: bool,
+    check: bool,
+    profile: bool,
+    coverage: Option<String>,
     warning_flags: Vec<String>,
+    message_format: Option<MessageFormat>,
 }
 
 impl<'a> Parser<'a> {
@@ -40,13 +57,22 @@ impl<'a> Parser<'a> {
             index: 0,
             command: None,
             path: None,
+            paths: Vec::new(),
+            filters: Vec::new(),
             program_args: Vec::new(),
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Standard,
             output: None,
             stdout: false,
             emit_air: false,
+            emit_air_passes: false,
+            emit_c: false,
+            verify_air: false,
+            check: false,
+            profile: false,
+            coverage: None,
             warning_flags: Vec::new(),
+            message_format: None,
         }
     }
 
@@ -97,12 +123,67 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
-            if token_str == "--emit-air" {
+            if let Some(mode) = token_str.strip_prefix("--emit-air")
+                && (mode.is_empty() || mode.starts_with('='))
+            {
+                match mode {
+                    "" => {}
+                    "=passes" => self.emit_air_passes = true,
+                    _ => return Err(format!("unknown --emit-air mode: {}", &mode[1..])),
+                }
                 self.emit_air = true;
                 self.advance();
                 continue;
             }
 
+            if token_str == "--emit-c" {
+                self.emit_c = true;
+                self.advance();
+                continue;
+            }
+
+            if token_str == "--verify-air" {
+                self.verify_air = true;
+                self.advance();
+                continue;
+            }
+
+            // only a flag for fmt; after a run path it belongs to the program
+            if token_str == "--check" && self.command == Some(CommandName::Fmt) {
+                self.check = true;
+                self.advance();
+                continue;
+            }
+
+            // same story as --check: once run has its path, it's a program arg
+            if token_str == "--profile" && self.path.is_none() {
+                self.profile = true;
+                self.advance();
+                continue;
+            }
+
+            if let Some(output) = token_str.strip_prefix("--coverage")
+                && (output.is_empty() || output.starts_with('='))
+                && (self.path.is_none() || self.command == Some(CommandName::Test))
+            {
+                let output = match output {
+                    "" => DEFAULT_COVERAGE_OUTPUT,
+                    "=" => return Err("--coverage= requires a path".to_string()),
+                    _ => &output[1..],
+                };
+                self.coverage = Some(output.to_string());
+                self.advance();
+                continue;
+            }
+
+            if let Some(consumed_next) = self.parse_message_format(token_str)? {
+                self.advance();
+                if consumed_next {
+                    self.advance();
+                }
+                continue;
+            }
+
             if let Some((wflag, consumed)) = self.parse_warning_flag(token_str)? {
                 self.warning_flags.push(wflag);
                 self.advance();
@@ -121,7 +202,9 @@ impl<'a> Parser<'a> {
             }
 
             if token_str.starts_with('-') {
-                if matches!(self.command, Some(CommandName::Run)) && self.path.is_some() {
+                if matches!(self.command, Some(CommandName::Run | CommandName::Debug))
+                    && self.path.is_some()
+                {
                     self.program_args.push(token);
                     self.advance();
                     continue;
@@ -137,6 +220,28 @@ impl<'a> Parser<'a> {
     }
 
     fn finish(self) -> Result<ParsedArgs, String> {
+        if self.profile && !matches!(self.command, None | Some(CommandName::Run)) {
+            return Err("--profile is only supported for run".to_string());
+        }
+        if self.coverage.is_some()
+            && !matches!(
+                self.command,
+                None | Some(CommandName::Run | CommandName::Test)
+            )
+        {
+            return Err("--coverage is only supported for run or test".to_string());
+        }
+        if self.message_format.is_some()
+            && !matches!(
+                self.command,
+                None | Some(CommandName::Run | CommandName::Compile | CommandName::Lint)
+            )
+        {
+            return Err("--message-format is only supported for compile, run or lint".to_string());
+        }
+        if self.profile && self.coverage.is_some() {
+            return Err("--profile and --coverage cannot be combined".to_string());
+        }
         let command = match self.command {
             None => Command::Help,
             Some(CommandName::Help) => Command::Help,
@@ -148,24 +253,110 @@ impl<'a> Parser<'a> {
                 if self.output.is_some() || self.stdout {
                     return Err("repl does not accept output flags".to_string());
                 }
-                if self.emit_air {
-                    return Err("--emit-air is only supported for compile".to_string());
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
                 }
                 Command::Repl
             }
             Some(CommandName::Run) => {
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
                 let path = self
                     .path
                     .ok_or_else(|| "missing file for run".to_string())?;
                 if self.output.is_some() || self.stdout {
                     return Err("output flags are only supported for compile or asm".to_string());
                 }
-                if self.emit_air {
-                    return Err("--emit-air is only supported for compile".to_string());
-                }
                 Command::Run {
                     path,
                     program_args: self.program_args,
+                    profile: self.profile,
+                    coverage: self.coverage,
+                }
+            }
+            Some(CommandName::Dap) => {
+                if self.path.is_some() || !self.program_args.is_empty() {
+                    return Err("dap does not accept a path or arguments".to_string());
+                }
+                if self.output.is_some() || self.stdout {
+                    return Err("dap does not accept output flags".to_string());
+                }
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
+                Command::Dap
+            }
+            Some(CommandName::Lsp) => {
+                if self.path.is_some() || !self.program_args.is_empty() {
+                    return Err("lsp does not accept a path or arguments".to_string());
+                }
+                if self.output.is_some() || self.stdout {
+                    return Err("lsp does not accept output flags".to_string());
+                }
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
+                Command::Lsp
+            }
+            Some(CommandName::Fmt) => {
+                if self.output.is_some() || self.stdout {
+                    return Err("fmt does not accept output flags".to_string());
+                }
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
+                // formats the current directory when given no paths
+                let paths = if self.paths.is_empty() {
+                    vec![".".to_string()]
+                } else {
+                    self.paths
+                };
+                Command::Fmt {
+                    paths,
+                    check: self.check,
+                }
+            }
+            Some(CommandName::Lint) => {
+                if self.output.is_some() || self.stdout {
+                    return Err("lint does not accept output flags".to_string());
+                }
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
+                let paths = if self.paths.is_empty() {
+                    vec![".".to_string()]
+                } else {
+                    self.paths
+                };
+                Command::Lint { paths }
+            }
+            Some(CommandName::Test) => {
+                if self.output.is_some() || self.stdout {
+                    return Err("test does not accept output flags".to_string());
+                }
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
+                Command::Test {
+                    path: self.path.unwrap_or_else(|| ".".to_string()),
+                    filters: self.filters,
+                    coverage: self.coverage,
+                }
+            }
+            Some(CommandName::Debug) => {
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
+                let path = self
+                    .path
+                    .ok_or_else(|| "missing file for debug".to_string())?;
+                if self.output.is_some() || self.stdout {
+                    return Err("output flags are only supported for compile or asm".to_string());
+                }
+                Command::Debug {
+                    path,
+                    program_args: self.program_args,
                 }
             }
             Some(CommandName::Compile) => {
@@ -181,22 +372,28 @@ impl<'a> Parser<'a> {
                 if self.emit_air && self.output.is_some() {
                     return Err("--emit-air and --output cannot be combined".to_string());
                 }
+                if self.emit_air && self.emit_c {
+                    return Err("--emit-air and --emit-c cannot be combined".to_string());
+                }
                 Command::Compile {
                     path,
                     output: self.output,
                     emit_air: self.emit_air,
+                    emit_air_passes: self.emit_air_passes,
+                    emit_c: self.emit_c,
+                    verify_air: self.verify_air,
                 }
             }
             Some(CommandName::Asm) => {
+                if let Some(flag) = self.compile_only_flag() {
+                    return Err(format!("{} is only supported for compile", flag));
+                }
                 let path = self
                     .path
                     .ok_or_else(|| "missing file for asm".to_string())?;
                 if !self.program_args.is_empty() {
                     return Err("asm does not accept extra arguments".to_string());
                 }
-                if self.emit_air {
-                    return Err("--emit-air is only supported for compile".to_string());
-                }
                 Command::Asm {
                     path,
                     output: self.output,
@@ -210,6 +407,7 @@ impl<'a> Parser<'a> {
             vm_args: self.vm_args,
             opt_level: self.opt_level,
             warning_flags: self.warning_flags,
+            message_format: self.message_format.unwrap_or_default(),
         })
     }
 
@@ -219,6 +417,7 @@ impl<'a> Parser<'a> {
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Standard,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     }
 
@@ -228,6 +427,7 @@ impl<'a> Parser<'a> {
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Standard,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     }
 
@@ -237,7 +437,7 @@ impl<'a> Parser<'a> {
                 self.command = Some(CommandName::Run);
                 self.path = Some(token.to_string());
             }
-            Some(CommandName::Run) => {
+            Some(CommandName::Run | CommandName::Debug) => {
                 if self.path.is_none() {
                     self.path = Some(token.to_string());
                 } else {
@@ -261,6 +461,21 @@ impl<'a> Parser<'a> {
             Some(CommandName::Repl) => {
                 return Err(format!("unexpected argument for repl: {}", token));
             }
+            Some(CommandName::Dap) => {
+                return Err(format!("unexpected argument for dap: {}", token));
+            }
+            Some(CommandName::Lsp) => {
+                return Err(format!("unexpected argument for lsp: {}", token));
+            }
+            Some(CommandName::Fmt | CommandName::Lint) => self.paths.push(token.to_string()),
+            // the first positional is where to look, the rest filter test names
+            Some(CommandName::Test) => {
+                if self.path.is_none() {
+                    self.path = Some(token.to_string());
+                } else {
+                    self.filters.push(token.to_string());
+                }
+            }
             Some(CommandName::Version) => {
                 return Err(format!("unexpected argument for version: {}", token));
             }
@@ -275,6 +490,12 @@ impl<'a> Parser<'a> {
             "compile" => Some(CommandName::Compile),
             "asm" => Some(CommandName::Asm),
             "repl" => Some(CommandName::Repl),
+            "debug" => Some(CommandName::Debug),
+            "dap" => Some(CommandName::Dap),
+            "lsp" => Some(CommandName::Lsp),
+            "fmt" => Some(CommandName::Fmt),
+            "lint" => Some(CommandName::Lint),
+            "test" => Some(CommandName::Test),
             "help" => Some(CommandName::Help),
             "version" => Some(CommandName::Version),
             _ => None,
@@ -355,6 +576,35 @@ impl<'a> Parser<'a> {
         Ok(None)
     }
 
+    fn parse_message_format(&mut self, token: &str) -> Result<Option<bool>, String> {
+        // after the script path of run/debug it's the program's own flag
+        if matches!(
+            self.command,
+            None | Some(CommandName::Run | CommandName::Debug)
+        ) && self.path.is_some()
+        {
+            return Ok(None);
+        }
+        let (value, consumed_next) = if token == "--message-format" {
+            let next = self
+                .peek_next()
+                .ok_or("missing value for --message-format")?;
+            (next, true)
+        } else if let Some(value) = token.strip_prefix("--message-format=") {
+            (value, false)
+        } else {
+            return Ok(None);
+        };
+        let format = MessageFormat::parse(value).ok_or_else(|| {
+            format!(
+                "unknown message format: {} (expected human, json or sarif)",
+                value
+            )
+        })?;
+        self.message_format = Some(format);
+        Ok(Some(consumed_next))
+    }
+
     fn is_help(&self, token: &str) -> bool {
         matches!(token, "-h" | "--help")
     }
@@ -367,6 +617,18 @@ impl<'a> Parser<'a> {
         token == "--stdout"
     }
 
+    fn compile_only_flag(&self) -> Option<&'static str> {
+        if self.emit_air {
+            Some("--emit-air")
+        } else if self.emit_c {
+            Some("--emit-c")
+        } else if self.verify_air {
+            Some("--verify-air")
+        } else {
+            None
+        }
+    }
+
     fn parse_output_option(&mut self, token: &str) -> Result<Option<bool>, String> {
         if token == "-o" || token == "--output" {
             let next = self
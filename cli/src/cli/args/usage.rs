@@ -5,6 +5,12 @@ pub fn usage() -> &'static str {
   aelys compile <file>
   aelys asm <file>
   aelys repl [flags]
+  aelys debug [flags] <file> [args...]
+  aelys dap [flags]
+  aelys lsp [flags]
+  aelys fmt [--check] [paths...]
+  aelys lint [flags] [paths...]
+  aelys test [flags] [path] [filters...]
   aelys version
 
 Flags (any position):
@@ -14,6 +20,13 @@ Flags (any position):
   -o, --output <path>        Output path (compile/asm)
   --stdout                   Print asm to stdout (asm)
   --emit-air                 Print AIR instead of compiling (compile)
+  --emit-air=passes          Print AIR before and after each AIR pass (compile)
+  --emit-c                   Write C11 source plus aelys_rt.h (compile)
+  --verify-air               Check the AIR for type and control flow errors (compile)
+  --profile                  Report call/opcode counts, write <file>.folded (run)
+  --coverage[=<path>]        Write line/branch coverage as lcov, default lcov.info (run/test)
+  --check                    Report unformatted files instead of rewriting them (fmt)
+  --message-format=<format>  Diagnostics as human, json or sarif, on stderr (compile/run/lint)
   -ae.<k>=<v>                VM option (e.g., -ae.max-heap=64M)
   --ae-<k>=<v>               VM option (e.g., --ae-max-heap=64M)
   --allow-caps=<list>        Allow native capabilities (comma-separated)
@@ -24,6 +37,7 @@ Warning flags:
   -Wall                      Enable all warnings
   -Werror                    Treat warnings as errors
   -W<category>               Enable specific category (inline, unused, deprecated, shadow)
+                             lint rules are set per project in the [lint] table of aelys.toml
   -Wno-<category>            Disable specific category
 
 Examples:
@@ -31,7 +45,15 @@ Examples:
   aelys main.aelys -O2 '-ae.trusted=true'  (quote in PowerShell)
   aelys run -O3 main.aelys arg1 arg2
   aelys repl -ae.max-heap=1G
+  aelys debug main.aelys
+  aelys run --profile main.aelys
   aelys asm main.aelys --stdout
   aelys compile main.aelys -o main.avbc -Wall -Werror
+  aelys compile --emit-c main.aelys -o main.c
+  aelys fmt --check src
+  aelys lint -Werror src
+  aelys lint --message-format=sarif src 2> lint.sarif
+  aelys test tests parse_
+  aelys test --coverage=coverage/lcov.info tests
   aelys run program.avbc"
 }
@@ -139,7 +139,15 @@ fn compile_source(
         .collect();
 
     let mut all_known_globals = imports.known_globals.clone();
-    for builtin in ["alloc", "free", "load", "store", "type"] {
+    for builtin in [
+        "alloc",
+        "free",
+        "load",
+        "store",
+        "type",
+        "assert",
+        "assert_eq",
+    ] {
         all_known_globals.insert(builtin.to_string());
     }
 
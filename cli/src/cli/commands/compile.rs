@@ -1,23 +1,34 @@
 // source -> avbc compiler
 
+use crate::cli::diagnostics::Reporter;
+use aelys_air::passes::PassManager;
 use aelys_backend::Compiler;
 use aelys_bytecode::asm::NativeBundle;
-use aelys_common::{Warning, WarningConfig};
+use aelys_common::{AelysError, CompileError, CompileErrorKind, Warning, WarningConfig};
 use aelys_driver::modules::{LoadedNativeInfo, load_modules_with_loader};
 use aelys_frontend::lexer::Lexer;
 use aelys_frontend::parser::Parser;
 use aelys_modules::manifest::Manifest;
 use aelys_opt::{OptimizationLevel, Optimizer};
 use aelys_runtime::{VM, VmConfig};
-use aelys_syntax::{Source, StmtKind};
+use aelys_syntax::{Source, Span, StmtKind};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-const BUILTIN_NAMES: &[&str] = &["alloc", "free", "load", "store", "type"];
+const BUILTIN_NAMES: &[&str] = &[
+    "alloc",
+    "free",
+    "load",
+    "store",
+    "type",
+    "assert",
+    "assert_eq",
+];
 
 #[allow(dead_code)]
 pub fn compile_to_avbc(path: &Path, opt_level: OptimizationLevel) -> Result<PathBuf, String> {
-    compile_to_avbc_with_output(path, None, opt_level, None).map(|r| r.output_path)
+    compile_to_avbc_with_output(path, None, opt_level, None, &mut Reporter::default())
+        .map(|r| r.output_path)
 }
 
 pub struct CompileResult {
@@ -30,6 +41,7 @@ pub fn compile_to_avbc_with_output(
     output: Option<PathBuf>,
     opt_level: OptimizationLevel,
     source_for_warnings: Option<Arc<Source>>,
+    reporter: &mut Reporter,
 ) -> Result<CompileResult, String> {
     match detect_format(path) {
         CompileInput::Assembly => {
@@ -53,10 +65,10 @@ pub fn compile_to_avbc_with_output(
 
     let tokens = Lexer::with_source(src.clone())
         .scan()
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| reporter.fail(&err))?;
     let stmts = Parser::new(tokens, src.clone())
         .parse()
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| reporter.fail(&err))?;
 
     let mut vm = VM::with_config_and_args(src.clone(), VmConfig::default(), Vec::new())
         .map_err(|err| err.to_string())?;
@@ -67,7 +79,7 @@ pub fn compile_to_avbc_with_output(
     }
 
     let (imports, loader) = load_modules_with_loader(&stmts, path, src.clone(), &mut vm)
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| reporter.fail(&err))?;
 
     let main_stmts: Vec<_> = stmts
         .into_iter()
@@ -86,11 +98,16 @@ pub fn compile_to_avbc_with_output(
         all_known_globals,
     )
     .map_err(|errors| {
-        if let Some(err) = errors.first() {
-            err.to_string()
-        } else {
-            "Unknown type error".to_string()
-        }
+        let (message, span) = match errors.first() {
+            Some(err) => (err.to_string(), err.span),
+            None => ("Unknown type error".to_string(), Span::dummy()),
+        };
+        let kind = CompileErrorKind::TypeInferenceError(message);
+        reporter.fail(&AelysError::Compile(CompileError::new(
+            kind,
+            span,
+            src.clone(),
+        )))
     })?;
 
     let mut optimizer = Optimizer::new(opt_level);
@@ -116,7 +133,7 @@ pub fn compile_to_avbc_with_output(
         imports.symbol_origins,
     )
     .compile_typed(&typed_program)
-    .map_err(|err| err.to_string())?;
+    .map_err(|err| reporter.fail(&err))?;
 
     // strip debug info (function names, variable names, line info) for release builds
     if opt_level != OptimizationLevel::None {
@@ -163,13 +180,14 @@ pub fn run_with_options(
     output: Option<String>,
     opt_level: OptimizationLevel,
     warn_config: WarningConfig,
+    reporter: &mut Reporter,
 ) -> Result<i32, String> {
     let output = output.map(PathBuf::from);
-    let result = compile_to_avbc_with_output(Path::new(path), output, opt_level, None)?;
+    let result = compile_to_avbc_with_output(Path::new(path), output, opt_level, None, reporter)?;
 
     for w in &result.warnings {
         if warn_config.is_enabled(&w.kind) {
-            eprintln!("{}", w);
+            reporter.warning(w);
         }
     }
 
@@ -182,17 +200,149 @@ pub fn run_with_options(
         ));
     }
 
-    eprintln!("Wrote {}", result.output_path.display());
+    reporter.status(&format!("Wrote {}", result.output_path.display()));
     Ok(0)
 }
 
-pub fn emit_air(path: &str, opt_level: OptimizationLevel) -> Result<i32, String> {
+pub fn emit_air(path: &str, opt_level: OptimizationLevel, verify: bool) -> Result<i32, String> {
+    let air = lower_to_air(Path::new(path), opt_level)?;
+    print!("{}", aelys_air::print::print_program(&air));
+    if verify {
+        check_air(&air, None)?;
+    }
+    Ok(0)
+}
+
+// the whole AIR pipeline, printed before the first pass and after each one
+pub fn emit_air_passes(
+    path: &str,
+    opt_level: OptimizationLevel,
+    verify: bool,
+) -> Result<i32, String> {
+    let mut air = lower_to_unoptimized_air(Path::new(path), opt_level)?;
+    // with --verify-air, the first stage that breaks the program is the one reported
+    let mut broken: Option<(String, aelys_air::AirProgram)> = None;
+    PassManager::standard().run_with_observer(&mut air, |stage, program| {
+        if stage == "input" {
+            println!("=== before AIR passes ===");
+        } else {
+            println!("\n=== after {} ===", stage);
+        }
+        print!("{}", aelys_air::print::print_program(program));
+        if verify && broken.is_none() && !aelys_air::verify(program).is_empty() {
+            broken = Some((stage.to_string(), program.clone()));
+        }
+    });
+    match broken {
+        Some((stage, program)) => check_air(&program, Some(&stage)).map(|_| 0),
+        None => Ok(0),
+    }
+}
+
+// --verify-air on a regular compile: build the AIR on the side and check it
+pub fn verify_air(path: &str, opt_level: OptimizationLevel) -> Result<(), String> {
+    let air = lower_to_air(Path::new(path), opt_level)?;
+    check_air(&air, None)
+}
+
+fn check_air(air: &aelys_air::AirProgram, stage: Option<&str>) -> Result<(), String> {
+    let errors = aelys_air::verify(air);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    for err in &errors {
+        eprintln!("{}", err);
+    }
+    let stage = match stage {
+        Some("input") => " before AIR passes".to_string(),
+        Some(stage) => format!(" after {}", stage),
+        None => String::new(),
+    };
+    Err(format!(
+        "AIR verification failed{}: {} error{}",
+        stage,
+        errors.len(),
+        if errors.len() == 1 { "" } else { "s" }
+    ))
+}
+
+// writes `<file>.c` (or the -o path) and the runtime header next to it
+pub fn emit_c(
+    path: &str,
+    output: Option<String>,
+    opt_level: OptimizationLevel,
+    verify: bool,
+) -> Result<i32, String> {
     let path = Path::new(path);
-    let content = std::fs::read_to_string(path)
-        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    let air = lower_to_air(path, opt_level)?;
+    if verify {
+        check_air(&air, None)?;
+    }
+    let code = aelys_air::cgen::emit_c(&air).map_err(|err| err.to_string())?;
+
+    let output_path = output.map(PathBuf::from).unwrap_or_else(|| {
+        let mut out = path.to_path_buf();
+        out.set_extension("c");
+        out
+    });
+    std::fs::write(&output_path, code)
+        .map_err(|err| format!("failed to write {}: {}", output_path.display(), err))?;
+
+    let header_path = output_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(aelys_air::cgen::RUNTIME_HEADER_NAME);
+    std::fs::write(&header_path, aelys_air::cgen::RUNTIME_HEADER)
+        .map_err(|err| format!("failed to write {}: {}", header_path.display(), err))?;
+
+    eprintln!("Wrote {}", output_path.display());
+    Ok(0)
+}
+
+// lex -> parse -> infer -> optimize -> lower, then layouts and monomorphization
+// AIR as the backends get it: AIR passes only run above -O0
+fn lower_to_air(
+    path: &Path,
+    opt_level: OptimizationLevel,
+) -> Result<aelys_air::AirProgram, String> {
+    let content = read_source(path)?;
+    source_to_air(path, &content, opt_level)
+}
+
+/// AIR for `content` as if it were the file at `path` (modules resolve from
+/// there), with the AIR passes run unless `opt_level` is none.
+pub(crate) fn source_to_air(
+    path: &Path,
+    content: &str,
+    opt_level: OptimizationLevel,
+) -> Result<aelys_air::AirProgram, String> {
+    let mut air = unoptimized_air_from_source(path, content, opt_level)?;
+    if opt_level != OptimizationLevel::None {
+        PassManager::standard().run(&mut air);
+    }
+    Ok(air)
+}
+
+fn lower_to_unoptimized_air(
+    path: &Path,
+    opt_level: OptimizationLevel,
+) -> Result<aelys_air::AirProgram, String> {
+    let content = read_source(path)?;
+    unoptimized_air_from_source(path, &content, opt_level)
+}
 
+fn read_source(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))
+}
+
+fn unoptimized_air_from_source(
+    path: &Path,
+    content: &str,
+    opt_level: OptimizationLevel,
+) -> Result<aelys_air::AirProgram, String> {
     let name = path.display().to_string();
-    let src = Source::new(&name, &content);
+    let src = Source::new(&name, content);
 
     let tokens = Lexer::with_source(src.clone())
         .scan()
@@ -240,10 +390,7 @@ pub fn emit_air(path: &str, opt_level: OptimizationLevel) -> Result<i32, String>
 
     let mut air = aelys_air::lower::lower(&typed_program);
     aelys_air::layout::compute_layouts(&mut air);
-    let air = aelys_air::mono::monomorphize(air);
-
-    print!("{}", aelys_air::print::print_program(&air));
-    Ok(0)
+    Ok(aelys_air::mono::monomorphize(air))
 }
 
 fn output_path_for(path: &Path) -> PathBuf {
@@ -0,0 +1,62 @@
+// `aelys run --coverage`: runs a script with line and branch coverage on and
+// writes an lcov tracefile. `aelys test --coverage` shares `write_lcov`.
+
+use crate::cli::commands::run::load_source;
+use crate::cli::diagnostics::Reporter;
+use crate::cli::vm_config::parse_vm_args_or_error;
+use aelys_common::WarningConfig;
+use aelys_driver::LoadedProgram;
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::CoverageReport;
+use std::path::Path;
+
+pub fn run_with_options(
+    path: &str,
+    program_args: Vec<String>,
+    vm_args: Vec<String>,
+    opt_level: OptimizationLevel,
+    warn_config: WarningConfig,
+    output: &str,
+    reporter: &mut Reporter,
+) -> Result<i32, String> {
+    let mut config = parse_vm_args_or_error(&vm_args)?.config;
+    config.coverage = true;
+    let LoadedProgram { mut vm, main, .. } = load_source(
+        Path::new(path),
+        config,
+        program_args,
+        opt_level,
+        &warn_config,
+        reporter,
+    )?;
+
+    let result = vm.execute(main);
+    // lines run before a failure still count
+    let report = vm.take_coverage().unwrap_or_default();
+    write_lcov(&report, output)?;
+
+    let value = result.map_err(|err| reporter.fail(&err.into()))?;
+    if !value.is_null() {
+        println!("{}", value);
+    }
+    Ok(0)
+}
+
+/// Writes `report` to `output` and prints the totals on stderr.
+pub fn write_lcov(report: &CoverageReport, output: &str) -> Result<(), String> {
+    std::fs::write(output, report.to_lcov())
+        .map_err(|err| format!("failed to write {}: {}", output, err))?;
+
+    let found = report.lines_found();
+    let hit = report.lines_hit();
+    let percent = if found == 0 {
+        100.0
+    } else {
+        hit as f64 * 100.0 / found as f64
+    };
+    eprintln!(
+        "coverage: {:.1}% of lines ({}/{}), written to {}",
+        percent, hit, found, output
+    );
+    Ok(())
+}
@@ -0,0 +1,432 @@
+// `aelys dap`: Debug Adapter Protocol over stdio, one thread, one program.
+// The VM runs in-process, so program stdout is forwarded as `output` events
+// instead of going to the real stdout (which carries the protocol).
+
+mod transport;
+
+use crate::cli::framing::MessageReader;
+use crate::cli::vm_config::parse_vm_args_or_error;
+use aelys_driver::{LoadedProgram, load_file};
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::{
+    Breakpoints, DebugCommand, DebugFrontend, Debugger, StopEvent, StopReason, VM, Value, VmConfig,
+};
+use serde_json::{Value as Json, json};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use transport::DapWriter;
+
+const THREAD_ID: i64 = 1;
+const GLOBALS_REF: i64 = 1;
+const LOCALS_REF_BASE: i64 = 100; // locals of frame N are LOCALS_REF_BASE + N
+
+pub fn run_with_options(vm_args: Vec<String>) -> Result<i32, String> {
+    run_dap_with_io(io::stdin().lock(), io::stdout(), vm_args)
+}
+
+pub fn run_dap_with_io<R, W>(input: R, output: W, vm_args: Vec<String>) -> Result<i32, String>
+where
+    R: BufRead + 'static,
+    W: Write + 'static,
+{
+    let config = parse_vm_args_or_error(&vm_args)?.config;
+    let adapter = Rc::new(RefCell::new(Adapter {
+        reader: MessageReader::new(input),
+        writer: Rc::new(RefCell::new(DapWriter::new(output))),
+        launch: None,
+        breakpoints: Breakpoints::default(),
+        code_lines: BTreeMap::new(),
+        disconnected: false,
+    }));
+
+    let loaded = adapter
+        .borrow_mut()
+        .configure(config)
+        .map_err(|err| err.to_string())?;
+    let Some(LoadedProgram { mut vm, main, .. }) = loaded else {
+        return Ok(0);
+    };
+
+    let (writer, stop_on_entry, breakpoints) = {
+        let a = adapter.borrow();
+        let stop_on_entry = a.launch.as_ref().is_some_and(|l| l.stop_on_entry);
+        (Rc::clone(&a.writer), stop_on_entry, a.breakpoints.clone())
+    };
+    let sink_writer = Rc::clone(&writer);
+    vm.set_stdout_sink(Some(Box::new(move |text| {
+        let _ = sink_writer
+            .borrow_mut()
+            .event("output", json!({ "category": "stdout", "output": text }));
+    })));
+
+    let mut debugger = Debugger::new(Box::new(Frontend(Rc::clone(&adapter))));
+    if stop_on_entry {
+        debugger = debugger.stop_on_entry();
+    }
+    *debugger.breakpoints_mut() = breakpoints;
+    vm.attach_debugger(debugger);
+
+    let exit_code = match vm.execute(main) {
+        Ok(_) => 0,
+        Err(err) => {
+            let _ = writer.borrow_mut().event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{}\n", err) }),
+            );
+            1
+        }
+    };
+    {
+        let mut w = writer.borrow_mut();
+        let _ = w.event("exited", json!({ "exitCode": exit_code }));
+        let _ = w.event("terminated", json!({}));
+    }
+
+    adapter
+        .borrow_mut()
+        .drain_until_disconnect()
+        .map_err(|err| err.to_string())?;
+    Ok(exit_code)
+}
+
+struct Launch {
+    program: PathBuf,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+struct Adapter<R, W> {
+    reader: MessageReader<R>,
+    writer: Rc<RefCell<DapWriter<W>>>,
+    launch: Option<Launch>,
+    // per source file, keyed by canonical path like the VM's sources
+    breakpoints: Breakpoints,
+    // lines with code per loaded file, filled in at launch
+    code_lines: BTreeMap<String, BTreeSet<u32>>,
+    disconnected: bool,
+}
+
+impl<R: BufRead, W: Write> Adapter<R, W> {
+    // Handles everything up to `configurationDone`. Returns `None` if the
+    // client went away before a program was launched.
+    fn configure(&mut self, config: VmConfig) -> io::Result<Option<LoadedProgram>> {
+        let mut loaded: Option<LoadedProgram> = None;
+        let mut configured = false;
+
+        while !(configured && loaded.is_some()) {
+            let Some(request) = self.reader.read_message()? else {
+                return Ok(None);
+            };
+            let args = &request["arguments"];
+            match command(&request) {
+                "initialize" => {
+                    let mut w = self.writer.borrow_mut();
+                    w.respond(
+                        &request,
+                        json!({
+                            "supportsConfigurationDoneRequest": true,
+                            "supportsEvaluateForHovers": true,
+                        }),
+                    )?;
+                    w.event("initialized", json!({}))?;
+                }
+                "launch" => match self.launch_program(args, config.clone()) {
+                    Ok(program) => {
+                        loaded = Some(program);
+                        self.writer.borrow_mut().respond(&request, json!({}))?;
+                    }
+                    Err(message) => self.writer.borrow_mut().fail(&request, &message)?,
+                },
+                "setBreakpoints" => self.set_breakpoints(&request)?,
+                "configurationDone" => {
+                    configured = true;
+                    self.writer.borrow_mut().respond(&request, json!({}))?;
+                }
+                "threads" => self.threads(&request)?,
+                "disconnect" | "terminate" => {
+                    self.disconnected = true;
+                    self.writer.borrow_mut().respond(&request, json!({}))?;
+                    return Ok(None);
+                }
+                other => self.unsupported(&request, other)?,
+            }
+        }
+        Ok(loaded)
+    }
+
+    fn launch_program(&mut self, args: &Json, config: VmConfig) -> Result<LoadedProgram, String> {
+        let program = args["program"]
+            .as_str()
+            .ok_or_else(|| "launch needs a `program` path".to_string())?;
+        let program_args = args["args"]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .filter_map(|a| a.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let launch = Launch {
+            program: PathBuf::from(program),
+            args: program_args,
+            stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+        };
+
+        let loaded = load_file(
+            &launch.program,
+            config,
+            launch.args.clone(),
+            OptimizationLevel::None,
+        )
+        .map_err(|err| err.to_string())?;
+        self.code_lines = loaded
+            .vm
+            .source_files()
+            .into_iter()
+            .map(|file| (file.to_string(), loaded.vm.source_lines_with_code(file)))
+            .collect();
+        self.launch = Some(launch);
+        Ok(loaded)
+    }
+
+    fn set_breakpoints(&mut self, request: &Json) -> io::Result<()> {
+        let lines: Vec<u32> = request["arguments"]["breakpoints"]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .filter_map(|bp| bp["line"].as_u64())
+                    .map(|line| line as u32)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let source = canonical(
+            request["arguments"]["source"]["path"]
+                .as_str()
+                .unwrap_or(""),
+        );
+
+        // the request lists every breakpoint of one file, other files keep theirs
+        self.breakpoints.clear_source(&source);
+        let mut reply = Vec::new();
+        for line in lines {
+            // before launch we can't tell which lines have code yet
+            let verified = self.launch.is_none()
+                || self
+                    .code_lines
+                    .get(&source)
+                    .is_some_and(|lines| lines.contains(&line));
+            if verified {
+                self.breakpoints.add(&source, line);
+            }
+            reply.push(json!({ "verified": verified, "line": line }));
+        }
+        self.writer
+            .borrow_mut()
+            .respond(request, json!({ "breakpoints": reply }))
+    }
+
+    fn threads(&mut self, request: &Json) -> io::Result<()> {
+        self.writer.borrow_mut().respond(
+            request,
+            json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+        )
+    }
+
+    fn unsupported(&mut self, request: &Json, command: &str) -> io::Result<()> {
+        self.writer
+            .borrow_mut()
+            .fail(request, &format!("unsupported request: {}", command))
+    }
+
+    // Serves requests while the program is paused, until one of them resumes it.
+    fn paused(&mut self, vm: &VM, stop: &StopEvent) -> io::Result<DebugCommand> {
+        let reason = match stop.reason {
+            StopReason::Entry => "entry",
+            StopReason::Breakpoint => "breakpoint",
+            StopReason::Step => "step",
+        };
+        self.writer.borrow_mut().event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+
+        loop {
+            let Some(request) = self.reader.read_message()? else {
+                self.disconnected = true;
+                return Ok(DebugCommand::Quit);
+            };
+            let resume = match command(&request) {
+                "continue" => Some(DebugCommand::Continue),
+                "next" => Some(DebugCommand::Next),
+                "stepIn" => Some(DebugCommand::Step),
+                "stepOut" => Some(DebugCommand::Finish),
+                "disconnect" | "terminate" => {
+                    self.disconnected = true;
+                    Some(DebugCommand::Quit)
+                }
+                _ => None,
+            };
+            if let Some(resume) = resume {
+                let body = if resume == DebugCommand::Continue {
+                    json!({ "allThreadsContinued": true })
+                } else {
+                    json!({})
+                };
+                self.writer.borrow_mut().respond(&request, body)?;
+                return Ok(resume);
+            }
+
+            match command(&request) {
+                "threads" => self.threads(&request)?,
+                "setBreakpoints" => self.set_breakpoints(&request)?,
+                "stackTrace" => self.stack_trace(&request, vm)?,
+                "scopes" => {
+                    let frame = request["arguments"]["frameId"].as_i64().unwrap_or(0);
+                    self.writer.borrow_mut().respond(
+                        &request,
+                        json!({ "scopes": [
+                            { "name": "Locals", "variablesReference": LOCALS_REF_BASE + frame, "expensive": false },
+                            { "name": "Globals", "variablesReference": GLOBALS_REF, "expensive": false },
+                        ]}),
+                    )?;
+                }
+                "variables" => {
+                    let reference = request["arguments"]["variablesReference"]
+                        .as_i64()
+                        .unwrap_or(0);
+                    let vars = if reference == GLOBALS_REF {
+                        vm.visible_globals()
+                    } else if reference >= LOCALS_REF_BASE {
+                        vm.frame_locals((reference - LOCALS_REF_BASE) as usize)
+                    } else {
+                        Vec::new()
+                    };
+                    let variables: Vec<Json> = vars
+                        .into_iter()
+                        .map(|(name, value)| {
+                            json!({
+                                "name": name,
+                                "value": display(vm, value),
+                                "type": vm.value_type_name(value),
+                                "variablesReference": 0,
+                            })
+                        })
+                        .collect();
+                    self.writer
+                        .borrow_mut()
+                        .respond(&request, json!({ "variables": variables }))?;
+                }
+                "evaluate" => {
+                    let args = &request["arguments"];
+                    let name = args["expression"].as_str().unwrap_or("").trim();
+                    let frame = args["frameId"].as_u64().unwrap_or(0) as usize;
+                    let mut w = self.writer.borrow_mut();
+                    match vm.lookup_variable(frame, name) {
+                        Some(value) => w.respond(
+                            &request,
+                            json!({
+                                "result": display(vm, value),
+                                "type": vm.value_type_name(value),
+                                "variablesReference": 0,
+                            }),
+                        )?,
+                        None => w.fail(&request, &format!("no variable named '{}'", name))?,
+                    }
+                }
+                "pause" => self.writer.borrow_mut().respond(&request, json!({}))?,
+                other => self.unsupported(&request, other)?,
+            }
+        }
+    }
+
+    fn stack_trace(&mut self, request: &Json, vm: &VM) -> io::Result<()> {
+        let frames: Vec<Json> = vm
+            .backtrace()
+            .into_iter()
+            .enumerate()
+            .map(|(id, frame)| {
+                let path = Path::new(&frame.source);
+                json!({
+                    "id": id,
+                    "name": frame.function,
+                    "line": frame.line,
+                    "column": 1,
+                    "source": {
+                        "name": path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default(),
+                        "path": frame.source,
+                    },
+                })
+            })
+            .collect();
+        let total = frames.len();
+        self.writer.borrow_mut().respond(
+            request,
+            json!({ "stackFrames": frames, "totalFrames": total }),
+        )
+    }
+
+    // Once the program is done the client still expects answers until it disconnects.
+    fn drain_until_disconnect(&mut self) -> io::Result<()> {
+        while !self.disconnected {
+            let Some(request) = self.reader.read_message()? else {
+                break;
+            };
+            match command(&request) {
+                "disconnect" | "terminate" => {
+                    self.disconnected = true;
+                    self.writer.borrow_mut().respond(&request, json!({}))?;
+                }
+                "threads" => self
+                    .writer
+                    .borrow_mut()
+                    .respond(&request, json!({ "threads": [] }))?,
+                _ => self
+                    .writer
+                    .borrow_mut()
+                    .fail(&request, "the program has exited")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Frontend<R, W>(Rc<RefCell<Adapter<R, W>>>);
+
+impl<R: BufRead, W: Write> DebugFrontend for Frontend<R, W> {
+    fn on_stop(
+        &mut self,
+        vm: &VM,
+        stop: &StopEvent,
+        breakpoints: &mut Breakpoints,
+    ) -> DebugCommand {
+        let mut adapter = self.0.borrow_mut();
+        let command = adapter.paused(vm, stop).unwrap_or(DebugCommand::Quit);
+        // setBreakpoints while paused updates the adapter's copy
+        *breakpoints = adapter.breakpoints.clone();
+        command
+    }
+}
+
+// Files are named by canonical path on both sides, since the client and the
+// loader may spell the same file differently.
+fn canonical(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn command(request: &Json) -> &str {
+    request["command"].as_str().unwrap_or("")
+}
+
+fn display(vm: &VM, value: Value) -> String {
+    if vm.value_type_name(value) == "string" {
+        format!("{:?}", vm.value_to_string(value))
+    } else {
+        vm.value_to_string(value)
+    }
+}
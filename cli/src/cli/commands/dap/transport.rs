@@ -0,0 +1,46 @@
+// DAP responses and events; framing lives in `cli::framing`
+
+use crate::cli::framing::write_message;
+use serde_json::{Value, json};
+use std::io::{self, Write};
+
+pub struct DapWriter<W> {
+    output: W,
+    seq: i64,
+}
+
+impl<W: Write> DapWriter<W> {
+    pub fn new(output: W) -> Self {
+        Self { output, seq: 0 }
+    }
+
+    pub fn respond(&mut self, request: &Value, body: Value) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    pub fn fail(&mut self, request: &Value, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }))
+    }
+
+    pub fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.output, &message)
+    }
+}
@@ -0,0 +1,262 @@
+use crate::cli::vm_config::parse_vm_args_or_error;
+use aelys_common::{WarningConfig, format_warnings};
+use aelys_driver::{LoadedProgram, load_file};
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::{
+    Breakpoints, DebugCommand, DebugFrontend, Debugger, StopEvent, StopReason, VM,
+};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const HELP: &str = "\
+commands:
+  break [file:]<line>  set a breakpoint, in the main file by default (b)
+  delete [file:]<line> remove a breakpoint (d)
+  continue, c          run to the next breakpoint
+  step, s              run to the next line, entering calls
+  next, n              run to the next line in this function
+  finish               run until this function returns
+  backtrace, bt        show the call stack
+  locals               show local variables
+  print <name>, p      show a variable
+  quit, q              stop the program";
+
+pub fn run_with_options(
+    path: &str,
+    program_args: Vec<String>,
+    vm_args: Vec<String>,
+    warn_config: WarningConfig,
+) -> Result<i32, String> {
+    run_debug_with_io(
+        path,
+        program_args,
+        vm_args,
+        warn_config,
+        io::stdin().lock(),
+        io::stdout(),
+    )
+}
+
+pub fn run_debug_with_io<R, W>(
+    path: &str,
+    program_args: Vec<String>,
+    vm_args: Vec<String>,
+    warn_config: WarningConfig,
+    input: R,
+    output: W,
+) -> Result<i32, String>
+where
+    R: BufRead + 'static,
+    W: Write + 'static,
+{
+    let parsed = parse_vm_args_or_error(&vm_args)?;
+    let path_ref = Path::new(path);
+    let source = std::fs::read_to_string(path_ref)
+        .map_err(|err| format!("failed to read {}: {}", path_ref.display(), err))?;
+
+    // -O0 so every local keeps its register and line info survives
+    let LoadedProgram {
+        mut vm,
+        main,
+        warnings,
+    } = load_file(
+        path_ref,
+        parsed.config,
+        program_args,
+        OptimizationLevel::None,
+    )
+    .map_err(|err| err.to_string())?;
+
+    for w in warnings.iter().filter(|w| warn_config.is_enabled(&w.kind)) {
+        eprintln!("{}", format_warnings(std::slice::from_ref(w)));
+    }
+
+    let main_file = vm.script_path().unwrap_or(path).to_string();
+    let mut source_lines = HashMap::new();
+    source_lines.insert(
+        main_file.clone(),
+        source.lines().map(str::to_string).collect(),
+    );
+    let frontend = CliFrontend {
+        input,
+        output,
+        main_file,
+        source_lines,
+    };
+    vm.attach_debugger(Debugger::new(Box::new(frontend)).stop_on_entry());
+
+    let value = vm.execute(main).map_err(|err| err.to_string())?;
+    if !value.is_null() {
+        println!("{}", vm.value_to_string(value));
+    }
+    Ok(0)
+}
+
+struct CliFrontend<R, W> {
+    input: R,
+    output: W,
+    // the file `break <line>` means, as the VM names it
+    main_file: String,
+    // read on first stop in each file
+    source_lines: HashMap<String, Vec<String>>,
+}
+
+impl<R: BufRead, W: Write> CliFrontend<R, W> {
+    fn show_stop(&mut self, stop: &StopEvent) -> io::Result<()> {
+        let why = match stop.reason {
+            StopReason::Entry => "entry",
+            StopReason::Breakpoint => "breakpoint",
+            StopReason::Step => "step",
+        };
+        writeln!(
+            self.output,
+            "stopped at {} in {} ({})",
+            self.position(&stop.source, stop.line),
+            stop.function,
+            why
+        )?;
+        let lines = self
+            .source_lines
+            .entry(stop.source.clone())
+            .or_insert_with(|| match std::fs::read_to_string(&stop.source) {
+                Ok(text) => text.lines().map(str::to_string).collect(),
+                Err(_) => Vec::new(),
+            });
+        if let Some(text) = (stop.line as usize)
+            .checked_sub(1)
+            .and_then(|i| lines.get(i))
+        {
+            writeln!(self.output, "{:>4} | {}", stop.line, text)?;
+        }
+        Ok(())
+    }
+
+    // "line N" in the main file, "file:N" in a module, the file shown
+    // relative to the main file's directory
+    fn position(&self, source: &str, line: u32) -> String {
+        if source == self.main_file {
+            return format!("line {}", line);
+        }
+        let dir = Path::new(&self.main_file).parent().unwrap_or(Path::new(""));
+        let shown = Path::new(source)
+            .strip_prefix(dir)
+            .unwrap_or(Path::new(source));
+        format!("{}:{}", shown.display(), line)
+    }
+
+    // `<line>` or `<file>:<line>`, the file matched against the loaded ones
+    // by path or by trailing path components
+    fn location(&self, vm: &VM, arg: Option<&str>) -> Result<(String, u32), String> {
+        let Some(arg) = arg else {
+            return Err("missing line".to_string());
+        };
+        let (file, line) = match arg.rsplit_once(':') {
+            Some((file, line)) => (Some(file), line),
+            None => (None, arg),
+        };
+        let line = line
+            .parse::<u32>()
+            .map_err(|_| format!("'{}' is not a line number", line))?;
+        let Some(file) = file else {
+            return Ok((self.main_file.clone(), line));
+        };
+        let wanted = std::fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file));
+        let files = vm.source_files();
+        let found = files
+            .iter()
+            .find(|loaded| Path::new(loaded) == wanted)
+            .or_else(|| {
+                files
+                    .iter()
+                    .find(|loaded| Path::new(loaded).ends_with(file))
+            });
+        match found {
+            Some(loaded) => Ok((loaded.to_string(), line)),
+            None => Err(format!("no loaded file matches '{}'", file)),
+        }
+    }
+
+    // Reads commands until one of them resumes execution.
+    fn prompt(&mut self, vm: &VM, breakpoints: &mut Breakpoints) -> io::Result<DebugCommand> {
+        let mut line = String::new();
+        loop {
+            write!(self.output, "(adb) ")?;
+            self.output.flush()?;
+            line.clear();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(DebugCommand::Quit);
+            }
+            let mut words = line.split_whitespace();
+            let Some(cmd) = words.next() else {
+                continue;
+            };
+            let arg = words.next();
+            match cmd {
+                "continue" | "c" => return Ok(DebugCommand::Continue),
+                "step" | "s" => return Ok(DebugCommand::Step),
+                "next" | "n" => return Ok(DebugCommand::Next),
+                "finish" => return Ok(DebugCommand::Finish),
+                "quit" | "q" => return Ok(DebugCommand::Quit),
+                "break" | "b" => match self.location(vm, arg) {
+                    Ok((file, n)) => {
+                        breakpoints.add(&file, n);
+                        let at = self.position(&file, n);
+                        writeln!(self.output, "breakpoint at {}", at)?;
+                    }
+                    Err(why) => writeln!(self.output, "{}; usage: break [file:]<line>", why)?,
+                },
+                "delete" | "d" => match self.location(vm, arg) {
+                    Ok((file, n)) => {
+                        let at = self.position(&file, n);
+                        if breakpoints.remove(&file, n) {
+                            writeln!(self.output, "removed breakpoint at {}", at)?
+                        } else {
+                            writeln!(self.output, "no breakpoint at {}", at)?
+                        }
+                    }
+                    Err(why) => writeln!(self.output, "{}; usage: delete [file:]<line>", why)?,
+                },
+                "backtrace" | "bt" => {
+                    for (i, frame) in vm.backtrace().iter().enumerate() {
+                        let at = self.position(&frame.source, frame.line);
+                        writeln!(self.output, "#{} {} at {}", i, frame.function, at)?;
+                    }
+                }
+                "locals" => {
+                    let locals = vm.frame_locals(0);
+                    if locals.is_empty() {
+                        writeln!(self.output, "no locals")?;
+                    }
+                    for (name, value) in locals {
+                        writeln!(self.output, "{} = {}", name, vm.value_to_string(value))?;
+                    }
+                }
+                "print" | "p" => match arg {
+                    Some(name) => match vm.lookup_variable(0, name) {
+                        Some(value) => {
+                            writeln!(self.output, "{} = {}", name, vm.value_to_string(value))?
+                        }
+                        None => writeln!(self.output, "no variable named '{}'", name)?,
+                    },
+                    None => writeln!(self.output, "usage: print <name>")?,
+                },
+                "help" | "h" => writeln!(self.output, "{}", HELP)?,
+                other => writeln!(self.output, "unknown command '{}', try 'help'", other)?,
+            }
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> DebugFrontend for CliFrontend<R, W> {
+    fn on_stop(
+        &mut self,
+        vm: &VM,
+        stop: &StopEvent,
+        breakpoints: &mut Breakpoints,
+    ) -> DebugCommand {
+        self.show_stop(stop)
+            .and_then(|_| self.prompt(vm, breakpoints))
+            .unwrap_or(DebugCommand::Quit)
+    }
+}
@@ -0,0 +1,51 @@
+// `aelys fmt`: rewrite files in the canonical style, or with --check only
+// report the ones that would change
+
+use crate::cli::files::collect_aelys_files;
+use aelys_frontend::formatter::format_source;
+use aelys_syntax::Source;
+
+pub fn run_with_options(paths: &[String], check: bool) -> Result<i32, String> {
+    let files = collect_aelys_files(paths)?;
+
+    let mut failed = false;
+    for file in &files {
+        let original = std::fs::read_to_string(file)
+            .map_err(|err| format!("failed to read {}: {}", file.display(), err))?;
+        let source = Source::new(file.display().to_string(), &original);
+        let formatted = match format_source(source) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == original {
+            continue;
+        }
+        if check {
+            eprintln!(
+                "{}: not formatted (first difference at line {})",
+                file.display(),
+                first_difference(&original, &formatted)
+            );
+            failed = true;
+        } else {
+            std::fs::write(file, formatted)
+                .map_err(|err| format!("failed to write {}: {}", file.display(), err))?;
+        }
+    }
+
+    Ok(if failed { 1 } else { 0 })
+}
+
+fn first_difference(original: &str, formatted: &str) -> usize {
+    let mut formatted_lines = formatted.lines();
+    for (i, line) in original.lines().enumerate() {
+        if formatted_lines.next() != Some(line) {
+            return i + 1;
+        }
+    }
+    original.lines().count() + 1
+}
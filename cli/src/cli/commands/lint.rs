@@ -0,0 +1,185 @@
+// `aelys lint [paths...]`: type checks every file (imports are resolved the
+// way the language server does it, without running any module code) and
+// runs the lint rules over the files of each project together. Levels come
+// from the `[lint]` table of the aelys.toml closest to each file; exits 1 if
+// a denied rule fired or a file didn't compile.
+
+use crate::cli::commands::lsp::imports::{StdlibIndex, resolve_imports, scope};
+use crate::cli::diagnostics::Reporter;
+use crate::cli::files::collect_aelys_files;
+use aelys_backend::Compiler;
+use aelys_common::{AelysError, CompileError, CompileErrorKind, WarningConfig};
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_modules::manifest::Manifest;
+use aelys_sema::lint::{self, LintConfig, LintLevel, LintModule};
+use aelys_sema::{TypeInference, TypedProgram};
+use aelys_syntax::{NeedsStmt, Source, StmtKind};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub fn run_with_options(
+    paths: &[String],
+    warn_config: WarningConfig,
+    reporter: &mut Reporter,
+) -> Result<i32, String> {
+    let files = collect_aelys_files(paths)?;
+
+    // files are linted together per project, each project with the levels of
+    // its own aelys.toml
+    let mut stdlib = StdlibIndex::new();
+    let mut projects: BTreeMap<Option<PathBuf>, Vec<CheckedModule>> = BTreeMap::new();
+    let mut failed = false;
+    for file in &files {
+        match check(file, &mut stdlib, reporter)? {
+            Some(module) => projects.entry(manifest_for(file)).or_default().push(module),
+            None => failed = true,
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let mut denied = 0;
+    for (manifest, checked) in &projects {
+        let config = project_config(manifest.as_deref())?;
+        let modules: Vec<LintModule> = checked
+            .iter()
+            .map(|(program, name, needs)| LintModule {
+                program,
+                name: name.clone(),
+                needs: needs.clone(),
+            })
+            .collect();
+        for w in lint::lint(&modules, &config) {
+            if !warn_config.is_enabled(&w.kind) {
+                continue;
+            }
+            if warn_config.treat_as_error || config.level_of(&w) == LintLevel::Deny {
+                denied += 1;
+            }
+            warnings.push(w);
+        }
+    }
+
+    for w in &warnings {
+        reporter.warning(w);
+    }
+    if denied > 0 {
+        reporter.status(&format!(
+            "error: {} of {} lint warning(s) denied",
+            denied,
+            warnings.len()
+        ));
+        failed = true;
+    }
+
+    Ok(if failed { 1 } else { 0 })
+}
+
+/// The aelys.toml closest to `file`, if there is one.
+fn manifest_for(file: &Path) -> Option<PathBuf> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let absolute = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    Manifest::find_project(&absolute)
+}
+
+/// Lint levels from `manifest`, the defaults without one.
+fn project_config(manifest: Option<&Path>) -> Result<LintConfig, String> {
+    let mut config = LintConfig::new();
+    let Some(manifest_path) = manifest else {
+        return Ok(config);
+    };
+
+    let manifest = Manifest::from_file(manifest_path)
+        .map_err(|err| format!("{}: {}", manifest_path.display(), err))?;
+    for (rule, level) in &manifest.lint {
+        let level = LintLevel::parse(level).ok_or_else(|| {
+            format!(
+                "{}: lint level for {} must be allow, warn or deny, not {}",
+                manifest_path.display(),
+                rule,
+                level
+            )
+        })?;
+        config
+            .set(rule, level)
+            .map_err(|err| format!("{}: {}", manifest_path.display(), err))?;
+    }
+    Ok(config)
+}
+
+type CheckedModule = (TypedProgram, String, Vec<NeedsStmt>);
+
+// None once the file's errors are reported, it has nothing to lint then
+fn check(
+    file: &Path,
+    stdlib: &mut StdlibIndex,
+    reporter: &mut Reporter,
+) -> Result<Option<CheckedModule>, String> {
+    let text = std::fs::read_to_string(file)
+        .map_err(|err| format!("failed to read {}: {}", file.display(), err))?;
+    let src = Source::new(file.display().to_string(), &text);
+    let parsed = Lexer::with_source(src.clone())
+        .scan()
+        .and_then(|tokens| Parser::new(tokens, src.clone()).parse());
+    let stmts = match parsed {
+        Ok(stmts) => stmts,
+        Err(err) => {
+            reporter.error(&err);
+            return Ok(None);
+        }
+    };
+
+    let mut imports = stdlib.prelude();
+    imports.extend(resolve_imports(&stmts, Some(file), stdlib));
+    let (module_aliases, known_globals) = scope(&imports);
+
+    let (needs, main_stmts): (Vec<_>, Vec<_>) = stmts
+        .into_iter()
+        .partition(|stmt| matches!(stmt.kind, StmtKind::Needs(_)));
+    let needs = needs
+        .into_iter()
+        .filter_map(|stmt| match stmt.kind {
+            StmtKind::Needs(needs) => Some(needs),
+            _ => None,
+        })
+        .collect();
+
+    let program = match TypeInference::infer_program_with_imports(
+        main_stmts,
+        src.clone(),
+        module_aliases.clone(),
+        known_globals.clone(),
+    ) {
+        Ok(program) => program,
+        Err(errors) => {
+            for err in errors {
+                let kind = CompileErrorKind::TypeInferenceError(err.to_string());
+                let err = CompileError::new(kind, err.span, src.clone());
+                reporter.error(&AelysError::Compile(err));
+            }
+            return Ok(None);
+        }
+    };
+
+    // undefined names and the like only show up in the compiler, the same
+    // check `aelys run` and the language server do
+    let compiled = Compiler::with_modules(
+        None,
+        src.clone(),
+        module_aliases,
+        known_globals,
+        HashSet::new(),
+        HashMap::new(),
+    )
+    .compile_typed(&program);
+    if let Err(err) = compiled {
+        reporter.error(&err);
+        return Ok(None);
+    }
+
+    let name = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(Some((program, name, needs)))
+}
@@ -0,0 +1,147 @@
+// Completion: members after `.`, otherwise every name in scope
+
+use super::document::Document;
+use super::index::{DeclKind, SymbolIndex};
+use aelys_backend::Compiler;
+use aelys_sema::ResolvedType;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+
+// CompletionItemKind values from the LSP spec
+const KIND_METHOD: u8 = 2;
+const KIND_FUNCTION: u8 = 3;
+const KIND_FIELD: u8 = 5;
+const KIND_VARIABLE: u8 = 6;
+const KIND_MODULE: u8 = 9;
+const KIND_CONSTANT: u8 = 21;
+const KIND_STRUCT: u8 = 22;
+
+pub fn complete(doc: &Document, offset: usize) -> Vec<Value> {
+    let Some(index) = &doc.index else {
+        return Vec::new();
+    };
+    let before = doc.lines.slice(&doc.text, 0, offset);
+    let before = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+    // drop the part of the name already typed; the client filters on it
+    let head = before.trim_end_matches(is_ident_char);
+
+    match head.strip_suffix('.') {
+        Some(receiver) => member_items(index, receiver, offset),
+        None => scope_items(index, offset),
+    }
+}
+
+fn member_items(index: &SymbolIndex, receiver: &str, offset: usize) -> Vec<Value> {
+    if receiver.ends_with('"') {
+        return string_methods();
+    }
+    let name = &receiver[receiver.trim_end_matches(is_ident_char).len()..];
+    if name.is_empty() {
+        return Vec::new();
+    }
+
+    if let Some(decl) = index
+        .visible_at(offset)
+        .into_iter()
+        .find(|d| d.name == name)
+    {
+        return match decl.ty.as_ref().map(ResolvedType::unwrap_uncertain) {
+            Some(ResolvedType::String | ResolvedType::Dynamic) | None => string_methods(),
+            Some(ResolvedType::Array(_)) => collection_methods(&[("len", 0)]),
+            Some(ResolvedType::Vec(_)) => collection_methods(&[
+                ("len", 0),
+                ("push", 1),
+                ("pop", 0),
+                ("capacity", 0),
+                ("reserve", 1),
+            ]),
+            Some(ResolvedType::Struct(struct_name)) => index
+                .fields_of(struct_name)
+                .into_iter()
+                .map(|field| item(&field.name, KIND_FIELD, &field.detail))
+                .collect(),
+            Some(_) => Vec::new(),
+        };
+    }
+
+    match index
+        .imports
+        .iter()
+        .rev()
+        .find(|i| i.is_qualified() && i.alias == name)
+    {
+        Some(import) => import
+            .exports
+            .iter()
+            .map(|e| item(&e.name, export_kind(e.is_function), &e.detail))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn scope_items(index: &SymbolIndex, offset: usize) -> Vec<Value> {
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut push = |label: &str, kind: u8, detail: &str| {
+        if seen.insert(label.to_string()) {
+            items.push(item(label, kind, detail));
+        }
+    };
+
+    for decl in index.visible_at(offset) {
+        let kind = match decl.kind {
+            DeclKind::Function => KIND_FUNCTION,
+            DeclKind::Struct => KIND_STRUCT,
+            DeclKind::Field => KIND_FIELD,
+            DeclKind::Variable | DeclKind::Parameter => KIND_VARIABLE,
+        };
+        push(&decl.name, kind, &decl.detail);
+    }
+    for import in index.imports.iter().rev() {
+        if import.is_qualified() {
+            push(&import.alias, KIND_MODULE, &import.module_name());
+        }
+        for export in import.exports.iter().filter(|e| import.brings_in(&e.name)) {
+            push(
+                &export.name,
+                export_kind(export.is_function),
+                &export.detail,
+            );
+        }
+    }
+    for builtin in Compiler::BUILTINS.iter().filter(|b| !b.starts_with("__")) {
+        push(builtin, KIND_FUNCTION, "builtin");
+    }
+    items
+}
+
+fn string_methods() -> Vec<Value> {
+    Compiler::STRING_METHODS
+        .iter()
+        .map(|(name, arity)| item(name, KIND_METHOD, &format!("fn {}/{}", name, arity)))
+        .collect()
+}
+
+// `own` are the methods the compiler handles itself, the rest go to array::
+fn collection_methods(own: &[(&str, usize)]) -> Vec<Value> {
+    own.iter()
+        .chain(Compiler::ARRAY_METHODS)
+        .map(|(name, arity)| item(name, KIND_METHOD, &format!("fn {}/{}", name, arity)))
+        .collect()
+}
+
+fn export_kind(is_function: bool) -> u8 {
+    if is_function {
+        KIND_FUNCTION
+    } else {
+        KIND_CONSTANT
+    }
+}
+
+fn item(label: &str, kind: u8, detail: &str) -> Value {
+    json!({ "label": label, "kind": kind, "detail": detail })
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
@@ -0,0 +1,178 @@
+// One open file and the result of the last analysis run over it
+
+use super::imports::{StdlibIndex, resolve_imports, scope};
+use super::index::{SymbolIndex, TypeMap};
+use super::position::LineIndex;
+use aelys_backend::Compiler;
+use aelys_common::error::{AelysError, CompileError, CompileErrorKind};
+use aelys_common::{Diagnostic, WarningConfig};
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_opt::{OptimizationLevel, Optimizer};
+use aelys_sema::TypeInference;
+use aelys_syntax::{Source, StmtKind};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+pub struct Document {
+    pub text: String,
+    pub path: Option<PathBuf>,
+    pub lines: LineIndex,
+    pub diagnostics: Vec<Diagnostic>,
+    // from the last version that parsed, so hover and completion keep
+    // working while the user is halfway through typing something
+    pub index: Option<SymbolIndex>,
+}
+
+impl Document {
+    pub fn new(uri: &str) -> Self {
+        Self {
+            text: String::new(),
+            path: uri_to_path(uri),
+            lines: LineIndex::new(""),
+            diagnostics: Vec::new(),
+            index: None,
+        }
+    }
+
+    // replaces the text and re-analyzes all of it, there is no per
+    // declaration re-analysis however small the edit was
+    pub fn update(&mut self, text: String, stdlib: &mut StdlibIndex, warn_config: &WarningConfig) {
+        self.lines = LineIndex::new(&text);
+        self.text = text;
+        self.analyze(stdlib, warn_config);
+    }
+
+    // lexer -> parser -> sema -> optimizer -> backend, stopping at the first
+    // stage that fails, like `aelys run` would
+    fn analyze(&mut self, stdlib: &mut StdlibIndex, warn_config: &WarningConfig) {
+        self.diagnostics.clear();
+        let name = self
+            .path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "<document>".to_string());
+        let src = Source::new(&name, &self.text);
+
+        let parsed = Lexer::with_source(src.clone())
+            .scan()
+            .and_then(|tokens| Parser::new(tokens, src.clone()).parse());
+        let stmts = match parsed {
+            Ok(stmts) => stmts,
+            Err(err) => {
+                self.push_error(&err);
+                return;
+            }
+        };
+
+        let mut imports = stdlib.prelude();
+        imports.extend(resolve_imports(&stmts, self.path.as_deref(), stdlib));
+
+        let (module_aliases, known_globals) = scope(&imports);
+
+        let main_stmts: Vec<_> = stmts
+            .iter()
+            .filter(|s| !matches!(s.kind, StmtKind::Needs(_)))
+            .cloned()
+            .collect();
+        let inferred = TypeInference::infer_program_full(
+            main_stmts,
+            src.clone(),
+            module_aliases.clone(),
+            known_globals.clone(),
+        );
+
+        let mut types = TypeMap::default();
+        match inferred {
+            Ok(result) => {
+                types = TypeMap::from_program(&result.program);
+                let mut warnings = result.warnings;
+
+                let mut optimizer = Optimizer::new(OptimizationLevel::Standard);
+                let program = optimizer.optimize(result.program);
+                warnings.extend(optimizer.take_warnings());
+                self.diagnostics.extend(
+                    warnings
+                        .iter()
+                        .filter(|w| warn_config.is_enabled(&w.kind))
+                        .map(Diagnostic::from),
+                );
+
+                let compiled = Compiler::with_modules(
+                    None,
+                    src.clone(),
+                    module_aliases,
+                    known_globals,
+                    HashSet::new(),
+                    HashMap::new(),
+                )
+                .compile_typed(&program);
+                if let Err(err) = compiled {
+                    self.push_error(&err);
+                }
+            }
+            Err(errors) => {
+                for err in errors {
+                    let kind = CompileErrorKind::TypeInferenceError(err.to_string());
+                    let err = CompileError::new(kind, err.span, src.clone());
+                    self.diagnostics.push(Diagnostic::from(&err));
+                }
+            }
+        }
+
+        self.index = Some(SymbolIndex::build(
+            &stmts,
+            &self.text,
+            &self.lines,
+            &types,
+            imports,
+        ));
+    }
+
+    fn push_error(&mut self, err: &AelysError) {
+        if let AelysError::Compile(err) = err {
+            self.diagnostics.push(Diagnostic::from(err));
+        }
+    }
+}
+
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%'
+            && let Some(hex) = tail.get(..2)
+            && let Ok(byte) = u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16)
+        {
+            bytes.push(byte);
+            rest = &tail[2..];
+            continue;
+        }
+        bytes.push(b);
+        rest = tail;
+    }
+    let decoded = String::from_utf8(bytes).ok()?;
+    // file:///C:/dir -> C:/dir
+    let decoded = match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => decoded[1..].to_string(),
+        _ => decoded,
+    };
+    Some(PathBuf::from(decoded))
+}
+
+pub fn path_to_uri(path: &std::path::Path) -> String {
+    let text = path.display().to_string().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !text.starts_with('/') {
+        uri.push('/');
+    }
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~:".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
@@ -0,0 +1,228 @@
+// What `needs` statements bring into scope, worked out without running any
+// module code: stdlib exports come from a scratch VM, script modules are
+// only parsed.
+
+use super::position::LineIndex;
+use aelys_backend::Compiler;
+use aelys_driver::modules::ModuleLoader;
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_runtime::stdlib;
+use aelys_runtime::{GcRef, ObjectKind, VM};
+use aelys_syntax::{ImportKind, Source, Span, Stmt, StmtKind};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// registered when a VM starts up, usable without `needs`
+const PRELUDE: &[&str] = &["io", "math", "convert", "time"];
+const QUALIFIED_PRELUDE: &[&str] = &["string", "json"];
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub name: String,
+    pub detail: String,
+    pub is_function: bool,
+    pub location: Option<(PathBuf, Span)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: Vec<String>,
+    pub kind: ImportKind,
+    pub alias: String,
+    pub exports: Vec<Export>,
+}
+
+impl Import {
+    pub fn module_name(&self) -> String {
+        self.path.join(".")
+    }
+
+    pub fn export(&self, name: &str) -> Option<&Export> {
+        self.exports.iter().find(|e| e.name == name)
+    }
+
+    /// Whether `name` can be used without the module prefix.
+    pub fn brings_in(&self, name: &str) -> bool {
+        match &self.kind {
+            ImportKind::Symbols(symbols) => symbols.iter().any(|s| s == name),
+            ImportKind::Wildcard | ImportKind::Module { alias: None } => {
+                self.export(name).is_some()
+            }
+            ImportKind::Module { alias: Some(_) } => false,
+        }
+    }
+
+    /// Whether `alias.member` refers to this module.
+    pub fn is_qualified(&self) -> bool {
+        matches!(self.kind, ImportKind::Module { .. })
+    }
+}
+
+pub struct StdlibIndex {
+    vm: Option<VM>,
+    cache: HashMap<String, Vec<Export>>,
+}
+
+impl StdlibIndex {
+    pub fn new() -> Self {
+        Self {
+            vm: VM::new(Source::new("<lsp>", "")).ok(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Exports of `std.<module>`, or `None` for an unknown module.
+    pub fn exports(&mut self, module: &str) -> Option<Vec<Export>> {
+        if let Some(exports) = self.cache.get(module) {
+            return Some(exports.clone());
+        }
+        let vm = self.vm.as_mut()?;
+        let registered = stdlib::register_std_module(vm, module).ok()?;
+        let exports: Vec<Export> = registered
+            .all_exports
+            .iter()
+            .map(|name| {
+                let qualified = format!("{}::{}", module, name);
+                let value = vm.get_global(&qualified);
+                let arity = value.and_then(|v| v.as_ptr()).and_then(|ptr| {
+                    match &vm.heap().get(GcRef::new(ptr))?.kind {
+                        ObjectKind::Native(native) => Some(native.arity),
+                        _ => None,
+                    }
+                });
+                let detail = match (arity, value) {
+                    (Some(arity), _) => format!("fn {}/{}", name, arity),
+                    (None, Some(v)) => format!("{}: {}", name, vm.value_type_name(v)),
+                    (None, None) => name.clone(),
+                };
+                Export {
+                    name: name.clone(),
+                    detail,
+                    is_function: arity.is_some(),
+                    location: None,
+                }
+            })
+            .collect();
+        self.cache.insert(module.to_string(), exports.clone());
+        Some(exports)
+    }
+
+    /// Modules every program sees without `needs`; `string` and `json` only
+    /// through method calls or their prefix.
+    pub fn prelude(&mut self) -> Vec<Import> {
+        let mut imports = Vec::new();
+        for module in PRELUDE
+            .iter()
+            .copied()
+            .chain(QUALIFIED_PRELUDE.iter().copied())
+        {
+            let kind = ImportKind::Module {
+                alias: QUALIFIED_PRELUDE
+                    .contains(&module)
+                    .then(|| module.to_string()),
+            };
+            imports.push(Import {
+                path: vec!["std".to_string(), module.to_string()],
+                kind,
+                alias: module.to_string(),
+                exports: self.exports(module).unwrap_or_default(),
+            });
+        }
+        imports
+    }
+}
+
+/// Resolves the `needs` statements of a document. Modules that can't be
+/// found still produce an entry, with no exports.
+pub fn resolve_imports(
+    stmts: &[Stmt],
+    doc_path: Option<&Path>,
+    stdlib: &mut StdlibIndex,
+) -> Vec<Import> {
+    let loader = doc_path.map(|path| ModuleLoader::new(path, Source::new("<lsp>", "")));
+    let mut imports = Vec::new();
+    for stmt in stmts {
+        let StmtKind::Needs(needs) = &stmt.kind else {
+            continue;
+        };
+        let Some(last) = needs.path.last() else {
+            continue;
+        };
+        let alias = match &needs.kind {
+            ImportKind::Module { alias: Some(alias) } => alias.clone(),
+            _ => last.clone(),
+        };
+        let exports = if stdlib::is_std_module(&needs.path) {
+            stdlib.exports(last).unwrap_or_default()
+        } else {
+            loader
+                .as_ref()
+                .and_then(|loader| loader.resolve_path(&needs.path).ok())
+                .map(|file| script_exports(&file))
+                .unwrap_or_default()
+        };
+        imports.push(Import {
+            path: needs.path.clone(),
+            kind: needs.kind.clone(),
+            alias,
+            exports,
+        });
+    }
+    imports
+}
+
+/// Module aliases and known globals to type check against, given what the
+/// document imports.
+pub fn scope(imports: &[Import]) -> (HashSet<String>, HashSet<String>) {
+    let mut module_aliases = HashSet::new();
+    let mut known_globals: HashSet<String> =
+        Compiler::BUILTINS.iter().map(|s| s.to_string()).collect();
+    for import in imports {
+        if import.is_qualified() {
+            module_aliases.insert(import.alias.clone());
+            for export in &import.exports {
+                known_globals.insert(format!("{}::{}", import.alias, export.name));
+            }
+        }
+        match &import.kind {
+            ImportKind::Symbols(symbols) => known_globals.extend(symbols.iter().cloned()),
+            ImportKind::Wildcard | ImportKind::Module { alias: None } => {
+                known_globals.extend(import.exports.iter().map(|e| e.name.clone()));
+            }
+            ImportKind::Module { alias: Some(_) } => {}
+        }
+    }
+    (module_aliases, known_globals)
+}
+
+fn script_exports(file: &Path) -> Vec<Export> {
+    let Ok(text) = std::fs::read_to_string(file) else {
+        return Vec::new();
+    };
+    let src = Source::new(file.display().to_string(), &text);
+    let Ok(tokens) = Lexer::with_source(src.clone()).scan() else {
+        return Vec::new();
+    };
+    let Ok(stmts) = Parser::new(tokens, src).parse() else {
+        return Vec::new();
+    };
+
+    let lines = LineIndex::new(&text);
+    let mut exports = Vec::new();
+    for stmt in &stmts {
+        let (name, is_function) = match &stmt.kind {
+            StmtKind::Function(func) if func.is_pub => (&func.name, true),
+            StmtKind::Let { name, is_pub, .. } if *is_pub => (name, false),
+            _ => continue,
+        };
+        let span = super::index::name_span(&text, &lines, stmt.span, name);
+        exports.push(Export {
+            name: name.clone(),
+            detail: super::index::signature(&text, &lines, stmt.span),
+            is_function,
+            location: Some((file.to_path_buf(), span)),
+        });
+    }
+    exports
+}
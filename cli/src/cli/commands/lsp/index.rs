@@ -0,0 +1,832 @@
+// Declarations and name references of one document. Built from the syntax
+// tree so navigation keeps working when type checking fails; inferred types
+// are layered on from the typed program when there is one.
+
+use super::imports::Import;
+use super::position::LineIndex;
+use aelys_sema::{
+    InferType, ResolvedType, TypedExpr, TypedExprKind, TypedFmtStringPart, TypedProgram, TypedStmt,
+    TypedStmtKind,
+};
+use aelys_syntax::{
+    Expr, ExprKind, FmtStringPart, Function, Parameter, Span, Stmt, StmtKind, TypeAnnotation,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclKind {
+    Function,
+    Variable,
+    Parameter,
+    Struct,
+    Field,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decl {
+    pub name: String,
+    pub kind: DeclKind,
+    pub name_span: Span,
+    pub full_span: Span,
+    // byte range in which the name resolves to this declaration
+    pub visible: (usize, usize),
+    pub parent: Option<usize>,
+    pub ty: Option<ResolvedType>,
+    // one line, e.g. `let mut x: i64` or `fn add(a: i64, b: i64) -> i64`
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Decl(usize),
+    Import(usize, String),
+    Module(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub span: Span,
+    pub ty: Option<ResolvedType>,
+    pub target: Option<Target>,
+}
+
+pub struct SymbolIndex {
+    pub decls: Vec<Decl>,
+    pub refs: Vec<Reference>,
+    pub imports: Vec<Import>,
+}
+
+impl SymbolIndex {
+    pub fn build(
+        stmts: &[Stmt],
+        text: &str,
+        lines: &LineIndex,
+        types: &TypeMap,
+        imports: Vec<Import>,
+    ) -> Self {
+        let mut builder = Builder {
+            text,
+            lines,
+            types,
+            imports: &imports,
+            decls: Vec::new(),
+            refs: Vec::new(),
+            scopes: Vec::new(),
+            parent: None,
+        };
+        // open-ended, so names stay visible in text appended since this build
+        builder.block(stmts, (0, usize::MAX));
+        let Builder { decls, refs, .. } = builder;
+        Self {
+            decls,
+            refs,
+            imports,
+        }
+    }
+
+    fn target_at(&self, offset: usize) -> Option<(Span, Option<Target>, Option<&ResolvedType>)> {
+        if let Some(r) = self.refs.iter().find(|r| contains(r.span, offset)) {
+            return Some((r.span, r.target.clone(), r.ty.as_ref()));
+        }
+        self.decls
+            .iter()
+            .position(|d| contains(d.name_span, offset))
+            .map(|i| (self.decls[i].name_span, Some(Target::Decl(i)), None))
+    }
+
+    /// Markdown for the name under `offset`.
+    pub fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let (span, target, ty) = self.target_at(offset)?;
+        let text = match target {
+            Some(Target::Decl(i)) => code_block(&self.decls[i].detail),
+            Some(Target::Import(i, name)) => {
+                let import = &self.imports[i];
+                let detail = import
+                    .export(&name)
+                    .map(|e| e.detail.clone())
+                    .unwrap_or(name);
+                format!("{}\n\nfrom `{}`", code_block(&detail), import.module_name())
+            }
+            Some(Target::Module(i)) => {
+                code_block(&format!("module {}", self.imports[i].module_name()))
+            }
+            None => code_block(&ty?.to_string()),
+        };
+        Some((span, text))
+    }
+
+    /// Where the name under `offset` is declared; `None` as the path means
+    /// this document.
+    pub fn definition(&self, offset: usize) -> Option<(Option<PathBuf>, Span)> {
+        match self.target_at(offset)?.1? {
+            Target::Decl(i) => Some((None, self.decls[i].name_span)),
+            Target::Import(i, name) => self.imports[i]
+                .export(&name)?
+                .location
+                .clone()
+                .map(|(path, span)| (Some(path), span)),
+            Target::Module(_) => None,
+        }
+    }
+
+    /// Declarations visible at `offset`, innermost first, one per name.
+    pub fn visible_at(&self, offset: usize) -> Vec<&Decl> {
+        let mut visible: Vec<&Decl> = self
+            .decls
+            .iter()
+            .filter(|d| d.kind != DeclKind::Field)
+            .filter(|d| d.visible.0 <= offset && offset <= d.visible.1)
+            .collect();
+        visible.sort_by_key(|d| std::cmp::Reverse(d.visible.0));
+        let mut seen = std::collections::HashSet::new();
+        visible.retain(|d| seen.insert(d.name.as_str()));
+        visible
+    }
+
+    pub fn fields_of(&self, struct_name: &str) -> Vec<&Decl> {
+        self.decls
+            .iter()
+            .filter(|d| {
+                d.kind == DeclKind::Field
+                    && d.parent.is_some_and(|p| self.decls[p].name == struct_name)
+            })
+            .collect()
+    }
+}
+
+struct Builder<'a> {
+    text: &'a str,
+    lines: &'a LineIndex,
+    types: &'a TypeMap,
+    imports: &'a [Import],
+    decls: Vec<Decl>,
+    refs: Vec<Reference>,
+    scopes: Vec<Vec<usize>>,
+    parent: Option<usize>,
+}
+
+impl Builder<'_> {
+    fn block(&mut self, stmts: &[Stmt], range: (usize, usize)) {
+        self.scopes.push(Vec::new());
+        // functions and structs can be used before their declaration
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Function(func) => {
+                    let detail = self
+                        .types
+                        .functions
+                        .get(&func.span.start)
+                        .cloned()
+                        .unwrap_or_else(|| signature(self.text, self.lines, func.span));
+                    let ty = self.types.decl(func.span.start);
+                    self.declare(&func.name, DeclKind::Function, stmt.span, range, ty, detail);
+                }
+                StmtKind::StructDecl { name, .. } => {
+                    let detail = format!("struct {}", name);
+                    self.declare(name, DeclKind::Struct, stmt.span, range, None, detail);
+                }
+                _ => {}
+            }
+        }
+        for stmt in stmts {
+            self.stmt(stmt, range.1);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, scope_end: usize) {
+        match &stmt.kind {
+            StmtKind::Expression(expr) => self.expr(expr),
+            StmtKind::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                if let Some(ann) = type_annotation {
+                    self.annotation(ann);
+                }
+                self.expr(initializer);
+                let ty = self.types.decl(stmt.span.start);
+                let detail = format!(
+                    "let {}{}{}",
+                    if *mutable { "mut " } else { "" },
+                    name,
+                    type_suffix(ty.as_ref())
+                );
+                self.declare(
+                    name,
+                    DeclKind::Variable,
+                    stmt.span,
+                    (stmt.span.end, scope_end),
+                    ty,
+                    detail,
+                );
+            }
+            StmtKind::Block(stmts) => self.block(stmts, (stmt.span.start, stmt.span.end)),
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.stmt(then_branch, then_branch.span.end);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch, else_branch.span.end);
+                }
+            }
+            StmtKind::While { condition, body } => {
+                self.expr(condition);
+                self.stmt(body, body.span.end);
+            }
+            StmtKind::For {
+                iterator,
+                start,
+                end,
+                step,
+                body,
+                ..
+            } => {
+                self.expr(start);
+                self.expr(end);
+                if let Some(step) = step.as_ref() {
+                    self.expr(step);
+                }
+                self.loop_body(stmt, iterator, body);
+            }
+            StmtKind::ForEach {
+                iterator,
+                iterable,
+                body,
+            } => {
+                self.expr(iterable);
+                self.loop_body(stmt, iterator, body);
+            }
+            StmtKind::Return(Some(expr)) => self.expr(expr),
+            StmtKind::Function(func) => self.function(func),
+            StmtKind::StructDecl { name, fields, .. } => {
+                let Some(owner) = self.lookup_decl(name, DeclKind::Struct) else {
+                    return;
+                };
+                for field in fields {
+                    self.annotation(&field.type_annotation);
+                    let detail = format!(
+                        "{}.{}: {}",
+                        name,
+                        field.name,
+                        self.lines.slice(
+                            self.text,
+                            field.type_annotation.span.start,
+                            field.type_annotation.span.end
+                        )
+                    );
+                    let name_span = name_span(self.text, self.lines, field.span, &field.name);
+                    self.decls.push(Decl {
+                        name: field.name.clone(),
+                        kind: DeclKind::Field,
+                        name_span,
+                        full_span: field.span,
+                        visible: (0, 0),
+                        parent: Some(owner),
+                        ty: None,
+                        detail,
+                    });
+                }
+            }
+            StmtKind::Return(None) | StmtKind::Break | StmtKind::Continue | StmtKind::Needs(_) => {}
+        }
+    }
+
+    fn loop_body(&mut self, stmt: &Stmt, iterator: &str, body: &Stmt) {
+        self.scopes.push(Vec::new());
+        let ty = self.types.decl(stmt.span.start);
+        let detail = format!("{}{}", iterator, type_suffix(ty.as_ref()));
+        let range = (stmt.span.start, stmt.span.end);
+        self.declare(iterator, DeclKind::Variable, stmt.span, range, ty, detail);
+        self.stmt(body, body.span.end);
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, func: &Function) {
+        let owner = self.lookup_decl(&func.name, DeclKind::Function);
+        let outer_parent = self.parent;
+        self.parent = owner.or(outer_parent);
+        if let Some(ret) = &func.return_type {
+            self.annotation(ret);
+        }
+        self.scopes.push(Vec::new());
+        self.params(&func.params, (func.span.start, func.span.end));
+        self.block(&func.body, (func.span.start, func.span.end));
+        self.scopes.pop();
+        self.parent = outer_parent;
+    }
+
+    fn params(&mut self, params: &[Parameter], range: (usize, usize)) {
+        for param in params {
+            if let Some(ann) = &param.type_annotation {
+                self.annotation(ann);
+            }
+            let ty = self.types.decl(param.span.start);
+            let detail = format!("{}{}", param.name, type_suffix(ty.as_ref()));
+            self.declare(
+                &param.name,
+                DeclKind::Parameter,
+                param.span,
+                range,
+                ty,
+                detail,
+            );
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Null => {}
+            ExprKind::FmtString(parts) => {
+                for part in parts {
+                    if let FmtStringPart::Expr(inner) = part {
+                        self.expr(inner);
+                    }
+                }
+            }
+            ExprKind::Identifier(name) => {
+                let target = self.resolve(name);
+                self.reference(expr.span, target);
+            }
+            ExprKind::Assign { name, value } => {
+                self.expr(value);
+                let target = self.resolve(name);
+                self.reference(leading(expr.span, name), target);
+            }
+            ExprKind::Binary { left, right, .. }
+            | ExprKind::And { left, right }
+            | ExprKind::Or { left, right } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { operand, .. } => self.expr(operand),
+            ExprKind::Call { callee, args } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::Grouping(inner) => self.expr(inner),
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                if let Some(ret) = return_type {
+                    self.annotation(ret);
+                }
+                self.scopes.push(Vec::new());
+                self.params(params, (expr.span.start, expr.span.end));
+                self.block(body, (expr.span.start, expr.span.end));
+                self.scopes.pop();
+            }
+            ExprKind::Member { object, member } => self.member(expr.span, object, member),
+            ExprKind::ArrayLiteral {
+                element_type,
+                elements,
+            }
+            | ExprKind::VecLiteral {
+                element_type,
+                elements,
+            } => {
+                if let Some(ann) = element_type {
+                    self.annotation(ann);
+                }
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            ExprKind::ArraySized { element_type, size } => {
+                if let Some(ann) = element_type {
+                    self.annotation(ann);
+                }
+                self.expr(size);
+            }
+            ExprKind::Index { object, index } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            ExprKind::IndexAssign {
+                object,
+                index,
+                value,
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            ExprKind::Range { start, end, .. } => {
+                if let Some(start) = start {
+                    self.expr(start);
+                }
+                if let Some(end) = end {
+                    self.expr(end);
+                }
+            }
+            ExprKind::Slice { object, range } => {
+                self.expr(object);
+                self.expr(range);
+            }
+            ExprKind::StructLiteral { name, fields } => {
+                let owner = self.lookup_decl(name, DeclKind::Struct);
+                self.reference(leading(expr.span, name), owner.map(Target::Decl));
+                for field in fields {
+                    self.expr(&field.value);
+                    let target = owner.and_then(|o| self.field(o, &field.name));
+                    self.reference(leading(field.span, &field.name), target);
+                }
+            }
+            ExprKind::Cast {
+                expr: inner,
+                target,
+            } => {
+                self.expr(inner);
+                self.annotation(target);
+            }
+        }
+    }
+
+    fn member(&mut self, span: Span, object: &Expr, member: &str) {
+        let member_span = Span {
+            start: span.end.saturating_sub(member.len()),
+            ..span
+        };
+        // `alias.name` on an imported module, unless a local shadows the alias
+        if let ExprKind::Identifier(alias) = &object.kind
+            && let Some(Target::Module(i)) = self.resolve(alias)
+        {
+            self.reference(object.span, Some(Target::Module(i)));
+            self.reference(member_span, Some(Target::Import(i, member.to_string())));
+            return;
+        }
+
+        self.expr(object);
+        let field = match self.types.expr(object.span) {
+            Some(ResolvedType::Struct(name)) => self
+                .lookup_decl(&name, DeclKind::Struct)
+                .and_then(|owner| self.field(owner, member)),
+            _ => None,
+        };
+        if field.is_some() {
+            self.reference(member_span, field);
+        }
+    }
+
+    fn annotation(&mut self, ann: &TypeAnnotation) {
+        if let Some(owner) = self.lookup_decl(&ann.name, DeclKind::Struct) {
+            self.reference(leading(ann.span, &ann.name), Some(Target::Decl(owner)));
+        }
+        for arg in ann.type_args.iter().chain(ann.fn_params.iter().flatten()) {
+            self.annotation(arg);
+        }
+        if let Some(ret) = &ann.fn_ret {
+            self.annotation(ret);
+        }
+    }
+
+    fn declare(
+        &mut self,
+        name: &str,
+        kind: DeclKind,
+        full_span: Span,
+        visible: (usize, usize),
+        ty: Option<ResolvedType>,
+        detail: String,
+    ) {
+        let name_span = name_span(self.text, self.lines, full_span, name);
+        self.decls.push(Decl {
+            name: name.to_string(),
+            kind,
+            name_span,
+            full_span,
+            visible,
+            parent: self.parent,
+            ty,
+            detail,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(self.decls.len() - 1);
+        }
+    }
+
+    fn reference(&mut self, span: Span, target: Option<Target>) {
+        let ty = match &target {
+            Some(Target::Decl(i)) => self.decls[*i].ty.clone(),
+            _ => None,
+        }
+        .or_else(|| self.types.expr(span));
+        self.refs.push(Reference { span, ty, target });
+    }
+
+    fn resolve(&self, name: &str) -> Option<Target> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(&i) = scope.iter().rev().find(|&&i| self.decls[i].name == name) {
+                return Some(Target::Decl(i));
+            }
+        }
+        // explicit imports come after the prelude, so search backwards
+        for (i, import) in self.imports.iter().enumerate().rev() {
+            if import.brings_in(name) {
+                return Some(Target::Import(i, name.to_string()));
+            }
+            if import.is_qualified() && import.alias == name {
+                return Some(Target::Module(i));
+            }
+        }
+        None
+    }
+
+    fn lookup_decl(&self, name: &str, kind: DeclKind) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .copied()
+            .find(|&i| self.decls[i].name == name && self.decls[i].kind == kind)
+    }
+
+    fn field(&self, owner: usize, name: &str) -> Option<Target> {
+        self.decls
+            .iter()
+            .position(|d| d.kind == DeclKind::Field && d.parent == Some(owner) && d.name == name)
+            .map(Target::Decl)
+    }
+}
+
+/// Inferred types from a successful type check, keyed by source position.
+#[derive(Default)]
+pub struct TypeMap {
+    exprs: HashMap<(usize, usize), InferType>,
+    // lets, loop variables and parameters, keyed by where they start
+    decls: HashMap<usize, InferType>,
+    functions: HashMap<usize, String>,
+}
+
+impl TypeMap {
+    pub fn from_program(program: &TypedProgram) -> Self {
+        let mut map = Self::default();
+        map.stmts(&program.stmts);
+        map
+    }
+
+    fn expr(&self, span: Span) -> Option<ResolvedType> {
+        self.exprs
+            .get(&(span.start, span.end))
+            .map(ResolvedType::from_infer_type)
+    }
+
+    fn decl(&self, start: usize) -> Option<ResolvedType> {
+        self.decls.get(&start).map(ResolvedType::from_infer_type)
+    }
+
+    fn stmts(&mut self, stmts: &[TypedStmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) {
+        match &stmt.kind {
+            TypedStmtKind::Expression(expr) => self.visit(expr),
+            TypedStmtKind::Let {
+                initializer,
+                var_type,
+                ..
+            } => {
+                self.decls.insert(stmt.span.start, var_type.clone());
+                self.visit(initializer);
+            }
+            TypedStmtKind::Block(stmts) => self.stmts(stmts),
+            TypedStmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit(condition);
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            TypedStmtKind::While { condition, body } => {
+                self.visit(condition);
+                self.stmt(body);
+            }
+            TypedStmtKind::For {
+                start,
+                end,
+                step,
+                body,
+                ..
+            } => {
+                self.decls.insert(stmt.span.start, start.ty.clone());
+                self.visit(start);
+                self.visit(end);
+                if let Some(step) = step.as_ref() {
+                    self.visit(step);
+                }
+                self.stmt(body);
+            }
+            TypedStmtKind::ForEach {
+                iterable,
+                elem_type,
+                body,
+                ..
+            } => {
+                self.decls.insert(stmt.span.start, elem_type.clone());
+                self.visit(iterable);
+                self.stmt(body);
+            }
+            TypedStmtKind::Return(Some(expr)) => self.visit(expr),
+            TypedStmtKind::Function(func) => {
+                let params: Vec<String> = func
+                    .params
+                    .iter()
+                    .map(|p| format!("{}: {}", p.name, ResolvedType::from_infer_type(&p.ty)))
+                    .collect();
+                let signature = format!(
+                    "fn {}({}) -> {}",
+                    func.name,
+                    params.join(", "),
+                    ResolvedType::from_infer_type(&func.return_type)
+                );
+                self.functions.insert(func.span.start, signature);
+                self.decls.insert(
+                    func.span.start,
+                    InferType::Function {
+                        params: func.params.iter().map(|p| p.ty.clone()).collect(),
+                        ret: Box::new(func.return_type.clone()),
+                    },
+                );
+                for param in &func.params {
+                    self.decls.insert(param.span.start, param.ty.clone());
+                }
+                self.stmts(&func.body);
+            }
+            TypedStmtKind::Return(None)
+            | TypedStmtKind::Break
+            | TypedStmtKind::Continue
+            | TypedStmtKind::Needs(_)
+            | TypedStmtKind::StructDecl { .. } => {}
+        }
+    }
+
+    fn visit(&mut self, expr: &TypedExpr) {
+        self.exprs
+            .insert((expr.span.start, expr.span.end), expr.ty.clone());
+        match &expr.kind {
+            TypedExprKind::Int(_)
+            | TypedExprKind::Float(_)
+            | TypedExprKind::Bool(_)
+            | TypedExprKind::String(_)
+            | TypedExprKind::Null
+            | TypedExprKind::Identifier(_) => {}
+            TypedExprKind::FmtString(parts) => {
+                for part in parts {
+                    if let TypedFmtStringPart::Expr(inner) = part {
+                        self.visit(inner);
+                    }
+                }
+            }
+            TypedExprKind::Binary { left, right, .. }
+            | TypedExprKind::And { left, right }
+            | TypedExprKind::Or { left, right } => {
+                self.visit(left);
+                self.visit(right);
+            }
+            TypedExprKind::Unary { operand, .. } => self.visit(operand),
+            TypedExprKind::Call { callee, args } => {
+                self.visit(callee);
+                for arg in args {
+                    self.visit(arg);
+                }
+            }
+            TypedExprKind::Assign { value, .. } => self.visit(value),
+            TypedExprKind::Grouping(inner) | TypedExprKind::Lambda(inner) => self.visit(inner),
+            TypedExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit(condition);
+                self.visit(then_branch);
+                self.visit(else_branch);
+            }
+            TypedExprKind::LambdaInner { params, body, .. } => {
+                for param in params {
+                    self.decls.insert(param.span.start, param.ty.clone());
+                }
+                self.stmts(body);
+            }
+            TypedExprKind::Member { object, .. } => self.visit(object),
+            TypedExprKind::ArrayLiteral { elements, .. }
+            | TypedExprKind::VecLiteral { elements, .. } => {
+                for element in elements {
+                    self.visit(element);
+                }
+            }
+            TypedExprKind::ArraySized { size, .. } => self.visit(size),
+            TypedExprKind::Index { object, index } => {
+                self.visit(object);
+                self.visit(index);
+            }
+            TypedExprKind::IndexAssign {
+                object,
+                index,
+                value,
+            } => {
+                self.visit(object);
+                self.visit(index);
+                self.visit(value);
+            }
+            TypedExprKind::Range { start, end, .. } => {
+                if let Some(start) = start {
+                    self.visit(start);
+                }
+                if let Some(end) = end {
+                    self.visit(end);
+                }
+            }
+            TypedExprKind::Slice { object, range } => {
+                self.visit(object);
+                self.visit(range);
+            }
+            TypedExprKind::StructLiteral { fields, .. } => {
+                for (_, value) in fields {
+                    self.visit(value);
+                }
+            }
+            TypedExprKind::Cast { expr: inner, .. } => self.visit(inner),
+        }
+    }
+}
+
+/// Span of the first whole-word `name` inside `within`; declarations only
+/// carry the span of the whole statement.
+pub fn name_span(text: &str, lines: &LineIndex, within: Span, name: &str) -> Span {
+    let body = lines.slice(text, within.start, within.end);
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let found = body.match_indices(name).find(|&(i, _)| {
+        let before = body[..i].chars().next_back();
+        let after = body[i + name.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    });
+    match found {
+        Some((i, _)) => {
+            let start = within.start + body[..i].chars().count();
+            Span {
+                start,
+                end: start + name.chars().count(),
+                ..within
+            }
+        }
+        None => leading(within, name),
+    }
+}
+
+/// Declaration header as written, up to the body: `fn add(a: int, b) -> int`.
+pub fn signature(text: &str, lines: &LineIndex, span: Span) -> String {
+    let source = lines.slice(text, span.start, span.end);
+    let header = source.split('{').next().unwrap_or(source);
+    let header = header.split('=').next().unwrap_or(header);
+    header.trim().to_string()
+}
+
+fn leading(span: Span, name: &str) -> Span {
+    Span {
+        end: span.start + name.chars().count(),
+        ..span
+    }
+}
+
+fn contains(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+fn type_suffix(ty: Option<&ResolvedType>) -> String {
+    ty.map(|t| format!(": {}", t)).unwrap_or_default()
+}
+
+fn code_block(code: &str) -> String {
+    format!("```aelys\n{}\n```", code)
+}
@@ -0,0 +1,299 @@
+// `aelys lsp`: Language Server Protocol over stdio. Only the text sync is
+// incremental: edits arrive as ranges and are patched into the stored text,
+// but every change then re-analyzes the whole document from scratch. Stdlib
+// exports are looked up once and cached.
+
+mod completion;
+mod document;
+pub(crate) mod imports;
+mod index;
+mod position;
+mod transport;
+
+use crate::cli::framing::MessageReader;
+use aelys_common::{Severity, WarningConfig};
+use document::{Document, path_to_uri};
+use imports::StdlibIndex;
+use index::{DeclKind, SymbolIndex};
+use position::LineIndex;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use transport::{INVALID_REQUEST, LspWriter, METHOD_NOT_FOUND};
+
+// SymbolKind values from the LSP spec
+const SYMBOL_FIELD: u8 = 8;
+const SYMBOL_FUNCTION: u8 = 12;
+const SYMBOL_VARIABLE: u8 = 13;
+const SYMBOL_STRUCT: u8 = 23;
+
+pub fn run_with_options(warn_config: WarningConfig) -> Result<i32, String> {
+    run_lsp_with_io(io::stdin().lock(), io::stdout(), warn_config)
+}
+
+pub fn run_lsp_with_io<R: BufRead, W: Write>(
+    input: R,
+    output: W,
+    warn_config: WarningConfig,
+) -> Result<i32, String> {
+    let mut server = Server {
+        reader: MessageReader::new(input),
+        writer: LspWriter::new(output),
+        documents: HashMap::new(),
+        stdlib: StdlibIndex::new(),
+        warn_config,
+        shutdown: false,
+    };
+    server.serve().map_err(|err| err.to_string())
+}
+
+struct Server<R, W> {
+    reader: MessageReader<R>,
+    writer: LspWriter<W>,
+    documents: HashMap<String, Document>,
+    stdlib: StdlibIndex,
+    warn_config: WarningConfig,
+    shutdown: bool,
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    // exit code follows the spec: 0 only if `shutdown` came before `exit`
+    fn serve(&mut self) -> io::Result<i32> {
+        while let Some(message) = self.reader.read_message()? {
+            let method = message["method"].as_str().unwrap_or_default();
+            if method == "exit" {
+                break;
+            }
+            match message.get("id") {
+                Some(id) => self.request(id, method, &message["params"])?,
+                None => self.notification(method, &message["params"])?,
+            }
+        }
+        Ok(if self.shutdown { 0 } else { 1 })
+    }
+
+    fn request(&mut self, id: &Value, method: &str, params: &Value) -> io::Result<()> {
+        if self.shutdown {
+            return self
+                .writer
+                .fail(id, INVALID_REQUEST, "server is shutting down");
+        }
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // incremental text sync only, see the top of this file
+                    "textDocumentSync": 2,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": { "triggerCharacters": ["."] },
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "aelys", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            _ => {
+                let message = format!("unsupported method '{}'", method);
+                return self.writer.fail(id, METHOD_NOT_FOUND, &message);
+            }
+        };
+        self.writer.respond(id, result)
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), Document::new(&uri));
+                self.update(&uri, text.to_string())
+            }
+            "textDocument/didChange" => {
+                let Some(doc) = self.documents.get(&uri) else {
+                    return Ok(());
+                };
+                let mut text = doc.text.clone();
+                for change in params["contentChanges"].as_array().into_iter().flatten() {
+                    apply_change(&mut text, change);
+                }
+                self.update(&uri, text)
+            }
+            // an imported module may have changed on disk
+            "textDocument/didSave" => {
+                let uris: Vec<String> = self.documents.keys().cloned().collect();
+                for uri in uris {
+                    let text = self.documents[&uri].text.clone();
+                    self.update(&uri, text)?;
+                }
+                Ok(())
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.writer.notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> io::Result<()> {
+        let Some(doc) = self.documents.get_mut(uri) else {
+            return Ok(());
+        };
+        doc.update(text, &mut self.stdlib, &self.warn_config);
+        let diagnostics: Vec<Value> = doc
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let message = match &d.hint {
+                    Some(hint) => format!("{}\nhint: {}", d.message, hint),
+                    None => d.message.clone(),
+                };
+                json!({
+                    "range": doc.lines.range(&doc.text, d.span),
+                    "severity": match d.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "code": d.code,
+                    "source": "aelys",
+                    "message": message,
+                })
+            })
+            .collect();
+        self.writer.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    // the document and char offset a position request points at
+    fn locate(&self, params: &Value) -> Option<(&Document, &SymbolIndex, usize)> {
+        let doc = self
+            .documents
+            .get(params["textDocument"]["uri"].as_str()?)?;
+        let offset = doc.lines.offset(&doc.text, &params["position"]);
+        Some((doc, doc.index.as_ref()?, offset))
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((doc, index, offset)) = self.locate(params) else {
+            return Value::Null;
+        };
+        match index.hover(offset) {
+            Some((span, text)) => json!({
+                "contents": { "kind": "markdown", "value": text },
+                "range": doc.lines.range(&doc.text, span),
+            }),
+            None => Value::Null,
+        }
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let Some((doc, index, offset)) = self.locate(params) else {
+            return Value::Null;
+        };
+        match index.definition(offset) {
+            Some((None, span)) => json!({
+                "uri": params["textDocument"]["uri"],
+                "range": doc.lines.range(&doc.text, span),
+            }),
+            Some((Some(path), span)) => self.location_in(&path, span),
+            None => Value::Null,
+        }
+    }
+
+    // a span in another file, preferring the editor's copy if it's open
+    fn location_in(&self, path: &Path, span: aelys_syntax::Span) -> Value {
+        let uri = path_to_uri(path);
+        let range = match self.documents.get(&uri) {
+            Some(doc) => doc.lines.range(&doc.text, span),
+            None => {
+                let text = std::fs::read_to_string(path).unwrap_or_default();
+                LineIndex::new(&text).range(&text, span)
+            }
+        };
+        json!({ "uri": uri, "range": range })
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some(doc) = params["textDocument"]["uri"]
+            .as_str()
+            .and_then(|uri| self.documents.get(uri))
+        else {
+            return Value::Null;
+        };
+        let offset = doc.lines.offset(&doc.text, &params["position"]);
+        json!({
+            "isIncomplete": false,
+            "items": completion::complete(doc, offset),
+        })
+    }
+
+    fn document_symbols(&self, params: &Value) -> Value {
+        let Some(doc) = params["textDocument"]["uri"]
+            .as_str()
+            .and_then(|uri| self.documents.get(uri))
+        else {
+            return Value::Null;
+        };
+        let Some(index) = &doc.index else {
+            return json!([]);
+        };
+        Value::Array(symbols_under(doc, index, None))
+    }
+}
+
+fn symbols_under(doc: &Document, index: &SymbolIndex, parent: Option<usize>) -> Vec<Value> {
+    index
+        .decls
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| d.parent == parent)
+        .filter_map(|(i, d)| {
+            let kind = match d.kind {
+                DeclKind::Function => SYMBOL_FUNCTION,
+                DeclKind::Struct => SYMBOL_STRUCT,
+                DeclKind::Field => SYMBOL_FIELD,
+                // locals belong to their function, not the outline
+                DeclKind::Variable if parent.is_none() => SYMBOL_VARIABLE,
+                DeclKind::Variable | DeclKind::Parameter => return None,
+            };
+            Some(json!({
+                "name": d.name,
+                "detail": d.detail,
+                "kind": kind,
+                "range": doc.lines.range(&doc.text, d.full_span),
+                "selectionRange": doc.lines.range(&doc.text, d.name_span),
+                "children": symbols_under(doc, index, Some(i)),
+            }))
+        })
+        .collect()
+}
+
+// full sync sends the whole text; a ranged change is applied in place
+fn apply_change(text: &mut String, change: &Value) {
+    let Some(new_text) = change["text"].as_str() else {
+        return;
+    };
+    if change.get("range").is_none() {
+        *text = new_text.to_string();
+        return;
+    }
+    let lines = LineIndex::new(text);
+    let start = lines.offset(text, &change["range"]["start"]);
+    let end = lines.offset(text, &change["range"]["end"]).max(start);
+    text.replace_range(lines.byte(start)..lines.byte(end), new_text);
+}
@@ -0,0 +1,73 @@
+// LSP positions are 0-based lines and UTF-16 columns; spans count chars
+
+use aelys_syntax::Span;
+use serde_json::{Value, json};
+
+pub struct LineIndex {
+    // byte offset of every char, plus one past the end
+    bytes: Vec<usize>,
+    // char offset where each line starts
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut bytes = Vec::with_capacity(text.len() + 1);
+        let mut line_starts = vec![0];
+        for (i, (byte, ch)) in text.char_indices().enumerate() {
+            bytes.push(byte);
+            if ch == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        bytes.push(text.len());
+        Self { bytes, line_starts }
+    }
+
+    /// Byte offset of char offset `offset`, clamped to the end.
+    pub fn byte(&self, offset: usize) -> usize {
+        self.bytes[offset.min(self.bytes.len() - 1)]
+    }
+
+    /// Text between two char offsets.
+    pub fn slice<'a>(&self, text: &'a str, start: usize, end: usize) -> &'a str {
+        text.get(self.byte(start)..self.byte(end.max(start)))
+            .unwrap_or("")
+    }
+
+    pub fn position(&self, text: &str, offset: usize) -> Value {
+        let offset = offset.min(self.bytes.len() - 1);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let character: usize = self
+            .slice(text, self.line_starts[line], offset)
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        json!({ "line": line, "character": character })
+    }
+
+    pub fn range(&self, text: &str, span: Span) -> Value {
+        json!({
+            "start": self.position(text, span.start),
+            "end": self.position(text, span.end.max(span.start)),
+        })
+    }
+
+    /// Char offset of an LSP `Position`, clamped to the document.
+    pub fn offset(&self, text: &str, position: &Value) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let mut remaining = position["character"].as_u64().unwrap_or(0) as usize;
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.bytes.len() - 1;
+        };
+        let mut offset = start;
+        for ch in text[self.byte(start)..].chars() {
+            if ch == '\n' || remaining == 0 {
+                break;
+            }
+            remaining = remaining.saturating_sub(ch.len_utf16());
+            offset += 1;
+        }
+        offset
+    }
+}
@@ -0,0 +1,43 @@
+// JSON-RPC 2.0 responses and notifications; framing lives in `cli::framing`
+
+use crate::cli::framing::write_message;
+use serde_json::{Value, json};
+use std::io::{self, Write};
+
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_REQUEST: i64 = -32600;
+
+pub struct LspWriter<W> {
+    output: W,
+}
+
+impl<W: Write> LspWriter<W> {
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+
+    pub fn respond(&mut self, id: &Value, result: Value) -> io::Result<()> {
+        write_message(
+            &mut self.output,
+            &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        )
+    }
+
+    pub fn fail(&mut self, id: &Value, code: i64, message: &str) -> io::Result<()> {
+        write_message(
+            &mut self.output,
+            &json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        )
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        write_message(
+            &mut self.output,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+}
@@ -0,0 +1,105 @@
+// `aelys run --profile`: runs a script with the VM profiler attached, prints
+// a report on stderr and leaves `<name>.folded` next to the script for
+// flamegraph tools (inferno-flamegraph, flamegraph.pl, speedscope).
+
+use crate::cli::commands::run::load_source;
+use crate::cli::diagnostics::Reporter;
+use crate::cli::vm_config::parse_vm_args_or_error;
+use aelys_common::WarningConfig;
+use aelys_driver::LoadedProgram;
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::{ProfileReport, Profiler};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// longer tables are cut, the folded file always has everything
+const MAX_ROWS: usize = 25;
+
+pub fn run_with_options(
+    path: &str,
+    program_args: Vec<String>,
+    vm_args: Vec<String>,
+    opt_level: OptimizationLevel,
+    warn_config: WarningConfig,
+    reporter: &mut Reporter,
+) -> Result<i32, String> {
+    let config = parse_vm_args_or_error(&vm_args)?.config;
+    let path_ref = Path::new(path);
+    let LoadedProgram { mut vm, main, .. } = load_source(
+        path_ref,
+        config,
+        program_args,
+        opt_level,
+        &warn_config,
+        reporter,
+    )?;
+
+    vm.attach_profiler(Profiler::new());
+    let result = vm.execute(main);
+    // a failing program still gets its profile, it's often why you look
+    let report = vm.detach_profiler().unwrap_or_default();
+
+    let folded_path = path_ref.with_extension("folded");
+    std::fs::write(&folded_path, folded(&report))
+        .map_err(|err| format!("failed to write {}: {}", folded_path.display(), err))?;
+    write_report(&report, &mut io::stderr()).map_err(|err| err.to_string())?;
+    eprintln!("folded stacks written to {}", folded_path.display());
+
+    let value = result.map_err(|err| reporter.fail(&err.into()))?;
+    if !value.is_null() {
+        println!("{}", value);
+    }
+    Ok(0)
+}
+
+/// One `outer;inner count` line per stack, counts in microseconds of self time.
+pub fn folded(report: &ProfileReport) -> String {
+    let mut out = String::new();
+    for (stack, time) in &report.folded {
+        let micros = time.as_micros();
+        if micros > 0 {
+            out.push_str(&format!("{} {}\n", stack, micros));
+        }
+    }
+    out
+}
+
+pub fn write_report<W: Write>(report: &ProfileReport, out: &mut W) -> io::Result<()> {
+    writeln!(out, "\nprofile: {} total", millis(report.total))?;
+
+    writeln!(
+        out,
+        "\n{:>10} {:>12} {:>12}  function",
+        "calls", "total", "self"
+    )?;
+    for f in report.functions.iter().take(MAX_ROWS) {
+        writeln!(
+            out,
+            "{:>10} {:>12} {:>12}  {} (line {})",
+            f.calls,
+            millis(f.inclusive),
+            millis(f.exclusive),
+            f.name,
+            f.line
+        )?;
+    }
+    more(out, report.functions.len())?;
+
+    writeln!(out, "\n{:>10}  opcode", "count")?;
+    for (name, count) in report.opcodes.iter().take(MAX_ROWS) {
+        writeln!(out, "{:>10}  {}", count, name)?;
+    }
+    more(out, report.opcodes.len())
+}
+
+fn more<W: Write>(out: &mut W, len: usize) -> io::Result<()> {
+    if len > MAX_ROWS {
+        writeln!(out, "{:>10}  ... {} more", "", len - MAX_ROWS)?;
+    }
+    Ok(())
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.3}ms", d.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,146 @@
+// Line editing for interactive sessions: rustyline with history kept in
+// ~/.aelys_history and tab completion of names the session knows about.
+
+use super::input::{Input, LineReader};
+use super::meta::COMMANDS;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::path::PathBuf;
+
+const KEYWORDS: &[&str] = &[
+    "let", "mut", "fn", "if", "else", "while", "for", "in", "step", "return", "break", "continue",
+    "and", "or", "not", "true", "false", "null", "pub", "needs", "as", "from", "struct",
+];
+
+#[derive(Default)]
+struct ReplHelper {
+    // globals, `module::member` pairs and module aliases
+    names: Vec<String>,
+}
+
+impl ReplHelper {
+    fn candidates(&self, line: &str, word: &str) -> Vec<String> {
+        if line.starts_with(':') && !line.contains(' ') {
+            return COMMANDS
+                .iter()
+                .filter(|c| c.starts_with(line))
+                .map(|c| c.to_string())
+                .collect();
+        }
+
+        // `math.sq` completes the members of math
+        if let Some((module, member)) = word.rsplit_once('.') {
+            let prefix = format!("{}::{}", module, member);
+            return self
+                .names
+                .iter()
+                .filter(|n| n.starts_with(&prefix))
+                .map(|n| format!("{}.{}", module, &n[module.len() + 2..]))
+                .collect();
+        }
+
+        let mut found: Vec<String> = KEYWORDS
+            .iter()
+            .map(|k| k.to_string())
+            .chain(self.names.iter().filter(|n| !n.contains("::")).cloned())
+            .filter(|n| n.starts_with(word))
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == ':'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &before[start..];
+        let found = self
+            .candidates(before, word)
+            .into_iter()
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: name,
+            })
+            .collect();
+        Ok((start, found))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+pub(super) struct LineEditor {
+    editor: Editor<ReplHelper, FileHistory>,
+    history: Option<PathBuf>,
+}
+
+impl LineEditor {
+    pub(super) fn new() -> Result<Self, String> {
+        let mut editor = Editor::new().map_err(|err| err.to_string())?;
+        editor.set_helper(Some(ReplHelper::default()));
+        let history = history_path();
+        if let Some(path) = &history {
+            // a missing file just means a first session
+            let _ = editor.load_history(path);
+        }
+        Ok(Self { editor, history })
+    }
+}
+
+impl Drop for LineEditor {
+    fn drop(&mut self) {
+        if let Some(path) = &self.history {
+            let _ = self.editor.save_history(path);
+        }
+    }
+}
+
+impl LineReader for LineEditor {
+    fn read_line(&mut self, prompt: &str) -> Result<Input, String> {
+        match self.editor.readline(prompt) {
+            Ok(line) => Ok(Input::Line(line)),
+            Err(ReadlineError::Interrupted) => Ok(Input::Interrupted),
+            Err(ReadlineError::Eof) => Ok(Input::Eof),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        let _ = self.editor.add_history_entry(entry);
+    }
+
+    fn set_names(&mut self, names: Vec<String>) {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.names = names;
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".aelys_history"))
+}
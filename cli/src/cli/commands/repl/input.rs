@@ -0,0 +1,98 @@
+// Where REPL lines come from, and when they add up to something to run.
+
+use std::io::{BufRead, Write};
+
+pub(super) enum Input {
+    Line(String),
+    // Ctrl-C: drop whatever has been typed so far
+    Interrupted,
+    Eof,
+}
+
+pub(super) trait LineReader {
+    fn read_line(&mut self, prompt: &str) -> Result<Input, String>;
+
+    fn add_history(&mut self, _entry: &str) {}
+
+    /// Called after each input with the names completion should offer.
+    fn set_names(&mut self, _names: Vec<String>) {}
+}
+
+/// Plain lines from any reader; prompts are written only when interactive.
+pub(super) struct Plain<R, W> {
+    pub input: R,
+    pub prompts: Option<W>,
+}
+
+impl<R: BufRead, W: Write> LineReader for Plain<R, W> {
+    fn read_line(&mut self, prompt: &str) -> Result<Input, String> {
+        if let Some(out) = self.prompts.as_mut() {
+            write!(out, "{}", prompt).map_err(|err| err.to_string())?;
+            out.flush().map_err(|err| err.to_string())?;
+        }
+        let mut line = String::new();
+        let bytes = self
+            .input
+            .read_line(&mut line)
+            .map_err(|err| err.to_string())?;
+        if bytes == 0 {
+            return Ok(Input::Eof);
+        }
+        Ok(Input::Line(line.trim_end_matches(['\n', '\r']).to_string()))
+    }
+}
+
+/// True once every bracket, string and block comment opened in `source` has
+/// been closed. Too many closers counts as complete so the parser can say so.
+pub(super) fn is_complete(source: &str) -> bool {
+    let mut depth: i32 = 0;
+    let mut comments = 0;
+    let mut in_string = false;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if comments > 0 {
+            match (c, chars.peek()) {
+                ('/', Some('*')) => {
+                    chars.next();
+                    comments += 1;
+                }
+                ('*', Some('/')) => {
+                    chars.next();
+                    comments -= 1;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                comments += 1;
+            }
+            ('"', _) => in_string = true,
+            ('(' | '[' | '{', _) => depth += 1,
+            (')' | ']' | '}', _) => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth <= 0 && comments == 0 && !in_string
+}
@@ -0,0 +1,147 @@
+// `:` commands. Whatever they print goes to the session's output, errors
+// included, so a bad argument never ends the session.
+
+use super::Session;
+use crate::cli::commands::compile::source_to_air;
+use aelys_backend::Compiler;
+use aelys_bytecode::asm::disassemble_to_string;
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_runtime::{Function, GcRef, ObjectKind, VM};
+use aelys_sema::{TypeInference, TypedStmtKind};
+use aelys_syntax::{Source, StmtKind};
+use std::io::{self, Write};
+use std::time::Instant;
+
+pub(super) const COMMANDS: &[&str] = &[
+    ":type", ":asm", ":air", ":load", ":time", ":reset", ":help", ":quit",
+];
+
+const HELP: &str = "\
+:type <expr>    static type of an expression
+:asm <fn>       bytecode of a function defined in this session
+:air [code]     AIR of the code, or of everything entered so far
+:load <file>    run a file in this session
+:time <code>    run code and show how long it took
+:reset          start over with a fresh VM
+:help           this list
+:quit           leave (so do exit, quit and Ctrl-D)";
+
+/// Runs one meta-command; false means the session should end.
+pub(super) fn run<W: Write>(session: &mut Session, line: &str, out: &mut W) -> io::Result<bool> {
+    let (command, arg) = match line.split_once(char::is_whitespace) {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (line, ""),
+    };
+    let needs_arg = |usage: &str| format!("usage: {}", usage);
+
+    let result = match command {
+        ":type" | ":t" if arg.is_empty() => Err(needs_arg(":type <expr>")),
+        ":type" | ":t" => type_of(session, arg),
+        ":asm" if arg.is_empty() => Err(needs_arg(":asm <fn>")),
+        ":asm" => disassemble(&session.vm, arg),
+        ":air" => air(session, arg),
+        ":load" | ":l" if arg.is_empty() => Err(needs_arg(":load <file>")),
+        ":load" | ":l" => match std::fs::read_to_string(arg) {
+            Ok(content) => {
+                session.eval(&content, arg, out)?;
+                return Ok(true);
+            }
+            Err(err) => Err(format!("failed to read {}: {}", arg, err)),
+        },
+        ":time" if arg.is_empty() => Err(needs_arg(":time <code>")),
+        ":time" => {
+            let started = Instant::now();
+            session.eval(arg, "<repl>", out)?;
+            let elapsed = started.elapsed();
+            Ok(format!("time: {:.3}ms", elapsed.as_secs_f64() * 1000.0))
+        }
+        ":reset" => match Session::new(session.config.clone(), session.opt_level) {
+            Ok(fresh) => {
+                *session = fresh;
+                Ok("session reset".to_string())
+            }
+            Err(err) => Err(err),
+        },
+        ":help" | ":h" | ":?" => Ok(HELP.to_string()),
+        ":quit" | ":q" => return Ok(false),
+        _ => Err(format!("unknown command {}, :help lists them", command)),
+    };
+
+    match result {
+        Ok(text) => writeln!(out, "{}", text.trim_end())?,
+        Err(err) => writeln!(out, "error: {}", err)?,
+    }
+    Ok(true)
+}
+
+// earlier inputs give the names they defined their types; if they no longer
+// check as one program, the expression is typed on its own
+fn type_of(session: &Session, expr: &str) -> Result<String, String> {
+    let with_session = format!("{}{}", session.source, expr);
+    infer_last(&session.vm, &with_session).or_else(|_| infer_last(&session.vm, expr))
+}
+
+fn infer_last(vm: &VM, code: &str) -> Result<String, String> {
+    let src = Source::new("<repl>", code);
+    let tokens = Lexer::with_source(src.clone())
+        .scan()
+        .map_err(|err| err.to_string())?;
+    let stmts: Vec<_> = Parser::new(tokens, src.clone())
+        .parse()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|stmt| !matches!(stmt.kind, StmtKind::Needs(_)))
+        .collect();
+
+    let mut known_globals = vm.repl_known_globals().clone();
+    known_globals.extend(Compiler::BUILTINS.iter().map(|b| b.to_string()));
+    let program = TypeInference::infer_program_with_imports(
+        stmts,
+        src,
+        vm.repl_module_aliases().clone(),
+        known_globals,
+    )
+    .map_err(|errors| {
+        errors
+            .first()
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| "Unknown type error".to_string())
+    })?;
+
+    match program.stmts.last().map(|stmt| &stmt.kind) {
+        Some(TypedStmtKind::Expression(expr)) => Ok(expr.ty.to_string()),
+        _ => Err("not an expression".to_string()),
+    }
+}
+
+fn disassemble(vm: &VM, name: &str) -> Result<String, String> {
+    let value = vm
+        .get_global(name)
+        .ok_or_else(|| format!("no function named {}", name))?;
+    let function = value
+        .as_ptr()
+        .and_then(|ptr| function_of(vm, GcRef::new(ptr)))
+        .ok_or_else(|| format!("{} is not a function", name))?;
+    Ok(disassemble_to_string(function, Some(vm.heap())))
+}
+
+fn function_of(vm: &VM, func_ref: GcRef) -> Option<&Function> {
+    match &vm.heap().get(func_ref)?.kind {
+        ObjectKind::Function(f) => Some(&f.function),
+        ObjectKind::Closure(c) => function_of(vm, c.function),
+        _ => None,
+    }
+}
+
+fn air(session: &Session, code: &str) -> Result<String, String> {
+    let code = if code.is_empty() {
+        session.source.as_str()
+    } else {
+        code
+    };
+    // modules resolve from the working directory, as they do for inputs
+    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let program = source_to_air(&cwd.join("repl.aelys"), code, session.opt_level)?;
+    Ok(aelys_air::print::print_program(&program))
+}
@@ -0,0 +1,172 @@
+// Interactive sessions get line editing, history and completion through
+// rustyline; piped input is read line by line. Either way input continues
+// over several lines until its brackets balance, and lines starting with `:`
+// are meta-commands (see meta.rs).
+
+mod editor;
+mod input;
+mod meta;
+
+use crate::cli::vm_config::parse_vm_args_or_error;
+use aelys::{new_vm_with_config, run_with_vm_and_opt};
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::{VM, VmConfig};
+use input::{Input, LineReader, Plain};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+const PROMPT: &str = "aelys> ";
+const CONTINUATION: &str = "  ...> ";
+
+#[allow(dead_code)]
+pub fn run_repl_with_io<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    opt_level: OptimizationLevel,
+    vm_args: Vec<String>,
+) -> Result<(), String> {
+    let mut reader = Plain {
+        input,
+        prompts: None::<io::Sink>,
+    };
+    run_repl_core(&mut reader, &mut output, opt_level, vm_args, false)
+}
+
+pub fn run_with_options(opt_level: OptimizationLevel, vm_args: Vec<String>) -> Result<i32, String> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if stdin.is_terminal() && stdout.is_terminal() {
+        let mut reader = editor::LineEditor::new()?;
+        run_repl_core(&mut reader, &mut stdout.lock(), opt_level, vm_args, true)?;
+    } else {
+        let mut reader = Plain {
+            input: stdin.lock(),
+            prompts: None::<io::Sink>,
+        };
+        run_repl_core(&mut reader, &mut stdout.lock(), opt_level, vm_args, false)?;
+    }
+    Ok(0)
+}
+
+/// The VM plus everything entered into it that ran, which is what `:type`
+/// and `:air` look at to know about earlier definitions.
+struct Session {
+    vm: VM,
+    config: VmConfig,
+    opt_level: OptimizationLevel,
+    source: String,
+}
+
+impl Session {
+    fn new(config: VmConfig, opt_level: OptimizationLevel) -> Result<Self, String> {
+        let vm = new_vm_with_config(config.clone(), Vec::new()).map_err(|err| err.to_string())?;
+        Ok(Self {
+            vm,
+            config,
+            opt_level,
+            source: String::new(),
+        })
+    }
+
+    fn eval<W: Write>(&mut self, code: &str, name: &str, output: &mut W) -> io::Result<bool> {
+        match run_with_vm_and_opt(&mut self.vm, code, name, self.opt_level) {
+            Ok(value) => {
+                self.source.push_str(code);
+                self.source.push('\n');
+                if !value.is_null() {
+                    writeln!(output, "{}", self.vm.value_to_string(value))?;
+                }
+                Ok(true)
+            }
+            Err(err) => {
+                writeln!(output, "{}", err)?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        let vm = &self.vm;
+        vm.repl_known_globals()
+            .iter()
+            .chain(vm.repl_known_native_globals())
+            .chain(vm.repl_module_aliases())
+            .cloned()
+            .collect()
+    }
+}
+
+fn run_repl_core<L: LineReader, W: Write>(
+    reader: &mut L,
+    output: &mut W,
+    opt_level: OptimizationLevel,
+    vm_args: Vec<String>,
+    interactive: bool,
+) -> Result<(), String> {
+    let parsed = parse_vm_args_or_error(&vm_args)?;
+    let mut session = Session::new(parsed.config, opt_level)?;
+    reader.set_names(session.names());
+
+    if interactive {
+        writeln!(output, "Aelys REPL (:help for commands, exit to quit)")
+            .map_err(|err| err.to_string())?;
+    }
+
+    let mut buffer = String::new();
+    loop {
+        let prompt = if buffer.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION
+        };
+        let line = match reader.read_line(prompt)? {
+            Input::Line(line) => line,
+            Input::Interrupted => {
+                buffer.clear();
+                continue;
+            }
+            Input::Eof => break,
+        };
+
+        if buffer.is_empty() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if trimmed == "exit" || trimmed == "quit" {
+                break;
+            }
+            if trimmed.starts_with(':') {
+                reader.add_history(trimmed);
+                let keep_going =
+                    meta::run(&mut session, trimmed, output).map_err(|err| err.to_string())?;
+                reader.set_names(session.names());
+                if !keep_going {
+                    break;
+                }
+                continue;
+            }
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+        if !input::is_complete(&buffer) {
+            continue;
+        }
+
+        let code = std::mem::take(&mut buffer);
+        let code = code.trim_end();
+        reader.add_history(code);
+        session
+            .eval(code, "<repl>", output)
+            .map_err(|err| err.to_string())?;
+        reader.set_names(session.names());
+    }
+
+    // input that ended halfway through still gets run, for the error
+    if !buffer.trim().is_empty() {
+        session
+            .eval(buffer.trim_end(), "<repl>", output)
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
@@ -1,10 +1,11 @@
+use crate::cli::diagnostics::Reporter;
 use crate::cli::vm_config::parse_vm_args_or_error;
-use aelys_common::{WarningConfig, format_warnings};
-use aelys_driver::run_file_full;
+use aelys_common::{Warning, WarningConfig};
+use aelys_driver::{LoadedProgram, load_file, run_file_full};
 use aelys_modules::manifest::Manifest;
 use aelys_opt::OptimizationLevel;
-use aelys_runtime::VM;
 use aelys_runtime::native::NativeLoader;
+use aelys_runtime::{VM, VmConfig};
 use aelys_syntax::{ImportKind, NeedsStmt, Source, Span};
 use semver::{Version, VersionReq};
 use std::collections::{HashMap, HashSet};
@@ -16,6 +17,7 @@ pub fn run_with_options(
     vm_args: Vec<String>,
     opt_level: OptimizationLevel,
     warn_config: WarningConfig,
+    reporter: &mut Reporter,
 ) -> Result<i32, String> {
     let parsed = parse_vm_args_or_error(&vm_args)?;
     let config = parsed.config;
@@ -27,22 +29,8 @@ pub fn run_with_options(
         InputFormat::Source => {
             ensure_utf8_source(path_ref)?;
             let result = run_file_full(path_ref, config, program_args, opt_level)
-                .map_err(|err| err.to_string())?;
-
-            let filtered: Vec<_> = result
-                .warnings
-                .iter()
-                .filter(|w| warn_config.is_enabled(&w.kind))
-                .collect();
-
-            for w in &filtered {
-                eprintln!("{}", format_warnings(std::slice::from_ref(*w)));
-            }
-
-            if warn_config.treat_as_error && !filtered.is_empty() {
-                return Err(format!("{} warning(s) treated as errors", filtered.len()));
-            }
-
+                .map_err(|err| reporter.fail(&err))?;
+            report_warnings(&result.warnings, &warn_config, reporter)?;
             result.value
         }
     };
@@ -53,6 +41,49 @@ pub fn run_with_options(
     Ok(0)
 }
 
+/// Compiles a source script without running it, for the instrumented runs
+/// (`--profile`, `--coverage`) that need the VM before execution starts.
+pub(crate) fn load_source(
+    path: &Path,
+    config: VmConfig,
+    program_args: Vec<String>,
+    opt_level: OptimizationLevel,
+    warn_config: &WarningConfig,
+    reporter: &mut Reporter,
+) -> Result<LoadedProgram, String> {
+    let compiled = path
+        .extension()
+        .is_some_and(|ext| ext == "avbc" || ext == "aasm");
+    if compiled || detect_format(path)? != InputFormat::Source {
+        return Err(format!("{} is not a source file", path.display()));
+    }
+    ensure_utf8_source(path)?;
+    let loaded =
+        load_file(path, config, program_args, opt_level).map_err(|err| reporter.fail(&err))?;
+    report_warnings(&loaded.warnings, warn_config, reporter)?;
+    Ok(loaded)
+}
+
+fn report_warnings(
+    warnings: &[Warning],
+    warn_config: &WarningConfig,
+    reporter: &mut Reporter,
+) -> Result<(), String> {
+    let filtered: Vec<_> = warnings
+        .iter()
+        .filter(|w| warn_config.is_enabled(&w.kind))
+        .collect();
+
+    for w in &filtered {
+        reporter.warning(w);
+    }
+
+    if warn_config.treat_as_error && !filtered.is_empty() {
+        return Err(format!("{} warning(s) treated as errors", filtered.len()));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum InputFormat {
     Source,
@@ -0,0 +1,239 @@
+// `aelys test`: finds `@test` functions under the given paths and runs each
+// one in a VM of its own, so state left behind by one test can't leak into
+// the next. Program output is captured and only shown for failures, and
+// with --coverage each VM's coverage is merged into one lcov file.
+
+use crate::cli::commands::coverage::write_lcov;
+use crate::cli::files::collect_aelys_files;
+use crate::cli::vm_config::parse_vm_args_or_error;
+use aelys_driver::{LoadedProgram, load_file};
+use aelys_frontend::lexer::Lexer;
+use aelys_frontend::parser::Parser;
+use aelys_opt::OptimizationLevel;
+use aelys_runtime::{CoverageReport, ObjectKind, VmConfig};
+use aelys_syntax::{Source, StmtKind};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Failure {
+    name: String,
+    file: String,
+    output: String,
+    message: String,
+}
+
+#[derive(Default)]
+struct Summary {
+    passed: usize,
+    filtered_out: usize,
+    failures: Vec<Failure>,
+    // every test's VM reports on its own, merged here
+    coverage: CoverageReport,
+}
+
+/// Runs the tests; with `coverage` set, also writes an lcov file there.
+pub fn run_with_options(
+    paths: &[String],
+    filters: &[String],
+    vm_args: Vec<String>,
+    opt_level: OptimizationLevel,
+    coverage: Option<&str>,
+) -> Result<i32, String> {
+    let mut config = parse_vm_args_or_error(&vm_args)?.config;
+    config.coverage = coverage.is_some();
+    let summary = run_tests(paths, filters, &config, opt_level, io::stdout())?;
+    if let Some(output) = coverage {
+        write_lcov(&summary.coverage, output)?;
+    }
+    Ok(exit_code(&summary))
+}
+
+#[allow(dead_code)]
+pub fn run_tests_with_io<W: Write>(
+    paths: &[String],
+    filters: &[String],
+    vm_args: Vec<String>,
+    opt_level: OptimizationLevel,
+    output: W,
+) -> Result<i32, String> {
+    let config = parse_vm_args_or_error(&vm_args)?.config;
+    let summary = run_tests(paths, filters, &config, opt_level, output)?;
+    Ok(exit_code(&summary))
+}
+
+fn run_tests<W: Write>(
+    paths: &[String],
+    filters: &[String],
+    config: &VmConfig,
+    opt_level: OptimizationLevel,
+    mut output: W,
+) -> Result<Summary, String> {
+    let files = collect_aelys_files(paths)?;
+    let mut summary = Summary::default();
+
+    for file in &files {
+        run_file(file, filters, config, opt_level, &mut summary, &mut output)
+            .map_err(|err| err.to_string())?;
+    }
+
+    report(&summary, &mut output).map_err(|err| err.to_string())?;
+    Ok(summary)
+}
+
+fn exit_code(summary: &Summary) -> i32 {
+    if summary.failures.is_empty() { 0 } else { 1 }
+}
+
+fn run_file<W: Write>(
+    file: &Path,
+    filters: &[String],
+    config: &VmConfig,
+    opt_level: OptimizationLevel,
+    summary: &mut Summary,
+    output: &mut W,
+) -> io::Result<()> {
+    let display = file.display().to_string();
+    let names = match discover(file) {
+        Ok(names) => names,
+        Err(message) => {
+            writeln!(output, "error: {} does not compile", display)?;
+            summary.failures.push(Failure {
+                name: "<compile>".to_string(),
+                file: display,
+                output: String::new(),
+                message,
+            });
+            return Ok(());
+        }
+    };
+
+    let (selected, skipped): (Vec<String>, Vec<String>) = names
+        .into_iter()
+        .partition(|name| filters.is_empty() || filters.iter().any(|f| name.contains(f)));
+    summary.filtered_out += skipped.len();
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    let plural = if selected.len() == 1 { "" } else { "s" };
+    writeln!(
+        output,
+        "\nrunning {} test{} from {}",
+        selected.len(),
+        plural,
+        display
+    )?;
+    for name in selected {
+        write!(output, "test {} ... ", name)?;
+        output.flush()?;
+        match run_test(file, &name, config, opt_level, &mut summary.coverage) {
+            Ok(()) => {
+                writeln!(output, "ok")?;
+                summary.passed += 1;
+            }
+            Err((message, captured)) => {
+                writeln!(output, "FAILED")?;
+                summary.failures.push(Failure {
+                    name,
+                    file: display.clone(),
+                    output: captured,
+                    message,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+// names of the top-level @test functions, in source order; modules are only
+// parsed here, they get compiled for real once per test
+fn discover(file: &Path) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|err| format!("failed to read {}: {}", file.display(), err))?;
+    let source = Source::new(file.display().to_string(), &content);
+    let tokens = Lexer::with_source(Arc::clone(&source))
+        .scan()
+        .map_err(|err| err.to_string())?;
+    let stmts = Parser::new(tokens, source)
+        .parse()
+        .map_err(|err| err.to_string())?;
+
+    Ok(stmts
+        .iter()
+        .filter_map(|stmt| match &stmt.kind {
+            StmtKind::Function(func) if func.is_test() => Some(func.name.clone()),
+            _ => None,
+        })
+        .collect())
+}
+
+// compiles the file into a fresh VM, runs its top level, then calls the test;
+// on failure hands back the error and whatever the program printed
+fn run_test(
+    file: &Path,
+    name: &str,
+    config: &VmConfig,
+    opt_level: OptimizationLevel,
+    coverage: &mut CoverageReport,
+) -> Result<(), (String, String)> {
+    let LoadedProgram { mut vm, main, .. } = load_file(file, config.clone(), Vec::new(), opt_level)
+        .map_err(|err| (err.to_string(), String::new()))?;
+
+    let captured = Rc::new(RefCell::new(String::new()));
+    let sink = Rc::clone(&captured);
+    vm.set_stdout_sink(Some(Box::new(move |text| sink.borrow_mut().push_str(text))));
+
+    // top-level functions sit in indexed globals; naming them lets us look the
+    // test up, the same way the REPL does after each input
+    let global_names = match vm.heap().get(main).map(|obj| &obj.kind) {
+        Some(ObjectKind::Function(func)) => func.function.global_layout.names().to_vec(),
+        _ => Vec::new(),
+    };
+    let result = vm.execute(main).and_then(|_| {
+        vm.sync_globals_to_hashmap(&global_names);
+        vm.call_function_by_name(name, &[])
+    });
+    vm.set_stdout_sink(None);
+    if let Some(report) = vm.take_coverage() {
+        coverage.merge(report);
+    }
+    result
+        .map(|_| ())
+        .map_err(|err| (err.to_string(), captured.take()))
+}
+
+fn report<W: Write>(summary: &Summary, output: &mut W) -> io::Result<()> {
+    if !summary.failures.is_empty() {
+        writeln!(output, "\nfailures:")?;
+        for failure in &summary.failures {
+            writeln!(output, "\n---- {} ({}) ----", failure.name, failure.file)?;
+            if !failure.output.is_empty() {
+                writeln!(output, "output:")?;
+                for line in failure.output.lines() {
+                    writeln!(output, "    {}", line)?;
+                }
+            }
+            write!(output, "{}", failure.message)?;
+            if !failure.message.ends_with('\n') {
+                writeln!(output)?;
+            }
+        }
+    }
+
+    let status = if summary.failures.is_empty() {
+        "ok"
+    } else {
+        "FAILED"
+    };
+    writeln!(
+        output,
+        "\ntest result: {}. {} passed; {} failed; {} filtered out",
+        status,
+        summary.passed,
+        summary.failures.len(),
+        summary.filtered_out
+    )
+}
@@ -0,0 +1,207 @@
+// Where compile, run and lint send their errors and warnings: the usual text,
+// or with --message-format one JSON object per line, or a single SARIF log
+// once the command is done. Everything goes to stderr like the text does, so
+// a script's own stdout stays clean.
+
+use aelys_common::{AelysError, Diagnostic, Severity, Warning};
+use aelys_syntax::Span;
+use serde_json::{Value, json};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif,
+}
+
+impl MessageFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "human" => Some(Self::Human),
+            "json" => Some(Self::Json),
+            "sarif" => Some(Self::Sarif),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Reporter {
+    format: MessageFormat,
+    // kept for SARIF, which is written in one go
+    collected: Vec<Diagnostic>,
+    reported_error: bool,
+}
+
+impl Reporter {
+    pub fn new(format: MessageFormat) -> Self {
+        Self {
+            format,
+            ..Self::default()
+        }
+    }
+
+    pub fn warning(&mut self, warning: &Warning) {
+        match self.format {
+            MessageFormat::Human => eprintln!("{}", warning),
+            _ => self.push(Diagnostic::from(warning)),
+        }
+    }
+
+    /// An error the command reports and carries on after.
+    pub fn error(&mut self, err: &AelysError) {
+        match self.format {
+            MessageFormat::Human => eprintln!("{}", err),
+            _ => self.push(Diagnostic::from(err)),
+        }
+    }
+
+    /// An error the command stops at: the text comes back for its `Err`,
+    /// which `finish` leaves out of machine output once this recorded it.
+    pub fn fail(&mut self, err: &AelysError) -> String {
+        if self.format != MessageFormat::Human {
+            self.push(Diagnostic::from(err));
+        }
+        err.to_string()
+    }
+
+    /// Progress and summary lines, which only the text output has.
+    pub fn status(&self, line: &str) {
+        if self.format == MessageFormat::Human {
+            eprintln!("{}", line);
+        }
+    }
+
+    /// Writes what's left and turns a failed command into exit code 1, its
+    /// message becoming a diagnostic unless one was already reported.
+    pub fn finish(mut self, result: Result<i32, String>) -> Result<i32, String> {
+        if self.format == MessageFormat::Human {
+            return result;
+        }
+        let code = match result {
+            Ok(code) => code,
+            Err(message) => {
+                if !self.reported_error {
+                    self.push(plain_error(message));
+                }
+                1
+            }
+        };
+        if self.format == MessageFormat::Sarif {
+            eprintln!("{}", sarif(&self.collected));
+        }
+        Ok(code)
+    }
+
+    fn push(&mut self, diagnostic: Diagnostic) {
+        self.reported_error |= diagnostic.severity == Severity::Error;
+        match self.format {
+            MessageFormat::Json => eprintln!("{}", to_json(&diagnostic)),
+            _ => self.collected.push(diagnostic),
+        }
+    }
+}
+
+// failures that aren't about a place in the source (missing file, -Werror)
+fn plain_error(message: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Error,
+        code: None,
+        message,
+        file: None,
+        span: Span::dummy(),
+        end_line: 0,
+        end_column: 0,
+        label: None,
+        notes: Vec::new(),
+        hint: None,
+    }
+}
+
+fn has_location(d: &Diagnostic) -> bool {
+    d.span.line > 0
+}
+
+fn to_json(d: &Diagnostic) -> Value {
+    let spans: Vec<Value> = has_location(d)
+        .then(|| {
+            json!({
+                "file": d.file,
+                "start": d.span.start,
+                "end": d.span.end,
+                "line": d.span.line,
+                "column": d.span.column,
+                "end_line": d.end_line,
+                "end_column": d.end_column,
+                "label": d.label,
+            })
+        })
+        .into_iter()
+        .collect();
+    json!({
+        "severity": d.severity.as_str(),
+        "code": d.code,
+        "message": d.message,
+        "spans": spans,
+        "notes": d.notes,
+        "hint": d.hint,
+    })
+}
+
+fn sarif(diagnostics: &[Diagnostic]) -> Value {
+    let mut rules: Vec<&str> = diagnostics
+        .iter()
+        .filter_map(|d| d.code.as_deref())
+        .collect();
+    rules.sort_unstable();
+    rules.dedup();
+    let rules: Vec<Value> = rules.into_iter().map(|id| json!({ "id": id })).collect();
+
+    let results: Vec<Value> = diagnostics.iter().map(sarif_result).collect();
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "aelys",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn sarif_result(d: &Diagnostic) -> Value {
+    let mut result = json!({
+        "level": d.severity.as_str(),
+        "message": { "text": d.message },
+    });
+    if let Some(code) = &d.code {
+        result["ruleId"] = json!(code);
+    }
+    if has_location(d) {
+        let mut location = json!({
+            "region": {
+                "startLine": d.span.line,
+                "startColumn": d.span.column,
+                "endLine": d.end_line,
+                "endColumn": d.end_column,
+            }
+        });
+        if let Some(file) = &d.file {
+            location["artifactLocation"] = json!({ "uri": file.replace('\\', "/") });
+        }
+        result["locations"] = json!([{ "physicalLocation": location }]);
+    }
+    // SARIF has no slot for these, its property bag is meant for this
+    if !d.notes.is_empty() || d.hint.is_some() {
+        result["properties"] = json!({ "notes": d.notes, "hint": d.hint });
+    }
+    result
+}
@@ -0,0 +1,42 @@
+// turning command line paths into the .aelys files behind them (fmt, lint, test)
+
+use std::path::{Path, PathBuf};
+
+/// Files are taken as given; directories are searched recursively for
+/// `.aelys` files, skipping hidden entries. The result is sorted per path.
+pub fn collect_aelys_files(paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect(Path::new(path), &mut files)?;
+    }
+    Ok(files)
+}
+
+fn collect(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        if !path.exists() {
+            return Err(format!("no such file or directory: {}", path.display()));
+        }
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let entries = std::fs::read_dir(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    let mut entries: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if entry.is_dir() {
+            collect(&entry, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == "aelys") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
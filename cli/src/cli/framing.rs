@@ -0,0 +1,57 @@
+// `Content-Length: N\r\n\r\n` followed by N bytes of JSON, shared by the
+// debug adapter and the language server
+
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+pub struct MessageReader<R> {
+    input: R,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    pub fn new(input: R) -> Self {
+        Self { input }
+    }
+
+    /// Next message, or `None` once the client closed the stream.
+    pub fn read_message(&mut self) -> io::Result<Option<Value>> {
+        let mut length = None;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                if length.is_some() {
+                    break;
+                }
+                continue;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                let n = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| invalid(format!("bad Content-Length: {}", value.trim())))?;
+                length = Some(n);
+            }
+        }
+
+        let mut body = vec![0u8; length.unwrap_or(0)];
+        self.input.read_exact(&mut body)?;
+        serde_json::from_slice(&body)
+            .map(Some)
+            .map_err(|err| invalid(err.to_string()))
+    }
+}
+
+pub fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -1,14 +1,26 @@
 pub mod args;
+pub mod diagnostics;
+pub mod files;
+pub mod framing;
 pub mod vm_config;
 
 pub mod commands {
     pub mod asm;
     pub mod compile;
+    pub mod coverage;
+    pub mod dap;
+    pub mod debug;
+    pub mod fmt;
+    pub mod lint;
+    pub mod lsp;
+    pub mod profile;
     pub mod repl;
     pub mod run;
+    pub mod test;
 }
 
 use aelys_common::WarningConfig;
+use diagnostics::Reporter;
 
 pub fn run() -> i32 {
     let args: Vec<String> = std::env::args().collect();
@@ -51,32 +63,83 @@ fn parse_warning_config(flags: &[String]) -> Result<WarningConfig, String> {
 }
 
 fn dispatch(parsed: args::ParsedArgs) -> Result<i32, String> {
+    let mut reporter = Reporter::new(parsed.message_format);
+    let result = run_command(parsed, &mut reporter);
+    reporter.finish(result)
+}
+
+fn run_command(parsed: args::ParsedArgs, reporter: &mut Reporter) -> Result<i32, String> {
     let warn_config = parse_warning_config(&parsed.warning_flags)?;
 
     match parsed.command {
         args::Command::Help => Ok(0),
         args::Command::Version => Ok(0),
 
-        args::Command::Run { path, program_args } => commands::run::run_with_options(
-            &path,
+        args::Command::Run {
+            path,
             program_args,
-            parsed.vm_args,
-            parsed.opt_level,
-            warn_config,
-        ),
+            profile,
+            coverage,
+        } => {
+            if let Some(output) = coverage {
+                commands::coverage::run_with_options(
+                    &path,
+                    program_args,
+                    parsed.vm_args,
+                    parsed.opt_level,
+                    warn_config,
+                    &output,
+                    reporter,
+                )
+            } else if profile {
+                commands::profile::run_with_options(
+                    &path,
+                    program_args,
+                    parsed.vm_args,
+                    parsed.opt_level,
+                    warn_config,
+                    reporter,
+                )
+            } else {
+                commands::run::run_with_options(
+                    &path,
+                    program_args,
+                    parsed.vm_args,
+                    parsed.opt_level,
+                    warn_config,
+                    reporter,
+                )
+            }
+        }
 
         args::Command::Compile {
             path,
             output,
             emit_air,
+            emit_air_passes,
+            emit_c,
+            verify_air,
         } => {
             if !parsed.vm_args.is_empty() {
                 return Err("vm flags are only supported for run or repl".to_string());
             }
-            if emit_air {
-                commands::compile::emit_air(&path, parsed.opt_level)
+            if emit_air_passes {
+                commands::compile::emit_air_passes(&path, parsed.opt_level, verify_air)
+            } else if emit_air {
+                commands::compile::emit_air(&path, parsed.opt_level, verify_air)
+            } else if emit_c {
+                commands::compile::emit_c(&path, output, parsed.opt_level, verify_air)
             } else {
-                commands::compile::run_with_options(&path, output, parsed.opt_level, warn_config)
+                if verify_air {
+                    commands::compile::verify_air(&path, parsed.opt_level)?;
+                }
+                commands::compile::run_with_options(
+                    &path,
+                    output,
+                    parsed.opt_level,
+                    warn_config,
+                    reporter,
+                )
             }
         }
 
@@ -88,6 +151,45 @@ fn dispatch(parsed: args::ParsedArgs) -> Result<i32, String> {
             commands::asm::run_with_options(&path, output, stdout, parsed.opt_level, parsed.vm_args)
         }
 
+        args::Command::Debug { path, program_args } => {
+            commands::debug::run_with_options(&path, program_args, parsed.vm_args, warn_config)
+        }
+
+        args::Command::Dap => commands::dap::run_with_options(parsed.vm_args),
+
+        args::Command::Lsp => {
+            if !parsed.vm_args.is_empty() {
+                return Err("vm flags are only supported for run or repl".to_string());
+            }
+            commands::lsp::run_with_options(warn_config)
+        }
+
+        args::Command::Fmt { paths, check } => {
+            if !parsed.vm_args.is_empty() {
+                return Err("vm flags are only supported for run or repl".to_string());
+            }
+            commands::fmt::run_with_options(&paths, check)
+        }
+
+        args::Command::Lint { paths } => {
+            if !parsed.vm_args.is_empty() {
+                return Err("vm flags are only supported for run or repl".to_string());
+            }
+            commands::lint::run_with_options(&paths, warn_config, reporter)
+        }
+
+        args::Command::Test {
+            path,
+            filters,
+            coverage,
+        } => commands::test::run_with_options(
+            std::slice::from_ref(&path),
+            &filters,
+            parsed.vm_args,
+            parsed.opt_level,
+            coverage.as_deref(),
+        ),
+
         args::Command::Repl => {
             let repl_opt = aelys_opt::OptimizationLevel::Basic;
             commands::repl::run_with_options(repl_opt, parsed.vm_args)
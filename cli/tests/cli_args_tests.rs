@@ -1,6 +1,6 @@
 use aelys_opt::OptimizationLevel;
 
-use aelys_cli::cli::args::{Command, ParsedArgs, parse_args};
+use aelys_cli::cli::args::{Command, MessageFormat, ParsedArgs, parse_args};
 
 #[test]
 fn parse_run_with_flags_anywhere() {
@@ -25,10 +25,13 @@ fn parse_run_with_flags_anywhere() {
             command: Command::Run {
                 path: "main.aelys".to_string(),
                 program_args: vec!["arg1".to_string(), "-x".to_string()],
+                profile: false,
+                coverage: None,
             },
             vm_args: vec!["-ae.trusted=true".to_string()],
             opt_level: OptimizationLevel::Aggressive,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     );
 }
@@ -48,10 +51,13 @@ fn parse_implicit_run_path_first() {
             command: Command::Run {
                 path: "main.aelys".to_string(),
                 program_args: Vec::new(),
+                profile: false,
+                coverage: None,
             },
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Basic,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     );
 }
@@ -72,6 +78,7 @@ fn parse_repl_with_vm_flags() {
             vm_args: vec!["-ae.max-heap=1M".to_string()],
             opt_level: OptimizationLevel::Standard,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     );
 }
@@ -103,10 +110,14 @@ fn parse_compile_output_flag() {
                 path: "main.aelys".to_string(),
                 output: Some("out.avbc".to_string()),
                 emit_air: false,
+                emit_air_passes: false,
+                emit_c: false,
+                verify_air: false,
             },
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Standard,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     );
 }
@@ -131,6 +142,7 @@ fn parse_asm_stdout_flag() {
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Standard,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     );
 }
@@ -151,6 +163,7 @@ fn parse_version_command() {
             vm_args: Vec::new(),
             opt_level: OptimizationLevel::Standard,
             warning_flags: Vec::new(),
+            message_format: MessageFormat::Human,
         }
     );
 }
@@ -255,6 +268,412 @@ fn parse_powershell_split_ae_dot_before_path() {
         Command::Run {
             path: "main.aelys".to_string(),
             program_args: Vec::new(),
+            profile: false,
+            coverage: None,
         }
     );
 }
+
+#[test]
+fn parse_compile_emit_c() {
+    let args = vec!["aelys", "compile", "--emit-c", "main.aelys", "-o", "out.c"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Compile {
+            path: "main.aelys".to_string(),
+            output: Some("out.c".to_string()),
+            emit_air: false,
+            emit_air_passes: false,
+            emit_c: true,
+            verify_air: false,
+        }
+    );
+}
+
+#[test]
+fn parse_emit_c_rejected_outside_compile() {
+    let args = vec!["aelys", "run", "--emit-c", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("--emit-c is only supported for compile"));
+}
+
+#[test]
+fn parse_compile_emit_air_passes() {
+    let args = vec!["aelys", "compile", "--emit-air=passes", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Compile {
+            path: "main.aelys".to_string(),
+            output: None,
+            emit_air: true,
+            emit_air_passes: true,
+            emit_c: false,
+            verify_air: false,
+        }
+    );
+}
+
+#[test]
+fn parse_unknown_emit_air_mode_errors() {
+    let args = vec!["aelys", "compile", "--emit-air=ssa", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("unknown --emit-air mode: ssa"));
+}
+
+#[test]
+fn parse_compile_verify_air() {
+    let args = vec!["aelys", "compile", "--verify-air", "--emit-c", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Compile {
+            path: "main.aelys".to_string(),
+            output: None,
+            emit_air: false,
+            emit_air_passes: false,
+            emit_c: true,
+            verify_air: true,
+        }
+    );
+}
+
+#[test]
+fn parse_verify_air_rejected_outside_compile() {
+    let args = vec!["aelys", "asm", "--verify-air", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("--verify-air is only supported for compile"));
+}
+
+#[test]
+fn parse_debug_with_program_args() {
+    let args = vec!["aelys", "debug", "main.aelys", "arg1", "-x"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Debug {
+            path: "main.aelys".to_string(),
+            program_args: vec!["arg1".to_string(), "-x".to_string()],
+        }
+    );
+}
+
+#[test]
+fn parse_debug_requires_path() {
+    let args = vec!["aelys", "debug"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("missing file for debug"));
+}
+
+#[test]
+fn parse_dap_rejects_path() {
+    let args = vec!["aelys", "dap", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("unexpected argument for dap"));
+}
+
+#[test]
+fn parse_lsp_rejects_path() {
+    let args = vec!["aelys", "lsp", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("unexpected argument for lsp"));
+}
+
+#[test]
+fn parse_fmt_check_with_paths() {
+    let args = vec!["aelys", "fmt", "--check", "src", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Fmt {
+            paths: vec!["src".to_string(), "main.aelys".to_string()],
+            check: true,
+        }
+    );
+}
+
+#[test]
+fn parse_lint_with_paths() {
+    let args = vec!["aelys", "lint", "-Werror", "src", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Lint {
+            paths: vec!["src".to_string(), "main.aelys".to_string()],
+        }
+    );
+    assert_eq!(parsed.warning_flags, vec!["error".to_string()]);
+}
+
+#[test]
+fn parse_lint_defaults_to_current_dir() {
+    let args = vec!["aelys", "lint"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Lint {
+            paths: vec![".".to_string()],
+        }
+    );
+}
+
+#[test]
+fn parse_fmt_defaults_to_current_dir() {
+    let args = vec!["aelys", "fmt"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Fmt {
+            paths: vec![".".to_string()],
+            check: false,
+        }
+    );
+}
+
+#[test]
+fn parse_check_after_run_path_is_program_arg() {
+    let args = vec!["aelys", "run", "main.aelys", "--check"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Run {
+            path: "main.aelys".to_string(),
+            program_args: vec!["--check".to_string()],
+            profile: false,
+            coverage: None,
+        }
+    );
+}
+
+#[test]
+fn parse_test_path_and_filters() {
+    let args = vec!["aelys", "test", "-O0", "tests", "parse_", "lexer"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Test {
+            path: "tests".to_string(),
+            filters: vec!["parse_".to_string(), "lexer".to_string()],
+            coverage: None,
+        }
+    );
+    assert_eq!(parsed.opt_level, OptimizationLevel::None);
+}
+
+#[test]
+fn parse_test_defaults_to_current_dir() {
+    let args = vec!["aelys", "test"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Test {
+            path: ".".to_string(),
+            filters: Vec::new(),
+            coverage: None,
+        }
+    );
+}
+
+#[test]
+fn parse_run_profile() {
+    let args = vec!["aelys", "run", "--profile", "main.aelys", "--profile"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let parsed = parse_args(&args).unwrap();
+
+    assert_eq!(
+        parsed.command,
+        Command::Run {
+            path: "main.aelys".to_string(),
+            program_args: vec!["--profile".to_string()],
+            profile: true,
+            coverage: None,
+        }
+    );
+}
+
+#[test]
+fn parse_profile_rejected_outside_run() {
+    let args = vec!["aelys", "compile", "--profile", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+
+    let err = parse_args(&args).unwrap_err();
+    assert!(err.contains("--profile"));
+}
+
+#[test]
+fn parse_coverage_for_run_and_test() {
+    let args = vec!["aelys", "run", "--coverage", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    let parsed = parse_args(&args).unwrap();
+    assert_eq!(
+        parsed.command,
+        Command::Run {
+            path: "main.aelys".to_string(),
+            program_args: Vec::new(),
+            profile: false,
+            coverage: Some("lcov.info".to_string()),
+        }
+    );
+
+    let args = vec!["aelys", "test", "tests", "--coverage=out/lcov.info"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    let parsed = parse_args(&args).unwrap();
+    assert_eq!(
+        parsed.command,
+        Command::Test {
+            path: "tests".to_string(),
+            filters: Vec::new(),
+            coverage: Some("out/lcov.info".to_string()),
+        }
+    );
+}
+
+#[test]
+fn parse_coverage_rejects_other_commands_and_profile() {
+    for args in [
+        vec!["aelys", "compile", "--coverage", "main.aelys"],
+        vec!["aelys", "run", "--coverage", "--profile", "main.aelys"],
+    ] {
+        let args = args.into_iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(parse_args(&args).is_err());
+    }
+}
+
+#[test]
+fn parse_message_format() {
+    let args = vec!["aelys", "lint", "--message-format=sarif", "src"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        parse_args(&args).unwrap().message_format,
+        MessageFormat::Sarif
+    );
+
+    let args = vec!["aelys", "compile", "--message-format", "json", "main.aelys"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        parse_args(&args).unwrap().message_format,
+        MessageFormat::Json
+    );
+
+    // after the script it's the script's
+    let args = vec!["aelys", "run", "main.aelys", "--message-format=json"]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    let parsed = parse_args(&args).unwrap();
+    assert_eq!(parsed.message_format, MessageFormat::Human);
+    assert_eq!(
+        parsed.command,
+        Command::Run {
+            path: "main.aelys".to_string(),
+            program_args: vec!["--message-format=json".to_string()],
+            profile: false,
+            coverage: None,
+        }
+    );
+}
+
+#[test]
+fn parse_message_format_rejects_unknown_format_and_commands() {
+    for args in [
+        vec!["aelys", "lint", "--message-format=xml"],
+        vec!["aelys", "fmt", "--message-format=json"],
+        vec!["aelys", "test", "--message-format=json"],
+    ] {
+        let args = args.into_iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(parse_args(&args).is_err());
+    }
+}
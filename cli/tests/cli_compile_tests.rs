@@ -14,3 +14,43 @@ fn compile_writes_avbc() {
     assert!(output.exists());
     assert_eq!(output.extension().unwrap(), "avbc");
 }
+
+#[test]
+fn emit_c_writes_source_and_runtime_header() {
+    let dir = std::env::temp_dir().join("aelys_cli_emit_c_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let src_path = dir.join("main.aelys");
+    std::fs::write(&src_path, "fn main() {\n    println(1 + 2)\n}\n").unwrap();
+
+    let code = aelys_cli::cli::commands::compile::emit_c(
+        src_path.to_str().unwrap(),
+        None,
+        OptimizationLevel::None,
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(code, 0);
+    let c = std::fs::read_to_string(dir.join("main.c")).unwrap();
+    assert!(c.contains("#include \"aelys_rt.h\""));
+    assert!(c.contains("aelys_main"));
+    assert!(dir.join("aelys_rt.h").exists());
+}
+
+#[test]
+fn verify_air_accepts_a_valid_program() {
+    let dir = std::env::temp_dir().join("aelys_cli_verify_air_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let src_path = dir.join("main.aelys");
+    std::fs::write(
+        &src_path,
+        "fn sum_to(n: int) -> int {\n    let mut total = 0\n    for i in 0..n {\n        total += i\n    }\n    return total\n}\nprintln(sum_to(10))\n",
+    )
+    .unwrap();
+
+    for level in [OptimizationLevel::None, OptimizationLevel::Standard] {
+        aelys_cli::cli::commands::compile::verify_air(src_path.to_str().unwrap(), level).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+use aelys_cli::cli::commands::coverage::run_with_options;
+use aelys_cli::cli::commands::test::run_with_options as run_tests;
+use aelys_cli::cli::diagnostics::Reporter;
+use aelys_common::WarningConfig;
+use aelys_opt::OptimizationLevel;
+use std::path::PathBuf;
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn run_coverage_reports_script_and_modules() {
+    let dir = fresh_dir("aelys_cli_coverage_run");
+    std::fs::write(
+        dir.join("shapes.aelys"),
+        "pub fn area(w: int, h: int) -> int {\n    return w * h\n}\n\npub fn unused() -> int {\n    return 0\n}\n",
+    )
+    .unwrap();
+    let script = dir.join("main.aelys");
+    std::fs::write(&script, "needs shapes\n\nlet a = shapes.area(2, 3)\n").unwrap();
+    let output = dir.join("lcov.info");
+
+    let code = run_with_options(
+        script.to_str().unwrap(),
+        Vec::new(),
+        Vec::new(),
+        OptimizationLevel::None,
+        WarningConfig::new(),
+        output.to_str().unwrap(),
+        &mut Reporter::default(),
+    )
+    .unwrap();
+    assert_eq!(code, 0);
+
+    let lcov = std::fs::read_to_string(&output).unwrap();
+    assert!(lcov.contains("main.aelys\n"), "{}", lcov);
+    assert!(lcov.contains("shapes.aelys\n"), "{}", lcov);
+    assert!(lcov.contains("FNDA:1,area\n"), "{}", lcov);
+    assert!(lcov.contains("FNDA:0,unused\n"), "{}", lcov);
+    assert_eq!(lcov.matches("end_of_record").count(), 2);
+}
+
+#[test]
+fn test_coverage_merges_every_test() {
+    let dir = fresh_dir("aelys_cli_coverage_test");
+    std::fs::write(
+        dir.join("math_test.aelys"),
+        "fn double(x: int) -> int {\n    return x * 2\n}\n\n@test\nfn one() {\n    assert_eq(double(1), 2)\n}\n\n@test\nfn two() {\n    assert_eq(double(2), 4)\n}\n",
+    )
+    .unwrap();
+    let output = dir.join("lcov.info");
+
+    let code = run_tests(
+        &[dir.display().to_string()],
+        &[],
+        Vec::new(),
+        OptimizationLevel::None,
+        Some(output.to_str().unwrap()),
+    )
+    .unwrap();
+    assert_eq!(code, 0);
+
+    let lcov = std::fs::read_to_string(&output).unwrap();
+    assert!(lcov.contains("FNDA:2,double\n"), "{}", lcov);
+    assert!(lcov.contains("FNDA:1,one\n"), "{}", lcov);
+    assert!(lcov.contains("DA:2,2\n"), "{}", lcov);
+}
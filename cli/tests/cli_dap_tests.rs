@@ -0,0 +1,253 @@
+use aelys_cli::cli::commands::dap::run_dap_with_io;
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+const PROGRAM: &str = "fn add(a: int, b: int) -> int {
+    let sum = a + b
+    return sum
+}
+let x = 10
+let y = add(x, 5)
+println(y)
+";
+
+fn write_program(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_dap_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.aelys");
+    std::fs::write(&path, PROGRAM).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+// Plays a fixed list of requests and returns every message the adapter sent.
+fn session(requests: Vec<(&str, Value)>) -> Vec<Value> {
+    let mut input = Vec::new();
+    for (i, (command, arguments)) in requests.into_iter().enumerate() {
+        let body = json!({
+            "seq": i + 1,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    }
+
+    let output = SharedOutput::default();
+    run_dap_with_io(std::io::Cursor::new(input), output.clone(), Vec::new()).unwrap();
+
+    let raw = String::from_utf8(output.0.take()).unwrap();
+    raw.split("Content-Length: ")
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| {
+            let (_, body) = chunk.split_once("\r\n\r\n").unwrap();
+            serde_json::from_str(body).unwrap()
+        })
+        .collect()
+}
+
+fn response<'a>(messages: &'a [Value], command: &str) -> &'a Value {
+    messages
+        .iter()
+        .find(|m| m["type"] == "response" && m["command"] == command)
+        .unwrap_or_else(|| panic!("no response to {command}"))
+}
+
+fn events<'a>(messages: &'a [Value], event: &str) -> Vec<&'a Value> {
+    messages.iter().filter(|m| m["event"] == event).collect()
+}
+
+#[test]
+fn dap_breakpoint_inspection_and_output() {
+    let program = write_program("inspect");
+    let messages = session(vec![
+        ("initialize", json!({ "adapterID": "aelys" })),
+        ("launch", json!({ "program": program })),
+        (
+            "setBreakpoints",
+            json!({ "source": { "path": program }, "breakpoints": [{ "line": 3 }, { "line": 4 }] }),
+        ),
+        ("configurationDone", json!({})),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("scopes", json!({ "frameId": 0 })),
+        ("variables", json!({ "variablesReference": 100 })),
+        ("variables", json!({ "variablesReference": 1 })),
+        ("evaluate", json!({ "expression": "x", "frameId": 0 })),
+        ("evaluate", json!({ "expression": "nope", "frameId": 0 })),
+        ("continue", json!({ "threadId": 1 })),
+        ("disconnect", json!({})),
+    ]);
+
+    assert_eq!(events(&messages, "initialized").len(), 1);
+    let bps = &response(&messages, "setBreakpoints")["body"]["breakpoints"];
+    assert_eq!(bps[0], json!({ "verified": true, "line": 3 }));
+    assert_eq!(bps[1], json!({ "verified": false, "line": 4 }));
+
+    let stopped = events(&messages, "stopped");
+    assert_eq!(stopped.len(), 1);
+    assert_eq!(stopped[0]["body"]["reason"], "breakpoint");
+
+    let frames = &response(&messages, "stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "add");
+    assert_eq!(frames[0]["line"], 3);
+    assert_eq!(frames[1]["name"], "<script>");
+    assert_eq!(frames[1]["line"], 6);
+
+    let variables: Vec<_> = messages
+        .iter()
+        .filter(|m| m["command"] == "variables")
+        .map(|m| m["body"]["variables"].clone())
+        .collect();
+    assert_eq!(variables[0][0]["name"], "sum");
+    assert_eq!(variables[0][0]["value"], "15");
+    assert!(
+        variables[1]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|v| v["name"] == "x" && v["value"] == "10")
+    );
+
+    let evals: Vec<_> = messages
+        .iter()
+        .filter(|m| m["command"] == "evaluate")
+        .collect();
+    assert_eq!(evals[0]["body"]["result"], "10");
+    assert_eq!(evals[1]["success"], false);
+
+    let output = events(&messages, "output");
+    assert_eq!(output[0]["body"]["output"], "15\n");
+    assert_eq!(events(&messages, "exited")[0]["body"]["exitCode"], 0);
+    assert_eq!(events(&messages, "terminated").len(), 1);
+    assert_eq!(response(&messages, "disconnect")["success"], true);
+}
+
+#[test]
+fn dap_stepping_requests_map_to_the_debugger() {
+    let program = write_program("step");
+    let messages = session(vec![
+        ("initialize", json!({})),
+        ("launch", json!({ "program": program, "stopOnEntry": true })),
+        ("configurationDone", json!({})),
+        ("next", json!({ "threadId": 1 })),
+        ("next", json!({ "threadId": 1 })),
+        ("stepIn", json!({ "threadId": 1 })),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("stepOut", json!({ "threadId": 1 })),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("disconnect", json!({})),
+    ]);
+
+    let reasons: Vec<_> = events(&messages, "stopped")
+        .iter()
+        .map(|e| e["body"]["reason"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(reasons, ["entry", "step", "step", "step", "step"]);
+
+    let traces: Vec<_> = messages
+        .iter()
+        .filter(|m| m["command"] == "stackTrace")
+        .map(|m| m["body"]["stackFrames"].clone())
+        .collect();
+    assert_eq!(traces[0][0]["name"], "add");
+    assert_eq!(traces[0][0]["line"], 2);
+    assert_eq!(traces[1][0]["name"], "<script>");
+    // disconnecting while paused stops the program before it prints
+    assert!(events(&messages, "output").is_empty());
+}
+
+#[test]
+fn dap_launch_of_missing_file_fails() {
+    let messages = session(vec![
+        ("initialize", json!({})),
+        ("launch", json!({ "program": "/nonexistent/main.aelys" })),
+        ("disconnect", json!({})),
+    ]);
+    assert_eq!(response(&messages, "launch")["success"], false);
+    assert_eq!(response(&messages, "disconnect")["success"], true);
+}
+
+#[test]
+fn dap_breakpoints_and_frames_are_per_file() {
+    let dir = std::env::temp_dir().join("aelys_cli_dap_modules");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let main = dir.join("main.aelys");
+    let util = dir.join("util.aelys");
+    std::fs::write(
+        &main,
+        "needs util\nlet a = util.helper(1)\nlet b = a * 2\nprintln(b)\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &util,
+        "pub fn helper(x) {\n    let y = x + 1\n    return y\n}\n",
+    )
+    .unwrap();
+    let main = main.canonicalize().unwrap().display().to_string();
+    let util = util.canonicalize().unwrap().display().to_string();
+
+    let messages = session(vec![
+        ("initialize", json!({})),
+        ("launch", json!({ "program": main })),
+        (
+            "setBreakpoints",
+            json!({ "source": { "path": util }, "breakpoints": [{ "line": 2 }] }),
+        ),
+        // a request for main.aelys leaves util.aelys's breakpoint alone
+        (
+            "setBreakpoints",
+            json!({ "source": { "path": main }, "breakpoints": [{ "line": 3 }] }),
+        ),
+        (
+            "setBreakpoints",
+            json!({ "source": { "path": dir.join("other.aelys") }, "breakpoints": [{ "line": 1 }] }),
+        ),
+        ("configurationDone", json!({})),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("continue", json!({ "threadId": 1 })),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("continue", json!({ "threadId": 1 })),
+        ("disconnect", json!({})),
+    ]);
+
+    let replies: Vec<_> = messages
+        .iter()
+        .filter(|m| m["command"] == "setBreakpoints")
+        .map(|m| m["body"]["breakpoints"][0]["verified"].clone())
+        .collect();
+    assert_eq!(replies, [true, true, false]);
+
+    let traces: Vec<_> = messages
+        .iter()
+        .filter(|m| m["command"] == "stackTrace")
+        .map(|m| m["body"]["stackFrames"].clone())
+        .collect();
+    assert_eq!(traces.len(), 2);
+    assert_eq!(traces[0][0]["name"], "helper");
+    assert_eq!(traces[0][0]["line"], 2);
+    assert_eq!(traces[0][0]["source"]["path"], util.as_str());
+    assert_eq!(traces[0][0]["source"]["name"], "util.aelys");
+    assert_eq!(traces[0][1]["source"]["path"], main.as_str());
+    assert_eq!(traces[1][0]["name"], "<script>");
+    assert_eq!(traces[1][0]["line"], 3);
+    assert_eq!(traces[1][0]["source"]["path"], main.as_str());
+    assert_eq!(events(&messages, "output")[0]["body"]["output"], "4\n");
+}
@@ -0,0 +1,124 @@
+use aelys_cli::cli::commands::debug::run_debug_with_io;
+use aelys_common::WarningConfig;
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn debug_session(name: &str, program: &str, commands: &str) -> String {
+    debug_files(name, &[("main.aelys", program)], commands)
+}
+
+// the first file is the one that runs
+fn debug_files(name: &str, files: &[(&str, &str)], commands: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_debug_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, text) in files {
+        std::fs::write(dir.join(file), text).unwrap();
+    }
+    let path = dir.join(files[0].0);
+
+    let output = SharedOutput::default();
+    run_debug_with_io(
+        path.to_str().unwrap(),
+        Vec::new(),
+        Vec::new(),
+        WarningConfig::new(),
+        std::io::Cursor::new(commands.to_string()),
+        output.clone(),
+    )
+    .unwrap();
+    String::from_utf8(output.0.take()).unwrap()
+}
+
+const PROGRAM: &str = "fn add(a: int, b: int) -> int {
+    let sum = a + b
+    return sum
+}
+let x = 10
+let y = add(x, 5)
+";
+
+#[test]
+fn debug_breaks_and_prints_variables() {
+    let text = debug_session(
+        "break",
+        PROGRAM,
+        "break 3\ncontinue\nprint sum\nprint x\nbacktrace\ncontinue\n",
+    );
+
+    assert!(text.contains("stopped at line 1 in <script> (entry)"));
+    assert!(text.contains("stopped at line 3 in add (breakpoint)"));
+    assert!(text.contains("   3 |     return sum"));
+    assert!(text.contains("sum = 15"));
+    assert!(text.contains("x = 10"));
+    assert!(text.contains("#0 add at line 3\n#1 <script> at line 6"));
+}
+
+#[test]
+fn debug_reports_unknown_variables_and_commands() {
+    let text = debug_session("unknown", PROGRAM, "print nope\nfrobnicate\nquit\n");
+
+    assert!(text.contains("no variable named 'nope'"));
+    assert!(text.contains("unknown command 'frobnicate'"));
+}
+
+#[test]
+fn debug_quits_at_end_of_input() {
+    let text = debug_session("eof", PROGRAM, "");
+    assert_eq!(text.matches("stopped at").count(), 1);
+}
+
+#[test]
+fn debug_breakpoints_name_their_file() {
+    let text = debug_files(
+        "modules",
+        &[
+            (
+                "main.aelys",
+                "needs util\nlet a = util.helper(1)\nlet b = a * 2\n",
+            ),
+            (
+                "util.aelys",
+                "pub fn helper(x) {\n    let y = x + 1\n    return y\n}\n",
+            ),
+        ],
+        "break 3\nbreak util.aelys:2\nbreak nope.aelys:1\ncontinue\nbacktrace\ncontinue\n",
+    );
+
+    assert!(text.contains("breakpoint at line 3\n"), "{text}");
+    assert!(text.contains("breakpoint at util.aelys:2\n"), "{text}");
+    assert!(
+        text.contains("no loaded file matches 'nope.aelys'"),
+        "{text}"
+    );
+    assert!(
+        text.contains("stopped at util.aelys:2 in helper (breakpoint)"),
+        "{text}"
+    );
+    assert!(text.contains("   2 |     let y = x + 1"), "{text}");
+    assert!(
+        text.contains("#0 helper at util.aelys:2\n#1 <script> at line 2"),
+        "{text}"
+    );
+    assert!(
+        text.contains("stopped at line 3 in <script> (breakpoint)"),
+        "{text}"
+    );
+    // line 3 of util.aelys has code too, but no breakpoint
+    assert!(!text.contains("util.aelys:3"), "{text}");
+}
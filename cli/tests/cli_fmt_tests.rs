@@ -0,0 +1,169 @@
+use aelys_cli::cli::commands::fmt::run_with_options;
+use aelys_frontend::formatter::{FormatError, format_source};
+use aelys_syntax::Source;
+
+fn format(code: &str) -> String {
+    format_source(Source::new("test.aelys", code)).unwrap()
+}
+
+fn temp_file(name: &str, code: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_fmt_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.aelys");
+    std::fs::write(&path, code).unwrap();
+    path
+}
+
+#[test]
+fn fmt_normalizes_spacing_and_keeps_comments() {
+    let code = "// header\nneeds std.math as m\nlet x=1+2 // trailing\n/* block */\nfn add(a:int,b:int)->int{ return a+b }\n";
+
+    assert_eq!(
+        format(code),
+        "// header\nneeds std.math as m\n\nlet x = 1 + 2 // trailing\n\n/* block */\nfn add(a: int, b: int) -> int {\n    return a + b\n}\n"
+    );
+}
+
+#[test]
+fn fmt_keeps_trailing_comment_after_its_parameter() {
+    let code = "fn add(a: int, // first\n       b: int) -> int {\n    return a + b\n}\n";
+    let expected = "fn add(\n    a: int, // first\n    b: int\n) -> int {\n    return a + b\n}\n";
+
+    assert_eq!(format(code), expected);
+    assert_eq!(format(expected), expected);
+}
+
+#[test]
+fn fmt_keeps_inline_comment_before_an_expression() {
+    let code = "fn f() {\n    let x = /* the answer */ 42\n    return /* why */ x\n}\n";
+
+    assert_eq!(format(code), code);
+}
+
+#[test]
+fn fmt_keeps_comments_between_call_arguments() {
+    let code = "let y = add(1, // one\n    2)\nlet z = add(1,   /* one */ 2)\nlet v = add(\n    // first\n    1,\n    2 // last\n)\n";
+    let expected = "let y = add(\n    1, // one\n    2\n)\nlet z = add(1, /* one */ 2)\nlet v = add(\n    // first\n    1,\n    2 // last\n)\n";
+
+    assert_eq!(format(code), expected);
+    assert_eq!(format(expected), expected);
+}
+
+#[test]
+fn fmt_keeps_decorators_on_their_own_lines() {
+    let code = "let a = 1\n@inline\n@no_gc\npub fn f( ) { }\n";
+
+    assert_eq!(
+        format(code),
+        "let a = 1\n\n@inline\n@no_gc\npub fn f() {}\n"
+    );
+}
+
+#[test]
+fn fmt_keeps_decorator_arguments() {
+    let code = "@allow( float_equality,unused_pub )\nfn f( ) { }\n";
+
+    assert_eq!(
+        format(code),
+        "@allow(float_equality, unused_pub)\nfn f() {}\n"
+    );
+}
+
+#[test]
+fn fmt_keeps_compound_assignments() {
+    let code = "let mut x = 0\nx+=2\nx++\nlet arr = [1, 2]\narr[0]*=3\n";
+
+    assert_eq!(
+        format(code),
+        "let mut x = 0\nx += 2\nx++\nlet arr = [1, 2]\narr[0] *= 3\n"
+    );
+}
+
+#[test]
+fn fmt_adds_semicolons_to_lambda_bodies_inside_calls() {
+    let code =
+        "apply(fn(x) { return x * 2 }, 3)\nlet g = fn(x) {\n    let y = x\n    return y\n}\n";
+
+    assert_eq!(
+        format(code),
+        "apply(fn(x) { return x * 2 }, 3)\nlet g = fn(x) {\n    let y = x\n    return y\n}\n"
+    );
+
+    let split = "apply(fn(x) {\n    let y = x * 2;\n    return y\n}, 3)\n";
+    assert_eq!(
+        format(split),
+        "apply(\n    fn(x) {\n        let y = x * 2;\n        return y;\n    },\n    3\n)\n"
+    );
+}
+
+#[test]
+fn fmt_splits_long_calls_one_argument_per_line() {
+    let code = "let total = compute(first_argument_value, second_argument_value, third_argument_value, fourth_argument_value)\n";
+
+    assert_eq!(
+        format(code),
+        "let total = compute(\n    first_argument_value,\n    second_argument_value,\n    third_argument_value,\n    fourth_argument_value\n)\n"
+    );
+}
+
+#[test]
+fn fmt_keeps_operator_spelling() {
+    let code = "let a = x && y\nlet b = x or y\n";
+
+    assert_eq!(format(code), code);
+}
+
+#[test]
+fn fmt_is_idempotent() {
+    let code = "needs std.io\nstruct P {\n    x: int, // x\n    y: int,\n}\nif a { print(1) } else if b {\n  print(2)\n}\nelse { print(3) }\nfor i in 0..10 step 2 { if i == 4 { continue } }\n";
+
+    let once = format(code);
+    assert_eq!(format(&once), once);
+}
+
+#[test]
+fn fmt_reports_syntax_errors() {
+    let result = format_source(Source::new("bad.aelys", "let = 1\n"));
+    assert!(matches!(result, Err(FormatError::Syntax(_))));
+}
+
+#[test]
+fn fmt_check_reports_without_rewriting() {
+    let path = temp_file("check", "let x=1\n");
+    let paths = vec![path.to_str().unwrap().to_string()];
+
+    assert_eq!(run_with_options(&paths, true).unwrap(), 1);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "let x=1\n");
+
+    assert_eq!(run_with_options(&paths, false).unwrap(), 0);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "let x = 1\n");
+    assert_eq!(run_with_options(&paths, true).unwrap(), 0);
+}
+
+#[test]
+fn fmt_walks_directories() {
+    let path = temp_file("walk", "let x=1\n");
+    let dir = path.parent().unwrap();
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub").join("other.aelys"), "let y=2\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "let z=3\n").unwrap();
+
+    let paths = vec![dir.to_str().unwrap().to_string()];
+    assert_eq!(run_with_options(&paths, false).unwrap(), 0);
+
+    assert_eq!(
+        std::fs::read_to_string(dir.join("sub").join("other.aelys")).unwrap(),
+        "let y = 2\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("notes.txt")).unwrap(),
+        "let z=3\n"
+    );
+}
+
+#[test]
+fn fmt_missing_path_errors() {
+    let paths = vec!["/definitely/not/here.aelys".to_string()];
+    assert!(run_with_options(&paths, true).is_err());
+}
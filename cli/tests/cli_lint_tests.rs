@@ -0,0 +1,123 @@
+use aelys_cli::cli::commands::lint::run_with_options;
+use aelys_cli::cli::diagnostics::Reporter;
+use aelys_common::WarningConfig;
+
+fn project(name: &str, files: &[(&str, &str)]) -> String {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_lint_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, content) in files {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir.display().to_string()
+}
+
+const MAIN: &str = "needs shapes\n\nfn same(a: float, b: float) -> bool {\n    return a == b\n}\nprint(shapes.area(2, 3))\n";
+const SHAPES: &str = "pub fn area(w: int, h: int) -> int {\n    return w * h\n}\n";
+
+#[test]
+fn lint_warnings_alone_pass() {
+    let dir = project("warn", &[("main.aelys", MAIN), ("shapes.aelys", SHAPES)]);
+    assert_eq!(
+        run_with_options(&[dir], WarningConfig::new(), &mut Reporter::default()).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn lint_fails_on_denied_rules_from_aelys_toml() {
+    let dir = project(
+        "deny",
+        &[
+            ("main.aelys", MAIN),
+            ("shapes.aelys", SHAPES),
+            ("aelys.toml", "[lint]\nfloat_equality = \"deny\"\n"),
+        ],
+    );
+    assert_eq!(
+        run_with_options(&[dir], WarningConfig::new(), &mut Reporter::default()).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn lint_werror_denies_everything() {
+    let dir = project("werror", &[("main.aelys", MAIN), ("shapes.aelys", SHAPES)]);
+    let mut config = WarningConfig::new();
+    config.parse_flag("error").unwrap();
+    assert_eq!(
+        run_with_options(&[dir], config, &mut Reporter::default()).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn lint_rejects_unknown_rules_in_aelys_toml() {
+    let dir = project(
+        "unknown",
+        &[
+            ("main.aelys", "print(1)\n"),
+            ("aelys.toml", "[lint]\nfloat_eq = \"deny\"\n"),
+        ],
+    );
+    let err = run_with_options(&[dir], WarningConfig::new(), &mut Reporter::default()).unwrap_err();
+    assert!(err.contains("unknown lint rule: float_eq"), "{}", err);
+}
+
+#[test]
+fn lint_fails_on_type_errors() {
+    let dir = project("broken", &[("main.aelys", "let x: int = \"text\"\n")]);
+    assert_eq!(
+        run_with_options(&[dir], WarningConfig::new(), &mut Reporter::default()).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn lint_fails_on_undefined_names() {
+    let dir = project(
+        "undefined",
+        &[("main.aelys", "let x = 1\nprint(x + missing)\n")],
+    );
+    assert_eq!(
+        run_with_options(&[dir], WarningConfig::new(), &mut Reporter::default()).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn lint_reads_aelys_toml_per_file() {
+    let dir = project(
+        "per_file",
+        &[
+            ("lax/main.aelys", MAIN),
+            ("lax/shapes.aelys", SHAPES),
+            ("strict/main.aelys", MAIN),
+            ("strict/shapes.aelys", SHAPES),
+            ("strict/aelys.toml", "[lint]\nfloat_equality = \"deny\"\n"),
+        ],
+    );
+    let lax = format!("{}/lax", dir);
+    let strict = format!("{}/strict", dir);
+    assert_eq!(
+        run_with_options(
+            std::slice::from_ref(&lax),
+            WarningConfig::new(),
+            &mut Reporter::default()
+        )
+        .unwrap(),
+        0
+    );
+    // the first path has no aelys.toml, the second one's still applies
+    assert_eq!(
+        run_with_options(
+            &[lax, strict],
+            WarningConfig::new(),
+            &mut Reporter::default()
+        )
+        .unwrap(),
+        1
+    );
+}
@@ -0,0 +1,327 @@
+use aelys_cli::cli::commands::lsp::run_lsp_with_io;
+use aelys_common::WarningConfig;
+use serde_json::{Value, json};
+use std::io::Write;
+use std::path::PathBuf;
+
+const MAIN: &str = "needs util
+needs std.math as m
+fn add(a: int, b: int) -> int {
+    let sum = a + b
+    return sum
+}
+let greeting = \"hello\"
+let n = double(add(1, 2))
+let r = m.sqrt(2.0)
+";
+
+const UTIL: &str = "pub fn double(x: int) -> int {
+    return x * 2
+}
+";
+
+fn write_project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_lsp_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.aelys"), MAIN).unwrap();
+    std::fs::write(dir.join("util.aelys"), UTIL).unwrap();
+    dir.join("main.aelys")
+}
+
+fn uri(path: &std::path::Path) -> String {
+    format!("file://{}", path.display())
+}
+
+// Opens `text` under `uri`, sends `requests` (method, params) with ids 1..,
+// shuts down, and returns every message plus the exit code. `didChange`
+// entries go out as notifications but still use up an id.
+fn session(uri: &str, text: &str, requests: Vec<(&str, Value)>) -> (Vec<Value>, i32) {
+    let mut messages = vec![
+        json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": uri, "languageId": "aelys", "version": 1, "text": text } },
+        }),
+    ];
+    for (i, (method, params)) in requests.into_iter().enumerate() {
+        let mut message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        if method != "textDocument/didChange" {
+            message["id"] = json!(i + 1);
+        }
+        messages.push(message);
+    }
+    messages.push(json!({ "jsonrpc": "2.0", "id": 999, "method": "shutdown" }));
+    messages.push(json!({ "jsonrpc": "2.0", "method": "exit" }));
+
+    let mut input = Vec::new();
+    for message in messages {
+        let body = message.to_string();
+        write!(input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    }
+
+    let mut output = Vec::new();
+    let code = run_lsp_with_io(
+        std::io::Cursor::new(input),
+        &mut output,
+        WarningConfig::new(),
+    )
+    .unwrap();
+
+    let raw = String::from_utf8(output).unwrap();
+    let replies = raw
+        .split("Content-Length: ")
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| {
+            let (_, body) = chunk.split_once("\r\n\r\n").unwrap();
+            serde_json::from_str(body).unwrap()
+        })
+        .collect();
+    (replies, code)
+}
+
+fn result(messages: &[Value], id: u64) -> &Value {
+    &messages
+        .iter()
+        .find(|m| m["id"] == id)
+        .unwrap_or_else(|| panic!("no response to request {id}"))["result"]
+}
+
+fn at(uri: &str, line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character },
+    })
+}
+
+fn labels(completion: &Value) -> Vec<&str> {
+    completion["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn lsp_reports_errors_and_warnings_with_codes() {
+    let text = "@inline
+fn spin(n: int) -> int {
+    return spin(n)
+}
+let x: int = \"text\"
+";
+    let (messages, code) = session("file:///tmp/diag.aelys", text, vec![]);
+    assert_eq!(code, 0);
+    let published = messages
+        .iter()
+        .find(|m| m["method"] == "textDocument/publishDiagnostics")
+        .unwrap();
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    let error = &diagnostics[0];
+    assert_eq!(error["code"], "E0301");
+    assert_eq!(error["severity"], 1);
+    assert_eq!(error["range"]["start"]["line"], 4);
+
+    // a type error stops before the optimizer, so W0101 needs a clean file
+    let clean = text.replace("let x: int = \"text\"", "let x = spin(1)");
+    let (messages, _) = session("file:///tmp/diag.aelys", &clean, vec![]);
+    let published = messages
+        .iter()
+        .find(|m| m["method"] == "textDocument/publishDiagnostics")
+        .unwrap();
+    let warning = &published["params"]["diagnostics"][0];
+    assert_eq!(warning["code"], "W0101");
+    assert_eq!(warning["severity"], 2);
+    assert!(warning["message"].as_str().unwrap().contains("spin"));
+}
+
+#[test]
+fn lsp_hover_shows_inferred_types() {
+    let path = write_project("hover");
+    let uri = uri(&path);
+    let (messages, _) = session(
+        &uri,
+        MAIN,
+        vec![
+            ("textDocument/hover", at(&uri, 3, 9)),
+            ("textDocument/hover", at(&uri, 2, 4)),
+            ("textDocument/hover", at(&uri, 8, 11)),
+        ],
+    );
+    let sum = result(&messages, 1)["contents"]["value"].as_str().unwrap();
+    assert!(sum.contains("let sum: i64"), "{sum}");
+    let add = result(&messages, 2)["contents"]["value"].as_str().unwrap();
+    assert!(add.contains("fn add(a: i64, b: i64) -> i64"), "{add}");
+    let sqrt = result(&messages, 3)["contents"]["value"].as_str().unwrap();
+    assert!(
+        sqrt.contains("fn sqrt/1") && sqrt.contains("std.math"),
+        "{sqrt}"
+    );
+}
+
+#[test]
+fn lsp_definition_follows_needs_imports() {
+    let path = write_project("definition");
+    let uri = uri(&path);
+    let (messages, _) = session(
+        &uri,
+        MAIN,
+        vec![
+            ("textDocument/definition", at(&uri, 7, 10)),
+            ("textDocument/definition", at(&uri, 7, 16)),
+            ("textDocument/definition", at(&uri, 4, 12)),
+        ],
+    );
+    let double = result(&messages, 1);
+    assert!(double["uri"].as_str().unwrap().ends_with("util.aelys"));
+    assert_eq!(
+        double["range"]["start"],
+        json!({ "line": 0, "character": 7 })
+    );
+
+    let add = result(&messages, 2);
+    assert_eq!(add["uri"], uri.as_str());
+    assert_eq!(add["range"]["start"], json!({ "line": 2, "character": 3 }));
+
+    let sum = result(&messages, 3);
+    assert_eq!(sum["range"]["start"], json!({ "line": 3, "character": 8 }));
+}
+
+#[test]
+fn lsp_completes_module_members_and_string_methods() {
+    let path = write_project("completion");
+    let uri = uri(&path);
+    // mid-edit the file doesn't parse; completion works off the last good version
+    let edited = format!("{}let a = m.\nlet b = greeting.\n", MAIN);
+    let (messages, _) = session(
+        &uri,
+        MAIN,
+        vec![
+            (
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri, "version": 2 },
+                    "contentChanges": [{ "text": edited }],
+                }),
+            ),
+            ("textDocument/completion", at(&uri, 9, 10)),
+            ("textDocument/completion", at(&uri, 10, 17)),
+            ("textDocument/completion", at(&uri, 4, 4)),
+        ],
+    );
+    let math = labels(result(&messages, 2));
+    assert!(math.contains(&"sqrt") && math.contains(&"PI"), "{math:?}");
+    let methods = labels(result(&messages, 3));
+    assert!(methods.contains(&"to_upper") && methods.contains(&"split"));
+    let scope = labels(result(&messages, 4));
+    for expected in ["sum", "a", "add", "double", "m"] {
+        assert!(
+            scope.contains(&expected),
+            "{expected} missing from {scope:?}"
+        );
+    }
+}
+
+#[test]
+fn lsp_document_symbols_outline_the_file() {
+    let path = write_project("symbols");
+    let uri = uri(&path);
+    let (messages, _) = session(
+        &uri,
+        MAIN,
+        vec![(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": uri } }),
+        )],
+    );
+    let names: Vec<&str> = result(&messages, 1)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["add", "greeting", "n", "r"]);
+}
+
+#[test]
+fn lsp_positions_count_utf16_after_non_ascii_text() {
+    let text = "let s = \"héllo 🙂\"\nlet t = s\n";
+    let (messages, _) = session(
+        "file:///tmp/utf16.aelys",
+        text,
+        vec![(
+            "textDocument/definition",
+            at("file:///tmp/utf16.aelys", 1, 8),
+        )],
+    );
+    let s = result(&messages, 1);
+    assert_eq!(s["range"]["start"], json!({ "line": 0, "character": 4 }));
+
+    let (messages, _) = session(
+        "file:///tmp/utf16.aelys",
+        "let s = \"🙂\"; let t = s\n",
+        vec![("textDocument/hover", at("file:///tmp/utf16.aelys", 0, 22))],
+    );
+    // the emoji is two UTF-16 units but one char in the span
+    let hover = result(&messages, 1);
+    assert_eq!(
+        hover["range"]["start"],
+        json!({ "line": 0, "character": 22 })
+    );
+}
+
+#[test]
+fn lsp_applies_range_edits_in_order() {
+    let uri = "file:///tmp/edits.aelys";
+    let text = "let a = \"\u{1F600}\"\nlet x: int = 1\n";
+    let edit = |line: u32, start: u32, end: u32, text: &str| {
+        json!({
+            "range": {
+                "start": { "line": line, "character": start },
+                "end": { "line": line, "character": end },
+            },
+            "text": text,
+        })
+    };
+    let (messages, _) = session(
+        uri,
+        text,
+        vec![
+            (
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri, "version": 2 },
+                    "contentChanges": [
+                        edit(1, 13, 14, "\"s\""),
+                        // after the emoji, which is two UTF-16 units
+                        edit(0, 11, 11, "!"),
+                        edit(0, 0, 0, "let z = 3\n"),
+                    ],
+                }),
+            ),
+            (
+                "textDocument/documentSymbol",
+                json!({ "textDocument": { "uri": uri } }),
+            ),
+        ],
+    );
+    assert_eq!(result(&messages, 0)["capabilities"]["textDocumentSync"], 2);
+
+    let names: Vec<&str> = result(&messages, 2)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["z", "a", "x"]);
+    let published = messages
+        .iter()
+        .rfind(|m| m["method"] == "textDocument/publishDiagnostics")
+        .unwrap();
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 2);
+}
@@ -0,0 +1,105 @@
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn script(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_message_format_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.aelys");
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+// exit code, stdout and stderr
+fn aelys(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_aelys-cli"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn json_lines(stderr: &str) -> Vec<Value> {
+    stderr
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn compile_json_reports_error_with_span() {
+    let path = script("compile", "let x: int = 1\nlet y = x +\n");
+    let (code, _, stderr) = aelys(&["compile", "--message-format=json", path.to_str().unwrap()]);
+
+    assert_eq!(code, 1);
+    let diagnostics = json_lines(&stderr);
+    assert_eq!(diagnostics.len(), 1);
+    let d = &diagnostics[0];
+    assert_eq!(d["severity"], "error");
+    assert!(d["code"].as_str().unwrap().starts_with('E'));
+    let span = &d["spans"][0];
+    assert_eq!(span["file"], path.to_str().unwrap());
+    assert_eq!(span["line"], 2);
+}
+
+#[test]
+fn run_json_keeps_program_output_on_stdout() {
+    let path = script(
+        "run",
+        "fn div(a: int, b: int) -> int {\n    return a / b\n}\nprint(\"before\")\ndiv(1, 0)\n",
+    );
+    let (code, stdout, stderr) = aelys(&[
+        "run",
+        "-O0",
+        "--message-format=json",
+        path.to_str().unwrap(),
+    ]);
+
+    assert_eq!(code, 1);
+    assert_eq!(stdout.trim(), "before");
+    let diagnostics = json_lines(&stderr);
+    assert_eq!(diagnostics.len(), 1);
+    let d = &diagnostics[0];
+    assert_eq!(d["severity"], "error");
+    assert!(d["code"].is_null());
+    assert_eq!(d["spans"][0]["line"], 2);
+    assert!(d["notes"][0].as_str().unwrap().contains("div"));
+}
+
+#[test]
+fn lint_sarif_is_one_log_with_rules_and_locations() {
+    let path = script(
+        "lint",
+        "fn same(a: float, b: float) -> bool {\n    return a == b\n}\nprint(same(1.0, 2.0))\n",
+    );
+    let (code, _, stderr) = aelys(&["lint", "--message-format=sarif", path.to_str().unwrap()]);
+
+    assert_eq!(code, 0);
+    let log: Value = serde_json::from_str(&stderr).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "aelys");
+    let result = &run["results"][0];
+    assert_eq!(result["level"], "warning");
+    assert_eq!(result["ruleId"], run["tool"]["driver"]["rules"][0]["id"]);
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 12);
+    assert_eq!(location["region"]["endColumn"], 18);
+}
+
+#[test]
+fn failures_without_a_location_are_diagnostics_too() {
+    let (code, _, stderr) = aelys(&["compile", "--message-format=json", "missing.aelys"]);
+
+    assert_eq!(code, 1);
+    let diagnostics = json_lines(&stderr);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], "error");
+    assert_eq!(diagnostics[0]["spans"], Value::Array(Vec::new()));
+}
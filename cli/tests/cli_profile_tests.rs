@@ -0,0 +1,51 @@
+use aelys_cli::cli::commands::profile::run_with_options;
+use aelys_cli::cli::diagnostics::Reporter;
+use aelys_common::WarningConfig;
+use aelys_opt::OptimizationLevel;
+
+#[test]
+fn profile_writes_folded_stacks_next_to_script() {
+    let dir = std::env::temp_dir().join("aelys_cli_profile");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("busy.aelys");
+    std::fs::write(
+        &script,
+        "fn spin(n: int) -> int {\n    let mut total = 0\n    for i in 0..n { total += i }\n    return total\n}\nspin(200000)\n",
+    )
+    .unwrap();
+
+    let code = run_with_options(
+        script.to_str().unwrap(),
+        Vec::new(),
+        Vec::new(),
+        OptimizationLevel::None,
+        WarningConfig::new(),
+        &mut Reporter::default(),
+    )
+    .unwrap();
+    assert_eq!(code, 0);
+
+    let folded = std::fs::read_to_string(dir.join("busy.folded")).unwrap();
+    let line = folded
+        .lines()
+        .find(|line| line.starts_with("<script>;spin "))
+        .unwrap_or_else(|| panic!("no spin stack in:\n{}", folded));
+    let micros: u64 = line.rsplit(' ').next().unwrap().parse().unwrap();
+    assert!(micros > 0);
+}
+
+#[test]
+fn profile_rejects_bytecode_input() {
+    let err = run_with_options(
+        "program.avbc",
+        Vec::new(),
+        Vec::new(),
+        OptimizationLevel::None,
+        WarningConfig::new(),
+        &mut Reporter::default(),
+    )
+    .unwrap_err();
+
+    assert!(err.contains("source file"));
+}
@@ -35,3 +35,75 @@ fn repl_recovers_after_error() {
     assert!(text.to_lowercase().contains("error"));
     assert!(text.contains("2"));
 }
+
+fn repl(input: &str) -> String {
+    let mut output = Vec::new();
+    run_repl_with_io(
+        input.as_bytes(),
+        &mut output,
+        OptimizationLevel::None,
+        Vec::new(),
+    )
+    .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn repl_continues_until_brackets_balance() {
+    let text = repl(
+        "fn add(a: int, b: int) -> int {\n    return a + b\n}\nadd(2, 3)\nlet v = [\n    1,\n    2\n]\nv\n",
+    );
+    assert!(!text.to_lowercase().contains("error"), "{}", text);
+    assert!(text.contains("5\n"));
+    assert!(text.contains("[1, 2]"));
+}
+
+#[test]
+fn repl_type_command() {
+    let text =
+        repl("fn add(a: int, b: int) -> int {\n    return a + b\n}\n:type add(1, 2)\n:type add\n");
+    assert!(text.contains("i64\n"));
+    assert!(text.contains("(i64, i64) -> i64"));
+}
+
+#[test]
+fn repl_asm_command() {
+    let text = repl("fn add(a: int, b: int) -> int { return a + b }\n:asm add\n:asm nothing\n");
+    assert!(text.contains(".name \"add\""));
+    assert!(text.contains("error: no function named nothing"));
+}
+
+#[test]
+fn repl_reset_forgets_definitions() {
+    let text = repl("let x = 40\n:reset\nx\n");
+    assert!(text.contains("session reset"));
+    assert!(text.to_lowercase().contains("error"));
+}
+
+#[test]
+fn repl_reports_bad_meta_commands() {
+    let text = repl(":nope\n:type\n");
+    assert!(text.contains("error: unknown command :nope"));
+    assert!(text.contains("error: usage: :type <expr>"));
+}
+
+#[test]
+fn repl_quit_stops_reading() {
+    let text = repl("1 + 1\n:quit\n40 + 2\n");
+    assert!(text.contains("2"));
+    assert!(!text.contains("42"));
+}
+
+#[test]
+fn repl_load_and_time() {
+    let dir = std::env::temp_dir().join(format!("aelys_repl_load_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("twice.aelys");
+    std::fs::write(&file, "fn twice(x: int) -> int {\n    return x * 2\n}\n").unwrap();
+
+    let text = repl(&format!(":load {}\n:time twice(21)\n", file.display()));
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(text.contains("42\n"), "{}", text);
+    assert!(text.contains("time: "));
+}
@@ -1,4 +1,5 @@
 use aelys_cli::cli::commands::run::run_with_options;
+use aelys_cli::cli::diagnostics::Reporter;
 use aelys_common::WarningConfig;
 use aelys_opt::OptimizationLevel;
 
@@ -26,6 +27,7 @@ fn run_accepts_bytecode_with_magic() {
         Vec::new(),
         OptimizationLevel::Standard,
         WarningConfig::new(),
+        &mut Reporter::default(),
     );
 
     assert!(result.is_ok());
@@ -49,6 +51,7 @@ fn run_bytecode_registers_stdlib_globals() {
         Vec::new(),
         OptimizationLevel::Standard,
         WarningConfig::new(),
+        &mut Reporter::default(),
     );
 
     assert!(result.is_ok());
@@ -1,4 +1,5 @@
 use aelys_cli::cli::commands::run::run_with_options;
+use aelys_cli::cli::diagnostics::Reporter;
 use aelys_common::WarningConfig;
 use aelys_opt::OptimizationLevel;
 
@@ -10,6 +11,7 @@ fn run_rejects_invalid_vm_args() {
         vec!["-ae.max-heap=1".to_string()],
         OptimizationLevel::None,
         WarningConfig::new(),
+        &mut Reporter::default(),
     )
     .unwrap_err();
 
@@ -34,6 +36,7 @@ fn run_accepts_aasm_file() {
         Vec::new(),
         OptimizationLevel::Standard,
         WarningConfig::new(),
+        &mut Reporter::default(),
     );
 
     assert!(result.is_ok());
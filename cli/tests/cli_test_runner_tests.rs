@@ -0,0 +1,159 @@
+use aelys_cli::cli::commands::test::run_tests_with_io;
+use aelys_opt::OptimizationLevel;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("aelys_cli_test_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+fn run_tests(dir: &Path, filters: &[&str]) -> (i32, String) {
+    let output = SharedOutput::default();
+    let filters: Vec<String> = filters.iter().map(|f| f.to_string()).collect();
+    let code = run_tests_with_io(
+        &[dir.display().to_string()],
+        &filters,
+        Vec::new(),
+        OptimizationLevel::Standard,
+        output.clone(),
+    )
+    .unwrap();
+    (code, String::from_utf8(output.0.take()).unwrap())
+}
+
+const COUNTER: &str = r#"
+let mut count = 0
+
+fn bump() {
+    count++
+    return count
+}
+
+@test
+fn first_bump() {
+    assert_eq(bump(), 1)
+}
+
+@test
+fn second_bump() {
+    assert_eq(bump(), 1)
+}
+
+fn not_a_test() {
+    assert(false)
+}
+"#;
+
+#[test]
+fn test_runs_each_test_in_a_fresh_vm() {
+    let dir = project("fresh", &[("counter.aelys", COUNTER)]);
+
+    let (code, output) = run_tests(&dir, &[]);
+
+    assert_eq!(code, 0, "{}", output);
+    assert!(output.contains("running 2 tests from"), "{}", output);
+    assert!(output.contains("test first_bump ... ok"), "{}", output);
+    assert!(output.contains("test second_bump ... ok"), "{}", output);
+    assert!(output.contains("test result: ok. 2 passed; 0 failed; 0 filtered out"));
+}
+
+#[test]
+fn test_failure_shows_source_and_output() {
+    let dir = project(
+        "failure",
+        &[(
+            "math.aelys",
+            "@test\nfn adds() {\n    print(\"checking\")\n    assert_eq(1 + 1, 3)\n}\n",
+        )],
+    );
+
+    let (code, output) = run_tests(&dir, &[]);
+
+    assert_eq!(code, 1);
+    assert!(output.contains("test adds ... FAILED"), "{}", output);
+    assert!(output.contains("---- adds ("), "{}", output);
+    assert!(output.contains("    checking"), "{}", output);
+    assert!(output.contains("left: 2"), "{}", output);
+    assert!(output.contains("math.aelys:4:1"), "{}", output);
+    assert!(output.contains("assert_eq(1 + 1, 3)"), "{}", output);
+    assert!(output.contains("test result: FAILED. 0 passed; 1 failed"));
+}
+
+#[test]
+fn test_discovers_tests_in_modules() {
+    let dir = project(
+        "modules",
+        &[
+            (
+                "lib/util.aelys",
+                "pub fn double(x) { return x * 2 }\n\n@test\nfn doubles() {\n    assert_eq(double(3), 6)\n}\n",
+            ),
+            (
+                "main.aelys",
+                "needs lib.util\n\n@test\nfn uses_util() {\n    assert(util.double(2) == 4)\n}\n",
+            ),
+            ("notes.txt", "@test\nfn ignored() { }\n"),
+        ],
+    );
+
+    let (code, output) = run_tests(&dir, &[]);
+
+    assert_eq!(code, 0, "{}", output);
+    assert!(output.contains("test doubles ... ok"), "{}", output);
+    assert!(output.contains("test uses_util ... ok"), "{}", output);
+    assert!(!output.contains("ignored"), "{}", output);
+}
+
+#[test]
+fn test_filters_by_name() {
+    let dir = project("filter", &[("counter.aelys", COUNTER)]);
+
+    let (code, output) = run_tests(&dir, &["second"]);
+
+    assert_eq!(code, 0);
+    assert!(!output.contains("first_bump"), "{}", output);
+    assert!(output.contains("test second_bump ... ok"), "{}", output);
+    assert!(
+        output.contains("1 passed; 0 failed; 1 filtered out"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_reports_files_that_do_not_compile() {
+    let dir = project("broken", &[("broken.aelys", "@test\nfn takes(x) { }\n")]);
+
+    let (code, output) = run_tests(&dir, &[]);
+
+    assert_eq!(code, 1);
+    assert!(output.contains("does not compile"), "{}", output);
+    assert!(
+        output.contains("no parameters on a @test function"),
+        "{}",
+        output
+    );
+}
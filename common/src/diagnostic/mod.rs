@@ -1 +1,143 @@
-// TODO: LSP diagnostics, better error spans
+// flattened view of errors and warnings for tools (LSP, machine-readable output)
+
+use crate::error::{AelysError, CompileError, RuntimeError};
+use crate::warning::Warning;
+use aelys_syntax::{Source, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<String>, // "E0101", "W0201"; runtime errors have none
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Span, // line 0 when there's no location
+    pub end_line: u32,
+    pub end_column: u32,
+    pub label: Option<String>, // what the carets say in the text output
+    pub notes: Vec<String>,
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    fn located(severity: Severity, message: String, span: Span, source: Option<&Source>) -> Self {
+        let (end_line, end_column) = match source {
+            Some(source) => end_position(source, span),
+            None => (span.line, span.column),
+        };
+        Self {
+            severity,
+            code: None,
+            message,
+            file: source.map(|s| s.name.clone()),
+            span,
+            end_line,
+            end_column,
+            label: None,
+            notes: Vec::new(),
+            hint: None,
+        }
+    }
+}
+
+impl From<&CompileError> for Diagnostic {
+    fn from(err: &CompileError) -> Self {
+        let mut d = Self::located(
+            Severity::Error,
+            err.kind.message(),
+            err.span,
+            Some(&err.source),
+        );
+        d.code = Some(format!("E{:04}", err.kind.code()));
+        d.label = Some(err.kind.annotation().to_string()).filter(|l| !l.is_empty());
+        d
+    }
+}
+
+impl From<&Warning> for Diagnostic {
+    fn from(w: &Warning) -> Self {
+        let mut d = Self::located(
+            Severity::Warning,
+            w.kind.message(w.context.as_deref()),
+            w.span,
+            w.source.as_deref(),
+        );
+        d.code = Some(format!("W{:04}", w.code()));
+        d.label = Some(w.kind.annotation().to_string()).filter(|l| !l.is_empty());
+        d.notes = w.kind.note().map(str::to_string).into_iter().collect();
+        d.hint = w.kind.hint().map(str::to_string);
+        d
+    }
+}
+
+// located at the innermost frame; the rest of the stack trace becomes notes
+impl From<&RuntimeError> for Diagnostic {
+    fn from(err: &RuntimeError) -> Self {
+        let message = err.kind.message();
+        let Some(frame) = err.stack_trace.first() else {
+            return Self::located(Severity::Error, message, Span::dummy(), None);
+        };
+        let start = offset_of(&err.source, frame.line, frame.column);
+        let span = Span::new(start, start, frame.line, frame.column);
+        let mut d = Self::located(Severity::Error, message, span, Some(&err.source));
+        d.notes = err
+            .stack_trace
+            .iter()
+            .map(|frame| {
+                let name = frame.function_name.as_deref().unwrap_or("<script>");
+                format!("in {} ({}:{})", name, err.source.name, frame.line)
+            })
+            .collect();
+        d
+    }
+}
+
+impl From<&AelysError> for Diagnostic {
+    fn from(err: &AelysError) -> Self {
+        match err {
+            AelysError::Compile(err) => Self::from(err),
+            AelysError::Runtime(err) => Self::from(err),
+        }
+    }
+}
+
+// line and column (both 1-based) just past the span
+fn end_position(source: &Source, span: Span) -> (u32, u32) {
+    if span.line == 0 {
+        return (0, 0);
+    }
+    let (mut line, mut column) = (1, 1);
+    for ch in source.content.chars().take(span.end.max(span.start)) {
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+fn offset_of(source: &Source, line: u32, column: u32) -> usize {
+    let before: usize = source
+        .content
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1) as usize)
+        .map(|l| l.chars().count())
+        .sum();
+    before + column.saturating_sub(1) as usize
+}
@@ -64,6 +64,15 @@ pub enum RuntimeErrorKind {
         index: i64,
         length: i64,
     },
+    /// `assert` got a falsy value, or `assert_eq` two different ones (printed).
+    AssertionFailed {
+        left: Option<String>,
+        right: Option<String>,
+    },
+    /// The attached debugger was told to quit. Unwinds every run loop, including
+    /// the ones nested under native callbacks; `VM::execute` turns it back into
+    /// a normal return.
+    DebuggerQuit,
 }
 
 impl RuntimeError {
@@ -131,6 +140,15 @@ impl RuntimeErrorKind {
                     index, length
                 )
             }
+            Self::AssertionFailed {
+                left: Some(left),
+                right: Some(right),
+            } => format!(
+                "assertion failed: left == right\n  left: {}\n right: {}",
+                left, right
+            ),
+            Self::AssertionFailed { .. } => "assertion failed".to_string(),
+            Self::DebuggerQuit => "debugger quit".to_string(),
         }
     }
 }
@@ -16,6 +16,12 @@ impl WarningKind {
             Self::UnknownType { .. } => "unknown type name",
             Self::UnknownTypeParameter { .. } => "unknown type parameter",
             Self::IncompatibleComparison { .. } => "mismatched types",
+            Self::UnreachableCode => "never runs",
+            Self::ConstantComparison { .. } => "same result every time",
+            Self::MissingFree { .. } => "allocated here",
+            Self::UnusedPub { .. } => "public here",
+            Self::FloatEquality { .. } => "exact float comparison",
+            Self::DynamicInPublicApi { .. } => "no static type",
         }
     }
 
@@ -34,6 +40,12 @@ impl WarningKind {
             Self::UnknownType { .. } => Some("use int, float, bool, string, array, or vec"),
             Self::UnknownTypeParameter { .. } => Some("use a known type like int, float, string"),
             Self::IncompatibleComparison { .. } => None,
+            Self::UnreachableCode => Some("remove it, or the statement that leaves before it"),
+            Self::ConstantComparison { .. } => None,
+            Self::MissingFree { .. } => Some("free the buffer before the function returns"),
+            Self::UnusedPub { .. } => Some("drop pub, or use it from another module"),
+            Self::FloatEquality { .. } => Some("check that the difference is below a tolerance"),
+            Self::DynamicInPublicApi { .. } => Some("add a type annotation"),
         }
     }
 
@@ -47,6 +59,10 @@ impl WarningKind {
             Self::InlinePublicFunction => {
                 Some("callers from other modules will still use the non-inlined version")
             }
+            Self::MissingFree { .. } => Some("memory from alloc is not garbage collected"),
+            Self::FloatEquality { .. } => {
+                Some("rounding can make values that should be equal differ slightly")
+            }
             _ => None,
         }
     }
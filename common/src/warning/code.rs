@@ -25,6 +25,14 @@ impl WarningKind {
             Self::UnknownType { .. } => 501,
             Self::UnknownTypeParameter { .. } => 502,
             Self::IncompatibleComparison { .. } => 503,
+
+            // lint: 600-699
+            Self::UnreachableCode => 601,
+            Self::ConstantComparison { .. } => 602,
+            Self::MissingFree { .. } => 603,
+            Self::UnusedPub { .. } => 604,
+            Self::FloatEquality { .. } => 605,
+            Self::DynamicInPublicApi { .. } => 606,
         }
     }
 }
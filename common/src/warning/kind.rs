@@ -46,6 +46,24 @@ pub enum WarningKind {
         right: String,
         op: String,
     },
+
+    // Lints (W06xx), only reported by `aelys lint`
+    UnreachableCode,
+    ConstantComparison {
+        result: bool,
+    },
+    MissingFree {
+        name: String,
+    },
+    UnusedPub {
+        name: String,
+    },
+    FloatEquality {
+        op: String,
+    },
+    DynamicInPublicApi {
+        what: String,
+    },
 }
 
 impl WarningKind {
@@ -79,6 +97,27 @@ impl WarningKind {
             WarningKind::UnknownType { .. }
             | WarningKind::UnknownTypeParameter { .. }
             | WarningKind::IncompatibleComparison { .. } => "type",
+
+            WarningKind::UnreachableCode
+            | WarningKind::ConstantComparison { .. }
+            | WarningKind::MissingFree { .. }
+            | WarningKind::UnusedPub { .. }
+            | WarningKind::FloatEquality { .. }
+            | WarningKind::DynamicInPublicApi { .. } => "lint",
+        }
+    }
+
+    /// Name of the lint rule, as written in `@allow(...)` and the `[lint]`
+    /// table of a manifest.
+    pub fn lint_rule(&self) -> Option<&'static str> {
+        match self {
+            WarningKind::UnreachableCode => Some("unreachable_code"),
+            WarningKind::ConstantComparison { .. } => Some("constant_comparison"),
+            WarningKind::MissingFree { .. } => Some("missing_free"),
+            WarningKind::UnusedPub { .. } => Some("unused_pub"),
+            WarningKind::FloatEquality { .. } => Some("float_equality"),
+            WarningKind::DynamicInPublicApi { .. } => Some("dynamic_in_pub_api"),
+            _ => None,
         }
     }
 }
@@ -79,6 +79,28 @@ impl WarningKind {
                     if op == "!=" { "true" } else { "false" }
                 )
             }
+
+            Self::UnreachableCode => "unreachable code".to_string(),
+
+            Self::ConstantComparison { result } => {
+                format!("comparison is always {}", result)
+            }
+
+            Self::MissingFree { name: func } => {
+                format!("@no_gc function '{}' allocates but never calls free", func)
+            }
+
+            Self::UnusedPub { name: item } => {
+                format!("'{}' is pub but no other module uses it", item)
+            }
+
+            Self::FloatEquality { op } => {
+                format!("floats compared with {}", op)
+            }
+
+            Self::DynamicInPublicApi { what } => {
+                format!("{} is dynamic in a public API", what)
+            }
         }
     }
 }
@@ -5,17 +5,32 @@ use aelys_common::{Result, Warning};
 use aelys_frontend::lexer::Lexer;
 use aelys_frontend::parser::Parser;
 use aelys_opt::{OptimizationLevel, Optimizer};
-use aelys_runtime::{VM, Value, VmConfig};
+use aelys_runtime::{GcRef, VM, Value, VmConfig};
 use aelys_sema::TypeInference;
 use aelys_syntax::{Source, Span};
 
-const BUILTIN_NAMES: &[&str] = &["alloc", "free", "load", "store", "type"];
+const BUILTIN_NAMES: &[&str] = &[
+    "alloc",
+    "free",
+    "load",
+    "store",
+    "type",
+    "assert",
+    "assert_eq",
+];
 
 pub struct RunResult {
     pub value: Value,
     pub warnings: Vec<Warning>,
 }
 
+/// A compiled script sitting in its VM, ready for `vm.execute(main)`.
+pub struct LoadedProgram {
+    pub vm: VM,
+    pub main: GcRef,
+    pub warnings: Vec<Warning>,
+}
+
 pub fn run_file(file_path: &std::path::Path) -> Result<Value> {
     run_file_with_config(file_path, VmConfig::default(), Vec::new())
 }
@@ -44,6 +59,22 @@ pub fn run_file_full(
     program_args: Vec<String>,
     opt_level: OptimizationLevel,
 ) -> Result<RunResult> {
+    let LoadedProgram {
+        mut vm,
+        main,
+        warnings,
+    } = load_file(file_path, config, program_args, opt_level)?;
+    let value = vm.execute(main)?;
+
+    Ok(RunResult { value, warnings })
+}
+
+pub fn load_file(
+    file_path: &std::path::Path,
+    config: VmConfig,
+    program_args: Vec<String>,
+    opt_level: OptimizationLevel,
+) -> Result<LoadedProgram> {
     let content = std::fs::read_to_string(file_path).map_err(|_| {
         AelysError::Compile(CompileError::new(
             CompileErrorKind::ModuleNotFound {
@@ -64,11 +95,11 @@ pub fn run_file_full(
     let mut vm =
         VM::with_config_and_args(src.clone(), config, program_args).map_err(AelysError::Runtime)?;
 
-    if let Ok(abs_path) = file_path.canonicalize() {
-        vm.set_script_path(abs_path.display().to_string());
-    } else {
-        vm.set_script_path(file_path.display().to_string());
-    }
+    let script_path = match file_path.canonicalize() {
+        Ok(abs_path) => abs_path.display().to_string(),
+        Err(_) => file_path.display().to_string(),
+    };
+    vm.set_script_path(script_path.clone());
 
     let imports = load_modules_for_program(&stmts, file_path, src.clone(), &mut vm)?;
 
@@ -163,9 +194,10 @@ pub fn run_file_full(
         .merge_heap(&mut compile_heap)
         .map_err(AelysError::Runtime)?;
     function.remap_constants(&remap);
+    vm.cover_source(&name, &function);
+    vm.add_source(&script_path, &function);
 
-    let func_ref = vm.alloc_function(function).map_err(AelysError::Runtime)?;
-    let value = vm.execute(func_ref)?;
+    let main = vm.alloc_function(function).map_err(AelysError::Runtime)?;
 
-    Ok(RunResult { value, warnings })
+    Ok(LoadedProgram { vm, main, warnings })
 }
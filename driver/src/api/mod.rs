@@ -6,7 +6,8 @@ mod vm;
 
 pub use call::{CallableFunction, call_function, get_function};
 pub use file::{
-    RunResult, run_file, run_file_full, run_file_with_config, run_file_with_config_and_opt,
+    LoadedProgram, RunResult, load_file, run_file, run_file_full, run_file_with_config,
+    run_file_with_config_and_opt,
 };
 pub use repl::{run_with_vm, run_with_vm_and_opt};
 pub use run::{run, run_source, run_with_config, run_with_config_and_opt};
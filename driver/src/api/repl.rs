@@ -9,7 +9,15 @@ use aelys_runtime::{VM, Value};
 use aelys_sema::TypeInference;
 use aelys_syntax::{Source, Span};
 
-const BUILTIN_NAMES: &[&str] = &["alloc", "free", "load", "store", "type"];
+const BUILTIN_NAMES: &[&str] = &[
+    "alloc",
+    "free",
+    "load",
+    "store",
+    "type",
+    "assert",
+    "assert_eq",
+];
 
 // REPL mode - uses Basic opt to keep top-level vars for subsequent inputs
 pub fn run_with_vm(vm: &mut VM, source: &str, name: &str) -> Result<Value> {
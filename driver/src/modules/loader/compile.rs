@@ -158,6 +158,11 @@ impl ModuleLoader {
         function.remap_constants(&remap);
 
         let global_layout = Arc::clone(&function.global_layout);
+        vm.cover_source(&file_path.display().to_string(), &function);
+        let source_path = file_path
+            .canonicalize()
+            .unwrap_or_else(|_| file_path.to_path_buf());
+        vm.add_source(&source_path.display().to_string(), &function);
 
         let func_ref = vm.alloc_function(function)?;
         vm.execute(func_ref)?;
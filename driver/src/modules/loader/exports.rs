@@ -14,32 +14,28 @@ impl ModuleLoader {
 
         for stmt in stmts {
             match &stmt.kind {
-                StmtKind::Function(func) => {
-                    if func.is_pub {
-                        exports.insert(
-                            func.name.clone(),
-                            ExportInfo {
-                                is_function: true,
-                                is_mutable: false,
-                            },
-                        );
-                    }
+                StmtKind::Function(func) if func.is_pub => {
+                    exports.insert(
+                        func.name.clone(),
+                        ExportInfo {
+                            is_function: true,
+                            is_mutable: false,
+                        },
+                    );
                 }
                 StmtKind::Let {
                     name,
                     mutable,
-                    is_pub,
+                    is_pub: true,
                     ..
                 } => {
-                    if *is_pub {
-                        exports.insert(
-                            name.clone(),
-                            ExportInfo {
-                                is_function: false,
-                                is_mutable: *mutable,
-                            },
-                        );
-                    }
+                    exports.insert(
+                        name.clone(),
+                        ExportInfo {
+                            is_function: false,
+                            is_mutable: *mutable,
+                        },
+                    );
                 }
                 StmtKind::Needs(_) => {}
                 _ => {}
@@ -0,0 +1,505 @@
+// expression layout: flat when it fits, otherwise one argument/element per line
+
+use super::printer::{Printer, push_comment, type_name};
+use super::{INDENT, MAX_WIDTH};
+use aelys_syntax::{
+    BinaryOp, Comment, Expr, ExprKind, Span, Stmt, StmtKind, StructFieldInit, UnaryOp,
+};
+
+impl<'a> Printer<'a> {
+    /// `expr` starting at column `col` on a line indented `depth` levels.
+    pub(super) fn expr(&mut self, expr: &Expr, depth: usize, col: usize) -> String {
+        // `/* */` comments written just before an expression stay in front of it
+        let mut lead = String::new();
+        while let Some(comment) = self.comments.get(self.next_comment)
+            && comment.span.start < expr.span.start
+            && comment.text.starts_with("/*")
+        {
+            lead.push_str(&comment.text);
+            lead.push(' ');
+            self.next_comment += 1;
+        }
+        let text = self.expr_kind(expr, depth, col + width(&lead));
+        lead + &text
+    }
+
+    fn expr_kind(&mut self, expr: &Expr, depth: usize, col: usize) -> String {
+        match &expr.kind {
+            // copied as written: keeps hex, exponents, escapes and `{}` parts
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::FmtString(_) => self.slice(expr.span.start, expr.span.end).to_string(),
+            ExprKind::Bool(value) => value.to_string(),
+            ExprKind::Null => "null".to_string(),
+            ExprKind::Identifier(name) => name.clone(),
+
+            ExprKind::Binary { left, op, right } => {
+                self.infix(left, op.as_str(), right, depth, col)
+            }
+            ExprKind::And { left, right } => {
+                let op = self.logical(left, right, "and", "&&");
+                self.infix(left, op, right, depth, col)
+            }
+            ExprKind::Or { left, right } => {
+                let op = self.logical(left, right, "or", "||");
+                self.infix(left, op, right, depth, col)
+            }
+            ExprKind::Unary { op, operand } => {
+                let prefix = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "not ",
+                    UnaryOp::BitNot => "~",
+                };
+                let operand = self.expr(operand, depth, col + prefix.len());
+                // `- -x` must not turn into `--x`
+                if *op == UnaryOp::Neg && operand.starts_with('-') {
+                    format!("- {}", operand)
+                } else {
+                    format!("{}{}", prefix, operand)
+                }
+            }
+
+            ExprKind::Assign { name, value } => {
+                if let Some((op, rhs)) =
+                    self.compound(expr.span.start + name.chars().count(), value)
+                {
+                    return match op {
+                        Compound::Step(step) => format!("{}{}", name, step),
+                        Compound::Assign(op) => {
+                            let head = format!("{} {}= ", name, op);
+                            let rhs = self.expr(rhs, depth, col + head.len());
+                            head + &rhs
+                        }
+                    };
+                }
+                let head = format!("{} = ", name);
+                let value = self.expr(value, depth, col + head.chars().count());
+                head + &value
+            }
+            ExprKind::IndexAssign {
+                object,
+                index,
+                value,
+            } => {
+                let target = self.index(object, index, depth, col);
+                let target_end = match &value.kind {
+                    ExprKind::Binary { left, .. } => left.span.end,
+                    _ => usize::MAX,
+                };
+                if let Some((Compound::Assign(op), rhs)) = self.compound(target_end, value) {
+                    let head = format!("{} {}= ", target, op);
+                    let rhs = self.expr(rhs, depth, col + width(&head));
+                    return head + &rhs;
+                }
+                let head = format!("{} = ", target);
+                let value = self.expr(value, depth, col + width(&head));
+                head + &value
+            }
+
+            ExprKind::Call { callee, args } => {
+                let callee = self.expr(callee, depth, col);
+                let col = col + last_line_width(&callee);
+                let args = self.list(args, expr.span.end - 1, depth, col, false);
+                callee + &args
+            }
+            ExprKind::Grouping(inner) => {
+                self.nesting += 1;
+                let inner = self.expr(inner, depth, col + 1);
+                self.nesting -= 1;
+                format!("({})", inner)
+            }
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.expr(condition, depth, col + 3);
+                let then_branch = self.expr(then_branch, depth, col);
+                let else_branch = self.expr(else_branch, depth, col);
+                format!(
+                    "if {} {{ {} }} else {{ {} }}",
+                    condition, then_branch, else_branch
+                )
+            }
+            ExprKind::Lambda {
+                params,
+                return_type,
+                body,
+            } => {
+                let head = format!("fn{}", self.signature(params, return_type.as_ref(), depth));
+                head.clone() + &self.lambda_body(expr, body, depth, col + width(&head))
+            }
+            ExprKind::Member { object, member } => {
+                format!("{}.{}", self.expr(object, depth, col), member)
+            }
+
+            ExprKind::ArrayLiteral { elements, .. } | ExprKind::VecLiteral { elements, .. } => {
+                let prefix = self.collection_prefix(expr);
+                let close = expr.span.end - 1;
+                let elements = self.list(elements, close, depth, col + width(&prefix), true);
+                prefix + &elements
+            }
+            ExprKind::ArraySized { size, .. } => {
+                let prefix = self.collection_prefix(expr);
+                let source = self.slice(expr.span.start, expr.span.end);
+                self.nesting += 1;
+                let size = self.expr(size, depth, col);
+                self.nesting -= 1;
+                if source.ends_with(')') {
+                    format!("{}({})", prefix, size)
+                } else {
+                    format!("{}[; {}]", prefix, size)
+                }
+            }
+            ExprKind::Index { object, index } => self.index(object, index, depth, col),
+            ExprKind::Slice { object, range } => self.index(object, range, depth, col),
+            ExprKind::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let mut out = String::new();
+                if let Some(start) = start {
+                    out += &self.expr(start, depth, col);
+                }
+                out.push_str(if *inclusive { "..=" } else { ".." });
+                if let Some(end) = end {
+                    out += &self.expr(end, depth, col);
+                }
+                out
+            }
+            ExprKind::StructLiteral { name, fields } => {
+                self.struct_literal(name, fields, depth, col)
+            }
+            ExprKind::Cast { expr, target } => {
+                format!("{} as {}", self.expr(expr, depth, col), type_name(target))
+            }
+        }
+    }
+
+    fn infix(&mut self, left: &Expr, op: &str, right: &Expr, depth: usize, col: usize) -> String {
+        self.operand += 1;
+        let left = self.expr(left, depth, col);
+        let col = col + last_line_width(&left) + op.len() + 2;
+        let right = self.expr(right, depth, col);
+        self.operand -= 1;
+        format!("{} {} {}", left, op, right)
+    }
+
+    // `and`/`or` and `&&`/`||` are the same token; keep whichever was written
+    fn logical(
+        &self,
+        left: &Expr,
+        right: &Expr,
+        word: &'static str,
+        symbol: &'static str,
+    ) -> &'static str {
+        if self.slice(left.span.end, right.span.start).contains(symbol) {
+            symbol
+        } else {
+            word
+        }
+    }
+
+    fn index(&mut self, object: &Expr, index: &Expr, depth: usize, col: usize) -> String {
+        let object = self.expr(object, depth, col);
+        self.nesting += 1;
+        let index = self.expr(index, depth, col + last_line_width(&object) + 1);
+        self.nesting -= 1;
+        format!("{}[{}]", object, index)
+    }
+
+    // `x += y`, `x++` and `a[i] -= y` reach us desugared into `x = x + y`;
+    // what follows the target in the source tells them apart
+    fn compound<'e>(&self, target_end: usize, value: &'e Expr) -> Option<(Compound, &'e Expr)> {
+        let ExprKind::Binary { op, right, .. } = &value.kind else {
+            return None;
+        };
+        let rest = self.slice(target_end, value.span.end.max(target_end));
+        let rest = rest.trim_start();
+        let symbol = op.as_str();
+        if rest.starts_with(&format!("{}=", symbol)) {
+            return Some((Compound::Assign(symbol), right));
+        }
+        match op {
+            BinaryOp::Add if rest.starts_with("++") => Some((Compound::Step("++"), right)),
+            BinaryOp::Sub if rest.starts_with("--") => Some((Compound::Step("--"), right)),
+            _ => None,
+        }
+    }
+
+    // `Array<int>` in `Array<int>[1, 2]`, or nothing for a bare `[1, 2]`
+    fn collection_prefix(&self, expr: &Expr) -> String {
+        let source = self.slice(expr.span.start, expr.span.end);
+        let name: String = source
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        let element_type = match &expr.kind {
+            ExprKind::ArrayLiteral { element_type, .. }
+            | ExprKind::VecLiteral { element_type, .. }
+            | ExprKind::ArraySized { element_type, .. } => element_type.as_ref(),
+            _ => None,
+        };
+        match element_type {
+            Some(ty) => format!("{}<{}>", name, type_name(ty)),
+            None => name,
+        }
+    }
+
+    /// `(a, b)` for calls (`brackets` false) or `[a, b]` for arrays, `close`
+    /// being the offset of the closing bracket.
+    fn list(
+        &mut self,
+        items: &[Expr],
+        close: usize,
+        depth: usize,
+        col: usize,
+        brackets: bool,
+    ) -> String {
+        let spans: Vec<Span> = items.iter().map(|item| item.span).collect();
+        let list = Delimited {
+            open: if brackets { '[' } else { '(' },
+            close: if brackets { ']' } else { ')' },
+            close_at: close,
+            // calls take no trailing comma
+            trailing_comma: brackets,
+            wrap: true,
+        };
+        self.delimited(&spans, list, depth, col, &mut |printer, i, depth, col| {
+            printer.expr(&items[i], depth, col)
+        })
+    }
+
+    /// Items flat when they fit, otherwise one per line; a multi-line last
+    /// item, typically a lambda, may hug the opening line instead. A comment
+    /// stays after the item it follows on the same line, or goes above the
+    /// one it precedes. `//` comments need a line break, so they force the
+    /// split even when `wrap` is off.
+    pub(super) fn delimited(
+        &mut self,
+        spans: &[Span],
+        list: Delimited,
+        depth: usize,
+        col: usize,
+        render: &mut dyn FnMut(&mut Self, usize, usize, usize) -> String,
+    ) -> String {
+        self.nesting += 1;
+        let mark = self.next_comment;
+
+        let mut forced = false;
+        let mut texts = Vec::new();
+        let mut pieces = Vec::new();
+        let mut item_col = col + 1;
+        let mut prev_end = None;
+        for (i, span) in spans.iter().enumerate() {
+            forced |= !self.leading_comments(prev_end, span.start).is_empty();
+            let text = render(self, i, depth, item_col);
+            let next = spans.get(i + 1).map_or(list.close_at, |s| s.start);
+            let trailing = self.trailing_comments(span.end, next);
+            forced |= trailing.iter().any(|c| c.text.starts_with("//"));
+
+            let mut piece = text.clone();
+            if i + 1 < spans.len() {
+                piece.push(',');
+            }
+            for comment in &trailing {
+                piece.push(' ');
+                piece.push_str(&comment.text);
+            }
+            item_col += last_line_width(&piece) + 1;
+            prev_end = Some(trailing.last().map_or(span.end, |c| c.span.end));
+            texts.push(text);
+            pieces.push(piece);
+        }
+        forced |= self.comment_before(list.close_at);
+
+        let joined = pieces.join(" ");
+        let (head, last) = match texts.split_last() {
+            Some((last, head)) => (head, last.as_str()),
+            None => (&[][..], ""),
+        };
+        let head_is_flat = head.iter().all(|item| !item.contains('\n'));
+        let fits = col + 2 + first_line_width(&joined) <= MAX_WIDTH;
+
+        let hugs = !joined.contains('\n') || head_is_flat && last.contains('\n');
+        let out = if !forced && hugs && (fits || self.operand > 0 || !list.wrap) {
+            format!("{}{}{}", list.open, joined, list.close)
+        } else {
+            let after_flat = self.next_comment;
+            self.next_comment = mark;
+            let pad = INDENT.repeat(depth + 1);
+            let mut out = format!("{}\n", list.open);
+            let mut prev_end = None;
+            for (i, span) in spans.iter().enumerate() {
+                for comment in self.leading_comments(prev_end, span.start) {
+                    push_comment(&mut out, &pad, comment);
+                }
+                out.push_str(&pad);
+                out += &render(self, i, depth + 1, pad.len());
+                if list.trailing_comma || i + 1 < spans.len() {
+                    out.push(',');
+                }
+                let next = spans.get(i + 1).map_or(list.close_at, |s| s.start);
+                let trailing = self.trailing_comments(span.end, next);
+                for comment in &trailing {
+                    out.push(' ');
+                    out.push_str(&comment.text);
+                }
+                out.push('\n');
+                prev_end = Some(trailing.last().map_or(span.end, |c| c.span.end));
+            }
+            while let Some(comment) = self.take_comment_before(list.close_at) {
+                push_comment(&mut out, &pad, comment);
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(list.close);
+            // splitting is only worth it if that makes everything fit
+            if !forced && hugs && out.lines().any(|line| width(line) > MAX_WIDTH) {
+                self.next_comment = after_flat;
+                format!("{}{}{}", list.open, joined, list.close)
+            } else {
+                out
+            }
+        };
+        self.nesting -= 1;
+        out
+    }
+
+    // comments before an item that are on lines of their own, or `//` ones
+    // the item can't follow on the same line
+    fn leading_comments(&mut self, after: Option<usize>, start: usize) -> Vec<&'a Comment> {
+        let mut comments = Vec::new();
+        while let Some(comment) = self.comments.get(self.next_comment)
+            && comment.span.start < start
+            && (comment.text.starts_with("//")
+                || after.is_some_and(|end| self.slice(end, comment.span.start).contains('\n')))
+        {
+            comments.push(comment);
+            self.next_comment += 1;
+        }
+        comments
+    }
+
+    // comments on the same line as an item's end, before `next` starts
+    fn trailing_comments(&mut self, mut end: usize, next: usize) -> Vec<&'a Comment> {
+        let mut comments = Vec::new();
+        while let Some(comment) = self.comments.get(self.next_comment)
+            && comment.span.start < next
+            && !self.slice(end, comment.span.start).contains('\n')
+        {
+            comments.push(comment);
+            self.next_comment += 1;
+            end = comment.span.end;
+        }
+        comments
+    }
+
+    // a struct literal split over lines needs its trailing comma: without it
+    // the line break before `}` would end the statement
+    fn struct_literal(
+        &mut self,
+        name: &str,
+        fields: &[StructFieldInit],
+        depth: usize,
+        col: usize,
+    ) -> String {
+        let mark = self.next_comment;
+        let mut flat = Vec::new();
+        for field in fields {
+            let value = self.expr(&field.value, depth, col);
+            flat.push(format!("{}: {}", field.name, value));
+        }
+        let joined = flat.join(", ");
+        let fits = col + width(name) + width(&joined) + 5 <= MAX_WIDTH;
+        if !joined.contains('\n') && (fits || self.operand > 0) {
+            return format!("{} {{ {} }}", name, joined);
+        }
+
+        let after_flat = self.next_comment;
+        self.next_comment = mark;
+        let pad = INDENT.repeat(depth + 1);
+        let mut out = format!("{} {{\n", name);
+        for field in fields {
+            let head = format!("{}{}: ", pad, field.name);
+            let value = self.expr(&field.value, depth + 1, width(&head));
+            out.push_str(&format!("{}{},\n", head, value));
+        }
+        out.push_str(&INDENT.repeat(depth));
+        out.push('}');
+        if !joined.contains('\n') && out.lines().any(|line| width(line) > MAX_WIDTH) {
+            self.next_comment = after_flat;
+            return format!("{} {{ {} }}", name, joined);
+        }
+        out
+    }
+
+    fn lambda_body(&mut self, lambda: &Expr, body: &[Stmt], depth: usize, col: usize) -> String {
+        let close = lambda.span.end - 1;
+        // `fn(x) x * 2`: a lone expression not preceded by `{`
+        if let [
+            Stmt {
+                kind: StmtKind::Expression(value),
+                ..
+            },
+        ] = body
+            && !self
+                .slice(lambda.span.start, value.span.start)
+                .trim_end()
+                .ends_with('{')
+        {
+            return format!(" {}", self.expr(value, depth, col + 1));
+        }
+
+        // a single statement stays on the lambda's line if written that way
+        if let [stmt] = body
+            && matches!(stmt.kind, StmtKind::Expression(_) | StmtKind::Return(_))
+            && !self.comment_before(lambda.span.end)
+            && !self
+                .slice(lambda.span.start, lambda.span.end)
+                .contains('\n')
+        {
+            let line = self.stmt_at(stmt, depth);
+            if !line.contains('\n') && col + width(&line) + 5 <= MAX_WIDTH {
+                return format!(" {{ {} }}", line);
+            }
+        }
+        format!(" {}", self.body(body, close, depth))
+    }
+
+    // a statement rendered on its own; used for lambdas kept on one line
+    fn stmt_at(&mut self, stmt: &Stmt, depth: usize) -> String {
+        let mark = self.next_comment;
+        let line = self.stmt(stmt, depth);
+        self.next_comment = mark;
+        line
+    }
+}
+
+pub(super) struct Delimited {
+    pub(super) open: char,
+    pub(super) close: char,
+    /// Offset of `close` in the source.
+    pub(super) close_at: usize,
+    pub(super) trailing_comma: bool,
+    /// Split when too wide; without it only comments split the list.
+    pub(super) wrap: bool,
+}
+
+enum Compound {
+    Assign(&'static str),
+    Step(&'static str),
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn first_line_width(text: &str) -> usize {
+    text.lines().next().map_or(0, width)
+}
+
+fn last_line_width(text: &str) -> usize {
+    text.lines().last().map_or(0, width)
+}
@@ -0,0 +1,99 @@
+// source -> canonical source. Parses with the regular parser, reprints the
+// AST and puts comments back by offset; literals are copied verbatim.
+
+mod expr;
+mod printer;
+
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use aelys_common::error::AelysError;
+use aelys_syntax::{Source, Token, TokenKind};
+use printer::Printer;
+use std::fmt;
+use std::sync::Arc;
+
+/// Lines longer than this get their calls, arrays and struct literals split.
+pub const MAX_WIDTH: usize = 100;
+
+const INDENT: &str = "    ";
+
+#[derive(Debug)]
+pub enum FormatError {
+    /// The input doesn't lex or parse.
+    Syntax(AelysError),
+    /// The output would lex differently from the input, so it's not written.
+    /// Always a formatter bug; `line` is where the two first disagree.
+    Unstable { line: u32 },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Syntax(err) => write!(f, "{}", err),
+            FormatError::Unstable { line } => write!(
+                f,
+                "formatting would change the meaning of line {}; file left as is",
+                line
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+pub fn format_source(source: Arc<Source>) -> Result<String, FormatError> {
+    let (tokens, comments) = Lexer::with_source(Arc::clone(&source))
+        .scan_with_comments()
+        .map_err(FormatError::Syntax)?;
+    let stmts = Parser::new(tokens.clone(), Arc::clone(&source))
+        .parse()
+        .map_err(FormatError::Syntax)?;
+
+    let output = Printer::new(&source.content, &tokens, &comments).program(&stmts);
+    check_unchanged(&tokens, &source.name, &output)?;
+    Ok(output)
+}
+
+// the printer only moves whitespace, semicolons and trailing commas around;
+// anything else showing up in the token stream means it got something wrong
+fn check_unchanged(before: &[Token], name: &str, output: &str) -> Result<(), FormatError> {
+    let reprinted = Source::new(name, output);
+    let after = Lexer::with_source(Arc::clone(&reprinted))
+        .scan()
+        .ok()
+        .filter(|tokens| Parser::new(tokens.clone(), reprinted).parse().is_ok());
+    let before = significant(before);
+    let after = after.as_deref().map(significant).unwrap_or_default();
+
+    let mismatch = before
+        .iter()
+        .zip(&after)
+        .position(|(a, b)| a.kind != b.kind)
+        .or_else(|| (before.len() != after.len()).then(|| before.len().min(after.len())));
+    match mismatch {
+        None => Ok(()),
+        Some(i) => Err(FormatError::Unstable {
+            line: before.get(i).or(before.last()).map_or(1, |t| t.span.line),
+        }),
+    }
+}
+
+fn significant(tokens: &[Token]) -> Vec<&Token> {
+    let kept: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| !matches!(t.kind, TokenKind::Semicolon | TokenKind::Eof))
+        .collect();
+    kept.iter()
+        .enumerate()
+        .filter(|(i, t)| {
+            let closes = kept.get(i + 1).is_some_and(|next| {
+                matches!(
+                    next.kind,
+                    TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace
+                )
+            });
+            !(t.kind == TokenKind::Comma && closes)
+        })
+        .map(|(_, t)| *t)
+        .collect()
+}
@@ -0,0 +1,535 @@
+// statement layout and comment placement
+
+use super::INDENT;
+use super::expr::Delimited;
+use aelys_syntax::{
+    Comment, Function, ImportKind, NeedsStmt, Parameter, Span, Stmt, StmtKind, StructFieldDecl,
+    Token, TokenKind, TypeAnnotation,
+};
+
+pub(super) struct Printer<'a> {
+    text: &'a str,
+    // byte offset of every char plus one past the end; spans count chars
+    bytes: Vec<usize>,
+    tokens: &'a [Token],
+    pub(super) comments: &'a [Comment],
+    pub(super) next_comment: usize,
+    // open `(` and `[` around what's being printed: the lexer inserts no
+    // semicolons in there, so statements in lambda bodies need explicit ones
+    pub(super) nesting: usize,
+    // operands being printed; a call split inside a longer expression just
+    // moves the problem, so those stay flat
+    pub(super) operand: usize,
+}
+
+// what a statement was, for the blank line rules at the top level
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Needs,
+    Decl,
+    Other,
+}
+
+impl<'a> Printer<'a> {
+    pub(super) fn new(text: &'a str, tokens: &'a [Token], comments: &'a [Comment]) -> Self {
+        let mut bytes: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        bytes.push(text.len());
+        Self {
+            text,
+            bytes,
+            tokens,
+            comments,
+            next_comment: 0,
+            nesting: 0,
+            operand: 0,
+        }
+    }
+
+    pub(super) fn program(&mut self, stmts: &[Stmt]) -> String {
+        let end = self.bytes.len() - 1;
+        self.lines(stmts, end, 0)
+    }
+
+    pub(super) fn slice(&self, start: usize, end: usize) -> &'a str {
+        let byte = |offset: usize| self.bytes[offset.min(self.bytes.len() - 1)];
+        &self.text[byte(start)..byte(end.max(start))]
+    }
+
+    /// Whether a comment starts before `offset` that hasn't been printed yet.
+    pub(super) fn comment_before(&self, offset: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|c| c.span.start < offset)
+    }
+
+    pub(super) fn take_comment_before(&mut self, offset: usize) -> Option<&'a Comment> {
+        let comment = self.comments.get(self.next_comment)?;
+        if comment.span.start >= offset {
+            return None;
+        }
+        self.next_comment += 1;
+        Some(comment)
+    }
+
+    fn blank_line_between(&self, from: usize, to: usize) -> bool {
+        self.slice(from, to).matches('\n').count() >= 2
+    }
+
+    // end of the last real token of a statement; spans of statements ended by
+    // a newline include the newline
+    fn code_end(&self, span: Span) -> usize {
+        let upto = self.tokens.partition_point(|t| t.span.end <= span.end);
+        self.tokens[..upto]
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.kind, TokenKind::Semicolon | TokenKind::Eof))
+            .map_or(span.end, |t| t.span.end)
+    }
+
+    /// Statements one per line at `depth`, with the comments before `close`.
+    pub(super) fn lines(&mut self, stmts: &[Stmt], close: usize, depth: usize) -> String {
+        // statements in a lambda body may split again, even inside an operand
+        let operand = std::mem::replace(&mut self.operand, 0);
+        let pad = INDENT.repeat(depth);
+        let mut out = String::new();
+        let mut prev: Option<(usize, Kind)> = None;
+
+        for stmt in stmts {
+            let start = stmt_start(stmt);
+            let kind = kind_of(stmt);
+            // at the top level, `needs` and declarations are set apart
+            let force_blank = match prev {
+                Some((_, prev_kind)) if depth == 0 => {
+                    (prev_kind == Kind::Needs && kind != Kind::Needs)
+                        || prev_kind == Kind::Decl
+                        || kind == Kind::Decl
+                }
+                _ => false,
+            };
+
+            let mut first = true;
+            while let Some(comment) = self.take_comment_before(start) {
+                if let Some((end, _)) = prev
+                    && (self.blank_line_between(end, comment.span.start) || (first && force_blank))
+                {
+                    out.push('\n');
+                }
+                push_comment(&mut out, &pad, comment);
+                prev = Some((comment.span.end, prev.map_or(Kind::Other, |(_, k)| k)));
+                first = false;
+            }
+            if let Some((end, _)) = prev
+                && (self.blank_line_between(end, start) || (first && force_blank))
+            {
+                out.push('\n');
+            }
+
+            let code = self.stmt(stmt, depth);
+            let mut end = self.code_end(stmt.span);
+            // comments inside a statement but outside any block go above it
+            while let Some(comment) = self.take_comment_before(end) {
+                push_comment(&mut out, &pad, comment);
+            }
+            out.push_str(&pad);
+            out.push_str(&code);
+            if self.nesting > 0 && needs_semicolon(stmt) {
+                out.push(';');
+            }
+            if let Some(comment) = self.comments.get(self.next_comment)
+                && comment.span.start < close
+                && !self.slice(end, comment.span.start).contains('\n')
+            {
+                out.push(' ');
+                out.push_str(&comment.text);
+                self.next_comment += 1;
+                end = comment.span.end;
+            }
+            out.push('\n');
+            prev = Some((end, kind));
+        }
+
+        while let Some(comment) = self.take_comment_before(close) {
+            if let Some((end, _)) = prev
+                && self.blank_line_between(end, comment.span.start)
+            {
+                out.push('\n');
+            }
+            push_comment(&mut out, &pad, comment);
+            prev = Some((comment.span.end, Kind::Other));
+        }
+        self.operand = operand;
+        out
+    }
+
+    /// `{ ... }` holding `stmts`, with `close` the offset of the `}`.
+    pub(super) fn body(&mut self, stmts: &[Stmt], close: usize, depth: usize) -> String {
+        if stmts.is_empty() && !self.comment_before(close) {
+            return "{}".to_string();
+        }
+        format!(
+            "{{\n{}{}}}",
+            self.lines(stmts, close, depth + 1),
+            INDENT.repeat(depth)
+        )
+    }
+
+    // a `Block` statement's span runs from `{` to `}`
+    fn block(&mut self, block: &Stmt, depth: usize) -> String {
+        match &block.kind {
+            StmtKind::Block(stmts) => self.body(stmts, block.span.end - 1, depth),
+            _ => format!("{{ {} }}", self.stmt(block, depth)),
+        }
+    }
+
+    /// One statement without its indentation or line break.
+    pub(super) fn stmt(&mut self, stmt: &Stmt, depth: usize) -> String {
+        let col = depth * INDENT.len();
+        match &stmt.kind {
+            StmtKind::Expression(expr) => self.expr(expr, depth, col),
+            StmtKind::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                is_pub,
+            } => {
+                let mut head = String::new();
+                if *is_pub {
+                    head.push_str("pub ");
+                }
+                head.push_str("let ");
+                if *mutable {
+                    head.push_str("mut ");
+                }
+                head.push_str(name);
+                if let Some(ty) = type_annotation {
+                    head.push_str(": ");
+                    head.push_str(&type_name(ty));
+                }
+                head.push_str(" = ");
+                let value = self.expr(initializer, depth, col + head.chars().count());
+                head + &value
+            }
+            StmtKind::Block(_) => self.block(stmt, depth),
+            StmtKind::If { .. } => self.if_stmt(stmt, depth),
+            StmtKind::While { condition, body } => {
+                let condition = self.expr(condition, depth, col + 6);
+                format!("while {} {}", condition, self.block(body, depth))
+            }
+            StmtKind::For {
+                iterator,
+                start,
+                end,
+                inclusive,
+                step,
+                body,
+            } => {
+                let mut head = format!("for {} in ", iterator);
+                head += &self.expr(start, depth, col);
+                head.push_str(if *inclusive { "..=" } else { ".." });
+                head += &self.expr(end, depth, col);
+                if let Some(step) = step.as_ref() {
+                    head.push_str(" step ");
+                    head += &self.expr(step, depth, col);
+                }
+                format!("{} {}", head, self.block(body, depth))
+            }
+            StmtKind::ForEach {
+                iterator,
+                iterable,
+                body,
+            } => {
+                let iterable = self.expr(iterable, depth, col);
+                format!(
+                    "for {} in {} {}",
+                    iterator,
+                    iterable,
+                    self.block(body, depth)
+                )
+            }
+            StmtKind::Break => "break".to_string(),
+            StmtKind::Continue => "continue".to_string(),
+            StmtKind::Return(None) => "return".to_string(),
+            StmtKind::Return(Some(value)) => {
+                format!("return {}", self.expr(value, depth, col + 7))
+            }
+            StmtKind::Function(func) => self.function(func, depth),
+            StmtKind::Needs(needs) => needs_line(needs),
+            StmtKind::StructDecl {
+                name,
+                type_params,
+                fields,
+                is_pub,
+            } => {
+                let mut head = String::new();
+                if *is_pub {
+                    head.push_str("pub ");
+                }
+                head.push_str("struct ");
+                head.push_str(name);
+                head.push_str(&type_params_list(type_params));
+                let close = stmt.span.end - 1;
+                format!("{} {}", head, self.struct_fields(fields, close, depth))
+            }
+        }
+    }
+
+    fn if_stmt(&mut self, stmt: &Stmt, depth: usize) -> String {
+        let StmtKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } = &stmt.kind
+        else {
+            return self.stmt(stmt, depth);
+        };
+        let condition = self.expr(condition, depth, depth * INDENT.len() + 3);
+        if let Some(line) = self.one_line_if(stmt, &condition, then_branch, depth) {
+            return line;
+        }
+        let mut out = format!("if {} {}", condition, self.block(then_branch, depth));
+        match else_branch.as_deref() {
+            Some(
+                branch @ Stmt {
+                    kind: StmtKind::If { .. },
+                    ..
+                },
+            ) => {
+                out.push_str(" else ");
+                out.push_str(&self.if_stmt(branch, depth));
+            }
+            Some(branch) => {
+                out.push_str(" else ");
+                out.push_str(&self.block(branch, depth));
+            }
+            None => {}
+        }
+        out
+    }
+
+    // `if done { return x }` stays as written if it was one line to begin with
+    fn one_line_if(
+        &mut self,
+        stmt: &Stmt,
+        condition: &str,
+        then_branch: &Stmt,
+        depth: usize,
+    ) -> Option<String> {
+        let StmtKind::If {
+            else_branch: None, ..
+        } = stmt.kind
+        else {
+            return None;
+        };
+        let StmtKind::Block(body) = &then_branch.kind else {
+            return None;
+        };
+        let [inner] = body.as_slice() else {
+            return None;
+        };
+        if !needs_semicolon(inner)
+            || self.comment_before(then_branch.span.end)
+            || self
+                .slice(stmt.span.start, then_branch.span.end)
+                .contains('\n')
+        {
+            return None;
+        }
+        let mark = self.next_comment;
+        let line = format!("if {} {{ {} }}", condition, self.stmt(inner, depth));
+        if line.contains('\n') || depth * INDENT.len() + line.chars().count() > super::MAX_WIDTH {
+            self.next_comment = mark;
+            return None;
+        }
+        Some(line)
+    }
+
+    fn function(&mut self, func: &Function, depth: usize) -> String {
+        let pad = INDENT.repeat(depth);
+        let mut out = String::new();
+        for decorator in &func.decorators {
+            out.push('@');
+            out.push_str(&decorator.name);
+            if !decorator.args.is_empty() {
+                out.push('(');
+                out.push_str(&decorator.args.join(", "));
+                out.push(')');
+            }
+            out.push('\n');
+            out.push_str(&pad);
+        }
+        if func.is_pub {
+            out.push_str("pub ");
+        }
+        out.push_str("fn ");
+        out.push_str(&func.name);
+        out.push_str(&type_params_list(&func.type_params));
+        out.push_str(&self.signature(&func.params, func.return_type.as_ref(), depth));
+        out.push(' ');
+        out.push_str(&self.body(&func.body, func.span.end - 1, depth));
+        out
+    }
+
+    /// `(a: int, b) -> T`. Parameter lists only split for comments.
+    pub(super) fn signature(
+        &mut self,
+        params: &[Parameter],
+        return_type: Option<&TypeAnnotation>,
+        depth: usize,
+    ) -> String {
+        let mut out = match params.last() {
+            None => "()".to_string(),
+            Some(last) => {
+                let after = self
+                    .tokens
+                    .partition_point(|t| t.span.start < last.span.end);
+                let close_at = self.tokens[after..]
+                    .iter()
+                    .find(|t| t.kind == TokenKind::RParen)
+                    .map_or(last.span.end, |t| t.span.start);
+                let spans: Vec<Span> = params.iter().map(|p| p.span).collect();
+                let list = Delimited {
+                    open: '(',
+                    close: ')',
+                    close_at,
+                    trailing_comma: false,
+                    wrap: false,
+                };
+                self.delimited(&spans, list, depth, 0, &mut |_, i, _, _| {
+                    parameter(&params[i])
+                })
+            }
+        };
+        if let Some(ty) = return_type {
+            out.push_str(" -> ");
+            out.push_str(&type_name(ty));
+        }
+        out
+    }
+
+    fn struct_fields(&mut self, fields: &[StructFieldDecl], close: usize, depth: usize) -> String {
+        if fields.is_empty() && !self.comment_before(close) {
+            return "{}".to_string();
+        }
+        let pad = INDENT.repeat(depth + 1);
+        let mut out = String::from("{\n");
+        let mut prev: Option<usize> = None;
+        for field in fields {
+            while let Some(comment) = self.take_comment_before(field.span.start) {
+                if prev.is_some_and(|end| self.blank_line_between(end, comment.span.start)) {
+                    out.push('\n');
+                }
+                push_comment(&mut out, &pad, comment);
+                prev = Some(comment.span.end);
+            }
+            if prev.is_some_and(|end| self.blank_line_between(end, field.span.start)) {
+                out.push('\n');
+            }
+            let mut end = field.span.end;
+            out.push_str(&format!(
+                "{}{}: {},",
+                pad,
+                field.name,
+                type_name(&field.type_annotation)
+            ));
+            if let Some(comment) = self.comments.get(self.next_comment)
+                && comment.span.start < close
+                && !self.slice(end, comment.span.start).contains('\n')
+            {
+                out.push(' ');
+                out.push_str(&comment.text);
+                self.next_comment += 1;
+                end = comment.span.end;
+            }
+            out.push('\n');
+            prev = Some(end);
+        }
+        while let Some(comment) = self.take_comment_before(close) {
+            push_comment(&mut out, &pad, comment);
+        }
+        out.push_str(&INDENT.repeat(depth));
+        out.push('}');
+        out
+    }
+}
+
+pub(super) fn push_comment(out: &mut String, pad: &str, comment: &Comment) {
+    out.push_str(pad);
+    out.push_str(&comment.text);
+    out.push('\n');
+}
+
+// decorators come before the `fn` the span starts at
+fn stmt_start(stmt: &Stmt) -> usize {
+    match &stmt.kind {
+        StmtKind::Function(func) => func
+            .decorators
+            .first()
+            .map_or(stmt.span.start, |d| d.span.start),
+        _ => stmt.span.start,
+    }
+}
+
+fn kind_of(stmt: &Stmt) -> Kind {
+    match stmt.kind {
+        StmtKind::Needs(_) => Kind::Needs,
+        StmtKind::Function(_) | StmtKind::StructDecl { .. } => Kind::Decl,
+        _ => Kind::Other,
+    }
+}
+
+// statements that end at a newline rather than a `}`
+fn needs_semicolon(stmt: &Stmt) -> bool {
+    matches!(
+        stmt.kind,
+        StmtKind::Expression(_)
+            | StmtKind::Let { .. }
+            | StmtKind::Return(_)
+            | StmtKind::Break
+            | StmtKind::Continue
+            | StmtKind::Needs(_)
+    )
+}
+
+fn needs_line(needs: &NeedsStmt) -> String {
+    let path = needs.path.join(".");
+    match &needs.kind {
+        ImportKind::Module { alias: Some(alias) } => format!("needs {} as {}", path, alias),
+        ImportKind::Module { alias: None } => format!("needs {}", path),
+        ImportKind::Symbols(symbols) => format!("needs {} from {}", symbols.join(", "), path),
+        ImportKind::Wildcard => format!("needs {}.*", path),
+    }
+}
+
+fn parameter(param: &Parameter) -> String {
+    let mut out = String::new();
+    if param.mutable {
+        out.push_str("mut ");
+    }
+    out.push_str(&param.name);
+    if let Some(ty) = &param.type_annotation {
+        out.push_str(": ");
+        out.push_str(&type_name(ty));
+    }
+    out
+}
+
+fn type_params_list(params: &[String]) -> String {
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    }
+}
+
+pub(super) fn type_name(ty: &TypeAnnotation) -> String {
+    if let (Some(params), Some(ret)) = (&ty.fn_params, &ty.fn_ret) {
+        let params: Vec<String> = params.iter().map(type_name).collect();
+        return format!("fn({}) -> {}", params.join(", "), type_name(ret));
+    }
+    if ty.type_args.is_empty() {
+        return ty.name.clone();
+    }
+    let args: Vec<String> = ty.type_args.iter().map(type_name).collect();
+    format!("{}<{}>", ty.name, args.join(", "))
+}
@@ -1,6 +1,6 @@
 use super::Lexer;
 use aelys_common::error::{CompileError, CompileErrorKind};
-use aelys_syntax::{Span, Token, TokenKind};
+use aelys_syntax::{Comment, Span, Token, TokenKind};
 use std::sync::Arc;
 
 impl Lexer {
@@ -10,6 +10,17 @@ impl Lexer {
         self.tokens.push(Token::new(kind, span));
     }
 
+    pub(super) fn add_comment(&mut self) {
+        self.add_comment_from(self.line);
+    }
+
+    // block comments can span lines, so the caller passes the line they began on
+    pub(super) fn add_comment_from(&mut self, line: u32) {
+        let text = self.chars[self.start..self.current].iter().collect();
+        let span = Span::new(self.start, self.current, line, self.start_column);
+        self.comments.push(Comment { text, span });
+    }
+
     pub(super) fn advance(&mut self) -> char {
         let c = self.chars.get(self.current).copied().unwrap_or('\0');
         self.current += 1;
@@ -2,7 +2,7 @@
 // TODO: consider switching to logos for speed
 
 use aelys_common::error::AelysError;
-use aelys_syntax::{Source, Token, TokenKind};
+use aelys_syntax::{Comment, Source, Token, TokenKind};
 use std::sync::Arc;
 
 mod comment;
@@ -20,6 +20,7 @@ pub struct Lexer {
     source: Arc<Source>,
     chars: Vec<char>,
     tokens: Vec<Token>,
+    comments: Vec<Comment>,
     start: usize,
     current: usize,
     line: u32,
@@ -35,6 +36,7 @@ impl Lexer {
             source: Source::new("<input>", source),
             chars: source.chars().collect(),
             tokens: Vec::new(),
+            comments: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
@@ -51,6 +53,7 @@ impl Lexer {
             source,
             chars,
             tokens: Vec::new(),
+            comments: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
@@ -61,7 +64,12 @@ impl Lexer {
         }
     }
 
-    pub fn scan(mut self) -> Result<Vec<Token>> {
+    pub fn scan(self) -> Result<Vec<Token>> {
+        self.scan_with_comments().map(|(tokens, _)| tokens)
+    }
+
+    /// Like `scan`, but also hands back the comments it skipped, in source order.
+    pub fn scan_with_comments(mut self) -> Result<(Vec<Token>, Vec<Comment>)> {
         while !self.is_at_end() {
             self.start = self.current;
             self.start_column = self.column;
@@ -73,6 +81,6 @@ impl Lexer {
         }
 
         self.add_token(TokenKind::Eof);
-        Ok(self.tokens)
+        Ok((self.tokens, self.comments))
     }
 }
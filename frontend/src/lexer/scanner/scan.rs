@@ -92,8 +92,11 @@ impl Lexer {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    self.add_comment();
                 } else if self.match_char('*') {
+                    let line = self.line;
                     self.block_comment()?;
+                    self.add_comment_from(line);
                 } else if self.match_char('=') {
                     self.add_token(TokenKind::SlashEq);
                 } else {
@@ -109,15 +112,8 @@ impl Lexer {
                 }
             }
 
-            '!' => {
-                if self.match_char('=') {
-                    self.add_token(TokenKind::BangEq);
-                } else {
-                    return Err(AelysError::Compile(
-                        self.error(CompileErrorKind::InvalidCharacter(c)),
-                    ));
-                }
-            }
+            // a lone '!' falls through to the invalid character error below
+            '!' if self.match_char('=') => self.add_token(TokenKind::BangEq),
 
             '<' => {
                 if self.match_char('<') {
@@ -1,2 +1,3 @@
+pub mod formatter;
 pub mod lexer;
 pub mod parser;
@@ -1,6 +1,8 @@
 use super::Parser;
 use aelys_common::Result;
-use aelys_syntax::{Decorator, TokenKind};
+use aelys_common::error::{CompileError, CompileErrorKind};
+use aelys_syntax::{Decorator, Parameter, TokenKind};
+use std::sync::Arc;
 
 impl Parser {
     pub(super) fn decorators(&mut self) -> Result<Vec<Decorator>> {
@@ -9,10 +11,51 @@ impl Parser {
         while self.match_token(&TokenKind::At) {
             let span = self.previous().span;
             let name = self.consume_identifier("decorator name")?;
-            decorators.push(Decorator { name, span });
+            let mut args = Vec::new();
+            if self.match_token(&TokenKind::LParen) {
+                loop {
+                    args.push(self.consume_identifier("decorator argument")?);
+                    if !self.match_token(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.consume(&TokenKind::RParen, ")")?;
+            }
+            decorators.push(Decorator { name, args, span });
             self.consume_semicolon()?;
         }
 
         Ok(decorators)
     }
+
+    // `aelys test` calls @test functions with no arguments and no way to
+    // pick type arguments, so anything else is rejected up front
+    pub(super) fn check_test_decorator(
+        &self,
+        decorators: &[Decorator],
+        type_params: &[String],
+        params: &[Parameter],
+    ) -> Result<()> {
+        if !decorators.iter().any(|d| d.name == "test") {
+            return Ok(());
+        }
+        if let Some(param) = params.first() {
+            return Err(CompileError::new(
+                CompileErrorKind::UnexpectedToken {
+                    expected: "no parameters on a @test function".to_string(),
+                    found: param.name.clone(),
+                },
+                param.span,
+                Arc::clone(&self.source),
+            )
+            .into());
+        }
+        if let Some(name) = type_params.first() {
+            return Err(self.error(CompileErrorKind::UnexpectedToken {
+                expected: "no type parameters on a @test function".to_string(),
+                found: name.clone(),
+            }));
+        }
+        Ok(())
+    }
 }
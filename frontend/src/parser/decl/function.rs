@@ -40,6 +40,7 @@ impl Parser {
         }
 
         self.consume(&TokenKind::RParen, ")")?;
+        self.check_test_decorator(&decorators, &type_params, &params)?;
 
         let return_type = if self.match_token(&TokenKind::Arrow) {
             Some(self.parse_type_annotation()?)
@@ -13,12 +13,14 @@ impl Parser {
         let name = self.consume_identifier("type name")?;
 
         if self.match_token(&TokenKind::Lt) {
-            let type_param = self.parse_type_annotation()?;
-            self.consume(&TokenKind::Gt, ">")?;
-            let end_span = self.previous().span;
-            Ok(TypeAnnotation::with_param(
+            let mut type_args = vec![self.parse_type_annotation()?];
+            while self.match_token(&TokenKind::Comma) {
+                type_args.push(self.parse_type_annotation()?);
+            }
+            let end_span = self.consume_type_args_close()?;
+            Ok(TypeAnnotation::with_args(
                 name,
-                type_param,
+                type_args,
                 start_span.merge(end_span),
             ))
         } else {
@@ -26,6 +28,30 @@ impl Parser {
         }
     }
 
+    // `Array<Vec<int>>` lexes its closing brackets as a single `>>`, so take
+    // the first half and leave a `>` behind for the enclosing annotation
+    fn consume_type_args_close(&mut self) -> Result<aelys_syntax::Span> {
+        if self.check(&TokenKind::Shr) {
+            let token = &mut self.tokens[self.current];
+            let first = aelys_syntax::Span::new(
+                token.span.start,
+                token.span.start + 1,
+                token.span.line,
+                token.span.column,
+            );
+            token.kind = TokenKind::Gt;
+            token.span = aelys_syntax::Span::new(
+                token.span.start + 1,
+                token.span.end,
+                token.span.line,
+                token.span.column + 1,
+            );
+            return Ok(first);
+        }
+        self.consume(&TokenKind::Gt, ">")?;
+        Ok(self.previous().span)
+    }
+
     fn parse_function_type_annotation(
         &mut self,
         start_span: aelys_syntax::Span,
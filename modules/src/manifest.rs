@@ -1,8 +1,8 @@
 // .aelys.toml manifest parsing
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Manifest {
@@ -10,6 +10,8 @@ pub struct Manifest {
     pub module: HashMap<String, ModulePolicy>,
     #[serde(default)]
     pub build: BuildPolicy,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lint: BTreeMap<String, String>, // rule -> "allow", "warn" or "deny"
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -105,6 +107,13 @@ impl Manifest {
         None
     }
 
+    // aelys.toml in `dir` or the closest directory above it
+    pub fn find_project(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|d| d.join("aelys.toml"))
+            .find(|path| path.is_file())
+    }
+
     pub fn module(&self, name: &str) -> Option<&ModulePolicy> {
         self.module.get(name)
     }
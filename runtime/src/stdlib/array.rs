@@ -0,0 +1,657 @@
+// array methods - `a.sort()`, `v.insert(i, x)` and the rest on Array and Vec
+// values compile to `array::sort(a)`, `array::insert(v, i, x)`. They work on
+// the storage as it is: an Array<Int> is sorted as a slice of i64 and a
+// Vec<Float> searched as f64s, only untyped collections hold Values.
+//
+// Ordering (sort, binary_search, min, max and sort_by_key's keys) is numeric
+// for ints and floats, byte-wise for strings and false < true for bools; a
+// mix of those is an error. Equality (contains, index_of, dedup) is `==`.
+
+use crate::stdlib::helpers::{get_int, get_string, make_string};
+use crate::stdlib::{StdModuleExports, register_native, string};
+use crate::vm::{GcRef, ObjectKind, VM, Value};
+use aelys_bytecode::object::{AelysVec, ArrayData, VecData};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use std::cmp::Ordering;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "array", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("array::{}", $n));
+        }};
+    }
+
+    // ordering
+    reg!("sort", 1, native_sort);
+    reg!("sort_by", 2, native_sort_by);
+    reg!("sort_by_key", 2, native_sort_by_key);
+    reg!("binary_search", 2, native_binary_search);
+    reg!("min", 1, native_min);
+    reg!("max", 1, native_max);
+    reg!("reverse", 1, native_reverse);
+    reg!("swap", 3, native_swap);
+
+    // searching
+    reg!("contains", 2, native_contains);
+    reg!("index_of", 2, native_index_of);
+
+    // growing and shrinking, Vec only
+    reg!("insert", 3, native_insert);
+    reg!("remove", 2, native_remove);
+    reg!("extend", 2, native_extend);
+    reg!("dedup", 1, native_dedup);
+
+    reg!("join", 2, native_join);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+/// The elements of an Array or Vec as they are stored.
+pub(crate) enum Items<'a> {
+    Ints(&'a [i64]),
+    Floats(&'a [f64]),
+    Bools(&'a [u8]),
+    Objects(&'a [Value]),
+}
+
+impl Items<'_> {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Items::Ints(s) => s.len(),
+            Items::Floats(s) => s.len(),
+            Items::Bools(s) => s.len(),
+            Items::Objects(s) => s.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, i: usize) -> Value {
+        match self {
+            Items::Ints(s) => Value::int(s[i]),
+            Items::Floats(s) => Value::float(s[i]),
+            Items::Bools(s) => Value::bool(s[i] != 0),
+            Items::Objects(s) => s[i],
+        }
+    }
+
+    fn to_values(&self) -> Vec<Value> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+}
+
+enum ItemsMut<'a> {
+    Ints(&'a mut [i64]),
+    Floats(&'a mut [f64]),
+    Bools(&'a mut [u8]),
+    Objects(&'a mut [Value]),
+}
+
+fn object_kind(vm: &VM, value: Value) -> Option<&ObjectKind> {
+    value
+        .as_ptr()
+        .and_then(|ptr| vm.heap().get(GcRef::new(ptr)))
+        .map(|obj| &obj.kind)
+}
+
+fn is_string(vm: &VM, value: Value) -> bool {
+    matches!(object_kind(vm, value), Some(ObjectKind::String(_)))
+}
+
+pub(crate) fn items<'a>(
+    vm: &'a VM,
+    value: Value,
+    op: &'static str,
+) -> Result<Items<'a>, RuntimeError> {
+    let data = match object_kind(vm, value) {
+        Some(ObjectKind::Array(array)) => match &array.data {
+            ArrayData::Ints(s) => Items::Ints(s),
+            ArrayData::Floats(s) => Items::Floats(s),
+            ArrayData::Bools(s) => Items::Bools(s),
+            ArrayData::Objects(s) => Items::Objects(s),
+        },
+        Some(ObjectKind::Vec(vec)) => match &vec.data {
+            VecData::Ints(v) => Items::Ints(v),
+            VecData::Floats(v) => Items::Floats(v),
+            VecData::Bools(v) => Items::Bools(v),
+            VecData::Objects(v) => Items::Objects(v),
+        },
+        _ => {
+            return Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+                operation: op,
+                expected: "array or vec",
+                got: vm.value_type_name(value).to_string(),
+            }));
+        }
+    };
+    Ok(data)
+}
+
+fn items_mut<'a>(
+    vm: &'a mut VM,
+    value: Value,
+    op: &'static str,
+) -> Result<ItemsMut<'a>, RuntimeError> {
+    items(vm, value, op)?;
+    let obj = value
+        .as_ptr()
+        .and_then(|ptr| vm.heap_mut().get_mut(GcRef::new(ptr)));
+    Ok(match obj.map(|obj| &mut obj.kind) {
+        Some(ObjectKind::Array(array)) => match &mut array.data {
+            ArrayData::Ints(s) => ItemsMut::Ints(s),
+            ArrayData::Floats(s) => ItemsMut::Floats(s),
+            ArrayData::Bools(s) => ItemsMut::Bools(s),
+            ArrayData::Objects(s) => ItemsMut::Objects(s),
+        },
+        Some(ObjectKind::Vec(vec)) => match &mut vec.data {
+            VecData::Ints(v) => ItemsMut::Ints(v),
+            VecData::Floats(v) => ItemsMut::Floats(v),
+            VecData::Bools(v) => ItemsMut::Bools(v),
+            VecData::Objects(v) => ItemsMut::Objects(v),
+        },
+        // checked by items()
+        _ => unreachable!(),
+    })
+}
+
+/// A Vec to grow or shrink; an Array's length is fixed.
+fn vec_mut<'a>(
+    vm: &'a mut VM,
+    value: Value,
+    op: &'static str,
+) -> Result<&'a mut AelysVec, RuntimeError> {
+    if !matches!(object_kind(vm, value), Some(ObjectKind::Vec(_))) {
+        return Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "vec",
+            got: vm.value_type_name(value).to_string(),
+        }));
+    }
+    let obj = value
+        .as_ptr()
+        .and_then(|ptr| vm.heap_mut().get_mut(GcRef::new(ptr)));
+    match obj.map(|obj| &mut obj.kind) {
+        Some(ObjectKind::Vec(vec)) => Ok(vec),
+        _ => unreachable!(),
+    }
+}
+
+fn index(vm: &VM, value: Value, len: usize, op: &'static str) -> Result<usize, RuntimeError> {
+    let i = get_int(vm, value, op)?;
+    if i < 0 || i as usize >= len {
+        return Err(vm.runtime_error(RuntimeErrorKind::IndexOutOfBounds {
+            index: i,
+            length: len as i64,
+        }));
+    }
+    Ok(i as usize)
+}
+
+fn fits(data: &VecData, value: Value) -> bool {
+    match data {
+        VecData::Ints(_) => value.is_int(),
+        VecData::Floats(_) => value.is_float(),
+        VecData::Bools(_) => value.is_bool(),
+        VecData::Objects(_) => true,
+    }
+}
+
+fn element_mismatch(vm: &VM, value: Value, op: &'static str) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected: "matching element type",
+        got: vm.value_type_name(value).to_string(),
+    })
+}
+
+/// `a == b` as the VM's Eq sees it, except that floats compare as numbers.
+fn equal(vm: &VM, a: Value, b: Value) -> bool {
+    if let (Some(x), Some(y)) = (a.as_float(), b.as_float()) {
+        return x == y;
+    }
+    if a == b {
+        return true;
+    }
+    match (object_kind(vm, a), object_kind(vm, b)) {
+        (Some(ObjectKind::String(x)), Some(ObjectKind::String(y))) => x == y,
+        _ => false,
+    }
+}
+
+// NaN, whatever its sign bit, goes after every number instead of poisoning
+// the order; NaNs are level with each other
+fn float_cmp(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+    }
+}
+
+enum Key<'a> {
+    Num(f64),
+    Str(&'a str),
+    Bool(bool),
+}
+
+fn key(vm: &VM, value: Value) -> Option<Key<'_>> {
+    if let Some(n) = value.as_int() {
+        // 48-bit ints are exact as f64
+        return Some(Key::Num(n as f64));
+    }
+    if let Some(f) = value.as_float() {
+        return Some(Key::Num(f));
+    }
+    if let Some(b) = value.as_bool() {
+        return Some(Key::Bool(b));
+    }
+    match object_kind(vm, value) {
+        Some(ObjectKind::String(s)) => Some(Key::Str(s.as_str())),
+        _ => None,
+    }
+}
+
+fn compare(vm: &VM, a: Value, b: Value, op: &'static str) -> Result<Ordering, RuntimeError> {
+    let order = match (key(vm, a), key(vm, b)) {
+        (Some(Key::Num(x)), Some(Key::Num(y))) => Some(float_cmp(x, y)),
+        (Some(Key::Str(x)), Some(Key::Str(y))) => Some(x.cmp(y)),
+        (Some(Key::Bool(x)), Some(Key::Bool(y))) => Some(x.cmp(&y)),
+        _ => None,
+    };
+    order.ok_or_else(|| {
+        vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "numbers, strings or bools of one kind",
+            got: format!("{} and {}", vm.value_type_name(a), vm.value_type_name(b)),
+        })
+    })
+}
+
+/// Stable order of `values`, which must all be comparable with each other.
+fn sorted_order(vm: &VM, values: &[Value], op: &'static str) -> Result<Vec<usize>, RuntimeError> {
+    for &v in values {
+        compare(vm, values[0], v, op)?;
+    }
+    // one kind throughout, so this is a total order
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| compare(vm, values[a], values[b], op).unwrap_or(Ordering::Equal));
+    Ok(order)
+}
+
+/// Put the elements of `value` in `order`, after checking that a callback
+/// didn't change its length in the meantime.
+pub(crate) fn reorder(
+    vm: &mut VM,
+    value: Value,
+    order: &[usize],
+    op: &'static str,
+) -> Result<(), RuntimeError> {
+    let len = items(vm, value, op)?.len();
+    if len != order.len() {
+        return Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "collection left alone while sorting",
+            got: format!("length changed from {} to {}", order.len(), len),
+        }));
+    }
+    fn permute<T: Copy>(s: &mut [T], order: &[usize]) {
+        let sorted: Vec<T> = order.iter().map(|&i| s[i]).collect();
+        s.copy_from_slice(&sorted);
+    }
+    match items_mut(vm, value, op)? {
+        ItemsMut::Ints(s) => permute(s, order),
+        ItemsMut::Floats(s) => permute(s, order),
+        ItemsMut::Bools(s) => permute(s, order),
+        ItemsMut::Objects(s) => permute(s, order),
+    }
+    Ok(())
+}
+
+/// Stable bottom-up merge sort with a comparison that can fail. Unlike
+/// slice::sort_by it doesn't panic on a comparison that isn't a total
+/// order, and a script's comparator needn't be one.
+fn merge_sort<T: Copy>(
+    items: &mut [T],
+    mut less: impl FnMut(T, T) -> Result<bool, RuntimeError>,
+) -> Result<(), RuntimeError> {
+    let n = items.len();
+    let mut buf = items.to_vec();
+    let mut width = 1;
+    while width < n {
+        let mut start = 0;
+        while start < n {
+            let mid = (start + width).min(n);
+            let end = (start + 2 * width).min(n);
+            let (mut i, mut j, mut k) = (start, mid, start);
+            while i < mid && j < end {
+                // the left run wins ties, which keeps the sort stable
+                if less(items[j], items[i])? {
+                    buf[k] = items[j];
+                    j += 1;
+                } else {
+                    buf[k] = items[i];
+                    i += 1;
+                }
+                k += 1;
+            }
+            buf[k..k + mid - i].copy_from_slice(&items[i..mid]);
+            k += mid - i;
+            buf[k..k + end - j].copy_from_slice(&items[j..end]);
+            start = end;
+        }
+        items.copy_from_slice(&buf);
+        width *= 2;
+    }
+    Ok(())
+}
+
+/// sort(a) - Sort in place, ascending.
+fn native_sort(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.sort";
+    let values = match items(vm, args[0], op)? {
+        Items::Objects(s) => s.to_vec(),
+        _ => {
+            match items_mut(vm, args[0], op)? {
+                ItemsMut::Ints(s) => s.sort_unstable(),
+                ItemsMut::Floats(s) => s.sort_unstable_by(|a, b| float_cmp(*a, *b)),
+                ItemsMut::Bools(s) => s.sort_unstable(),
+                ItemsMut::Objects(_) => unreachable!(),
+            }
+            return Ok(Value::null());
+        }
+    };
+    let order = sorted_order(vm, &values, op)?;
+    reorder(vm, args[0], &order, op)?;
+    Ok(Value::null())
+}
+
+/// sort_by(a, cmp) - Sort in place with `cmp(x, y)`, which returns an int
+/// below, at or above 0 for x before, level with or after y. Stable.
+fn native_sort_by(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.sort_by";
+    let cmp = args[1];
+    let values = items(vm, args[0], op)?.to_values();
+    let mut order: Vec<usize> = (0..values.len()).collect();
+
+    // the copies in `values` must outlive whatever the comparator does
+    // to the collection, so nothing is collected until the sort is done
+    vm.enter_no_gc();
+    let sorted = merge_sort(&mut order, |a, b| {
+        let result = vm.call_value_from_native(cmp, &[values[a], values[b]])?;
+        match result.as_int() {
+            Some(n) => Ok(n < 0),
+            None => Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+                operation: op,
+                expected: "int from the comparator",
+                got: vm.value_type_name(result).to_string(),
+            })),
+        }
+    });
+    vm.exit_no_gc();
+    sorted?;
+
+    reorder(vm, args[0], &order, op)?;
+    Ok(Value::null())
+}
+
+/// sort_by_key(a, f) - Sort in place by `f(x)`, called once per element.
+/// Stable.
+fn native_sort_by_key(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.sort_by_key";
+    let f = args[1];
+    let values = items(vm, args[0], op)?.to_values();
+
+    // keys made by `f` aren't reachable from anything the GC scans
+    vm.enter_no_gc();
+    let order = values
+        .iter()
+        .map(|&v| vm.call_value_from_native(f, &[v]))
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(|keys| sorted_order(vm, &keys, op));
+    vm.exit_no_gc();
+
+    reorder(vm, args[0], &order?, op)?;
+    Ok(Value::null())
+}
+
+/// binary_search(a, x) - Index of an element equal to `x` in `a`, which
+/// must be sorted ascending. -1 when there is none.
+fn native_binary_search(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.binary_search";
+    let needle = args[1];
+    let items = items(vm, args[0], op)?;
+    let (mut lo, mut hi) = (0, items.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match compare(vm, items.get(mid), needle, op)? {
+            Ordering::Less => lo = mid + 1,
+            Ordering::Greater => hi = mid,
+            Ordering::Equal => return Ok(Value::int(mid as i64)),
+        }
+    }
+    Ok(Value::int(-1))
+}
+
+fn extreme(vm: &VM, value: Value, want: Ordering, op: &'static str) -> Result<Value, RuntimeError> {
+    let pick = |best: Value, v: Value| -> Result<Value, RuntimeError> {
+        Ok(if compare(vm, v, best, op)? == want {
+            v
+        } else {
+            best
+        })
+    };
+    Ok(match items(vm, value, op)? {
+        Items::Ints(s) => {
+            let found = if want == Ordering::Less {
+                s.iter().min()
+            } else {
+                s.iter().max()
+            };
+            found.map_or(Value::null(), |&n| Value::int(n))
+        }
+        Items::Floats(s) => s
+            .iter()
+            .copied()
+            .reduce(|best, f| if float_cmp(f, best) == want { f } else { best })
+            .map_or(Value::null(), Value::float),
+        items @ (Items::Bools(_) | Items::Objects(_)) => {
+            let mut best = None;
+            for v in items.to_values() {
+                best = Some(match best {
+                    Some(b) => pick(b, v)?,
+                    None => {
+                        compare(vm, v, v, op)?;
+                        v
+                    }
+                });
+            }
+            best.unwrap_or(Value::null())
+        }
+    })
+}
+
+/// min(a) - Smallest element, the first of equals. null when `a` is empty.
+fn native_min(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    extreme(vm, args[0], Ordering::Less, "array.min")
+}
+
+/// max(a) - Largest element, the first of equals. null when `a` is empty.
+fn native_max(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    extreme(vm, args[0], Ordering::Greater, "array.max")
+}
+
+/// reverse(a) - Reverse in place. A string gets string.reverse.
+fn native_reverse(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    if is_string(vm, args[0]) {
+        return string::native_reverse(vm, args);
+    }
+    match items_mut(vm, args[0], "array.reverse")? {
+        ItemsMut::Ints(s) => s.reverse(),
+        ItemsMut::Floats(s) => s.reverse(),
+        ItemsMut::Bools(s) => s.reverse(),
+        ItemsMut::Objects(s) => s.reverse(),
+    }
+    Ok(Value::null())
+}
+
+/// swap(a, i, j) - Exchange the elements at `i` and `j`.
+fn native_swap(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.swap";
+    let len = items(vm, args[0], op)?.len();
+    let i = index(vm, args[1], len, op)?;
+    let j = index(vm, args[2], len, op)?;
+    match items_mut(vm, args[0], op)? {
+        ItemsMut::Ints(s) => s.swap(i, j),
+        ItemsMut::Floats(s) => s.swap(i, j),
+        ItemsMut::Bools(s) => s.swap(i, j),
+        ItemsMut::Objects(s) => s.swap(i, j),
+    }
+    Ok(Value::null())
+}
+
+fn position(
+    vm: &VM,
+    value: Value,
+    needle: Value,
+    op: &'static str,
+) -> Result<Option<usize>, RuntimeError> {
+    let items = items(vm, value, op)?;
+    Ok(match items {
+        // numbers compare by value, as in equal(), so 2 finds 2.0 and back
+        Items::Ints(s) => match (needle.as_int(), needle.as_float()) {
+            (Some(n), _) => s.iter().position(|&e| e == n),
+            (_, Some(f)) => s.iter().position(|&e| e as f64 == f),
+            _ => None,
+        },
+        Items::Floats(s) => match (needle.as_float(), needle.as_int()) {
+            (Some(f), _) => s.iter().position(|&e| e == f),
+            (_, Some(n)) => s.iter().position(|&e| e == n as f64),
+            _ => None,
+        },
+        Items::Bools(s) => needle
+            .as_bool()
+            .and_then(|b| s.iter().position(|&e| e == b as u8)),
+        Items::Objects(s) => s.iter().position(|&e| equal(vm, e, needle)),
+    })
+}
+
+/// contains(a, x) - Whether some element == `x`. A string gets
+/// string.contains.
+fn native_contains(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    if is_string(vm, args[0]) {
+        return string::native_contains(vm, args);
+    }
+    let found = position(vm, args[0], args[1], "array.contains")?;
+    Ok(Value::bool(found.is_some()))
+}
+
+/// index_of(a, x) - Index of the first element == `x`, -1 when there is none.
+fn native_index_of(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let found = position(vm, args[0], args[1], "array.index_of")?;
+    Ok(Value::int(found.map_or(-1, |i| i as i64)))
+}
+
+/// insert(v, i, x) - Insert `x` at `i` (0..=len), shifting the rest up.
+fn native_insert(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.insert";
+    let x = args[2];
+    let vec = vec_mut(vm, args[0], op)?;
+    let (len, fit) = (vec.len(), fits(&vec.data, x));
+    // len itself is allowed, as for push
+    let i = index(vm, args[1], len + 1, op)?;
+    if !fit {
+        return Err(element_mismatch(vm, x, op));
+    }
+    match &mut vec_mut(vm, args[0], op)?.data {
+        VecData::Ints(v) => v.insert(i, x.as_int().unwrap_or_default()),
+        VecData::Floats(v) => v.insert(i, x.as_float().unwrap_or_default()),
+        VecData::Bools(v) => v.insert(i, x.as_bool().unwrap_or_default() as u8),
+        VecData::Objects(v) => v.insert(i, x),
+    }
+    Ok(Value::null())
+}
+
+/// remove(v, i) - Remove and return the element at `i`, shifting the rest
+/// down.
+fn native_remove(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.remove";
+    let len = vec_mut(vm, args[0], op)?.len();
+    let i = index(vm, args[1], len, op)?;
+    Ok(match &mut vec_mut(vm, args[0], op)?.data {
+        VecData::Ints(v) => Value::int(v.remove(i)),
+        VecData::Floats(v) => Value::float(v.remove(i)),
+        VecData::Bools(v) => Value::bool(v.remove(i) != 0),
+        VecData::Objects(v) => v.remove(i),
+    })
+}
+
+/// extend(v, other) - Append every element of the Array or Vec `other`.
+fn native_extend(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.extend";
+    let values = items(vm, args[1], op)?.to_values();
+    // all or nothing: every element is checked before the first goes in
+    let data = &vec_mut(vm, args[0], op)?.data;
+    if let Some(&v) = values.iter().find(|&&v| !fits(data, v)) {
+        return Err(element_mismatch(vm, v, op));
+    }
+    let vec = vec_mut(vm, args[0], op)?;
+    vec.reserve(values.len());
+    for v in values {
+        vec.push(v);
+    }
+    Ok(Value::null())
+}
+
+/// dedup(v) - Drop elements == the one before them, so a sorted Vec ends
+/// up with no duplicates.
+fn native_dedup(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.dedup";
+    let objects = match &mut vec_mut(vm, args[0], op)?.data {
+        VecData::Ints(v) => {
+            v.dedup();
+            return Ok(Value::null());
+        }
+        VecData::Floats(v) => {
+            v.dedup();
+            return Ok(Value::null());
+        }
+        VecData::Bools(v) => {
+            v.dedup();
+            return Ok(Value::null());
+        }
+        VecData::Objects(v) => v.clone(),
+    };
+    let mut kept: Vec<Value> = Vec::with_capacity(objects.len());
+    for v in objects {
+        if kept.last().is_none_or(|&last| !equal(vm, last, v)) {
+            kept.push(v);
+        }
+    }
+    if let VecData::Objects(v) = &mut vec_mut(vm, args[0], op)?.data {
+        *v = kept;
+    }
+    Ok(Value::null())
+}
+
+/// join(a, sep) - The elements as strings with `sep` between them. A string
+/// gets string.join.
+fn native_join(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "array.join";
+    if is_string(vm, args[0]) {
+        return string::native_join(vm, args);
+    }
+    let sep = get_string(vm, args[1], op)?;
+    let items = items(vm, args[0], op)?;
+    let parts: Vec<String> = (0..items.len())
+        .map(|i| vm.value_to_string(items.get(i)))
+        .collect();
+    let joined = parts.join(sep);
+    make_string(vm, &joined)
+}
@@ -0,0 +1,117 @@
+// crypto module - HMAC, constant-time comparison and random bytes from the
+// OS generator. sys.random is a toy, anything secret should come from here.
+
+use crate::stdlib::hash::{to_hex, unknown_algorithm};
+use crate::stdlib::helpers::{get_bytes, get_int, get_string, make_buffer};
+use crate::stdlib::{StdModuleExports, register_native};
+use crate::vm::{VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Sha512};
+
+// random_bytes is for keys and tokens, not bulk data
+const MAX_RANDOM_BYTES: i64 = 1024 * 1024;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "crypto", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("crypto::{}", $n));
+        }};
+    }
+
+    reg!("hmac", 3, native_hmac);
+    reg!("constant_time_eq", 2, native_constant_time_eq);
+    reg!("random_bytes", 1, native_random_bytes);
+    reg!("random_hex", 1, native_random_hex);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+fn crypto_error(vm: &VM, op: &'static str, msg: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected: "valid crypto operation",
+        got: msg,
+    })
+}
+
+fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // HMAC takes keys of any length
+    let mut mac =
+        <M as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// hmac(algorithm, key, data) - HMAC-SHA256 or HMAC-SHA512 as hex. Key and
+/// data are strings or byte buffers.
+fn native_hmac(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "crypto.hmac";
+    let algorithm = get_string(vm, args[0], op)?;
+    let key = get_bytes(vm, args[1], op)?;
+    let data = get_bytes(vm, args[2], op)?;
+    let tag = match algorithm {
+        "sha256" => mac::<Hmac<Sha256>>(key, data),
+        "sha512" => mac::<Hmac<Sha512>>(key, data),
+        _ => {
+            return Err(unknown_algorithm(
+                vm,
+                op,
+                "\"sha256\" or \"sha512\"",
+                args[0],
+            ));
+        }
+    };
+    let hex = to_hex(&tag);
+    Ok(Value::ptr(vm.alloc_string(&hex)?.index()))
+}
+
+/// constant_time_eq(a, b) - Compare two strings or buffers without the time
+/// taken depending on where they differ, for checking MACs and tokens.
+fn native_constant_time_eq(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "crypto.constant_time_eq";
+    let a = get_bytes(vm, args[0], op)?;
+    let b = get_bytes(vm, args[1], op)?;
+    // the length isn't secret, only the contents
+    if a.len() != b.len() {
+        return Ok(Value::bool(false));
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    Ok(Value::bool(std::hint::black_box(diff) == 0))
+}
+
+fn random(vm: &VM, n: Value, op: &'static str) -> Result<Vec<u8>, RuntimeError> {
+    let n = get_int(vm, n, op)?;
+    if !(0..=MAX_RANDOM_BYTES).contains(&n) {
+        return Err(crypto_error(
+            vm,
+            op,
+            format!("length {} outside 0..={}", n, MAX_RANDOM_BYTES),
+        ));
+    }
+    let mut bytes = vec![0u8; n as usize];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| crypto_error(vm, op, format!("OS random source failed: {}", e)))?;
+    Ok(bytes)
+}
+
+/// random_bytes(n) - `n` bytes from the OS CSPRNG in a new byte buffer.
+fn native_random_bytes(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let bytes = random(vm, args[0], "crypto.random_bytes")?;
+    Ok(make_buffer(vm, bytes))
+}
+
+/// random_hex(n) - `n` random bytes as 2n hex digits, e.g. for tokens.
+fn native_random_hex(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let bytes = random(vm, args[0], "crypto.random_hex")?;
+    let hex = to_hex(&bytes);
+    Ok(Value::ptr(vm.alloc_string(&hex)?.index()))
+}
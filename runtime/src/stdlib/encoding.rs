@@ -0,0 +1,262 @@
+// encoding module - text encodings for binary data, between strings and
+// std.bytes buffers
+//
+// Encoders take a string (its UTF-8 bytes) or a buffer and give a string,
+// decoders give a new buffer. encode_text/decode_text convert text to and from the
+// bytes of a character set.
+
+use crate::stdlib::hash::to_hex;
+use crate::stdlib::helpers::{get_bytes, get_string, make_buffer};
+use crate::stdlib::{StdModuleExports, register_native};
+use crate::vm::{VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+
+// URL-safe input is taken with or without its padding
+const URL_SAFE_LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "encoding", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("encoding::{}", $n));
+        }};
+    }
+
+    reg!("base64_encode", 1, native_base64_encode);
+    reg!("base64_decode", 1, native_base64_decode);
+    reg!("base64url_encode", 1, native_base64url_encode);
+    reg!("base64url_decode", 1, native_base64url_decode);
+    reg!("hex_encode", 1, native_hex_encode);
+    reg!("hex_decode", 1, native_hex_decode);
+    reg!("url_encode", 1, native_url_encode);
+    reg!("url_decode", 1, native_url_decode);
+
+    // character sets
+    reg!("encode_text", 2, native_encode_text);
+    reg!("decode_text", 2, native_decode_text);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+fn encoding_error(vm: &VM, op: &'static str, expected: &'static str, msg: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected,
+        got: msg,
+    })
+}
+
+fn new_string(vm: &mut VM, s: &str) -> Result<Value, RuntimeError> {
+    Ok(Value::ptr(vm.alloc_string(s)?.index()))
+}
+
+/// base64_encode(data) - Standard base64 with padding.
+fn native_base64_encode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let encoded = STANDARD.encode(get_bytes(vm, args[0], "encoding.base64_encode")?);
+    new_string(vm, &encoded)
+}
+
+/// base64_decode(s) - Standard base64 to a new buffer.
+fn native_base64_decode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "encoding.base64_decode";
+    let decoded = STANDARD
+        .decode(get_bytes(vm, args[0], op)?)
+        .map_err(|e| encoding_error(vm, op, "valid base64", e.to_string()))?;
+    Ok(make_buffer(vm, decoded))
+}
+
+/// base64url_encode(data) - URL and filename safe base64, without padding.
+fn native_base64url_encode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let encoded = URL_SAFE_NO_PAD.encode(get_bytes(vm, args[0], "encoding.base64url_encode")?);
+    new_string(vm, &encoded)
+}
+
+/// base64url_decode(s) - URL-safe base64, padded or not, to a new buffer.
+fn native_base64url_decode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "encoding.base64url_decode";
+    let decoded = URL_SAFE_LENIENT
+        .decode(get_bytes(vm, args[0], op)?)
+        .map_err(|e| encoding_error(vm, op, "valid base64url", e.to_string()))?;
+    Ok(make_buffer(vm, decoded))
+}
+
+/// hex_encode(data) - Lowercase hex, two digits per byte.
+fn native_hex_encode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let encoded = to_hex(get_bytes(vm, args[0], "encoding.hex_encode")?);
+    new_string(vm, &encoded)
+}
+
+/// hex_decode(s) - Hex digits, either case, to a new buffer.
+fn native_hex_decode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "encoding.hex_decode";
+    let decoded = decode_hex(get_bytes(vm, args[0], op)?)
+        .map_err(|msg| encoding_error(vm, op, "valid hex", msg))?;
+    Ok(make_buffer(vm, decoded))
+}
+
+fn decode_hex(text: &[u8]) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err(format!("odd number of digits ({})", text.len()));
+    }
+    let digit = |i: usize| {
+        (text[i] as char)
+            .to_digit(16)
+            .ok_or_else(|| format!("'{}' at position {}", text[i].escape_ascii(), i))
+    };
+    (0..text.len())
+        .step_by(2)
+        .map(|i| Ok((digit(i)? * 16 + digit(i + 1)?) as u8))
+        .collect()
+}
+
+/// url_encode(s) - Percent-encode everything but the RFC 3986 unreserved
+/// characters (letters, digits, `-._~`), so the result fits anywhere in a URL.
+fn native_url_encode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let data = get_bytes(vm, args[0], "encoding.url_encode")?;
+    let mut out = String::with_capacity(data.len());
+    for &b in data {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    new_string(vm, &out)
+}
+
+/// url_decode(s) - Undo percent-encoding. `+` stays a `+`.
+fn native_url_decode(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "encoding.url_decode";
+    let text = get_string(vm, args[0], op)?.as_bytes();
+    let mut out = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] == b'%' {
+            let escape = text
+                .get(i + 1..i + 3)
+                .and_then(|pair| decode_hex(pair).ok());
+            let Some(byte) = escape else {
+                return Err(encoding_error(
+                    vm,
+                    op,
+                    "valid percent-encoding",
+                    format!("bad escape at position {}", i),
+                ));
+            };
+            out.push(byte[0]);
+            i += 3;
+        } else {
+            out.push(text[i]);
+            i += 1;
+        }
+    }
+    let decoded = String::from_utf8(out).map_err(|e| {
+        encoding_error(
+            vm,
+            op,
+            "valid percent-encoding",
+            format!("invalid UTF-8 at byte {}", e.utf8_error().valid_up_to()),
+        )
+    })?;
+    new_string(vm, &decoded)
+}
+
+#[derive(Clone, Copy)]
+enum Charset {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+fn charset(vm: &VM, value: Value, op: &'static str) -> Result<Charset, RuntimeError> {
+    let name = get_string(vm, value, op)?;
+    match name.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(Charset::Utf8),
+        "utf-16le" | "utf16le" => Ok(Charset::Utf16Le),
+        "utf-16be" | "utf16be" => Ok(Charset::Utf16Be),
+        "latin-1" | "latin1" | "iso-8859-1" => Ok(Charset::Latin1),
+        _ => Err(encoding_error(
+            vm,
+            op,
+            "\"utf-8\", \"utf-16le\", \"utf-16be\" or \"latin-1\"",
+            format!("\"{}\"", name),
+        )),
+    }
+}
+
+/// encode_text(s, charset) - The bytes of `s` in a character set, as a new
+/// buffer. UTF-16 has no byte order mark.
+fn native_encode_text(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "encoding.encode_text";
+    let charset = charset(vm, args[1], op)?;
+    let s = get_string(vm, args[0], op)?;
+    let bytes = match charset {
+        Charset::Utf8 => s.as_bytes().to_vec(),
+        Charset::Utf16Le => s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        Charset::Utf16Be => s.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        Charset::Latin1 => {
+            let mut bytes = Vec::with_capacity(s.len());
+            for (i, c) in s.chars().enumerate() {
+                match u8::try_from(c) {
+                    Ok(b) => bytes.push(b),
+                    Err(_) => {
+                        return Err(encoding_error(
+                            vm,
+                            op,
+                            "text Latin-1 can represent",
+                            format!("'{}' at character {}", c, i),
+                        ));
+                    }
+                }
+            }
+            bytes
+        }
+    };
+    Ok(make_buffer(vm, bytes))
+}
+
+/// decode_text(data, charset) - Text from bytes in a character set. Malformed
+/// input is an error rather than replacement characters.
+fn native_decode_text(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "encoding.decode_text";
+    let charset = charset(vm, args[1], op)?;
+    let data = get_bytes(vm, args[0], op)?;
+    let decoded = match charset {
+        Charset::Utf8 => std::str::from_utf8(data)
+            .map(str::to_string)
+            .map_err(|e| format!("invalid UTF-8 at byte {}", e.valid_up_to())),
+        Charset::Utf16Le | Charset::Utf16Be => decode_utf16(data, charset),
+        Charset::Latin1 => Ok(data.iter().map(|&b| b as char).collect()),
+    };
+    match decoded {
+        Ok(s) => new_string(vm, &s),
+        Err(msg) => Err(encoding_error(vm, op, "valid encoded text", msg)),
+    }
+}
+
+fn decode_utf16(data: &[u8], charset: Charset) -> Result<String, String> {
+    if !data.len().is_multiple_of(2) {
+        return Err(format!("odd UTF-16 length of {} bytes", data.len()));
+    }
+    let units = data.chunks_exact(2).map(|pair| match charset {
+        Charset::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|e| format!("unpaired surrogate {:#06x}", e.unpaired_surrogate()))
+}
@@ -0,0 +1,132 @@
+// hash module - digests and checksums of strings or std.bytes buffers
+//
+// Digests come back as lowercase hex, or as a new buffer through digest().
+// None of these are for passwords, they're fast on purpose.
+
+use crate::stdlib::helpers::{get_bytes, get_string, make_buffer};
+use crate::stdlib::{StdModuleExports, register_native};
+use crate::vm::{VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use sha2::{Digest, Sha256, Sha512};
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "hash", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("hash::{}", $n));
+        }};
+    }
+
+    reg!("sha256", 1, native_sha256);
+    reg!("sha512", 1, native_sha512);
+    reg!("blake3", 1, native_blake3);
+    reg!("digest", 2, native_digest);
+
+    // checksums
+    reg!("crc32", 1, native_crc32);
+    reg!("fnv1a", 1, native_fnv1a);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+/// Digest of `data` with a named algorithm, None for an unknown name.
+pub(crate) fn digest_of(algorithm: &str, data: &[u8]) -> Option<Vec<u8>> {
+    Some(match algorithm {
+        "sha256" => Sha256::digest(data).to_vec(),
+        "sha512" => Sha512::digest(data).to_vec(),
+        "blake3" => blake3::hash(data).as_bytes().to_vec(),
+        _ => return None,
+    })
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    out
+}
+
+fn hex_digest(
+    vm: &mut VM,
+    value: Value,
+    algorithm: &str,
+    op: &'static str,
+) -> Result<Value, RuntimeError> {
+    let data = get_bytes(vm, value, op)?;
+    let hex = to_hex(&digest_of(algorithm, data).unwrap_or_default());
+    Ok(Value::ptr(vm.alloc_string(&hex)?.index()))
+}
+
+/// sha256(data) - SHA-256 of a string or buffer, as hex.
+fn native_sha256(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    hex_digest(vm, args[0], "sha256", "hash.sha256")
+}
+
+/// sha512(data) - SHA-512 of a string or buffer, as hex.
+fn native_sha512(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    hex_digest(vm, args[0], "sha512", "hash.sha512")
+}
+
+/// blake3(data) - 256-bit BLAKE3 of a string or buffer, as hex.
+fn native_blake3(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    hex_digest(vm, args[0], "blake3", "hash.blake3")
+}
+
+/// digest(algorithm, data) - Raw digest in a new byte buffer.
+fn native_digest(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "hash.digest";
+    let algorithm = get_string(vm, args[0], op)?;
+    let data = get_bytes(vm, args[1], op)?;
+    match digest_of(algorithm, data) {
+        Some(digest) => Ok(make_buffer(vm, digest)),
+        None => Err(unknown_algorithm(
+            vm,
+            op,
+            "\"sha256\", \"sha512\" or \"blake3\"",
+            args[0],
+        )),
+    }
+}
+
+pub(crate) fn unknown_algorithm(
+    vm: &VM,
+    op: &'static str,
+    expected: &'static str,
+    name: Value,
+) -> RuntimeError {
+    let got = get_string(vm, name, op).unwrap_or_default();
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected,
+        got: format!("\"{}\"", got),
+    })
+}
+
+/// crc32(data) - CRC-32 (IEEE) checksum as an int.
+fn native_crc32(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let data = get_bytes(vm, args[0], "hash.crc32")?;
+    Ok(Value::int(crc32fast::hash(data) as i64))
+}
+
+/// fnv1a(data) - 64-bit FNV-1a as 16 hex digits, the checksum native
+/// modules are pinned with. It doesn't fit an int.
+fn native_fnv1a(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let data = get_bytes(vm, args[0], "hash.fnv1a")?;
+    let mut hash = 0xcbf29ce484222325u64;
+    for &byte in data {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let hex = format!("{:016x}", hash);
+    Ok(Value::ptr(vm.alloc_string(&hex)?.index()))
+}
@@ -0,0 +1,277 @@
+// http.get, http.post and http.request.
+//
+// One request per connection (Connection: close), http:// only, redirects
+// are returned rather than followed. Options are [key, value] pairs:
+// headers, timeout (ms, for connecting and for each read or write, 30s by
+// default) and binary (the body as a std.bytes buffer instead of a string).
+
+use super::wire::{self, Head};
+use super::{get_headers, http_error, make_headers, make_object};
+use crate::stdlib::helpers::{get_bool, get_bytes, get_int, get_string, make_buffer};
+use crate::stdlib::json::pairs;
+use crate::vm::{VM, Value};
+use aelys_common::error::RuntimeError;
+use std::io::{self, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+const USER_AGENT: &str = concat!("aelys/", env!("CARGO_PKG_VERSION"));
+
+/// get(url, opts) - GET `url`. `opts` is null or [key, value] pairs.
+pub(super) fn native_get(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    send(vm, "http.get", "GET", args[0], None, args[1])
+}
+
+/// post(url, body, opts) - POST a string or byte buffer body to `url`.
+pub(super) fn native_post(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    send(vm, "http.post", "POST", args[0], Some(args[1]), args[2])
+}
+
+/// request(method, url, body, opts) - Any method, `body` null for none.
+pub(super) fn native_request(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "http.request";
+    let method = get_string(vm, args[0], op)?.to_ascii_uppercase();
+    let body = (!args[2].is_null()).then_some(args[2]);
+    send(vm, op, &method, args[1], body, args[3])
+}
+
+struct Options {
+    headers: Vec<(String, String)>,
+    timeout_ms: u64,
+    binary: bool,
+}
+
+fn options(vm: &VM, value: Value, op: &'static str) -> Result<Options, RuntimeError> {
+    let mut opts = Options {
+        headers: Vec::new(),
+        timeout_ms: DEFAULT_TIMEOUT_MS,
+        binary: false,
+    };
+    if value.is_null() {
+        return Ok(opts);
+    }
+    for (key, value) in pairs(vm, value, op)? {
+        match key.as_str() {
+            "headers" => opts.headers = get_headers(vm, value, op)?,
+            "timeout" => {
+                let ms = get_int(vm, value, op)?;
+                if ms <= 0 {
+                    return Err(http_error(vm, op, "timeout must be positive".to_string()));
+                }
+                opts.timeout_ms = ms as u64;
+            }
+            "binary" => opts.binary = get_bool(vm, value, op)?,
+            _ => return Err(http_error(vm, op, format!("unknown option '{}'", key))),
+        }
+    }
+    Ok(opts)
+}
+
+fn send(
+    vm: &mut VM,
+    op: &'static str,
+    method: &str,
+    url: Value,
+    body: Option<Value>,
+    opts: Value,
+) -> Result<Value, RuntimeError> {
+    let url = get_string(vm, url, op)?;
+    let url = Url::parse(url).map_err(|msg| http_error(vm, op, msg))?;
+    let opts = options(vm, opts, op)?;
+    if method.is_empty() || wire::check_header(method, "").is_err() {
+        return Err(http_error(vm, op, format!("invalid method '{}'", method)));
+    }
+    let body = match body {
+        Some(body) => Some(get_bytes(vm, body, op)?.to_vec()),
+        None => None,
+    };
+
+    let mut headers = Vec::new();
+    let mut default = |name: &str, value: String| {
+        if wire::header(&opts.headers, name).is_none() {
+            headers.push((name.to_string(), value));
+        }
+    };
+    default("Host", url.authority.clone());
+    default("User-Agent", USER_AGENT.to_string());
+    default("Accept", "*/*".to_string());
+    if let Some(body) = &body {
+        default("Content-Length", body.len().to_string());
+    } else if matches!(method, "POST" | "PUT" | "PATCH") {
+        default("Content-Length", "0".to_string());
+    }
+    headers.extend(opts.headers.iter().cloned());
+    headers.push(("Connection".to_string(), "close".to_string()));
+
+    let request = format!("{} {} HTTP/1.1", method, url.target);
+    let (head, body) = exchange(
+        &url,
+        &request,
+        &headers,
+        body.as_deref().unwrap_or_default(),
+        method == "HEAD",
+        opts.timeout_ms,
+    )
+    .map_err(|msg| http_error(vm, op, msg))?;
+
+    let (status, reason) = status_line(&head.start).map_err(|msg| http_error(vm, op, msg))?;
+    let reason = Value::ptr(vm.alloc_string(reason)?.index());
+    let header_values = make_headers(vm, &head.headers)?;
+    let body = if opts.binary {
+        make_buffer(vm, body)
+    } else {
+        Value::ptr(vm.alloc_string(&String::from_utf8_lossy(&body))?.index())
+    };
+    make_object(
+        vm,
+        vec![
+            ("status", Value::int(status as i64)),
+            ("reason", reason),
+            ("headers", header_values),
+            ("body", body),
+        ],
+    )
+}
+
+struct Url {
+    host: String,
+    port: u16,
+    /// host[:port] as it goes in the Host header
+    authority: String,
+    /// path and query
+    target: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Result<Url, String> {
+        let rest = match url.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => rest,
+            Some((scheme, _)) if scheme.eq_ignore_ascii_case("https") => {
+                return Err("https is not supported, only http:// URLs".to_string());
+            }
+            _ => return Err(format!("'{}' is not an http:// URL", url)),
+        };
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, target) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('?') => (&rest[..i], format!("/{}", &rest[i..])),
+            Some(i) => (&rest[..i], rest[i..].to_string()),
+            None => (rest, "/".to_string()),
+        };
+        if authority.contains('@') {
+            return Err("credentials in the URL are not supported".to_string());
+        }
+        // [v6 address] or name, then an optional :port
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(v6) => {
+                let (host, after) = v6
+                    .split_once(']')
+                    .ok_or_else(|| format!("unclosed '[' in '{}'", url))?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        if host.is_empty() {
+            return Err(format!("no host in '{}'", url));
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| format!("invalid port '{}' in '{}'", port, url))?,
+            None => 80,
+        };
+        if target.contains(|c: char| c.is_ascii_whitespace() || c.is_ascii_control()) {
+            return Err("URL has spaces or control characters in its path".to_string());
+        }
+        Ok(Url {
+            host: host.to_string(),
+            port,
+            authority: authority.to_string(),
+            target,
+        })
+    }
+}
+
+fn exchange(
+    url: &Url,
+    request: &str,
+    headers: &[(String, String)],
+    body: &[u8],
+    head_request: bool,
+    timeout_ms: u64,
+) -> Result<(Head, Vec<u8>), String> {
+    let timeout = Duration::from_millis(timeout_ms);
+    let timed_out = |e: io::Error| match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            format!("timed out after {}ms", timeout_ms)
+        }
+        _ => e.to_string(),
+    };
+    let stream = connect(url, timeout).map_err(|e| {
+        format!(
+            "cannot connect to {}:{}: {}",
+            url.host,
+            url.port,
+            timed_out(e)
+        )
+    })?;
+    stream.set_read_timeout(Some(timeout)).map_err(timed_out)?;
+    stream.set_write_timeout(Some(timeout)).map_err(timed_out)?;
+
+    wire::write_message(&mut &stream, request, headers, body, true).map_err(timed_out)?;
+
+    let timeouts = |msg: String| {
+        if msg == wire::TIMED_OUT {
+            format!("timed out after {}ms", timeout_ms)
+        } else {
+            msg
+        }
+    };
+    let mut reader = BufReader::new(&stream);
+    loop {
+        let head = wire::read_head(&mut reader)
+            .map_err(timeouts)?
+            .ok_or_else(|| "server closed the connection without a response".to_string())?;
+        let (status, _) = status_line(&head.start)?;
+        // interim responses such as 100 Continue come before the real one
+        if (100..200).contains(&status) {
+            continue;
+        }
+        let body = if head_request || status == 204 || status == 304 {
+            Vec::new()
+        } else {
+            wire::read_body(&mut reader, &head.headers, true).map_err(timeouts)?
+        };
+        return Ok((head, body));
+    }
+}
+
+fn connect(url: &Url, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(io::ErrorKind::NotFound, "host has no addresses");
+    for addr in (url.host.as_str(), url.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+// "HTTP/1.1 200 OK" -> (200, "OK")
+fn status_line(line: &str) -> Result<(u16, &str), String> {
+    let malformed = || format!("malformed status line '{}'", line);
+    let (version, rest) = line.split_once(' ').ok_or_else(malformed)?;
+    if !version.starts_with("HTTP/1.") {
+        return Err(malformed());
+    }
+    let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+    let status = code
+        .parse()
+        .ok()
+        .filter(|s| (100..1000).contains(s))
+        .ok_or_else(malformed)?;
+    Ok((status, reason))
+}
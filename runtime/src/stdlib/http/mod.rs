@@ -0,0 +1,114 @@
+// http module - HTTP/1.1 on top of plain TCP: a blocking client and a small
+// server that hands each request to an Aelys function.
+//
+// Requests and responses are objects in the std.json sense, an Array of
+// [key, value] pairs, so json.get reads their fields and a handler can write
+// its response as a literal. Headers are [name, value] pairs as well, in the
+// order they were sent; http.header looks one up ignoring case.
+
+use crate::stdlib::helpers::{get_string, make_string};
+use crate::stdlib::json::pairs;
+use crate::stdlib::{StdModuleExports, register_native};
+use crate::vm::{VM, Value};
+use aelys_bytecode::object::AelysArray;
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+
+mod client;
+mod server;
+mod wire;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "http", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("http::{}", $n));
+        }};
+    }
+
+    // client
+    reg!("get", 2, client::native_get);
+    reg!("post", 3, client::native_post);
+    reg!("request", 4, client::native_request);
+
+    // server
+    reg!("serve", 2, server::native_serve);
+    reg!("stop", 0, server::native_stop);
+
+    reg!("header", 2, native_header);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+fn http_error(vm: &VM, op: &'static str, msg: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected: "valid HTTP exchange",
+        got: msg,
+    })
+}
+
+/// Build an object from its fields.
+fn make_object(vm: &mut VM, fields: Vec<(&str, Value)>) -> Result<Value, RuntimeError> {
+    let mut entries = Vec::with_capacity(fields.len());
+    for (key, value) in fields {
+        let key = make_string(vm, key)?;
+        let pair = vm.alloc_array(AelysArray::from_objects(vec![key, value]))?;
+        entries.push(Value::ptr(pair.index()));
+    }
+    Ok(Value::ptr(
+        vm.alloc_array(AelysArray::from_objects(entries))?.index(),
+    ))
+}
+
+fn make_headers(vm: &mut VM, headers: &[(String, String)]) -> Result<Value, RuntimeError> {
+    let mut entries = Vec::with_capacity(headers.len());
+    for (name, value) in headers {
+        let name = make_string(vm, name)?;
+        let value = Value::ptr(vm.alloc_string(value)?.index());
+        let pair = vm.alloc_array(AelysArray::from_objects(vec![name, value]))?;
+        entries.push(Value::ptr(pair.index()));
+    }
+    Ok(Value::ptr(
+        vm.alloc_array(AelysArray::from_objects(entries))?.index(),
+    ))
+}
+
+/// Headers given by a script, checked so they can't break the framing.
+fn get_headers(
+    vm: &VM,
+    value: Value,
+    op: &'static str,
+) -> Result<Vec<(String, String)>, RuntimeError> {
+    let mut headers = Vec::new();
+    for (name, value) in pairs(vm, value, op)? {
+        let value = get_string(vm, value, op)?.to_string();
+        wire::check_header(&name, &value).map_err(|msg| http_error(vm, op, msg))?;
+        headers.push((name, value));
+    }
+    Ok(headers)
+}
+
+/// header(message, name) - Value of a request or response header, the name
+/// matched ignoring case. null when it isn't there.
+fn native_header(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "http.header";
+    let name = get_string(vm, args[1], op)?;
+    let headers = pairs(vm, args[0], op)?
+        .into_iter()
+        .find(|(key, _)| key == "headers")
+        .map(|(_, headers)| headers);
+    let Some(headers) = headers else {
+        return Ok(Value::null());
+    };
+    let found = pairs(vm, headers, op)?
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name));
+    Ok(found.map_or(Value::null(), |(_, value)| value))
+}
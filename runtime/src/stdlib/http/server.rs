@@ -0,0 +1,287 @@
+// http.serve and http.stop.
+//
+// Connections are handled one after another, one request each, on the
+// thread running the script. The handler gets a request object (method,
+// path, query, headers, body, remote) and returns a string body, null for
+// 204 No Content, or a response object with status, headers and body.
+
+use super::wire::{self, Head};
+use super::{get_headers, http_error, make_headers, make_object};
+use crate::stdlib::Resource;
+use crate::stdlib::helpers::get_string;
+use crate::stdlib::json::pairs;
+use crate::vm::{GcRef, ObjectKind, VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use std::io::{BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+// a client that stops sending (or reading) for this long is dropped
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// serve(addr, handler) - Listen on "host:port" and call `handler(request)`
+/// for every request until the handler calls http.stop(). An error in the
+/// handler is answered with a 500 and then ends serve with that error.
+pub(super) fn native_serve(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "http.serve";
+    let addr = get_string(vm, args[0], op)?.to_string();
+    let handler = args[1];
+    if !is_callable(vm, handler) {
+        return Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "function",
+            got: vm.value_type_name(handler).to_string(),
+        }));
+    }
+    let listener = TcpListener::bind(&addr)
+        .map_err(|e| http_error(vm, op, format!("cannot listen on {}: {}", addr, e)))?;
+
+    vm.http_stop = false;
+    for stream in listener.incoming() {
+        // a connection that failed before it was accepted isn't the script's problem
+        let Ok(stream) = stream else {
+            continue;
+        };
+        handle(vm, op, handler, stream)?;
+        if vm.http_stop {
+            break;
+        }
+    }
+    Ok(Value::null())
+}
+
+/// stop() - Make http.serve return once the current response is sent.
+pub(super) fn native_stop(vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    vm.http_stop = true;
+    Ok(Value::null())
+}
+
+fn is_callable(vm: &VM, value: Value) -> bool {
+    value
+        .as_ptr()
+        .and_then(|ptr| vm.heap().get(GcRef::new(ptr)))
+        .is_some_and(|obj| {
+            matches!(
+                obj.kind,
+                ObjectKind::Function(_) | ObjectKind::Closure(_) | ObjectKind::Native(_)
+            )
+        })
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "text/plain; charset=utf-8".to_string(),
+            )],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+}
+
+fn handle(
+    vm: &mut VM,
+    op: &'static str,
+    handler: Value,
+    stream: TcpStream,
+) -> Result<(), RuntimeError> {
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+    let remote = stream
+        .peer_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_default();
+
+    let mut reader = BufReader::new(&stream);
+    let (head, body) = match read_request(&mut reader, &stream) {
+        Ok(Some(request)) => request,
+        // the client connected and left, nothing to answer
+        Ok(None) => return Ok(()),
+        Err(msg) => {
+            respond(&stream, "GET", Response::text(400, &format!("{}\n", msg)));
+            return Ok(());
+        }
+    };
+    // checked by read_request
+    let mut start = head.start.split(' ');
+    let method = start.next().unwrap_or_default().to_string();
+    let target = start.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let fields = vec![
+        ("method", Value::ptr(vm.alloc_string(&method)?.index())),
+        ("path", Value::ptr(vm.alloc_string(path)?.index())),
+        ("query", Value::ptr(vm.alloc_string(query)?.index())),
+        ("headers", make_headers(vm, &head.headers)?),
+        (
+            "body",
+            Value::ptr(vm.alloc_string(&String::from_utf8_lossy(&body))?.index()),
+        ),
+        ("remote", Value::ptr(vm.alloc_string(&remote)?.index())),
+    ];
+    let request = make_object(vm, fields)?;
+
+    let response = vm
+        .call_value_from_native(handler, &[request])
+        .and_then(|value| response(vm, value, op));
+    match response {
+        Ok(response) => {
+            respond(&stream, &method, response);
+            Ok(())
+        }
+        Err(e) => {
+            respond(
+                &stream,
+                &method,
+                Response::text(500, "internal server error\n"),
+            );
+            Err(e)
+        }
+    }
+}
+
+fn read_request(
+    reader: &mut BufReader<&TcpStream>,
+    stream: &TcpStream,
+) -> Result<Option<(Head, Vec<u8>)>, String> {
+    let Some(head) = wire::read_head(reader)? else {
+        return Ok(None);
+    };
+    let parts: Vec<&str> = head.start.split(' ').collect();
+    let valid = match parts[..] {
+        [method, target, version] => {
+            !method.is_empty()
+                && wire::check_header(method, "").is_ok()
+                && target.starts_with('/')
+                && version.starts_with("HTTP/1.")
+        }
+        _ => false,
+    };
+    if !valid {
+        return Err(format!("malformed request line '{}'", head.start));
+    }
+    // clients that ask first (curl does for big uploads) wait for this
+    if wire::header(&head.headers, "Expect").is_some_and(|e| e.eq_ignore_ascii_case("100-continue"))
+    {
+        let mut stream = stream;
+        stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .map_err(|e| e.to_string())?;
+    }
+    let body = wire::read_body(reader, &head.headers, false)?;
+    Ok(Some((head, body)))
+}
+
+// what the handler returned, as something to send
+fn response(vm: &VM, value: Value, op: &'static str) -> Result<Response, RuntimeError> {
+    if value.is_null() {
+        return Ok(Response {
+            status: 204,
+            headers: Vec::new(),
+            body: Vec::new(),
+        });
+    }
+    if let Ok(body) = get_string(vm, value, op) {
+        return Ok(Response::text(200, body));
+    }
+    let mut response = Response {
+        status: 200,
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+    let mut content_type = "text/plain; charset=utf-8";
+    for (key, value) in pairs(vm, value, op)? {
+        match key.as_str() {
+            "status" => {
+                let status = value.as_int().and_then(|s| u16::try_from(s).ok());
+                match status.filter(|s| (100..1000).contains(s)) {
+                    Some(status) => response.status = status,
+                    None => {
+                        let got = value
+                            .as_int()
+                            .map_or(vm.value_type_name(value).to_string(), |s| s.to_string());
+                        return Err(http_error(
+                            vm,
+                            op,
+                            format!("status must be an int in 100..=999, got {}", got),
+                        ));
+                    }
+                }
+            }
+            "headers" => response.headers = get_headers(vm, value, op)?,
+            "body" => {
+                // a string is text, a byte buffer handle is raw data
+                if let Ok(text) = get_string(vm, value, op) {
+                    response.body = text.as_bytes().to_vec();
+                } else {
+                    let handle = value.as_int().filter(|&h| h >= 0);
+                    match handle.and_then(|h| vm.get_resource(h as usize)) {
+                        Some(Resource::ByteBuffer(buf)) => response.body = buf.data.clone(),
+                        _ => {
+                            return Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+                                operation: op,
+                                expected: "string or byte buffer body",
+                                got: vm.value_type_name(value).to_string(),
+                            }));
+                        }
+                    }
+                    content_type = "application/octet-stream";
+                }
+            }
+            _ => {
+                return Err(http_error(
+                    vm,
+                    op,
+                    format!("unknown response field '{}'", key),
+                ));
+            }
+        }
+    }
+    if wire::header(&response.headers, "Content-Type").is_none() && !response.body.is_empty() {
+        response
+            .headers
+            .push(("Content-Type".to_string(), content_type.to_string()));
+    }
+    Ok(response)
+}
+
+fn respond(mut stream: &TcpStream, method: &str, mut response: Response) {
+    // framing is the server's job, whatever the handler said
+    response.headers.retain(|(name, _)| {
+        !name.eq_ignore_ascii_case("Content-Length")
+            && !name.eq_ignore_ascii_case("Transfer-Encoding")
+            && !name.eq_ignore_ascii_case("Connection")
+    });
+    let no_body = response.status == 204 || response.status == 304 || response.status < 200;
+    if !no_body {
+        response.headers.push((
+            "Content-Length".to_string(),
+            response.body.len().to_string(),
+        ));
+    }
+    response
+        .headers
+        .push(("Connection".to_string(), "close".to_string()));
+    let start = format!(
+        "HTTP/1.1 {} {}",
+        response.status,
+        wire::reason(response.status)
+    );
+    let with_body = method != "HEAD" && !no_body;
+    // the client may already be gone, and that only matters to the client
+    let _ = wire::write_message(
+        &mut stream,
+        &start,
+        &response.headers,
+        &response.body,
+        with_body,
+    );
+}
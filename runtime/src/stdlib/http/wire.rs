@@ -0,0 +1,252 @@
+// HTTP/1.1 message framing shared by the client and the server. Nothing in
+// here touches the VM, errors are plain messages the callers wrap.
+
+use std::io::{self, BufRead, Read, Write};
+
+// a request or status line plus headers bigger than this is refused
+const MAX_HEAD_SIZE: u64 = 64 * 1024;
+const MAX_BODY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// What a read that hit the socket timeout fails with.
+pub(super) const TIMED_OUT: &str = "timed out";
+
+fn io_error(e: io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => TIMED_OUT.to_string(),
+        _ => e.to_string(),
+    }
+}
+
+/// Start line and headers of a message, header names as they were sent.
+pub(super) struct Head {
+    pub start: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// First value of a header, the name compared ignoring case.
+pub(super) fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Read a start line and headers. None when the peer closed the connection
+/// before sending anything.
+pub(super) fn read_head(r: &mut impl BufRead) -> Result<Option<Head>, String> {
+    let mut budget = MAX_HEAD_SIZE;
+    let Some(start) = read_line(r, &mut budget)? else {
+        return Ok(None);
+    };
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(r, &mut budget)?
+            .ok_or_else(|| "connection closed in the middle of the headers".to_string())?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("malformed header line '{}'", line))?;
+        if name.is_empty() || name.trim() != name {
+            return Err(format!("malformed header name '{}'", name));
+        }
+        headers.push((name.to_string(), value.trim().to_string()));
+    }
+    Ok(Some(Head { start, headers }))
+}
+
+// one CRLF (or bare LF) terminated line, without its terminator
+fn read_line(r: &mut impl BufRead, budget: &mut u64) -> Result<Option<String>, String> {
+    let mut buf = Vec::new();
+    let n = r
+        .by_ref()
+        .take(*budget)
+        .read_until(b'\n', &mut buf)
+        .map_err(io_error)?;
+    if n == 0 {
+        return Ok(None);
+    }
+    *budget -= n as u64;
+    if buf.pop() != Some(b'\n') {
+        return Err(if *budget == 0 {
+            format!("headers longer than {} bytes", MAX_HEAD_SIZE)
+        } else {
+            "connection closed in the middle of a line".to_string()
+        });
+    }
+    if buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|_| "header line is not valid UTF-8".to_string())
+}
+
+/// Read the body the headers announce: chunked, Content-Length, or (when
+/// `to_eof`, as for a response without either) everything until the peer
+/// closes the connection.
+pub(super) fn read_body(
+    r: &mut impl BufRead,
+    headers: &[(String, String)],
+    to_eof: bool,
+) -> Result<Vec<u8>, String> {
+    let chunked = header(headers, "Transfer-Encoding").is_some_and(|te| {
+        te.rsplit(',')
+            .next()
+            .is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked"))
+    });
+    if chunked {
+        return read_chunked(r);
+    }
+    if let Some(len) = header(headers, "Content-Length") {
+        let len: u64 = len
+            .parse()
+            .map_err(|_| format!("bad Content-Length '{}'", len))?;
+        if len > MAX_BODY_SIZE {
+            return Err(body_too_large());
+        }
+        let mut body = vec![0; len as usize];
+        r.read_exact(&mut body).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                format!("connection closed before the {} byte body ended", len)
+            }
+            _ => io_error(e),
+        })?;
+        return Ok(body);
+    }
+    let mut body = Vec::new();
+    if to_eof {
+        r.by_ref()
+            .take(MAX_BODY_SIZE + 1)
+            .read_to_end(&mut body)
+            .map_err(io_error)?;
+        if body.len() as u64 > MAX_BODY_SIZE {
+            return Err(body_too_large());
+        }
+    }
+    Ok(body)
+}
+
+fn body_too_large() -> String {
+    format!("body larger than {} bytes", MAX_BODY_SIZE)
+}
+
+fn read_chunked(r: &mut impl BufRead) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    loop {
+        let mut budget = MAX_HEAD_SIZE;
+        let line = read_line(r, &mut budget)?
+            .ok_or_else(|| "connection closed in the middle of a chunked body".to_string())?;
+        // chunk extensions after ';' are allowed and ignored
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size =
+            u64::from_str_radix(size, 16).map_err(|_| format!("bad chunk size '{}'", size))?;
+        if size == 0 {
+            break;
+        }
+        if size > MAX_BODY_SIZE - body.len() as u64 {
+            return Err(body_too_large());
+        }
+        let start = body.len();
+        body.resize(start + size as usize, 0);
+        r.read_exact(&mut body[start..])
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => {
+                    "connection closed in the middle of a chunk".to_string()
+                }
+                _ => io_error(e),
+            })?;
+        let mut crlf = Vec::new();
+        r.read_until(b'\n', &mut crlf).map_err(io_error)?;
+        if crlf.trim_ascii() != b"" {
+            return Err("chunk longer than its size".to_string());
+        }
+    }
+    // trailers, up to the blank line that ends the message
+    let mut budget = MAX_HEAD_SIZE;
+    while let Some(line) = read_line(r, &mut budget)? {
+        if line.is_empty() {
+            break;
+        }
+    }
+    Ok(body)
+}
+
+/// Write a whole message. The body is framed with Content-Length by the
+/// caller's headers; `with_body` is false for replies to HEAD.
+pub(super) fn write_message(
+    w: &mut impl Write,
+    start: &str,
+    headers: &[(String, String)],
+    body: &[u8],
+    with_body: bool,
+) -> io::Result<()> {
+    let mut head = String::with_capacity(256);
+    head.push_str(start);
+    head.push_str("\r\n");
+    for (name, value) in headers {
+        head.push_str(name);
+        head.push_str(": ");
+        head.push_str(value);
+        head.push_str("\r\n");
+    }
+    head.push_str("\r\n");
+    // one write for small messages, so they go out in one segment
+    let mut out = head.into_bytes();
+    if with_body {
+        out.extend_from_slice(body);
+    }
+    w.write_all(&out)?;
+    w.flush()
+}
+
+/// A header name or value that is safe to put on the wire as is.
+pub(super) fn check_header(name: &str, value: &str) -> Result<(), String> {
+    let token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if name.is_empty() || !name.chars().all(token) {
+        return Err(format!("invalid header name '{}'", name.escape_default()));
+    }
+    if value.contains(['\r', '\n', '\0']) {
+        return Err(format!("header '{}' has a line break in its value", name));
+    }
+    Ok(())
+}
+
+/// The standard reason phrase, or "" for codes without one.
+pub(super) fn reason(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        101 => "Switching Protocols",
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
@@ -42,13 +42,15 @@ pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
 }
 
 fn native_print(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
-    print!("{}", vm.value_to_string(args[0]));
+    let text = vm.value_to_string(args[0]);
+    vm.write_stdout(&text);
     let _ = io::stdout().flush();
     Ok(Value::null())
 }
 
 fn native_println(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
-    println!("{}", vm.value_to_string(args[0]));
+    let text = format!("{}\n", vm.value_to_string(args[0]));
+    vm.write_stdout(&text);
     Ok(Value::null())
 }
 
@@ -138,14 +140,16 @@ fn native_eflush(_vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
 }
 
 fn native_print_inline(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
-    print!("{}", vm.value_to_string(args[0]));
+    let text = vm.value_to_string(args[0]);
+    vm.write_stdout(&text);
     let _ = io::stdout().flush();
     Ok(Value::null())
 }
 
 // prompt + readline combo, python-style
 fn native_input(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
-    print!("{}", vm.value_to_string(args[0]));
+    let prompt = vm.value_to_string(args[0]);
+    vm.write_stdout(&prompt);
     let _ = io::stdout().flush();
 
     let mut line = String::new();
@@ -0,0 +1,685 @@
+// json module - JSON text to Aelys values and back
+//
+// There is no map type, so a JSON object becomes an Array of [key, value]
+// pairs and a JSON array becomes a Vec. stringify reverses that: a Vec is
+// always an array, an Array is an object when every element is a
+// [string, value] pair (an empty untyped Array is `{}`), otherwise an array.
+
+use crate::stdlib::helpers::{get_handle, get_int, get_string, make_string};
+use crate::stdlib::{Resource, StdModuleExports, register_native};
+use crate::vm::{GcRef, ObjectKind, VM, Value};
+use aelys_bytecode::object::{AelysArray, AelysVec, ArrayData};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+// deeper documents (or a Vec that contains itself) are rejected rather than
+// overflowing the native stack
+const MAX_DEPTH: usize = 512;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "json", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("json::{}", $n));
+        }};
+    }
+
+    reg!("parse", 1, native_parse);
+    reg!("stringify", 2, native_stringify);
+    reg!("read", 1, native_read);
+
+    // objects
+    reg!("get", 2, native_get);
+    reg!("has", 2, native_has);
+    reg!("keys", 1, native_keys);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+fn json_error(vm: &VM, op: &'static str, msg: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected: "valid JSON",
+        got: msg,
+    })
+}
+
+/// Parsed document, built before touching the heap so a syntax error
+/// halfway through allocates nothing.
+enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+fn native_parse(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let text = get_string(vm, args[0], "json.parse")?;
+    let mut parser = Parser::new(text.as_bytes());
+    let json = parser
+        .document()
+        .map_err(|e| json_error(vm, "json.parse", e))?;
+    to_value(vm, json)
+}
+
+// next value from a file opened with fs.open, null at the end of the file;
+// a file of several values (NDJSON) is read one call at a time
+fn native_read(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let h = get_handle(vm, args[0], "json.read")?;
+    let parsed = match vm.get_resource_mut(h) {
+        Some(Resource::File(f)) => match f.reader.as_mut() {
+            Some(reader) => Parser::new(reader).next_value(),
+            None => return Err(json_error(vm, "json.read", "not opened for reading".into())),
+        },
+        _ => return Err(json_error(vm, "json.read", "invalid handle".into())),
+    };
+    match parsed.map_err(|e| json_error(vm, "json.read", e))? {
+        Some(json) => to_value(vm, json),
+        None => Ok(Value::null()),
+    }
+}
+
+fn native_stringify(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let indent = get_int(vm, args[1], "json.stringify")?;
+    if !(0..=16).contains(&indent) {
+        return Err(json_error(
+            vm,
+            "json.stringify",
+            format!("indent {} (must be 0 to 16)", indent),
+        ));
+    }
+    let mut writer = Writer {
+        vm,
+        indent: indent as usize,
+        out: String::new(),
+    };
+    writer
+        .value(args[0], 0)
+        .map_err(|e| json_error(vm, "json.stringify", e))?;
+    let out = writer.out;
+    let s = vm.alloc_string(&out)?;
+    Ok(Value::ptr(s.index()))
+}
+
+fn native_get(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let key = get_string(vm, args[1], "json.get")?;
+    let found = pairs(vm, args[0], "json.get")?
+        .into_iter()
+        .find(|(k, _)| k.as_str() == key);
+    Ok(found.map_or(Value::null(), |(_, v)| v))
+}
+
+fn native_has(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let key = get_string(vm, args[1], "json.has")?;
+    let found = pairs(vm, args[0], "json.has")?
+        .iter()
+        .any(|(k, _)| k.as_str() == key);
+    Ok(Value::bool(found))
+}
+
+fn native_keys(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let keys: Vec<String> = pairs(vm, args[0], "json.keys")?
+        .into_iter()
+        .map(|(k, _)| k)
+        .collect();
+    let mut values = Vec::with_capacity(keys.len());
+    for key in &keys {
+        values.push(make_string(vm, key)?);
+    }
+    let v = vm.alloc_vec(AelysVec::from_objects(values))?;
+    Ok(Value::ptr(v.index()))
+}
+
+fn to_value(vm: &mut VM, json: Json) -> Result<Value, RuntimeError> {
+    Ok(match json {
+        Json::Null => Value::null(),
+        Json::Bool(b) => Value::bool(b),
+        Json::Int(n) => Value::int(n),
+        Json::Float(f) => Value::float(f),
+        Json::String(s) => Value::ptr(vm.alloc_string(&s)?.index()),
+        Json::Array(items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                values.push(to_value(vm, item)?);
+            }
+            Value::ptr(vm.alloc_vec(AelysVec::from_objects(values))?.index())
+        }
+        Json::Object(fields) => {
+            let mut entries = Vec::with_capacity(fields.len());
+            for (key, value) in fields {
+                let key = make_string(vm, &key)?;
+                let value = to_value(vm, value)?;
+                let pair = vm.alloc_array(AelysArray::from_objects(vec![key, value]))?;
+                entries.push(Value::ptr(pair.index()));
+            }
+            Value::ptr(vm.alloc_array(AelysArray::from_objects(entries))?.index())
+        }
+    })
+}
+
+fn string_of(vm: &VM, value: Value) -> Option<&str> {
+    let obj = vm.heap().get(GcRef::new(value.as_ptr()?))?;
+    match &obj.kind {
+        ObjectKind::String(s) => Some(s.as_str()),
+        _ => None,
+    }
+}
+
+// the [key, value] pairs of an object, None when the Array isn't one
+fn object_pairs(vm: &VM, elements: &[Value]) -> Option<Vec<(String, Value)>> {
+    elements
+        .iter()
+        .map(|&element| {
+            let obj = vm.heap().get(GcRef::new(element.as_ptr()?))?;
+            let ObjectKind::Array(pair) = &obj.kind else {
+                return None;
+            };
+            match pair.data.as_objects()? {
+                [key, value] => Some((string_of(vm, *key)?.to_string(), *value)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The pairs of an object-shaped Array, also how other modules take options.
+pub(crate) fn pairs(
+    vm: &VM,
+    value: Value,
+    op: &'static str,
+) -> Result<Vec<(String, Value)>, RuntimeError> {
+    let found = value
+        .as_ptr()
+        .and_then(|ptr| vm.heap().get(GcRef::new(ptr)))
+        .and_then(|obj| match &obj.kind {
+            ObjectKind::Array(array) => object_pairs(vm, array.data.as_objects()?),
+            _ => None,
+        });
+    found.ok_or_else(|| {
+        vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "object (Array of [key, value] pairs)",
+            got: vm.value_type_name(value).to_string(),
+        })
+    })
+}
+
+struct Writer<'a> {
+    vm: &'a VM,
+    indent: usize,
+    out: String,
+}
+
+impl Writer<'_> {
+    fn value(&mut self, value: Value, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("nesting deeper than {} (a cycle?)", MAX_DEPTH));
+        }
+        if value.is_null() {
+            self.out.push_str("null");
+        } else if let Some(b) = value.as_bool() {
+            self.out.push_str(if b { "true" } else { "false" });
+        } else if let Some(n) = value.as_int() {
+            self.out.push_str(&n.to_string());
+        } else if let Some(f) = value.as_float() {
+            self.float(f)?;
+        } else {
+            self.object(value, depth)?;
+        }
+        Ok(())
+    }
+
+    fn float(&mut self, f: f64) -> Result<(), String> {
+        if !f.is_finite() {
+            return Err(format!("{} has no JSON representation", f));
+        }
+        // keep the `.0` so the number reads back as a float
+        if f.fract() == 0.0 && f.abs() < 1e16 {
+            self.out.push_str(&format!("{:.1}", f));
+        } else {
+            self.out.push_str(&f.to_string());
+        }
+        Ok(())
+    }
+
+    fn object(&mut self, value: Value, depth: usize) -> Result<(), String> {
+        let vm = self.vm;
+        let obj = value
+            .as_ptr()
+            .and_then(|ptr| vm.heap().get(GcRef::new(ptr)))
+            .ok_or_else(|| format!("{} has no JSON representation", vm.value_type_name(value)))?;
+        match &obj.kind {
+            ObjectKind::String(s) => {
+                escape(s.as_str(), &mut self.out);
+                Ok(())
+            }
+            ObjectKind::Vec(v) => {
+                let items: Vec<Value> = (0..v.len()).filter_map(|i| v.get(i)).collect();
+                self.array(&items, depth)
+            }
+            ObjectKind::Array(a) => {
+                if let ArrayData::Objects(elements) = &a.data
+                    && let Some(mut fields) = object_pairs(vm, elements)
+                {
+                    fields.sort_by(|a, b| a.0.cmp(&b.0));
+                    return self.fields(&fields, depth);
+                }
+                let items: Vec<Value> = (0..a.len()).filter_map(|i| a.get(i)).collect();
+                self.array(&items, depth)
+            }
+            _ => Err(format!(
+                "{} has no JSON representation",
+                vm.value_type_name(value)
+            )),
+        }
+    }
+
+    fn array(&mut self, items: &[Value], depth: usize) -> Result<(), String> {
+        if items.is_empty() {
+            self.out.push_str("[]");
+            return Ok(());
+        }
+        self.out.push('[');
+        for (i, &item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(depth + 1);
+            self.value(item, depth + 1)?;
+        }
+        self.newline(depth);
+        self.out.push(']');
+        Ok(())
+    }
+
+    fn fields(&mut self, fields: &[(String, Value)], depth: usize) -> Result<(), String> {
+        if fields.is_empty() {
+            self.out.push_str("{}");
+            return Ok(());
+        }
+        self.out.push('{');
+        for (i, (key, value)) in fields.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(depth + 1);
+            escape(key, &mut self.out);
+            self.out.push(':');
+            if self.indent > 0 {
+                self.out.push(' ');
+            }
+            self.value(*value, depth + 1)?;
+        }
+        self.newline(depth);
+        self.out.push('}');
+        Ok(())
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if self.indent > 0 {
+            self.out.push('\n');
+            self.out.push_str(&" ".repeat(self.indent * depth));
+        }
+    }
+}
+
+fn escape(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Byte source for the parser: a string, or a file read as it goes.
+trait Input {
+    fn peek(&mut self) -> Result<Option<u8>, String>;
+    fn bump(&mut self);
+}
+
+impl Input for &[u8] {
+    fn peek(&mut self) -> Result<Option<u8>, String> {
+        Ok(self.first().copied())
+    }
+    fn bump(&mut self) {
+        *self = &self[1..];
+    }
+}
+
+impl<R: BufRead> Input for &mut R {
+    fn peek(&mut self) -> Result<Option<u8>, String> {
+        let buf = self.fill_buf().map_err(|e| format!("read: {}", e))?;
+        Ok(buf.first().copied())
+    }
+    fn bump(&mut self) {
+        self.consume(1);
+    }
+}
+
+struct Parser<I> {
+    input: I,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+
+impl<I: Input> Parser<I> {
+    fn new(input: I) -> Self {
+        Self {
+            input,
+            line: 1,
+            column: 1,
+            depth: 0,
+        }
+    }
+
+    /// A whole text holding exactly one value.
+    fn document(&mut self) -> Result<Json, String> {
+        let json = self
+            .next_value()?
+            .ok_or_else(|| self.error("empty input"))?;
+        self.whitespace()?;
+        match self.input.peek()? {
+            None => Ok(json),
+            Some(_) => Err(self.error("trailing characters after the value")),
+        }
+    }
+
+    /// The next value, None if only whitespace is left.
+    fn next_value(&mut self) -> Result<Option<Json>, String> {
+        self.whitespace()?;
+        if self.input.peek()?.is_none() {
+            return Ok(None);
+        }
+        self.value().map(Some)
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("{} at line {}, column {}", msg, self.line, self.column)
+    }
+
+    fn unexpected(&mut self, wanted: &str) -> String {
+        match self.input.peek() {
+            Ok(Some(b)) if b.is_ascii_graphic() => {
+                self.error(&format!("expected {}, found '{}'", wanted, b as char))
+            }
+            Ok(Some(_)) => self.error(&format!("expected {}, found unexpected character", wanted)),
+            Ok(None) => self.error(&format!("expected {}, found end of input", wanted)),
+            Err(e) => e,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<u8>, String> {
+        let b = self.input.peek()?;
+        if let Some(b) = b {
+            self.input.bump();
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if b & 0xC0 != 0x80 {
+                // columns count characters, not UTF-8 continuation bytes
+                self.column += 1;
+            }
+        }
+        Ok(b)
+    }
+
+    fn eat(&mut self, b: u8) -> Result<bool, String> {
+        if self.input.peek()? == Some(b) {
+            self.next()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn expect(&mut self, b: u8, wanted: &str) -> Result<(), String> {
+        if self.eat(b)? {
+            Ok(())
+        } else {
+            Err(self.unexpected(wanted))
+        }
+    }
+
+    fn whitespace(&mut self) -> Result<(), String> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.peek()? {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    fn keyword(&mut self, word: &str, json: Json) -> Result<Json, String> {
+        for &b in word.as_bytes() {
+            if !self.eat(b)? {
+                return Err(self.unexpected(&format!("'{}'", word)));
+            }
+        }
+        Ok(json)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.input.peek()? {
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(&format!("nesting deeper than {}", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let json = parse(self);
+        self.depth -= 1;
+        json
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.next()?;
+        let mut items = Vec::new();
+        self.whitespace()?;
+        if self.eat(b']')? {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            self.whitespace()?;
+            items.push(self.value()?);
+            self.whitespace()?;
+            if self.eat(b']')? {
+                return Ok(Json::Array(items));
+            }
+            self.expect(b',', "',' or ']'")?;
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.next()?;
+        let mut fields: Vec<(String, Json)> = Vec::new();
+        // a repeated key keeps its first position and its last value
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        self.whitespace()?;
+        if self.eat(b'}')? {
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.whitespace()?;
+            if self.input.peek()? != Some(b'"') {
+                return Err(self.unexpected("a string key"));
+            }
+            let key = self.string()?;
+            self.whitespace()?;
+            self.expect(b':', "':' after the key")?;
+            self.whitespace()?;
+            let value = self.value()?;
+            match seen.get(&key) {
+                Some(&i) => fields[i].1 = value,
+                None => {
+                    seen.insert(key.clone(), fields.len());
+                    fields.push((key, value));
+                }
+            }
+            self.whitespace()?;
+            if self.eat(b'}')? {
+                return Ok(Json::Object(fields));
+            }
+            self.expect(b',', "',' or '}'")?;
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.next()?;
+        let mut bytes = Vec::new();
+        loop {
+            match self.next()? {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let c = self.escape()?;
+                    let mut buf = [0u8; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(b) if b < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                Some(b) => bytes.push(b),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        Ok(match self.next()? {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{08}',
+            Some(b'f') => '\u{0c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let high = self.hex4()?;
+                if !(0xD800..0xDC00).contains(&high) {
+                    return char::from_u32(high).ok_or_else(|| self.error("lone surrogate"));
+                }
+                // the other half of a surrogate pair has to follow
+                if !(self.eat(b'\\')? && self.eat(b'u')?) {
+                    return Err(self.error("lone surrogate"));
+                }
+                let low = self.hex4()?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    return Err(self.error("lone surrogate"));
+                }
+                let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                char::from_u32(c).ok_or_else(|| self.error("invalid \\u escape"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut n = 0;
+        for _ in 0..4 {
+            let digit = self
+                .input
+                .peek()?
+                .and_then(|b| (b as char).to_digit(16))
+                .ok_or_else(|| self.error("invalid \\u escape"))?;
+            self.next()?;
+            n = n * 16 + digit;
+        }
+        Ok(n)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let (line, column) = (self.line, self.column);
+        let mut text = String::new();
+        let mut float = false;
+        if self.eat(b'-')? {
+            text.push('-');
+        }
+        match self.input.peek()? {
+            Some(b'0') => {
+                self.next()?;
+                text.push('0');
+            }
+            Some(b'1'..=b'9') => self.digits(&mut text)?,
+            _ => return Err(self.unexpected("a digit")),
+        }
+        if self.eat(b'.')? {
+            float = true;
+            text.push('.');
+            if !matches!(self.input.peek()?, Some(b'0'..=b'9')) {
+                return Err(self.unexpected("a digit after '.'"));
+            }
+            self.digits(&mut text)?;
+        }
+        if let Some(e @ (b'e' | b'E')) = self.input.peek()? {
+            self.next()?;
+            float = true;
+            text.push(e as char);
+            if let Some(sign @ (b'+' | b'-')) = self.input.peek()? {
+                self.next()?;
+                text.push(sign as char);
+            }
+            if !matches!(self.input.peek()?, Some(b'0'..=b'9')) {
+                return Err(self.unexpected("a digit in the exponent"));
+            }
+            self.digits(&mut text)?;
+        }
+
+        // integers outside the 48-bit int range become floats
+        if !float
+            && let Ok(n) = text.parse::<i64>()
+            && (Value::INT_MIN..=Value::INT_MAX).contains(&n)
+        {
+            return Ok(Json::Int(n));
+        }
+        match text.parse::<f64>() {
+            Ok(f) if f.is_finite() => Ok(Json::Float(f)),
+            // 1e400 parses to infinity, which stringify would refuse
+            Ok(_) => Err(format!(
+                "number out of range at line {}, column {}",
+                line, column
+            )),
+            Err(_) => Err(format!(
+                "invalid number at line {}, column {}",
+                line, column
+            )),
+        }
+    }
+
+    fn digits(&mut self, text: &mut String) -> Result<(), String> {
+        while let Some(b @ b'0'..=b'9') = self.input.peek()? {
+            self.next()?;
+            text.push(b as char);
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 //! std.math - Mathematical functions and constants
 
 use crate::stdlib::helpers::get_number;
-use crate::stdlib::{StdModuleExports, register_native};
+use crate::stdlib::{StdModuleExports, random, register_native};
 use crate::vm::{VM, Value};
 use aelys_common::error::{RuntimeError, RuntimeErrorKind};
 use rand::Rng;
@@ -343,8 +343,8 @@ fn native_is_finite(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError>
     }
 }
 
-/// randint(debut, fin) - Random integer in range [debut, fin] (both inclusive)
-// TODO: this deserves its own std module
+/// randint(debut, fin) - Random integer in range [debut, fin] (both inclusive),
+/// from the default generator of std.random
 fn native_randint(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
     let debut = args[0].as_int().ok_or_else(|| {
         vm.runtime_error(RuntimeErrorKind::TypeError {
@@ -369,7 +369,6 @@ fn native_randint(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
         ))));
     }
 
-    let mut rng = rand::thread_rng();
-    let result = rng.gen_range(debut..=fin);
+    let result = random::default_rng(vm).gen_range(debut..=fin);
     Ok(Value::int(result))
 }
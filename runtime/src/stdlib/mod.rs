@@ -1,11 +1,20 @@
+pub mod array;
 pub mod bytes;
 pub mod convert;
+pub mod crypto;
+pub mod encoding;
 pub mod fs;
+pub mod hash;
+pub mod http;
 pub mod io;
+pub mod json;
 pub mod math;
 pub mod net;
+pub mod random;
+pub mod regex;
 pub mod string;
 pub mod sys;
+pub mod thread;
 pub mod time;
 
 use crate::vm::{GcRef, ObjectKind, VM, Value};
@@ -13,6 +22,10 @@ use aelys_common::error::{RuntimeError, RuntimeErrorKind};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::process::{Child, ChildStdin};
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+use std::thread::JoinHandle;
 use std::time::Instant;
 
 #[derive(Debug)]
@@ -23,6 +36,11 @@ pub enum Resource {
     UdpSocket(UdpSocketResource),
     Timer(Instant),
     ByteBuffer(ByteBuffer),
+    Regex(::regex::Regex),
+    Process(ProcessResource),
+    Rng(Box<rand_chacha::ChaCha8Rng>),
+    Thread(ThreadResource),
+    Channel(ChannelResource),
 }
 
 #[derive(Debug)]
@@ -53,6 +71,33 @@ pub struct UdpSocketResource {
     pub timeout_ms: Option<u64>,
 }
 
+/// A child started by sys.spawn. Its stdout and stderr are drained line by
+/// line on their own threads, so a chatty child never blocks on a full pipe.
+#[derive(Debug)]
+pub struct ProcessResource {
+    pub child: Child,
+    pub stdin: Option<ChildStdin>,
+    pub stdout: Option<Receiver<String>>,
+    pub stderr: Option<Receiver<String>>,
+    pub timeout_ms: Option<u64>,
+    /// Exit code once the child has been waited for.
+    pub exit_code: Option<i64>,
+}
+
+/// One VM's reference to a thread.channel; the channel is dropped with the
+/// last of them.
+#[derive(Debug)]
+pub struct ChannelResource {
+    pub(crate) channel: Arc<thread::Channel>,
+}
+
+/// A thread started by thread.spawn, with what its function returned (or
+/// why it failed) once it ends.
+#[derive(Debug)]
+pub struct ThreadResource {
+    pub(crate) handle: JoinHandle<Result<thread::Message, String>>,
+}
+
 #[derive(Debug)]
 pub struct ByteBuffer {
     pub data: Vec<u8>,
@@ -68,6 +113,14 @@ pub const STD_MODULES: &[&str] = &[
     "std.convert",
     "std.sys",
     "std.bytes",
+    "std.json",
+    "std.regex",
+    "std.hash",
+    "std.crypto",
+    "std.encoding",
+    "std.http",
+    "std.random",
+    "std.thread",
 ];
 
 pub fn is_std_module(path: &[String]) -> bool {
@@ -124,6 +177,21 @@ pub fn register_std_module(
         "convert" => convert::register(vm),
         "sys" => sys::register(vm),
         "bytes" => bytes::register(vm),
+        "json" => json::register(vm),
+        "regex" => regex::register(vm),
+        "hash" => hash::register(vm),
+        "crypto" => crypto::register(vm),
+        "encoding" => encoding::register(vm),
+        "http" => {
+            if !vm.capabilities().allow_net {
+                return Err(vm.runtime_error(RuntimeErrorKind::CapabilityDenied {
+                    operation: "std.http",
+                }));
+            }
+            http::register(vm)
+        }
+        "random" => random::register(vm),
+        "thread" => thread::register(vm),
         _ => Err(
             vm.runtime_error(RuntimeErrorKind::UndefinedVariable(format!(
                 "std.{}",
@@ -219,6 +287,41 @@ pub mod helpers {
         Ok(handle as usize)
     }
 
+    /// Data given as a string (its UTF-8 bytes) or a `std.bytes` buffer handle.
+    pub fn get_bytes<'a>(
+        vm: &'a VM,
+        value: Value,
+        op: &'static str,
+    ) -> Result<&'a [u8], RuntimeError> {
+        if value.as_int().is_some() {
+            let handle = get_handle(vm, value, op)?;
+            return match vm.get_resource(handle) {
+                Some(Resource::ByteBuffer(buf)) => Ok(&buf.data),
+                _ => Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+                    operation: op,
+                    expected: "byte buffer handle",
+                    got: format!("invalid handle {}", handle),
+                })),
+            };
+        }
+        if let Some(ptr) = value.as_ptr()
+            && let Some(obj) = vm.heap().get(GcRef::new(ptr))
+            && let ObjectKind::String(s) = &obj.kind
+        {
+            return Ok(s.as_bytes());
+        }
+        Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "string or byte buffer",
+            got: vm.value_type_name(value).to_string(),
+        }))
+    }
+
+    /// A new `std.bytes` buffer holding `data`, returned as its handle.
+    pub fn make_buffer(vm: &mut VM, data: Vec<u8>) -> Value {
+        Value::int(vm.store_resource(Resource::ByteBuffer(ByteBuffer { data })) as i64)
+    }
+
     pub fn make_int_checked(vm: &VM, n: i64, op: &'static str) -> Result<Value, RuntimeError> {
         Value::int_checked(n).map_err(|_| {
             vm.runtime_error(RuntimeErrorKind::TypeError {
@@ -0,0 +1,212 @@
+// random module - seedable generators. A generator is a handle from new(),
+// or null for the VM's default one, which sys.random, sys.random_int and
+// math.randint draw from as well. The default is seeded from the OS unless
+// the VM was started with `-ae.seed=N`.
+//
+// Generators are ChaCha8, so a seed gives the same sequence on every
+// platform and every run. None of this is fit for secrets: std.crypto is.
+
+use crate::stdlib::array::{items, reorder};
+use crate::stdlib::helpers::{get_handle, get_int, get_number, make_int_checked};
+use crate::stdlib::{Resource, StdModuleExports, register_native};
+use crate::vm::{VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "random", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("random::{}", $n));
+        }};
+    }
+
+    reg!("new", 1, native_new);
+    reg!("free", 1, native_free);
+
+    // distributions
+    reg!("float", 1, native_float);
+    reg!("int", 3, native_int);
+    reg!("uniform", 3, native_uniform);
+    reg!("normal", 3, native_normal);
+    reg!("exponential", 2, native_exponential);
+
+    // collections
+    reg!("shuffle", 2, native_shuffle);
+    reg!("choice", 2, native_choice);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+/// The VM's default generator, made on first use.
+pub(crate) fn default_rng(vm: &mut VM) -> &mut ChaCha8Rng {
+    let seed = vm.config.seed;
+    vm.default_rng
+        .get_or_insert_with(|| ChaCha8Rng::seed_from_u64(seed.unwrap_or_else(rand::random)))
+}
+
+fn random_error(vm: &VM, op: &'static str, expected: &'static str, got: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected,
+        got,
+    })
+}
+
+/// The generator behind a handle, or the default one for null.
+fn rng<'a>(
+    vm: &'a mut VM,
+    value: Value,
+    op: &'static str,
+) -> Result<&'a mut ChaCha8Rng, RuntimeError> {
+    if value.is_null() {
+        return Ok(default_rng(vm));
+    }
+    let handle = get_handle(vm, value, op)?;
+    if !matches!(vm.get_resource(handle), Some(Resource::Rng(_))) {
+        return Err(random_error(
+            vm,
+            op,
+            "generator handle or null",
+            format!("handle {}", handle),
+        ));
+    }
+    match vm.get_resource_mut(handle) {
+        Some(Resource::Rng(rng)) => Ok(rng),
+        _ => unreachable!(),
+    }
+}
+
+fn finite(vm: &VM, value: Value, op: &'static str) -> Result<f64, RuntimeError> {
+    let n = get_number(vm, value, op)?;
+    if !n.is_finite() {
+        return Err(random_error(vm, op, "finite number", n.to_string()));
+    }
+    Ok(n)
+}
+
+/// new(seed) - A generator seeded with the int `seed`, or from the OS when
+/// `seed` is null. Returns a handle.
+fn native_new(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let rng = if args[0].is_null() {
+        ChaCha8Rng::seed_from_u64(rand::random())
+    } else {
+        ChaCha8Rng::seed_from_u64(get_int(vm, args[0], "random.new")? as u64)
+    };
+    let handle = vm.store_resource(Resource::Rng(Box::new(rng)));
+    Ok(Value::int(handle as i64))
+}
+
+/// free(g) - Release a generator. `free(null)` is a no-op.
+fn native_free(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    if args[0].is_null() {
+        return Ok(Value::null());
+    }
+    let handle = get_handle(vm, args[0], "random.free")?;
+    match vm.get_resource(handle) {
+        Some(Resource::Rng(_)) => {
+            vm.take_resource(handle);
+            Ok(Value::null())
+        }
+        _ => Err(random_error(
+            vm,
+            "random.free",
+            "generator handle",
+            format!("handle {}", handle),
+        )),
+    }
+}
+
+/// float(g) - Float in [0, 1).
+fn native_float(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let x: f64 = rng(vm, args[0], "random.float")?.r#gen();
+    Ok(Value::float(x))
+}
+
+/// int(g, lo, hi) - Int in [lo, hi], both ends included.
+fn native_int(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "random.int";
+    let lo = get_int(vm, args[1], op)?;
+    let hi = get_int(vm, args[2], op)?;
+    if lo > hi {
+        return Err(random_error(vm, op, "lo <= hi", format!("{} > {}", lo, hi)));
+    }
+    let n = rng(vm, args[0], op)?.gen_range(lo..=hi);
+    make_int_checked(vm, n, op)
+}
+
+/// uniform(g, lo, hi) - Float in [lo, hi). `lo` itself when the two are
+/// equal.
+fn native_uniform(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "random.uniform";
+    let lo = finite(vm, args[1], op)?;
+    let hi = finite(vm, args[2], op)?;
+    if lo > hi {
+        return Err(random_error(vm, op, "lo <= hi", format!("{} > {}", lo, hi)));
+    }
+    if lo == hi {
+        return Ok(Value::float(lo));
+    }
+    let x = rng(vm, args[0], op)?.gen_range(lo..hi);
+    Ok(Value::float(x))
+}
+
+/// normal(g, mean, sd) - Float from the normal distribution with standard
+/// deviation `sd`.
+fn native_normal(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "random.normal";
+    let mean = finite(vm, args[1], op)?;
+    let sd = finite(vm, args[2], op)?;
+    if sd < 0.0 {
+        return Err(random_error(vm, op, "sd >= 0", sd.to_string()));
+    }
+    // Box-Muller; 1 - u keeps the log away from 0
+    let rng = rng(vm, args[0], op)?;
+    let u1 = 1.0 - rng.r#gen::<f64>();
+    let u2: f64 = rng.r#gen();
+    let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+    Ok(Value::float(mean + sd * z))
+}
+
+/// exponential(g, rate) - Float from the exponential distribution with
+/// mean 1 / rate.
+fn native_exponential(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "random.exponential";
+    let rate = finite(vm, args[1], op)?;
+    if rate <= 0.0 {
+        return Err(random_error(vm, op, "rate > 0", rate.to_string()));
+    }
+    let u = 1.0 - rng(vm, args[0], op)?.r#gen::<f64>();
+    Ok(Value::float(-u.ln() / rate))
+}
+
+/// shuffle(g, a) - Shuffle an Array or Vec in place.
+fn native_shuffle(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "random.shuffle";
+    let len = items(vm, args[1], op)?.len();
+    let mut order: Vec<usize> = (0..len).collect();
+    order.shuffle(rng(vm, args[0], op)?);
+    reorder(vm, args[1], &order, op)?;
+    Ok(Value::null())
+}
+
+/// choice(g, a) - A random element of an Array or Vec, null when it's
+/// empty.
+fn native_choice(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "random.choice";
+    let len = items(vm, args[1], op)?.len();
+    if len == 0 {
+        return Ok(Value::null());
+    }
+    let i = rng(vm, args[0], op)?.gen_range(0..len);
+    Ok(items(vm, args[1], op)?.get(i))
+}
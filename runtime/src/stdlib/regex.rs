@@ -0,0 +1,237 @@
+// regex module - patterns on top of the `regex` crate, whose matching is
+// linear in the input, so a pattern can't blow up on a hostile string.
+//
+// Every function takes either a handle from compile() or the pattern itself.
+// Patterns passed as strings go through a per-VM cache, so a literal inside
+// a loop is compiled once, not on every iteration.
+
+use crate::stdlib::helpers::{get_handle, get_int, get_string};
+use crate::stdlib::{Resource, StdModuleExports, register_native};
+use crate::vm::{VM, Value};
+use aelys_bytecode::object::AelysVec;
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use regex::Regex;
+
+// past this many distinct patterns the cache starts over, a program building
+// patterns from data shouldn't grow it forever
+const CACHE_CAPACITY: usize = 256;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "regex", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("regex::{}", $n));
+        }};
+    }
+
+    reg!("compile", 1, native_compile);
+    reg!("free", 1, native_free);
+    reg!("escape", 1, native_escape);
+
+    reg!("is_match", 2, native_is_match);
+    reg!("find", 2, native_find);
+    reg!("find_all", 2, native_find_all);
+    reg!("captures", 2, native_captures);
+    reg!("group", 3, native_group);
+    reg!("replace", 3, native_replace);
+    reg!("replace_all", 3, native_replace_all);
+    reg!("split", 2, native_split);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+fn regex_error(vm: &VM, op: &'static str, msg: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected: "valid regex",
+        got: msg,
+    })
+}
+
+fn compile_cached(vm: &mut VM, pattern: &str, op: &'static str) -> Result<Regex, RuntimeError> {
+    if let Some(re) = vm.regex_cache.get(pattern) {
+        // cloning shares the compiled program
+        return Ok(re.clone());
+    }
+    let re = Regex::new(pattern).map_err(|e| regex_error(vm, op, e.to_string()))?;
+    if vm.regex_cache.len() >= CACHE_CAPACITY {
+        vm.regex_cache.clear();
+    }
+    vm.regex_cache.insert(pattern.to_string(), re.clone());
+    Ok(re)
+}
+
+/// The regex behind a handle or a pattern string.
+fn get_regex(vm: &mut VM, value: Value, op: &'static str) -> Result<Regex, RuntimeError> {
+    if value.as_int().is_some() {
+        let handle = get_handle(vm, value, op)?;
+        return match vm.get_resource(handle) {
+            Some(Resource::Regex(re)) => Ok(re.clone()),
+            _ => Err(regex_error(vm, op, "invalid regex handle".to_string())),
+        };
+    }
+    let pattern = get_string(vm, value, op)?.to_string();
+    compile_cached(vm, &pattern, op)
+}
+
+fn new_string(vm: &mut VM, s: &str) -> Result<Value, RuntimeError> {
+    Ok(Value::ptr(vm.alloc_string(s)?.index()))
+}
+
+fn string_vec<'a>(
+    vm: &mut VM,
+    items: impl IntoIterator<Item = Option<&'a str>>,
+) -> Result<Value, RuntimeError> {
+    let mut values = Vec::new();
+    for item in items {
+        values.push(match item {
+            Some(s) => new_string(vm, s)?,
+            None => Value::null(),
+        });
+    }
+    let v = vm.alloc_vec(AelysVec::from_objects(values))?;
+    Ok(Value::ptr(v.index()))
+}
+
+// the subject string is copied out since the heap can't stay borrowed while
+// results are allocated
+fn text(vm: &VM, value: Value, op: &'static str) -> Result<String, RuntimeError> {
+    Ok(get_string(vm, value, op)?.to_string())
+}
+
+/// compile(pattern) - Compile a pattern, returns a handle.
+fn native_compile(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let pattern = text(vm, args[0], "regex.compile")?;
+    let re = compile_cached(vm, &pattern, "regex.compile")?;
+    let handle = vm.store_resource(Resource::Regex(re));
+    Ok(Value::int(handle as i64))
+}
+
+/// free(handle) - Release a compiled pattern. `free(null)` is a no-op.
+fn native_free(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    if args[0].is_null() {
+        return Ok(Value::null());
+    }
+    let handle = get_handle(vm, args[0], "regex.free")?;
+    match vm.get_resource(handle) {
+        Some(Resource::Regex(_)) => {
+            vm.take_resource(handle);
+            Ok(Value::null())
+        }
+        _ => Err(regex_error(
+            vm,
+            "regex.free",
+            "invalid regex handle".to_string(),
+        )),
+    }
+}
+
+/// escape(s) - Pattern matching `s` literally.
+fn native_escape(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let s = get_string(vm, args[0], "regex.escape")?;
+    let escaped = regex::escape(s);
+    new_string(vm, &escaped)
+}
+
+fn native_is_match(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.is_match")?;
+    let s = get_string(vm, args[1], "regex.is_match")?;
+    Ok(Value::bool(re.is_match(s)))
+}
+
+/// find(re, s) - First match, or null.
+fn native_find(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.find")?;
+    let s = text(vm, args[1], "regex.find")?;
+    match re.find(&s) {
+        Some(m) => new_string(vm, m.as_str()),
+        None => Ok(Value::null()),
+    }
+}
+
+/// find_all(re, s) - Every non-overlapping match, as a Vec of strings.
+fn native_find_all(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.find_all")?;
+    let s = text(vm, args[1], "regex.find_all")?;
+    string_vec(vm, re.find_iter(&s).map(|m| Some(m.as_str())))
+}
+
+/// captures(re, s) - Groups of the first match by index, 0 being the whole
+/// match and null for a group that didn't take part. null if nothing matches.
+fn native_captures(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.captures")?;
+    let s = text(vm, args[1], "regex.captures")?;
+    match re.captures(&s) {
+        Some(caps) => string_vec(vm, caps.iter().map(|g| g.map(|m| m.as_str()))),
+        None => Ok(Value::null()),
+    }
+}
+
+/// group(re, s, group) - One group of the first match, by index or by name.
+fn native_group(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "regex.group";
+    let re = get_regex(vm, args[0], op)?;
+    let s = text(vm, args[1], op)?;
+    let found = if args[2].as_int().is_some() {
+        let index = get_int(vm, args[2], op)?;
+        if index < 0 || index as usize >= re.captures_len() {
+            return Err(regex_error(
+                vm,
+                op,
+                format!("no group {} in /{}/", index, re.as_str()),
+            ));
+        }
+        re.captures(&s)
+            .and_then(|caps| caps.get(index as usize))
+            .map(|m| m.as_str().to_string())
+    } else {
+        let name = get_string(vm, args[2], op)?;
+        if !re.capture_names().any(|n| n == Some(name)) {
+            return Err(regex_error(
+                vm,
+                op,
+                format!("no group named '{}' in /{}/", name, re.as_str()),
+            ));
+        }
+        re.captures(&s)
+            .and_then(|caps| caps.name(name))
+            .map(|m| m.as_str().to_string())
+    };
+    match found {
+        Some(m) => new_string(vm, &m),
+        None => Ok(Value::null()),
+    }
+}
+
+/// replace(re, s, with) - Replace the first match. `with` can refer to
+/// groups as `$1`, `$name` or `${name}`, `$$` is a literal `$`.
+fn native_replace(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.replace")?;
+    let s = get_string(vm, args[1], "regex.replace")?;
+    let with = get_string(vm, args[2], "regex.replace")?;
+    let out = re.replace(s, with).into_owned();
+    new_string(vm, &out)
+}
+
+/// replace_all(re, s, with) - Replace every match, same `$` references.
+fn native_replace_all(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.replace_all")?;
+    let s = get_string(vm, args[1], "regex.replace_all")?;
+    let with = get_string(vm, args[2], "regex.replace_all")?;
+    let out = re.replace_all(s, with).into_owned();
+    new_string(vm, &out)
+}
+
+/// split(re, s) - The pieces between matches, as a Vec of strings.
+fn native_split(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let re = get_regex(vm, args[0], "regex.split")?;
+    let s = text(vm, args[1], "regex.split")?;
+    string_vec(vm, re.split(&s).map(Some))
+}
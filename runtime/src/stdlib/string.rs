@@ -153,7 +153,7 @@ fn native_capitalize(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError>
     }
 }
 
-fn native_contains(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+pub(crate) fn native_contains(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
     let haystack = get_string(vm, args[0], "string.contains")?;
     let needle = get_string(vm, args[1], "string.contains")?;
     Ok(Value::bool(haystack.contains(needle)))
@@ -233,7 +233,7 @@ fn native_split(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
 
 /// join(parts, sep) - Join parts by separator.
 /// parts are expected to be newline-separated.
-fn native_join(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+pub(crate) fn native_join(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
     let parts = get_string(vm, args[0], "string.join")?;
     let sep = get_string(vm, args[1], "string.join")?;
     let result = parts.lines().collect::<Vec<&str>>().join(sep);
@@ -253,7 +253,7 @@ fn native_repeat(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
 }
 
 /// reverse(s) - Reverse string (by characters).
-fn native_reverse(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+pub(crate) fn native_reverse(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
     let s = get_string(vm, args[0], "string.reverse")?;
     let reversed: String = s.chars().rev().collect();
     make_string(vm, &reversed)
@@ -1,9 +1,13 @@
 use crate::stdlib::helpers::{get_int, get_string, make_string};
-use crate::stdlib::{StdModuleExports, register_native};
+use crate::stdlib::{StdModuleExports, random, register_native};
 use crate::vm::{VM, Value};
 use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use rand::Rng;
 use std::env;
 use std::process::Command;
+use std::sync::atomic::Ordering;
+
+mod process;
 
 pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
     let mut all_exports = Vec::new();
@@ -40,6 +44,16 @@ pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
     reg_fn!("exec_output", 1, native_exec_output);
     reg_fn!("exec_args", 2, native_exec_args);
     reg_fn!("exec_args_output", 2, native_exec_args_output);
+    reg_fn!("spawn", 3, process::native_spawn);
+    reg_fn!("write_stdin", 2, process::native_write_stdin);
+    reg_fn!("close_stdin", 1, process::native_close_stdin);
+    reg_fn!("read_stdout", 1, process::native_read_stdout);
+    reg_fn!("read_stderr", 1, process::native_read_stderr);
+    reg_fn!("wait", 1, process::native_wait);
+    reg_fn!("try_wait", 1, process::native_try_wait);
+    reg_fn!("kill", 1, process::native_kill);
+    reg_fn!("child_pid", 1, process::native_child_pid);
+    reg_fn!("close", 1, process::native_close);
     reg_fn!("random", 0, native_random);
     reg_fn!("random_int", 2, native_random_int);
 
@@ -128,8 +142,9 @@ fn native_env(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
 fn native_set_env(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
     let name = get_string(vm, args[0], "sys.set_env")?.to_string();
     let value = get_string(vm, args[1], "sys.set_env")?.to_string();
-    // SAFETY: We're setting environment variables with valid UTF-8 strings.
-    // This is safe as long as no other threads are reading env vars concurrently.
+    single_threaded(vm, "sys.set_env")?;
+    // SAFETY: no std.thread thread is running (checked above), so nothing
+    // else in the program reads the environment concurrently.
     unsafe { env::set_var(&name, &value) };
     Ok(Value::null())
 }
@@ -137,12 +152,27 @@ fn native_set_env(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
 /// unset_env(name) - Unset environment variable.
 fn native_unset_env(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
     let name = get_string(vm, args[0], "sys.unset_env")?.to_string();
-    // SAFETY: We're removing environment variables.
-    // This is safe as long as no other threads are reading env vars concurrently.
+    single_threaded(vm, "sys.unset_env")?;
+    // SAFETY: as in set_env.
     unsafe { env::remove_var(&name) };
     Ok(Value::null())
 }
 
+/// Changing the environment races with reads on other threads, so it's only
+/// allowed while no std.thread thread runs. A thread counts itself, so this
+/// also refuses it inside one.
+fn single_threaded(vm: &VM, op: &'static str) -> Result<(), RuntimeError> {
+    let running = vm.running_threads.load(Ordering::SeqCst);
+    if running == 0 {
+        return Ok(());
+    }
+    Err(sys_error(
+        vm,
+        op,
+        format!("environment change with {} thread(s) running", running),
+    ))
+}
+
 /// env_vars() - Get all environment variables as NAME=VALUE lines.
 fn native_env_vars(vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
     let vars: Vec<String> = env::vars().map(|(k, v)| format!("{}={}", k, v)).collect();
@@ -427,26 +457,11 @@ fn native_exec_args_output(vm: &mut VM, args: &[Value]) -> Result<Value, Runtime
     }
 }
 
-/// random() - Get a random float between 0 and 1.
-// TODO: Imagine making prng3d and not using a proper PRNG here :)
-fn native_random(_vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
-    // Simple PRNG using system time as seed
-    use std::time::SystemTime;
-
-    let seed = SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos() as u64;
-
-    // Simple xorshift
-    let mut x = seed;
-    x ^= x << 13;
-    x ^= x >> 7;
-    x ^= x << 17;
-
-    // Convert to float in [0, 1)
-    let result = (x as f64) / (u64::MAX as f64);
-    Ok(Value::float(result))
+/// random() - Get a random float between 0 and 1, from the default
+/// generator of std.random.
+fn native_random(vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    let x: f64 = random::default_rng(vm).r#gen();
+    Ok(Value::float(x))
 }
 
 /// random_int(min, max) - Get a random integer in range [min, max].
@@ -462,21 +477,5 @@ fn native_random_int(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError>
         ));
     }
 
-    use std::time::SystemTime;
-
-    let seed = SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos() as u64;
-
-    // Simple xorshift
-    let mut x = seed;
-    x ^= x << 13;
-    x ^= x >> 7;
-    x ^= x << 17;
-
-    // Map to range
-    let range = (max - min + 1) as u64;
-    let result = min + (x % range) as i64;
-    Ok(Value::int(result))
+    Ok(Value::int(random::default_rng(vm).gen_range(min..=max)))
 }
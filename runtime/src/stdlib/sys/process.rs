@@ -0,0 +1,349 @@
+// sys.spawn and the calls on the handle it returns.
+//
+// Options come as [key, value] pairs, the same shape std.json uses for
+// objects: cwd, env, clear_env, timeout (ms, for wait and the reads) and
+// stdin/stdout/stderr ("pipe", "inherit" or "null", all piped by default).
+
+use super::sys_error;
+use crate::stdlib::helpers::{get_bool, get_handle, get_int, get_string};
+use crate::stdlib::json::pairs;
+use crate::stdlib::{ProcessResource, Resource};
+use crate::vm::{GcRef, ObjectKind, VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// how often wait() checks on a child when it has a deadline
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+// unread lines kept per stream; past that the reader thread stops reading
+// and the child blocks on a full pipe until the program catches up
+const LINE_BUFFER: usize = 1024;
+// longer lines are handed out in pieces of this many bytes
+const MAX_LINE: usize = 64 * 1024;
+
+/// spawn(cmd, args, opts) - Start `cmd` (no shell) with a Vec or Array of
+/// string arguments. `opts` is null or [key, value] pairs. Returns a handle.
+pub(super) fn native_spawn(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "sys.spawn";
+    if !vm.capabilities().allow_exec {
+        return Err(vm.runtime_error(RuntimeErrorKind::CapabilityDenied { operation: op }));
+    }
+    let program = get_string(vm, args[0], op)?.to_string();
+    let mut command = Command::new(&program);
+    command.args(string_list(vm, args[1], op)?);
+
+    let mut timeout_ms = None;
+    let mut stdio = [Stdio::piped(), Stdio::piped(), Stdio::piped()];
+    let options = if args[2].is_null() {
+        Vec::new()
+    } else {
+        pairs(vm, args[2], op)?
+    };
+    for (key, value) in options {
+        match key.as_str() {
+            "cwd" => {
+                command.current_dir(get_string(vm, value, op)?);
+            }
+            "env" => {
+                for (name, value) in pairs(vm, value, op)? {
+                    command.env(name, get_string(vm, value, op)?);
+                }
+            }
+            "clear_env" => {
+                if get_bool(vm, value, op)? {
+                    command.env_clear();
+                }
+            }
+            "timeout" => {
+                let ms = get_int(vm, value, op)?;
+                if ms <= 0 {
+                    return Err(sys_error(vm, op, "timeout must be positive".to_string()));
+                }
+                timeout_ms = Some(ms as u64);
+            }
+            "stdin" | "stdout" | "stderr" => {
+                let slot = match key.as_str() {
+                    "stdin" => 0,
+                    "stdout" => 1,
+                    _ => 2,
+                };
+                stdio[slot] = match get_string(vm, value, op)? {
+                    "pipe" => Stdio::piped(),
+                    "inherit" => Stdio::inherit(),
+                    "null" => Stdio::null(),
+                    other => {
+                        return Err(sys_error(
+                            vm,
+                            op,
+                            format!(
+                                "{} must be \"pipe\", \"inherit\" or \"null\", got \"{}\"",
+                                key, other
+                            ),
+                        ));
+                    }
+                };
+            }
+            _ => return Err(sys_error(vm, op, format!("unknown option '{}'", key))),
+        }
+    }
+    let [stdin, stdout, stderr] = stdio;
+    command.stdin(stdin).stdout(stdout).stderr(stderr);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| sys_error(vm, op, format!("failed to spawn '{}': {}", program, e)))?;
+    let process = ProcessResource {
+        stdin: child.stdin.take(),
+        stdout: child.stdout.take().map(drain_lines),
+        stderr: child.stderr.take().map(drain_lines),
+        child,
+        timeout_ms,
+        exit_code: None,
+    };
+    Ok(Value::int(
+        vm.store_resource(Resource::Process(process)) as i64
+    ))
+}
+
+/// write_stdin(h, s) - Write `s` to the child's stdin (no newline added).
+pub(super) fn native_write_stdin(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "sys.write_stdin";
+    let data = get_string(vm, args[1], op)?.to_string();
+    let process = get_process(vm, args[0], op)?;
+    let result = match process.stdin.as_mut() {
+        Some(stdin) => stdin.write_all(data.as_bytes()).and_then(|_| stdin.flush()),
+        None => {
+            return Err(sys_error(
+                vm,
+                op,
+                "stdin is not piped or closed".to_string(),
+            ));
+        }
+    };
+    result.map_err(|e| sys_error(vm, op, format!("write failed: {}", e)))?;
+    Ok(Value::null())
+}
+
+/// close_stdin(h) - Close the child's stdin so it sees end of input.
+pub(super) fn native_close_stdin(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    get_process(vm, args[0], "sys.close_stdin")?.stdin = None;
+    Ok(Value::null())
+}
+
+/// read_stdout(h) - Next line of the child's stdout, null once it's done.
+pub(super) fn native_read_stdout(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    read_line(vm, args[0], "sys.read_stdout", |p| p.stdout.as_ref())
+}
+
+/// read_stderr(h) - Next line of the child's stderr, null once it's done.
+pub(super) fn native_read_stderr(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    read_line(vm, args[0], "sys.read_stderr", |p| p.stderr.as_ref())
+}
+
+/// wait(h) - Wait for the child to exit, returns its exit code (-1 when a
+/// signal ended it). Past the timeout the child is killed and this fails.
+pub(super) fn native_wait(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "sys.wait";
+    let process = get_process(vm, args[0], op)?;
+    if let Some(code) = process.exit_code {
+        return Ok(Value::int(code));
+    }
+    // a child reading its stdin would otherwise never finish
+    process.stdin = None;
+
+    let result = match process.timeout_ms {
+        None => process.child.wait().map(Some),
+        Some(ms) => {
+            let deadline = Instant::now() + Duration::from_millis(ms);
+            loop {
+                match process.child.try_wait() {
+                    Ok(Some(status)) => break Ok(Some(status)),
+                    Ok(None) if Instant::now() >= deadline => {
+                        let _ = process.child.kill();
+                        let _ = process.child.wait();
+                        break Ok(None);
+                    }
+                    Ok(None) => thread::sleep(POLL_INTERVAL),
+                    Err(e) => break Err(e),
+                }
+            }
+        }
+    };
+    match result {
+        Ok(Some(status)) => {
+            let code = status.code().unwrap_or(-1) as i64;
+            process.exit_code = Some(code);
+            Ok(Value::int(code))
+        }
+        Ok(None) => {
+            let ms = process.timeout_ms.unwrap_or_default();
+            process.exit_code = Some(-1);
+            Err(sys_error(
+                vm,
+                op,
+                format!("timed out after {}ms, process killed", ms),
+            ))
+        }
+        Err(e) => Err(sys_error(vm, op, format!("wait failed: {}", e))),
+    }
+}
+
+/// try_wait(h) - Exit code if the child has exited, null if it's running.
+pub(super) fn native_try_wait(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "sys.try_wait";
+    let process = get_process(vm, args[0], op)?;
+    if let Some(code) = process.exit_code {
+        return Ok(Value::int(code));
+    }
+    match process.child.try_wait() {
+        Ok(Some(status)) => {
+            let code = status.code().unwrap_or(-1) as i64;
+            process.exit_code = Some(code);
+            Ok(Value::int(code))
+        }
+        Ok(None) => Ok(Value::null()),
+        Err(e) => Err(sys_error(vm, op, format!("wait failed: {}", e))),
+    }
+}
+
+/// kill(h) - Kill the child. Does nothing if it already exited.
+pub(super) fn native_kill(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let process = get_process(vm, args[0], "sys.kill")?;
+    if process.exit_code.is_none() {
+        let _ = process.child.kill();
+    }
+    Ok(Value::null())
+}
+
+/// child_pid(h) - OS process id of the child.
+pub(super) fn native_child_pid(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let process = get_process(vm, args[0], "sys.child_pid")?;
+    Ok(Value::int(process.child.id() as i64))
+}
+
+/// close(h) - Release the handle, killing the child if it's still running.
+pub(super) fn native_close(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "sys.close";
+    get_process(vm, args[0], op)?;
+    let handle = get_handle(vm, args[0], op)?;
+    // dropping it kills the child, see below
+    vm.take_resource(handle);
+    Ok(Value::null())
+}
+
+// A handle that is closed, or still open when its VM goes away, takes a
+// running child with it rather than leaving it orphaned.
+impl Drop for ProcessResource {
+    fn drop(&mut self) {
+        if self.exit_code.is_none()
+            && let Ok(None) = self.child.try_wait()
+        {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+fn get_process<'a>(
+    vm: &'a mut VM,
+    value: Value,
+    op: &'static str,
+) -> Result<&'a mut ProcessResource, RuntimeError> {
+    let handle = get_handle(vm, value, op)?;
+    if !matches!(vm.get_resource(handle), Some(Resource::Process(_))) {
+        return Err(sys_error(vm, op, "invalid process handle".to_string()));
+    }
+    match vm.get_resource_mut(handle) {
+        Some(Resource::Process(process)) => Ok(process),
+        _ => unreachable!("checked above"),
+    }
+}
+
+fn read_line(
+    vm: &mut VM,
+    value: Value,
+    op: &'static str,
+    stream: fn(&ProcessResource) -> Option<&Receiver<String>>,
+) -> Result<Value, RuntimeError> {
+    let process = get_process(vm, value, op)?;
+    let timeout_ms = process.timeout_ms;
+    let Some(lines) = stream(process) else {
+        return Err(sys_error(vm, op, "stream is not piped".to_string()));
+    };
+    let line = match timeout_ms {
+        None => lines.recv().ok(),
+        Some(ms) => match lines.recv_timeout(Duration::from_millis(ms)) {
+            Ok(line) => Some(line),
+            Err(RecvTimeoutError::Disconnected) => None,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(sys_error(vm, op, format!("timed out after {}ms", ms)));
+            }
+        },
+    };
+    match line {
+        Some(line) => Ok(Value::ptr(vm.alloc_string(&line)?.index())),
+        None => Ok(Value::null()),
+    }
+}
+
+// lines without their terminator, invalid UTF-8 replaced, at most
+// LINE_BUFFER of them waiting to be read
+fn drain_lines(stream: impl Read + Send + 'static) -> Receiver<String> {
+    let (tx, rx) = mpsc::sync_channel(LINE_BUFFER);
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match (&mut reader)
+                .take(MAX_LINE as u64)
+                .read_until(b'\n', &mut buf)
+            {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if buf.last() == Some(&b'\n') {
+                        buf.pop();
+                        if buf.last() == Some(&b'\r') {
+                            buf.pop();
+                        }
+                    }
+                    if tx.send(String::from_utf8_lossy(&buf).into_owned()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
+
+// arguments as a Vec or Array of strings, null for none
+fn string_list(vm: &VM, value: Value, op: &'static str) -> Result<Vec<String>, RuntimeError> {
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+    let items = value
+        .as_ptr()
+        .and_then(|ptr| vm.heap().get(GcRef::new(ptr)))
+        .and_then(|obj| match &obj.kind {
+            ObjectKind::Array(array) if array.is_empty() => Some(&[][..]),
+            ObjectKind::Vec(vec) if vec.is_empty() => Some(&[][..]),
+            ObjectKind::Array(array) => array.data.as_objects(),
+            ObjectKind::Vec(vec) => vec.data.as_objects(),
+            _ => None,
+        });
+    let Some(items) = items else {
+        return Err(vm.runtime_error(RuntimeErrorKind::TypeError {
+            operation: op,
+            expected: "Vec or Array of strings",
+            got: vm.value_type_name(value).to_string(),
+        }));
+    };
+    items
+        .iter()
+        .map(|&item| get_string(vm, item, op).map(str::to_string))
+        .collect()
+}
@@ -0,0 +1,107 @@
+// Channels. A channel handle is a resource like any other, holding one
+// reference to the channel; a thread starts with the channels of the VM that
+// spawned it under the same handles, so both sides talk about the same one.
+// The channel goes away with the last VM that has it.
+
+use super::message::Message;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+pub(crate) struct Channel {
+    state: Mutex<State>,
+    // signalled on a new message and on close
+    readable: Condvar,
+    // signalled when a message is taken and on close
+    writable: Condvar,
+    // None for unbounded
+    capacity: Option<usize>,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Message>,
+    closed: bool,
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Channel {
+    pub(crate) fn new(capacity: Option<usize>) -> Arc<Channel> {
+        Arc::new(Channel {
+            state: Mutex::new(State::default()),
+            readable: Condvar::new(),
+            writable: Condvar::new(),
+            capacity,
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queue `message`, waiting for room in a bounded channel. Gives it back
+    /// if the channel is closed.
+    pub(crate) fn send(&self, message: Message) -> Result<(), Message> {
+        let mut state = self.state();
+        while !state.closed && self.capacity.is_some_and(|cap| state.queue.len() >= cap) {
+            state = self
+                .writable
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        if state.closed {
+            return Err(message);
+        }
+        state.queue.push_back(message);
+        self.readable.notify_one();
+        Ok(())
+    }
+
+    /// The oldest message. Waits up to `timeout` (forever for None) while the
+    /// channel is empty and open; None when it's closed and drained or the
+    /// time ran out.
+    pub(crate) fn recv(&self, timeout: Option<Duration>) -> Option<Message> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut state = self.state();
+        loop {
+            if let Some(message) = state.queue.pop_front() {
+                self.writable.notify_one();
+                return Some(message);
+            }
+            if state.closed {
+                return None;
+            }
+            state = match deadline {
+                None => self
+                    .readable
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return None;
+                    }
+                    self.readable
+                        .wait_timeout(state, left)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        }
+    }
+
+    /// No more sends; receivers still get what's queued.
+    pub(crate) fn close(&self) {
+        self.state().closed = true;
+        self.readable.notify_all();
+        self.writable.notify_all();
+    }
+}
@@ -0,0 +1,234 @@
+// Values on their way from one VM to another. Every VM has its own heap, so
+// what a thread starts with (its function, arguments and the globals the
+// function uses), what goes through a channel and what join hands back is
+// copied out into a Message and rebuilt in the other heap.
+//
+// Functions travel as .avbc bytes, the format `aelys compile` writes, which
+// already knows how to carry constants and nested functions and drops the
+// inline caches that point into the old heap.
+
+use crate::vm::{
+    AelysClosure, AelysUpvalue, Function, GcObject, GcRef, NativeFn, NativeFunctionImpl,
+    ObjectKind, VM, Value,
+};
+use aelys_bytecode::object::{AelysArray, AelysVec, ArrayData, ClosureCache, VecData};
+use aelys_common::error::RuntimeError;
+
+// deeper than this is most likely an Array holding itself
+const MAX_DEPTH: usize = 64;
+
+pub(crate) enum Message {
+    // null, bool, int or float
+    Scalar(Value),
+    Str(String),
+    Array(Items),
+    Vec(Items),
+    Function(Vec<u8>),
+    Closure {
+        function: Vec<u8>,
+        upvalues: Vec<Message>,
+    },
+    Native {
+        name: String,
+        arity: u8,
+        func: NativeFn,
+    },
+}
+
+pub(crate) enum Items {
+    Ints(Vec<i64>),
+    Floats(Vec<f64>),
+    Bools(Vec<u8>),
+    Values(Vec<Message>),
+}
+
+/// Copies values out of a VM. Data only for channels and join; `code` also
+/// takes functions and records the globals they use.
+pub(crate) struct Packer<'a> {
+    vm: &'a VM,
+    // None when functions can't be packed
+    uses: Option<Vec<String>>,
+}
+
+impl<'a> Packer<'a> {
+    pub(crate) fn data(vm: &'a VM) -> Self {
+        Self { vm, uses: None }
+    }
+
+    pub(crate) fn code(vm: &'a VM) -> Self {
+        Self {
+            vm,
+            uses: Some(Vec::new()),
+        }
+    }
+
+    /// Next global used by a function packed so far, each name once per
+    /// function that uses it.
+    pub(crate) fn next_use(&mut self) -> Option<String> {
+        self.uses.as_mut()?.pop()
+    }
+
+    /// `value` as a Message, or what made it impossible.
+    pub(crate) fn pack(&mut self, value: Value) -> Result<Message, String> {
+        self.pack_at(value, 0)
+    }
+
+    fn pack_at(&mut self, value: Value, depth: usize) -> Result<Message, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("values nested more than {} deep", MAX_DEPTH));
+        }
+        let Some(ptr) = value.as_ptr() else {
+            return Ok(Message::Scalar(value));
+        };
+        let heap = self.vm.heap();
+        let Some(obj) = heap.get(GcRef::new(ptr)) else {
+            return Err("a dangling reference".to_string());
+        };
+        match &obj.kind {
+            ObjectKind::String(s) => Ok(Message::Str(s.as_str().to_string())),
+            ObjectKind::Array(array) => {
+                let items = match &array.data {
+                    ArrayData::Ints(s) => Items::Ints(s.to_vec()),
+                    ArrayData::Floats(s) => Items::Floats(s.to_vec()),
+                    ArrayData::Bools(s) => Items::Bools(s.to_vec()),
+                    ArrayData::Objects(s) => Items::Values(self.pack_all(s, depth)?),
+                };
+                Ok(Message::Array(items))
+            }
+            ObjectKind::Vec(vec) => {
+                let items = match &vec.data {
+                    VecData::Ints(v) => Items::Ints(v.clone()),
+                    VecData::Floats(v) => Items::Floats(v.clone()),
+                    VecData::Bools(v) => Items::Bools(v.clone()),
+                    VecData::Objects(v) => Items::Values(self.pack_all(v, depth)?),
+                };
+                Ok(Message::Vec(items))
+            }
+            _ if self.uses.is_none() => Err(format!("a {}", self.vm.value_type_name(value))),
+            ObjectKind::Function(f) => Ok(Message::Function(self.pack_function(&f.function))),
+            ObjectKind::Closure(closure) => {
+                let function = match heap.get(closure.function).map(|o| &o.kind) {
+                    Some(ObjectKind::Function(f)) => self.pack_function(&f.function),
+                    _ => return Err("a broken closure".to_string()),
+                };
+                let captured: Vec<Value> = closure
+                    .upvalues
+                    .iter()
+                    .map(|&upvalue| self.vm.get_upvalue_value(upvalue))
+                    .collect();
+                Ok(Message::Closure {
+                    function,
+                    upvalues: self.pack_all(&captured, depth)?,
+                })
+            }
+            ObjectKind::Native(native) => match self.vm.native_registry.get(&native.name) {
+                Some(&NativeFunctionImpl::Rust(func)) => Ok(Message::Native {
+                    name: native.name.clone(),
+                    arity: native.arity,
+                    func,
+                }),
+                // its code lives in a library loaded by this VM only
+                _ => Err(format!("'{}' from a native module", native.name)),
+            },
+            ObjectKind::Upvalue(_) => Err("an upvalue".to_string()),
+        }
+    }
+
+    fn pack_all(&mut self, values: &[Value], depth: usize) -> Result<Vec<Message>, String> {
+        values.iter().map(|&v| self.pack_at(v, depth + 1)).collect()
+    }
+
+    fn pack_function(&mut self, function: &Function) -> Vec<u8> {
+        if let Some(uses) = &mut self.uses {
+            global_names(function, uses);
+        }
+        aelys_bytecode::serialize(function, self.vm.heap())
+    }
+}
+
+fn global_names(function: &Function, out: &mut Vec<String>) {
+    out.extend(
+        function
+            .global_layout
+            .names()
+            .iter()
+            .filter(|name| !name.is_empty())
+            .cloned(),
+    );
+    for nested in &function.nested_functions {
+        global_names(nested, out);
+    }
+}
+
+/// Rebuild a Message in `vm`'s heap. The caller keeps the GC off until the
+/// result is rooted somewhere.
+pub(crate) fn unpack(vm: &mut VM, message: Message) -> Result<Value, RuntimeError> {
+    let value = match message {
+        Message::Scalar(value) => value,
+        Message::Str(s) => Value::ptr(vm.alloc_string(&s)?.index()),
+        Message::Array(items) => {
+            let data = match items {
+                Items::Ints(v) => ArrayData::Ints(v.into_boxed_slice()),
+                Items::Floats(v) => ArrayData::Floats(v.into_boxed_slice()),
+                Items::Bools(v) => ArrayData::Bools(v.into_boxed_slice()),
+                Items::Values(v) => ArrayData::Objects(unpack_all(vm, v)?.into_boxed_slice()),
+            };
+            Value::ptr(vm.alloc_array(AelysArray { data })?.index())
+        }
+        Message::Vec(items) => {
+            let data = match items {
+                Items::Ints(v) => VecData::Ints(v),
+                Items::Floats(v) => VecData::Floats(v),
+                Items::Bools(v) => VecData::Bools(v),
+                Items::Values(v) => VecData::Objects(unpack_all(vm, v)?),
+            };
+            Value::ptr(vm.alloc_vec(AelysVec { data })?.index())
+        }
+        Message::Function(bytes) => Value::ptr(unpack_function(vm, &bytes)?.index()),
+        Message::Closure { function, upvalues } => {
+            let function = unpack_function(vm, &function)?;
+            let mut refs = Vec::with_capacity(upvalues.len());
+            for value in unpack_all(vm, upvalues)? {
+                let mut upvalue = AelysUpvalue::new_open(0, 0);
+                upvalue.close(value);
+                refs.push(vm.alloc_object(GcObject::new(ObjectKind::Upvalue(upvalue)))?);
+            }
+            let cache = match vm.heap().get(function).map(|o| &o.kind) {
+                Some(ObjectKind::Function(f)) => ClosureCache {
+                    bytecode_ptr: f.function.bytecode.as_ptr(),
+                    bytecode_len: f.function.bytecode.len(),
+                    constants_ptr: f.function.constants.as_ptr(),
+                    constants_len: f.function.constants.len(),
+                    arity: f.function.arity,
+                    num_registers: f.function.num_registers,
+                },
+                _ => unreachable!("unpack_function allocates a function"),
+            };
+            let closure = AelysClosure::with_cache(function, refs, cache);
+            Value::ptr(
+                vm.alloc_object(GcObject::new(ObjectKind::Closure(closure)))?
+                    .index(),
+            )
+        }
+        Message::Native { name, arity, func } => {
+            Value::ptr(vm.alloc_native(&name, arity, func)?.index())
+        }
+    };
+    Ok(value)
+}
+
+fn unpack_all(vm: &mut VM, messages: Vec<Message>) -> Result<Vec<Value>, RuntimeError> {
+    messages.into_iter().map(|m| unpack(vm, m)).collect()
+}
+
+fn unpack_function(vm: &mut VM, bytes: &[u8]) -> Result<GcRef, RuntimeError> {
+    let (mut function, mut heap) = aelys_bytecode::deserialize(bytes).map_err(|e| {
+        vm.runtime_error(aelys_common::error::RuntimeErrorKind::InvalidBytecode(
+            e.to_string(),
+        ))
+    })?;
+    let remap = vm.merge_heap(&mut heap)?;
+    function.remap_constants(&remap);
+    vm.verify_function_value(&function)?;
+    vm.alloc_function(function)
+}
@@ -0,0 +1,395 @@
+// thread module - OS threads, each running its own VM. A thread gets a copy
+// of its function, its arguments and the globals that function uses, then
+// shares nothing with the VM that started it: values go back and forth
+// through channels (copied, see message.rs) and join hands back the result.
+// The channels it can use are the ones its parent had when it was spawned.
+//
+// How many threads may run at once is capped by VmConfig::max_threads, for
+// the whole tree of VMs a program starts.
+
+use crate::stdlib::array::items;
+use crate::stdlib::helpers::{get_handle, get_int};
+use crate::stdlib::{ChannelResource, Resource, StdModuleExports, ThreadResource, register_native};
+use crate::vm::{VM, Value, VmConfig};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use aelys_syntax::Source;
+use rand::RngCore;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+mod channel;
+mod message;
+
+pub(crate) use channel::Channel;
+pub(crate) use message::Message;
+use message::{Packer, unpack};
+
+// like the main thread's, since calls from natives back into Aelys recurse
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
+    let mut exports = Vec::new();
+    let mut natives = Vec::new();
+
+    macro_rules! reg {
+        ($n:expr, $a:expr, $f:expr) => {{
+            register_native(vm, "thread", $n, $a, $f)?;
+            exports.push($n.to_string());
+            natives.push(format!("thread::{}", $n));
+        }};
+    }
+
+    // threads
+    reg!("spawn", 2, native_spawn);
+    reg!("join", 1, native_join);
+    reg!("is_done", 1, native_is_done);
+    reg!("cpu_count", 0, native_cpu_count);
+
+    // channels
+    reg!("channel", 1, native_channel);
+    reg!("send", 2, native_send);
+    reg!("recv", 1, native_recv);
+    reg!("try_recv", 1, native_try_recv);
+    reg!("recv_timeout", 2, native_recv_timeout);
+    reg!("close", 1, native_close);
+
+    Ok(StdModuleExports {
+        all_exports: exports,
+        native_functions: natives,
+    })
+}
+
+fn thread_error(vm: &VM, op: &'static str, expected: &'static str, got: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected,
+        got,
+    })
+}
+
+/// What a new thread needs to set up its VM, all of it owned.
+struct Start {
+    source: Arc<Source>,
+    config: VmConfig,
+    program_args: Vec<String>,
+    script_path: Option<String>,
+    running: Arc<AtomicUsize>,
+    // the parent's channels, by handle
+    channels: Vec<(usize, Arc<Channel>)>,
+    globals: Vec<(String, Message)>,
+    function: Message,
+    args: Vec<Message>,
+}
+
+/// Frees the thread's slot when it ends, however it ends.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// spawn(f, args) - Run `f` on a new thread with the elements of the Array
+/// or Vec `args` as arguments (null for none). Returns a thread handle for
+/// join.
+fn native_spawn(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "thread.spawn";
+    let kind = vm.value_type_name(args[0]);
+    if !matches!(kind, "function" | "closure" | "native function") {
+        return Err(thread_error(vm, op, "function", kind.to_string()));
+    }
+    let call_args: Vec<Value> = if args[1].is_null() {
+        Vec::new()
+    } else {
+        let items = items(vm, args[1], op)?;
+        (0..items.len()).map(|i| items.get(i)).collect()
+    };
+
+    // globals_by_index may be ahead of the map the function's uses are read from
+    vm.sync_current_function_globals();
+    let packed = {
+        let mut packer = Packer::code(vm);
+        let copy_error = |vm: &VM, why: String| {
+            thread_error(
+                vm,
+                op,
+                "function, arguments and globals a thread can copy",
+                why,
+            )
+        };
+        let function = packer.pack(args[0]).map_err(|e| copy_error(vm, e))?;
+        let mut copied_args = Vec::with_capacity(call_args.len());
+        for &arg in &call_args {
+            copied_args.push(packer.pack(arg).map_err(|e| copy_error(vm, e))?);
+        }
+        let mut globals: Vec<(String, Message)> = Vec::new();
+        while let Some(name) = packer.next_use() {
+            if globals.iter().any(|(seen, _)| *seen == name) {
+                continue;
+            }
+            if let Some(value) = vm.get_global(&name) {
+                let message = packer
+                    .pack(value)
+                    .map_err(|e| copy_error(vm, format!("{} in global '{}'", e, name)))?;
+                globals.push((name, message));
+            }
+        }
+        (function, copied_args, globals)
+    };
+    let (function, copied_args, globals) = packed;
+
+    let max = vm.config.max_threads;
+    let taken = vm
+        .running_threads
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+            (n < max).then_some(n + 1)
+        });
+    if let Err(n) = taken {
+        return Err(thread_error(
+            vm,
+            op,
+            "fewer running threads than -ae.max-threads",
+            format!("{} of {} running", n, max),
+        ));
+    }
+    let slot = Slot(Arc::clone(&vm.running_threads));
+
+    let mut config = vm.config.clone();
+    config.coverage = false;
+    // each thread gets its own stream, still reproducible with -ae.seed
+    if config.seed.is_some() {
+        config.seed = Some(crate::stdlib::random::default_rng(vm).next_u64());
+    }
+    let channels = (0..vm.resources.len())
+        .filter_map(|handle| match vm.get_resource(handle) {
+            Some(Resource::Channel(c)) => Some((handle, Arc::clone(&c.channel))),
+            _ => None,
+        })
+        .collect();
+    let start = Start {
+        source: Arc::clone(&vm.source),
+        config,
+        program_args: vm.program_args.clone(),
+        script_path: vm.script_path.clone(),
+        running: Arc::clone(&vm.running_threads),
+        channels,
+        globals,
+        function,
+        args: copied_args,
+    };
+    let spawned = std::thread::Builder::new()
+        .name("aelys-thread".to_string())
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let _slot = slot;
+            run(start)
+        });
+    match spawned {
+        Ok(handle) => {
+            let thread = vm.store_resource(Resource::Thread(ThreadResource { handle }));
+            Ok(Value::int(thread as i64))
+        }
+        Err(e) => Err(thread_error(vm, op, "thread started", e.to_string())),
+    }
+}
+
+/// Body of a spawned thread: a new VM running the function.
+fn run(start: Start) -> Result<Message, String> {
+    let describe = |e: RuntimeError| {
+        let mut text = e.kind.message();
+        if let Some(frame) = e.stack_trace.first() {
+            text.push_str(&format!(" (line {}", frame.line));
+            if let Some(name) = &frame.function_name {
+                text.push_str(&format!(", in {}", name));
+            }
+            text.push(')');
+        }
+        text
+    };
+    let mut vm = VM::with_config_and_args(start.source, start.config, start.program_args)
+        .map_err(describe)?;
+    vm.script_path = start.script_path;
+    vm.running_threads = start.running;
+    for (handle, channel) in start.channels {
+        vm.store_resource_at(handle, Resource::Channel(ChannelResource { channel }));
+    }
+
+    vm.enter_no_gc();
+    let unpacked = (|| {
+        for (name, message) in start.globals {
+            let value = unpack(&mut vm, message)?;
+            vm.set_global(name, value);
+        }
+        let function = unpack(&mut vm, start.function)?;
+        let mut args = Vec::with_capacity(start.args.len());
+        for message in start.args {
+            args.push(unpack(&mut vm, message)?);
+        }
+        Ok((function, args))
+    })();
+    vm.exit_no_gc();
+    let (function, args) = unpacked.map_err(describe)?;
+
+    let result = vm.call_value(function, &args).map_err(describe)?;
+    Packer::data(&vm)
+        .pack(result)
+        .map_err(|e| format!("can't return {} from a thread", e))
+}
+
+fn get_thread(vm: &VM, value: Value, op: &'static str) -> Result<usize, RuntimeError> {
+    let handle = get_handle(vm, value, op)?;
+    match vm.get_resource(handle) {
+        Some(Resource::Thread(_)) => Ok(handle),
+        _ => Err(thread_error(
+            vm,
+            op,
+            "thread handle",
+            format!("handle {}", handle),
+        )),
+    }
+}
+
+/// join(t) - Wait for the thread to end and return what its function
+/// returned. An error in the thread is raised here. The handle is spent.
+fn native_join(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "thread.join";
+    let handle = get_thread(vm, args[0], op)?;
+    let Some(Resource::Thread(thread)) = vm.take_resource(handle) else {
+        unreachable!("get_thread checked the handle");
+    };
+    let outcome = thread
+        .handle
+        .join()
+        .unwrap_or_else(|_| Err("the thread panicked".to_string()));
+    match outcome {
+        Ok(message) => {
+            vm.enter_no_gc();
+            let value = unpack(vm, message);
+            vm.exit_no_gc();
+            value
+        }
+        Err(why) => Err(thread_error(vm, op, "thread that finished", why)),
+    }
+}
+
+/// is_done(t) - Whether the thread has finished, so join won't wait.
+fn native_is_done(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let handle = get_thread(vm, args[0], "thread.is_done")?;
+    match vm.get_resource(handle) {
+        Some(Resource::Thread(thread)) => Ok(Value::bool(thread.handle.is_finished())),
+        _ => unreachable!("get_thread checked the handle"),
+    }
+}
+
+/// cpu_count() - How many threads the machine runs in parallel.
+fn native_cpu_count(_vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    let n = std::thread::available_parallelism().map_or(1, |n| n.get());
+    Ok(Value::int(n as i64))
+}
+
+/// channel(capacity) - A new channel holding up to `capacity` messages
+/// before send waits, or any number for null. Returns a channel handle.
+fn native_channel(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "thread.channel";
+    let capacity = if args[0].is_null() {
+        None
+    } else {
+        let n = get_int(vm, args[0], op)?;
+        if n < 1 {
+            return Err(thread_error(vm, op, "capacity >= 1 or null", n.to_string()));
+        }
+        Some(n as usize)
+    };
+    let channel = Channel::new(capacity);
+    let handle = vm.store_resource(Resource::Channel(ChannelResource { channel }));
+    Ok(Value::int(handle as i64))
+}
+
+fn get_channel(vm: &VM, value: Value, op: &'static str) -> Result<Arc<Channel>, RuntimeError> {
+    let handle = get_handle(vm, value, op)?;
+    match vm.get_resource(handle) {
+        Some(Resource::Channel(c)) => Ok(Arc::clone(&c.channel)),
+        _ => Err(thread_error(
+            vm,
+            op,
+            "channel handle",
+            format!("handle {}", handle),
+        )),
+    }
+}
+
+/// send(ch, v) - Queue a copy of `v` (bool, number, string, or Array or Vec
+/// of those and null), waiting while a bounded channel is full.
+fn native_send(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "thread.send";
+    let channel = get_channel(vm, args[0], op)?;
+    // recv gives null for a closed channel, so null can't be a message
+    if args[1].is_null() {
+        return Err(thread_error(
+            vm,
+            op,
+            "bool, number, string, Array or Vec",
+            "null".to_string(),
+        ));
+    }
+    let message = Packer::data(vm)
+        .pack(args[1])
+        .map_err(|why| thread_error(vm, op, "null, bool, number, string, Array or Vec", why))?;
+    if channel.send(message).is_err() {
+        return Err(thread_error(
+            vm,
+            op,
+            "open channel",
+            "closed channel".to_string(),
+        ));
+    }
+    Ok(Value::null())
+}
+
+fn receive(
+    vm: &mut VM,
+    value: Value,
+    timeout: Option<Duration>,
+    op: &'static str,
+) -> Result<Value, RuntimeError> {
+    let channel = get_channel(vm, value, op)?;
+    match channel.recv(timeout) {
+        Some(message) => {
+            vm.enter_no_gc();
+            let value = unpack(vm, message);
+            vm.exit_no_gc();
+            value
+        }
+        None => Ok(Value::null()),
+    }
+}
+
+/// recv(ch) - The oldest message, waiting for one. null once the channel is
+/// closed and empty.
+fn native_recv(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    receive(vm, args[0], None, "thread.recv")
+}
+
+/// try_recv(ch) - The oldest message, or null when there's none right now.
+fn native_try_recv(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    receive(vm, args[0], Some(Duration::ZERO), "thread.try_recv")
+}
+
+/// recv_timeout(ch, ms) - Like recv, but null after waiting `ms`
+/// milliseconds.
+fn native_recv_timeout(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "thread.recv_timeout";
+    let ms = get_int(vm, args[1], op)?;
+    if ms < 0 {
+        return Err(thread_error(vm, op, "ms >= 0", ms.to_string()));
+    }
+    receive(vm, args[0], Some(Duration::from_millis(ms as u64)), op)
+}
+
+/// close(ch) - No more sends. Receivers get what's left, then null.
+fn native_close(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    get_channel(vm, args[0], "thread.close")?.close();
+    Ok(Value::null())
+}
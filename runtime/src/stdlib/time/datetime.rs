@@ -0,0 +1,587 @@
+// DateTime and Duration values.
+//
+// A DateTime is a string in RFC 3339 form with the zone appended the way
+// RFC 9557 does it: `2024-03-10T03:30:00-04:00[America/New_York]`. The
+// offset pins the instant and the bracketed IANA name says how to move
+// around the calendar from there; UTC is a plain `Z` and a fixed offset has
+// no brackets. Being strings they're immutable, print as they are and go
+// through json unchanged, and every function here accepts any RFC 3339
+// text as well. A Duration is a number of seconds, like now() and
+// elapsed() return, so durations add, scale and compare with the usual
+// operators.
+
+use super::days_to_ymd;
+use super::duration::{native_duration_str, native_parse_duration};
+use super::pattern::{native_strftime, native_strptime};
+use super::zone::{Zone, local_type, local_zone, offset_at, parse_offset, parse_zone, resolve};
+use crate::stdlib::helpers::{get_int, get_number, get_string, make_string};
+use crate::vm::{NativeFn, VM, Value};
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Registered as `time::x` only: names like `add` and `diff` are too generic
+/// to also become globals the way `time.now` does.
+pub(super) const NATIVES: &[(&str, u8, NativeFn)] = &[
+    ("utc_now", 0, native_utc_now),
+    ("local_now", 0, native_local_now),
+    ("local_zone", 0, native_local_zone),
+    ("instant", 0, native_instant),
+    ("datetime", 7, native_datetime),
+    ("from_timestamp", 2, native_from_timestamp),
+    ("timestamp", 1, native_timestamp),
+    ("parse_datetime", 1, native_parse_datetime),
+    ("strptime", 3, native_strptime),
+    ("strftime", 2, native_strftime),
+    ("to_zone", 2, native_to_zone),
+    ("field", 2, native_field),
+    ("add", 2, native_add),
+    ("add_days", 2, native_add_days),
+    ("add_months", 2, native_add_months),
+    ("diff", 2, native_diff),
+    ("compare", 2, native_compare),
+    ("parse_duration", 1, native_parse_duration),
+    ("duration_str", 1, native_duration_str),
+];
+
+pub(super) const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+pub(super) struct DateTime {
+    pub(super) secs: i64,
+    pub(super) nanos: u32,
+    pub(super) zone: Zone,
+}
+
+/// What the clocks in a DateTime's zone read.
+pub(super) struct Civil {
+    pub(super) year: i32,
+    pub(super) month: u32,
+    pub(super) day: u32,
+    pub(super) hour: u32,
+    pub(super) minute: u32,
+    pub(super) second: u32,
+    pub(super) weekday: u32,
+    pub(super) yearday: u32,
+    pub(super) offset: i32,
+}
+
+pub(super) fn datetime_error(
+    vm: &VM,
+    op: &'static str,
+    expected: &'static str,
+    got: String,
+) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected,
+        got,
+    })
+}
+
+/// Days since 1970-01-01 (Howard Hinnant's days_from_civil).
+pub(super) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub(super) fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+pub(super) fn civil(vm: &VM, dt: &DateTime, op: &'static str) -> Result<Civil, RuntimeError> {
+    let offset = offset_at(vm, &dt.zone, dt.secs, op)?;
+    let local = dt.secs + offset as i64;
+    let days = local.div_euclid(86400);
+    let second_of_day = local.rem_euclid(86400) as u32;
+    let (year, month, day, yearday) = days_to_ymd(days as i32);
+    Ok(Civil {
+        year,
+        month,
+        day,
+        hour: second_of_day / 3600,
+        minute: second_of_day / 60 % 60,
+        second: second_of_day % 60,
+        weekday: (days + 4).rem_euclid(7) as u32,
+        yearday,
+        offset,
+    })
+}
+
+/// The DateTime at `days` since the epoch and `second_of_day` on the clocks
+/// of `zone`.
+pub(super) fn at_wall(
+    vm: &VM,
+    zone: Zone,
+    days: i64,
+    second_of_day: i64,
+    nanos: u32,
+    op: &'static str,
+) -> Result<DateTime, RuntimeError> {
+    let secs = resolve(vm, &zone, days * 86400 + second_of_day, op)?;
+    Ok(DateTime { secs, nanos, zone })
+}
+
+/// The DateTime as a string, see the top of the file.
+pub(super) fn render(vm: &VM, dt: &DateTime, op: &'static str) -> Result<String, RuntimeError> {
+    let c = civil(vm, dt, op)?;
+    if !(0..=9999).contains(&c.year) {
+        return Err(datetime_error(
+            vm,
+            op,
+            "date-time between years 0000 and 9999",
+            format!("year {}", c.year),
+        ));
+    }
+    let mut s = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        c.year, c.month, c.day, c.hour, c.minute, c.second
+    );
+    if dt.nanos > 0 {
+        let frac = format!("{:09}", dt.nanos);
+        s.push('.');
+        s.push_str(frac.trim_end_matches('0'));
+    }
+    match &dt.zone {
+        Zone::Utc => s.push('Z'),
+        Zone::Fixed(_) => s.push_str(&super::zone::format_offset(c.offset)),
+        Zone::Named(name) => {
+            s.push_str(&super::zone::format_offset(c.offset));
+            s.push('[');
+            s.push_str(name);
+            s.push(']');
+        }
+    }
+    Ok(s)
+}
+
+pub(super) fn make_datetime(
+    vm: &mut VM,
+    dt: &DateTime,
+    op: &'static str,
+) -> Result<Value, RuntimeError> {
+    let s = render(vm, dt, op)?;
+    make_string(vm, &s)
+}
+
+struct Scan<'a> {
+    s: &'a [u8],
+    i: usize,
+}
+
+impl Scan<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.i).copied()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        let hit = self.peek() == Some(b);
+        if hit {
+            self.i += 1;
+        }
+        hit
+    }
+
+    /// Exactly `n` digits.
+    fn num(&mut self, n: usize) -> Option<u32> {
+        let digits = self.s.get(self.i..self.i + n)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.i += n;
+        Some(digits.iter().fold(0, |acc, d| acc * 10 + (d - b'0') as u32))
+    }
+}
+
+/// ISO 8601 / RFC 3339 text: a date, optionally a time (`T` or a space
+/// between), an offset and a `[Zone]`. With neither offset nor zone it's UTC.
+fn parse_iso(vm: &mut VM, text: &str, op: &'static str) -> Result<DateTime, RuntimeError> {
+    let fail = |vm: &VM, why: &str| {
+        datetime_error(
+            vm,
+            op,
+            "ISO 8601 date-time",
+            format!("'{}' ({})", text, why),
+        )
+    };
+    let mut sc = Scan {
+        s: text.as_bytes(),
+        i: 0,
+    };
+    let (Some(year), true, Some(month), true, Some(day)) =
+        (sc.num(4), sc.eat(b'-'), sc.num(2), sc.eat(b'-'), sc.num(2))
+    else {
+        return Err(fail(vm, "expected YYYY-MM-DD"));
+    };
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year as i64, month) {
+        return Err(fail(vm, "no such date"));
+    }
+
+    let (mut hour, mut minute, mut second, mut nanos) = (0, 0, 0, 0);
+    if matches!(sc.peek(), Some(b'T' | b't' | b' ')) {
+        sc.i += 1;
+        let (Some(h), true, Some(m)) = (sc.num(2), sc.eat(b':'), sc.num(2)) else {
+            return Err(fail(vm, "expected HH:MM after the date"));
+        };
+        (hour, minute) = (h, m);
+        if sc.eat(b':') {
+            second = sc.num(2).ok_or_else(|| fail(vm, "expected seconds"))?;
+            if sc.eat(b'.') || sc.eat(b',') {
+                let start = sc.i;
+                while sc.peek().is_some_and(|b| b.is_ascii_digit()) {
+                    sc.i += 1;
+                }
+                let frac = &text[start..sc.i];
+                if frac.is_empty() {
+                    return Err(fail(vm, "expected digits after the decimal point"));
+                }
+                // past nanoseconds the digits are dropped
+                let frac = format!("{:0<9}", &frac[..frac.len().min(9)]);
+                nanos = frac.parse().unwrap_or(0);
+            }
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(fail(vm, "no such time"));
+        }
+    }
+
+    // None: no offset, Some(None): Z, Some(Some(o)): numeric
+    let offset = match sc.peek() {
+        Some(b'Z' | b'z') => {
+            sc.i += 1;
+            Some(None)
+        }
+        Some(b'+' | b'-') => {
+            let start = sc.i;
+            sc.i += 1;
+            while sc.peek().is_some_and(|b| b.is_ascii_digit() || b == b':') {
+                sc.i += 1;
+            }
+            let o = parse_offset(&text[start..sc.i]).ok_or_else(|| fail(vm, "bad offset"))?;
+            Some(Some(o))
+        }
+        _ => None,
+    };
+
+    let mut zone_name = None;
+    while sc.eat(b'[') {
+        let end = text[sc.i..]
+            .find(']')
+            .ok_or_else(|| fail(vm, "unclosed ["))?;
+        let annotation = text[sc.i..sc.i + end].trim_start_matches('!');
+        // key=value annotations (calendars and such) don't concern us
+        if !annotation.contains('=') {
+            zone_name = Some(annotation.to_string());
+        }
+        sc.i += end + 1;
+    }
+    if sc.i < text.len() {
+        return Err(fail(vm, &format!("unexpected '{}'", &text[sc.i..])));
+    }
+
+    let wall = days_from_civil(year as i64, month, day) * 86400
+        + (hour * 3600 + minute * 60 + second) as i64;
+    let zone = match &zone_name {
+        Some(name) => Some(parse_zone(vm, name, op)?),
+        None => None,
+    };
+    let dt = match (offset, zone) {
+        (Some(o), Some(zone)) => {
+            let o = o.unwrap_or(0);
+            let secs = wall - o as i64;
+            if offset_at(vm, &zone, secs, op)? != o {
+                return Err(fail(vm, "the offset doesn't match the zone"));
+            }
+            DateTime { secs, nanos, zone }
+        }
+        (Some(None), None) => DateTime {
+            secs: wall,
+            nanos,
+            zone: Zone::Utc,
+        },
+        (Some(Some(o)), None) => DateTime {
+            secs: wall - o as i64,
+            nanos,
+            zone: Zone::Fixed(o),
+        },
+        (None, Some(zone)) => DateTime {
+            secs: resolve(vm, &zone, wall, op)?,
+            nanos,
+            zone,
+        },
+        (None, None) => DateTime {
+            secs: wall,
+            nanos,
+            zone: Zone::Utc,
+        },
+    };
+    Ok(dt)
+}
+
+pub(super) fn get_datetime(
+    vm: &mut VM,
+    value: Value,
+    op: &'static str,
+) -> Result<DateTime, RuntimeError> {
+    let text = get_string(vm, value, op)?.to_string();
+    parse_iso(vm, &text, op)
+}
+
+/// A zone argument: null for UTC, else anything parse_zone takes.
+pub(super) fn get_zone(vm: &mut VM, value: Value, op: &'static str) -> Result<Zone, RuntimeError> {
+    if value.is_null() {
+        return Ok(Zone::Utc);
+    }
+    let name = get_string(vm, value, op)?.to_string();
+    parse_zone(vm, &name, op)
+}
+
+fn now(zone: Zone) -> DateTime {
+    let (secs, nanos) = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => {
+            let before = -(e.duration().as_nanos() as i128);
+            (
+                before.div_euclid(NANOS_PER_SEC as i128) as i64,
+                before.rem_euclid(NANOS_PER_SEC as i128) as u32,
+            )
+        }
+    };
+    DateTime { secs, nanos, zone }
+}
+
+/// Seconds as (whole seconds, nanoseconds), or None past what a DateTime
+/// can reach anyway.
+fn split_seconds(secs: f64) -> Option<(i64, u32)> {
+    if !secs.is_finite() || secs.abs() > 1e12 {
+        return None;
+    }
+    let total = (secs * 1e9).round() as i128;
+    Some((
+        total.div_euclid(NANOS_PER_SEC as i128) as i64,
+        total.rem_euclid(NANOS_PER_SEC as i128) as u32,
+    ))
+}
+
+fn seconds_between(a: &DateTime, b: &DateTime) -> f64 {
+    (a.secs - b.secs) as f64 + (a.nanos as f64 - b.nanos as f64) / 1e9
+}
+
+/// utc_now() - The current wall-clock time in UTC.
+fn native_utc_now(vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    make_datetime(vm, &now(Zone::Utc), "time.utc_now")
+}
+
+/// local_now() - The current wall-clock time in the system's zone.
+fn native_local_now(vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    let zone = local_zone(vm);
+    make_datetime(vm, &now(zone), "time.local_now")
+}
+
+/// local_zone() - Name of the system's zone, "UTC" when it has none.
+fn native_local_zone(vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    let name = local_zone(vm).name();
+    make_string(vm, &name)
+}
+
+/// instant() - Seconds on the monotonic clock, counted from some point
+/// before the first call. Unlike the wall clock it never jumps, so the
+/// difference of two instants is a reliable Duration.
+fn native_instant(_vm: &mut VM, _args: &[Value]) -> Result<Value, RuntimeError> {
+    static START: OnceLock<Instant> = OnceLock::new();
+    Ok(Value::float(
+        START.get_or_init(Instant::now).elapsed().as_secs_f64(),
+    ))
+}
+
+/// datetime(year, month, day, hour, minute, second, zone) - The DateTime
+/// whose clocks in `zone` read that. `second` may have a fraction.
+fn native_datetime(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.datetime";
+    let mut parts = [0i64; 5];
+    for (part, &arg) in parts.iter_mut().zip(args) {
+        *part = get_int(vm, arg, op)?;
+    }
+    let [year, month, day, hour, minute] = parts;
+    let second = get_number(vm, args[5], op)?;
+    let zone = get_zone(vm, args[6], op)?;
+    let valid_date = (0..=9999).contains(&year)
+        && (1..=12).contains(&month)
+        && day >= 1
+        && day <= days_in_month(year, month as u32) as i64;
+    let valid_time =
+        (0..24).contains(&hour) && (0..60).contains(&minute) && (0.0..60.0).contains(&second);
+    if !valid_date || !valid_time {
+        return Err(datetime_error(
+            vm,
+            op,
+            "a real date and time",
+            format!(
+                "{}-{:02}-{:02} {:02}:{:02}:{}",
+                year, month, day, hour, minute, second
+            ),
+        ));
+    }
+    let (whole, nanos) = split_seconds(second).unwrap_or((0, 0));
+    let days = days_from_civil(year, month as u32, day as u32);
+    let dt = at_wall(vm, zone, days, hour * 3600 + minute * 60 + whole, nanos, op)?;
+    make_datetime(vm, &dt, op)
+}
+
+/// from_timestamp(secs, zone) - The DateTime `secs` seconds after the Unix
+/// epoch, in `zone`.
+fn native_from_timestamp(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.from_timestamp";
+    let (secs, nanos) = match args[0].as_int() {
+        Some(secs) => (secs, 0),
+        None => {
+            let secs = get_number(vm, args[0], op)?;
+            split_seconds(secs)
+                .ok_or_else(|| datetime_error(vm, op, "timestamp in range", secs.to_string()))?
+        }
+    };
+    let zone = get_zone(vm, args[1], op)?;
+    make_datetime(vm, &DateTime { secs, nanos, zone }, op)
+}
+
+/// timestamp(dt) - Seconds since the Unix epoch, as a float.
+fn native_timestamp(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let dt = get_datetime(vm, args[0], "time.timestamp")?;
+    Ok(Value::float(dt.secs as f64 + dt.nanos as f64 / 1e9))
+}
+
+/// parse_datetime(s) - ISO 8601 / RFC 3339 text as a DateTime.
+fn native_parse_datetime(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.parse_datetime";
+    let dt = get_datetime(vm, args[0], op)?;
+    make_datetime(vm, &dt, op)
+}
+
+/// to_zone(dt, zone) - The same instant on the clocks of `zone`.
+fn native_to_zone(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.to_zone";
+    let mut dt = get_datetime(vm, args[0], op)?;
+    dt.zone = get_zone(vm, args[1], op)?;
+    make_datetime(vm, &dt, op)
+}
+
+/// field(dt, name) - One part of a DateTime as its zone sees it: year,
+/// month, day, hour, minute, second, nanosecond, weekday (0 is Sunday),
+/// yearday, offset (seconds east of UTC) or zone.
+fn native_field(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.field";
+    let dt = get_datetime(vm, args[0], op)?;
+    let name = get_string(vm, args[1], op)?.to_string();
+    let c = civil(vm, &dt, op)?;
+    let n = match name.as_str() {
+        "year" => c.year as i64,
+        "month" => c.month as i64,
+        "day" => c.day as i64,
+        "hour" => c.hour as i64,
+        "minute" => c.minute as i64,
+        "second" => c.second as i64,
+        "nanosecond" => dt.nanos as i64,
+        "weekday" => c.weekday as i64,
+        "yearday" => c.yearday as i64,
+        "offset" => c.offset as i64,
+        "zone" => return make_string(vm, &dt.zone.name()),
+        _ => {
+            return Err(datetime_error(
+                vm,
+                op,
+                "year, month, day, hour, minute, second, nanosecond, weekday, yearday, offset or zone",
+                format!("'{}'", name),
+            ));
+        }
+    };
+    Ok(Value::int(n))
+}
+
+/// add(dt, d) - The DateTime `d` seconds later (earlier when negative).
+/// That's elapsed time: across a DST change the clocks move by more or less.
+fn native_add(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.add";
+    let mut dt = get_datetime(vm, args[0], op)?;
+    let d = get_number(vm, args[1], op)?;
+    let (secs, nanos) = split_seconds(d)
+        .ok_or_else(|| datetime_error(vm, op, "duration in range", d.to_string()))?;
+    let total = dt.nanos as i64 + nanos as i64;
+    dt.secs += secs + total / NANOS_PER_SEC;
+    dt.nanos = (total % NANOS_PER_SEC) as u32;
+    make_datetime(vm, &dt, op)
+}
+
+fn checked_count(vm: &VM, value: Value, op: &'static str) -> Result<i64, RuntimeError> {
+    let n = get_int(vm, value, op)?;
+    if n.abs() > 10_000 * 366 {
+        return Err(datetime_error(
+            vm,
+            op,
+            "count within 10000 years",
+            n.to_string(),
+        ));
+    }
+    Ok(n)
+}
+
+/// add_days(dt, n) - The same clock time `n` calendar days later.
+fn native_add_days(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.add_days";
+    let dt = get_datetime(vm, args[0], op)?;
+    let n = checked_count(vm, args[1], op)?;
+    let c = civil(vm, &dt, op)?;
+    let days = days_from_civil(c.year as i64, c.month, c.day) + n;
+    let second_of_day = (c.hour * 3600 + c.minute * 60 + c.second) as i64;
+    let shifted = at_wall(vm, dt.zone, days, second_of_day, dt.nanos, op)?;
+    make_datetime(vm, &shifted, op)
+}
+
+/// add_months(dt, n) - The same clock time `n` months later. The day is
+/// cut back to the end of shorter months: Jan 31 + 1 month is Feb 28/29.
+fn native_add_months(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.add_months";
+    let dt = get_datetime(vm, args[0], op)?;
+    let n = checked_count(vm, args[1], op)?;
+    let c = civil(vm, &dt, op)?;
+    let index = c.year as i64 * 12 + (c.month as i64 - 1) + n;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    let day = c.day.min(days_in_month(year, month));
+    let days = days_from_civil(year, month, day);
+    let second_of_day = (c.hour * 3600 + c.minute * 60 + c.second) as i64;
+    let shifted = at_wall(vm, dt.zone, days, second_of_day, dt.nanos, op)?;
+    make_datetime(vm, &shifted, op)
+}
+
+/// diff(a, b) - Seconds from `b` to `a`, negative when `a` is earlier.
+fn native_diff(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let a = get_datetime(vm, args[0], "time.diff")?;
+    let b = get_datetime(vm, args[1], "time.diff")?;
+    Ok(Value::float(seconds_between(&a, &b)))
+}
+
+/// compare(a, b) - -1, 0 or 1 as `a` is before, at or after `b`, whatever
+/// their zones.
+fn native_compare(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let a = get_datetime(vm, args[0], "time.compare")?;
+    let b = get_datetime(vm, args[1], "time.compare")?;
+    let ord = (a.secs, a.nanos).cmp(&(b.secs, b.nanos));
+    Ok(Value::int(ord as i64))
+}
+
+/// Abbreviation of the zone at `dt` ("CET", "EDT"), for strftime's %Z.
+pub(super) fn abbreviation(
+    vm: &VM,
+    dt: &DateTime,
+    op: &'static str,
+) -> Result<String, RuntimeError> {
+    local_type(vm, &dt.zone, dt.secs, op).map(|(_, abbr)| abbr)
+}
@@ -0,0 +1,165 @@
+// Durations as text. parse_duration reads ISO 8601 (`PT1H30M`, `P2DT4H`)
+// and the short form duration_str writes (`1h30m`, `1.5s`, `250ms`).
+
+use super::datetime::datetime_error;
+use crate::stdlib::helpers::{get_number, get_string, make_string};
+use crate::vm::{VM, Value};
+use aelys_common::error::RuntimeError;
+
+const SHORT_UNITS: &[(&str, f64)] = &[
+    ("d", 86400.0),
+    ("h", 3600.0),
+    ("m", 60.0),
+    ("s", 1.0),
+    ("ms", 1e-3),
+    ("us", 1e-6),
+    ("µs", 1e-6),
+    ("ns", 1e-9),
+];
+
+/// A number at the start of `s` (digits with an optional fraction) and
+/// what follows it.
+fn leading_number(s: &str) -> Option<(f64, &str)> {
+    let end = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+        .unwrap_or(s.len());
+    let n = s[..end].replace(',', ".").parse().ok()?;
+    Some((n, &s[end..]))
+}
+
+fn parse_iso(mut s: &str) -> Result<f64, String> {
+    let mut total = 0.0;
+    let mut in_time = false;
+    let mut parts = 0;
+    while !s.is_empty() {
+        if let Some(rest) = s.strip_prefix(['T', 't']) {
+            in_time = true;
+            s = rest;
+            continue;
+        }
+        let (n, rest) = leading_number(s).ok_or("expected a number")?;
+        let unit = rest.chars().next().ok_or("missing unit")?;
+        let scale = match (in_time, unit.to_ascii_uppercase()) {
+            (false, 'W') => 604800.0,
+            (false, 'D') => 86400.0,
+            (false, 'Y' | 'M') => return Err("years and months have no fixed length".into()),
+            (true, 'H') => 3600.0,
+            (true, 'M') => 60.0,
+            (true, 'S') => 1.0,
+            _ => return Err(format!("unexpected '{}'", unit)),
+        };
+        total += n * scale;
+        parts += 1;
+        s = &rest[unit.len_utf8()..];
+    }
+    if parts == 0 {
+        return Err("no components".into());
+    }
+    Ok(total)
+}
+
+fn parse_short(mut s: &str) -> Result<f64, String> {
+    if s == "0" {
+        return Ok(0.0);
+    }
+    let mut total = 0.0;
+    while !s.is_empty() {
+        let (n, rest) = leading_number(s).ok_or("expected a number")?;
+        let rest = rest.trim_start();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let unit = &rest[..end];
+        let &(_, scale) = SHORT_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .ok_or_else(|| format!("unknown unit '{}'", unit))?;
+        total += n * scale;
+        s = rest[end..].trim_start();
+    }
+    Ok(total)
+}
+
+/// parse_duration(s) - Seconds in an ISO 8601 duration (`PT1H30M`) or a
+/// short one (`1h30m`, `2d`, `1.5s`, `250ms`, `10us`, `5ns`), with an
+/// optional sign.
+pub(super) fn native_parse_duration(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.parse_duration";
+    let text = get_string(vm, args[0], op)?.trim().to_string();
+    let (sign, body) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.strip_prefix('+').unwrap_or(&text)),
+    };
+    let parsed = match body.strip_prefix(['P', 'p']) {
+        Some(iso) => parse_iso(iso),
+        None if body.is_empty() => Err("empty".to_string()),
+        None => parse_short(body),
+    };
+    match parsed {
+        Ok(secs) => Ok(Value::float(sign * secs)),
+        Err(why) => Err(datetime_error(
+            vm,
+            op,
+            "duration like PT1H30M or 1h30m",
+            format!("'{}' ({})", text, why),
+        )),
+    }
+}
+
+/// `whole.frac`, `frac` being `digits` decimals, without trailing zeros.
+fn with_fraction(whole: u128, frac: u128, digits: usize) -> String {
+    let frac = format!("{:0width$}", frac, width = digits);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, frac)
+    }
+}
+
+/// duration_str(d) - `d` seconds written short: `1h30m`, `2d4h`, `1.5s`,
+/// `250ms`. parse_duration reads it back.
+pub(super) fn native_duration_str(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.duration_str";
+    let d = get_number(vm, args[0], op)?;
+    if !d.is_finite() || d.abs() > 1e13 {
+        return Err(datetime_error(vm, op, "duration in range", d.to_string()));
+    }
+    let ns = (d.abs() * 1e9).round() as u128;
+    let mut s = String::new();
+    if d < 0.0 && ns > 0 {
+        s.push('-');
+    }
+    if ns == 0 {
+        s.push_str("0s");
+    } else if ns < 1_000 {
+        s.push_str(&format!("{}ns", ns));
+    } else if ns < 1_000_000 {
+        s.push_str(&with_fraction(ns / 1_000, ns % 1_000, 3));
+        s.push_str("us");
+    } else if ns < 1_000_000_000 {
+        s.push_str(&with_fraction(ns / 1_000_000, ns % 1_000_000, 6));
+        s.push_str("ms");
+    } else {
+        let secs = ns / 1_000_000_000;
+        for (n, unit) in [
+            (secs / 86400, "d"),
+            (secs / 3600 % 24, "h"),
+            (secs / 60 % 60, "m"),
+        ] {
+            if n > 0 {
+                s.push_str(&format!("{}{}", n, unit));
+            }
+        }
+        let rest = ns % 60_000_000_000;
+        if rest > 0 {
+            s.push_str(&with_fraction(
+                rest / 1_000_000_000,
+                rest % 1_000_000_000,
+                9,
+            ));
+            s.push('s');
+        }
+    }
+    make_string(vm, &s)
+}
@@ -6,6 +6,11 @@ use crate::vm::{VM, Value};
 use aelys_common::error::{RuntimeError, RuntimeErrorKind};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+mod datetime;
+mod duration;
+mod pattern;
+mod zone;
+
 pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
     let mut all_exports = Vec::new();
     let mut native_functions = Vec::new();
@@ -42,12 +47,22 @@ pub fn register(vm: &mut VM) -> Result<StdModuleExports, RuntimeError> {
     reg_fn!("date", 0, native_date);
     reg_fn!("time_str", 0, native_time_str);
 
+    for &(name, arity, func) in datetime::NATIVES {
+        reg_fn!(name, arity, func);
+    }
+
     Ok(StdModuleExports {
         all_exports,
         native_functions,
     })
 }
 
+/// Whether `name` is one of the DateTime/Duration functions, which stay
+/// behind `time.` when the module is auto-registered.
+pub(crate) fn is_qualified_only(name: &str) -> bool {
+    datetime::NATIVES.iter().any(|&(n, _, _)| n == name)
+}
+
 fn time_error(vm: &VM, op: &'static str, msg: String) -> RuntimeError {
     vm.runtime_error(RuntimeErrorKind::TypeError {
         operation: op,
@@ -0,0 +1,384 @@
+// strftime/strptime patterns, with the specifiers time.format knows plus a
+// few more:
+//
+//   %Y year  %y year % 100  %m month  %d day  %e day, space-padded
+//   %H hour  %I hour 1-12  %p AM/PM  %M minute  %S second  %f microseconds
+//   %j day of year  %w weekday, 0 is Sunday  %a %A weekday name
+//   %b %B month name  %z +hhmm  %:z +hh:mm  %Z abbreviation (CET, EDT)
+//   %s seconds since the epoch  %F %Y-%m-%d  %T %H:%M:%S  %% a percent sign
+
+use super::datetime::{
+    DateTime, abbreviation, at_wall, civil, datetime_error, days_from_civil, days_in_month,
+    get_datetime, get_zone, make_datetime,
+};
+use super::zone::{Zone, parse_offset};
+use crate::stdlib::helpers::{get_string, make_string};
+use crate::vm::{VM, Value};
+use aelys_common::error::RuntimeError;
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+enum Piece {
+    Literal(char),
+    // the letter after %, or ":z"
+    Spec(String),
+}
+
+/// The pattern split into literal text and specifiers, %F and %T spelled out.
+fn specifiers(vm: &VM, pattern: &str, op: &'static str) -> Result<Vec<Piece>, RuntimeError> {
+    let mut pieces = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            pieces.push(Piece::Literal(c));
+            continue;
+        }
+        match chars.next() {
+            Some('%') => pieces.push(Piece::Literal('%')),
+            Some('F') => pieces.extend(specifiers(vm, "%Y-%m-%d", op)?),
+            Some('T') => pieces.extend(specifiers(vm, "%H:%M:%S", op)?),
+            Some(':') if chars.peek() == Some(&'z') => {
+                chars.next();
+                pieces.push(Piece::Spec(":z".to_string()));
+            }
+            Some(c) if "YymdeHIpMSfjwaAbBzZs".contains(c) => {
+                pieces.push(Piece::Spec(c.to_string()))
+            }
+            other => {
+                return Err(datetime_error(
+                    vm,
+                    op,
+                    "known format specifier",
+                    format!("%{}", other.map(String::from).unwrap_or_default()),
+                ));
+            }
+        }
+    }
+    Ok(pieces)
+}
+
+/// strftime(dt, pattern) - A DateTime written with `pattern`, as its zone
+/// sees it.
+pub(super) fn native_strftime(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.strftime";
+    let dt = get_datetime(vm, args[0], op)?;
+    let pattern = get_string(vm, args[1], op)?.to_string();
+    let pieces = specifiers(vm, &pattern, op)?;
+    let c = civil(vm, &dt, op)?;
+    let offset = |colon: bool| {
+        let sign = if c.offset < 0 { '-' } else { '+' };
+        let a = c.offset.unsigned_abs();
+        let sep = if colon { ":" } else { "" };
+        format!("{}{:02}{}{:02}", sign, a / 3600, sep, a / 60 % 60)
+    };
+    let mut out = String::new();
+    for piece in pieces {
+        let spec = match piece {
+            Piece::Literal(ch) => {
+                out.push(ch);
+                continue;
+            }
+            Piece::Spec(spec) => spec,
+        };
+        let text = match spec.as_str() {
+            "Y" => format!("{:04}", c.year),
+            "y" => format!("{:02}", c.year % 100),
+            "m" => format!("{:02}", c.month),
+            "d" => format!("{:02}", c.day),
+            "e" => format!("{:>2}", c.day),
+            "H" => format!("{:02}", c.hour),
+            "I" => format!("{:02}", (c.hour + 11) % 12 + 1),
+            "p" => (if c.hour < 12 { "AM" } else { "PM" }).to_string(),
+            "M" => format!("{:02}", c.minute),
+            "S" => format!("{:02}", c.second),
+            "f" => format!("{:06}", dt.nanos / 1000),
+            "j" => format!("{:03}", c.yearday),
+            "w" => c.weekday.to_string(),
+            "a" => WEEKDAYS[c.weekday as usize][..3].to_string(),
+            "A" => WEEKDAYS[c.weekday as usize].to_string(),
+            "b" => MONTHS[c.month as usize - 1][..3].to_string(),
+            "B" => MONTHS[c.month as usize - 1].to_string(),
+            "z" => offset(false),
+            ":z" => offset(true),
+            "Z" => abbreviation(vm, &dt, op)?,
+            "s" => dt.secs.to_string(),
+            _ => unreachable!("specifiers() only lets known ones through"),
+        };
+        out.push_str(&text);
+    }
+    make_string(vm, &out)
+}
+
+/// What strptime has read so far.
+#[derive(Default)]
+struct Fields {
+    year: Option<i64>,
+    month: Option<i64>,
+    day: Option<i64>,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    nanos: u32,
+    // Some(true) for PM, hour is then on a 12-hour clock
+    pm: Option<bool>,
+    // Some(None) for Z
+    offset: Option<Option<i32>>,
+    epoch: Option<i64>,
+}
+
+struct Input<'a> {
+    s: &'a str,
+    i: usize,
+}
+
+impl Input<'_> {
+    fn rest(&self) -> &str {
+        &self.s[self.i..]
+    }
+
+    /// Between `min` and `max` digits.
+    fn digits(&mut self, min: usize, max: usize) -> Option<i64> {
+        let len = self
+            .rest()
+            .bytes()
+            .take(max)
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if len < min {
+            return None;
+        }
+        let n = self.rest()[..len].parse().ok()?;
+        self.i += len;
+        Some(n)
+    }
+
+    /// Index in `names` of the name (or its first three letters) coming
+    /// next, ignoring case.
+    fn name(&mut self, names: &[&str]) -> Option<usize> {
+        let rest = self.rest().to_ascii_lowercase();
+        for (index, name) in names.iter().enumerate() {
+            let name = name.to_ascii_lowercase();
+            for candidate in [&name[..], &name[..3]] {
+                if rest.starts_with(candidate) {
+                    self.i += candidate.len();
+                    return Some(index);
+                }
+            }
+        }
+        None
+    }
+}
+
+fn read(input: &mut Input, spec: &str, f: &mut Fields) -> Option<()> {
+    match spec {
+        "Y" => f.year = Some(input.digits(4, 4)?),
+        "y" => {
+            let y = input.digits(2, 2)?;
+            f.year = Some(if y < 69 { 2000 + y } else { 1900 + y });
+        }
+        "m" => f.month = Some(input.digits(1, 2)?),
+        "d" => f.day = Some(input.digits(1, 2)?),
+        "e" => {
+            if input.rest().starts_with(' ') {
+                input.i += 1;
+            }
+            f.day = Some(input.digits(1, 2)?);
+        }
+        "H" => f.hour = input.digits(1, 2)?,
+        "I" => {
+            f.hour = input.digits(1, 2)?;
+            f.pm.get_or_insert(false);
+        }
+        "p" => {
+            let upper = input.rest().get(..2)?.to_ascii_uppercase();
+            f.pm = Some(match upper.as_str() {
+                "AM" => false,
+                "PM" => true,
+                _ => return None,
+            });
+            input.i += 2;
+        }
+        "M" => f.minute = input.digits(1, 2)?,
+        "S" => f.second = input.digits(1, 2)?,
+        "f" => {
+            let start = input.i;
+            input.digits(1, 9)?;
+            let frac = format!("{:0<9}", &input.s[start..input.i]);
+            f.nanos = frac.parse().ok()?;
+        }
+        "j" => {
+            input.digits(1, 3)?;
+        }
+        "w" => {
+            input.digits(1, 1)?;
+        }
+        "a" | "A" => {
+            input.name(&WEEKDAYS)?;
+        }
+        "b" | "B" => f.month = Some(input.name(&MONTHS)? as i64 + 1),
+        "z" | ":z" => {
+            if input.rest().starts_with(['Z', 'z']) {
+                input.i += 1;
+                f.offset = Some(None);
+            } else {
+                let len = input
+                    .rest()
+                    .char_indices()
+                    .skip(1)
+                    .find(|&(_, c)| !c.is_ascii_digit() && c != ':')
+                    .map_or(input.rest().len(), |(i, _)| i);
+                f.offset = Some(Some(parse_offset(&input.rest()[..len])?));
+                input.i += len;
+            }
+        }
+        "s" => {
+            let negative = input.rest().starts_with('-');
+            if negative {
+                input.i += 1;
+            }
+            let n = input.digits(1, 12)?;
+            f.epoch = Some(if negative { -n } else { n });
+        }
+        // abbreviations are ambiguous (IST is three zones), so %Z is
+        // matched but not used
+        "Z" => {
+            let len = input
+                .rest()
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(input.rest().len());
+            if len == 0 {
+                return None;
+            }
+            input.i += len;
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+/// strptime(s, pattern, zone) - Read a DateTime out of `s`. Whitespace in
+/// the pattern matches any amount of it. Without %z or %s the clock time is
+/// taken in `zone` (null is UTC); with them `zone` only says which zone to
+/// show the result in.
+pub(super) fn native_strptime(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    let op = "time.strptime";
+    let text = get_string(vm, args[0], op)?.to_string();
+    let pattern = get_string(vm, args[1], op)?.to_string();
+    let zone = if args[2].is_null() {
+        None
+    } else {
+        Some(get_zone(vm, args[2], op)?)
+    };
+    let pieces = specifiers(vm, &pattern, op)?;
+    let mismatch = |vm: &VM, at: usize, why: &str| {
+        datetime_error(
+            vm,
+            op,
+            "text matching the pattern",
+            format!("'{}' at column {} ({})", text, at + 1, why),
+        )
+    };
+
+    let mut input = Input { s: &text, i: 0 };
+    let mut f = Fields::default();
+    for piece in pieces {
+        match piece {
+            Piece::Literal(c) if c.is_whitespace() => {
+                input.i = text.len() - input.rest().trim_start().len();
+            }
+            Piece::Literal(c) => {
+                if !input.rest().starts_with(c) {
+                    return Err(mismatch(vm, input.i, &format!("expected '{}'", c)));
+                }
+                input.i += c.len_utf8();
+            }
+            Piece::Spec(spec) => {
+                let at = input.i;
+                if read(&mut input, &spec, &mut f).is_none() {
+                    return Err(mismatch(vm, at, &format!("doesn't fit %{}", spec)));
+                }
+            }
+        }
+    }
+    if !input.rest().is_empty() {
+        return Err(mismatch(vm, input.i, "text left over"));
+    }
+
+    if let Some(secs) = f.epoch {
+        let dt = DateTime {
+            secs,
+            nanos: f.nanos,
+            zone: zone.unwrap_or(Zone::Utc),
+        };
+        return make_datetime(vm, &dt, op);
+    }
+
+    let (year, month, day) = (
+        f.year.unwrap_or(1970),
+        f.month.unwrap_or(1),
+        f.day.unwrap_or(1),
+    );
+    let mut hour = f.hour;
+    if let Some(pm) = f.pm {
+        if !(1..=12).contains(&hour) {
+            return Err(mismatch(vm, 0, "12-hour clock hour out of range"));
+        }
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+    let valid = (1..=12).contains(&month)
+        && day >= 1
+        && day <= days_in_month(year, month as u32) as i64
+        && hour < 24
+        && f.minute < 60
+        && f.second < 60;
+    if !valid {
+        return Err(mismatch(vm, 0, "no such date or time"));
+    }
+    let days = days_from_civil(year, month as u32, day as u32);
+    let second_of_day = hour * 3600 + f.minute * 60 + f.second;
+    let dt = match f.offset {
+        Some(offset) => {
+            let secs = days * 86400 + second_of_day - offset.unwrap_or(0) as i64;
+            let read_zone = match offset {
+                None => Zone::Utc,
+                Some(o) => Zone::Fixed(o),
+            };
+            DateTime {
+                secs,
+                nanos: f.nanos,
+                zone: zone.unwrap_or(read_zone),
+            }
+        }
+        None => at_wall(
+            vm,
+            zone.unwrap_or(Zone::Utc),
+            days,
+            second_of_day,
+            f.nanos,
+            op,
+        )?,
+    };
+    make_datetime(vm, &dt, op)
+}
@@ -0,0 +1,211 @@
+// time zones - UTC, fixed offsets, and IANA zones read from the system's
+// tzdata (TZif files under $TZDIR or /usr/share/zoneinfo). Parsed zones are
+// kept in the VM's tz_cache, so a name is read from disk once.
+
+use crate::vm::VM;
+use aelys_common::error::{RuntimeError, RuntimeErrorKind};
+use std::path::Path;
+
+const TZDATA_DIRS: &[&str] = &[
+    "/usr/share/zoneinfo",
+    "/usr/lib/zoneinfo",
+    "/usr/share/lib/zoneinfo",
+    "/etc/zoneinfo",
+];
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum Zone {
+    Utc,
+    // seconds east of UTC
+    Fixed(i32),
+    // an IANA name, always present in vm.tz_cache
+    Named(String),
+}
+
+impl Zone {
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Zone::Utc => "UTC".to_string(),
+            Zone::Fixed(offset) => format_offset(*offset),
+            Zone::Named(name) => name.clone(),
+        }
+    }
+}
+
+fn zone_error(vm: &VM, op: &'static str, got: String) -> RuntimeError {
+    vm.runtime_error(RuntimeErrorKind::TypeError {
+        operation: op,
+        expected: "UTC, \"local\", an offset like +05:30 or an IANA zone name",
+        got,
+    })
+}
+
+/// "UTC", "local", "+05:30" or an IANA name like "Europe/Paris".
+pub(crate) fn parse_zone(vm: &mut VM, name: &str, op: &'static str) -> Result<Zone, RuntimeError> {
+    match name {
+        "UTC" | "utc" | "Z" | "z" => Ok(Zone::Utc),
+        "local" => Ok(local_zone(vm)),
+        _ if name.starts_with(['+', '-']) => parse_offset(name)
+            .map(Zone::Fixed)
+            .ok_or_else(|| zone_error(vm, op, format!("bad offset '{}'", name))),
+        _ => {
+            load(vm, name).map_err(|e| zone_error(vm, op, e))?;
+            Ok(Zone::Named(name.to_string()))
+        }
+    }
+}
+
+/// `+05:30`, `-0800`, `+05` or `+05:30:15` as seconds east of UTC.
+pub(crate) fn parse_offset(s: &str) -> Option<i32> {
+    let sign = match s.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let rest = &s[1..];
+    let digits: String = rest.chars().filter(|&c| c != ':').collect();
+    let colons = rest.len() - digits.len();
+    let well_formed = match digits.len() {
+        2 => colons == 0,
+        4 => colons == 0 || rest.as_bytes().get(2) == Some(&b':') && colons == 1,
+        6 => rest.as_bytes().get(2) == Some(&b':') && rest.as_bytes().get(5) == Some(&b':'),
+        _ => false,
+    };
+    if !well_formed || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let part = |i: usize| {
+        digits
+            .get(i..i + 2)
+            .map_or(0, |p| p.parse::<i32>().unwrap_or(0))
+    };
+    let (h, m, sec) = (part(0), part(2), part(4));
+    if h > 23 || m > 59 || sec > 59 {
+        return None;
+    }
+    Some(sign * (h * 3600 + m * 60 + sec))
+}
+
+/// `+05:30`, with seconds only when there are some.
+pub(crate) fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let a = offset.unsigned_abs();
+    if a.is_multiple_of(60) {
+        format!("{}{:02}:{:02}", sign, a / 3600, a / 60 % 60)
+    } else {
+        format!("{}{:02}:{:02}:{:02}", sign, a / 3600, a / 60 % 60, a % 60)
+    }
+}
+
+/// The system's zone: $TZ, else what /etc/localtime points to, else
+/// /etc/timezone. UTC when none of those names a zone we can read.
+pub(crate) fn local_zone(vm: &mut VM) -> Zone {
+    let mut candidates = Vec::new();
+    if let Ok(tz) = std::env::var("TZ") {
+        candidates.push(tz.trim_start_matches(':').to_string());
+    }
+    if let Ok(target) = std::fs::read_link("/etc/localtime")
+        && let Some((_, name)) = target.to_string_lossy().split_once("zoneinfo/")
+    {
+        candidates.push(name.to_string());
+    }
+    if let Ok(name) = std::fs::read_to_string("/etc/timezone") {
+        candidates.push(name.trim().to_string());
+    }
+    for name in candidates {
+        if matches!(name.as_str(), "UTC" | "Etc/UTC" | "UTC0") {
+            return Zone::Utc;
+        }
+        if load(vm, &name).is_ok() {
+            return Zone::Named(name);
+        }
+    }
+    Zone::Utc
+}
+
+fn load(vm: &mut VM, name: &str) -> Result<(), String> {
+    if vm.tz_cache.contains_key(name) {
+        return Ok(());
+    }
+    let valid = !name.is_empty()
+        && name.split('/').all(|part| {
+            !part.is_empty()
+                && part != "."
+                && part != ".."
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"_+-.".contains(&b))
+        });
+    if !valid {
+        return Err(format!("invalid zone name '{}'", name));
+    }
+    let dirs = std::env::var("TZDIR")
+        .into_iter()
+        .chain(TZDATA_DIRS.iter().map(|d| d.to_string()));
+    for dir in dirs {
+        let Ok(bytes) = std::fs::read(Path::new(&dir).join(name)) else {
+            continue;
+        };
+        let zone = tz::TimeZone::from_tz_data(&bytes)
+            .map_err(|e| format!("'{}' ({}/{}: {})", name, dir, name, e))?;
+        vm.tz_cache.insert(name.to_string(), zone);
+        return Ok(());
+    }
+    Err(format!("unknown zone '{}'", name))
+}
+
+/// Offset from UTC and abbreviation in force at `secs` since the epoch.
+pub(crate) fn local_type(
+    vm: &VM,
+    zone: &Zone,
+    secs: i64,
+    op: &'static str,
+) -> Result<(i32, String), RuntimeError> {
+    match zone {
+        Zone::Utc => Ok((0, "UTC".to_string())),
+        Zone::Fixed(offset) => Ok((*offset, format_offset(*offset))),
+        Zone::Named(name) => vm
+            .tz_cache
+            .get(name)
+            .ok_or_else(|| format!("zone '{}' not loaded", name))
+            .and_then(|tz| {
+                tz.find_local_time_type(secs)
+                    .map(|t| (t.ut_offset(), t.time_zone_designation().to_string()))
+                    .map_err(|e| format!("{}: {}", name, e))
+            })
+            .map_err(|e| zone_error(vm, op, e)),
+    }
+}
+
+pub(crate) fn offset_at(
+    vm: &VM,
+    zone: &Zone,
+    secs: i64,
+    op: &'static str,
+) -> Result<i32, RuntimeError> {
+    local_type(vm, zone, secs, op).map(|(offset, _)| offset)
+}
+
+/// The instant at which clocks in `zone` read `wall` (seconds since the
+/// epoch as if the zone were UTC). A time skipped by a forward jump lands
+/// that far past the jump, a time that happens twice is the earlier one.
+pub(crate) fn resolve(
+    vm: &VM,
+    zone: &Zone,
+    wall: i64,
+    op: &'static str,
+) -> Result<i64, RuntimeError> {
+    let Zone::Named(_) = zone else {
+        return Ok(wall - offset_at(vm, zone, 0, op)? as i64);
+    };
+    let before = offset_at(vm, zone, wall - 86400, op)?;
+    let after = offset_at(vm, zone, wall + 86400, op)?;
+    let mut found: Option<i64> = None;
+    for offset in [before, after] {
+        let t = wall - offset as i64;
+        if offset_at(vm, zone, t, op)? == offset {
+            found = Some(found.map_or(t, |f| f.min(t)));
+        }
+    }
+    Ok(found.unwrap_or(wall - before as i64))
+}
@@ -76,6 +76,24 @@ fn apply_vm_arg(
             config.capabilities.allow_exec = enabled;
             Ok(())
         }
+        "seed" => {
+            let seed = raw_value.parse().map_err(|_| VmArgsError::InvalidValue {
+                arg: raw_arg.to_string(),
+                value: raw_value.to_string(),
+                reason: "expected a non-negative integer".to_string(),
+            })?;
+            config.seed = Some(seed);
+            Ok(())
+        }
+        "max-threads" => {
+            let max = raw_value.parse().map_err(|_| VmArgsError::InvalidValue {
+                arg: raw_arg.to_string(),
+                value: raw_value.to_string(),
+                reason: "expected a non-negative integer".to_string(),
+            })?;
+            config.max_threads = max;
+            Ok(())
+        }
         "trusted" => {
             let enabled = parse_bool(raw_value, raw_arg)?;
             *trusted_enabled |= enabled;
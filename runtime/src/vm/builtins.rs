@@ -1,5 +1,6 @@
 use super::VM;
 use super::Value;
+use super::{GcRef, ObjectKind};
 use aelys_common::error::{RuntimeError, RuntimeErrorKind};
 
 // core builtins only - everything else is stdlib
@@ -22,6 +23,12 @@ pub fn register_builtins(vm: &mut VM) -> Result<(), RuntimeError> {
     let tostring_fn = vm.alloc_native("__tostring", 1, builtin_tostring)?;
     vm.set_global("__tostring".to_string(), Value::ptr(tostring_fn.index()));
 
+    let assert_fn = vm.alloc_native("assert", 1, builtin_assert)?;
+    vm.set_global("assert".to_string(), Value::ptr(assert_fn.index()));
+
+    let assert_eq_fn = vm.alloc_native("assert_eq", 2, builtin_assert_eq)?;
+    vm.set_global("assert_eq".to_string(), Value::ptr(assert_eq_fn.index()));
+
     Ok(())
 }
 
@@ -145,3 +152,68 @@ pub fn builtin_tostring(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeErr
     let str_ref = vm.alloc_string(&s)?;
     Ok(Value::ptr(str_ref.index()))
 }
+
+// same truthiness as `if`: only null and false fail
+pub fn builtin_assert(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    if args[0].is_null() || args[0].as_bool() == Some(false) {
+        return Err(vm.runtime_error(RuntimeErrorKind::AssertionFailed {
+            left: None,
+            right: None,
+        }));
+    }
+    Ok(Value::null())
+}
+
+pub fn builtin_assert_eq(vm: &mut VM, args: &[Value]) -> Result<Value, RuntimeError> {
+    if structurally_equal(vm, args[0], args[1]) {
+        return Ok(Value::null());
+    }
+    Err(vm.runtime_error(RuntimeErrorKind::AssertionFailed {
+        left: Some(describe(vm, args[0])),
+        right: Some(describe(vm, args[1])),
+    }))
+}
+
+// `==` plus element-wise comparison of arrays and vecs, so tests can compare
+// against a literal
+fn structurally_equal(vm: &VM, lhs: Value, rhs: Value) -> bool {
+    if lhs == rhs {
+        return true;
+    }
+    let (Some(lp), Some(rp)) = (lhs.as_ptr(), rhs.as_ptr()) else {
+        return false;
+    };
+    let (Some(lo), Some(ro)) = (vm.heap().get(GcRef::new(lp)), vm.heap().get(GcRef::new(rp)))
+    else {
+        return false;
+    };
+    let elements = |kind: &ObjectKind| -> Option<Vec<Value>> {
+        match kind {
+            ObjectKind::Array(a) => Some((0..a.len()).filter_map(|i| a.get(i)).collect()),
+            ObjectKind::Vec(v) => Some((0..v.len()).filter_map(|i| v.get(i)).collect()),
+            _ => None,
+        }
+    };
+    match (&lo.kind, &ro.kind) {
+        (ObjectKind::String(ls), ObjectKind::String(rs)) => ls == rs,
+        (left, right) => match (elements(left), elements(right)) {
+            (Some(l), Some(r)) => {
+                l.len() == r.len()
+                    && l.iter()
+                        .zip(&r)
+                        .all(|(a, b)| structurally_equal(vm, *a, *b))
+            }
+            _ => false,
+        },
+    }
+}
+
+// strings are quoted so `"1"` and `1` don't look the same in the report
+fn describe(vm: &VM, value: Value) -> String {
+    let text = vm.value_to_string(value);
+    if vm.value_type_name(value) == "string" {
+        format!("{:?}", text)
+    } else {
+        text
+    }
+}
@@ -33,6 +33,7 @@ impl VM {
     pub(super) fn call_function_kind(
         &mut self,
         func_ref: GcRef,
+        base: usize,
         args: &[Value],
         arity: u8,
         num_registers: u8,
@@ -52,13 +53,13 @@ impl VM {
 
         self.ensure_function_verified(func_ref)?;
 
-        let needed = num_registers as usize;
+        let needed = base + num_registers as usize;
         if needed > self.registers.len() {
             self.registers.resize(needed, Value::null());
         }
 
         for (i, arg) in args.iter().enumerate() {
-            self.registers[i] = *arg;
+            self.registers[base + i] = *arg;
         }
 
         let gmap_id = self.global_mapping_id_for_layout(&global_layout);
@@ -66,7 +67,7 @@ impl VM {
 
         let mut frame = CallFrame::new(
             func_ref,
-            0,
+            base,
             bytecode_ptr,
             bytecode_len,
             constants_ptr,
@@ -83,6 +84,7 @@ impl VM {
     pub(super) fn call_closure_kind(
         &mut self,
         inner_func_ref: GcRef,
+        base: usize,
         args: &[Value],
         arity: u8,
         num_registers: u8,
@@ -103,13 +105,13 @@ impl VM {
 
         self.ensure_function_verified(inner_func_ref)?;
 
-        let needed = num_registers as usize;
+        let needed = base + num_registers as usize;
         if needed > self.registers.len() {
             self.registers.resize(needed, Value::null());
         }
 
         for (i, arg) in args.iter().enumerate() {
-            self.registers[i] = *arg;
+            self.registers[base + i] = *arg;
         }
 
         self.current_upvalues = upvalues;
@@ -119,7 +121,7 @@ impl VM {
 
         let mut frame = CallFrame::with_upvalues(
             inner_func_ref,
-            0,
+            base,
             0,
             bytecode_ptr,
             bytecode_len,
@@ -1,4 +1,4 @@
-use super::super::{GcRef, ObjectKind, VM, Value};
+use super::super::{GcRef, MAX_REGISTERS, ObjectKind, VM, Value};
 use super::kinds::FuncKind;
 use aelys_common::error::{RuntimeError, RuntimeErrorKind};
 use std::sync::Arc;
@@ -6,6 +6,57 @@ use std::sync::Arc;
 impl VM {
     /// Call a function value with the given arguments.
     pub fn call_value(&mut self, func_value: Value, args: &[Value]) -> Result<Value, RuntimeError> {
+        self.call_value_at(func_value, args, 0)
+    }
+
+    /// Call a function value from inside a native function, while the Aelys
+    /// code that called the native is still on the stack. Its frames are
+    /// parked (still GC roots) and the callee runs in the registers above
+    /// them, so the interpreter loop returns once the callee does.
+    pub fn call_value_from_native(
+        &mut self,
+        func_value: Value,
+        args: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        let Some(caller) = self.frames.last().cloned() else {
+            return self.call_value(func_value, args);
+        };
+        let base = caller.base + caller.num_registers as usize;
+        if base + u8::MAX as usize > MAX_REGISTERS {
+            return Err(self.runtime_error(RuntimeErrorKind::StackOverflow));
+        }
+
+        self.sync_current_function_globals();
+        let parked_frames = self.parked_frames.len();
+        let parked_upvalues = self.parked_upvalues.len();
+        let frames = std::mem::take(&mut self.frames);
+        self.parked_frames.extend(frames);
+        let upvalues = std::mem::take(&mut self.current_upvalues);
+        self.parked_upvalues.extend_from_slice(&upvalues);
+
+        let result = self.call_value_at(func_value, args, base);
+
+        // same hand-back as a Return into a caller with another global layout
+        if self.current_global_mapping_id != caller.global_mapping_id
+            && let Some(ptr) = func_value.as_ptr()
+        {
+            self.sync_function_globals(GcRef::new(ptr));
+        }
+        // an error leaves the callee's frames behind
+        self.clear_frames();
+        self.frames = self.parked_frames.split_off(parked_frames);
+        self.parked_upvalues.truncate(parked_upvalues);
+        self.current_upvalues = upvalues;
+        self.prepare_globals_for_function(caller.function);
+        result
+    }
+
+    fn call_value_at(
+        &mut self,
+        func_value: Value,
+        args: &[Value],
+        base: usize,
+    ) -> Result<Value, RuntimeError> {
         let func_ptr = func_value.as_ptr().ok_or_else(|| {
             self.runtime_error(RuntimeErrorKind::NotCallable(
                 self.value_type_name(func_value).to_string(),
@@ -37,6 +88,7 @@ impl VM {
                 global_layout,
             } => self.call_function_kind(
                 func_ref,
+                base,
                 args,
                 arity,
                 num_registers,
@@ -58,6 +110,7 @@ impl VM {
                 global_layout,
             } => self.call_closure_kind(
                 inner_func_ref,
+                base,
                 args,
                 arity,
                 num_registers,
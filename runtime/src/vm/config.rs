@@ -31,11 +31,20 @@ pub struct VmConfig {
     pub allow_hot_reload: bool,
     pub allowed_caps: HashSet<String>,
     pub denied_caps: HashSet<String>,
+    /// Record line and branch coverage, see `VM::take_coverage`.
+    pub coverage: bool,
+    /// Seed of the default random generator (std.random, sys.random,
+    /// math.randint). Seeded from the OS when None.
+    pub seed: Option<u64>,
+    /// How many std.thread threads may run at once, counting those started
+    /// by other threads.
+    pub max_threads: usize,
 }
 
 impl VmConfig {
     pub const DEFAULT_MAX_HEAP_BYTES: u64 = 4 * 1024 * 1024 * 1024;
     pub const MIN_HEAP_BYTES: u64 = 1024 * 1024;
+    pub const DEFAULT_MAX_THREADS: usize = 64;
 
     pub fn new(max_heap_bytes: u64) -> Result<Self, VmConfigError> {
         let config = Self {
@@ -44,6 +53,9 @@ impl VmConfig {
             allow_hot_reload: false,
             allowed_caps: HashSet::new(),
             denied_caps: HashSet::new(),
+            coverage: false,
+            seed: None,
+            max_threads: Self::DEFAULT_MAX_THREADS,
         };
         config.validate()?;
         Ok(config)
@@ -97,6 +109,9 @@ impl Default for VmConfig {
             allow_hot_reload: false,
             allowed_caps: HashSet::new(),
             denied_caps: HashSet::new(),
+            coverage: false,
+            seed: None,
+            max_threads: Self::DEFAULT_MAX_THREADS,
         }
     }
 }
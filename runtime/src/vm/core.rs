@@ -1,12 +1,17 @@
 use super::config::VmConfig;
+use super::coverage::Coverage;
+use super::debug::Debugger;
 use super::frame::CallFrame;
 use super::manual_heap::ManualHeap;
+use super::output::OutputSink;
+use super::profile::Profiler;
 use super::{GcRef, Heap, NativeFunctionImpl, Value};
 use crate::native::NativeModule;
 use crate::stdlib::Resource;
 use aelys_syntax::Source;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
 
 pub const MAX_FRAMES: usize = 1024;
 pub const MAX_REGISTERS: usize = 65536;
@@ -20,6 +25,10 @@ pub struct VM {
     pub(crate) manual_heap: ManualHeap,
     pub(crate) registers: Vec<Value>,
     pub(crate) frames: Vec<CallFrame>,
+    // frames (and their upvalues) of Aelys code waiting on a native that
+    // called back into the VM, see call_value_from_native
+    pub(crate) parked_frames: Vec<CallFrame>,
+    pub(crate) parked_upvalues: Vec<GcRef>,
     pub(crate) globals: HashMap<String, Value>,
     pub(crate) global_mutability: HashMap<String, bool>,
     pub(crate) globals_by_index_cache: HashMap<usize, Arc<Vec<Value>>>,
@@ -30,6 +39,15 @@ pub struct VM {
     pub(crate) current_upvalues: Vec<GcRef>,
     pub(crate) call_site_cache: Vec<CallSiteCacheEntry>,
     pub(crate) resources: Vec<Option<Resource>>,
+    pub(crate) regex_cache: HashMap<String, regex::Regex>,
+    // IANA zones read from the system tzdata by std.time, by name
+    pub(crate) tz_cache: HashMap<String, tz::TimeZone>,
+    // set by http.stop, checked by http.serve between requests
+    pub(crate) http_stop: bool,
+    // default generator of std.random, made on first use from config.seed
+    pub(crate) default_rng: Option<rand_chacha::ChaCha8Rng>,
+    // std.thread threads running, shared by every VM of the program
+    pub(crate) running_threads: Arc<AtomicUsize>,
     pub(crate) native_modules: HashMap<String, NativeModule>,
     pub(crate) native_registry: HashMap<String, NativeFunctionImpl>,
 
@@ -40,6 +58,14 @@ pub struct VM {
     pub(crate) repl_known_globals: HashSet<String>,
     pub(crate) repl_known_native_globals: HashSet<String>,
     pub(crate) repl_symbol_origins: HashMap<String, String>,
+    pub(crate) debugger: Option<Box<Debugger>>,
+    // file each loaded function came from, by bytecode address, and the lines
+    // with code in each file; see add_source
+    pub(crate) function_sources: HashMap<usize, String>,
+    pub(crate) source_code_lines: BTreeMap<String, BTreeSet<u32>>,
+    pub(crate) profiler: Option<Box<Profiler>>,
+    pub(crate) coverage: Option<Box<Coverage>>,
+    pub(crate) stdout_sink: Option<OutputSink>,
 }
 
 // MIC entry for CallGlobal - avoids repeat lookups
@@ -0,0 +1,348 @@
+// Line and branch coverage. Turned on through `VmConfig::coverage` so modules
+// loaded before the script runs are counted too. Every copy of a function
+// (closures get one each time they're made) shares its bytecode buffer, so
+// hits are keyed by the buffer's address rather than by heap object.
+
+use super::{Function, OpCode, VM};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy)]
+struct PendingBranch {
+    code: usize,
+    at: usize,
+    depth: usize,
+    target: usize,
+    len: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct Coverage {
+    // files to report on, with the top-level function of each; holding the
+    // function keeps its buffers, and so the hit keys, alive
+    sources: Vec<(String, Function)>,
+    hits: HashMap<usize, Vec<u64>>,
+    entries: HashMap<usize, u64>,
+    // [jumped, fell through] per conditional instruction
+    branches: HashMap<(usize, usize), [u64; 2]>,
+    // a conditional seen on the previous instruction, resolved by the next one
+    pending: Option<PendingBranch>,
+    depth: usize,
+    code: usize,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn resolve_pending(&mut self, code: usize, at: usize, depth: usize) {
+        let Some(branch) = self.pending.take() else {
+            return;
+        };
+        let jumped = if branch.code == code && branch.depth == depth {
+            at != branch.at + 1
+        } else {
+            // the function was left, by whichever side points past its end
+            branch.target >= branch.len
+        };
+        let outcomes = self.branches.entry((branch.code, branch.at)).or_default();
+        outcomes[if jumped { 0 } else { 1 }] += 1;
+    }
+}
+
+fn is_conditional(opcode: u8) -> bool {
+    matches!(
+        OpCode::from_u8(opcode),
+        Some(
+            OpCode::JumpIf
+                | OpCode::JumpIfNot
+                | OpCode::ForLoopI
+                | OpCode::ForLoopIInc
+                | OpCode::WhileLoopLt
+                | OpCode::StringForLoop
+                | OpCode::VecForLoop
+                | OpCode::ArrayForLoop
+        )
+    )
+}
+
+fn has_cache_words(opcode: u8) -> bool {
+    matches!(
+        OpCode::from_u8(opcode),
+        Some(OpCode::CallGlobal | OpCode::CallGlobalMono | OpCode::CallGlobalNative)
+    )
+}
+
+// instruction indices of `func`, skipping the inline cache words after calls
+fn instructions(func: &Function) -> impl Iterator<Item = (usize, u32)> + '_ {
+    let mut skip = 0;
+    func.bytecode
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(move |(_, instr)| {
+            if skip > 0 {
+                skip -= 1;
+                return false;
+            }
+            if has_cache_words((instr >> 24) as u8) {
+                skip = 2;
+            }
+            true
+        })
+}
+
+/// Coverage of every registered source file, ready to be written as lcov.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileCoverage {
+    pub path: String,
+    pub functions: Vec<FunctionCoverage>,
+    /// Hit count of every line that has code.
+    pub lines: BTreeMap<u32, u64>,
+    pub branches: Vec<BranchCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCoverage {
+    pub name: String,
+    pub line: u32,
+    pub calls: u64,
+}
+
+/// One conditional instruction. `taken` is `[jumped, fell through]`, or
+/// `None` if the instruction itself never ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchCoverage {
+    pub line: u32,
+    pub block: u32,
+    pub taken: Option<[u64; 2]>,
+}
+
+impl FileCoverage {
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&hits| hits > 0).count()
+    }
+}
+
+impl CoverageReport {
+    pub fn lines_found(&self) -> usize {
+        self.files.iter().map(|f| f.lines.len()).sum()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.files.iter().map(FileCoverage::lines_hit).sum()
+    }
+
+    /// Adds the counts of another run; files, functions and branches are
+    /// matched by path, name and position.
+    pub fn merge(&mut self, other: CoverageReport) {
+        for file in other.files {
+            let Some(mine) = self.files.iter_mut().find(|f| f.path == file.path) else {
+                self.files.push(file);
+                continue;
+            };
+            for func in file.functions {
+                match mine
+                    .functions
+                    .iter_mut()
+                    .find(|f| f.name == func.name && f.line == func.line)
+                {
+                    Some(existing) => existing.calls += func.calls,
+                    None => mine.functions.push(func),
+                }
+            }
+            for (line, hits) in file.lines {
+                *mine.lines.entry(line).or_default() += hits;
+            }
+            for branch in file.branches {
+                match mine
+                    .branches
+                    .iter_mut()
+                    .find(|b| b.line == branch.line && b.block == branch.block)
+                {
+                    Some(existing) => {
+                        existing.taken = match (existing.taken, branch.taken) {
+                            (Some(a), Some(b)) => Some([a[0] + b[0], a[1] + b[1]]),
+                            (a, b) => a.or(b),
+                        }
+                    }
+                    None => mine.branches.push(branch),
+                }
+            }
+        }
+    }
+
+    /// The report in lcov's tracefile format (`genhtml`, codecov, coveralls).
+    pub fn to_lcov(&self) -> String {
+        let mut out = String::new();
+        for file in &self.files {
+            out.push_str("TN:\n");
+            out.push_str(&format!("SF:{}\n", file.path));
+            for func in &file.functions {
+                out.push_str(&format!("FN:{},{}\n", func.line, func.name));
+            }
+            for func in &file.functions {
+                out.push_str(&format!("FNDA:{},{}\n", func.calls, func.name));
+            }
+            let hit = file.functions.iter().filter(|f| f.calls > 0).count();
+            out.push_str(&format!("FNF:{}\nFNH:{}\n", file.functions.len(), hit));
+
+            for branch in &file.branches {
+                let counts = match branch.taken {
+                    Some(taken) => taken.map(|count| count.to_string()),
+                    None => ["-".to_string(), "-".to_string()],
+                };
+                for (side, count) in counts.iter().enumerate() {
+                    out.push_str(&format!(
+                        "BRDA:{},{},{},{}\n",
+                        branch.line, branch.block, side, count
+                    ));
+                }
+            }
+            let taken = file
+                .branches
+                .iter()
+                .filter_map(|b| b.taken)
+                .flatten()
+                .filter(|&count| count > 0)
+                .count();
+            out.push_str(&format!("BRF:{}\nBRH:{}\n", file.branches.len() * 2, taken));
+
+            for (line, hits) in &file.lines {
+                out.push_str(&format!("DA:{},{}\n", line, hits));
+            }
+            out.push_str(&format!(
+                "LF:{}\nLH:{}\n",
+                file.lines.len(),
+                file.lines_hit()
+            ));
+            out.push_str("end_of_record\n");
+        }
+        out
+    }
+}
+
+impl VM {
+    /// Adds `path` to the coverage report, with `function` as its top level.
+    /// Does nothing unless the VM was created with `VmConfig::coverage`.
+    pub fn cover_source(&mut self, path: &str, function: &Function) {
+        if let Some(coverage) = self.coverage.as_deref_mut() {
+            coverage.sources.push((path.to_string(), function.clone()));
+        }
+    }
+
+    /// Stops recording and returns what ran, per registered source file.
+    pub fn take_coverage(&mut self) -> Option<CoverageReport> {
+        let mut coverage = self.coverage.take()?;
+        coverage.resolve_pending(0, 0, usize::MAX);
+
+        let files = coverage
+            .sources
+            .iter()
+            .map(|(path, main)| {
+                let mut file = FileCoverage {
+                    path: path.clone(),
+                    ..FileCoverage::default()
+                };
+                let mut names = HashSet::new();
+                collect(&coverage, main, true, &mut names, &mut file);
+                file
+            })
+            .collect();
+        Some(CoverageReport { files })
+    }
+
+    pub(crate) fn coverage_hook(&mut self, code: usize, len: usize, at: usize, instr: u32) {
+        let depth = self.frames.len();
+        let Some(coverage) = self.coverage.as_deref_mut() else {
+            return;
+        };
+        coverage.resolve_pending(code, at, depth);
+
+        // deeper means a call, same depth in other code a tail call
+        if depth > coverage.depth || (depth == coverage.depth && code != coverage.code) {
+            *coverage.entries.entry(code).or_default() += 1;
+        }
+        coverage.depth = depth;
+        coverage.code = code;
+
+        let hits = coverage.hits.entry(code).or_insert_with(|| vec![0; len]);
+        if let Some(count) = hits.get_mut(at) {
+            *count += 1;
+        }
+
+        let opcode = (instr >> 24) as u8;
+        if is_conditional(opcode) {
+            let offset = (instr & 0xFFFF) as i16 as isize;
+            coverage.pending = Some(PendingBranch {
+                code,
+                at,
+                depth,
+                target: (at as isize + 1 + offset).max(0) as usize,
+                len,
+            });
+        }
+    }
+}
+
+// walks `func` and its nested functions, in order, into `file`
+fn collect(
+    coverage: &Coverage,
+    func: &Function,
+    top_level: bool,
+    names: &mut HashSet<String>,
+    file: &mut FileCoverage,
+) {
+    let code = func.bytecode.as_ptr() as usize;
+    let hits = coverage.hits.get(&code);
+    let hit = |at: usize| hits.and_then(|h| h.get(at)).copied().unwrap_or(0);
+
+    if !top_level {
+        let line = func.get_line(0);
+        let base = func.name.clone().unwrap_or_else(|| "<lambda>".to_string());
+        // lambdas all share a name, lcov wants them told apart
+        let name = if names.insert(base.clone()) {
+            base
+        } else {
+            format!("{}@{}", base, line)
+        };
+        file.functions.push(FunctionCoverage {
+            name,
+            line,
+            calls: coverage.entries.get(&code).copied().unwrap_or(0),
+        });
+    }
+
+    for (at, instr) in instructions(func) {
+        let line = func.get_line(at);
+        if line == 0 {
+            continue;
+        }
+        let entry = file.lines.entry(line).or_default();
+        *entry = (*entry).max(hit(at));
+
+        if is_conditional((instr >> 24) as u8) {
+            let taken = (hit(at) > 0).then(|| {
+                coverage
+                    .branches
+                    .get(&(code, at))
+                    .copied()
+                    .unwrap_or_default()
+            });
+            file.branches.push(BranchCoverage {
+                line,
+                block: file.branches.len() as u32,
+                taken,
+            });
+        }
+    }
+
+    for nested in &func.nested_functions {
+        collect(coverage, nested, false, names, file);
+    }
+}
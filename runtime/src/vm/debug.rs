@@ -0,0 +1,390 @@
+// Source-level debugging. The dispatch loop calls `debug_hook` before every
+// instruction while a debugger is attached; everything here stays off the hot
+// path otherwise.
+
+use super::{Function, GcRef, ObjectKind, VM, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    Continue,
+    Step,   // stop at the next new line, entering calls
+    Next,   // stop at the next new line in this frame or a caller
+    Finish, // stop once the current frame has returned
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StopEvent {
+    pub reason: StopReason,
+    pub source: String,
+    pub line: u32,
+    pub function: String,
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub function: String,
+    pub source: String,
+    pub line: u32,
+}
+
+/// Called with the paused VM whenever execution stops.
+pub trait DebugFrontend {
+    fn on_stop(&mut self, vm: &VM, stop: &StopEvent, breakpoints: &mut Breakpoints)
+    -> DebugCommand;
+}
+
+/// Breakpoint lines per source file, the file named the way `add_source`
+/// registered it (`StopEvent::source`, `FrameInfo::source`).
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoints {
+    lines: BTreeMap<String, BTreeSet<u32>>,
+}
+
+impl Breakpoints {
+    pub fn add(&mut self, source: &str, line: u32) -> bool {
+        self.lines
+            .entry(source.to_string())
+            .or_default()
+            .insert(line)
+    }
+
+    pub fn remove(&mut self, source: &str, line: u32) -> bool {
+        let Some(lines) = self.lines.get_mut(source) else {
+            return false;
+        };
+        let removed = lines.remove(&line);
+        if lines.is_empty() {
+            self.lines.remove(source);
+        }
+        removed
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Removes the breakpoints of `source`, leaving other files alone.
+    pub fn clear_source(&mut self, source: &str) {
+        self.lines.remove(source);
+    }
+
+    pub fn contains(&self, source: &str, line: u32) -> bool {
+        self.lines
+            .get(source)
+            .is_some_and(|lines| lines.contains(&line))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        self.lines
+            .iter()
+            .flat_map(|(source, lines)| lines.iter().map(move |&line| (source.as_str(), line)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepMode {
+    Entry,
+    Continue,
+    Step,
+    Next(usize),
+    Finish(usize),
+}
+
+pub struct Debugger {
+    breakpoints: Breakpoints,
+    mode: StepMode,
+    // last line seen per frame depth, so returning into the middle of a line
+    // doesn't count as reaching it again
+    frame_lines: Vec<u32>,
+    frontend: Box<dyn DebugFrontend>,
+}
+
+impl Debugger {
+    pub fn new(frontend: Box<dyn DebugFrontend>) -> Self {
+        Self {
+            breakpoints: Breakpoints::default(),
+            mode: StepMode::Continue,
+            frame_lines: Vec::new(),
+            frontend,
+        }
+    }
+
+    /// Pause on the first line before anything runs.
+    pub fn stop_on_entry(mut self) -> Self {
+        self.mode = StepMode::Entry;
+        self
+    }
+
+    pub fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+
+    pub fn breakpoints_mut(&mut self) -> &mut Breakpoints {
+        &mut self.breakpoints
+    }
+
+    fn should_stop(&mut self, depth: usize, source: &str, line: u32) -> Option<StopReason> {
+        self.frame_lines.truncate(depth);
+        let new_line = if self.frame_lines.len() < depth {
+            self.frame_lines.resize(depth, 0);
+            true
+        } else {
+            self.frame_lines[depth - 1] != line
+        };
+        self.frame_lines[depth - 1] = line;
+
+        if line == 0 {
+            return None;
+        }
+        match self.mode {
+            StepMode::Entry => return Some(StopReason::Entry),
+            StepMode::Finish(d) if depth < d => return Some(StopReason::Step),
+            StepMode::Step if new_line => return Some(StopReason::Step),
+            StepMode::Next(d) if new_line && depth <= d => return Some(StopReason::Step),
+            _ => {}
+        }
+        if new_line && self.breakpoints.contains(source, line) {
+            return Some(StopReason::Breakpoint);
+        }
+        None
+    }
+}
+
+impl VM {
+    pub fn attach_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Some(Box::new(debugger));
+    }
+
+    pub fn detach_debugger(&mut self) -> Option<Debugger> {
+        self.debugger.take().map(|d| *d)
+    }
+
+    /// Records `path` as the file `function` and its nested functions come
+    /// from, so breakpoints and backtraces can tell modules apart. Functions
+    /// never registered belong to the VM's own source.
+    pub fn add_source(&mut self, path: &str, function: &Function) {
+        // keyed by bytecode address like coverage; every copy of a function
+        // shares its buffer
+        if !function.bytecode.is_empty() {
+            self.function_sources
+                .insert(function.bytecode.as_ptr() as usize, path.to_string());
+        }
+        self.source_code_lines
+            .entry(path.to_string())
+            .or_default()
+            .extend(
+                function
+                    .lines
+                    .iter()
+                    .map(|&(_, line)| line)
+                    .filter(|&l| l != 0),
+            );
+        for nested in &function.nested_functions {
+            self.add_source(path, nested);
+        }
+    }
+
+    /// Every file registered with `add_source`, sorted.
+    pub fn source_files(&self) -> Vec<&str> {
+        self.source_code_lines.keys().map(String::as_str).collect()
+    }
+
+    /// Lines with code in `source`, a file registered with `add_source`;
+    /// empty for any other.
+    pub fn source_lines_with_code(&self, source: &str) -> BTreeSet<u32> {
+        self.source_code_lines
+            .get(source)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Returns false when the frontend asked to quit.
+    pub(crate) fn debug_hook(&mut self) -> bool {
+        let Some(mut debugger) = self.debugger.take() else {
+            return true;
+        };
+        let depth = self.frames.len();
+        let (function, line, source) = match self.frames.last() {
+            Some(frame) => {
+                let (function, line) = self.frame_position(frame.function, frame.ip);
+                (function, line, self.frame_source(frame.function))
+            }
+            None => (String::new(), 0, self.source.name.as_str()),
+        };
+
+        let mut keep_running = true;
+        if let Some(reason) = debugger.should_stop(depth, source, line) {
+            let stop = StopEvent {
+                reason,
+                source: source.to_string(),
+                line,
+                function,
+                depth,
+            };
+            let Debugger {
+                frontend,
+                breakpoints,
+                ..
+            } = &mut *debugger;
+            let command = frontend.on_stop(self, &stop, breakpoints);
+            debugger.mode = match command {
+                DebugCommand::Continue => StepMode::Continue,
+                DebugCommand::Step => StepMode::Step,
+                DebugCommand::Next => StepMode::Next(depth),
+                DebugCommand::Finish => StepMode::Finish(depth),
+                DebugCommand::Quit => {
+                    keep_running = false;
+                    StepMode::Continue
+                }
+            };
+        }
+
+        self.debugger = Some(debugger);
+        keep_running
+    }
+
+    /// Innermost frame first.
+    pub fn backtrace(&self) -> Vec<FrameInfo> {
+        self.frames
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, frame)| {
+                let (function, line) = self.frame_position(frame.function, frame_ip(i, frame.ip));
+                let source = self.frame_source(frame.function).to_string();
+                FrameInfo {
+                    function,
+                    source,
+                    line,
+                }
+            })
+            .collect()
+    }
+
+    /// Named locals live at the current instruction of frame `depth`
+    /// (0 = innermost), in declaration order.
+    pub fn frame_locals(&self, depth: usize) -> Vec<(String, Value)> {
+        let Some(frame) = self.frames.iter().rev().nth(depth) else {
+            return Vec::new();
+        };
+        let Some(func) = self.frame_bytecode(frame.function) else {
+            return Vec::new();
+        };
+        let mut live: Vec<_> = func.live_locals(frame_ip(depth, frame.ip)).collect();
+        live.sort_by_key(|local| local.start);
+
+        let mut locals: Vec<(String, Value)> = Vec::new();
+        for local in live {
+            let value = self
+                .registers
+                .get(frame.base + local.register as usize)
+                .copied()
+                .unwrap_or(Value::null());
+            // an inner shadowing local replaces the outer one
+            locals.retain(|(name, _)| name != &local.name);
+            locals.push((local.name.clone(), value));
+        }
+        locals
+    }
+
+    /// Resolves `name` the way frame `depth` would: its locals, then globals.
+    pub fn lookup_variable(&self, depth: usize, name: &str) -> Option<Value> {
+        if let Some((_, value)) = self
+            .frame_locals(depth)
+            .into_iter()
+            .find(|(local, _)| local == name)
+        {
+            return Some(value);
+        }
+        self.lookup_global(name)
+    }
+
+    /// Data globals referenced by any active frame, sorted by name. Functions
+    /// are left out.
+    pub fn visible_globals(&self) -> Vec<(String, Value)> {
+        let mut names: Vec<&String> = self
+            .frames
+            .iter()
+            .filter_map(|frame| self.frame_bytecode(frame.function))
+            .flat_map(|func| func.global_layout.names().iter())
+            .filter(|name| !name.is_empty() && !name.contains("::"))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), self.lookup_global(name)?)))
+            .filter(|(_, value)| {
+                !matches!(
+                    self.value_type_name(*value),
+                    "function" | "native function" | "closure"
+                )
+            })
+            .collect()
+    }
+
+    fn lookup_global(&self, name: &str) -> Option<Value> {
+        // globals_by_index holds whichever layout was loaded last, which may
+        // belong to a caller when the callee touches no globals
+        for frame in self.frames.iter().rev() {
+            if let Some(func) = self.frame_bytecode(frame.function)
+                && func.global_layout.id() == self.current_global_mapping_id
+                && let Some(idx) = func.global_layout.names().iter().position(|n| n == name)
+                && let Some(value) = self.globals_by_index.get(idx)
+            {
+                return Some(*value);
+            }
+        }
+        self.get_global(name)
+    }
+
+    pub(super) fn frame_bytecode(&self, func_ref: GcRef) -> Option<&Function> {
+        match &self.heap.get(func_ref)?.kind {
+            ObjectKind::Function(f) => Some(&f.function),
+            ObjectKind::Closure(c) => match &self.heap.get(c.function)?.kind {
+                ObjectKind::Function(f) => Some(&f.function),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn frame_source(&self, func_ref: GcRef) -> &str {
+        self.frame_bytecode(func_ref)
+            .and_then(|func| {
+                self.function_sources
+                    .get(&(func.bytecode.as_ptr() as usize))
+            })
+            .map_or(self.source.name.as_str(), String::as_str)
+    }
+
+    pub(super) fn frame_position(&self, func_ref: GcRef, ip: usize) -> (String, u32) {
+        match self.frame_bytecode(func_ref) {
+            Some(func) => (
+                func.name.clone().unwrap_or_else(|| "<script>".to_string()),
+                func.get_line(ip),
+            ),
+            None => ("<unknown>".to_string(), 0),
+        }
+    }
+}
+
+// Callers sit just past their call instruction; the innermost frame is about
+// to execute `ip`.
+fn frame_ip(depth_from_top: usize, ip: usize) -> usize {
+    if depth_from_top == 0 {
+        ip
+    } else {
+        ip.saturating_sub(1)
+    }
+}
@@ -40,11 +40,14 @@ impl VM {
         let mut upvalues_len = frame.upvalues_len;
         let mut current_frame_idx = frame_idx;
         let mut global_mapping_id = frame.global_mapping_id;
+        let debugging = self.debugger.is_some();
+        let profiling = self.profiler.is_some();
+        let covering = self.coverage.is_some();
 
         loop {
             // Check end of bytecode
             if ip >= bytecode_len {
-                self.frames.pop();
+                self.pop_frame();
                 if self.frames.is_empty() {
                     return Ok(Value::null());
                 }
@@ -67,11 +70,24 @@ impl VM {
                 continue;
             }
 
+            if debugging {
+                self.frames[current_frame_idx].ip = ip;
+                if !self.debug_hook() {
+                    return Err(self.runtime_error(RuntimeErrorKind::DebuggerQuit));
+                }
+            }
+
             // Fetch instruction
             let instr = unsafe { *bytecode_ptr.add(ip) };
             ip += 1;
 
             let opcode_byte = (instr >> 24) as u8;
+            if profiling {
+                self.profile_hook(opcode_byte);
+            }
+            if covering {
+                self.coverage_hook(bytecode_ptr as usize, bytecode_len, ip - 1, instr);
+            }
 
             // Get registers pointer (may change after resize, but we refresh it for calls)
             let mut regs_ptr = self.registers.as_mut_ptr();
@@ -70,7 +70,15 @@ impl VM {
         );
         frame.global_mapping_id = global_mapping_id;
         self.push_frame(frame)?;
-        self.run_fast()
+        match self.run_fast() {
+            // a quit from inside a callback has already unwound the native
+            // calls in between, what's left is this run's own frames
+            Err(err) if matches!(err.kind, RuntimeErrorKind::DebuggerQuit) => {
+                self.clear_frames();
+                Ok(Value::null())
+            }
+            result => result,
+        }
     }
 
     pub(crate) fn ensure_function_verified(&mut self, func_ref: GcRef) -> Result<(), RuntimeError> {
@@ -6,12 +6,28 @@ impl VM {
         if self.frames.len() >= MAX_FRAMES {
             return Err(self.runtime_error(RuntimeErrorKind::StackOverflow));
         }
-        self.frames.push(frame);
+        self.enter_frame(frame);
         Ok(())
     }
 
+    // For dispatch, which checks MAX_FRAMES itself. Every frame goes on and
+    // off the stack through here and `pop_frame`, so that's where the
+    // profiler sees calls start and end.
+    #[inline(always)]
+    pub(crate) fn enter_frame(&mut self, frame: CallFrame) {
+        if let Some(profiler) = self.profiler.as_deref_mut() {
+            profiler.enter(frame.function);
+        }
+        self.frames.push(frame);
+    }
+
+    #[inline(always)]
     pub fn pop_frame(&mut self) -> Option<CallFrame> {
-        self.frames.pop()
+        let frame = self.frames.pop()?;
+        if let Some(profiler) = self.profiler.as_deref_mut() {
+            profiler.leave();
+        }
+        Some(frame)
     }
 
     pub fn current_frame(&self) -> Result<&CallFrame, RuntimeError> {
@@ -41,7 +57,7 @@ impl VM {
     }
 
     pub fn clear_frames(&mut self) {
-        self.frames.clear();
+        while self.pop_frame().is_some() {}
     }
 
     pub fn read_register(&self, reg: u8) -> Result<Value, RuntimeError> {
@@ -36,7 +36,7 @@ impl VM {
     }
 
     pub fn collect(&mut self) {
-        for frame in &self.frames {
+        for frame in self.parked_frames.iter().chain(&self.frames) {
             let base = frame.base;
             let count = frame.num_registers as usize;
             for i in 0..count {
@@ -65,7 +65,7 @@ impl VM {
         for &upval_ref in &self.open_upvalues {
             self.heap.mark(upval_ref);
         }
-        for &upval_ref in &self.current_upvalues {
+        for &upval_ref in self.current_upvalues.iter().chain(&self.parked_upvalues) {
             self.heap.mark(upval_ref);
         }
 
@@ -1,11 +1,13 @@
 use super::config::{VMCapabilities, VmConfig};
+use super::coverage::Coverage;
 use super::manual_heap::ManualHeap;
 use super::{Heap, Value};
 use super::{MAX_FRAMES, MAX_REGISTERS, VM};
 use aelys_common::error::RuntimeError;
 use aelys_syntax::Source;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
 
 impl VM {
     pub fn new(source: Arc<Source>) -> Result<Self, RuntimeError> {
@@ -32,6 +34,7 @@ impl VM {
         config: VmConfig,
         program_args: Vec<String>,
     ) -> Result<Self, RuntimeError> {
+        let coverage = config.coverage.then(|| Box::new(Coverage::new()));
         let mut vm = Self {
             heap: Heap::new(),
             config,
@@ -42,6 +45,8 @@ impl VM {
                 regs
             },
             frames: Vec::with_capacity(MAX_FRAMES),
+            parked_frames: Vec::new(),
+            parked_upvalues: Vec::new(),
             globals: HashMap::new(),
             global_mutability: HashMap::new(),
             globals_by_index_cache: HashMap::with_capacity(32),
@@ -52,6 +57,11 @@ impl VM {
             current_upvalues: Vec::new(),
             call_site_cache: Vec::with_capacity(64),
             resources: Vec::with_capacity(16),
+            regex_cache: HashMap::new(),
+            tz_cache: HashMap::new(),
+            http_stop: false,
+            default_rng: None,
+            running_threads: Arc::new(AtomicUsize::new(0)),
             native_modules: HashMap::new(),
             native_registry: HashMap::new(),
             current_global_mapping_id: 0,
@@ -61,23 +71,44 @@ impl VM {
             repl_known_globals: HashSet::new(),
             repl_known_native_globals: HashSet::new(),
             repl_symbol_origins: HashMap::new(),
+            debugger: None,
+            function_sources: HashMap::new(),
+            source_code_lines: BTreeMap::new(),
+            profiler: None,
+            coverage,
+            stdout_sink: None,
         };
         super::builtins::register_builtins(&mut vm)?;
 
         // Auto-register safe stdlib modules so they work without `needs std.X`.
         // String: qualified only (dot-syntax compiles s.trim() → string::trim(s))
-        let string_exports = crate::stdlib::string::register(&mut vm)?;
-        vm.repl_module_aliases.insert("string".to_string());
-        for name in &string_exports.all_exports {
-            let qualified = format!("string::{}", name);
-            vm.repl_known_globals.insert(qualified.clone());
-            vm.repl_known_native_globals.insert(qualified.clone());
-            vm.repl_symbol_origins
-                .insert(name.clone(), format!("string::{}", name));
+        // JSON: qualified only too, `parse` and `get` are too generic to take
+        type RegFn = fn(&mut VM) -> Result<crate::stdlib::StdModuleExports, RuntimeError>;
+        let qualified_modules: &[(&str, RegFn)] = &[
+            ("string", crate::stdlib::string::register),
+            ("json", crate::stdlib::json::register),
+        ];
+        for &(module_name, register_fn) in qualified_modules {
+            let exports = register_fn(&mut vm)?;
+            vm.repl_module_aliases.insert(module_name.to_string());
+            for name in &exports.all_exports {
+                let qualified = format!("{}::{}", module_name, name);
+                vm.repl_known_globals.insert(qualified.clone());
+                vm.repl_known_native_globals.insert(qualified.clone());
+                vm.repl_symbol_origins.insert(name.clone(), qualified);
+            }
         }
 
-        // IO, math, convert, time: qualified + unqualified aliases
-        type RegFn = fn(&mut VM) -> Result<crate::stdlib::StdModuleExports, RuntimeError>;
+        // Array/Vec methods: reachable only through dot-syntax (a.sort() →
+        // array::sort(a)), no `array` alias to shadow variables named that
+        let exports = crate::stdlib::array::register(&mut vm)?;
+        for name in &exports.native_functions {
+            vm.repl_known_globals.insert(name.clone());
+            vm.repl_known_native_globals.insert(name.clone());
+        }
+
+        // IO, math, convert, time: qualified + unqualified aliases, except for
+        // time's DateTime functions (`time.add`, `time.diff`, ...)
         let auto_modules: &[(&str, RegFn)] = &[
             ("io", crate::stdlib::io::register),
             ("math", crate::stdlib::math::register),
@@ -89,13 +120,18 @@ impl VM {
             vm.repl_module_aliases.insert(module_name.to_string());
             for name in &exports.all_exports {
                 let qualified = format!("{}::{}", module_name, name);
-                if let Some(value) = vm.get_global(&qualified) {
-                    vm.set_global(name.clone(), value);
+                if module_name == "time" && crate::stdlib::time::is_qualified_only(name) {
+                    vm.repl_known_globals.insert(qualified.clone());
+                } else {
+                    if let Some(value) = vm.get_global(&qualified) {
+                        vm.set_global(name.clone(), value);
+                    }
+                    vm.repl_known_globals.insert(name.clone());
+                    vm.repl_known_native_globals.insert(name.clone());
+                    vm.repl_symbol_origins
+                        .insert(name.clone(), qualified.clone());
                 }
-                vm.repl_known_globals.insert(name.clone());
-                vm.repl_known_native_globals.insert(name.clone());
-                vm.repl_known_native_globals.insert(qualified.clone());
-                vm.repl_symbol_origins.insert(name.clone(), qualified);
+                vm.repl_known_native_globals.insert(qualified);
             }
         }
 
@@ -5,6 +5,8 @@ mod call_api;
 mod config;
 mod config_access;
 mod core;
+mod coverage;
+mod debug;
 mod errors;
 mod execute;
 mod frame;
@@ -15,6 +17,8 @@ mod init;
 pub mod manual_heap;
 mod native;
 mod native_registry;
+mod output;
+mod profile;
 mod repl;
 mod resources;
 
@@ -38,13 +42,20 @@ pub use aelys_bytecode::{
 };
 pub use args::{VmArgsError, VmArgsParsed, parse_vm_args};
 pub use builtins::{
-    builtin_alloc, builtin_free, builtin_load, builtin_store, builtin_type, register_builtins,
+    builtin_alloc, builtin_assert, builtin_assert_eq, builtin_free, builtin_load, builtin_store,
+    builtin_type, register_builtins,
 };
 pub use config::{VMCapabilities, VmConfig, VmConfigError};
 pub use core::{
     CallSiteCacheEntry, MAX_CALL_SITE_SLOTS, MAX_FRAMES, MAX_NO_GC_DEPTH, MAX_REGISTERS,
     StepResult, VM,
 };
+pub use coverage::{BranchCoverage, CoverageReport, FileCoverage, FunctionCoverage};
+pub use debug::{
+    Breakpoints, DebugCommand, DebugFrontend, Debugger, FrameInfo, StopEvent, StopReason,
+};
 pub use frame::CallFrame;
 pub use manual_heap::{ManualHeap, ManualHeapGuard};
 pub use native::{NativeFn, NativeFunctionImpl, build_native_vm_api};
+pub use output::OutputSink;
+pub use profile::{FunctionProfile, ProfileReport, Profiler};
@@ -0,0 +1,18 @@
+use super::VM;
+
+/// Receives everything the program prints to stdout (`print`, `println`, ...).
+pub type OutputSink = Box<dyn FnMut(&str)>;
+
+impl VM {
+    /// Route program stdout somewhere else, e.g. a debug adapter that owns the real stdout.
+    pub fn set_stdout_sink(&mut self, sink: Option<OutputSink>) {
+        self.stdout_sink = sink;
+    }
+
+    pub fn write_stdout(&mut self, text: &str) {
+        match &mut self.stdout_sink {
+            Some(sink) => sink(text),
+            None => print!("{}", text),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// Call and opcode profiling. Like the debugger, the dispatch loop only calls
+// `profile_hook` while a profiler is attached, and only to count opcodes.
+// Calls are recorded by `push_frame`/`enter_frame` and `pop_frame`, which
+// every call and return goes through; the clock is read once per each.
+
+use super::{GcRef, OpCode, VM};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default)]
+struct FunctionStats {
+    calls: u64,
+    inclusive: Duration,
+    exclusive: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveCall {
+    function: GcRef,
+    // this call's place in the call tree
+    node: usize,
+    entered: Instant,
+}
+
+// One distinct call stack: `function` called from the stack at `parent`.
+#[derive(Debug, Clone)]
+struct CallNode {
+    function: GcRef,
+    parent: usize,
+    self_time: Duration,
+}
+
+// node 0, the parent of top-level calls
+const ROOT: usize = 0;
+
+pub struct Profiler {
+    stack: Vec<ActiveCall>,
+    functions: HashMap<GcRef, FunctionStats>,
+    // activations of each function currently on the stack, so recursion
+    // doesn't count the same time twice towards inclusive
+    active: HashMap<GcRef, usize>,
+    opcodes: Box<[u64; 256]>,
+    // the call tree, with self time per node; folded stacks are only spelled
+    // out at the end, in detach_profiler
+    nodes: Vec<CallNode>,
+    children: HashMap<(usize, GcRef), usize>,
+    // start of the current stretch of self time for the top of `stack`
+    since: Instant,
+    started: Instant,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            stack: Vec::new(),
+            functions: HashMap::new(),
+            active: HashMap::new(),
+            opcodes: Box::new([0; 256]),
+            nodes: vec![CallNode {
+                function: GcRef::new(0),
+                parent: ROOT,
+                self_time: Duration::ZERO,
+            }],
+            children: HashMap::new(),
+            since: now,
+            started: now,
+        }
+    }
+
+    // bills the time since the last stack change to whatever was on top
+    fn charge_self_time(&mut self, now: Instant) {
+        let elapsed = now - self.since;
+        self.since = now;
+        let Some(top) = self.stack.last() else {
+            return;
+        };
+        self.functions.entry(top.function).or_default().exclusive += elapsed;
+        self.nodes[top.node].self_time += elapsed;
+    }
+
+    pub(crate) fn enter(&mut self, function: GcRef) {
+        let now = Instant::now();
+        self.charge_self_time(now);
+        self.functions.entry(function).or_default().calls += 1;
+        *self.active.entry(function).or_default() += 1;
+        let parent = self.stack.last().map_or(ROOT, |call| call.node);
+        let node = match self.children.get(&(parent, function)) {
+            Some(&node) => node,
+            None => {
+                self.nodes.push(CallNode {
+                    function,
+                    parent,
+                    self_time: Duration::ZERO,
+                });
+                self.children
+                    .insert((parent, function), self.nodes.len() - 1);
+                self.nodes.len() - 1
+            }
+        };
+        self.stack.push(ActiveCall {
+            function,
+            node,
+            entered: now,
+        });
+    }
+
+    pub(crate) fn leave(&mut self) {
+        let now = Instant::now();
+        self.charge_self_time(now);
+        self.pop(now);
+    }
+
+    fn pop(&mut self, now: Instant) {
+        let Some(call) = self.stack.pop() else {
+            return;
+        };
+        let active = self.active.entry(call.function).or_default();
+        *active = active.saturating_sub(1);
+        if *active == 0 {
+            self.functions.entry(call.function).or_default().inclusive += now - call.entered;
+        }
+    }
+
+    fn finish(&mut self) -> Duration {
+        let now = Instant::now();
+        self.charge_self_time(now);
+        while !self.stack.is_empty() {
+            self.pop(now);
+        }
+        now - self.started
+    }
+}
+
+/// What a profiled run spent its time on, with function names resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    pub total: Duration,
+    /// Sorted by exclusive time, most expensive first.
+    pub functions: Vec<FunctionProfile>,
+    /// Opcode name and how many times it ran, most frequent first.
+    pub opcodes: Vec<(String, u64)>,
+    /// `outer;inner;innermost` stacks with their self time, in the folded
+    /// format flamegraph tools read.
+    pub folded: Vec<(String, Duration)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub name: String,
+    /// Line of the function's first instruction.
+    pub line: u32,
+    pub calls: u64,
+    pub inclusive: Duration,
+    pub exclusive: Duration,
+}
+
+impl VM {
+    pub fn attach_profiler(&mut self, profiler: Profiler) {
+        self.profiler = Some(Box::new(profiler));
+    }
+
+    /// Stops profiling and resolves what was collected against this VM's heap.
+    pub fn detach_profiler(&mut self) -> Option<ProfileReport> {
+        let mut profiler = self.profiler.take()?;
+        let total = profiler.finish();
+
+        let mut functions: Vec<FunctionProfile> = profiler
+            .functions
+            .iter()
+            .map(|(function, stats)| {
+                let (name, line) = self.frame_position(*function, 0);
+                FunctionProfile {
+                    name,
+                    line,
+                    calls: stats.calls,
+                    inclusive: stats.inclusive,
+                    exclusive: stats.exclusive,
+                }
+            })
+            .collect();
+        functions.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then(a.name.cmp(&b.name)));
+
+        let mut opcodes: Vec<(String, u64)> = profiler
+            .opcodes
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(byte, count)| {
+                let name = match OpCode::from_u8(byte as u8) {
+                    Some(op) => format!("{:?}", op),
+                    None => format!("op{}", byte),
+                };
+                (name, *count)
+            })
+            .collect();
+        opcodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        // a node's stack is its parent's plus its own function, and parents
+        // always come first in `nodes`
+        let mut stacks: Vec<String> = Vec::with_capacity(profiler.nodes.len());
+        let mut folded: Vec<(String, Duration)> = Vec::new();
+        for (id, node) in profiler.nodes.iter().enumerate() {
+            if id == ROOT {
+                stacks.push(String::new());
+                continue;
+            }
+            let name = self.frame_position(node.function, 0).0;
+            let stack = match node.parent {
+                ROOT => name,
+                parent => format!("{};{}", stacks[parent], name),
+            };
+            folded.push((stack.clone(), node.self_time));
+            stacks.push(stack);
+        }
+        folded.sort();
+
+        Some(ProfileReport {
+            total,
+            functions,
+            opcodes,
+            folded,
+        })
+    }
+
+    pub(crate) fn profile_hook(&mut self, opcode: u8) {
+        if let Some(profiler) = self.profiler.as_deref_mut() {
+            profiler.opcodes[opcode as usize] += 1;
+        }
+    }
+}
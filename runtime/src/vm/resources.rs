@@ -16,6 +16,14 @@ impl VM {
         handle
     }
 
+    /// Put `resource` under a given handle, replacing whatever was there.
+    pub(crate) fn store_resource_at(&mut self, handle: usize, resource: Resource) {
+        if self.resources.len() <= handle {
+            self.resources.resize_with(handle + 1, || None);
+        }
+        self.resources[handle] = Some(resource);
+    }
+
     /// Get a resource by handle.
     pub fn get_resource(&self, handle: usize) -> Option<&Resource> {
         self.resources.get(handle)?.as_ref()
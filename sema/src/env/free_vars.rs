@@ -19,7 +19,7 @@ impl TypeEnv {
                     collect_vars(ret, vars);
                 }
                 InferType::Array(inner) => collect_vars(inner, vars),
-                InferType::Tuple(elems) => {
+                InferType::Tuple(elems) | InferType::GenericStruct { args: elems, .. } => {
                     for e in elems {
                         collect_vars(e, vars);
                     }
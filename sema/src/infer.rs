@@ -15,6 +15,7 @@ use crate::constraint::{Constraint, TypeError};
 use crate::env::TypeEnv;
 use crate::types::{InferType, TypeTable, TypeVarGen};
 use aelys_common::Warning;
+use std::collections::HashMap;
 
 const MAX_INFERENCE_DEPTH: usize = 200;
 
@@ -34,4 +35,6 @@ pub struct TypeInference {
     warnings: Vec<Warning>,
     pub(crate) type_table: TypeTable,
     type_params_in_scope: Vec<String>,
+    // generic function name -> its type parameters
+    generic_functions: HashMap<String, Vec<String>>,
 }
@@ -82,7 +82,7 @@ impl TypeInference {
             InferType::Array(inner) => {
                 self.force_dynamic(inner, subst);
             }
-            InferType::Tuple(elems) => {
+            InferType::Tuple(elems) | InferType::GenericStruct { args: elems, .. } => {
                 for e in elems {
                     self.force_dynamic(e, subst);
                 }
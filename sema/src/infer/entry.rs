@@ -25,6 +25,7 @@ impl Default for TypeInference {
             warnings: Vec::new(),
             type_table: TypeTable::new(),
             type_params_in_scope: Vec::new(),
+            generic_functions: Default::default(),
         }
     }
 }
@@ -47,14 +48,17 @@ impl TypeInference {
         let name_lower = ann.name.to_lowercase();
 
         if KNOWN_TYPE_NAMES.contains(&name_lower.as_str()) {
-            if let Some(ref param) = ann.type_param {
-                self.check_type_annotation(param);
+            for arg in &ann.type_args {
+                self.check_type_annotation(arg);
             }
             return;
         }
 
         if ann.name.chars().next().is_some_and(|c| c.is_uppercase()) {
             if self.type_table.has_struct(&ann.name) || self.env.contains(&ann.name) {
+                for arg in &ann.type_args {
+                    self.check_type_annotation(arg);
+                }
                 return;
             }
             self.errors.push(TypeError {
@@ -44,6 +44,19 @@ impl TypeInference {
                 }
             }
 
+            // each call of a generic function gets fresh type arguments
+            let mut is_generic_call = false;
+            let callee_ty = match &typed_callee.kind {
+                TypedExprKind::Identifier(name) if self.generic_functions.contains_key(name) => {
+                    is_generic_call = true;
+                    let type_params = self.generic_functions[name].clone();
+                    let args: Vec<InferType> =
+                        type_params.iter().map(|_| self.type_gen.fresh()).collect();
+                    typed_callee.ty.substitute_params(&type_params, &args)
+                }
+                _ => typed_callee.ty.clone(),
+            };
+
             let ret = self.type_gen.fresh();
 
             let arg_types: Vec<InferType> = typed_args.iter().map(|a| a.ty.clone()).collect();
@@ -52,14 +65,21 @@ impl TypeInference {
                 ret: Box::new(ret.clone()),
             };
 
+            // the instantiated return type keeps its shape (`Pair<?, ?>`)
+            // for member accesses before the constraints are solved
+            let instantiated_ret = match &callee_ty {
+                InferType::Function { ret, .. } if is_generic_call => Some((**ret).clone()),
+                _ => None,
+            };
+
             self.constraints.push(Constraint::equal(
-                typed_callee.ty.clone(),
+                callee_ty,
                 expected_fn_type,
                 span,
                 ConstraintReason::Other("function call".to_string()),
             ));
 
-            ret
+            instantiated_ret.unwrap_or(ret)
         };
 
         (
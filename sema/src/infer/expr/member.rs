@@ -13,20 +13,15 @@ impl TypeInference {
     ) -> (TypedExprKind, InferType) {
         let typed_object = self.infer_expr(object);
 
-        let ty = match &typed_object.ty {
-            InferType::Struct(name) => {
-                if let Some(def) = self.type_table.get_struct(name) {
-                    def.fields
-                        .iter()
-                        .find(|f| f.name == member)
-                        .map(|f| f.ty.clone())
-                        .unwrap_or(InferType::Dynamic)
-                } else {
-                    InferType::Dynamic
-                }
-            }
-            _ => InferType::Dynamic,
+        let (name, args) = match &typed_object.ty {
+            InferType::Struct(name) => (Some(name), &[][..]),
+            InferType::GenericStruct { name, args } => (Some(name), &args[..]),
+            _ => (None, &[][..]),
         };
+        let ty = name
+            .and_then(|name| self.type_table.get_struct(name))
+            .and_then(|def| def.field_type(member, args))
+            .unwrap_or(InferType::Dynamic);
 
         (
             TypedExprKind::Member {
@@ -43,17 +38,26 @@ impl TypeInference {
         fields: &[StructFieldInit],
         _span: Span,
     ) -> (TypedExprKind, InferType) {
+        // every literal of a generic struct gets its own type arguments,
+        // solved from the field values
+        let type_args: Vec<InferType> = self
+            .type_table
+            .get_struct(name)
+            .map(|def| def.type_params.len())
+            .map(|n| (0..n).map(|_| self.type_gen.fresh()).collect())
+            .unwrap_or_default();
+
         let typed_fields: Vec<(String, Box<TypedExpr>)> = fields
             .iter()
             .map(|f| {
                 let typed_value = self.infer_expr(&f.value);
 
                 if let Some(def) = self.type_table.get_struct(name)
-                    && let Some(field_def) = def.fields.iter().find(|df| df.name == f.name)
+                    && let Some(field_ty) = def.field_type(&f.name, &type_args)
                 {
                     self.constraints.push(Constraint::equal(
                         typed_value.ty.clone(),
-                        field_def.ty.clone(),
+                        field_ty,
                         f.span,
                         ConstraintReason::TypeAnnotation {
                             var_name: format!("{}.{}", name, f.name),
@@ -65,12 +69,21 @@ impl TypeInference {
             })
             .collect();
 
+        let ty = if type_args.is_empty() {
+            InferType::Struct(name.to_string())
+        } else {
+            InferType::GenericStruct {
+                name: name.to_string(),
+                args: type_args,
+            }
+        };
+
         (
             TypedExprKind::StructLiteral {
                 name: name.to_string(),
                 fields: typed_fields,
             },
-            InferType::Struct(name.to_string()),
+            ty,
         )
     }
 }
@@ -77,6 +77,10 @@ impl TypeInference {
         };
 
         self.type_params_in_scope = saved_type_params;
+        if !func.type_params.is_empty() {
+            self.generic_functions
+                .insert(full_name.clone(), func.type_params.clone());
+        }
 
         let fn_type = Rc::new(InferType::Function {
             params: param_types,
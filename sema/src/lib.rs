@@ -1,6 +1,7 @@
 pub mod constraint;
 pub mod env;
 pub mod infer;
+pub mod lint;
 pub mod typed_ast;
 pub mod types;
 pub mod unify;
@@ -8,6 +9,7 @@ pub mod unify;
 pub use constraint::{Constraint, ConstraintReason, TypeError};
 pub use env::TypeEnv;
 pub use infer::{TypeInference, entry::InferenceResult};
+pub use lint::{LintConfig, LintLevel, LintModule};
 pub use typed_ast::{
     TypedExpr, TypedExprKind, TypedFmtStringPart, TypedFunction, TypedParam, TypedProgram,
     TypedStmt, TypedStmtKind,
@@ -0,0 +1,145 @@
+// Lints: code that type checks but is probably not what was meant. They run
+// over the typed AST of a whole project at once (unused_pub needs to see who
+// imports what) and are only reported by `aelys lint`, never by compile.
+
+mod walk;
+
+use crate::typed_ast::{TypedProgram, TypedStmtKind};
+use aelys_common::{Warning, WarningKind};
+use aelys_syntax::{ImportKind, NeedsStmt};
+use std::collections::{HashMap, HashSet};
+use walk::Walker;
+
+pub const RULES: &[&str] = &[
+    "unreachable_code",
+    "constant_comparison",
+    "missing_free",
+    "unused_pub",
+    "float_equality",
+    "dynamic_in_pub_api",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level {
+            "allow" => Some(Self::Allow),
+            "warn" => Some(Self::Warn),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Per-rule levels; rules not mentioned warn.
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, rule: &str, level: LintLevel) -> Result<(), String> {
+        if !RULES.contains(&rule) {
+            return Err(format!("unknown lint rule: {}", rule));
+        }
+        self.levels.insert(rule.to_string(), level);
+        Ok(())
+    }
+
+    pub fn level(&self, rule: &str) -> LintLevel {
+        self.levels.get(rule).copied().unwrap_or(LintLevel::Warn)
+    }
+
+    /// Level of the rule behind `warning`; warnings that aren't lints warn.
+    pub fn level_of(&self, warning: &Warning) -> LintLevel {
+        warning
+            .kind
+            .lint_rule()
+            .map_or(LintLevel::Warn, |rule| self.level(rule))
+    }
+}
+
+/// One file of the project.
+pub struct LintModule<'a> {
+    pub program: &'a TypedProgram,
+    /// The name other files import it by, `utils` for utils.aelys.
+    pub name: String,
+    /// Its `needs` statements, which type inference leaves out.
+    pub needs: Vec<NeedsStmt>,
+}
+
+/// Lints every module, returning what isn't allowed, per module in source
+/// order.
+pub fn lint(modules: &[LintModule], config: &LintConfig) -> Vec<Warning> {
+    let mut walkers: Vec<Walker> = modules
+        .iter()
+        .map(|module| {
+            let mut walker = Walker::new(config, module.program.source.clone());
+            walker.stmts(&module.program.stmts);
+            walker
+        })
+        .collect();
+
+    for (i, module) in modules.iter().enumerate() {
+        unused_pub(i, module, modules, &mut walkers);
+    }
+
+    walkers
+        .into_iter()
+        .flat_map(|walker| {
+            let mut warnings = walker.warnings;
+            warnings.sort_by_key(|w| w.span.start);
+            warnings
+        })
+        .collect()
+}
+
+// only for modules something in the project imports: in the entry script
+// `pub` has no one to be public to
+fn unused_pub(i: usize, module: &LintModule, modules: &[LintModule], walkers: &mut [Walker]) {
+    let mut importers = Vec::new();
+    let mut imported_names = HashSet::new();
+    for (j, other) in modules.iter().enumerate().filter(|&(j, _)| j != i) {
+        for needs in other
+            .needs
+            .iter()
+            .filter(|n| n.path.last() == Some(&module.name))
+        {
+            importers.push(j);
+            if let ImportKind::Symbols(symbols) = &needs.kind {
+                imported_names.extend(symbols.iter().cloned());
+            }
+        }
+    }
+    if importers.is_empty() {
+        return;
+    }
+
+    let used = |name: &str| {
+        imported_names.contains(name) || importers.iter().any(|&j| walkers[j].uses(name))
+    };
+    let mut unused = Vec::new();
+    for stmt in &module.program.stmts {
+        let (name, allowed) = match &stmt.kind {
+            TypedStmtKind::Function(func) if func.is_pub => (&func.name, walk::allows(func)),
+            TypedStmtKind::Let { name, is_pub, .. } if *is_pub => (name, Vec::new()),
+            _ => continue,
+        };
+        if !used(name) && !allowed.iter().any(|rule| rule == "unused_pub") {
+            unused.push((name.clone(), stmt.span));
+        }
+    }
+    for (name, span) in unused {
+        walkers[i].report(WarningKind::UnusedPub { name }, span);
+    }
+}
@@ -0,0 +1,351 @@
+// The per-file rules, checked in a single walk that also notes every name
+// the file uses (for unused_pub).
+
+use super::{LintConfig, LintLevel};
+use crate::typed_ast::{
+    TypedExpr, TypedExprKind, TypedFmtStringPart, TypedFunction, TypedStmt, TypedStmtKind,
+};
+use crate::types::InferType;
+use aelys_common::{Warning, WarningKind};
+use aelys_syntax::{BinaryOp, Source, Span};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Rules named by the `@allow(...)` decorators of a function.
+pub(super) fn allows(func: &TypedFunction) -> Vec<String> {
+    func.decorators
+        .iter()
+        .filter(|d| d.name == "allow")
+        .flat_map(|d| d.args.iter().cloned())
+        .collect()
+}
+
+// what a @no_gc function has done so far
+#[derive(Default)]
+struct NoGc {
+    alloc: Option<Span>,
+    freed: bool,
+}
+
+pub(super) struct Walker<'a> {
+    config: &'a LintConfig,
+    source: Arc<Source>,
+    // rules allowed by the functions we're inside of
+    allowed: Vec<String>,
+    no_gc: Option<NoGc>,
+    used: HashSet<String>,
+    pub(super) warnings: Vec<Warning>,
+}
+
+impl<'a> Walker<'a> {
+    pub(super) fn new(config: &'a LintConfig, source: Arc<Source>) -> Self {
+        Self {
+            config,
+            source,
+            allowed: Vec::new(),
+            no_gc: None,
+            used: HashSet::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Whether the file refers to `name`, directly or as `module.name`.
+    pub(super) fn uses(&self, name: &str) -> bool {
+        self.used.contains(name)
+    }
+
+    pub(super) fn report(&mut self, kind: WarningKind, span: Span) {
+        let Some(rule) = kind.lint_rule() else {
+            return;
+        };
+        if self.config.level(rule) == LintLevel::Allow || self.allowed.iter().any(|r| r == rule) {
+            return;
+        }
+        self.warnings
+            .push(Warning::new(kind, span).with_source(self.source.clone()));
+    }
+
+    pub(super) fn stmts(&mut self, stmts: &[TypedStmt]) {
+        // one warning per block, on the first statement that can't run
+        let unreachable = stmts
+            .iter()
+            .position(diverges)
+            .and_then(|i| stmts[i + 1..].iter().find(|s| !is_declaration(s)));
+        if let Some(stmt) = unreachable {
+            self.report(WarningKind::UnreachableCode, stmt.span);
+        }
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) {
+        match &stmt.kind {
+            TypedStmtKind::Expression(expr) => self.expr(expr),
+            TypedStmtKind::Let {
+                name,
+                initializer,
+                var_type,
+                is_pub,
+                ..
+            } => {
+                if *is_pub && untyped(var_type) {
+                    let what = format!("'{}'", name);
+                    self.report(WarningKind::DynamicInPublicApi { what }, stmt.span);
+                }
+                self.expr(initializer);
+            }
+            TypedStmtKind::Block(stmts) => self.stmts(stmts),
+            TypedStmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            TypedStmtKind::While { condition, body } => {
+                self.expr(condition);
+                self.stmt(body);
+            }
+            TypedStmtKind::For {
+                start,
+                end,
+                step,
+                body,
+                ..
+            } => {
+                self.expr(start);
+                self.expr(end);
+                if let Some(step) = step.as_ref() {
+                    self.expr(step);
+                }
+                self.stmt(body);
+            }
+            TypedStmtKind::ForEach { iterable, body, .. } => {
+                self.expr(iterable);
+                self.stmt(body);
+            }
+            TypedStmtKind::Return(Some(expr)) => self.expr(expr),
+            TypedStmtKind::Function(func) => self.function(func),
+            TypedStmtKind::Return(None)
+            | TypedStmtKind::Break
+            | TypedStmtKind::Continue
+            | TypedStmtKind::Needs(_)
+            | TypedStmtKind::StructDecl { .. } => {}
+        }
+    }
+
+    fn function(&mut self, func: &TypedFunction) {
+        let outer_allowed = self.allowed.len();
+        self.allowed.extend(allows(func));
+
+        if func.is_pub {
+            for param in func.params.iter().filter(|p| untyped(&p.ty)) {
+                let what = format!("parameter '{}' of '{}'", param.name, func.name);
+                self.report(WarningKind::DynamicInPublicApi { what }, param.span);
+            }
+            if untyped(&func.return_type) {
+                let what = format!("return type of '{}'", func.name);
+                self.report(WarningKind::DynamicInPublicApi { what }, func.span);
+            }
+        }
+
+        let no_gc = func.decorators.iter().any(|d| d.name == "no_gc");
+        let outer_no_gc = std::mem::replace(&mut self.no_gc, no_gc.then(NoGc::default));
+        self.stmts(&func.body);
+        if let Some(NoGc {
+            alloc: Some(span),
+            freed: false,
+        }) = self.no_gc
+        {
+            let name = func.name.clone();
+            self.report(WarningKind::MissingFree { name }, span);
+        }
+        self.no_gc = outer_no_gc;
+
+        self.allowed.truncate(outer_allowed);
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) {
+        match &expr.kind {
+            TypedExprKind::Int(_)
+            | TypedExprKind::Float(_)
+            | TypedExprKind::Bool(_)
+            | TypedExprKind::String(_)
+            | TypedExprKind::Null => {}
+            TypedExprKind::FmtString(parts) => {
+                for part in parts {
+                    if let TypedFmtStringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            TypedExprKind::Identifier(name) => {
+                self.used.insert(name.clone());
+            }
+            TypedExprKind::Binary { left, op, right } => {
+                self.comparison(left, *op, right, expr.span);
+                self.expr(left);
+                self.expr(right);
+            }
+            TypedExprKind::Unary { operand, .. } => self.expr(operand),
+            TypedExprKind::And { left, right } | TypedExprKind::Or { left, right } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            TypedExprKind::Call { callee, args } => {
+                self.call(callee, expr.span);
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            TypedExprKind::Assign { value, .. } => self.expr(value),
+            TypedExprKind::Grouping(inner)
+            | TypedExprKind::Lambda(inner)
+            | TypedExprKind::Cast { expr: inner, .. } => self.expr(inner),
+            TypedExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            TypedExprKind::LambdaInner { body, .. } => self.stmts(body),
+            TypedExprKind::Member { object, member } => {
+                self.used.insert(member.clone());
+                self.expr(object);
+            }
+            TypedExprKind::ArrayLiteral { elements, .. }
+            | TypedExprKind::VecLiteral { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            TypedExprKind::ArraySized { size, .. } => self.expr(size),
+            TypedExprKind::Index { object, index } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            TypedExprKind::IndexAssign {
+                object,
+                index,
+                value,
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            TypedExprKind::Range { start, end, .. } => {
+                for bound in [start, end].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            TypedExprKind::Slice { object, range } => {
+                self.expr(object);
+                self.expr(range);
+            }
+            TypedExprKind::StructLiteral { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+        }
+    }
+
+    fn comparison(&mut self, left: &TypedExpr, op: BinaryOp, right: &TypedExpr, span: Span) {
+        if let Some(result) = constant_comparison(left, op, right) {
+            self.report(WarningKind::ConstantComparison { result }, span);
+        } else if matches!(op, BinaryOp::Eq | BinaryOp::Ne)
+            && (left.ty.is_float() || right.ty.is_float())
+        {
+            let op = op.as_str().to_string();
+            self.report(WarningKind::FloatEquality { op }, span);
+        }
+    }
+
+    // `alloc(n)` / `free(buf)`, and their `bytes.` counterparts
+    fn call(&mut self, callee: &TypedExpr, span: Span) {
+        let Some(no_gc) = self.no_gc.as_mut() else {
+            return;
+        };
+        let name = match &callee.kind {
+            TypedExprKind::Identifier(name) => name,
+            TypedExprKind::Member { member, .. } => member,
+            _ => return,
+        };
+        match name.as_str() {
+            "alloc" => {
+                no_gc.alloc.get_or_insert(span);
+            }
+            "free" => no_gc.freed = true,
+            _ => {}
+        }
+    }
+}
+
+// leaves the enclosing block (or function) on every path
+fn diverges(stmt: &TypedStmt) -> bool {
+    match &stmt.kind {
+        TypedStmtKind::Return(_) | TypedStmtKind::Break | TypedStmtKind::Continue => true,
+        TypedStmtKind::Block(stmts) => stmts.iter().any(diverges),
+        TypedStmtKind::If {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => diverges(then_branch) && diverges(else_branch),
+        _ => false,
+    }
+}
+
+// a type variable nothing pinned down is just as dynamic to callers
+fn untyped(ty: &InferType) -> bool {
+    ty.has_dynamic() || ty.has_vars()
+}
+
+fn is_declaration(stmt: &TypedStmt) -> bool {
+    matches!(
+        stmt.kind,
+        TypedStmtKind::Function(_) | TypedStmtKind::StructDecl { .. } | TypedStmtKind::Needs(_)
+    )
+}
+
+// literals compared with literals, or a variable with itself; floats are
+// left out of the latter since `x == x` is how NaN gets checked
+fn constant_comparison(left: &TypedExpr, op: BinaryOp, right: &TypedExpr) -> Option<bool> {
+    let ordering = match (&ungroup(left).kind, &ungroup(right).kind) {
+        (TypedExprKind::Int(a), TypedExprKind::Int(b)) => a.cmp(b),
+        (TypedExprKind::Float(a), TypedExprKind::Float(b)) => a.partial_cmp(b)?,
+        (TypedExprKind::Bool(a), TypedExprKind::Bool(b)) => a.cmp(b),
+        (TypedExprKind::String(a), TypedExprKind::String(b)) => a.cmp(b),
+        (TypedExprKind::Identifier(a), TypedExprKind::Identifier(b))
+            if a == b && left.ty.is_concrete() && !left.ty.is_float() =>
+        {
+            Ordering::Equal
+        }
+        _ => return None,
+    };
+    match op {
+        BinaryOp::Eq => Some(ordering.is_eq()),
+        BinaryOp::Ne => Some(ordering.is_ne()),
+        BinaryOp::Lt => Some(ordering.is_lt()),
+        BinaryOp::Le => Some(ordering.is_le()),
+        BinaryOp::Gt => Some(ordering.is_gt()),
+        BinaryOp::Ge => Some(ordering.is_ge()),
+        _ => None,
+    }
+}
+
+fn ungroup(expr: &TypedExpr) -> &TypedExpr {
+    match &expr.kind {
+        TypedExprKind::Grouping(inner) => ungroup(inner),
+        _ => expr,
+    }
+}
@@ -28,6 +28,11 @@ pub enum InferType {
     Range,
 
     Struct(std::string::String),
+    /// A generic struct applied to type arguments, `Pair<int, string>`
+    GenericStruct {
+        name: std::string::String,
+        args: Vec<InferType>,
+    },
 
     Var(TypeVarId),
 
@@ -64,7 +69,23 @@ impl InferType {
                 params.iter().any(|p| p.has_vars()) || ret.has_vars()
             }
             InferType::Array(inner) | InferType::Vec(inner) => inner.has_vars(),
-            InferType::Tuple(elems) => elems.iter().any(|e| e.has_vars()),
+            InferType::Tuple(elems) | InferType::GenericStruct { args: elems, .. } => {
+                elems.iter().any(|e| e.has_vars())
+            }
+            _ => false,
+        }
+    }
+
+    pub fn has_dynamic(&self) -> bool {
+        match self {
+            InferType::Dynamic => true,
+            InferType::Function { params, ret } => {
+                params.iter().any(|p| p.has_dynamic()) || ret.has_dynamic()
+            }
+            InferType::Array(inner) | InferType::Vec(inner) => inner.has_dynamic(),
+            InferType::Tuple(elems) | InferType::GenericStruct { args: elems, .. } => {
+                elems.iter().any(|e| e.has_dynamic())
+            }
             _ => false,
         }
     }
@@ -127,23 +148,30 @@ impl InferType {
             "null" | "void" => InferType::Null,
             "array" => {
                 let inner = ann
-                    .type_param
-                    .as_ref()
-                    .map(|p| Self::from_annotation(p))
+                    .type_args
+                    .first()
+                    .map(Self::from_annotation)
                     .unwrap_or(InferType::Dynamic);
                 InferType::Array(Box::new(inner))
             }
             "vec" => {
                 let inner = ann
-                    .type_param
-                    .as_ref()
-                    .map(|p| Self::from_annotation(p))
+                    .type_args
+                    .first()
+                    .map(Self::from_annotation)
                     .unwrap_or(InferType::Dynamic);
                 InferType::Vec(Box::new(inner))
             }
             _ => {
                 if ann.name.chars().next().is_some_and(|c| c.is_uppercase()) {
-                    InferType::Struct(ann.name.clone())
+                    if ann.type_args.is_empty() {
+                        InferType::Struct(ann.name.clone())
+                    } else {
+                        InferType::GenericStruct {
+                            name: ann.name.clone(),
+                            args: ann.type_args.iter().map(Self::from_annotation).collect(),
+                        }
+                    }
                 } else {
                     InferType::Dynamic
                 }
@@ -176,6 +204,30 @@ impl InferType {
         }
     }
 
+    /// Replace the named type parameters (`Struct("T")` after annotation
+    /// lowering) with the matching entry of `args`
+    pub fn substitute_params(&self, params: &[std::string::String], args: &[InferType]) -> Self {
+        let sub = |t: &InferType| t.substitute_params(params, args);
+        match self {
+            InferType::Struct(name) => match params.iter().position(|p| p == name) {
+                Some(i) => args.get(i).cloned().unwrap_or(InferType::Dynamic),
+                None => self.clone(),
+            },
+            InferType::GenericStruct { name, args: inner } => InferType::GenericStruct {
+                name: name.clone(),
+                args: inner.iter().map(sub).collect(),
+            },
+            InferType::Function { params: ps, ret } => InferType::Function {
+                params: ps.iter().map(sub).collect(),
+                ret: Box::new(sub(ret)),
+            },
+            InferType::Array(inner) => InferType::Array(Box::new(sub(inner))),
+            InferType::Vec(inner) => InferType::Vec(Box::new(sub(inner))),
+            InferType::Tuple(elems) => InferType::Tuple(elems.iter().map(sub).collect()),
+            _ => self.clone(),
+        }
+    }
+
     pub fn as_var_id(&self) -> Option<TypeVarId> {
         match self {
             InferType::Var(id) => Some(*id),
@@ -261,6 +313,16 @@ impl fmt::Display for InferType {
             }
             InferType::Range => write!(f, "range"),
             InferType::Struct(name) => write!(f, "{}", name),
+            InferType::GenericStruct { name, args } => {
+                write!(f, "{}<", name)?;
+                for (i, a) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", a)?;
+                }
+                write!(f, ">")
+            }
             InferType::Var(id) => write!(f, "{}", id),
             InferType::Dynamic => write!(f, "dynamic"),
         }
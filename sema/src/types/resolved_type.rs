@@ -119,7 +119,9 @@ impl ResolvedType {
                 ResolvedType::Tuple(elems.iter().map(ResolvedType::from_infer_type).collect())
             }
             InferType::Range => ResolvedType::Range,
-            InferType::Struct(name) => ResolvedType::Struct(name.clone()),
+            InferType::Struct(name) | InferType::GenericStruct { name, .. } => {
+                ResolvedType::Struct(name.clone())
+            }
             InferType::Var(_) => ResolvedType::Dynamic,
             InferType::Dynamic => ResolvedType::Dynamic,
         }
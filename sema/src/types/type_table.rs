@@ -14,6 +14,15 @@ pub struct StructDef {
     pub fields: Vec<StructField>,
 }
 
+impl StructDef {
+    /// Field type with the struct's type parameters replaced by `args`;
+    /// parameters without an argument stay open as `Dynamic`
+    pub fn field_type(&self, field: &str, args: &[InferType]) -> Option<InferType> {
+        let field = self.fields.iter().find(|f| f.name == field)?;
+        Some(field.ty.substitute_params(&self.type_params, args))
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct TypeTable {
     structs: HashMap<String, StructDef>,
@@ -24,6 +24,24 @@ pub fn unify(t1: &InferType, t2: &InferType, subst: &mut Substitution) -> UnifyR
 
         (InferType::Struct(a), InferType::Struct(b)) if a == b => Ok(()),
 
+        (
+            InferType::GenericStruct { name: a, args: a1 },
+            InferType::GenericStruct { name: b, args: a2 },
+        ) if a == b && a1.len() == a2.len() => {
+            for (x, y) in a1.iter().zip(a2.iter()) {
+                unify(x, y, subst)?;
+            }
+            Ok(())
+        }
+
+        // a bare `Box` leaves the arguments of a generic struct open
+        (InferType::GenericStruct { name: a, .. }, InferType::Struct(b))
+        | (InferType::Struct(b), InferType::GenericStruct { name: a, .. })
+            if a == b =>
+        {
+            Ok(())
+        }
+
         (InferType::Dynamic, _) | (_, InferType::Dynamic) => Ok(()),
 
         (InferType::Var(id1), InferType::Var(id2)) if id1 == id2 => Ok(()),
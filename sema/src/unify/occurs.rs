@@ -7,7 +7,9 @@ pub(super) fn occurs_check(var: TypeVarId, ty: &InferType) -> bool {
             params.iter().any(|p| occurs_check(var, p)) || occurs_check(var, ret)
         }
         InferType::Array(inner) | InferType::Vec(inner) => occurs_check(var, inner),
-        InferType::Tuple(elems) => elems.iter().any(|e| occurs_check(var, e)),
+        InferType::Tuple(elems) | InferType::GenericStruct { args: elems, .. } => {
+            elems.iter().any(|e| occurs_check(var, e))
+        }
         InferType::I8
         | InferType::I16
         | InferType::I32
@@ -45,6 +45,10 @@ impl Substitution {
             InferType::Tuple(elems) => {
                 InferType::Tuple(elems.iter().map(|e| self.apply(e)).collect())
             }
+            InferType::GenericStruct { name, args } => InferType::GenericStruct {
+                name: name.clone(),
+                args: args.iter().map(|a| self.apply(a)).collect(),
+            },
             InferType::I8
             | InferType::I16
             | InferType::I32
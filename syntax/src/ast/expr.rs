@@ -3,7 +3,7 @@ use crate::Span;
 #[derive(Debug, Clone)]
 pub struct TypeAnnotation {
     pub name: String,
-    pub type_param: Option<Box<TypeAnnotation>>,
+    pub type_args: Vec<TypeAnnotation>,
     pub fn_params: Option<Vec<TypeAnnotation>>,
     pub fn_ret: Option<Box<TypeAnnotation>>,
     pub span: Span,
@@ -13,7 +13,7 @@ impl TypeAnnotation {
     pub fn new(name: String, span: Span) -> Self {
         Self {
             name,
-            type_param: None,
+            type_args: Vec::new(),
             fn_params: None,
             fn_ret: None,
             span,
@@ -21,9 +21,13 @@ impl TypeAnnotation {
     }
 
     pub fn with_param(name: String, type_param: TypeAnnotation, span: Span) -> Self {
+        Self::with_args(name, vec![type_param], span)
+    }
+
+    pub fn with_args(name: String, type_args: Vec<TypeAnnotation>, span: Span) -> Self {
         Self {
             name,
-            type_param: Some(Box::new(type_param)),
+            type_args,
             fn_params: None,
             fn_ret: None,
             span,
@@ -33,7 +37,7 @@ impl TypeAnnotation {
     pub fn function_type(params: Vec<TypeAnnotation>, ret: TypeAnnotation, span: Span) -> Self {
         Self {
             name: "fn".to_string(),
-            type_param: None,
+            type_args: Vec::new(),
             fn_params: Some(params),
             fn_ret: Some(Box::new(ret)),
             span,
@@ -102,8 +102,17 @@ pub struct Function {
     pub span: Span,
 }
 
+impl Function {
+    /// Marked `@test`, i.e. run by `aelys test`.
+    pub fn is_test(&self) -> bool {
+        self.decorators.iter().any(|d| d.name == "test")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Decorator {
     pub name: String,
+    /// Names in parentheses, as in `@allow(float_equality)`.
+    pub args: Vec<String>,
     pub span: Span,
 }
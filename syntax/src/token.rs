@@ -12,6 +12,14 @@ impl Token {
     }
 }
 
+/// A `//` or `/* */` comment, kept aside by the lexer for tools that
+/// reprint source (the formatter). `text` includes the delimiters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
 /// Part of a format string: either literal text, a placeholder {}, or an expression {expr}
 #[derive(Debug, Clone, PartialEq)]
 pub enum FmtPart {